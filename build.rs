@@ -0,0 +1,31 @@
+//! Build script: record the resolved maki crate versions.
+//!
+//! maki-core and maki-decompiler are path dependencies, so their versions are
+//! not visible to the crate at compile time. They are read from `Cargo.lock`
+//! and exposed as `NITEN_MAKI_CORE_VERSION` / `NITEN_MAKI_DECOMPILER_VERSION`.
+
+use std::fs;
+
+fn main() {
+    println!("cargo:rerun-if-changed=Cargo.lock");
+
+    let lock = fs::read_to_string("Cargo.lock").unwrap_or_default();
+    for (package, var) in [
+        ("maki-core", "NITEN_MAKI_CORE_VERSION"),
+        ("maki-decompiler", "NITEN_MAKI_DECOMPILER_VERSION"),
+    ] {
+        let version = locked_version(&lock, package).unwrap_or("unknown");
+        println!("cargo:rustc-env={}={}", var, version);
+    }
+}
+
+/// Version of `package` in a `Cargo.lock` file.
+fn locked_version<'a>(lock: &'a str, package: &str) -> Option<&'a str> {
+    let name_line = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    lines.find(|line| *line == name_line)?;
+    lines
+        .next()?
+        .strip_prefix("version = \"")?
+        .strip_suffix('"')
+}
@@ -0,0 +1,334 @@
+//! Element annotation route handlers.
+//!
+//! Review notes attached to profile elements. Annotations are metadata only:
+//! they live in a sidecar file next to the profile IR and never reach exports.
+//! Each annotation references its element by [`NodeId`] only, so notes follow
+//! added elements and slices through renames and never attach to a different
+//! element that later reuses the path. Inherited elements keep their node IDs
+//! because base trees derive them from the element id.
+//!
+//! # Routes
+//!
+//! - `GET    /api/projects/:projectId/profiles/:profileId/elements/:path/annotations` - List annotations
+//! - `POST   /api/projects/:projectId/profiles/:profileId/elements/:path/annotations` - Add annotation
+//! - `DELETE /api/projects/:projectId/profiles/:profileId/elements/:path/annotations/:annotationId` - Delete annotation
+//!
+//! Element paths never contain `/`, so the annotation suffix is split off the
+//! element wildcard segment shared with the element editing routes.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::ir::{ElementNode, NodeId};
+use crate::state::AppState;
+
+use super::dto::ApiResponse;
+use super::profile_merge::hydrate_profile_document;
+use super::profiles::{ElementPath, ErrorResponse};
+use super::provenance::{element_provenance, provenance_target};
+use super::storage::ProfileStorage;
+
+/// Create annotation routes.
+pub fn annotation_routes() -> Router<AppState> {
+    Router::new().route(
+        "/{profileId}/elements/{*path}",
+        get(list_annotations).post(add_annotation),
+    )
+}
+
+// === Types ===
+
+/// A review note attached to an element.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Annotation {
+    /// Annotation ID.
+    pub id: String,
+    /// Node the annotation is attached to.
+    pub node_id: NodeId,
+    /// Element path when the annotation was written.
+    pub path: String,
+    /// Author of the note.
+    pub author: String,
+    /// Note text.
+    pub text: String,
+    /// Creation timestamp.
+    pub created_at: DateTime<Utc>,
+}
+
+impl Annotation {
+    /// Create an annotation on `element`.
+    pub fn new(element: &ElementNode, author: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            node_id: element.id,
+            path: element.path.clone(),
+            author: author.into(),
+            text: text.into(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Whether this annotation belongs to `element`.
+    pub fn is_on(&self, element: &ElementNode) -> bool {
+        self.node_id == element.id
+    }
+}
+
+/// Request body for adding an annotation.
+#[derive(Debug, Deserialize)]
+pub struct AddAnnotationRequest {
+    /// Author of the note.
+    pub author: String,
+    /// Note text.
+    pub text: String,
+}
+
+/// Response for deleting an annotation.
+#[derive(Debug, Serialize)]
+pub struct DeleteAnnotationResponse {
+    /// ID of the deleted annotation.
+    pub id: String,
+}
+
+/// Split a wildcard element segment of the form `<path>/annotations`.
+pub fn annotations_target(segment: &str) -> Option<&str> {
+    segment
+        .trim_start_matches('/')
+        .strip_suffix("/annotations")
+}
+
+/// Split a wildcard element segment of the form `<path>/annotations/<id>`.
+pub fn annotation_target(segment: &str) -> Option<(&str, &str)> {
+    let (element_path, annotation_id) = segment.trim_start_matches('/').rsplit_once('/')?;
+    let element_path = element_path.strip_suffix("/annotations")?;
+    Some((element_path, annotation_id))
+}
+
+// === Route Handlers ===
+
+/// GET /api/projects/:projectId/profiles/:profileId/elements/:path/annotations
+///
+/// Also dispatches `GET .../elements/:path/provenance`, which shares the
+/// element wildcard route.
+async fn list_annotations(
+    State(state): State<AppState>,
+    Path(params): Path<ElementPath>,
+) -> impl IntoResponse {
+    let Some(element_path) = annotations_target(&params.path) else {
+        if let Some(element_path) = provenance_target(&params.path) {
+            return element_provenance(&state, &params, element_path).await;
+        }
+        return ErrorResponse::not_found("Route", &params.path).into_response();
+    };
+    let (storage, element) = match load_element(&state, &params, element_path).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    match storage.load_annotations(&params.profile_id).await {
+        Ok(annotations) => {
+            let annotations: Vec<Annotation> =
+                annotations.into_iter().filter(|a| a.is_on(&element)).collect();
+            Json(ApiResponse::ok(annotations)).into_response()
+        }
+        Err(e) => Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    }
+}
+
+/// POST /api/projects/:projectId/profiles/:profileId/elements/:path/annotations
+async fn add_annotation(
+    State(state): State<AppState>,
+    Path(params): Path<ElementPath>,
+    Json(req): Json<AddAnnotationRequest>,
+) -> impl IntoResponse {
+    let Some(element_path) = annotations_target(&params.path) else {
+        return ErrorResponse::not_found("Route", &params.path).into_response();
+    };
+    if req.author.trim().is_empty() || req.text.trim().is_empty() {
+        return ErrorResponse::bad_request("Annotation author and text are required")
+            .into_response();
+    }
+    let (storage, element) = match load_element(&state, &params, element_path).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    let annotation = Annotation::new(&element, req.author.trim(), req.text);
+    let lock = state.annotation_lock(&params.project_id, &params.profile_id);
+    let _guard = lock.lock().await;
+    match storage.add_annotation(&params.profile_id, annotation.clone()).await {
+        Ok(()) => (StatusCode::CREATED, Json(ApiResponse::ok(annotation))).into_response(),
+        Err(e) => Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    }
+}
+
+/// DELETE /api/projects/:projectId/profiles/:profileId/elements/:path/annotations/:annotationId
+///
+/// Dispatched from the element removal handler, which owns `DELETE` on the
+/// element wildcard route.
+pub(super) async fn delete_annotation(
+    state: &AppState,
+    params: &ElementPath,
+    element_path: &str,
+    annotation_id: &str,
+) -> Response {
+    let (storage, element) = match load_element(state, params, element_path).await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+
+    let lock = state.annotation_lock(&params.project_id, &params.profile_id);
+    let _guard = lock.lock().await;
+    let annotations = match storage.load_annotations(&params.profile_id).await {
+        Ok(annotations) => annotations,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    if !annotations.iter().any(|a| a.id == annotation_id && a.is_on(&element)) {
+        return ErrorResponse::not_found("Annotation", annotation_id).into_response();
+    }
+
+    match storage.delete_annotation(&params.profile_id, annotation_id).await {
+        Ok(_) => Json(ApiResponse::ok(DeleteAnnotationResponse {
+            id: annotation_id.to_string(),
+        }))
+        .into_response(),
+        Err(e) => Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    }
+}
+
+/// Load the profile and resolve the annotated element on its merged tree.
+///
+/// Returns the project's file storage, which holds the annotations sidecar.
+async fn load_element(
+    state: &AppState,
+    params: &ElementPath,
+    element_path: &str,
+) -> Result<(ProfileStorage, ElementNode), Response> {
+    let project_dir = state
+        .project_path(&params.project_id)
+        .map_err(|e| ErrorResponse::from(e).into_response())?;
+    let doc = state
+        .storage()
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+        .map_err(|e| Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response())?;
+    let doc = hydrate_profile_document(state, doc)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let element = doc
+        .resource
+        .find_element(element_path)
+        .cloned()
+        .ok_or_else(|| ErrorResponse::not_found("Element", element_path).into_response())?;
+    Ok((ProfileStorage::new(&project_dir), element))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfileDocument, ProfiledResource};
+    use tempfile::TempDir;
+
+    fn create_test_document() -> ProfileDocument {
+        let metadata = DocumentMetadata::new(
+            "annotated",
+            "http://example.org/fhir/StructureDefinition/Annotated",
+            "Annotated",
+        );
+        let resource = ProfiledResource::new(
+            &metadata.url,
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        let mut doc = ProfileDocument::new(metadata, resource);
+        doc.resource
+            .root
+            .add_child(ElementNode::new("Patient.name".to_string()));
+        doc
+    }
+
+    #[test]
+    fn test_annotation_targets() {
+        assert_eq!(
+            annotations_target("/Patient.name/annotations"),
+            Some("Patient.name")
+        );
+        assert_eq!(annotations_target("Patient.name"), None);
+        assert_eq!(
+            annotation_target("Patient.identifier:mrn/annotations/abc"),
+            Some(("Patient.identifier:mrn", "abc"))
+        );
+        assert_eq!(annotation_target("Patient.name"), None);
+    }
+
+    #[tokio::test]
+    async fn test_add_list_delete_annotations() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = ProfileStorage::new(temp_dir.path());
+        let doc = create_test_document();
+        storage.save_profile(&doc).await.unwrap();
+        let name = doc.resource.find_element("Patient.name").unwrap();
+
+        assert!(storage.load_annotations("annotated").await.unwrap().is_empty());
+
+        let first = Annotation::new(name, "alice", "Should this be 1..1?");
+        let second = Annotation::new(&doc.resource.root, "bob", "Check the base");
+        storage.add_annotation("annotated", first.clone()).await.unwrap();
+        storage.add_annotation("annotated", second.clone()).await.unwrap();
+
+        let on_name: Vec<Annotation> = storage
+            .load_annotations("annotated")
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|a| a.is_on(name))
+            .collect();
+        assert_eq!(on_name, vec![first.clone()]);
+
+        assert!(storage.delete_annotation("annotated", &first.id).await.unwrap());
+        assert!(!storage.delete_annotation("annotated", &first.id).await.unwrap());
+        assert_eq!(
+            storage.load_annotations("annotated").await.unwrap(),
+            vec![second]
+        );
+    }
+
+    #[test]
+    fn test_annotation_follows_node_through_rename() {
+        let mut element = ElementNode::new("Patient.extension:old".to_string());
+        let annotation = Annotation::new(&element, "alice", "Rename me");
+
+        element.path = "Patient.extension:new".to_string();
+        assert!(annotation.is_on(&element));
+        assert!(!annotation.is_on(&ElementNode::new("Patient.extension:old2".to_string())));
+
+        // A new element reusing the old path does not pick up the note
+        assert!(!annotation.is_on(&ElementNode::new("Patient.extension:old".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_annotations_stay_out_of_profile_ir() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = ProfileStorage::new(temp_dir.path());
+        let doc = create_test_document();
+        storage.save_profile(&doc).await.unwrap();
+
+        let annotation = Annotation::new(&doc.resource.root, "alice", "Note");
+        storage.add_annotation("annotated", annotation).await.unwrap();
+
+        let content = tokio::fs::read_to_string(temp_dir.path().join("IR/resources/annotated.json"))
+            .await
+            .unwrap();
+        assert!(!content.contains("alice"));
+    }
+}
@@ -0,0 +1,300 @@
+//! Debug API route handlers.
+//!
+//! Internal tooling for catching import/export regressions. These routes are
+//! only mounted when `Config::enable_debug_routes` is enabled and are not part of the
+//! public API.
+//!
+//! # Routes
+//!
+//! - `POST /api/_debug/fsh-roundtrip` - Import FSH, decompile it back, and diff the IR
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use axum::{extract::State, response::IntoResponse, routing::post, Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::decompiler::decompile_sd_value_to_fsh;
+use crate::export::{ExportConfig, StructureDefinitionExporter};
+use crate::fsh::{FshImportOptions, FshImporter};
+use crate::ir::{FhirVersion, ProfileDocument};
+use crate::merge::DifferentialElement;
+use crate::state::AppState;
+
+use super::dto::ApiResponse;
+use super::profiles::ErrorResponse;
+
+/// Create debug routes.
+pub fn debug_routes() -> Router<AppState> {
+    Router::new().route("/fsh-roundtrip", post(fsh_roundtrip))
+}
+
+// === Request/Response Types ===
+
+/// Request body for an FSH round-trip.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FshRoundtripRequest {
+    /// FSH source to round-trip.
+    pub content: String,
+    /// FHIR version (defaults to R4).
+    pub fhir_version: Option<String>,
+    /// Canonical base for generated URLs.
+    pub canonical_base: Option<String>,
+}
+
+/// Round-trip result for all profiles in the FSH source.
+#[derive(Debug, Serialize)]
+pub struct FshRoundtripResponse {
+    /// One entry per imported profile.
+    pub profiles: Vec<RoundtripProfile>,
+    /// Importer warnings from the original FSH.
+    pub warnings: Vec<String>,
+}
+
+/// Round-trip result for a single profile.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoundtripProfile {
+    /// Profile name.
+    pub name: String,
+    /// IR imported from the original FSH.
+    pub ir: ProfileDocument,
+    /// FSH regenerated by the decompiler.
+    pub regenerated_fsh: Option<String>,
+    /// Differences between the original and re-imported differentials.
+    pub differences: Vec<RoundtripDifference>,
+    /// Error that stopped the round-trip for this profile.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// A structural difference found by the round-trip.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RoundtripDifference {
+    /// Element key (`path` or `path:sliceName`).
+    pub path: String,
+    /// What changed.
+    pub kind: DifferenceKind,
+}
+
+/// Kind of round-trip difference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DifferenceKind {
+    /// Element constrained in the original but lost after the round-trip.
+    Missing,
+    /// Element constrained only after the round-trip.
+    Added,
+    /// Element present in both with different constraints or slicing.
+    Changed,
+}
+
+// === Route Handlers ===
+
+/// POST /api/_debug/fsh-roundtrip
+/// Import FSH, regenerate it via the decompiler, re-import, and diff the IR.
+async fn fsh_roundtrip(
+    State(_state): State<AppState>,
+    Json(req): Json<FshRoundtripRequest>,
+) -> impl IntoResponse {
+    let fhir_version = match req.fhir_version.as_deref() {
+        None => FhirVersion::R4,
+        Some(v) => match FhirVersion::from_str(v) {
+            Some(v) => v,
+            None => {
+                return ErrorResponse::bad_request(format!("Invalid FHIR version: {}", v))
+                    .into_response()
+            }
+        },
+    };
+
+    let mut options = FshImportOptions::default().with_fhir_version(fhir_version);
+    if let Some(base) = req.canonical_base {
+        options = options.with_canonical_base(base);
+    }
+    let importer = match FshImporter::with_options(options).await {
+        Ok(i) => i,
+        Err(e) => {
+            return ErrorResponse::internal_error(format!(
+                "Failed to initialize FSH importer: {}",
+                e
+            ))
+            .into_response()
+        }
+    };
+
+    let source = Path::new("roundtrip.fsh");
+    let imported = match importer.import_content(&req.content, source).await {
+        Ok(result) => result,
+        Err(e) => {
+            return ErrorResponse::validation_error(format!("FSH import failed: {}", e))
+                .into_response()
+        }
+    };
+
+    let mut profiles = Vec::new();
+    for doc in imported.value {
+        let mut entry = RoundtripProfile {
+            name: doc.metadata.name.clone(),
+            ir: doc.clone(),
+            regenerated_fsh: None,
+            differences: Vec::new(),
+            error: None,
+        };
+
+        let mut exporter =
+            StructureDefinitionExporter::with_config(ExportConfig::differential_only());
+        let fsh = match exporter.export_value(&doc).await {
+            Ok(sd) => decompile_sd_value_to_fsh(&sd, doc.resource.fhir_version)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(format!("SD export failed: {}", e)),
+        };
+        let fsh = match fsh {
+            Ok(fsh) => fsh,
+            Err(e) => {
+                entry.error = Some(e);
+                profiles.push(entry);
+                continue;
+            }
+        };
+
+        match importer.import_content(&fsh, source).await {
+            Ok(reimported) => {
+                match reimported.value.iter().find(|d| d.metadata.name == doc.metadata.name) {
+                    Some(again) => {
+                        entry.differences = diff_differentials(
+                            &differential_of(&doc),
+                            &differential_of(again),
+                        );
+                    }
+                    None => {
+                        entry.error =
+                            Some("Regenerated FSH did not define the profile".to_string());
+                    }
+                }
+            }
+            Err(e) => entry.error = Some(format!("Re-import failed: {}", e)),
+        }
+        entry.regenerated_fsh = Some(fsh);
+        profiles.push(entry);
+    }
+
+    let response = FshRoundtripResponse {
+        profiles,
+        warnings: imported.warnings.iter().map(ToString::to_string).collect(),
+    };
+    Json(ApiResponse::ok(response)).into_response()
+}
+
+// === Helpers ===
+
+/// Get a document's differential, extracting it from the tree if needed.
+fn differential_of(doc: &ProfileDocument) -> Vec<DifferentialElement> {
+    if doc.resource.differential.is_empty() {
+        crate::merge::extract_differential(&doc.resource.root)
+    } else {
+        doc.resource.differential.clone()
+    }
+}
+
+/// Compare two differentials element by element.
+///
+/// Elements are matched by path and slice name; node ids, element ids and
+/// source positions are ignored.
+fn diff_differentials(
+    original: &[DifferentialElement],
+    roundtripped: &[DifferentialElement],
+) -> Vec<RoundtripDifference> {
+    fn keyed(elements: &[DifferentialElement]) -> BTreeMap<String, serde_json::Value> {
+        elements
+            .iter()
+            .map(|e| {
+                let key = match &e.slice_name {
+                    Some(slice) if !e.path.contains(':') => format!("{}:{}", e.path, slice),
+                    _ => e.path.clone(),
+                };
+                let shape = serde_json::json!({
+                    "constraints": e.constraints,
+                    "slicing": e.slicing,
+                });
+                (key, shape)
+            })
+            .collect()
+    }
+
+    let before = keyed(original);
+    let after = keyed(roundtripped);
+    let mut differences = Vec::new();
+
+    for (path, shape) in &before {
+        match after.get(path) {
+            None => differences.push(RoundtripDifference {
+                path: path.clone(),
+                kind: DifferenceKind::Missing,
+            }),
+            Some(other) if other != shape => differences.push(RoundtripDifference {
+                path: path.clone(),
+                kind: DifferenceKind::Changed,
+            }),
+            Some(_) => {}
+        }
+    }
+    for path in after.keys().filter(|path| !before.contains_key(*path)) {
+        differences.push(RoundtripDifference {
+            path: path.clone(),
+            kind: DifferenceKind::Added,
+        });
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::Cardinality;
+
+    fn element(path: &str, min: u32) -> DifferentialElement {
+        let mut element = DifferentialElement::new(path.to_string());
+        element.constraints.cardinality = Some(Cardinality::new(min, None));
+        element
+    }
+
+    #[test]
+    fn test_diff_differentials() {
+        let original = vec![
+            element("Patient.name", 1),
+            element("Patient.birthDate", 1),
+            element("Patient.gender", 1),
+        ];
+        let roundtripped = vec![
+            element("Patient.name", 1),
+            element("Patient.birthDate", 0),
+            element("Patient.identifier", 1),
+        ];
+
+        let differences = diff_differentials(&original, &roundtripped);
+        assert_eq!(
+            differences,
+            vec![
+                RoundtripDifference {
+                    path: "Patient.birthDate".to_string(),
+                    kind: DifferenceKind::Changed,
+                },
+                RoundtripDifference {
+                    path: "Patient.gender".to_string(),
+                    kind: DifferenceKind::Missing,
+                },
+                RoundtripDifference {
+                    path: "Patient.identifier".to_string(),
+                    kind: DifferenceKind::Added,
+                },
+            ]
+        );
+
+        // Fresh node ids don't count as differences
+        assert!(diff_differentials(&original, &original.clone()).is_empty());
+    }
+}
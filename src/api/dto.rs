@@ -3,17 +3,22 @@
 //! Defines request and response types for all profile-related endpoints.
 
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::export::DifferentialStats;
+use crate::fsh::{FshImportError, FshWarning};
+use crate::import::ImportWarning;
 use crate::ir::{
-    DocumentMetadata, ElementConstraints, FhirVersion, ProfileDocument, ProfileStatus,
-    ProfiledResource,
+    Cardinality, Derivation, DiscriminatorType, DocumentMetadata, EditingMode, ElementConstraints,
+    ElementSource, ExtensionContext,
+    FhirVersion, ParseCardinalityError, ProfileDocument, ProfileStatus, ProfiledResource, StructureKind,
 };
 
 // === Response Wrapper ===
 
 /// Error information for failed responses.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 pub struct ErrorInfo {
     /// Error code for programmatic handling.
     pub code: String,
@@ -23,7 +28,7 @@ pub struct ErrorInfo {
 
 /// Standard API response wrapper.
 /// Matches frontend expectations: { success, data?, error?, diagnostics?, metadata? }
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ApiResponse<T> {
     /// Whether the request was successful.
     pub success: bool,
@@ -80,7 +85,7 @@ impl<T> ApiResponse<T> {
 }
 
 /// Response metadata.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ResponseMetadata {
     /// Timestamp of the response.
     pub timestamp: DateTime<Utc>,
@@ -106,7 +111,10 @@ impl ResponseMetadata {
 }
 
 /// Diagnostic message (warning or error).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Diagnostics not tied to FSH or JSON text leave the source position
+/// unset, e.g. with `..Default::default()`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct Diagnostic {
     /// Severity (error, warning, info).
     pub severity: DiagnosticSeverity,
@@ -117,10 +125,82 @@ pub struct Diagnostic {
     /// Element path if applicable.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub path: Option<String>,
+    /// Source file for diagnostics about FSH or JSON text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Source line (1-based) for diagnostics about FSH or JSON text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// Source column (1-based) for diagnostics about FSH or JSON text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+}
+
+impl Diagnostic {
+    /// Convert an FSH import error into error diagnostics.
+    ///
+    /// `Multiple` errors are flattened; the source file and position are kept
+    /// so the editor can underline the offending FSH.
+    pub fn from_fsh_error(error: &FshImportError) -> Vec<Self> {
+        let (code, message) = match error {
+            FshImportError::Multiple(errors) => {
+                return errors.iter().flat_map(Self::from_fsh_error).collect();
+            }
+            FshImportError::Parse { message, .. } => ("FSH_PARSE_ERROR", message.clone()),
+            FshImportError::Semantic { message, .. } => ("FSH_SEMANTIC_ERROR", message.clone()),
+            FshImportError::ResourceNotFound { .. } => {
+                ("FSH_RESOURCE_NOT_FOUND", error.to_string())
+            }
+            FshImportError::UnsupportedConstruct { .. } => {
+                ("FSH_UNSUPPORTED_CONSTRUCT", error.to_string())
+            }
+            FshImportError::DependencyResolution { .. } => {
+                ("FSH_UNRESOLVED_DEPENDENCY", error.to_string())
+            }
+            FshImportError::MappingError { message } => ("FSH_MAPPING_ERROR", message.clone()),
+        };
+        let position = error.position();
+
+        vec![Self {
+            severity: DiagnosticSeverity::Error,
+            code: code.to_string(),
+            message,
+            path: None,
+            file: error.file().map(|f| f.display().to_string()),
+            line: position.map(|(line, _)| line),
+            column: position.map(|(_, column)| column),
+        }]
+    }
+}
+
+impl From<&FshWarning> for Diagnostic {
+    fn from(warning: &FshWarning) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Warning,
+            code: warning.code.as_str().to_string(),
+            message: warning.message.clone(),
+            path: None,
+            file: warning.file.as_ref().map(|p| p.display().to_string()),
+            line: warning.line,
+            column: warning.column,
+        }
+    }
+}
+
+impl From<&ImportWarning> for Diagnostic {
+    fn from(warning: &ImportWarning) -> Self {
+        Self {
+            severity: DiagnosticSeverity::Warning,
+            code: warning.code.as_str().to_string(),
+            message: warning.message.clone(),
+            path: warning.path.clone(),
+            ..Default::default()
+        }
+    }
 }
 
 /// Diagnostic severity levels.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum DiagnosticSeverity {
     /// Fatal error.
@@ -128,6 +208,7 @@ pub enum DiagnosticSeverity {
     /// Warning (non-fatal).
     Warning,
     /// Informational.
+    #[default]
     Info,
 }
 
@@ -144,10 +225,13 @@ pub struct ListProfilesQuery {
     /// Page size (default 50, max 100).
     #[serde(rename = "pageSize")]
     pub page_size: Option<u32>,
+    /// Only profiles modified at or after this time (ISO 8601).
+    #[serde(rename = "modifiedSince")]
+    pub modified_since: Option<DateTime<Utc>>,
 }
 
 /// Profile list item (summary).
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ProfileListItem {
     /// Profile ID.
     pub id: String,
@@ -178,7 +262,7 @@ pub struct ProfileListItem {
 }
 
 /// Kind of resource document.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum ResourceKind {
     /// Profile on a resource.
     Profile,
@@ -210,7 +294,7 @@ impl From<&ProfileDocument> for ProfileListItem {
 }
 
 /// Paginated profile list response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ProfileListResponse {
     /// List of profiles.
     pub profiles: Vec<ProfileListItem>,
@@ -219,7 +303,7 @@ pub struct ProfileListResponse {
 }
 
 /// Pagination information.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct PaginationInfo {
     /// Current page (1-based).
     pub page: u32,
@@ -237,16 +321,28 @@ pub struct PaginationInfo {
 // === Create Profile ===
 
 /// Request to create a new profile.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct CreateProfileRequest {
     /// Base resource type (e.g., "Patient", "Observation").
-    #[serde(rename = "resourceType")]
+    ///
+    /// Not used for logical models.
+    #[serde(rename = "resourceType", default)]
     pub resource_type: String,
+    /// Structure kind; `logical` creates a logical model instead of a profile.
+    #[serde(default)]
+    pub kind: Option<StructureKind>,
+    /// Derivation; `specialization` defines a new type named `name` from
+    /// `resourceType` instead of constraining it.
+    #[serde(default)]
+    pub derivation: Option<Derivation>,
     /// FHIR version.
     #[serde(rename = "fhirVersion")]
     pub fhir_version: String,
     /// Profile name (computer-friendly).
     pub name: String,
+    /// Profile id (optional, defaults to the slugified name).
+    #[serde(default)]
+    pub id: Option<String>,
     /// Canonical URL (optional, will be generated if not provided).
     pub url: Option<String>,
     /// Display title.
@@ -260,7 +356,7 @@ pub struct CreateProfileRequest {
 // === Get Profile Details ===
 
 /// Full profile details response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ProfileDetailsResponse {
     /// Document ID.
     #[serde(rename = "documentId")]
@@ -271,6 +367,9 @@ pub struct ProfileDetailsResponse {
     pub resource: ProfiledResource,
     /// Edit history summary.
     pub history: HistorySummary,
+    /// What element edits operate on.
+    #[serde(rename = "editingMode")]
+    pub editing_mode: EditingMode,
     /// Whether the profile is dirty.
     #[serde(rename = "isDirty")]
     pub is_dirty: bool,
@@ -291,14 +390,43 @@ impl From<&ProfileDocument> for ProfileDetailsResponse {
                 undo_count: doc.history.undo_stack_size(),
                 redo_count: doc.history.redo_stack_size(),
             },
+            editing_mode: doc.editing_mode,
             is_dirty: doc.is_dirty(),
             file_path: doc.file_path.clone(),
         }
     }
 }
 
+/// Profile complexity summary.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileStatsResponse {
+    /// Counts over the profile's differential.
+    pub differential: DifferentialStats,
+    /// Elements in the merged tree, slices and their children included.
+    pub element_count: usize,
+    /// Elements of the merged tree the profile modifies or adds.
+    pub constrained_element_count: usize,
+    /// Slices in the merged tree, re-slices included.
+    pub slice_count: usize,
+}
+
+impl From<&ProfileDocument> for ProfileStatsResponse {
+    fn from(doc: &ProfileDocument) -> Self {
+        let elements = || doc.resource.root.descendants();
+        Self {
+            differential: DifferentialStats::from_resource(&doc.resource),
+            element_count: elements().count(),
+            constrained_element_count: elements()
+                .filter(|e| e.source != ElementSource::Inherited)
+                .count(),
+            slice_count: elements().map(|e| e.slices.len()).sum(),
+        }
+    }
+}
+
 /// Summary of edit history.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct HistorySummary {
     /// Whether undo is available.
     #[serde(rename = "canUndo")]
@@ -316,8 +444,18 @@ pub struct HistorySummary {
 
 // === Update Element ===
 
+/// Query parameters for element updates.
+#[derive(Debug, Default, Deserialize)]
+pub struct UpdateElementQuery {
+    /// Apply `flags.mustSupport` to the element and all its descendants.
+    #[serde(default)]
+    pub recursive: bool,
+    /// Editing mode of this edit; defaults to the profile's editing mode.
+    pub mode: Option<EditingMode>,
+}
+
 /// Request to update an element's constraints.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateElementRequest {
     /// Cardinality constraint.
     pub cardinality: Option<CardinalityUpdate>,
@@ -333,10 +471,66 @@ pub struct UpdateElementRequest {
     pub definition: Option<String>,
     /// Comment.
     pub comment: Option<String>,
+    /// Default value used when the element is absent (`defaultValue[x]`).
+    #[serde(rename = "defaultValue")]
+    pub default_value: Option<serde_json::Value>,
+    /// Meaning when the element is missing (only for optional elements).
+    #[serde(rename = "meaningWhenMissing")]
+    pub meaning_when_missing: Option<String>,
+    /// Require the element to carry a value, not only extensions (R5+).
+    #[serde(rename = "mustHaveValue")]
+    pub must_have_value: Option<bool>,
+    /// Examples to add, or replace when the label already exists.
+    pub examples: Option<Vec<ExampleUpdate>>,
+    /// Labels of examples to remove.
+    #[serde(rename = "removeExamples")]
+    pub remove_examples: Option<Vec<String>>,
+    /// Changes to the element's existing slicing.
+    pub slicing: Option<SlicingUpdate>,
+}
+
+/// Changes to an existing slicing definition.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SlicingUpdate {
+    /// Slicing rules ("open", "closed", "openAtEnd").
+    pub rules: Option<String>,
+    /// Slicing description (an empty string clears it).
+    pub description: Option<String>,
+    /// Discriminators to add.
+    #[serde(rename = "addDiscriminators")]
+    pub add_discriminators: Option<Vec<DiscriminatorUpdate>>,
+    /// Discriminators to remove.
+    #[serde(rename = "removeDiscriminators")]
+    pub remove_discriminators: Option<Vec<DiscriminatorUpdate>>,
+}
+
+/// A slicing discriminator.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DiscriminatorUpdate {
+    /// Discriminator type (value, exists, pattern, type, profile, position).
+    #[serde(rename = "type")]
+    pub discriminator_type: DiscriminatorType,
+    /// FHIRPath to the discriminating element.
+    pub path: String,
+}
+
+/// Example value update, keyed by label.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ExampleUpdate {
+    /// Example label.
+    pub label: String,
+    /// Example value (JSON).
+    pub value: serde_json::Value,
+    /// FHIR type of the value (e.g., "Quantity"); needed for choice elements.
+    #[serde(rename = "valueType")]
+    pub value_type: Option<String>,
 }
 
 /// Cardinality update.
+///
+/// Accepts `{"min": 1, "max": "*"}` or the FHIR string form `"1..*"`.
 #[derive(Debug, Deserialize)]
+#[serde(try_from = "CardinalityInput")]
 pub struct CardinalityUpdate {
     /// Minimum cardinality.
     pub min: Option<u32>,
@@ -344,8 +538,49 @@ pub struct CardinalityUpdate {
     pub max: Option<MaxCardinality>,
 }
 
+/// Wire forms of a cardinality update.
+#[derive(Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum CardinalityInput {
+    Text(String),
+    Fields {
+        min: Option<u32>,
+        max: Option<MaxCardinality>,
+    },
+}
+
+impl JsonSchema for CardinalityUpdate {
+    fn schema_name() -> String {
+        "CardinalityUpdate".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        CardinalityInput::json_schema(generator)
+    }
+}
+
+impl TryFrom<CardinalityInput> for CardinalityUpdate {
+    type Error = ParseCardinalityError;
+
+    fn try_from(input: CardinalityInput) -> Result<Self, Self::Error> {
+        match input {
+            CardinalityInput::Text(text) => {
+                let cardinality: Cardinality = text.parse()?;
+                Ok(Self {
+                    min: Some(cardinality.min),
+                    max: Some(match cardinality.max {
+                        Some(max) => MaxCardinality::Bounded(max),
+                        None => MaxCardinality::Unbounded("*".to_string()),
+                    }),
+                })
+            }
+            CardinalityInput::Fields { min, max } => Ok(Self { min, max }),
+        }
+    }
+}
+
 /// Maximum cardinality value.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum MaxCardinality {
     /// Numeric maximum.
@@ -365,7 +600,7 @@ impl MaxCardinality {
 }
 
 /// Flags update.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct FlagsUpdate {
     /// Must support flag.
     #[serde(rename = "mustSupport")]
@@ -382,7 +617,7 @@ pub struct FlagsUpdate {
 }
 
 /// Type constraint update.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct TypeConstraintUpdate {
     /// Type code (e.g., "Reference", "CodeableConcept").
     pub code: String,
@@ -394,7 +629,7 @@ pub struct TypeConstraintUpdate {
 }
 
 /// Binding update.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct BindingUpdate {
     /// Binding strength.
     pub strength: String,
@@ -406,7 +641,7 @@ pub struct BindingUpdate {
 }
 
 /// Response after updating an element.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct UpdateElementResponse {
     /// Updated element path.
     pub path: String,
@@ -417,10 +652,17 @@ pub struct UpdateElementResponse {
     pub validation: Vec<Diagnostic>,
 }
 
+/// Response after removing an element.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RemoveElementResponse {
+    /// Removed element path.
+    pub path: String,
+}
+
 // === Update Metadata ===
 
 /// Request to update profile metadata.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct UpdateMetadataRequest {
     /// Profile name.
     pub name: Option<String>,
@@ -440,12 +682,51 @@ pub struct UpdateMetadataRequest {
     pub copyright: Option<String>,
     /// Experimental flag.
     pub experimental: Option<bool>,
+    /// Abstract flag: the profile can be used as a base but not instantiated.
+    #[serde(rename = "abstract")]
+    pub is_abstract: Option<bool>,
+    /// Extension context (Extension definitions only).
+    pub context: Option<Vec<ExtensionContext>>,
+    /// Whether element edits target the differential or the full snapshot.
+    #[serde(rename = "editingMode")]
+    pub editing_mode: Option<EditingMode>,
+}
+
+// === Rename Profile ===
+
+/// Request to rename a profile. Omitted fields keep their current value.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenameProfileRequest {
+    /// New document ID.
+    pub id: Option<String>,
+    /// New computer-friendly name.
+    pub name: Option<String>,
+    /// New canonical URL.
+    pub url: Option<String>,
+}
+
+/// Rename result response.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct RenameProfileResponse {
+    /// Renamed profile details.
+    pub profile: ProfileDetailsResponse,
+    /// IDs of project profiles whose references were rewritten.
+    #[serde(rename = "updatedDependents")]
+    pub updated_dependents: Vec<String>,
 }
 
 // === Import Profile ===
 
+/// Query parameters for profile import.
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportQuery {
+    /// Run the import without writing files or updating the project index.
+    #[serde(default, rename = "dryRun")]
+    pub dry_run: bool,
+}
+
 /// Request to import a profile from SD or FSH.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ImportProfileRequest {
     /// Content format.
     pub format: ImportFormat,
@@ -457,7 +738,7 @@ pub struct ImportProfileRequest {
 }
 
 /// Import format.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ImportFormat {
     /// FHIR StructureDefinition JSON.
@@ -467,18 +748,57 @@ pub enum ImportFormat {
 }
 
 /// Import result response.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ImportResponse {
     /// Imported profile details.
     pub profile: ProfileDetailsResponse,
     /// Import diagnostics.
     pub diagnostics: Vec<Diagnostic>,
+    /// Example instances imported alongside the profile (FSH only).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub instances: Vec<ImportedInstance>,
+    /// Whether this was a dry run (nothing was persisted).
+    #[serde(rename = "dryRun", skip_serializing_if = "std::ops::Not::not")]
+    pub dry_run: bool,
+}
+
+/// An example instance persisted as a project resource during import.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ImportedInstance {
+    /// Project resource ID.
+    pub id: String,
+    /// Instance name.
+    pub name: String,
+    /// FHIR resource type of the instance.
+    #[serde(rename = "resourceType")]
+    pub resource_type: String,
+    /// The `InstanceOf` reference from FSH.
+    #[serde(rename = "instanceOf")]
+    pub instance_of: String,
+}
+
+// === Validate FSH ===
+
+/// Query parameters for FSH validation.
+#[derive(Debug, Deserialize)]
+pub struct ValidateFshQuery {
+    /// FSH source to check.
+    pub content: String,
+}
+
+/// FSH validation result.
+#[derive(Debug, Serialize, JsonSchema)]
+pub struct ValidateFshResponse {
+    /// Whether the FSH parsed and mapped without errors.
+    pub valid: bool,
+    /// Parser, semantic and mapping diagnostics.
+    pub diagnostics: Vec<Diagnostic>,
 }
 
 // === Delete Profile ===
 
 /// Response for delete confirmation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct DeleteConfirmation {
     /// Whether deletion requires confirmation.
     #[serde(rename = "requiresConfirmation")]
@@ -511,6 +831,49 @@ mod tests {
         assert_eq!(error.message, "Something went wrong");
     }
 
+    #[test]
+    fn test_diagnostic_from_fsh_parse_error() {
+        let error = FshImportError::Multiple(vec![
+            FshImportError::parse("profiles/patient.fsh", 7, 3, "Unexpected token"),
+            FshImportError::mapping("No profiles could be successfully mapped"),
+        ]);
+
+        let diagnostics = Diagnostic::from_fsh_error(&error);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].code, "FSH_PARSE_ERROR");
+        assert_eq!(diagnostics[0].file.as_deref(), Some("profiles/patient.fsh"));
+        assert_eq!(diagnostics[0].line, Some(7));
+        assert_eq!(diagnostics[0].column, Some(3));
+        assert_eq!(diagnostics[1].line, None);
+    }
+
+    #[test]
+    fn test_diagnostic_from_import_warning() {
+        use crate::import::ImportWarningCode;
+
+        let warning = ImportWarning::new(ImportWarningCode::NormalizedPath, "Path normalized")
+            .at_path("Patient.name");
+
+        let diagnostic = Diagnostic::from(&warning);
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostic.code, "NORMALIZED_PATH");
+        assert_eq!(diagnostic.path.as_deref(), Some("Patient.name"));
+    }
+
+    #[test]
+    fn test_cardinality_update_string_form() {
+        let update: CardinalityUpdate = serde_json::from_value(serde_json::json!("1..*")).unwrap();
+        assert_eq!(update.min, Some(1));
+        assert_eq!(update.max.unwrap().to_option(), None);
+
+        let update: CardinalityUpdate =
+            serde_json::from_value(serde_json::json!({ "min": 0, "max": 1 })).unwrap();
+        assert_eq!(update.max.unwrap().to_option(), Some(1));
+
+        let err = serde_json::from_value::<CardinalityUpdate>(serde_json::json!("2..1")).unwrap_err();
+        assert!(err.to_string().contains("min is greater than max"));
+    }
+
     #[test]
     fn test_max_cardinality_conversion() {
         let bounded = MaxCardinality::Bounded(5);
@@ -0,0 +1,101 @@
+//! Live profile events over WebSocket.
+//!
+//! Clients connect per profile and receive engine events as JSON text
+//! frames (`{"type": "validation_completed", ...}`):
+//! - `validation_completed` after any validation run
+//! - `operation_applied` after an edit is applied
+//! - `document_saved` after the profile is persisted
+//!
+//! The server pings every [`HEARTBEAT_INTERVAL`] to keep idle connections
+//! open through proxies.
+
+use std::time::Duration;
+
+use axum::{
+    body::Bytes,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::engine::EngineEvent;
+use crate::paths::validate_path_id;
+use crate::state::AppState;
+
+use super::profiles::{ErrorResponse, ProfilePath};
+
+/// Interval between heartbeat pings.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Create profile event routes.
+pub fn event_routes() -> Router<AppState> {
+    Router::new().route("/{profileId}/ws", get(profile_events))
+}
+
+/// GET /api/projects/:projectId/profiles/:profileId/ws
+/// Subscribe to live events of a profile.
+async fn profile_events(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> Response {
+    if let Err(e) = state.project_path(&params.project_id) {
+        return ErrorResponse::from(e).into_response();
+    }
+    if let Err(e) = validate_path_id("profile", &params.profile_id) {
+        return ErrorResponse::from(e).into_response();
+    }
+
+    // Subscribe before upgrading so no event is missed during the handshake
+    let document_id = AppState::profile_document_id(&params.project_id, &params.profile_id);
+    let events = state.events().subscribe(&document_id);
+
+    ws.on_upgrade(move |socket| forward_events(socket, events))
+}
+
+/// Forward events to the socket until either side closes.
+async fn forward_events(mut socket: WebSocket, mut events: Receiver<EngineEvent>) {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick completes immediately
+    heartbeat.tick().await;
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let event = match event {
+                    Ok(event) => event,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::debug!("WebSocket subscriber lagged, skipped {} events", skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                let text = match serde_json::to_string(&event) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::warn!("Failed to serialize engine event: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            _ = heartbeat.tick() => {
+                if socket.send(Message::Ping(Bytes::new())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pongs and client messages are ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
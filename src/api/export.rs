@@ -17,17 +17,23 @@ use axum::{
 use sha2::{Digest, Sha256};
 use std::io::Write;
 use std::path::Path as FsPath;
+use std::future::Future;
+use std::time::{Duration, Instant};
 use zip::write::SimpleFileOptions;
 
 use crate::decompiler::{DecompilerError, decompile_sd_value_to_fsh};
 use crate::export::{ExportConfig, StructureDefinitionExporter, merge_original_sd_fields};
+use crate::fsh::with_leading_comment;
 use crate::ir::ProfileDocument;
+use crate::project::{DependencyGraph, ProjectIndex, ResourceKind};
 use crate::state::AppState;
+use crate::validation::rules::instance::validate_abstract_profile_instances;
 
 use super::dto::{ApiResponse, Diagnostic, DiagnosticSeverity};
 use super::export_dto::*;
-use super::profile_merge::hydrate_profile_document;
-use super::profiles::{ErrorResponse, ProfilePath, ProjectPath};
+use super::highlight::generate_highlighting;
+use super::profile_merge::{hydrate_or_respond, hydrate_profile_document};
+use super::profiles::{load_canonical_index, ErrorResponse, ProfilePath, ProjectPath};
 use super::storage::{ProfileStorage, StorageError};
 
 /// Create export routes.
@@ -39,11 +45,13 @@ pub fn export_routes() -> Router<AppState> {
             get(export_sd).head(export_sd_headers),
         )
         .route("/{profileId}/export/sd/base", get(export_base_sd))
+        .route("/{profileId}/base/tree", get(get_base_tree))
         .route(
             "/{profileId}/export/fsh",
             get(export_fsh).head(export_fsh_headers),
         )
         .route("/{profileId}/export/schema", get(export_schema))
+        .route("/{profileId}/export/csv", get(export_csv))
         .route("/{profileId}/preview", get(preview))
 }
 
@@ -68,30 +76,34 @@ async fn export_sd(
     Query(query): Query<SdExportQuery>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
         }
         Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
     };
-    let doc = match hydrate_profile_document(&state, doc).await {
-        Ok(d) => d,
-        Err(e) => return e.into_response(),
-    };
-    let doc = match hydrate_profile_document(&state, doc).await {
-        Ok(d) => d,
-        Err(e) => return e.into_response(),
-    };
-    let doc = match hydrate_profile_document(&state, doc).await {
+    let doc = match hydrate_or_respond(&state, doc).await {
         Ok(d) => d,
-        Err(e) => return e.into_response(),
+        Err(response) => return response,
     };
 
+    if let Some(element) = &query.element {
+        if doc.resource.find_element(element).is_none() {
+            return ErrorResponse::not_found("Element", element).into_response();
+        }
+    }
+
     // Validate before export
     let validation = validate_for_export(&doc);
     if !validation.can_export(query.force) {
@@ -119,6 +131,7 @@ async fn export_sd(
     };
 
     // Export to JSON
+    let started = Instant::now();
     let mut exporter = StructureDefinitionExporter::with_config(config);
     let mut json_value = match exporter.export_value(&doc).await {
         Ok(v) => v,
@@ -127,6 +140,10 @@ async fn export_sd(
         }
     };
     merge_original_sd_for_export(&project_dir, &doc, &mut json_value).await;
+    if let Some(element) = &query.element {
+        restrict_to_subtree(&mut json_value, element);
+    }
+    state.metrics().observe_export("sd", started.elapsed());
 
     // Serialize for content and ETag
     let json_value = crate::export::recursively_sort_value(&json_value);
@@ -148,10 +165,17 @@ async fn export_sd(
         }
     }
 
-    // Persist if requested
-    let persisted_path = if query.persist {
-        match storage.save_sd_json(&doc.metadata.name, &json_string).await {
-            Ok(path) => Some(path.display().to_string()),
+    // Persist if requested (with the IR, so the two never disagree); a partial
+    // export never replaces the full SD
+    let persisted_path = if query.persist && query.element.is_none() {
+        match storage
+            .save_profile_with_sources(&params.project_id, &doc, Some(&json_string), None)
+            .await
+        {
+            Ok(()) => ProfileStorage::new(&project_dir)
+                .sd_path(&doc.metadata.name)
+                .ok()
+                .map(|path| path.display().to_string()),
             Err(e) => {
                 tracing::warn!("Failed to persist SD export: {}", e);
                 None
@@ -213,10 +237,12 @@ async fn export_base_sd(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
@@ -224,13 +250,13 @@ async fn export_base_sd(
         Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
     };
 
-    let base_url = doc.resource.base.url.clone();
+    let base_url = doc.resource.base.canonical();
     let base_name = doc
         .resource
         .base
         .name
         .clone()
-        .or_else(|| base_url.rsplit('/').next().map(String::from))
+        .or_else(|| doc.resource.base.url.rsplit('/').next().map(String::from))
         .unwrap_or_else(|| "Base".to_string());
 
     let canonical_manager = match state.canonical_manager().await {
@@ -240,7 +266,8 @@ async fn export_base_sd(
                 .into_response();
         }
     };
-    let resolver = crate::base::BaseResolver::new(canonical_manager);
+    let resolver = crate::base::BaseResolver::new(canonical_manager)
+        .with_retry(state.config().resolve_retry_policy());
 
     let json_value = match resolver.load_base_sd_json(&base_url).await {
         Ok(v) => crate::export::recursively_sort_value(&v),
@@ -284,6 +311,87 @@ async fn export_base_sd(
     resp
 }
 
+/// GET /api/projects/:projectId/profiles/:profileId/base/tree
+///
+/// Get the parsed base definition element tree (all elements inherited).
+/// HEAD is served by the same handler without a body.
+async fn get_base_tree(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> impl IntoResponse {
+    let storage = state.storage();
+
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(d) => d,
+        Err(StorageError::NotFound(_)) => {
+            return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
+        }
+        Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
+    };
+
+    let canonical_manager = match state.canonical_manager().await {
+        Ok(mgr) => mgr.clone(),
+        Err(e) => {
+            return ErrorResponse::internal_error(format!("Canonical manager error: {}", e))
+                .into_response();
+        }
+    };
+    let resolver = crate::base::BaseResolver::new(canonical_manager)
+        .with_cache(state.base_tree_cache().clone())
+        .with_retry(state.config().resolve_retry_policy());
+
+    let base_url = doc.resource.base.canonical();
+    let mut root = match resolver
+        .load_base_tree(&base_url, doc.resource.fhir_version)
+        .await
+    {
+        Ok(tree) => tree,
+        Err(
+            e @ (crate::base::BaseResolverError::ResolutionFailed(..)
+            | crate::base::BaseResolverError::VersionNotInstalled(..)),
+        ) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse::new(
+                    StatusCode::NOT_FOUND,
+                    "BASE_NOT_FOUND",
+                    e.to_string(),
+                )),
+            )
+                .into_response();
+        }
+        Err(e) => {
+            return ErrorResponse::internal_error(format!("Failed to load base tree: {}", e))
+                .into_response();
+        }
+    };
+    mark_inherited(&mut root);
+
+    let response = BaseTreeResponse {
+        base_url,
+        fhir_version: doc.resource.fhir_version.as_str().to_string(),
+        element_count: root.descendants().count(),
+        root,
+    };
+
+    Json(ApiResponse::ok(response)).into_response()
+}
+
+/// Mark every element and slice in a tree as inherited.
+fn mark_inherited(element: &mut crate::ir::ElementNode) {
+    element.source = crate::ir::ElementSource::Inherited;
+    for child in &mut element.children {
+        mark_inherited(child);
+    }
+    for slice in element.slices.values_mut() {
+        slice.source = crate::ir::ElementSource::Inherited;
+        mark_inherited(&mut slice.element);
+    }
+}
+
 /// HEAD /api/projects/:projectId/profiles/:profileId/export/sd
 ///
 /// Get headers for SD export (for caching checks).
@@ -292,25 +400,23 @@ async fn export_sd_headers(
     Path(params): Path<ProfilePath>,
     Query(query): Query<SdExportQuery>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(_) => return StatusCode::NOT_FOUND.into_response(),
     };
-    let doc = match hydrate_profile_document(&state, doc).await {
-        Ok(d) => d,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    };
-    let doc = match hydrate_profile_document(&state, doc).await {
+    let doc = match hydrate_or_respond(&state, doc).await {
         Ok(d) => d,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
-    };
-    let doc = match hydrate_profile_document(&state, doc).await {
-        Ok(d) => d,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        Err(response) => return response,
     };
 
     // Build minimal export to calculate ETag
@@ -331,6 +437,12 @@ async fn export_sd_headers(
         Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
     merge_original_sd_for_export(&project_dir, &doc, &mut json_value).await;
+    if let Some(element) = &query.element {
+        if doc.resource.find_element(element).is_none() {
+            return StatusCode::NOT_FOUND.into_response();
+        }
+        restrict_to_subtree(&mut json_value, element);
+    }
 
     let json_string = if query.pretty {
         serde_json::to_string_pretty(&json_value).unwrap_or_default()
@@ -366,11 +478,17 @@ async fn export_fsh(
     Query(query): Query<FshExportQuery>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
@@ -379,9 +497,9 @@ async fn export_fsh(
     };
 
     // Hydrate the profile (merge base + differential into root tree)
-    let doc = match hydrate_profile_document(&state, doc).await {
+    let doc = match hydrate_or_respond(&state, doc).await {
         Ok(d) => d,
-        Err(e) => return e.into_response(),
+        Err(response) => return response,
     };
 
     // Validate before export
@@ -399,13 +517,17 @@ async fn export_fsh(
     }
 
     // Export to SD JSON, then decompile to FSH using maki-decompiler
-    let fsh_content = match generate_fsh_via_decompiler(&project_dir, &doc).await {
+    let started = Instant::now();
+    let export_timeout = state.config().export_timeout_duration();
+    let fsh_content = match generate_fsh_via_decompiler(&project_dir, &doc, export_timeout).await {
         Ok(fsh) => fsh,
+        Err(e @ DecompilerError::TimedOut(_)) => return export_timeout_response(e),
         Err(e) => {
             return ErrorResponse::internal_error(format!("FSH decompilation failed: {}", e))
                 .into_response();
         }
     };
+    state.metrics().observe_export("fsh", started.elapsed());
 
     // Calculate ETag
     let etag = calculate_etag(&fsh_content);
@@ -419,10 +541,16 @@ async fn export_fsh(
         }
     }
 
-    // Persist if requested
+    // Persist if requested, together with the IR
     let persisted_path = if query.persist {
-        match storage.save_fsh(&doc.metadata.name, &fsh_content).await {
-            Ok(path) => Some(path.display().to_string()),
+        match storage
+            .save_profile_with_sources(&params.project_id, &doc, None, Some(&fsh_content))
+            .await
+        {
+            Ok(()) => ProfileStorage::new(&project_dir)
+                .fsh_path(&doc.metadata.name)
+                .ok()
+                .map(|path| path.display().to_string()),
             Err(e) => {
                 tracing::warn!("Failed to persist FSH export: {}", e);
                 None
@@ -484,23 +612,31 @@ async fn export_fsh_headers(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(_) => return StatusCode::NOT_FOUND.into_response(),
     };
 
     // Hydrate the profile
-    let doc = match hydrate_profile_document(&state, doc).await {
+    let doc = match hydrate_or_respond(&state, doc).await {
         Ok(d) => d,
-        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        Err(response) => return response,
     };
 
-    let fsh_content = match generate_fsh_via_decompiler(&project_dir, &doc).await {
+    let export_timeout = state.config().export_timeout_duration();
+    let fsh_content = match generate_fsh_via_decompiler(&project_dir, &doc, export_timeout).await {
         Ok(fsh) => fsh,
+        Err(DecompilerError::TimedOut(_)) => return StatusCode::GATEWAY_TIMEOUT.into_response(),
         Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
     };
     let etag = calculate_etag(&fsh_content);
@@ -519,22 +655,133 @@ async fn export_fsh_headers(
         .into_response()
 }
 
+// === CSV Export ===
+
+/// Column header of the CSV element summary.
+const CSV_HEADER: &str = "path,cardinality,types,mustSupport,bindingStrength,bindingValueSet,short";
+
+/// GET /api/projects/:projectId/profiles/:profileId/export/csv
+///
+/// Export a spreadsheet-friendly summary of the hydrated element tree,
+/// one row per element (including slices).
+async fn export_csv(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> impl IntoResponse {
+    let storage = state.storage();
+
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(d) => d,
+        Err(StorageError::NotFound(_)) => {
+            return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
+        }
+        Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
+    };
+
+    let doc = match hydrate_or_respond(&state, doc).await {
+        Ok(d) => d,
+        Err(response) => return response,
+    };
+
+    let csv = element_tree_csv(&doc.resource.root);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(
+            header::CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}.csv\"", doc.metadata.name),
+        )
+        .body(Body::from(csv))
+        .unwrap()
+        .into_response()
+}
+
+/// Render an element tree as CSV, one row per element and slice.
+fn element_tree_csv(root: &crate::ir::ElementNode) -> String {
+    let mut rows = vec![CSV_HEADER.to_string()];
+    collect_csv_rows(root, &root.path, &mut rows);
+    let mut csv = rows.join("\r\n");
+    csv.push_str("\r\n");
+    csv
+}
+
+/// Append the row for `element` (identified by `id`) and its descendants.
+fn collect_csv_rows(element: &crate::ir::ElementNode, id: &str, rows: &mut Vec<String>) {
+    let constraints = &element.constraints;
+    let types = constraints
+        .types
+        .iter()
+        .map(|t| t.code.as_str())
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let binding = constraints.binding.as_ref();
+    let fields = [
+        id.to_string(),
+        constraints
+            .cardinality
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        types,
+        constraints.flags.must_support.to_string(),
+        binding
+            .map(|b| b.strength.as_str().to_string())
+            .unwrap_or_default(),
+        binding.map(|b| b.value_set.clone()).unwrap_or_default(),
+        constraints.short.clone().unwrap_or_default(),
+    ];
+    rows.push(
+        fields
+            .iter()
+            .map(|field| csv_field(field))
+            .collect::<Vec<_>>()
+            .join(","),
+    );
+
+    for child in &element.children {
+        collect_csv_rows(child, &format!("{}.{}", id, child.short_name()), rows);
+    }
+    for (name, slice) in &element.slices {
+        collect_csv_rows(&slice.element, &format!("{}:{}", id, name), rows);
+    }
+}
+
+/// Quote a CSV field when it contains a delimiter, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
 // === FHIR Schema Export (R4) ===
 
 /// GET /api/projects/:projectId/profiles/:profileId/export/schema
 ///
-/// Export a profile as FHIR Schema JSON.
+/// Export a profile as FHIR Schema JSON. `?mode=differential` produces a
+/// schema containing only the profile's own constraints.
 async fn export_schema(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
-    Query(query): Query<FshExportQuery>, // Reuse FshExportQuery for persist/force
+    Query(query): Query<SchemaExportQuery>,
     headers: HeaderMap,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
@@ -543,9 +790,9 @@ async fn export_schema(
     };
 
     // Hydrate the profile
-    let doc = match hydrate_profile_document(&state, doc).await {
+    let doc = match hydrate_or_respond(&state, doc).await {
         Ok(d) => d,
-        Err(e) => return e.into_response(),
+        Err(response) => return response,
     };
 
     // Validate before export
@@ -563,13 +810,35 @@ async fn export_schema(
     }
 
     // Export to SD JSON, then convert to FHIR Schema
-    let schema_content = match generate_fhirschema(&project_dir, &doc).await {
+    let started = Instant::now();
+    let export_timeout = state.config().export_timeout_duration();
+    let schema_content = match generate_fhirschema_with_mode(
+        &project_dir,
+        &doc,
+        query.mode,
+        export_timeout,
+    )
+    .await
+    {
         Ok(s) => s,
+        Err(e @ SchemaGenerationError::TimedOut(_)) => return export_timeout_response(e),
+        Err(e @ SchemaGenerationError::NotRepresentable(_)) => {
+            return (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(ErrorResponse::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "SCHEMA_NOT_REPRESENTABLE",
+                    e.to_string(),
+                )),
+            )
+                .into_response();
+        }
         Err(e) => {
             return ErrorResponse::internal_error(format!("FHIR Schema conversion failed: {}", e))
                 .into_response();
         }
     };
+    state.metrics().observe_export("schema", started.elapsed());
 
     // Calculate ETag
     let etag = calculate_etag(&schema_content);
@@ -583,10 +852,15 @@ async fn export_schema(
         }
     }
 
+    let schema_name = match query.mode {
+        SchemaExportMode::Snapshot => format!("{}.schema", doc.metadata.name),
+        SchemaExportMode::Differential => format!("{}.differential.schema", doc.metadata.name),
+    };
+
     // Persist if requested
     let persisted_path = if query.persist {
-        match storage
-            .save_sd_json(&format!("{}.schema", doc.metadata.name), &schema_content)
+        match ProfileStorage::new(&project_dir)
+            .save_sd_json(&schema_name, &schema_content)
             .await
         {
             Ok(path) => Some(path.display().to_string()),
@@ -605,7 +879,7 @@ async fn export_schema(
         name: doc.metadata.name.clone(),
         url: doc.metadata.url.clone(),
         fhir_version: doc.resource.fhir_version.as_str().to_string(),
-        filename: format!("{}.schema.json", doc.metadata.name),
+        filename: format!("{}.json", schema_name),
         content_type: "application/schema+json".to_string(),
         etag: etag.clone(),
         persisted_path,
@@ -629,10 +903,7 @@ async fn export_schema(
     );
     headers.insert(
         header::CONTENT_DISPOSITION,
-        HeaderValue::from_str(&format!(
-            "attachment; filename=\"{}.schema.json\"",
-            doc.metadata.name
-        ))
+        HeaderValue::from_str(&format!("attachment; filename=\"{}.json\"", schema_name))
         .unwrap(),
     );
 
@@ -650,16 +921,21 @@ async fn bulk_export(
     Path(params): Path<ProjectPath>,
     Query(query): Query<BulkExportQuery>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load all profiles
-    let profiles = match storage.list_profiles().await {
+    let profiles = match storage.list_profiles(&params.project_id).await {
         Ok(p) => p,
         Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
     };
 
-    if profiles.is_empty() {
+    let instances = load_project_instances(&project_dir).await;
+
+    if profiles.is_empty() && instances.is_empty() {
         return (
             StatusCode::OK,
             Json(ApiResponse::ok(BulkExportResponse {
@@ -680,10 +956,10 @@ async fn bulk_export(
 
     match query.structure {
         ExportStructure::Flat => {
-            bulk_export_flat(&state, &params.project_id, profiles, &query).await
+            bulk_export_flat(&state, &params.project_id, profiles, instances, &query).await
         }
         ExportStructure::Packaged => {
-            bulk_export_packaged(&state, &params.project_id, profiles, &query).await
+            bulk_export_packaged(&state, &params.project_id, profiles, instances, &query).await
         }
     }
 }
@@ -693,14 +969,21 @@ async fn bulk_export_flat(
     state: &AppState,
     project_id: &str,
     profiles: Vec<ProfileDocument>,
+    instances: Vec<ProjectInstance>,
     query: &BulkExportQuery,
 ) -> Response<Body> {
-    let project_dir = state.project_path(project_id);
+    let project_dir = match state.project_path(project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let export_timeout = state.config().export_timeout_duration();
     let mut files = Vec::new();
-    let mut diagnostics = Vec::new();
+    let mut diagnostics = abstract_profile_instance_diagnostics(&profiles, &instances);
+    let (profiles, order_warning) = order_profiles_for_export(profiles);
+    diagnostics.extend(order_warning);
     let mut success_count = 0u32;
     let mut failed_count = 0u32;
-    let total = profiles.len() as u32;
+    let total = (profiles.len() + instances.len()) as u32;
 
     for doc in profiles {
         let resource_id = doc.metadata.id.clone();
@@ -717,6 +1000,7 @@ async fn bulk_export_flat(
                         code: "HYDRATION_FAILED".to_string(),
                         message: "Failed to hydrate profile for export".to_string(),
                         path: None,
+                        ..Default::default()
                     }],
                 });
                 continue;
@@ -770,6 +1054,7 @@ async fn bulk_export_flat(
                             code: "EXPORT_FAILED".to_string(),
                             message: format!("SD export failed: {}", e),
                             path: None,
+                            ..Default::default()
                         }],
                     });
                 }
@@ -778,7 +1063,7 @@ async fn bulk_export_flat(
 
         // Export FSH if requested
         if matches!(query.format, BulkExportFormat::Fsh | BulkExportFormat::Both) {
-            match generate_fsh_via_decompiler(&project_dir, &doc).await {
+            match generate_fsh_via_decompiler(&project_dir, &doc, export_timeout).await {
                 Ok(content) => {
                     files.push(ExportedFile {
                         resource_id: doc.metadata.id.clone(),
@@ -798,6 +1083,7 @@ async fn bulk_export_flat(
                             code: "FSH_DECOMPILE_FAILED".to_string(),
                             message: format!("FSH decompilation failed: {}", e),
                             path: None,
+                            ..Default::default()
                         }],
                     });
                 }
@@ -809,7 +1095,7 @@ async fn bulk_export_flat(
             query.format,
             BulkExportFormat::FhirSchema | BulkExportFormat::Both
         ) {
-            match generate_fhirschema(&project_dir, &doc).await {
+            match generate_fhirschema(&project_dir, &doc, export_timeout).await {
                 Ok(content) => {
                     files.push(ExportedFile {
                         resource_id: doc.metadata.id.clone(),
@@ -829,6 +1115,7 @@ async fn bulk_export_flat(
                             code: "SCHEMA_GEN_FAILED".to_string(),
                             message: format!("FHIR Schema generation failed: {}", e),
                             path: None,
+                            ..Default::default()
                         }],
                     });
                 }
@@ -841,6 +1128,26 @@ async fn bulk_export_flat(
         }
     }
 
+    // Example instances are exported as-is alongside the SDs
+    if matches!(query.format, BulkExportFormat::Sd | BulkExportFormat::Both) {
+        for instance in instances {
+            let content = if query.pretty {
+                serde_json::to_string_pretty(&instance.resource).unwrap_or_default()
+            } else {
+                serde_json::to_string(&instance.resource).unwrap_or_default()
+            };
+            files.push(ExportedFile {
+                path: format!("SD/Instance/{}.json", instance.id),
+                resource_id: instance.id.clone(),
+                name: instance.id,
+                format: "sd".to_string(),
+                content,
+                is_base64: false,
+            });
+            success_count += 1;
+        }
+    }
+
     let formats = match query.format {
         BulkExportFormat::Sd => vec!["sd".to_string()],
         BulkExportFormat::Fsh => vec!["fsh".to_string()],
@@ -873,9 +1180,26 @@ async fn bulk_export_packaged(
     state: &AppState,
     project_id: &str,
     profiles: Vec<ProfileDocument>,
+    instances: Vec<ProjectInstance>,
     query: &BulkExportQuery,
 ) -> Response<Body> {
-    let project_dir = state.project_path(project_id);
+    let project_dir = match state.project_path(project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let export_timeout = state.config().export_timeout_duration();
+    for resource in abstract_profile_instance_diagnostics(&profiles, &instances) {
+        for diagnostic in &resource.diagnostics {
+            tracing::warn!("{}", diagnostic.message);
+        }
+    }
+    let (profiles, order_warning) = order_profiles_for_export(profiles);
+    if let Some(warning) = order_warning {
+        for diagnostic in &warning.diagnostics {
+            tracing::warn!("{}", diagnostic.message);
+        }
+    }
+    let index = load_canonical_index(state, project_id).await;
     // Create in-memory ZIP file
     let mut zip_buffer = Vec::new();
     {
@@ -933,9 +1257,10 @@ async fn bulk_export_packaged(
 
             // Export FSH if requested
             if matches!(query.format, BulkExportFormat::Fsh | BulkExportFormat::Both) {
-                match generate_fsh_via_decompiler(&project_dir, &doc).await {
+                match generate_fsh_via_decompiler(&project_dir, &doc, export_timeout).await {
                     Ok(fsh_content) => {
-                        let path = format!("input/fsh/profiles/{}.fsh", doc.metadata.name);
+                        let kind = export_resource_kind(&doc, &index);
+                        let path = packaged_fsh_path(kind, &doc.metadata.name);
                         let _ = zip.start_file(&path, options);
                         let _ = zip.write_all(fsh_content.as_bytes());
                     }
@@ -950,7 +1275,7 @@ async fn bulk_export_packaged(
                 query.format,
                 BulkExportFormat::FhirSchema | BulkExportFormat::Both
             ) {
-                match generate_fhirschema(&project_dir, &doc).await {
+                match generate_fhirschema(&project_dir, &doc, export_timeout).await {
                     Ok(content) => {
                         let path = format!("input/schema/{}.schema.json", doc.metadata.name);
                         let _ = zip.start_file(&path, options);
@@ -967,6 +1292,25 @@ async fn bulk_export_packaged(
             }
         }
 
+        // Add example instances
+        if matches!(query.format, BulkExportFormat::Sd | BulkExportFormat::Both) {
+            for instance in &instances {
+                let path = format!(
+                    "input/examples/{}-{}.json",
+                    instance.resource_type(),
+                    instance.id
+                );
+                let content = if query.pretty {
+                    serde_json::to_string_pretty(&instance.resource).unwrap_or_default()
+                } else {
+                    serde_json::to_string(&instance.resource).unwrap_or_default()
+                };
+                if zip.start_file(&path, options).is_ok() {
+                    let _ = zip.write_all(content.as_bytes());
+                }
+            }
+        }
+
         // Add IG scaffold files
         let ig_json = generate_ig_scaffold(project_id, &hydrated_profiles);
         if zip.start_file("ig.ini", options).is_ok() {
@@ -1010,22 +1354,211 @@ async fn bulk_export_packaged(
         .unwrap()
 }
 
+/// Resource kind of a profile document, for routing it in exports.
+///
+/// Documents deriving from Extension (directly or through project
+/// extensions) are extensions; otherwise the project index kind applies.
+fn export_resource_kind(doc: &ProfileDocument, index: &ProjectIndex) -> ResourceKind {
+    let is_extension = doc.resource.is_extension_with(|url| {
+        index
+            .find_by_canonical(url)
+            .and_then(|resource| resource.base.clone())
+    });
+    if is_extension {
+        return ResourceKind::Extension;
+    }
+    index
+        .get_resource(&doc.metadata.id)
+        .map_or(ResourceKind::Profile, |resource| resource.kind)
+}
+
+/// Path of a FSH file in a packaged export, in the SUSHI subfolder for its kind.
+fn packaged_fsh_path(kind: ResourceKind, name: &str) -> String {
+    format!("input/fsh/{}/{}.fsh", kind.fsh_subdir(), name)
+}
+
+/// An example instance stored as raw JSON under `SD/Instance`.
+pub(super) struct ProjectInstance {
+    /// Resource ID (file stem).
+    pub id: String,
+    /// Raw resource JSON.
+    pub resource: serde_json::Value,
+}
+
+impl ProjectInstance {
+    fn resource_type(&self) -> &str {
+        self.resource
+            .get("resourceType")
+            .and_then(|v| v.as_str())
+            .unwrap_or("Resource")
+    }
+}
+
+/// Errors for example instances claiming conformance (`meta.profile`) to an
+/// abstract project profile, which may only be used as a base.
+fn abstract_profile_instance_diagnostics(
+    profiles: &[ProfileDocument],
+    instances: &[ProjectInstance],
+) -> Vec<ResourceDiagnostic> {
+    instances
+        .iter()
+        .filter_map(|instance| {
+            let diagnostics: Vec<Diagnostic> = profiles
+                .iter()
+                .flat_map(|doc| {
+                    validate_abstract_profile_instances(
+                        doc,
+                        [(instance.id.as_str(), &instance.resource)],
+                    )
+                })
+                .map(|diagnostic| Diagnostic {
+                    severity: DiagnosticSeverity::Error,
+                    code: diagnostic.code,
+                    message: diagnostic.message,
+                    path: Some("meta.profile".to_string()),
+                    ..Default::default()
+                })
+                .collect();
+            (!diagnostics.is_empty()).then(|| ResourceDiagnostic {
+                resource_id: instance.id.clone(),
+                name: instance.id.clone(),
+                diagnostics,
+            })
+        })
+        .collect()
+}
+
+/// Load the project's example instances, sorted by ID.
+///
+/// Unreadable or invalid files are skipped with a warning.
+pub(super) async fn load_project_instances(project_dir: &FsPath) -> Vec<ProjectInstance> {
+    let dir = project_dir
+        .join("SD")
+        .join(ResourceKind::Instance.sd_subdir());
+    let Ok(mut entries) = tokio::fs::read_dir(&dir).await else {
+        return Vec::new();
+    };
+
+    let mut instances = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(id) = path.file_stem().and_then(|s| s.to_str()).map(str::to_string) else {
+            continue;
+        };
+        let parsed = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|content| serde_json::from_str(&content).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(resource) => instances.push(ProjectInstance { id, resource }),
+            Err(e) => tracing::warn!("Skipping instance {}: {}", path.display(), e),
+        }
+    }
+
+    instances.sort_by(|a, b| a.id.cmp(&b.id));
+    instances
+}
+
+/// Order profiles for bulk export so that dependencies come before dependents.
+///
+/// A profile depends on another project profile when it uses it as its base or
+/// references it from a type `profile`/`targetProfile`. Profiles are sorted by
+/// name first, and if the graph has a cycle that name order is kept and a
+/// warning diagnostic is returned.
+fn order_profiles_for_export(
+    mut profiles: Vec<ProfileDocument>,
+) -> (Vec<ProfileDocument>, Option<ResourceDiagnostic>) {
+    profiles.sort_by(|a, b| a.metadata.name.cmp(&b.metadata.name));
+
+    let urls: std::collections::HashSet<String> =
+        profiles.iter().map(|doc| doc.metadata.url.clone()).collect();
+    let mut graph = DependencyGraph::new();
+    for doc in &profiles {
+        let url = &doc.metadata.url;
+        graph.add_node(url);
+
+        for dep in doc.resource.referenced_canonicals() {
+            if dep != url && urls.contains(dep) {
+                graph.add_dependency(url, dep);
+            }
+        }
+    }
+
+    let order = match graph.topological_order() {
+        Ok(order) => order,
+        Err(e) => {
+            let (resource_id, name) = profiles
+                .iter()
+                .find(|doc| doc.metadata.url == e.resource)
+                .map(|doc| (doc.metadata.id.clone(), doc.metadata.name.clone()))
+                .unwrap_or_else(|| (e.resource.clone(), e.resource.clone()));
+            let warning = ResourceDiagnostic {
+                resource_id,
+                name,
+                diagnostics: vec![Diagnostic {
+                    severity: DiagnosticSeverity::Warning,
+                    code: "DEPENDENCY_CYCLE".to_string(),
+                    message: format!(
+                        "Circular dependency involving '{}'; exporting in name order",
+                        e.resource
+                    ),
+                    path: None,
+                    ..Default::default()
+                }],
+            };
+            return (profiles, Some(warning));
+        }
+    };
+
+    let position: std::collections::HashMap<&str, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(i, url)| (url.as_str(), i))
+        .collect();
+    let mut ranked: Vec<(usize, ProfileDocument)> = profiles
+        .into_iter()
+        .map(|doc| {
+            let rank = position
+                .get(doc.metadata.url.as_str())
+                .copied()
+                .unwrap_or(usize::MAX);
+            (rank, doc)
+        })
+        .collect();
+    ranked.sort_by_key(|(rank, _)| *rank);
+
+    (ranked.into_iter().map(|(_, doc)| doc).collect(), None)
+}
+
 // === Preview (R5) ===
 
 /// GET /api/projects/:projectId/profiles/:profileId/preview
 ///
 /// Get formatted preview without downloading.
+///
+/// Honors `If-None-Match` with an ETag over format and content, so repeated
+/// refreshes of an unchanged profile return 304.
 #[axum::debug_handler]
 async fn preview(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
     Query(query): Query<PreviewQuery>,
+    headers: HeaderMap,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Load profile
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response();
@@ -1036,6 +1569,7 @@ async fn preview(
     // Validate
     let validation = validate_for_export(&doc);
 
+    let export_timeout = state.config().export_timeout_duration();
     let (content, language) = match query.format {
         PreviewFormat::Sd => {
             let config = ExportConfig::default().pretty();
@@ -1053,15 +1587,21 @@ async fn preview(
                 }
             }
         }
-        PreviewFormat::Fsh => match generate_fsh_via_decompiler(&project_dir, &doc).await {
+        PreviewFormat::Fsh => match generate_fsh_via_decompiler(&project_dir, &doc, export_timeout)
+            .await
+        {
             Ok(content) => (content, "fsh"),
+            Err(e @ DecompilerError::TimedOut(_)) => return export_timeout_response(e),
             Err(e) => {
                 return ErrorResponse::internal_error(format!("Export failed: {}", e))
                     .into_response();
             }
         },
-        PreviewFormat::FhirSchema => match generate_fhirschema(&project_dir, &doc).await {
+        PreviewFormat::FhirSchema => match generate_fhirschema(&project_dir, &doc, export_timeout)
+            .await
+        {
             Ok(content) => (content, "json"),
+            Err(e @ SchemaGenerationError::TimedOut(_)) => return export_timeout_response(e),
             Err(e) => {
                 return ErrorResponse::internal_error(format!("Export failed: {}", e))
                     .into_response();
@@ -1069,6 +1609,21 @@ async fn preview(
         },
     };
 
+    // ETag covers the format and highlight flag as well as the content
+    let etag = calculate_etag(&format!(
+        "{:?}\n{}\n{}",
+        query.format, query.highlight, content
+    ));
+
+    // Check If-None-Match for caching
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH) {
+        if let Ok(value) = if_none_match.to_str() {
+            if value == etag || value == format!("\"{}\"", etag) {
+                return StatusCode::NOT_MODIFIED.into_response();
+            }
+        }
+    }
+
     // Generate syntax highlighting if requested
     let highlighting = if query.highlight {
         Some(generate_highlighting(&content, language))
@@ -1083,7 +1638,12 @@ async fn preview(
         diagnostics: validation.diagnostics,
     };
 
-    Json(ApiResponse::ok(response)).into_response()
+    let mut resp = Json(ApiResponse::ok(response)).into_response();
+    resp.headers_mut().insert(
+        header::ETAG,
+        HeaderValue::from_str(&format!("\"{}\"", etag)).unwrap(),
+    );
+    resp
 }
 
 // === Helper Functions ===
@@ -1112,6 +1672,69 @@ async fn merge_original_sd_for_export(
     }
 }
 
+/// Restrict an exported SD's differential and snapshot to one element's subtree.
+///
+/// Keeps the element (including its slices) and its descendants; every
+/// ancestor is reduced to an `id`/`path` stub so the fragment stays anchored.
+/// `element` is an element id such as `Observation.component` or
+/// `Patient.identifier:mrn`.
+fn restrict_to_subtree(sd_value: &mut serde_json::Value, element: &str) {
+    for view in ["differential", "snapshot"] {
+        let Some(elements) = sd_value
+            .get_mut(view)
+            .and_then(|v| v.get_mut("element"))
+            .and_then(|v| v.as_array_mut())
+        else {
+            continue;
+        };
+
+        let subtree: Vec<serde_json::Value> = elements
+            .iter()
+            .filter(|e| {
+                let id = e
+                    .get("id")
+                    .or_else(|| e.get("path"))
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default();
+                id == element
+                    || id
+                        .strip_prefix(element)
+                        .is_some_and(|rest| rest.starts_with('.') || rest.starts_with(':'))
+            })
+            .cloned()
+            .collect();
+
+        let mut restricted = ancestor_stubs(element);
+        restricted.extend(subtree);
+        *elements = restricted;
+    }
+}
+
+/// `id`/`path` stubs for the ancestors of an element id, outermost first.
+fn ancestor_stubs(element: &str) -> Vec<serde_json::Value> {
+    let segments: Vec<&str> = element.split('.').collect();
+    let mut ids = Vec::new();
+    let mut paths = Vec::new();
+
+    segments[..segments.len() - 1]
+        .iter()
+        .map(|segment| {
+            let (name, slice_name) = match segment.split_once(':') {
+                Some((name, slice_name)) => (name, Some(slice_name)),
+                None => (*segment, None),
+            };
+            ids.push(*segment);
+            paths.push(name);
+
+            let mut stub = serde_json::json!({ "id": ids.join("."), "path": paths.join(".") });
+            if let Some(slice_name) = slice_name {
+                stub["sliceName"] = serde_json::json!(slice_name);
+            }
+            stub
+        })
+        .collect()
+}
+
 /// Validate a profile document before export.
 fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
     let mut diagnostics = Vec::new();
@@ -1123,6 +1746,7 @@ fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
             code: "MISSING_URL".to_string(),
             message: "Profile URL is required for export".to_string(),
             path: None,
+            ..Default::default()
         });
     }
 
@@ -1132,6 +1756,7 @@ fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
             code: "MISSING_NAME".to_string(),
             message: "Profile name is required for export".to_string(),
             path: None,
+            ..Default::default()
         });
     }
 
@@ -1144,6 +1769,7 @@ fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
                 code: "INVALID_NAME_FORMAT".to_string(),
                 message: "Profile name should start with an uppercase letter".to_string(),
                 path: None,
+                ..Default::default()
             });
         }
         if doc.metadata.name.contains(' ') {
@@ -1152,6 +1778,7 @@ fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
                 code: "INVALID_NAME_SPACES".to_string(),
                 message: "Profile name cannot contain spaces".to_string(),
                 path: None,
+                ..Default::default()
             });
         }
     }
@@ -1163,6 +1790,7 @@ fn validate_for_export(doc: &ProfileDocument) -> ValidationResult {
             code: "MISSING_BASE".to_string(),
             message: "Base definition URL is required".to_string(),
             path: None,
+            ..Default::default()
         });
     }
 
@@ -1186,6 +1814,7 @@ fn validate_element_tree(element: &crate::ir::ElementNode, diagnostics: &mut Vec
                         element.path, max, card.min
                     ),
                     path: Some(element.path.clone()),
+                    ..Default::default()
                 });
             }
         }
@@ -1202,6 +1831,7 @@ fn validate_element_tree(element: &crate::ir::ElementNode, diagnostics: &mut Vec
                     element.path
                 ),
                 path: Some(element.path.clone()),
+                ..Default::default()
             });
         }
     }
@@ -1238,6 +1868,17 @@ fn calculate_etag(content: &str) -> String {
 async fn generate_fsh_via_decompiler(
     project_dir: &FsPath,
     doc: &ProfileDocument,
+    limit: Duration,
+) -> Result<String, DecompilerError> {
+    let (project_dir, doc) = (project_dir.to_path_buf(), doc.clone());
+    let step = async move { decompile_profile(&project_dir, &doc).await };
+    within_export_timeout(limit, DecompilerError::TimedOut, step).await
+}
+
+/// Export a profile as a differential-only SD and decompile it to FSH.
+async fn decompile_profile(
+    project_dir: &FsPath,
+    doc: &ProfileDocument,
 ) -> Result<String, DecompilerError> {
     // Export with differential-only to generate minimal FSH
     // maki-decompiler reads from sd.differential to extract rules
@@ -1251,13 +1892,75 @@ async fn generate_fsh_via_decompiler(
     merge_original_sd_for_export(project_dir, doc, &mut sd_value).await;
 
     // Use maki-decompiler to convert SD to FSH
-    decompile_sd_value_to_fsh(&sd_value, doc.resource.fhir_version).await
+    let fsh = decompile_sd_value_to_fsh(&sd_value, doc.resource.fhir_version).await?;
+    Ok(with_leading_comment(&fsh, doc.resource.fsh_comment.as_deref()))
 }
 
-/// Generate FHIR Schema content using octofhir-fhirschema.
+/// Errors raised while generating FHIR Schema content.
+#[derive(Debug, thiserror::Error)]
+enum SchemaGenerationError {
+    /// Export or translation failed.
+    #[error("{0}")]
+    Failed(String),
+    /// The profile's constraints cannot be expressed as a differential schema.
+    #[error("Differential FHIR Schema is not representable: {0}")]
+    NotRepresentable(String),
+    /// Conversion did not finish within the export timeout.
+    #[error("FHIR Schema conversion timed out after {0:?}")]
+    TimedOut(Duration),
+}
+
+/// Run an export step, failing with `timed_out(limit)` once `limit` has passed.
+///
+/// Decompiling or converting a huge profile can take arbitrarily long, and
+/// most of that time is synchronous work that never yields to the runtime.
+/// The step therefore runs on the blocking pool and the timeout is applied to
+/// its join handle: the request is answered once `limit` has passed, while
+/// the abandoned step finishes in the background without holding a worker.
+async fn within_export_timeout<T, E, F>(
+    limit: Duration,
+    timed_out: impl FnOnce(Duration) -> E,
+    step: F,
+) -> Result<T, E>
+where
+    F: Future<Output = Result<T, E>> + Send + 'static,
+    T: Send + 'static,
+    E: Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    let task = tokio::task::spawn_blocking(move || runtime.block_on(step));
+    match tokio::time::timeout(limit, task).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(join_error)) => std::panic::resume_unwind(join_error.into_panic()),
+        Err(_) => Err(timed_out(limit)),
+    }
+}
+
+/// 504 response for an export step that hit the export timeout.
+fn export_timeout_response(error: impl std::fmt::Display) -> Response<Body> {
+    ErrorResponse::new(StatusCode::GATEWAY_TIMEOUT, "EXPORT_TIMEOUT", error.to_string())
+        .into_response()
+}
+
+/// Generate FHIR Schema content, giving up after `limit`.
 async fn generate_fhirschema(
     project_dir: &FsPath,
     doc: &ProfileDocument,
+    limit: Duration,
+) -> Result<String, SchemaGenerationError> {
+    let (project_dir, doc) = (project_dir.to_path_buf(), doc.clone());
+    let step = async move {
+        snapshot_fhirschema(&project_dir, &doc)
+            .await
+            .map_err(SchemaGenerationError::Failed)
+    };
+    within_export_timeout(limit, SchemaGenerationError::TimedOut, step).await
+}
+
+/// Generate FHIR Schema content using octofhir-fhirschema.
+async fn snapshot_fhirschema(
+    project_dir: &FsPath,
+    doc: &ProfileDocument,
 ) -> Result<String, String> {
     // Export with full snapshot as FHIR Schema converter usually needs it
     let config = ExportConfig::default();
@@ -1269,7 +1972,84 @@ async fn generate_fhirschema(
         .map_err(|e| format!("SD export failed: {}", e))?;
     merge_original_sd_for_export(project_dir, doc, &mut sd_value).await;
 
-    // Convert SD to FHIR Schema using translate function
+    let schema = translate_sd_to_fhirschema(sd_value)?;
+
+    // Return as pretty JSON
+    serde_json::to_string_pretty(&schema).map_err(|e| format!("Serialization failed: {}", e))
+}
+
+/// Generate FHIR Schema content in the requested mode.
+async fn generate_fhirschema_with_mode(
+    project_dir: &FsPath,
+    doc: &ProfileDocument,
+    mode: SchemaExportMode,
+    limit: Duration,
+) -> Result<String, SchemaGenerationError> {
+    match mode {
+        SchemaExportMode::Snapshot => generate_fhirschema(project_dir, doc, limit).await,
+        SchemaExportMode::Differential => {
+            let (project_dir, doc) = (project_dir.to_path_buf(), doc.clone());
+            let step = async move { generate_differential_fhirschema(&project_dir, &doc).await };
+            within_export_timeout(limit, SchemaGenerationError::TimedOut, step).await
+        }
+    }
+}
+
+/// Generate a FHIR Schema reflecting only the profile's differential.
+///
+/// The differential-only SD is translated directly. If the translator needs a
+/// snapshot, the hydrated (base + differential) tree is translated instead and
+/// the result is pruned back to the differential paths.
+async fn generate_differential_fhirschema(
+    project_dir: &FsPath,
+    doc: &ProfileDocument,
+) -> Result<String, SchemaGenerationError> {
+    let mut exporter = StructureDefinitionExporter::with_config(ExportConfig::differential_only());
+    let mut sd_value = exporter
+        .export_value(doc)
+        .await
+        .map_err(|e| SchemaGenerationError::Failed(format!("SD export failed: {}", e)))?;
+    merge_original_sd_for_export(project_dir, doc, &mut sd_value).await;
+
+    let schema = match translate_sd_to_fhirschema(sd_value) {
+        Ok(schema) => schema,
+        Err(differential_error) => {
+            tracing::debug!(
+                "Differential-only schema translation failed ({}), falling back to snapshot",
+                differential_error
+            );
+            if doc.resource.root.is_empty() {
+                return Err(SchemaGenerationError::NotRepresentable(format!(
+                    "base definition '{}' is not resolved and the differential cannot be translated on its own",
+                    doc.resource.base.url
+                )));
+            }
+
+            let mut exporter = StructureDefinitionExporter::with_config(ExportConfig::default());
+            let mut full_sd = exporter
+                .export_value(doc)
+                .await
+                .map_err(|e| SchemaGenerationError::Failed(format!("SD export failed: {}", e)))?;
+            merge_original_sd_for_export(project_dir, doc, &mut full_sd).await;
+
+            let mut schema =
+                translate_sd_to_fhirschema(full_sd).map_err(SchemaGenerationError::Failed)?;
+            let paths = differential_schema_paths(doc);
+            if !prune_schema_elements(&mut schema, &paths) {
+                return Err(SchemaGenerationError::NotRepresentable(
+                    "translated schema has no element structure to prune".to_string(),
+                ));
+            }
+            schema
+        }
+    };
+
+    serde_json::to_string_pretty(&schema)
+        .map_err(|e| SchemaGenerationError::Failed(format!("Serialization failed: {}", e)))
+}
+
+/// Translate a StructureDefinition JSON value into a FHIR Schema JSON value.
+fn translate_sd_to_fhirschema(sd_value: serde_json::Value) -> Result<serde_json::Value, String> {
     // We need to deserialize into StructureDefinition struct first
     let sd: octofhir_fhirschema::StructureDefinition = serde_json::from_value(sd_value)
         .map_err(|e| format!("Deserialization failed for Schema conversion: {}", e))?;
@@ -1277,185 +2057,56 @@ async fn generate_fhirschema(
     let schema = octofhir_fhirschema::translate(sd, None)
         .map_err(|e| format!("FHIR Schema conversion failed: {}", e))?;
 
-    // Return as pretty JSON
-    serde_json::to_string_pretty(&schema).map_err(|e| format!("Serialization failed: {}", e))
+    serde_json::to_value(&schema).map_err(|e| format!("Serialization failed: {}", e))
 }
 
-/// Generate basic syntax highlighting tokens.
-fn generate_highlighting(content: &str, language: &str) -> SyntaxHighlighting {
-    let mut tokens = Vec::new();
-
-    match language {
-        "json" => {
-            // Simple JSON tokenization
-            for (line_num, line) in content.lines().enumerate() {
-                let line_num = line_num as u32;
-
-                // Find string keys
-                let chars: Vec<char> = line.chars().collect();
-                let mut i = 0;
-                while i < chars.len() {
-                    if chars[i] == '"' {
-                        let start = i;
-                        i += 1;
-                        while i < chars.len() && chars[i] != '"' {
-                            if chars[i] == '\\' {
-                                i += 1;
-                            }
-                            i += 1;
-                        }
-                        if i < chars.len() {
-                            i += 1;
-                        }
-                        // Check if followed by colon (key) or not (value)
-                        let mut j = i;
-                        while j < chars.len() && chars[j].is_whitespace() {
-                            j += 1;
-                        }
-                        let token_type = if j < chars.len() && chars[j] == ':' {
-                            "key"
-                        } else {
-                            "string"
-                        };
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: start as u32,
-                            end_column: i as u32,
-                            token_type: token_type.to_string(),
-                        });
-                    } else if chars[i].is_ascii_digit()
-                        || (chars[i] == '-' && i + 1 < chars.len() && chars[i + 1].is_ascii_digit())
-                    {
-                        let start = i;
-                        while i < chars.len()
-                            && (chars[i].is_ascii_digit()
-                                || chars[i] == '.'
-                                || chars[i] == '-'
-                                || chars[i] == 'e'
-                                || chars[i] == 'E')
-                        {
-                            i += 1;
-                        }
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: start as u32,
-                            end_column: i as u32,
-                            token_type: "number".to_string(),
-                        });
-                    } else if content[i..].starts_with("true")
-                        || content[i..].starts_with("false")
-                        || content[i..].starts_with("null")
-                    {
-                        let word_len = if chars[i..].starts_with(&['t', 'r', 'u', 'e']) {
-                            4
-                        } else if chars[i..].starts_with(&['f', 'a', 'l', 's', 'e']) {
-                            5
-                        } else {
-                            4
-                        };
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: i as u32,
-                            end_column: (i + word_len) as u32,
-                            token_type: "keyword".to_string(),
-                        });
-                        i += word_len;
-                    } else {
-                        i += 1;
-                    }
-                }
-            }
-        }
-        "fsh" => {
-            // Simple FSH tokenization
-            let keywords = [
-                "Profile",
-                "Parent",
-                "Id",
-                "Title",
-                "Description",
-                "Extension",
-                "ValueSet",
-                "CodeSystem",
-                "Instance",
-                "InstanceOf",
-                "Usage",
-                "Alias",
-                "Invariant",
-                "Logical",
-                "Resource",
-                "RuleSet",
-                "Mapping",
-                "Source",
-                "Target",
-                "from",
-                "contains",
-                "and",
-                "or",
-                "only",
-                "MS",
-            ];
-
-            for (line_num, line) in content.lines().enumerate() {
-                let line_num = line_num as u32;
-
-                // Check for keywords at start of line
-                for keyword in &keywords {
-                    if line.starts_with(keyword) {
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: 0,
-                            end_column: keyword.len() as u32,
-                            token_type: "keyword".to_string(),
-                        });
-                        break;
-                    }
-                }
+/// Collect differential element paths relative to the resource root, without slice names.
+fn differential_schema_paths(doc: &ProfileDocument) -> Vec<Vec<String>> {
+    doc.resource
+        .differential
+        .iter()
+        .map(|diff| {
+            diff.path
+                .split('.')
+                .skip(1)
+                .map(|segment| {
+                    segment
+                        .split_once(':')
+                        .map_or(segment, |(name, _)| name)
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .filter(|segments| !segments.is_empty())
+        .collect()
+}
 
-                // Find strings
-                let mut i = 0;
-                let chars: Vec<char> = line.chars().collect();
-                while i < chars.len() {
-                    if chars[i] == '"' {
-                        let start = i;
-                        i += 1;
-                        while i < chars.len() && chars[i] != '"' {
-                            if chars[i] == '\\' {
-                                i += 1;
-                            }
-                            i += 1;
-                        }
-                        if i < chars.len() {
-                            i += 1;
-                        }
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: start as u32,
-                            end_column: i as u32,
-                            token_type: "string".to_string(),
-                        });
-                    } else if chars[i] == '/' && i + 1 < chars.len() && chars[i + 1] == '/' {
-                        // Comment
-                        tokens.push(HighlightToken {
-                            line: line_num,
-                            start_column: i as u32,
-                            end_column: chars.len() as u32,
-                            token_type: "comment".to_string(),
-                        });
-                        break;
-                    } else {
-                        i += 1;
-                    }
-                }
-            }
+/// Keep only schema elements on the given paths (and their ancestors).
+///
+/// Returns `false` if the schema has no `elements` map to prune.
+fn prune_schema_elements(schema: &mut serde_json::Value, paths: &[Vec<String>]) -> bool {
+    let Some(elements) = schema
+        .get_mut("elements")
+        .and_then(serde_json::Value::as_object_mut)
+    else {
+        return paths.is_empty();
+    };
+
+    elements.retain(|name, _| paths.iter().any(|p| p.first() == Some(name)));
+    for (name, element) in elements.iter_mut() {
+        // A differential entry on the element itself keeps its whole subtree
+        if paths.iter().any(|p| p.len() == 1 && &p[0] == name) {
+            continue;
         }
-        _ => {}
+        let nested: Vec<Vec<String>> = paths
+            .iter()
+            .filter(|p| p.len() > 1 && &p[0] == name)
+            .map(|p| p[1..].to_vec())
+            .collect();
+        prune_schema_elements(element, &nested);
     }
 
-    SyntaxHighlighting {
-        language: language.to_string(),
-        tokens,
-    }
+    true
 }
 
 /// Generate ImplementationGuide scaffold JSON.
@@ -1527,6 +2178,85 @@ FSHOnly: false
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_packaged_fsh_path_by_kind() {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+        let document = |name: &str, base: &str| {
+            let url = format!("http://example.org/fhir/StructureDefinition/{}", name);
+            let metadata = DocumentMetadata::new(name, &url, name);
+            let resource =
+                ProfiledResource::new(&url, FhirVersion::R4, BaseDefinition::resource(base));
+            ProfileDocument::new(metadata, resource)
+        };
+        let profile = document("MyPatient", "Patient");
+        let extension = document("BirthPlace", "Extension");
+        let index = ProjectIndex::new();
+
+        let profile_path =
+            packaged_fsh_path(export_resource_kind(&profile, &index), &profile.metadata.name);
+        let extension_path =
+            packaged_fsh_path(export_resource_kind(&extension, &index), &extension.metadata.name);
+        assert_eq!(profile_path, "input/fsh/profiles/MyPatient.fsh");
+        assert_eq!(extension_path, "input/fsh/extensions/BirthPlace.fsh");
+    }
+
+    #[test]
+    fn test_restrict_to_subtree() {
+        let element = |id: &str, path: &str| serde_json::json!({ "id": id, "path": path, "short": id });
+        let mut sd = serde_json::json!({
+            "resourceType": "StructureDefinition",
+            "differential": { "element": [
+                element("Observation", "Observation"),
+                element("Observation.status", "Observation.status"),
+                element("Observation.component", "Observation.component"),
+                element("Observation.component:systolic", "Observation.component"),
+                element("Observation.component:systolic.code", "Observation.component.code"),
+                element("Observation.componentNote", "Observation.componentNote"),
+            ]}
+        });
+
+        restrict_to_subtree(&mut sd, "Observation.component");
+        let ids: Vec<&str> = sd["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "Observation",
+                "Observation.component",
+                "Observation.component:systolic",
+                "Observation.component:systolic.code",
+            ]
+        );
+        // The root ancestor is a bare stub
+        assert_eq!(
+            sd["differential"]["element"][0],
+            serde_json::json!({ "id": "Observation", "path": "Observation" })
+        );
+        assert!(sd.get("snapshot").is_none());
+    }
+
+    #[test]
+    fn test_ancestor_stubs_of_slice_child() {
+        let stubs = ancestor_stubs("Observation.component:systolic.code");
+        assert_eq!(
+            stubs,
+            vec![
+                serde_json::json!({ "id": "Observation", "path": "Observation" }),
+                serde_json::json!({ "id": "Observation.component", "path": "Observation.component" }),
+                serde_json::json!({
+                    "id": "Observation.component:systolic",
+                    "path": "Observation.component",
+                    "sliceName": "systolic"
+                }),
+            ]
+        );
+    }
+
     #[test]
     fn test_calculate_etag() {
         let content = r#"{"resourceType": "StructureDefinition"}"#;
@@ -1537,6 +2267,406 @@ mod tests {
         assert_eq!(etag1.len(), 16);
     }
 
+    #[test]
+    fn test_mark_inherited() {
+        use crate::ir::{ElementNode, ElementSource, SliceNode, SlicingDefinition};
+
+        let mut root = ElementNode::new("Patient".to_string());
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        identifier.source = ElementSource::Modified;
+        identifier.slicing = Some(SlicingDefinition::new(Vec::new()));
+        identifier.add_slice("mrn".to_string(), SliceNode::new("mrn"));
+        root.add_child(identifier);
+
+        mark_inherited(&mut root);
+
+        assert!(root.descendants().all(|e| e.source == ElementSource::Inherited));
+        assert_eq!(
+            root.children[0].slices["mrn"].source,
+            ElementSource::Inherited
+        );
+    }
+
+    #[test]
+    fn test_element_tree_csv() {
+        use crate::ir::{Binding, Cardinality, ElementNode};
+
+        let mut root = ElementNode::new("Patient".to_string());
+        let mut gender = ElementNode::new("Patient.gender".to_string());
+        gender.constraints.cardinality = Some(Cardinality::required());
+        gender.constraints.flags.must_support = true;
+        gender.constraints.short = Some("male, female, other".to_string());
+        gender.constraints.binding = Some(Binding::required(
+            "http://hl7.org/fhir/ValueSet/administrative-gender",
+        ));
+        root.add_child(gender);
+
+        let csv = element_tree_csv(&root);
+        let rows: Vec<&str> = csv.lines().collect();
+        assert_eq!(rows[0], CSV_HEADER);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[2],
+            "Patient.gender,1..1,,true,required,http://hl7.org/fhir/ValueSet/administrative-gender,\"male, female, other\""
+        );
+    }
+
+    #[test]
+    fn test_prune_schema_elements() {
+        let mut schema = serde_json::json!({
+            "elements": {
+                "identifier": { "type": "Identifier" },
+                "name": {
+                    "type": "HumanName",
+                    "elements": {
+                        "family": { "type": "string" },
+                        "given": { "type": "string" }
+                    }
+                },
+                "gender": { "type": "code" }
+            }
+        });
+        let paths = vec![
+            vec!["identifier".to_string()],
+            vec!["name".to_string(), "family".to_string()],
+        ];
+
+        assert!(prune_schema_elements(&mut schema, &paths));
+        let elements = schema["elements"].as_object().unwrap();
+        assert_eq!(elements.len(), 2);
+        assert!(elements.contains_key("identifier"));
+        let name_elements = elements["name"]["elements"].as_object().unwrap();
+        assert_eq!(name_elements.len(), 1);
+        assert!(name_elements.contains_key("family"));
+    }
+
+    #[tokio::test]
+    async fn test_slow_export_step_times_out() {
+        let slow_decompile = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok::<_, DecompilerError>("Profile: Slow".to_string())
+        };
+        let result =
+            within_export_timeout(Duration::from_millis(20), DecompilerError::TimedOut, slow_decompile)
+                .await;
+        let error = result.unwrap_err();
+        assert!(matches!(error, DecompilerError::TimedOut(limit) if limit == Duration::from_millis(20)));
+
+        let response = export_timeout_response(error);
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+
+        // Steps that finish in time are passed through
+        let quick = async { Ok::<_, DecompilerError>("Profile: Quick".to_string()) };
+        let result = within_export_timeout(Duration::from_secs(5), DecompilerError::TimedOut, quick);
+        assert_eq!(result.await.unwrap(), "Profile: Quick");
+    }
+
+    #[tokio::test]
+    async fn test_blocking_export_step_times_out() {
+        // A conversion stuck in synchronous work never yields to the runtime
+        let started = Instant::now();
+        let blocking_translate = async {
+            std::thread::sleep(Duration::from_secs(2));
+            Ok::<_, SchemaGenerationError>("{}".to_string())
+        };
+        let result = within_export_timeout(
+            Duration::from_millis(20),
+            SchemaGenerationError::TimedOut,
+            blocking_translate,
+        )
+        .await;
+        assert!(matches!(result, Err(SchemaGenerationError::TimedOut(_))));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn test_generate_fhirschema_respects_limit() {
+        let workspace = tempfile::tempdir().unwrap();
+        let doc = dependency_test_profile(
+            "SlowProfile",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+
+        // No step can finish before a zero limit passes
+        let result = generate_fhirschema(workspace.path(), &doc, Duration::ZERO).await;
+        assert!(matches!(result, Err(SchemaGenerationError::TimedOut(limit)) if limit.is_zero()));
+
+        let result = generate_fsh_via_decompiler(workspace.path(), &doc, Duration::ZERO).await;
+        assert!(matches!(result, Err(DecompilerError::TimedOut(limit)) if limit.is_zero()));
+    }
+
+    #[tokio::test]
+    async fn test_export_stops_on_hydration_error() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::tempdir().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let doc = dependency_test_profile(
+            "TestPatient",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+        state.storage().save_profile("demo", &doc).await.unwrap();
+        state.make_canonical_manager_unavailable();
+
+        let router = Router::new()
+            .nest("/api/projects/{projectId}/profiles", export_routes())
+            .with_state(state);
+        let request = axum::http::Request::builder()
+            .uri("/api/projects/demo/profiles/testpatient/export/sd")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_decompiled_fsh_restores_leading_comment() {
+        let workspace = tempfile::tempdir().unwrap();
+        let mut doc = dependency_test_profile(
+            "CommentedPatient",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+        let comment = "// Demo patient profiles\n// Maintained alongside the demo IG";
+        doc.resource.fsh_comment = Some(comment.to_string());
+
+        let fsh = generate_fsh_via_decompiler(workspace.path(), &doc, Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert!(fsh.starts_with(comment), "comment missing from:\n{fsh}");
+        assert!(fsh.contains("Profile: CommentedPatient"));
+        assert_eq!(fsh.matches(comment).count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_fhirschema_modes() {
+        use crate::ir::{
+            BaseDefinition, Cardinality, DifferentialElement, DocumentMetadata, ElementNode,
+            ElementSource, FhirVersion, ProfiledResource,
+        };
+
+        let metadata = DocumentMetadata::new(
+            "schema-patient",
+            "http://example.org/fhir/StructureDefinition/SchemaPatient",
+            "SchemaPatient",
+        );
+        let mut resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/SchemaPatient",
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        for path in ["Patient.identifier", "Patient.gender"] {
+            let mut element = ElementNode::new(path.to_string());
+            element.source = ElementSource::Inherited;
+            element.constraints.cardinality = Some(Cardinality::optional());
+            resource.root.add_child(element);
+        }
+        resource.root.children[0].source = ElementSource::Modified;
+        resource.root.children[0].constraints.cardinality = Some(Cardinality::required());
+        let mut diff = DifferentialElement::new("Patient.identifier".to_string());
+        diff.constraints.cardinality = Some(Cardinality::required());
+        resource.differential.push(diff);
+        let doc = ProfileDocument::new(metadata, resource);
+
+        const LIMIT: Duration = Duration::from_secs(60);
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let snapshot =
+            generate_fhirschema_with_mode(temp_dir.path(), &doc, SchemaExportMode::Snapshot, LIMIT)
+                .await
+                .expect("Snapshot schema failed");
+        let differential =
+            generate_fhirschema_with_mode(temp_dir.path(), &doc, SchemaExportMode::Differential, LIMIT)
+                .await
+                .expect("Differential schema failed");
+
+        let snapshot: serde_json::Value = serde_json::from_str(&snapshot).unwrap();
+        let differential: serde_json::Value = serde_json::from_str(&differential).unwrap();
+        let element_count = |schema: &serde_json::Value| {
+            schema
+                .get("elements")
+                .and_then(serde_json::Value::as_object)
+                .map_or(0, serde_json::Map::len)
+        };
+        assert!(element_count(&differential) <= element_count(&snapshot));
+        assert!(
+            differential
+                .get("elements")
+                .and_then(|e| e.get("gender"))
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preview_not_modified_with_etag() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let doc = dependency_test_profile("PreviewPatient", "http://hl7.org/fhir/StructureDefinition/Patient");
+        let profile_id = doc.metadata.id.clone();
+        ProfileStorage::new(state.project_path("demo").unwrap())
+            .save_profile(&doc)
+            .await
+            .unwrap();
+
+        let path = || {
+            Path(ProfilePath {
+                project_id: "demo".to_string(),
+                profile_id: profile_id.clone(),
+            })
+        };
+        let query = || {
+            Query(PreviewQuery {
+                format: PreviewFormat::Sd,
+                highlight: false,
+            })
+        };
+
+        let first = preview(State(state.clone()), path(), query(), HeaderMap::new())
+            .await
+            .into_response();
+        assert_eq!(first.status(), StatusCode::OK);
+        let etag = first.headers().get(header::ETAG).unwrap().clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let second = preview(State(state.clone()), path(), query(), headers.clone())
+            .await
+            .into_response();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+
+        // A different format is a different representation
+        let schema_query = Query(PreviewQuery {
+            format: PreviewFormat::FhirSchema,
+            highlight: false,
+        });
+        let other = preview(State(state), path(), schema_query, headers)
+            .await
+            .into_response();
+        assert_ne!(other.status(), StatusCode::NOT_MODIFIED);
+    }
+
+    #[tokio::test]
+    async fn test_persisted_sd_export_is_saved_with_ir() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let doc = dependency_test_profile(
+            "PersistedPatient",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        storage.save_profile(&doc).await.unwrap();
+
+        let query: SdExportQuery =
+            serde_json::from_value(serde_json::json!({ "persist": true, "force": true })).unwrap();
+        let path = Path(ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: doc.metadata.id.clone(),
+        });
+        let response = export_sd(State(state.clone()), path, Query(query), HeaderMap::new())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let sd_path = storage.sd_path(&doc.metadata.name).unwrap();
+        let persisted: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(sd_path).unwrap()).unwrap();
+        assert_eq!(persisted["url"], doc.metadata.url);
+        assert!(storage.load_profile(&doc.metadata.id).await.is_ok());
+        assert!(!workspace
+            .path()
+            .join("demo/SD/StructureDefinition/PersistedPatient.json.tmp")
+            .exists());
+    }
+
+    fn dependency_test_profile(name: &str, base_url: &str) -> ProfileDocument {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+        let url = format!("http://example.org/fhir/StructureDefinition/{}", name);
+        let metadata = DocumentMetadata::new(name.to_lowercase(), &url, name);
+        let resource =
+            ProfiledResource::new(&url, FhirVersion::R4, BaseDefinition::new(base_url));
+        ProfileDocument::new(metadata, resource)
+    }
+
+    #[test]
+    fn test_order_profiles_for_export() {
+        let profile_b = dependency_test_profile(
+            "ProfileB",
+            "http://example.org/fhir/StructureDefinition/ProfileA",
+        );
+        let profile_a = dependency_test_profile(
+            "ProfileA",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+
+        let (ordered, warning) = order_profiles_for_export(vec![profile_b, profile_a]);
+        assert!(warning.is_none());
+        let names: Vec<&str> = ordered.iter().map(|d| d.metadata.name.as_str()).collect();
+        assert_eq!(names, vec!["ProfileA", "ProfileB"]);
+
+        // Dependency order wins over name order
+        let alpha = dependency_test_profile(
+            "Alpha",
+            "http://example.org/fhir/StructureDefinition/Zulu",
+        );
+        let zulu = dependency_test_profile("Zulu", "http://hl7.org/fhir/StructureDefinition/Patient");
+
+        let (ordered, _) = order_profiles_for_export(vec![alpha, zulu]);
+        let names: Vec<&str> = ordered.iter().map(|d| d.metadata.name.as_str()).collect();
+        assert_eq!(names, vec!["Zulu", "Alpha"]);
+    }
+
+    #[test]
+    fn test_order_profiles_for_export_cycle() {
+        let a = dependency_test_profile(
+            "ProfileA",
+            "http://example.org/fhir/StructureDefinition/ProfileB",
+        );
+        let b = dependency_test_profile(
+            "ProfileB",
+            "http://example.org/fhir/StructureDefinition/ProfileA",
+        );
+
+        let (ordered, warning) = order_profiles_for_export(vec![b, a]);
+        let names: Vec<&str> = ordered.iter().map(|d| d.metadata.name.as_str()).collect();
+        assert_eq!(names, vec!["ProfileA", "ProfileB"]);
+        assert_eq!(warning.unwrap().diagnostics[0].code, "DEPENDENCY_CYCLE");
+    }
+
+    #[test]
+    fn test_instances_of_abstract_profiles() {
+        use crate::validation::rules::instance::codes;
+
+        let mut base = dependency_test_profile(
+            "BasePatient",
+            "http://hl7.org/fhir/StructureDefinition/Patient",
+        );
+        base.resource.is_abstract = true;
+        let concrete = dependency_test_profile(
+            "ConcretePatient",
+            "http://example.org/fhir/StructureDefinition/BasePatient",
+        );
+        let instance = |id: &str, profile: &str| ProjectInstance {
+            id: id.to_string(),
+            resource: serde_json::json!({
+                "resourceType": "Patient",
+                "meta": { "profile": [profile] }
+            }),
+        };
+        let instances = vec![
+            instance("abstract-example", "http://example.org/fhir/StructureDefinition/BasePatient|1.0.0"),
+            instance("concrete-example", "http://example.org/fhir/StructureDefinition/ConcretePatient"),
+        ];
+
+        let diagnostics = abstract_profile_instance_diagnostics(&[base, concrete], &instances);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].resource_id, "abstract-example");
+        assert_eq!(
+            diagnostics[0].diagnostics[0].code,
+            codes::INSTANCE_OF_ABSTRACT_PROFILE
+        );
+    }
+
     #[test]
     fn test_validation_result() {
         let result = ValidationResult::valid();
@@ -2,6 +2,7 @@
 //!
 //! Defines request and response types for export-related endpoints.
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::dto::{Diagnostic, DiagnosticSeverity};
@@ -23,6 +24,10 @@ pub struct SdExportQuery {
     /// Force export even with validation warnings (default: false)
     #[serde(default)]
     pub force: bool,
+    /// Export only this element's subtree (e.g. "Observation.component")
+    /// as a partial StructureDefinition
+    #[serde(default)]
+    pub element: Option<String>,
 }
 
 fn default_sd_format() -> SdExportFormat {
@@ -52,6 +57,31 @@ pub struct FshExportQuery {
     pub force: bool,
 }
 
+/// Query parameters for FHIR Schema export endpoint.
+#[derive(Debug, Deserialize)]
+pub struct SchemaExportQuery {
+    /// Schema mode: "snapshot" (default) or "differential"
+    #[serde(default)]
+    pub mode: SchemaExportMode,
+    /// Persist exported file to SD/ directory (default: false)
+    #[serde(default)]
+    pub persist: bool,
+    /// Force export even with validation warnings (default: false)
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// FHIR Schema export mode options.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SchemaExportMode {
+    /// Translate the full snapshot (default)
+    #[default]
+    Snapshot,
+    /// Translate only the profile's own constraints
+    Differential,
+}
+
 /// Query parameters for bulk export endpoint.
 #[derive(Debug, Deserialize)]
 pub struct BulkExportQuery {
@@ -118,7 +148,7 @@ fn default_highlight() -> bool {
 }
 
 /// Preview format options.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum PreviewFormat {
     /// Preview as StructureDefinition JSON
@@ -132,7 +162,7 @@ pub enum PreviewFormat {
 // === Response Types ===
 
 /// Response for single resource SD export.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SdExportResponse {
     /// The exported StructureDefinition JSON
     pub data: serde_json::Value,
@@ -144,7 +174,7 @@ pub struct SdExportResponse {
 }
 
 /// Response for single resource FSH export.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct FshExportResponse {
     /// The exported FSH content
     pub data: String,
@@ -156,7 +186,7 @@ pub struct FshExportResponse {
 }
 
 /// Metadata about the export operation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ExportMetadata {
     /// Resource ID
     #[serde(rename = "resourceId")]
@@ -180,8 +210,24 @@ pub struct ExportMetadata {
     pub persisted_path: Option<String>,
 }
 
-/// Response for preview endpoint.
+/// Response for the base element tree endpoint.
 #[derive(Debug, Serialize)]
+pub struct BaseTreeResponse {
+    /// Canonical URL of the base definition
+    #[serde(rename = "baseUrl")]
+    pub base_url: String,
+    /// FHIR version used to resolve the base
+    #[serde(rename = "fhirVersion")]
+    pub fhir_version: String,
+    /// Number of elements in the tree (including slices)
+    #[serde(rename = "elementCount")]
+    pub element_count: usize,
+    /// Root of the parsed base element tree (all elements inherited)
+    pub root: crate::ir::ElementNode,
+}
+
+/// Response for preview endpoint.
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct PreviewResponse {
     /// The formatted content
     pub content: String,
@@ -196,7 +242,7 @@ pub struct PreviewResponse {
 }
 
 /// Syntax highlighting metadata for preview.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct SyntaxHighlighting {
     /// Language identifier
     pub language: String,
@@ -205,14 +251,14 @@ pub struct SyntaxHighlighting {
 }
 
 /// A syntax highlighting token.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct HighlightToken {
     /// Start line (0-based)
     pub line: u32,
-    /// Start column (0-based)
+    /// Start column (0-based, in characters)
     #[serde(rename = "startColumn")]
     pub start_column: u32,
-    /// End column (0-based)
+    /// End column (0-based, exclusive, in characters)
     #[serde(rename = "endColumn")]
     pub end_column: u32,
     /// Token type (keyword, string, number, etc.)
@@ -221,7 +267,7 @@ pub struct HighlightToken {
 }
 
 /// Response for bulk export endpoint (flat structure).
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct BulkExportResponse {
     /// Project ID
     #[serde(rename = "projectId")]
@@ -236,7 +282,7 @@ pub struct BulkExportResponse {
 }
 
 /// A single exported file in bulk export.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ExportedFile {
     /// Resource ID
     #[serde(rename = "resourceId")]
@@ -255,7 +301,7 @@ pub struct ExportedFile {
 }
 
 /// Summary of bulk export operation.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ExportSummary {
     /// Total resources processed
     #[serde(rename = "totalResources")]
@@ -274,7 +320,7 @@ pub struct ExportSummary {
 }
 
 /// Diagnostic tied to a specific resource.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ResourceDiagnostic {
     /// Resource ID
     #[serde(rename = "resourceId")]
@@ -288,7 +334,7 @@ pub struct ResourceDiagnostic {
 // === Validation Result ===
 
 /// Pre-export validation result.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ValidationResult {
     /// Whether the profile is valid for export
     pub valid: bool,
@@ -356,6 +402,7 @@ mod tests {
             code: "TEST".to_string(),
             message: "Test warning".to_string(),
             path: None,
+            ..Default::default()
         }];
         let result = ValidationResult::from_diagnostics(diagnostics);
 
@@ -371,6 +418,7 @@ mod tests {
             code: "TEST".to_string(),
             message: "Test error".to_string(),
             path: None,
+            ..Default::default()
         }];
         let result = ValidationResult::from_diagnostics(diagnostics);
 
@@ -0,0 +1,267 @@
+//! Syntax highlighting for export previews.
+//!
+//! A small line-based tokenizer for the JSON and FSH previews. Token columns
+//! are character (not byte) offsets within a line, so multi-byte UTF-8 text
+//! never splits a token mid-character.
+
+use super::export_dto::{HighlightToken, SyntaxHighlighting};
+
+/// FSH keywords highlighted at the start of a line.
+const FSH_KEYWORDS: &[&str] = &[
+    "Profile",
+    "Parent",
+    "Id",
+    "Title",
+    "Description",
+    "Extension",
+    "ValueSet",
+    "CodeSystem",
+    "Instance",
+    "InstanceOf",
+    "Usage",
+    "Alias",
+    "Invariant",
+    "Logical",
+    "Resource",
+    "RuleSet",
+    "Mapping",
+    "Source",
+    "Target",
+    "from",
+    "contains",
+    "and",
+    "or",
+    "only",
+    "MS",
+];
+
+/// JSON literals highlighted as keywords.
+const JSON_LITERALS: &[&str] = &["true", "false", "null"];
+
+/// Generate syntax highlighting tokens for `content`.
+///
+/// Languages other than `json` and `fsh` produce no tokens.
+pub fn generate_highlighting(content: &str, language: &str) -> SyntaxHighlighting {
+    let tokenize_line: fn(&[char], u32, &mut Vec<HighlightToken>) = match language {
+        "json" => tokenize_json_line,
+        "fsh" => tokenize_fsh_line,
+        _ => no_tokens,
+    };
+
+    let mut tokens = Vec::new();
+    for (line_num, line) in content.lines().enumerate() {
+        let chars: Vec<char> = line.chars().collect();
+        tokenize_line(&chars, line_num as u32, &mut tokens);
+    }
+
+    SyntaxHighlighting {
+        language: language.to_string(),
+        tokens,
+    }
+}
+
+fn no_tokens(_: &[char], _: u32, _: &mut Vec<HighlightToken>) {}
+
+/// Tokenize one JSON line: keys, strings, numbers and literals.
+fn tokenize_json_line(chars: &[char], line: u32, tokens: &mut Vec<HighlightToken>) {
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '"' {
+            let start = i;
+            i = string_end(chars, i);
+            // A string followed by a colon is an object key
+            let next = chars[i..].iter().find(|c| !c.is_whitespace());
+            let token_type = if next == Some(&':') { "key" } else { "string" };
+            tokens.push(token(line, start, i, token_type));
+        } else if chars[i].is_ascii_digit()
+            || (chars[i] == '-' && chars.get(i + 1).is_some_and(char::is_ascii_digit))
+        {
+            let start = i;
+            while i < chars.len() && matches!(chars[i], '0'..='9' | '.' | '-' | '+' | 'e' | 'E') {
+                i += 1;
+            }
+            tokens.push(token(line, start, i, "number"));
+        } else if let Some(word) = JSON_LITERALS
+            .iter()
+            .find(|word| starts_with_word(&chars[i..], word))
+        {
+            tokens.push(token(line, i, i + word.len(), "keyword"));
+            i += word.len();
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// Tokenize one FSH line: a leading keyword, strings and `//` comments.
+fn tokenize_fsh_line(chars: &[char], line: u32, tokens: &mut Vec<HighlightToken>) {
+    if let Some(keyword) = FSH_KEYWORDS
+        .iter()
+        .find(|keyword| starts_with_word(chars, keyword))
+    {
+        tokens.push(token(line, 0, keyword.len(), "keyword"));
+    }
+
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '"' {
+            let start = i;
+            i = string_end(chars, i);
+            tokens.push(token(line, start, i, "string"));
+        } else if chars[i] == '/' && chars.get(i + 1) == Some(&'/') {
+            // Comment runs to the end of the line
+            tokens.push(token(line, i, chars.len(), "comment"));
+            break;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+/// End (exclusive) of the string literal opening at `start`.
+///
+/// Backslash escapes are skipped; an unterminated string ends at the end of
+/// the line.
+fn string_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 2,
+            '"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    chars.len()
+}
+
+/// Whether `chars` starts with `word` as a whole word.
+fn starts_with_word(chars: &[char], word: &str) -> bool {
+    let len = word.chars().count();
+    chars.len() >= len
+        && chars.iter().zip(word.chars()).all(|(a, b)| *a == b)
+        && !chars
+            .get(len)
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+}
+
+fn token(line: u32, start: usize, end: usize, token_type: &str) -> HighlightToken {
+    HighlightToken {
+        line,
+        start_column: start as u32,
+        end_column: end as u32,
+        token_type: token_type.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (text, token type) pairs for every token on the given line.
+    fn spans(content: &str, language: &str, line: u32) -> Vec<(String, String)> {
+        let chars: Vec<char> = content.lines().nth(line as usize).unwrap().chars().collect();
+        generate_highlighting(content, language)
+            .tokens
+            .iter()
+            .filter(|t| t.line == line)
+            .map(|t| {
+                let text: String = chars[t.start_column as usize..t.end_column as usize]
+                    .iter()
+                    .collect();
+                (text, t.token_type.clone())
+            })
+            .collect()
+    }
+
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected
+            .iter()
+            .map(|(text, kind)| (text.to_string(), kind.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_json_keys_strings_numbers() {
+        let content = r#"  "min": 0, "max": "*", "ratio": -1.5e3, "ok": false"#;
+        assert_eq!(
+            spans(content, "json", 0),
+            pairs(&[
+                ("\"min\"", "key"),
+                ("0", "number"),
+                ("\"max\"", "key"),
+                ("\"*\"", "string"),
+                ("\"ratio\"", "key"),
+                ("-1.5e3", "number"),
+                ("\"ok\"", "key"),
+                ("false", "keyword"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_json_escaped_quotes() {
+        let content = r#""short": "say \"hi\": now""#;
+        assert_eq!(
+            spans(content, "json", 0),
+            pairs(&[("\"short\"", "key"), (r#""say \"hi\": now""#, "string")])
+        );
+    }
+
+    #[test]
+    fn test_json_non_ascii_content() {
+        let content = "{\n  \"description\": \"Patient àé 患者\", \"active\": true\n}";
+        assert_eq!(
+            spans(content, "json", 1),
+            pairs(&[
+                ("\"description\"", "key"),
+                ("\"Patient àé 患者\"", "string"),
+                ("\"active\"", "key"),
+                ("true", "keyword"),
+            ])
+        );
+
+        // An unterminated escape at end of line stays within the line
+        let highlighting = generate_highlighting("\"é\\", "json");
+        assert_eq!(highlighting.tokens[0].end_column, 3);
+    }
+
+    #[test]
+    fn test_json_literal_needs_word_boundary() {
+        assert!(spans("nullable", "json", 0).is_empty());
+    }
+
+    #[test]
+    fn test_fsh_keywords_strings_comments() {
+        let content = "Profile: MyPatient\n* name 1..1 MS // must have a name\nIdentifier: x";
+        assert_eq!(spans(content, "fsh", 0), pairs(&[("Profile", "keyword")]));
+        assert_eq!(
+            spans(content, "fsh", 1),
+            pairs(&[("// must have a name", "comment")])
+        );
+        // "Id" only matches as a whole word
+        assert!(spans(content, "fsh", 2).is_empty());
+    }
+
+    #[test]
+    fn test_fsh_comment_markers_inside_strings() {
+        let content = r#"* ^url = "http://example.org/x" // canonical"#;
+        assert_eq!(
+            spans(content, "fsh", 0),
+            pairs(&[
+                ("\"http://example.org/x\"", "string"),
+                ("// canonical", "comment"),
+            ])
+        );
+
+        let content = r#"Title: "A \"quoted\" title""#;
+        assert_eq!(
+            spans(content, "fsh", 0),
+            pairs(&[("Title", "keyword"), (r#""A \"quoted\" title""#, "string")])
+        );
+    }
+
+    #[test]
+    fn test_unknown_language_has_no_tokens() {
+        assert!(generate_highlighting("{\"a\": 1}", "xml").tokens.is_empty());
+    }
+}
@@ -6,11 +6,12 @@
 //!
 //! - `POST /api/projects/:projectId/profiles/:profileId/undo` - Undo last operation
 //! - `POST /api/projects/:projectId/profiles/:profileId/redo` - Redo next operation
-//! - `GET  /api/projects/:projectId/profiles/:profileId/history` - Get history list
+//! - `GET  /api/projects/:projectId/profiles/:profileId/history` - Get history list (`?limit=`)
 //! - `POST /api/projects/:projectId/profiles/:profileId/history/goto` - Jump to index
+//! - `POST /api/projects/:projectId/profiles/:profileId/history/squash` - Drop history
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
     routing::{get, post},
     Json, Router,
 };
@@ -21,7 +22,7 @@ use crate::state::AppState;
 
 use super::profile_merge::hydrate_profile_document;
 use super::profiles::{ErrorResponse, ProfilePath};
-use super::storage::{ProfileStorage, StorageError};
+use super::storage::StorageError;
 
 /// Create history routes.
 pub fn history_routes() -> Router<AppState> {
@@ -30,6 +31,7 @@ pub fn history_routes() -> Router<AppState> {
         .route("/{profileId}/redo", post(redo))
         .route("/{profileId}/history", get(get_history))
         .route("/{profileId}/history/goto", post(goto_history))
+        .route("/{profileId}/history/squash", post(squash_history))
 }
 
 // === Response Types ===
@@ -78,6 +80,13 @@ pub struct HistoryResponse {
     pub operations: Vec<OperationSummary>,
 }
 
+/// Query parameters for the history list.
+#[derive(Debug, Default, Deserialize)]
+pub struct HistoryQuery {
+    /// Maximum number of (most recent) operations to return.
+    pub limit: Option<usize>,
+}
+
 /// Request for goto operation.
 #[derive(Debug, Deserialize)]
 pub struct GotoRequest {
@@ -100,6 +109,23 @@ pub struct GotoResponse {
     pub profile: ProfileSummary,
 }
 
+/// Request for squash operation.
+#[derive(Debug, Deserialize)]
+pub struct SquashRequest {
+    /// Must be `true`; squashing cannot be undone.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+/// Response for squash operation.
+#[derive(Debug, Serialize)]
+pub struct SquashResponse {
+    /// Number of operations removed from the history.
+    pub squashed: usize,
+    /// Current history state.
+    pub history: HistoryState,
+}
+
 // === Handlers ===
 
 /// Undo the last operation.
@@ -109,12 +135,11 @@ async fn undo(
     State(state): State<AppState>,
     Path(path): Path<ProfilePath>,
 ) -> Result<Json<UndoRedoResponse>, (axum::http::StatusCode, Json<ErrorResponse>)> {
-    let project_dir = state.project_path(&path.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load profile
     let mut doc = storage
-        .load_profile(&path.profile_id)
+        .load_profile(&path.project_id, &path.profile_id)
         .await
         .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
     let mut doc = match hydrate_profile_document(&state, doc).await {
@@ -142,10 +167,17 @@ async fn undo(
         doc.mark_dirty();
 
         // Save the updated document
-        storage
-            .save_profile(&doc)
-            .await
-            .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
+        storage.save_profile(&path.project_id, &doc).await.map_err(
+            |e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() },
+        )?;
+        state.notify_operation_applied(
+            &path.project_id,
+            &path.profile_id,
+            format!("Undo {}", description.as_deref().unwrap_or("operation")),
+            None,
+            doc.history.state(),
+        );
+        state.notify_profile_saved(&path.project_id, &path.profile_id);
     }
 
     Ok(Json(UndoRedoResponse {
@@ -163,12 +195,11 @@ async fn redo(
     State(state): State<AppState>,
     Path(path): Path<ProfilePath>,
 ) -> Result<Json<UndoRedoResponse>, (axum::http::StatusCode, Json<ErrorResponse>)> {
-    let project_dir = state.project_path(&path.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load profile
     let mut doc = storage
-        .load_profile(&path.profile_id)
+        .load_profile(&path.project_id, &path.profile_id)
         .await
         .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
     let mut doc = match hydrate_profile_document(&state, doc).await {
@@ -195,10 +226,17 @@ async fn redo(
         doc.mark_dirty();
 
         // Save the updated document
-        storage
-            .save_profile(&doc)
-            .await
-            .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
+        storage.save_profile(&path.project_id, &doc).await.map_err(
+            |e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() },
+        )?;
+        state.notify_operation_applied(
+            &path.project_id,
+            &path.profile_id,
+            format!("Redo {}", description.as_deref().unwrap_or("operation")),
+            None,
+            doc.history.state(),
+        );
+        state.notify_profile_saved(&path.project_id, &path.profile_id);
     }
 
     Ok(Json(UndoRedoResponse {
@@ -215,23 +253,19 @@ async fn redo(
 async fn get_history(
     State(state): State<AppState>,
     Path(path): Path<ProfilePath>,
+    Query(query): Query<HistoryQuery>,
 ) -> Result<Json<HistoryResponse>, (axum::http::StatusCode, Json<ErrorResponse>)> {
-    let project_dir = state.project_path(&path.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
-    // Load profile
+    // Load profile (history is stored with the IR, no hydration needed)
     let doc = storage
-        .load_profile(&path.profile_id)
+        .load_profile(&path.project_id, &path.profile_id)
         .await
         .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
-    let doc = match hydrate_profile_document(&state, doc).await {
-        Ok(d) => d,
-        Err(e) => return Err((axum::http::StatusCode::INTERNAL_SERVER_ERROR, Json(e))),
-    };
 
     Ok(Json(HistoryResponse {
         state: doc.history.state(),
-        operations: doc.history.get_operations(),
+        operations: doc.history.recent_operations(query.limit),
     }))
 }
 
@@ -243,12 +277,11 @@ async fn goto_history(
     Path(path): Path<ProfilePath>,
     Json(request): Json<GotoRequest>,
 ) -> Result<Json<GotoResponse>, (axum::http::StatusCode, Json<ErrorResponse>)> {
-    let project_dir = state.project_path(&path.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load profile
     let mut doc = storage
-        .load_profile(&path.profile_id)
+        .load_profile(&path.project_id, &path.profile_id)
         .await
         .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
     let mut doc = match hydrate_profile_document(&state, doc).await {
@@ -275,10 +308,17 @@ async fn goto_history(
         doc.mark_dirty();
 
         // Save the updated document
-        storage
-            .save_profile(&doc)
-            .await
-            .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
+        storage.save_profile(&path.project_id, &doc).await.map_err(
+            |e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() },
+        )?;
+        state.notify_operation_applied(
+            &path.project_id,
+            &path.profile_id,
+            format!("Go to history index {}", target_index),
+            None,
+            doc.history.state(),
+        );
+        state.notify_profile_saved(&path.project_id, &path.profile_id);
     }
 
     Ok(Json(GotoResponse {
@@ -290,6 +330,49 @@ async fn goto_history(
     }))
 }
 
+/// Collapse the history so the IR file stops carrying old operations.
+///
+/// POST /api/projects/:projectId/profiles/:profileId/history/squash
+async fn squash_history(
+    State(state): State<AppState>,
+    Path(path): Path<ProfilePath>,
+    Json(request): Json<SquashRequest>,
+) -> Result<Json<SquashResponse>, (axum::http::StatusCode, Json<ErrorResponse>)> {
+    if !request.confirm {
+        return Err(ErrorResponse::bad_request(
+            "Squashing history cannot be undone; resend with \"confirm\": true",
+        ));
+    }
+
+    let storage = state.storage();
+
+    // History lives in the IR file, so the tree does not need hydrating
+    let mut doc = storage
+        .load_profile(&path.project_id, &path.profile_id)
+        .await
+        .map_err(|e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() })?;
+
+    let squashed = doc.history.squash();
+    if squashed > 0 {
+        storage.save_profile(&path.project_id, &doc).await.map_err(
+            |e: StorageError| -> (axum::http::StatusCode, Json<ErrorResponse>) { e.into() },
+        )?;
+        state.notify_operation_applied(
+            &path.project_id,
+            &path.profile_id,
+            format!("Squash {} operations", squashed),
+            None,
+            doc.history.state(),
+        );
+        state.notify_profile_saved(&path.project_id, &path.profile_id);
+    }
+
+    Ok(Json(SquashResponse {
+        squashed,
+        history: doc.history.state(),
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -315,4 +398,42 @@ mod tests {
         assert_eq!(summary.name, "TestPatient");
         assert!(!summary.is_dirty);
     }
+
+    #[tokio::test]
+    async fn test_undo_publishes_operation_and_saved_events() {
+        use crate::engine::EngineEvent;
+        use crate::ir::{
+            Change, DocumentMetadata, FhirVersion, NodeId, Operation, ProfiledResource,
+        };
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let url = "http://example.org/fhir/StructureDefinition/LabBatch";
+        let resource = ProfiledResource::logical(url, FhirVersion::R4, "LabBatch");
+        let mut doc =
+            ProfileDocument::new(DocumentMetadata::new("labbatch", url, "LabBatch"), resource);
+        doc.history.push(Operation::single(
+            "Set title",
+            Change::set(NodeId::new(), "title", None, serde_json::json!("Batch")),
+        ));
+        state.storage().save_profile("demo", &doc).await.unwrap();
+
+        let mut events = state.events().subscribe("demo/labbatch");
+        let path = ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: "labbatch".to_string(),
+        };
+        let Json(response) = undo(State(state.clone()), Path(path)).await.unwrap();
+        assert!(response.success);
+
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            EngineEvent::OperationApplied(_)
+        ));
+        assert!(matches!(
+            events.recv().await.unwrap(),
+            EngineEvent::DocumentSaved(_)
+        ));
+    }
 }
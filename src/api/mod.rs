@@ -7,56 +7,109 @@
 //!
 //! ## Profile Management
 //! - `GET    /api/projects/:projectId/profiles` - List profiles
+//!   (`?modifiedSince=` keeps profiles modified at or after an ISO 8601 time)
 //! - `POST   /api/projects/:projectId/profiles` - Create profile
+//!   (an `Idempotency-Key` header replays the first result for a short while)
 //! - `GET    /api/projects/:projectId/profiles/:profileId` - Get profile details
+//! - `PUT    /api/projects/:projectId/profiles/:profileId` - Replace with a full IR document
+//!   (honors `If-Match` against the profile ETag)
 //! - `DELETE /api/projects/:projectId/profiles/:profileId` - Delete profile
+//! - `GET    /api/projects/:projectId/profiles/:profileId/stats` - Differential statistics and element/slice counts
 //! - `PATCH  /api/projects/:projectId/profiles/:profileId/metadata` - Update metadata
 //! - `PATCH  /api/projects/:projectId/profiles/:profileId/elements/:path` - Update element
+//!   (`?recursive=true` applies mustSupport to the whole subtree)
+//!   (`slicing` edits rules, discriminators and description of existing slicing)
+//!   (`cardinality` is `{min, max}` or a FHIR string such as `"1..*"`)
+//!   (`mustHaveValue` is only accepted for R5+ profiles)
+//!   (`?mode=snapshot` edits the full snapshot and stores the minimal differential;
+//!   the default comes from the profile's `editingMode`, set via metadata)
+//! - `DELETE /api/projects/:projectId/profiles/:profileId/elements/:path` - Remove added element
+//! - `GET|POST /api/projects/:projectId/profiles/:profileId/elements/:path/annotations` - Review annotations
+//!   (`DELETE .../annotations/:annotationId` removes one; annotations never reach exports)
+//! - `GET    /api/projects/:projectId/profiles/:profileId/elements/:path/provenance` - Explain where each constraint comes from
 //! - `POST   /api/projects/:projectId/profiles/:profileId/import` - Import SD/FSH
+//!   (`?dryRun=true` previews the result without persisting)
+//! - `GET    /api/projects/:projectId/profiles/:profileId/fsh/validate?content=` - Lint FSH without importing
 //!
 //! ## Export
 //! - `GET    /api/projects/:projectId/profiles/:profileId/export/sd` - Export as SD JSON
+//!   (`?element=` exports only that element's subtree as a partial SD)
 //! - `GET    /api/projects/:projectId/profiles/:profileId/export/fsh` - Export as FSH
+//! - `GET    /api/projects/:projectId/profiles/:profileId/export/schema?mode=` - Export as FHIR Schema
+//! - `GET    /api/projects/:projectId/profiles/:profileId/export/csv` - Element summary as CSV
+//! - `GET    /api/projects/:projectId/profiles/:profileId/base/tree` - Parsed base element tree
 //! - `GET    /api/projects/:projectId/profiles/:profileId/preview` - Preview content
+//!   (ETag over format and content; `If-None-Match` returns 304)
 //! - `GET    /api/projects/:projectId/export` - Bulk export all profiles
 //!
 //! ## Validation
 //! - `POST   /api/projects/:projectId/profiles/:profileId/validate` - Full validation
 //! - `POST   /api/projects/:projectId/profiles/:profileId/validate/quick` - Quick structural validation
 //! - `POST   /api/projects/:projectId/profiles/:profileId/validate/element` - Validate specific element
+//! - `GET    /api/projects/:projectId/profiles/:profileId/validate/summary` - Error/warning/info counts only
+//! - `GET    /api/projects/:projectId/profiles/:profileId/validate/snapshot` - Compare the imported snapshot with a regenerated one
+//! - `POST   /api/projects/:projectId/validate` - Validate every profile in the project
+//!
+//! ## Live Events
+//! - `GET    /api/projects/:projectId/profiles/:profileId/ws` - WebSocket stream of profile events
 //!
 //! ## Package Management
 //! - `GET    /api/packages` - List installed packages
 //! - `GET    /api/packages/search?q=` - Search registry for packages
+//! - `GET    /api/packages/:packageId/contents?type=&offset=&limit=` - Resources of an installed package, with type facets
 //! - `POST   /api/packages/:packageId/install` - Install package (SSE stream)
+//!   (`?wait=true` blocks and returns the final result as JSON instead)
 //! - `POST   /api/packages/:packageId/uninstall` - Uninstall package
+//!   (409 while project resources depend on it, unless `?force=true`)
 //!
 //! ## Resource Search
 //! - `GET    /api/search/extensions?q=&package=` - Search extensions
 //! - `GET    /api/search/valuesets?q=` - Search value sets
 //! - `GET    /api/search/resources?q=&type=&package=` - Generic resource search
+//! - `GET    /api/base-resources?fhirVersion=` - Base resource types of the core package
+//!
+//! Search results are ordered by descending relevance; `minScore=` drops weaker matches.
+//!
+//! ## API Description
+//! - `GET    /api/version` - Server, maki-core/maki-decompiler and supported FHIR versions
+//! - `GET    /api/openapi.json` - OpenAPI 3.0 spec with JSON schemas of the request/response DTOs
+//!
+//! ## Debug (only with `--enable-debug-routes`)
+//! - `POST   /api/_debug/fsh-roundtrip` - FSH import/export round-trip with IR diff
 
+pub mod annotations;
+pub mod debug;
 pub mod dto;
+pub mod events;
 pub mod export;
 pub mod export_dto;
+pub mod highlight;
 pub mod history;
+pub mod openapi;
 pub mod packages;
 pub mod packages_dto;
+pub mod persistence;
 pub mod profile_merge;
 pub mod profiles;
 pub mod projects;
+pub mod provenance;
 pub mod registry_catalog;
 pub mod search_api;
 pub mod storage;
 pub mod validation;
 
+pub use annotations::annotation_routes;
+pub use debug::debug_routes;
 pub use dto::*;
+pub use events::event_routes;
 pub use export::{export_routes, project_export_routes};
 pub use history::history_routes;
+pub use openapi::openapi_routes;
 pub use packages::package_routes;
+pub use persistence::{FileStorage, MemoryStorage, Storage};
 pub use profiles::profile_routes;
 pub use projects::project_routes;
-pub use profile_merge::hydrate_profile_document;
-pub use search_api::search_routes;
+pub use profile_merge::{hydrate_or_respond, hydrate_profile_document};
+pub use search_api::{base_resource_routes, search_routes};
 pub use storage::ProfileStorage;
 pub use validation::validation_routes;
@@ -0,0 +1,227 @@
+//! OpenAPI specification route handler.
+//!
+//! Serves an OpenAPI 3.0 document describing the profile CRUD, element
+//! editing, export and validation endpoints. Request and response schemas are
+//! generated from the API DTOs with `schemars`, so the spec follows the Rust
+//! types instead of being maintained by hand.
+//!
+//! # Routes
+//!
+//! - `GET /api/openapi.json` - OpenAPI specification
+
+use axum::{response::IntoResponse, routing::get, Json, Router};
+use schemars::r#gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use crate::state::AppState;
+
+use super::dto::*;
+use super::export_dto::{BulkExportResponse, FshExportResponse, PreviewResponse, SdExportResponse};
+use super::profiles::ErrorResponse;
+use super::validation::{
+    ValidateElementRequest, ValidateRequest, ValidateResponse, ValidationSummaryResponse,
+};
+
+/// Create OpenAPI routes.
+pub fn openapi_routes() -> Router<AppState> {
+    Router::new().route("/openapi.json", get(get_openapi))
+}
+
+/// GET /api/openapi.json
+async fn get_openapi() -> impl IntoResponse {
+    Json(openapi_spec())
+}
+
+/// Build the OpenAPI document.
+pub fn openapi_spec() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let g = &mut generator;
+    let error = json_content::<ErrorResponse>(g, "Error");
+
+    let profiles = "/api/projects/{projectId}/profiles";
+    let profile = "/api/projects/{projectId}/profiles/{profileId}";
+    let element = "/api/projects/{projectId}/profiles/{profileId}/elements/{path}";
+
+    let endpoints = vec![
+        // Profile CRUD
+        Endpoint::new("get", profiles, "List profiles")
+            .returns(json_content::<ApiResponse<ProfileListResponse>>(g, "Profile list")),
+        Endpoint::new("post", profiles, "Create profile")
+            .accepts(request_body::<CreateProfileRequest>(g))
+            .returns_status(
+                "201",
+                json_content::<ApiResponse<ProfileDetailsResponse>>(g, "Created profile"),
+            ),
+        Endpoint::new("get", profile, "Get profile details")
+            .returns(json_content::<ApiResponse<ProfileDetailsResponse>>(g, "Profile details")),
+        Endpoint::new("get", &format!("{}/stats", profile), "Profile complexity summary")
+            .returns(json_content::<ApiResponse<ProfileStatsResponse>>(g, "Profile stats")),
+        Endpoint::new("delete", profile, "Delete profile")
+            .returns_status("204", json!({ "description": "Profile deleted" })),
+        Endpoint::new("patch", &format!("{}/metadata", profile), "Update metadata")
+            .accepts(request_body::<UpdateMetadataRequest>(g))
+            .returns(json_content::<ApiResponse<ProfileDetailsResponse>>(g, "Updated profile")),
+        Endpoint::new("post", &format!("{}/rename", profile), "Rename profile")
+            .accepts(request_body::<RenameProfileRequest>(g))
+            .returns(json_content::<ApiResponse<RenameProfileResponse>>(g, "Renamed profile")),
+        Endpoint::new("post", &format!("{}/import", profile), "Import SD/FSH")
+            .accepts(request_body::<ImportProfileRequest>(g))
+            .returns(json_content::<ApiResponse<ImportResponse>>(g, "Imported profile")),
+        // Element editing
+        Endpoint::new("patch", element, "Update element")
+            .accepts(request_body::<UpdateElementRequest>(g))
+            .returns(json_content::<ApiResponse<UpdateElementResponse>>(g, "Updated element")),
+        Endpoint::new("delete", element, "Remove added element")
+            .returns(json_content::<ApiResponse<RemoveElementResponse>>(g, "Removed element")),
+        // Export
+        Endpoint::new("get", &format!("{}/export/sd", profile), "Export as SD JSON")
+            .returns(json_content::<ApiResponse<SdExportResponse>>(g, "StructureDefinition")),
+        Endpoint::new("get", &format!("{}/export/fsh", profile), "Export as FSH")
+            .returns(json_content::<ApiResponse<FshExportResponse>>(g, "FSH source")),
+        Endpoint::new("get", &format!("{}/preview", profile), "Preview content")
+            .returns(json_content::<ApiResponse<PreviewResponse>>(g, "Preview")),
+        Endpoint::new("get", "/api/projects/{projectId}/export", "Bulk export all profiles")
+            .returns(json_content::<ApiResponse<BulkExportResponse>>(g, "Exported files")),
+        // Validation
+        Endpoint::new("post", &format!("{}/validate", profile), "Full validation")
+            .accepts(request_body::<ValidateRequest>(g))
+            .returns(json_content::<ValidateResponse>(g, "Validation result")),
+        Endpoint::new("post", &format!("{}/validate/quick", profile), "Quick structural validation")
+            .returns(json_content::<ValidateResponse>(g, "Validation result")),
+        Endpoint::new("post", &format!("{}/validate/element", profile), "Validate specific element")
+            .accepts(request_body::<ValidateElementRequest>(g))
+            .returns(json_content::<ValidateResponse>(g, "Validation result")),
+        Endpoint::new("get", &format!("{}/validate/summary", profile), "Validation counts")
+            .returns(json_content::<ValidationSummaryResponse>(g, "Validation summary")),
+    ];
+
+    let mut paths = json!({});
+    for endpoint in endpoints {
+        let mut operation = json!({
+            "summary": endpoint.summary,
+            "parameters": path_parameters(&endpoint.path),
+            "responses": {
+                endpoint.status: endpoint.response,
+                "default": error.clone(),
+            },
+        });
+        if let Some(request) = endpoint.request {
+            operation["requestBody"] = request;
+        }
+        paths[endpoint.path.as_str()][endpoint.method] = operation;
+    }
+
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "FHIR Profile Builder API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(),
+        },
+    })
+}
+
+/// One documented operation.
+struct Endpoint {
+    method: &'static str,
+    path: String,
+    summary: &'static str,
+    request: Option<Value>,
+    status: &'static str,
+    response: Value,
+}
+
+impl Endpoint {
+    fn new(method: &'static str, path: &str, summary: &'static str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            summary,
+            request: None,
+            status: "200",
+            response: Value::Null,
+        }
+    }
+
+    fn accepts(mut self, request: Value) -> Self {
+        self.request = Some(request);
+        self
+    }
+
+    fn returns(self, response: Value) -> Self {
+        self.returns_status("200", response)
+    }
+
+    fn returns_status(mut self, status: &'static str, response: Value) -> Self {
+        self.status = status;
+        self.response = response;
+        self
+    }
+}
+
+/// JSON request body referencing the schema of `T`.
+fn request_body<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    json!({
+        "required": true,
+        "content": { "application/json": { "schema": generator.subschema_for::<T>() } },
+    })
+}
+
+/// JSON response referencing the schema of `T`.
+fn json_content<T: JsonSchema>(generator: &mut SchemaGenerator, description: &str) -> Value {
+    json!({
+        "description": description,
+        "content": { "application/json": { "schema": generator.subschema_for::<T>() } },
+    })
+}
+
+/// Path parameters for every `{name}` segment of a route template.
+fn path_parameters(path: &str) -> Vec<Value> {
+    path.split('/')
+        .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'))
+        .map(|name| {
+            json!({
+                "name": name,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" },
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_spec_includes_create_profile_schema() {
+        let spec = serde_json::to_string(&openapi_spec()).unwrap();
+        let spec: Value = serde_json::from_str(&spec).unwrap();
+
+        assert_eq!(spec["openapi"], "3.0.3");
+        let schema = &spec["components"]["schemas"]["CreateProfileRequest"];
+        assert_eq!(schema["type"], "object");
+        assert!(schema["properties"]["resourceType"].is_object());
+        assert!(schema["properties"]["fhirVersion"].is_object());
+
+        let create = &spec["paths"]["/api/projects/{projectId}/profiles"]["post"];
+        assert_eq!(
+            create["requestBody"]["content"]["application/json"]["schema"]["$ref"],
+            "#/components/schemas/CreateProfileRequest"
+        );
+        assert!(create["responses"]["201"].is_object());
+        assert!(spec["components"]["schemas"]["CardinalityUpdate"].is_object());
+
+        // IR types keep their shape instead of collapsing to strings or `any`
+        let schemas = &spec["components"]["schemas"];
+        let statuses = schemas["ProfileStatus"]["enum"].as_array().unwrap();
+        assert!(statuses.contains(&json!("draft")));
+        assert!(schemas["ElementConstraints"]["properties"]["cardinality"].is_object());
+        assert!(schemas["ExtensionContext"]["properties"]["expression"].is_object());
+    }
+}
@@ -2,7 +2,7 @@
 //!
 //! Provides REST endpoints for managing FHIR packages through the canonical manager.
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::convert::Infallible;
 use std::sync::Arc;
 
@@ -16,19 +16,23 @@ use axum::{
     routing::{get, post},
     Json, Router,
 };
+use async_trait::async_trait;
 use chrono::Utc;
-use futures::stream::Stream;
 use octofhir_canonical_manager::registry::DownloadProgress;
+use octofhir_canonical_manager::CanonicalManager;
 use tokio::sync::{mpsc, RwLock};
 use tokio_stream::wrappers::ReceiverStream;
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::project::{find_package_dependents, ProjectService};
 use crate::state::AppState;
 
 use super::packages_dto::{
-    parse_package_id, InstallJobDto, InstallJobStatus, InstallProgressEvent, PackageDetailsDto,
-    PackageDto, PackageErrorResponse, PackageResourceCountsDto, PackageSearchQuery,
-    PackageSearchResultDto,
+    parse_package_id, InstallJobDto, InstallJobStatus, InstallProgressEvent, InstallQuery,
+    InstallResultDto, PackageContentsQuery, PackageContentsResponse, PackageDetailsDto, PackageDto,
+    PackageErrorResponse, PackageResourceCountsDto, PackageResourceDto, PackageSearchQuery,
+    PackageSearchResultDto, UninstallQuery,
 };
 
 /// In-memory store for install jobs (for polling).
@@ -43,6 +47,7 @@ pub fn package_routes() -> Router<AppState> {
         .route("/", get(list_packages))
         .route("/search", get(search_packages))
         .route("/{packageId}", get(get_package_details))
+        .route("/{packageId}/contents", get(get_package_contents))
         .route("/{packageId}/install", post(install_package))
         .route("/{packageId}/install/start", post(start_install_job))
         .route("/{packageId}/uninstall", post(uninstall_package))
@@ -288,16 +293,197 @@ async fn get_package_details(
     Json(details).into_response()
 }
 
+/// Resource types listed by the package contents endpoint.
+const CONTENT_RESOURCE_TYPES: [&str; 3] = ["StructureDefinition", "ValueSet", "CodeSystem"];
+
+/// Default page size of the package contents endpoint.
+const DEFAULT_CONTENTS_LIMIT: usize = 50;
+
+/// Largest page size of the package contents endpoint.
+const MAX_CONTENTS_LIMIT: usize = 500;
+
+/// Per-package resource index.
+///
+/// Implemented by the canonical manager; abstracted so the contents listing
+/// can be exercised without installed packages.
+#[async_trait]
+pub trait PackageIndex: Send + Sync {
+    /// All StructureDefinitions, ValueSets and CodeSystems of a package, or
+    /// `None` if the package is not installed.
+    /// A `version` of `latest` matches any installed version.
+    async fn package_resources(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<Vec<PackageResourceDto>>, String>;
+}
+
+#[async_trait]
+impl PackageIndex for CanonicalManager {
+    async fn package_resources(
+        &self,
+        name: &str,
+        version: &str,
+    ) -> Result<Option<Vec<PackageResourceDto>>, String> {
+        let installed = self
+            .storage()
+            .list_packages()
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .any(|p| p.name == name && (version == "latest" || p.version == version));
+        if !installed {
+            return Ok(None);
+        }
+
+        let mut builder = self.search().await.package(name);
+        for resource_type in CONTENT_RESOURCE_TYPES {
+            builder = builder.resource_type(resource_type);
+        }
+        let result = builder.limit(100_000).execute().await.map_err(|e| e.to_string())?;
+
+        Ok(Some(
+            result
+                .resources
+                .into_iter()
+                .map(|r| r.index)
+                .filter(|index| version == "latest" || index.package_version == version)
+                .map(|index| PackageResourceDto {
+                    resource_type: index.resource_type,
+                    url: index.canonical_url,
+                    name: index.name,
+                    kind: index.sd_kind,
+                    derivation: index.sd_derivation,
+                    sd_type: index.sd_type,
+                })
+                .collect(),
+        ))
+    }
+}
+
+/// List one page of a package's resources, with per-type facet counts over
+/// the whole package. Returns `None` if the package is not installed.
+async fn list_package_contents(
+    index: &dyn PackageIndex,
+    package_id: &str,
+    query: &PackageContentsQuery,
+) -> Result<Option<PackageContentsResponse>, String> {
+    let (name, version) = parse_package_id(package_id);
+    let Some(mut resources) = index.package_resources(&name, &version).await? else {
+        return Ok(None);
+    };
+    resources.retain(|r| CONTENT_RESOURCE_TYPES.contains(&r.resource_type.as_str()));
+    resources.sort_by(|a, b| {
+        a.resource_type
+            .cmp(&b.resource_type)
+            .then_with(|| a.url.cmp(&b.url))
+    });
+
+    let mut facets = std::collections::BTreeMap::new();
+    for resource in &resources {
+        *facets.entry(resource.resource_type.clone()).or_insert(0) += 1;
+    }
+
+    if let Some(resource_type) = &query.resource_type {
+        resources.retain(|r| &r.resource_type == resource_type);
+    }
+    let total = resources.len();
+    let offset = query.offset.unwrap_or(0);
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONTENTS_LIMIT)
+        .min(MAX_CONTENTS_LIMIT);
+    let resources = resources.into_iter().skip(offset).take(limit).collect();
+
+    Ok(Some(PackageContentsResponse {
+        package_id: package_id.to_string(),
+        resources,
+        total,
+        offset,
+        limit,
+        facets,
+    }))
+}
+
+/// GET /api/packages/:packageId/contents - List resources in an installed package.
+async fn get_package_contents(
+    State(state): State<AppState>,
+    Path(package_id): Path<String>,
+    Query(query): Query<PackageContentsQuery>,
+) -> Response {
+    let manager = match state.canonical_manager().await {
+        Ok(m) => m,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PackageErrorResponse::contents_failed(format!(
+                    "Failed to initialize package manager: {e}"
+                ))),
+            )
+                .into_response();
+        }
+    };
+
+    match list_package_contents(manager.as_ref(), &package_id, &query).await {
+        Ok(Some(contents)) => Json(contents).into_response(),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(PackageErrorResponse::not_found(format!(
+                "Package {} is not installed",
+                package_id
+            ))),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(PackageErrorResponse::contents_failed(format!(
+                "Failed to list package contents: {e}"
+            ))),
+        )
+            .into_response(),
+    }
+}
+
 /// POST /api/packages/:packageId/install - Install package with SSE progress.
+///
+/// With `?wait=true` the request blocks until the install finishes and
+/// returns an [`InstallResultDto`] instead, for scripts that cannot consume
+/// SSE.
 async fn install_package(
     State(state): State<AppState>,
     Path(package_id): Path<String>,
-) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    Query(query): Query<InstallQuery>,
+) -> Response {
+    let rx = spawn_install(state, package_id.clone());
+
+    if query.wait {
+        let result = await_install_result(&package_id, rx).await;
+        let status = if result.success {
+            StatusCode::OK
+        } else {
+            StatusCode::INTERNAL_SERVER_ERROR
+        };
+        return (status, Json(result)).into_response();
+    }
+
+    // Convert channel to SSE stream
+    let stream = ReceiverStream::new(rx).map(|event| {
+        let json = serde_json::to_string(&event).unwrap_or_else(|_| "{}".to_string());
+        Ok::<_, Infallible>(Event::default().data(json))
+    });
+
+    Sse::new(stream).keep_alive(KeepAlive::default()).into_response()
+}
+
+/// Install a package in the background, reporting progress over a channel.
+///
+/// The last event sent is always `Complete` or `Error`.
+fn spawn_install(state: AppState, package_id: String) -> mpsc::Receiver<InstallProgressEvent> {
     let (tx, rx) = mpsc::channel::<InstallProgressEvent>(100);
     let (name, version) = parse_package_id(&package_id);
 
     // Spawn installation task
-    tokio::spawn(async move {
+    let install = async move {
         // Send start event
         let _ = tx
             .send(InstallProgressEvent::Start {
@@ -321,6 +507,14 @@ async fn install_package(
             }
         };
 
+        // Packages present before the install, to count pulled-in dependencies
+        let installed_before: HashSet<(String, String)> = manager
+            .storage()
+            .list_packages()
+            .await
+            .map(|packages| packages.into_iter().map(|p| (p.name, p.version)).collect())
+            .unwrap_or_default();
+
         // Create progress reporter
         let progress = SseProgressReporter::new(tx.clone(), package_id.clone());
 
@@ -330,6 +524,8 @@ async fn install_package(
         // Install the package
         match manager.install_package(&name, &version).await {
             Ok(()) => {
+                state.invalidate_base_trees();
+
                 // Send extracting event
                 let _ = tx
                     .send(InstallProgressEvent::Extracting {
@@ -347,6 +543,13 @@ async fn install_package(
                 // Get the installed package info
                 match manager.storage().list_packages().await {
                     Ok(packages) => {
+                        let dependency_count = packages
+                            .iter()
+                            .filter(|p| !(p.name == name && p.version == version))
+                            .filter(|p| {
+                                !installed_before.contains(&(p.name.clone(), p.version.clone()))
+                            })
+                            .count();
                         if let Some(pkg) =
                             packages.iter().find(|p| p.name == name && p.version == version)
                         {
@@ -367,7 +570,12 @@ async fn install_package(
                                     total: pkg.resource_count as u32,
                                 }),
                             };
-                            let _ = tx.send(InstallProgressEvent::Complete { package: dto }).await;
+                            let _ = tx
+                                .send(InstallProgressEvent::Complete {
+                                    package: dto,
+                                    dependency_count,
+                                })
+                                .await;
                         } else {
                             // Package installed but couldn't find in list (unlikely)
                             let dto = PackageDto {
@@ -380,7 +588,12 @@ async fn install_package(
                                 installed_at: None,
                                 resource_counts: None,
                             };
-                            let _ = tx.send(InstallProgressEvent::Complete { package: dto }).await;
+                            let _ = tx
+                                .send(InstallProgressEvent::Complete {
+                                    package: dto,
+                                    dependency_count,
+                                })
+                                .await;
                         }
                     }
                     Err(e) => {
@@ -396,7 +609,12 @@ async fn install_package(
                             installed_at: None,
                             resource_counts: None,
                         };
-                        let _ = tx.send(InstallProgressEvent::Complete { package: dto }).await;
+                        let _ = tx
+                            .send(InstallProgressEvent::Complete {
+                                package: dto,
+                                dependency_count: 0,
+                            })
+                            .await;
                     }
                 }
             }
@@ -410,15 +628,34 @@ async fn install_package(
                     .await;
             }
         }
-    });
+    };
+    tokio::spawn(install.in_current_span());
 
-    // Convert channel to SSE stream
-    let stream = ReceiverStream::new(rx).map(|event| {
-        let json = serde_json::to_string(&event).unwrap_or_else(|_| "{}".to_string());
-        Ok(Event::default().data(json))
-    });
+    rx
+}
 
-    Sse::new(stream).keep_alive(KeepAlive::default())
+/// Drain install progress events until the terminal one.
+async fn await_install_result(
+    package_id: &str,
+    mut rx: mpsc::Receiver<InstallProgressEvent>,
+) -> InstallResultDto {
+    while let Some(event) = rx.recv().await {
+        match event {
+            InstallProgressEvent::Complete {
+                package,
+                dependency_count,
+            } => return InstallResultDto::installed(package_id, package, dependency_count),
+            InstallProgressEvent::Error { message, code, .. } => {
+                return InstallResultDto::failed(package_id, message, code);
+            }
+            _ => {}
+        }
+    }
+    InstallResultDto::failed(
+        package_id,
+        "Install task ended without a result",
+        "INSTALL_FAILED",
+    )
 }
 
 /// POST /api/packages/:packageId/install/start - Start install job (polling-based).
@@ -456,7 +693,7 @@ async fn start_install_job(
     let jobs_clone = jobs.clone();
     let job_id_clone = job_id.clone();
 
-    tokio::spawn(async move {
+    let install = async move {
         // Update to downloading status with initial progress
         {
             let mut jobs_lock = jobs_clone.write().await;
@@ -521,6 +758,8 @@ async fn start_install_job(
 
         match install_result {
             Ok(()) => {
+                state.invalidate_base_trees();
+
                 // Update to extracting
                 {
                     let mut jobs_lock = jobs_clone.write().await;
@@ -614,7 +853,8 @@ async fn start_install_job(
                 }
             }
         }
-    });
+    };
+    tokio::spawn(install.in_current_span());
 
     // Return job info immediately
     Json(job).into_response()
@@ -640,17 +880,51 @@ async fn get_install_job(
     }
 }
 
+/// Installed packages an uninstall runs against.
+///
+/// Implemented by the canonical manager; abstracted so the uninstall checks
+/// can be exercised without installed packages.
+#[async_trait]
+pub trait PackageStore: Send + Sync {
+    /// `(name, version)` of the package a canonical URL resolves from.
+    async fn package_of(&self, url: &str) -> Option<(String, String)>;
+
+    /// Remove an installed package.
+    async fn uninstall(&self, name: &str, version: &str) -> Result<(), String>;
+}
+
+#[async_trait]
+impl PackageStore for CanonicalManager {
+    async fn package_of(&self, url: &str) -> Option<(String, String)> {
+        let resource = self.resolve(url).await.ok()?;
+        Some((
+            resource.package_info.name.clone(),
+            resource.package_info.version.clone(),
+        ))
+    }
+
+    async fn uninstall(&self, name: &str, version: &str) -> Result<(), String> {
+        self.remove_package(name, version)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
+
 /// POST /api/packages/:packageId/uninstall - Uninstall a package.
+///
+/// Refuses with 409 while project resources reference canonicals resolved
+/// from the package, unless `?force=true`.
 async fn uninstall_package(
     State(state): State<AppState>,
     Path(package_id): Path<String>,
+    Query(query): Query<UninstallQuery>,
 ) -> Response {
     let manager = match state.canonical_manager().await {
         Ok(m) => m,
         Err(e) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(PackageErrorResponse::install_failed(format!(
+                Json(PackageErrorResponse::uninstall_failed(format!(
                     "Failed to initialize package manager: {e}"
                 ))),
             )
@@ -658,13 +932,70 @@ async fn uninstall_package(
         }
     };
 
-    let (name, version) = parse_package_id(&package_id);
+    uninstall_from(&state, manager.as_ref(), &package_id, query.force).await
+}
 
-    match manager.remove_package(&name, &version).await {
-        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+/// Uninstall a package from `store`, refusing while project resources
+/// depend on it unless `force` is set.
+async fn uninstall_from(
+    state: &AppState,
+    store: &dyn PackageStore,
+    package_id: &str,
+    force: bool,
+) -> Response {
+    let (name, version) = parse_package_id(package_id);
+
+    if !force {
+        let service = ProjectService::new(state.workspace_dir().clone());
+        let references = match service.workspace_references().await {
+            Ok(references) => references,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(PackageErrorResponse::uninstall_failed(format!(
+                        "Failed to scan projects for package usage: {e}"
+                    ))),
+                )
+                    .into_response();
+            }
+        };
+
+        let urls: BTreeSet<&String> = references
+            .iter()
+            .flat_map(|(_, profiles)| profiles.iter().flat_map(|p| &p.references))
+            .collect();
+        let mut resolved = BTreeMap::new();
+        for url in urls {
+            if let Some(package) = store.package_of(url).await {
+                resolved.insert(url.clone(), package);
+            }
+        }
+
+        let dependents = find_package_dependents(&references, &resolved, &name, &version);
+        if !dependents.is_empty() {
+            return (
+                StatusCode::CONFLICT,
+                Json(PackageErrorResponse::in_use(
+                    format!(
+                        "Package {} is used by {} project resource(s); pass force=true to uninstall anyway",
+                        package_id,
+                        dependents.len()
+                    ),
+                    serde_json::to_value(&dependents).unwrap_or_default(),
+                )),
+            )
+                .into_response();
+        }
+    }
+
+    match store.uninstall(&name, &version).await {
+        Ok(()) => {
+            state.invalidate_base_trees();
+            StatusCode::NO_CONTENT.into_response()
+        }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(PackageErrorResponse::install_failed(format!(
+            Json(PackageErrorResponse::uninstall_failed(format!(
                 "Failed to uninstall package: {e}"
             ))),
         )
@@ -711,3 +1042,241 @@ impl DownloadProgress for SseProgressReporter {
 
 // Need to implement StreamExt for the stream
 use futures::StreamExt;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(version: &str) -> PackageDto {
+        PackageDto {
+            id: format!("hl7.fhir.us.core@{}", version),
+            name: "hl7.fhir.us.core".to_string(),
+            version: version.to_string(),
+            description: None,
+            fhir_version: "4.0.1".to_string(),
+            installed: true,
+            installed_at: None,
+            resource_counts: None,
+        }
+    }
+
+    /// Stub install task that emits the given events, then closes the channel.
+    fn stub_install(events: Vec<InstallProgressEvent>) -> mpsc::Receiver<InstallProgressEvent> {
+        let (tx, rx) = mpsc::channel(100);
+        tokio::spawn(async move {
+            for event in events {
+                let _ = tx.send(event).await;
+            }
+        });
+        rx
+    }
+
+    /// Package index with fixed contents.
+    struct StubIndex(Vec<PackageResourceDto>);
+
+    #[async_trait]
+    impl PackageIndex for StubIndex {
+        async fn package_resources(
+            &self,
+            name: &str,
+            _version: &str,
+        ) -> Result<Option<Vec<PackageResourceDto>>, String> {
+            Ok((name == "hl7.fhir.us.core").then(|| self.0.clone()))
+        }
+    }
+
+    fn resource(resource_type: &str, url: &str) -> PackageResourceDto {
+        PackageResourceDto {
+            resource_type: resource_type.to_string(),
+            url: url.to_string(),
+            name: None,
+            kind: None,
+            derivation: None,
+            sd_type: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_package_contents_paginated_with_facets() {
+        let base = "http://hl7.org/fhir/us/core";
+        let index = StubIndex(vec![
+            resource("ValueSet", &format!("{base}/ValueSet/b")),
+            resource("StructureDefinition", &format!("{base}/StructureDefinition/b")),
+            resource("SearchParameter", &format!("{base}/SearchParameter/a")),
+            resource("StructureDefinition", &format!("{base}/StructureDefinition/a")),
+            resource("CodeSystem", &format!("{base}/CodeSystem/a")),
+            resource("ValueSet", &format!("{base}/ValueSet/a")),
+        ]);
+
+        let query = PackageContentsQuery {
+            resource_type: None,
+            offset: Some(1),
+            limit: Some(2),
+        };
+        let contents = list_package_contents(&index, "hl7.fhir.us.core@6.1.0", &query)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(contents.total, 5);
+        assert_eq!(contents.facets["StructureDefinition"], 2);
+        assert_eq!(contents.facets["ValueSet"], 2);
+        assert_eq!(contents.facets["CodeSystem"], 1);
+        assert!(!contents.facets.contains_key("SearchParameter"));
+        let urls: Vec<&str> = contents.resources.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(
+            urls,
+            vec![
+                "http://hl7.org/fhir/us/core/StructureDefinition/a",
+                "http://hl7.org/fhir/us/core/StructureDefinition/b",
+            ]
+        );
+
+        // The type filter narrows the listing but not the facets
+        let query = PackageContentsQuery {
+            resource_type: Some("ValueSet".to_string()),
+            ..Default::default()
+        };
+        let contents = list_package_contents(&index, "hl7.fhir.us.core", &query)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(contents.total, 2);
+        assert_eq!(contents.resources.len(), 2);
+        assert_eq!(contents.facets.len(), 3);
+
+        // Oversized pages are capped
+        let query = PackageContentsQuery {
+            limit: Some(100_000),
+            ..Default::default()
+        };
+        let contents = list_package_contents(&index, "hl7.fhir.us.core", &query)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(contents.limit, MAX_CONTENTS_LIMIT);
+
+        // Packages that are not installed are reported as missing
+        let missing = list_package_contents(&index, "hl7.fhir.uv.ips", &query).await;
+        assert!(missing.unwrap().is_none());
+    }
+
+    /// Package store resolving every canonical to the core package, recording
+    /// removals.
+    #[derive(Default)]
+    struct StubStore {
+        removed: std::sync::Mutex<Vec<(String, String)>>,
+    }
+
+    #[async_trait]
+    impl PackageStore for StubStore {
+        async fn package_of(&self, _url: &str) -> Option<(String, String)> {
+            Some(("hl7.fhir.r4.core".to_string(), "4.0.1".to_string()))
+        }
+
+        async fn uninstall(&self, name: &str, version: &str) -> Result<(), String> {
+            self.removed
+                .lock()
+                .unwrap()
+                .push((name.to_string(), version.to_string()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uninstall_refused_while_in_use() {
+        use crate::project::{AddResourceRequest, CreateProjectRequest, ResourceKind};
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let service = ProjectService::new(workspace.path().to_path_buf());
+        service
+            .create_project(CreateProjectRequest {
+                id: "my-ig".to_string(),
+                name: "My IG".to_string(),
+                canonical_base: "http://example.org/fhir".to_string(),
+                fhir_version: None,
+                description: None,
+                publisher: None,
+                dependencies: None,
+            })
+            .await
+            .unwrap();
+        service
+            .add_resource(
+                "my-ig",
+                AddResourceRequest {
+                    id: None,
+                    name: "MyPatient".to_string(),
+                    kind: ResourceKind::Profile,
+                    canonical_url: None,
+                    base: Some("Patient".to_string()),
+                    source_format: None,
+                    description: None,
+                    context: None,
+                    purpose: None,
+                    content: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let store = StubStore::default();
+        let response = uninstall_from(&state, &store, "hl7.fhir.r4.core@4.0.1", false).await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["code"], "PACKAGE_IN_USE");
+        assert_eq!(json["details"][0]["projectId"], "my-ig");
+        assert!(json["details"][0]["canonicals"]
+            .as_array()
+            .unwrap()
+            .contains(&"http://hl7.org/fhir/StructureDefinition/Patient".into()));
+        assert!(store.removed.lock().unwrap().is_empty());
+
+        // Forcing skips the check
+        let response = uninstall_from(&state, &store, "hl7.fhir.r4.core@4.0.1", true).await;
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            *store.removed.lock().unwrap(),
+            vec![("hl7.fhir.r4.core".to_string(), "4.0.1".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blocking_install_returns_terminal_result() {
+        let package_id = "hl7.fhir.us.core@6.1.0";
+        let rx = stub_install(vec![
+            InstallProgressEvent::Start {
+                package_id: package_id.to_string(),
+                total_bytes: None,
+            },
+            InstallProgressEvent::Indexing {
+                package_id: package_id.to_string(),
+            },
+            InstallProgressEvent::Complete {
+                package: package("6.1.0"),
+                dependency_count: 3,
+            },
+        ]);
+        let result = await_install_result(package_id, rx).await;
+        assert!(result.success);
+        assert_eq!(result.installed_version.as_deref(), Some("6.1.0"));
+        assert_eq!(result.dependency_count, 3);
+
+        let rx = stub_install(vec![InstallProgressEvent::Error {
+            package_id: package_id.to_string(),
+            message: "not found".to_string(),
+            code: "INSTALL_FAILED".to_string(),
+        }]);
+        let result = await_install_result(package_id, rx).await;
+        assert!(!result.success);
+        assert_eq!(result.error.as_deref(), Some("not found"));
+
+        // A task that dies silently still yields a terminal result
+        let result = await_install_result(package_id, stub_install(Vec::new())).await;
+        assert!(!result.success);
+        assert_eq!(result.code.as_deref(), Some("INSTALL_FAILED"));
+    }
+}
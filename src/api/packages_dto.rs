@@ -5,6 +5,8 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Deserializer, Serialize};
 
+use crate::base::BaseResource;
+
 /// Deserialize a field that can be either a single string or a Vec<String>.
 /// This handles URL query params like `?package=foo` (single) or `?package=foo&package=bar` (multiple).
 fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
@@ -309,7 +311,12 @@ pub enum InstallProgressEvent {
         package_id: String,
     },
     /// Installation completed successfully
-    Complete { package: PackageDto },
+    Complete {
+        package: PackageDto,
+        /// Dependencies installed along with the package
+        #[serde(rename = "dependencyCount")]
+        dependency_count: usize,
+    },
     /// Installation failed
     Error {
         #[serde(rename = "packageId")]
@@ -319,6 +326,137 @@ pub enum InstallProgressEvent {
     },
 }
 
+/// Query parameters for package install.
+#[derive(Debug, Default, Deserialize)]
+pub struct InstallQuery {
+    /// Block until the install finishes and return [`InstallResultDto`]
+    /// instead of streaming SSE progress
+    #[serde(default)]
+    pub wait: bool,
+}
+
+/// Final result of a blocking (`?wait=true`) package install.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallResultDto {
+    /// Whether the package was installed
+    pub success: bool,
+    /// Requested package (name@version)
+    pub package_id: String,
+    /// Installed version
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub installed_version: Option<String>,
+    /// Dependencies installed along with the package
+    pub dependency_count: usize,
+    /// Installed package info
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<PackageDto>,
+    /// Error message if failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Error code if failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl InstallResultDto {
+    /// Result of a successful install.
+    pub fn installed(
+        package_id: impl Into<String>,
+        package: PackageDto,
+        dependency_count: usize,
+    ) -> Self {
+        Self {
+            success: true,
+            package_id: package_id.into(),
+            installed_version: Some(package.version.clone()),
+            dependency_count,
+            package: Some(package),
+            error: None,
+            code: None,
+        }
+    }
+
+    /// Result of a failed install.
+    pub fn failed(
+        package_id: impl Into<String>,
+        message: impl Into<String>,
+        code: impl Into<String>,
+    ) -> Self {
+        Self {
+            success: false,
+            package_id: package_id.into(),
+            installed_version: None,
+            dependency_count: 0,
+            package: None,
+            error: Some(message.into()),
+            code: Some(code.into()),
+        }
+    }
+}
+
+/// Query parameters for package uninstall.
+#[derive(Debug, Default, Deserialize)]
+pub struct UninstallQuery {
+    /// Uninstall even if project resources depend on the package
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Query parameters for listing package contents.
+#[derive(Debug, Default, Deserialize)]
+pub struct PackageContentsQuery {
+    /// Only list resources of this type (StructureDefinition, ValueSet, CodeSystem)
+    #[serde(rename = "type", default)]
+    pub resource_type: Option<String>,
+    /// Number of resources to skip
+    #[serde(default)]
+    pub offset: Option<usize>,
+    /// Maximum number of resources to return (default 50, at most 500)
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// A resource in an installed package.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageResourceDto {
+    /// Resource type
+    pub resource_type: String,
+    /// Canonical URL
+    pub url: String,
+    /// Resource name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// StructureDefinition kind (resource, complex-type, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// StructureDefinition derivation (specialization, constraint)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub derivation: Option<String>,
+    /// Type constrained or defined by a StructureDefinition
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub sd_type: Option<String>,
+}
+
+/// A page of an installed package's resources.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageContentsResponse {
+    /// Package identifier (name@version)
+    pub package_id: String,
+    /// Resources on this page, ordered by type and canonical URL
+    pub resources: Vec<PackageResourceDto>,
+    /// Number of resources matching the type filter
+    pub total: usize,
+    /// Offset of this page
+    pub offset: usize,
+    /// Page size
+    pub limit: usize,
+    /// Resource count per type, over the whole package
+    pub facets: std::collections::BTreeMap<String, usize>,
+}
+
 /// Query parameters for package search.
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -351,6 +489,9 @@ pub struct ResourceSearchQuery {
     /// FHIR version filter (e.g., "4.0.1", "5.0.0")
     #[serde(default)]
     pub fhir_version: Option<String>,
+    /// Drop matches scoring below this relevance
+    #[serde(default, rename = "minScore")]
+    pub min_score: Option<f64>,
     /// Maximum number of results
     #[serde(default)]
     pub limit: Option<usize>,
@@ -377,6 +518,9 @@ pub struct ExtensionSearchQuery {
     /// Extension context path filter (e.g., "Patient", "Observation.value")
     #[serde(default)]
     pub context_path: Option<String>,
+    /// Drop matches scoring below this relevance
+    #[serde(default, rename = "minScore")]
+    pub min_score: Option<f64>,
     /// Maximum number of results
     #[serde(default)]
     pub limit: Option<usize>,
@@ -400,6 +544,9 @@ pub struct ValueSetSearchQuery {
     /// Code system URL filter
     #[serde(default)]
     pub system: Option<String>,
+    /// Drop matches scoring below this relevance
+    #[serde(default, rename = "minScore")]
+    pub min_score: Option<f64>,
     /// Maximum number of results
     #[serde(default)]
     pub limit: Option<usize>,
@@ -426,6 +573,9 @@ pub struct ProfileSearchQuery {
     /// Derivation type filter (constraint, specialization)
     #[serde(default)]
     pub derivation: Option<String>,
+    /// Drop matches scoring below this relevance
+    #[serde(default, rename = "minScore")]
+    pub min_score: Option<f64>,
     /// Maximum number of results
     #[serde(default)]
     pub limit: Option<usize>,
@@ -437,14 +587,24 @@ pub struct ProfileSearchQuery {
 /// Query parameters for element search.
 #[derive(Debug, Deserialize)]
 pub struct ElementSearchQuery {
-    /// Profile ID to search within
-    pub profile_id: String,
+    /// Profile ID or URL to search within (all installed profiles if omitted)
+    #[serde(default)]
+    pub profile_id: Option<String>,
     /// Text query for element path or description
     #[serde(default)]
     pub q: Option<String>,
+    /// Resource type filter (StructureDefinition.type)
+    #[serde(default)]
+    pub resource_type: Option<String>,
+    /// Only return elements flagged mustSupport
+    #[serde(default)]
+    pub must_support_only: bool,
     /// Maximum number of results
     #[serde(default)]
     pub limit: Option<usize>,
+    /// Offset for pagination
+    #[serde(default)]
+    pub offset: Option<usize>,
 }
 
 /// Query parameters for base resource search.
@@ -464,24 +624,25 @@ pub struct BaseResourceSearchQuery {
     pub limit: Option<usize>,
 }
 
-/// Base resource type information (for profile creation).
+/// Query for `GET /api/base-resources`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseResourceListQuery {
+    /// FHIR version (e.g., "R4", "4.0.1"); defaults to R4.
+    #[serde(default)]
+    pub fhir_version: Option<String>,
+}
+
+/// Profileable base resource types of a FHIR version.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct BaseResourceDto {
-    /// Resource type name (e.g., "Patient", "Observation")
-    pub name: String,
-    /// Canonical URL
-    pub url: String,
-    /// Resource title
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub title: Option<String>,
-    /// Resource description
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub description: Option<String>,
-    /// Package name
+pub struct BaseResourceListResponse {
+    /// FHIR version label (e.g., "R4").
+    pub fhir_version: String,
+    /// Core package the resources come from.
     pub package_name: String,
-    /// Package version
-    pub package_version: String,
+    /// Base resources, sorted by name.
+    pub resources: Vec<BaseResource>,
 }
 
 /// Extension context information.
@@ -576,6 +737,15 @@ pub struct ElementDto {
     /// Cardinality max
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max: Option<String>,
+    /// Whether the element is flagged mustSupport
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub must_support: bool,
+    /// Canonical URL of the profile containing the element
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile_url: Option<String>,
+    /// Package containing the profile
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
 }
 
 /// Generic resource search result.
@@ -665,6 +835,33 @@ impl PackageErrorResponse {
         }
     }
 
+    pub fn contents_failed(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: "CONTENTS_FAILED".to_string(),
+            status: 500,
+            details: None,
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: "BAD_REQUEST".to_string(),
+            status: 400,
+            details: None,
+        }
+    }
+
+    pub fn lookup_failed(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: "LOOKUP_FAILED".to_string(),
+            status: 500,
+            details: None,
+        }
+    }
+
     pub fn network_error(message: impl Into<String>) -> Self {
         Self {
             error: message.into(),
@@ -674,6 +871,24 @@ impl PackageErrorResponse {
         }
     }
 
+    pub fn uninstall_failed(message: impl Into<String>) -> Self {
+        Self {
+            error: message.into(),
+            code: "UNINSTALL_FAILED".to_string(),
+            status: 500,
+            details: None,
+        }
+    }
+
+    pub fn in_use(message: impl Into<String>, dependents: serde_json::Value) -> Self {
+        Self {
+            error: message.into(),
+            code: "PACKAGE_IN_USE".to_string(),
+            status: 409,
+            details: Some(dependents),
+        }
+    }
+
     pub fn already_installed(message: impl Into<String>) -> Self {
         Self {
             error: message.into(),
@@ -0,0 +1,394 @@
+//! Pluggable persistence backends for profile documents.
+//!
+//! Handlers reach profiles through the [`Storage`] trait held by
+//! [`AppState`](crate::state::AppState), so hosted deployments can swap the
+//! filesystem for object storage or a database without touching handlers.
+//! The trait covers the profile documents themselves; files kept next to
+//! them in the project directory (imported SD and FSH sources, annotations)
+//! are still read and written through [`ProfileStorage`].
+//!
+//! - [`FileStorage`] - the default; resolves each project to its directory in
+//!   the workspace and delegates to [`ProfileStorage`]
+//! - [`MemoryStorage`] - keeps documents in memory, for tests
+//!
+//! Every backend persists the differential-only [`stored_form`] of a profile,
+//! so loaded documents look the same whichever backend is in use.
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use dashmap::DashMap;
+use indexmap::IndexMap;
+use tokio::sync::Mutex;
+
+use crate::ir::ProfileDocument;
+use crate::paths::{join_id, validate_path_id};
+
+use super::storage::{
+    check_if_match, etag_of, stored_form, ProfileStorage, StorageError, StorageResult,
+};
+
+/// Profile persistence backend, keyed by project and profile id.
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// List all profiles of a project, in creation order.
+    async fn list_profiles(&self, project_id: &str) -> StorageResult<Vec<ProfileDocument>>;
+
+    /// Load a profile.
+    async fn load_profile(&self, project_id: &str, profile_id: &str)
+        -> StorageResult<ProfileDocument>;
+
+    /// Create or overwrite a profile.
+    async fn save_profile(&self, project_id: &str, doc: &ProfileDocument) -> StorageResult<()>;
+
+    /// Create or overwrite a profile together with its SD JSON and FSH sources.
+    ///
+    /// Either everything is saved or nothing changes.
+    async fn save_profile_with_sources(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        sd_json: Option<&str>,
+        fsh: Option<&str>,
+    ) -> StorageResult<()>;
+
+    /// Create or overwrite several profiles of a project at once.
+    ///
+    /// Either all of them are saved or nothing changes.
+    async fn save_profiles(&self, project_id: &str, docs: &[ProfileDocument]) -> StorageResult<()>;
+
+    /// Delete a profile. Deleting a missing profile is not an error.
+    async fn delete_profile(&self, project_id: &str, profile_id: &str) -> StorageResult<()>;
+
+    /// Check if a profile exists.
+    async fn profile_exists(&self, project_id: &str, profile_id: &str) -> StorageResult<bool> {
+        match self.load_profile(project_id, profile_id).await {
+            Ok(_) => Ok(true),
+            Err(StorageError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Entity tag of the stored profile.
+    async fn profile_etag(&self, project_id: &str, profile_id: &str) -> StorageResult<String>;
+
+    /// Replace an existing profile wholesale.
+    ///
+    /// When `if_match` is given (an `If-Match` header value), the stored
+    /// profile must still carry that entity tag, otherwise
+    /// [`StorageError::ConcurrentModification`] is returned. The check and
+    /// the write are atomic: of two replacements carrying the same tag,
+    /// only one succeeds.
+    async fn replace_profile(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        if_match: Option<&str>,
+    ) -> StorageResult<()>;
+}
+
+/// Filesystem backend rooted at the workspace directory.
+#[derive(Debug, Clone)]
+pub struct FileStorage {
+    workspace_dir: PathBuf,
+    /// Per-profile locks serializing replacements (key: "project_id/profile_id").
+    replace_locks: Arc<DashMap<String, Arc<Mutex<()>>>>,
+}
+
+impl FileStorage {
+    /// Create a filesystem backend for a workspace.
+    pub fn new(workspace_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            workspace_dir: workspace_dir.into(),
+            replace_locks: Arc::default(),
+        }
+    }
+
+    /// Storage of a single project.
+    pub fn project(&self, project_id: &str) -> StorageResult<ProfileStorage> {
+        let project_dir = join_id(&self.workspace_dir, "project id", project_id)?;
+        Ok(ProfileStorage::new(project_dir))
+    }
+}
+
+#[async_trait]
+impl Storage for FileStorage {
+    async fn list_profiles(&self, project_id: &str) -> StorageResult<Vec<ProfileDocument>> {
+        let storage = self.project(project_id)?;
+        storage.init().await?;
+        storage.list_profiles().await
+    }
+
+    async fn load_profile(
+        &self,
+        project_id: &str,
+        profile_id: &str,
+    ) -> StorageResult<ProfileDocument> {
+        self.project(project_id)?.load_profile(profile_id).await
+    }
+
+    async fn save_profile(&self, project_id: &str, doc: &ProfileDocument) -> StorageResult<()> {
+        self.project(project_id)?.save_profile(doc).await
+    }
+
+    async fn save_profile_with_sources(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        sd_json: Option<&str>,
+        fsh: Option<&str>,
+    ) -> StorageResult<()> {
+        self.project(project_id)?
+            .save_profile_with_sources(doc, sd_json, fsh)
+            .await
+    }
+
+    async fn save_profiles(&self, project_id: &str, docs: &[ProfileDocument]) -> StorageResult<()> {
+        self.project(project_id)?.save_profiles(docs).await
+    }
+
+    async fn delete_profile(&self, project_id: &str, profile_id: &str) -> StorageResult<()> {
+        self.project(project_id)?.delete_profile(profile_id).await
+    }
+
+    async fn profile_exists(&self, project_id: &str, profile_id: &str) -> StorageResult<bool> {
+        Ok(self.project(project_id)?.profile_exists(profile_id).await)
+    }
+
+    async fn profile_etag(&self, project_id: &str, profile_id: &str) -> StorageResult<String> {
+        self.project(project_id)?.profile_etag(profile_id).await
+    }
+
+    async fn replace_profile(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        if_match: Option<&str>,
+    ) -> StorageResult<()> {
+        let storage = self.project(project_id)?;
+        let lock = self
+            .replace_locks
+            .entry(format!("{}/{}", project_id, doc.metadata.id))
+            .or_default()
+            .clone();
+        let _guard = lock.lock().await;
+        storage.replace_profile(doc, if_match).await
+    }
+}
+
+/// In-memory backend.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    /// Stored profiles (project id -> profile id -> document).
+    projects: RwLock<IndexMap<String, IndexMap<String, ProfileDocument>>>,
+}
+
+impl MemoryStorage {
+    /// Create an empty in-memory backend.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    async fn list_profiles(&self, project_id: &str) -> StorageResult<Vec<ProfileDocument>> {
+        validate_path_id("project id", project_id)?;
+        let projects = self.projects.read().unwrap_or_else(|e| e.into_inner());
+        Ok(projects
+            .get(project_id)
+            .map(|profiles| profiles.values().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    async fn load_profile(
+        &self,
+        project_id: &str,
+        profile_id: &str,
+    ) -> StorageResult<ProfileDocument> {
+        validate_path_id("project id", project_id)?;
+        validate_path_id("profile id", profile_id)?;
+        let projects = self.projects.read().unwrap_or_else(|e| e.into_inner());
+        projects
+            .get(project_id)
+            .and_then(|profiles| profiles.get(profile_id))
+            .cloned()
+            .ok_or_else(|| StorageError::NotFound(profile_id.to_string()))
+    }
+
+    async fn save_profile(&self, project_id: &str, doc: &ProfileDocument) -> StorageResult<()> {
+        validate_path_id("project id", project_id)?;
+        validate_path_id("profile id", &doc.metadata.id)?;
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        projects
+            .entry(project_id.to_string())
+            .or_default()
+            .insert(doc.metadata.id.clone(), stored_form(doc));
+        Ok(())
+    }
+
+    /// Sources are not kept: nothing reads them back from this backend.
+    async fn save_profile_with_sources(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        _sd_json: Option<&str>,
+        _fsh: Option<&str>,
+    ) -> StorageResult<()> {
+        self.save_profile(project_id, doc).await
+    }
+
+    async fn save_profiles(&self, project_id: &str, docs: &[ProfileDocument]) -> StorageResult<()> {
+        validate_path_id("project id", project_id)?;
+        for doc in docs {
+            validate_path_id("profile id", &doc.metadata.id)?;
+        }
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        let profiles = projects.entry(project_id.to_string()).or_default();
+        for doc in docs {
+            profiles.insert(doc.metadata.id.clone(), stored_form(doc));
+        }
+        Ok(())
+    }
+
+    async fn delete_profile(&self, project_id: &str, profile_id: &str) -> StorageResult<()> {
+        validate_path_id("project id", project_id)?;
+        validate_path_id("profile id", profile_id)?;
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(profiles) = projects.get_mut(project_id) {
+            profiles.shift_remove(profile_id);
+        }
+        Ok(())
+    }
+
+    async fn profile_etag(&self, project_id: &str, profile_id: &str) -> StorageResult<String> {
+        let doc = self.load_profile(project_id, profile_id).await?;
+        Ok(etag_of(&serde_json::to_vec(&doc)?))
+    }
+
+    async fn replace_profile(
+        &self,
+        project_id: &str,
+        doc: &ProfileDocument,
+        if_match: Option<&str>,
+    ) -> StorageResult<()> {
+        validate_path_id("project id", project_id)?;
+        validate_path_id("profile id", &doc.metadata.id)?;
+
+        // Check and write under one lock so concurrent replacements serialize
+        let mut projects = self.projects.write().unwrap_or_else(|e| e.into_inner());
+        let stored = projects
+            .get_mut(project_id)
+            .and_then(|profiles| profiles.get_mut(&doc.metadata.id))
+            .ok_or_else(|| StorageError::NotFound(doc.metadata.id.clone()))?;
+        let current = etag_of(&serde_json::to_vec(&*stored)?);
+        check_if_match(&doc.metadata.id, &current, if_match)?;
+
+        let mut doc = doc.clone();
+        doc.modified_at = Utc::now();
+        *stored = stored_form(&doc);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{
+        BaseDefinition, DocumentMetadata, ElementNode, ElementSource, FhirVersion, ProfiledResource,
+    };
+    use tempfile::TempDir;
+
+    fn create_test_document(id: &str) -> ProfileDocument {
+        let metadata = DocumentMetadata::new(
+            id,
+            format!("http://example.org/fhir/StructureDefinition/{}", id),
+            id,
+        );
+        let resource = ProfiledResource::new(
+            &metadata.url,
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        let mut doc = ProfileDocument::new(metadata, resource);
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.source = ElementSource::Modified;
+        doc.resource.root.add_child(name);
+        doc
+    }
+
+    /// Exercise the contract every backend must honor.
+    async fn check_backend(storage: &dyn Storage) {
+        assert!(storage.list_profiles("demo").await.unwrap().is_empty());
+        assert!(matches!(
+            storage.load_profile("demo", "first").await,
+            Err(StorageError::NotFound(_))
+        ));
+
+        storage.save_profile("demo", &create_test_document("first")).await.unwrap();
+        storage.save_profile("demo", &create_test_document("second")).await.unwrap();
+        storage.save_profile("other", &create_test_document("third")).await.unwrap();
+
+        let ids: Vec<String> = storage
+            .list_profiles("demo")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|doc| doc.metadata.id)
+            .collect();
+        assert_eq!(ids, vec!["first", "second"]);
+
+        // Only the differential is persisted
+        let loaded = storage.load_profile("demo", "first").await.unwrap();
+        assert!(loaded.resource.root.children.is_empty());
+        assert!(!loaded.resource.differential.is_empty());
+
+        // Replacing checks the entity tag
+        let etag = storage.profile_etag("demo", "first").await.unwrap();
+        assert!(matches!(
+            storage
+                .replace_profile("demo", &loaded, Some("\"stale\""))
+                .await,
+            Err(StorageError::ConcurrentModification(_))
+        ));
+        storage
+            .replace_profile("demo", &loaded, Some(&etag))
+            .await
+            .unwrap();
+
+        assert!(storage.profile_exists("demo", "first").await.unwrap());
+        storage.delete_profile("demo", "first").await.unwrap();
+        storage.delete_profile("demo", "first").await.unwrap();
+        assert!(!storage.profile_exists("demo", "first").await.unwrap());
+        assert_eq!(storage.list_profiles("demo").await.unwrap().len(), 1);
+
+        // A batch is saved as a whole or not at all
+        let batch = [
+            create_test_document("fourth"),
+            create_test_document("../fifth"),
+        ];
+        assert!(storage.save_profiles("demo", &batch).await.is_err());
+        assert!(!storage.profile_exists("demo", "fourth").await.unwrap());
+        storage.save_profiles("demo", &batch[..1]).await.unwrap();
+        assert_eq!(storage.list_profiles("demo").await.unwrap().len(), 2);
+
+        assert!(matches!(
+            storage.load_profile("../demo", "second").await,
+            Err(StorageError::InvalidId(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_file_storage_backend() {
+        let temp_dir = TempDir::new().unwrap();
+        check_backend(&FileStorage::new(temp_dir.path())).await;
+        assert!(temp_dir.path().join("demo/IR/resources/second.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_memory_storage_backend() {
+        check_backend(&MemoryStorage::new()).await;
+    }
+}
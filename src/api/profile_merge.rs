@@ -2,11 +2,14 @@
 //!
 //! Ensures a loaded document has a merged element tree for UI/export.
 
+use std::sync::Arc;
+
 use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
 
 use crate::base::BaseResolver;
 use crate::ir::{ElementNode, ProfileDocument};
-use crate::merge::ElementTreeMerger;
+use crate::merge::{minimize_differential, ConstraintOrigins, ElementTreeMerger};
 use crate::state::AppState;
 
 use super::profiles::ErrorResponse;
@@ -14,15 +17,85 @@ use super::profiles::ErrorResponse;
 /// Hydrate a profile document by merging its differential onto the base tree.
 pub async fn hydrate_profile_document(
     state: &AppState,
-    mut doc: ProfileDocument,
+    doc: ProfileDocument,
 ) -> Result<ProfileDocument, ErrorResponse> {
+    hydrate_with_origins(state, doc).await.map(|(doc, _)| doc)
+}
+
+/// Hydrate a profile document, also returning which constraint fields of
+/// each merged element came from the profile's differential.
+pub async fn hydrate_with_origins(
+    state: &AppState,
+    mut doc: ProfileDocument,
+) -> Result<(ProfileDocument, ConstraintOrigins), ErrorResponse> {
     if doc.resource.differential.is_empty() && !doc.resource.root.is_empty() {
         doc.resource.extract_differential();
     }
 
-    let base_url = doc.resource.base.url.clone();
-    let fhir_version = doc.resource.fhir_version;
     let resource_type = doc.resource.resource_type().to_string();
+    let base_tree = match load_base_tree(state, &doc).await? {
+        Some(base_tree) => {
+            doc.resource.hydrated_base = Some(Arc::new(base_tree.clone()));
+            base_tree
+        }
+        None => ElementNode::new(resource_type),
+    };
+
+    let merger = ElementTreeMerger::new();
+    let (root, origins) = merger.merge_with_origins(base_tree, &doc.resource.differential);
+    doc.resource.root = root;
+    doc.resource.hydrated = true;
+
+    Ok((doc, origins))
+}
+
+/// Replace the element tree of a hydrated document with its minimal differential.
+///
+/// Used for snapshot-mode edits, which may touch any field of any element:
+/// the differential is re-extracted from the tree and reduced to what differs
+/// from the base. The tree is dropped and the document no longer counts as
+/// hydrated, so saving stores the reduced differential instead of extracting
+/// it again.
+pub async fn minimize_to_base(
+    state: &AppState,
+    doc: &mut ProfileDocument,
+) -> Result<(), ErrorResponse> {
+    doc.resource.extract_differential();
+    if let Some(base_tree) = load_base_tree(state, doc).await? {
+        minimize_differential(&mut doc.resource.differential, &base_tree);
+    }
+    doc.resource.root = ElementNode::default();
+    doc.resource.hydrated = false;
+    Ok(())
+}
+
+/// Hydrate a profile document, converting a failure into a ready response.
+///
+/// Handlers call this exactly once after loading and return the `Err`
+/// response as-is.
+pub async fn hydrate_or_respond(
+    state: &AppState,
+    doc: ProfileDocument,
+) -> Result<ProfileDocument, Response> {
+    hydrate_profile_document(state, doc)
+        .await
+        .map_err(IntoResponse::into_response)
+}
+
+/// Load the base element tree a profile constrains.
+///
+/// Returns `Ok(None)` for specializations (including logical models), which
+/// define all their elements, and (after logging) when the base definition
+/// cannot be resolved from installed packages.
+pub async fn load_base_tree(
+    state: &AppState,
+    doc: &ProfileDocument,
+) -> Result<Option<ElementNode>, ErrorResponse> {
+    if doc.resource.is_specialization() {
+        return Ok(None);
+    }
+
+    let base_url = &doc.resource.base.canonical();
 
     let canonical_manager = state
         .canonical_manager()
@@ -34,11 +107,12 @@ pub async fn hydrate_profile_document(
                 format!("Canonical manager error: {}", e),
             )
         })?;
-    let resolver = BaseResolver::new(canonical_manager.clone());
-    let merger = ElementTreeMerger::new();
+    let resolver = BaseResolver::new(canonical_manager.clone())
+        .with_cache(state.base_tree_cache().clone())
+        .with_retry(state.config().resolve_retry_policy());
 
-    let base_tree = match resolver.load_base_tree(&base_url, fhir_version).await {
-        Ok(tree) => tree,
+    match resolver.load_base_tree(base_url, doc.resource.fhir_version).await {
+        Ok(tree) => Ok(Some(tree)),
         Err(e) => {
             tracing::warn!(
                 "Failed to resolve base '{}' for profile '{}': {}",
@@ -46,11 +120,32 @@ pub async fn hydrate_profile_document(
                 doc.metadata.id,
                 e
             );
-            ElementNode::new(resource_type)
+            Ok(None)
         }
-    };
+    }
+}
 
-    doc.resource.root = merger.merge(base_tree, &doc.resource.differential);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    Ok(doc)
+    #[tokio::test]
+    async fn test_hydration_error_short_circuits() {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+        let workspace = tempfile::tempdir().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        state.make_canonical_manager_unavailable();
+
+        let url = "http://example.org/fhir/StructureDefinition/TestPatient";
+        let resource =
+            ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        let doc = ProfileDocument::new(
+            DocumentMetadata::new("test-patient", url, "TestPatient"),
+            resource,
+        );
+
+        let response = hydrate_or_respond(&state, doc).await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
 }
@@ -4,32 +4,55 @@
 
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
     routing::{get, patch, post},
     Json, Router,
 };
-use uuid::Uuid;
 
 use crate::ir::{
-    BaseDefinition, Binding, BindingStrength, Cardinality, DocumentMetadata, ElementNode,
-    FhirVersion, ProfileDocument, ProfileStatus, ProfiledResource, TypeConstraint,
+    BaseDefinition, BindingStrength, Derivation, DocumentMetadata, EditingMode, ElementNode,
+    FhirVersion, ProfileDocument, ProfileStatus, ProfiledResource, SlicingRules, StructureKind,
+    TypeConstraint,
 };
-use crate::state::AppState;
+use crate::operations::{
+    apply_operation, AddDiscriminator, Operation, OperationError, RemoveDiscriminator,
+    RemoveElement, RemoveExample, SetAbstract, SetBinding, SetCardinality, SetComment,
+    SetDefaultValue, SetDefinition, SetExample, SetExtensionContext, SetIsModifier,
+    SetIsSummary, SetMeaningWhenMissing, SetMustHaveValue, SetMustSupport,
+    SetMustSupportRecursive, SetShort, SetSlicingDescription, SetSlicingRules,
+    SetTypeConstraints,
+};
+use crate::paths::{InvalidPathId, validate_path_id};
+use crate::project::{
+    slugify, CanonicalUsage, DependencyGraph, ProjectError, ProjectIndex, ProjectResource,
+    ProjectService, ResourceKind,
+};
+use crate::state::{AppState, IdempotencyReservation};
 
+use super::annotations::{annotation_target, delete_annotation};
 use super::dto::*;
-use super::profile_merge::hydrate_profile_document;
+use super::profile_merge::{hydrate_profile_document, load_base_tree, minimize_to_base};
 use super::storage::{ProfileStorage, StorageError};
 
 /// Create profile routes.
 pub fn profile_routes() -> Router<AppState> {
     Router::new()
         .route("/", get(list_profiles).post(create_profile))
-        .route("/{profileId}", get(get_profile).delete(delete_profile))
+        .route(
+            "/{profileId}",
+            get(get_profile).put(replace_profile).delete(delete_profile),
+        )
         .route("/{profileId}/metadata", patch(update_metadata))
-        .route("/{profileId}/elements/{*path}", patch(update_element))
+        .route("/{profileId}/rename", post(rename_profile))
+        .route(
+            "/{profileId}/elements/{*path}",
+            patch(update_element).delete(remove_element),
+        )
         .route("/{profileId}/import", post(import_profile))
+        .route("/{profileId}/fsh/validate", get(validate_fsh))
         .route("/{profileId}/input-it", get(get_input_it))
+        .route("/{profileId}/stats", get(get_profile_stats))
 }
 
 /// Path parameters for project-scoped routes.
@@ -61,7 +84,7 @@ pub struct ElementPath {
 // === Error Handling ===
 
 /// API error response.
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, serde::Serialize, schemars::JsonSchema)]
 pub struct ErrorResponse {
     error: String,
     code: String,
@@ -80,6 +103,17 @@ impl ErrorResponse {
         }
     }
 
+    /// Human-readable error message.
+    pub fn message(&self) -> &str {
+        &self.error
+    }
+
+    /// Attach structured details (e.g., diagnostics) to the error.
+    pub fn with_details(mut self, details: serde_json::Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+
     pub fn not_found(resource: &str, id: &str) -> (StatusCode, Json<Self>) {
         (
             StatusCode::NOT_FOUND,
@@ -129,10 +163,23 @@ impl IntoResponse for ErrorResponse {
     }
 }
 
+impl From<InvalidPathId> for ErrorResponse {
+    fn from(err: InvalidPathId) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "INVALID_ID", err.to_string())
+    }
+}
+
+impl From<InvalidPathId> for (StatusCode, Json<ErrorResponse>) {
+    fn from(err: InvalidPathId) -> Self {
+        (StatusCode::BAD_REQUEST, Json(err.into()))
+    }
+}
+
 impl From<StorageError> for (StatusCode, Json<ErrorResponse>) {
     fn from(err: StorageError) -> Self {
         match err {
             StorageError::NotFound(id) => ErrorResponse::not_found("Profile", &id),
+            StorageError::InvalidId(e) => e.into(),
             StorageError::ConcurrentModification(id) => (
                 StatusCode::CONFLICT,
                 Json(ErrorResponse::new(
@@ -146,6 +193,58 @@ impl From<StorageError> for (StatusCode, Json<ErrorResponse>) {
     }
 }
 
+// === Canonical URL Uniqueness ===
+
+/// Build a project index covering both project resources and stored profiles.
+///
+/// Profiles created through the profile API are tracked in the profile index,
+/// while project resources live in the project index; canonical URL checks
+/// need to see both.
+pub(super) async fn load_canonical_index(state: &AppState, project_id: &str) -> ProjectIndex {
+    let project_service = ProjectService::new(state.workspace_dir().clone());
+    let mut index = project_service.load_index(project_id).await.unwrap_or_default();
+
+    if let Ok(profiles) = state.storage().list_profiles(project_id).await {
+        for profile in profiles {
+            let metadata = &profile.metadata;
+            if index.get_resource(&metadata.id).is_none() {
+                index.add_resource(ProjectResource::new(
+                    &metadata.id,
+                    &metadata.url,
+                    &metadata.name,
+                    ResourceKind::Profile,
+                ));
+            }
+        }
+    }
+
+    index
+}
+
+/// Check whether an imported document is an Extension definition.
+///
+/// Follows the base canonical chain through the project's own resources, so
+/// extensions derived from custom extension profiles are recognized too.
+async fn is_extension_in_project(state: &AppState, project_id: &str, doc: &ProfileDocument) -> bool {
+    let index = load_canonical_index(state, project_id).await;
+    doc.resource.is_extension_with(|url| {
+        index
+            .find_by_canonical(url)
+            .and_then(|resource| resource.base.clone())
+    })
+}
+
+/// Check whether a document derives from Extension through its base chain.
+///
+/// Project resources are followed first; otherwise the base is resolved from
+/// installed packages, whose element tree is rooted at the base's type.
+async fn extends_extension(state: &AppState, project_id: &str, doc: &ProfileDocument) -> bool {
+    if is_extension_in_project(state, project_id, doc).await {
+        return true;
+    }
+    matches!(load_base_tree(state, doc).await, Ok(Some(tree)) if tree.path == "Extension")
+}
+
 // === Route Handlers ===
 
 /// GET /api/projects/:projectId/profiles
@@ -155,21 +254,13 @@ async fn list_profiles(
     Path(params): Path<ProjectPath>,
     Query(query): Query<ListProfilesQuery>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
-
-    // Initialize storage if needed
-    if let Err(e) = storage.init().await {
-        return ErrorResponse::internal_error(e.to_string()).into_response();
-    }
-
     // Load profiles
-    let profiles = match storage.list_profiles().await {
+    let profiles = match state.storage().list_profiles(&params.project_id).await {
         Ok(p) => p,
         Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
     };
 
-    // Apply FHIR version filter
+    // Apply FHIR version and modification time filters
     let filtered: Vec<_> = profiles
         .iter()
         .filter(|p| {
@@ -177,6 +268,7 @@ async fn list_profiles(
                 FhirVersion::from_str(v).is_some_and(|fv| fv == p.resource.fhir_version)
             })
         })
+        .filter(|p| query.modified_since.is_none_or(|since| p.modified_at >= since))
         .collect();
 
     // Pagination
@@ -206,43 +298,95 @@ async fn list_profiles(
     Json(ApiResponse::ok(response)).into_response()
 }
 
-/// POST /api/projects/:projectId/profiles
-/// Create a new profile.
-async fn create_profile(
-    State(state): State<AppState>,
-    Path(params): Path<ProjectPath>,
-    Json(req): Json<CreateProfileRequest>,
-) -> impl IntoResponse {
+/// Canonical base for a profile created without a URL.
+///
+/// Prefers the project's canonical base and falls back to the server's
+/// configured default when the project has none (or cannot be loaded).
+async fn default_canonical_base(state: &AppState, project_id: &str) -> String {
+    let project_service = ProjectService::new(state.workspace_dir().clone());
+    match project_service.load_project(project_id).await {
+        Ok(project) if !project.canonical_base.trim().is_empty() => project.canonical_base,
+        _ => state.config().default_canonical_base.clone(),
+    }
+}
+
+/// Validate a create-profile request and build the new document.
+async fn new_profile_document(
+    state: &AppState,
+    project_id: &str,
+    req: CreateProfileRequest,
+) -> Result<ProfileDocument, axum::response::Response> {
     // Validate FHIR version
     let fhir_version = match FhirVersion::from_str(&req.fhir_version) {
         Some(v) => v,
         None => {
-            return ErrorResponse::bad_request(format!(
+            return Err(ErrorResponse::bad_request(format!(
                 "Invalid FHIR version: {}. Valid values: R4, R4B, R5, R6",
                 req.fhir_version
             ))
-            .into_response()
+            .into_response())
         }
     };
 
+    let is_logical = req.kind == Some(StructureKind::Logical);
+
     // Validate resource type (basic check)
-    if req.resource_type.is_empty() {
-        return ErrorResponse::bad_request("Resource type is required").into_response();
+    if req.resource_type.is_empty() && !is_logical {
+        return Err(ErrorResponse::bad_request("Resource type is required").into_response());
     }
 
     // Validate name
     if req.name.is_empty() {
-        return ErrorResponse::bad_request("Profile name is required").into_response();
+        return Err(ErrorResponse::bad_request("Profile name is required").into_response());
     }
 
-    // Generate ID and URL
-    let profile_id = format!("{}-{}", req.name.to_lowercase(), Uuid::new_v4().to_string()[..8].to_string());
-    let url = req.url.unwrap_or_else(|| {
-        format!(
-            "http://example.org/fhir/StructureDefinition/{}",
-            req.name
+    let canonical_index = load_canonical_index(state, project_id).await;
+
+    // Use the requested ID, or derive a unique one from the name
+    let profile_id = match req.id {
+        Some(id) => {
+            if let Err(e) = validate_path_id("profile id", &id) {
+                return Err(ErrorResponse::from(e).into_response());
+            }
+            if canonical_index.get_resource(&id).is_some() {
+                return Err((
+                    StatusCode::CONFLICT,
+                    Json(ErrorResponse::new(
+                        StatusCode::CONFLICT,
+                        "DUPLICATE_ID",
+                        format!("Profile id '{}' is already used in this project", id),
+                    )),
+                )
+                    .into_response());
+            }
+            id
+        }
+        None => unique_profile_id(&req.name, |id| canonical_index.get_resource(id).is_some()),
+    };
+    let url = match req.url {
+        Some(url) => url,
+        None => {
+            let canonical_base = default_canonical_base(state, project_id).await;
+            format!(
+                "{}/StructureDefinition/{}",
+                canonical_base.trim_end_matches('/'),
+                req.name
+            )
+        }
+    };
+
+    // Reject canonical URLs already used in this project
+    if let Some(existing) = canonical_index.find_by_canonical(&url) {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new(
+                StatusCode::CONFLICT,
+                "DUPLICATE_CANONICAL_URL",
+                format!("Canonical URL '{}' is already used by resource '{}'", url, existing.id),
+            )),
         )
-    });
+            .into_response());
+    }
 
     // Create metadata
     let mut metadata = DocumentMetadata::new(&profile_id, &url, &req.name);
@@ -257,26 +401,91 @@ async fn create_profile(
     }
 
     // Create resource
-    let resource = ProfiledResource::new(
-        &url,
-        fhir_version,
-        BaseDefinition::resource(&req.resource_type),
-    );
-
-    // Create document
-    let doc = ProfileDocument::new(metadata, resource);
+    let resource = if is_logical {
+        ProfiledResource::logical(&url, fhir_version, &req.name)
+    } else if req.derivation == Some(Derivation::Specialization) {
+        ProfiledResource::specialization(&url, fhir_version, &req.resource_type, &req.name)
+    } else {
+        ProfiledResource::new(
+            &url,
+            fhir_version,
+            BaseDefinition::resource(&req.resource_type),
+        )
+    };
 
-    // Save to storage
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    Ok(ProfileDocument::new(metadata, resource))
+}
 
-    if let Err(e) = storage.init().await {
-        return ErrorResponse::internal_error(e.to_string()).into_response();
+/// POST /api/projects/:projectId/profiles
+/// Create a new profile.
+async fn create_profile(
+    State(state): State<AppState>,
+    Path(params): Path<ProjectPath>,
+    headers: HeaderMap,
+    Json(req): Json<CreateProfileRequest>,
+) -> impl IntoResponse {
+    // Reserve the Idempotency-Key before creating anything, so concurrent
+    // requests with the same key cannot both create a profile; a key that
+    // already created one replays it
+    let idempotency_key = headers
+        .get("idempotency-key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    if let Some(key) = &idempotency_key {
+        let mut reservation = state.reserve_idempotency_key(&params.project_id, key);
+        if let IdempotencyReservation::Completed(profile_id) = &reservation {
+            let replayed = replay_created_profile(&state, &params.project_id, profile_id).await;
+            if let Some(response) = replayed {
+                return response;
+            }
+            // The profile is gone; create it again under the same key
+            state.forget_idempotent_profile(&params.project_id, key, profile_id);
+            reservation = state.reserve_idempotency_key(&params.project_id, key);
+        }
+        if !matches!(reservation, IdempotencyReservation::Reserved) {
+            return (
+                StatusCode::CONFLICT,
+                Json(ErrorResponse::new(
+                    StatusCode::CONFLICT,
+                    "IDEMPOTENCY_KEY_IN_USE",
+                    format!(
+                        "A request with Idempotency-Key '{}' is still in progress",
+                        key
+                    ),
+                )),
+            )
+                .into_response();
+        }
     }
 
-    if let Err(e) = storage.save_profile(&doc).await {
+    let doc = match new_profile_document(&state, &params.project_id, req).await {
+        Ok(doc) => doc,
+        Err(response) => {
+            if let Some(key) = &idempotency_key {
+                state.release_idempotency_key(&params.project_id, key);
+            }
+            return response;
+        }
+    };
+
+    // Save to storage
+    if let Err(e) = state.storage().save_profile(&params.project_id, &doc).await {
+        if let Some(key) = &idempotency_key {
+            state.release_idempotency_key(&params.project_id, key);
+        }
         return ErrorResponse::internal_error(e.to_string()).into_response();
     }
+    if let Some(key) = &idempotency_key {
+        state.remember_idempotency_key(&params.project_id, key, &doc.metadata.id);
+    }
+    state.notify_operation_applied(
+        &params.project_id,
+        &doc.metadata.id,
+        "Create profile",
+        None,
+        doc.history.state(),
+    );
+    state.notify_profile_saved(&params.project_id, &doc.metadata.id);
 
     let hydrated = match hydrate_profile_document(&state, doc).await {
         Ok(d) => d,
@@ -286,28 +495,275 @@ async fn create_profile(
     (StatusCode::CREATED, Json(ApiResponse::ok(response))).into_response()
 }
 
+/// Derive a profile id from a name: its slug, with `-2`, `-3`, ... appended
+/// while the id is already taken.
+fn unique_profile_id(name: &str, is_taken: impl Fn(&str) -> bool) -> String {
+    let slug = slugify(name);
+    let base = if slug.is_empty() { "profile".to_string() } else { slug };
+
+    if !is_taken(&base) {
+        return base;
+    }
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|id| !is_taken(id))
+        .expect("unbounded suffix range")
+}
+
+/// Respond with a profile created by an earlier request, as `create_profile` did.
+///
+/// Returns `None` when the profile no longer exists, so a fresh one is created.
+async fn replay_created_profile(
+    state: &AppState,
+    project_id: &str,
+    profile_id: &str,
+) -> Option<axum::response::Response> {
+    let doc = state
+        .storage()
+        .load_profile(project_id, profile_id)
+        .await
+        .ok()?;
+    let response = match hydrate_profile_document(state, doc).await {
+        Ok(hydrated) => {
+            let response = ProfileDetailsResponse::from(&hydrated);
+            (StatusCode::CREATED, Json(ApiResponse::ok(response))).into_response()
+        }
+        Err(e) => e.into_response(),
+    };
+    Some(response)
+}
+
 /// GET /api/projects/:projectId/profiles/:profileId
 /// Get profile details.
 async fn get_profile(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
-    match storage.load_profile(&params.profile_id).await {
+    match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(doc) => {
             let doc = match hydrate_profile_document(&state, doc).await {
                 Ok(d) => d,
                 Err(e) => return e.into_response(),
             };
             let response = ProfileDetailsResponse::from(&doc);
-            Json(ApiResponse::ok(response)).into_response()
+            match storage
+                .profile_etag(&params.project_id, &params.profile_id)
+                .await
+            {
+                Ok(etag) => (
+                    [(header::ETAG, format!("\"{}\"", etag))],
+                    Json(ApiResponse::ok(response)),
+                )
+                    .into_response(),
+                Err(_) => Json(ApiResponse::ok(response)).into_response(),
+            }
         }
         Err(e) => Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
     }
 }
 
+/// GET /api/projects/:projectId/profiles/:profileId/stats
+/// Summarize a profile's complexity: differential statistics plus element
+/// and slice counts of the merged tree.
+async fn get_profile_stats(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> impl IntoResponse {
+    let doc = match state
+        .storage()
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(doc) => doc,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    let doc = match hydrate_profile_document(&state, doc).await {
+        Ok(d) => d,
+        Err(e) => return e.into_response(),
+    };
+
+    Json(ApiResponse::ok(ProfileStatsResponse::from(&doc))).into_response()
+}
+
+/// PUT /api/projects/:projectId/profiles/:profileId
+/// Replace a profile with a full IR document.
+///
+/// Honors `If-Match` against the profile's ETag. Renames go through the
+/// rename endpoint, so the body must keep the stored id and canonical URL.
+async fn replace_profile(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+    headers: HeaderMap,
+    Json(body): Json<serde_json::Value>,
+) -> impl IntoResponse {
+    let storage = state.storage();
+
+    let mut doc: ProfileDocument = match serde_json::from_value(body) {
+        Ok(doc) => doc,
+        Err(e) => {
+            return ErrorResponse::bad_request(format!("Invalid profile document: {}", e))
+                .into_response();
+        }
+    };
+    let existing = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    if let Err(message) = check_replace_consistency(&doc, &params.profile_id, &existing.metadata.url)
+    {
+        return ErrorResponse::validation_error(message).into_response();
+    }
+
+    doc.mark_dirty();
+    let if_match = headers.get(header::IF_MATCH).and_then(|v| v.to_str().ok());
+    if let Err(e) = storage
+        .replace_profile(&params.project_id, &doc, if_match)
+        .await
+    {
+        return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response();
+    }
+    state.notify_profile_saved(&params.project_id, &params.profile_id);
+
+    let etag = storage
+        .profile_etag(&params.project_id, &params.profile_id)
+        .await
+        .ok();
+    let hydrated = match hydrate_profile_document(&state, doc).await {
+        Ok(d) => d,
+        Err(e) => return e.into_response(),
+    };
+    let response = Json(ApiResponse::ok(ProfileDetailsResponse::from(&hydrated)));
+    match etag {
+        Some(etag) => ([(header::ETAG, format!("\"{}\"", etag))], response).into_response(),
+        None => response.into_response(),
+    }
+}
+
+/// Move the annotations, sources and index entry of a renamed profile and
+/// drop the document stored under its old id.
+///
+/// `original` is the profile as it was before the rename.
+async fn move_renamed_profile(
+    state: &AppState,
+    project_id: &str,
+    project_files: &ProfileStorage,
+    project_service: &ProjectService,
+    original: &ProfileDocument,
+    doc: &ProfileDocument,
+) -> Result<(), String> {
+    let old_id = original.metadata.id.as_str();
+    let new_id = doc.metadata.id.as_str();
+    if new_id != old_id {
+        project_files
+            .rename_annotations(old_id, new_id)
+            .await
+            .map_err(|e| format!("Failed to rename annotations: {}", e))?;
+        state
+            .storage()
+            .delete_profile(project_id, old_id)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    project_files
+        .rename_profile_sources(&original.metadata.name, doc)
+        .await
+        .map_err(|e| format!("Failed to rename source files: {}", e))?;
+    project_service
+        .rename_resource(
+            project_id,
+            old_id,
+            new_id,
+            &doc.metadata.name,
+            &doc.metadata.url,
+        )
+        .await
+        .map_err(|e| format!("Failed to update project index: {}", e))
+}
+
+/// Undo a rename whose file moves failed, restoring `originals`.
+///
+/// `originals` holds the documents as they were before the rename, the
+/// renamed profile last. Best effort: steps that fail are logged and the
+/// rest still run.
+async fn roll_back_rename(
+    state: &AppState,
+    project_id: &str,
+    project_files: &ProfileStorage,
+    originals: &[ProfileDocument],
+    doc: &ProfileDocument,
+) {
+    let Some(original) = originals.last() else {
+        return;
+    };
+    let old_id = original.metadata.id.as_str();
+    let new_id = doc.metadata.id.as_str();
+    if let Err(e) = project_files
+        .rename_profile_sources(&doc.metadata.name, original)
+        .await
+    {
+        tracing::warn!("Failed to restore source files of {}: {}", old_id, e);
+    }
+    if new_id != old_id {
+        if let Err(e) = project_files.rename_annotations(new_id, old_id).await {
+            tracing::warn!("Failed to restore annotations of {}: {}", old_id, e);
+        }
+        if let Err(e) = state.storage().delete_profile(project_id, new_id).await {
+            tracing::warn!("Failed to remove renamed profile {}: {}", new_id, e);
+        }
+    }
+    if let Err(e) = state.storage().save_profiles(project_id, originals).await {
+        tracing::error!("Failed to roll back rename of {}: {}", old_id, e);
+    }
+}
+
+/// Check that a replacement document matches the profile it replaces.
+///
+/// The id must match the path, the canonical URL must match the stored one
+/// (in both metadata and resource), and every differential path must be
+/// rooted at the profiled type.
+fn check_replace_consistency(
+    doc: &ProfileDocument,
+    profile_id: &str,
+    stored_url: &str,
+) -> Result<(), String> {
+    if doc.metadata.id != profile_id {
+        return Err(format!(
+            "Document id '{}' does not match profile id '{}'",
+            doc.metadata.id, profile_id
+        ));
+    }
+    if doc.metadata.url != stored_url || doc.resource.url != stored_url {
+        return Err(format!(
+            "Document url does not match the profile's canonical URL '{}'; use rename to change it",
+            stored_url
+        ));
+    }
+
+    let root = doc.resource.resource_type();
+    let prefix = format!("{}.", root);
+    if let Some(element) = doc
+        .resource
+        .differential
+        .iter()
+        .find(|e| e.path != root && !e.path.starts_with(&prefix))
+    {
+        return Err(format!(
+            "Differential path '{}' is not rooted at '{}'",
+            element.path, root
+        ));
+    }
+
+    Ok(())
+}
+
 /// GET /api/projects/:projectId/profiles/:profileId/input-it
 /// Get the original input StructureDefinition resource.
 async fn get_input_it(
@@ -316,11 +772,16 @@ async fn get_input_it(
 ) -> impl IntoResponse {
     use tokio::fs;
 
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
-
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
     // First load the profile to get its name
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match state
+        .storage()
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
     };
@@ -364,11 +825,17 @@ async fn delete_profile(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
 
     // Check if profile exists
-    let doc = match storage.load_profile(&params.profile_id).await {
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(StorageError::NotFound(_)) => {
             return ErrorResponse::not_found("Profile", &params.profile_id).into_response()
@@ -376,9 +843,15 @@ async fn delete_profile(
         Err(e) => return ErrorResponse::internal_error(e.to_string()).into_response(),
     };
 
-    // Delete all associated files
+    // Delete the profile and its source files
     if let Err(e) = storage
-        .delete_profile_files(&params.profile_id, &doc.metadata.name)
+        .delete_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        return ErrorResponse::internal_error(e.to_string()).into_response();
+    }
+    if let Err(e) = ProfileStorage::new(&project_dir)
+        .delete_profile_sources(&doc.metadata.name)
         .await
     {
         return ErrorResponse::internal_error(e.to_string()).into_response();
@@ -394,11 +867,13 @@ async fn update_metadata(
     Path(params): Path<ProfilePath>,
     Json(req): Json<UpdateMetadataRequest>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load existing profile
-    let mut doc = match storage.load_profile(&params.profile_id).await {
+    let mut doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
     };
@@ -435,13 +910,47 @@ async fn update_metadata(
     if let Some(experimental) = req.experimental {
         doc.metadata.experimental = experimental;
     }
+    if let Some(editing_mode) = req.editing_mode {
+        doc.editing_mode = editing_mode;
+    }
+    if let Some(is_abstract) = req.is_abstract {
+        let op = SetAbstract::new(is_abstract).with_previous(doc.resource.is_abstract);
+        if let Err(e) = apply_operation(&mut doc, &op) {
+            return ErrorResponse::bad_request(e.to_string()).into_response();
+        }
+        state.notify_operation_applied(
+            &params.project_id,
+            &params.profile_id,
+            op.description(),
+            None,
+            doc.history.state(),
+        );
+    }
+    if let Some(context) = req.context {
+        let mut op = SetExtensionContext::new(context).with_previous(doc.resource.context.clone());
+        if !doc.resource.is_extension() && extends_extension(&state, &params.project_id, &doc).await
+        {
+            op = op.derived_from_extension();
+        }
+        if let Err(e) = apply_operation(&mut doc, &op) {
+            return ErrorResponse::bad_request(e.to_string()).into_response();
+        }
+        state.notify_operation_applied(
+            &params.project_id,
+            &params.profile_id,
+            op.description(),
+            None,
+            doc.history.state(),
+        );
+    }
 
     doc.mark_dirty();
 
     // Save updated profile
-    if let Err(e) = storage.save_profile(&doc).await {
+    if let Err(e) = storage.save_profile(&params.project_id, &doc).await {
         return ErrorResponse::internal_error(e.to_string()).into_response();
     }
+    state.notify_profile_saved(&params.project_id, &params.profile_id);
 
     let hydrated = match hydrate_profile_document(&state, doc).await {
         Ok(d) => d,
@@ -451,21 +960,195 @@ async fn update_metadata(
     Json(ApiResponse::ok(response)).into_response()
 }
 
+/// POST /api/projects/:projectId/profiles/:profileId/rename
+/// Rename a profile's id, name and/or canonical URL.
+///
+/// Project profiles that reference the old canonical URL (as base, via a
+/// type profile or as an extension context) are rewritten to the new one.
+/// The renamed profile and its rewritten dependents are saved as one
+/// transaction; if moving its annotations, sources or index entry fails
+/// afterwards, the whole rename is rolled back.
+async fn rename_profile(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+    Json(req): Json<RenameProfileRequest>,
+) -> impl IntoResponse {
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
+    let storage = state.storage();
+    let project_files = ProfileStorage::new(&project_dir);
+    let project_service = ProjectService::new(state.workspace_dir().clone());
+
+    let mut doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+
+    let old_id = doc.metadata.id.clone();
+    let old_name = doc.metadata.name.clone();
+    let old_url = doc.metadata.url.clone();
+    let new_id = req.id.unwrap_or_else(|| old_id.clone());
+    let new_name = req.name.unwrap_or_else(|| old_name.clone());
+    let new_url = req.url.unwrap_or_else(|| old_url.clone());
+
+    if new_id.is_empty() || new_name.is_empty() || new_url.is_empty() {
+        return ErrorResponse::bad_request("Profile id, name and url must not be empty")
+            .into_response();
+    }
+    if new_id == old_id && new_name == old_name && new_url == old_url {
+        return ErrorResponse::bad_request("Nothing to rename").into_response();
+    }
+    let id_taken = match storage.profile_exists(&params.project_id, &new_id).await {
+        Ok(exists) => new_id != old_id && exists,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    if id_taken {
+        return ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "PROFILE_EXISTS",
+            format!("Profile '{}' already exists", new_id),
+        )
+        .into_response();
+    }
+    if new_url != old_url {
+        let canonical_index = load_canonical_index(&state, &params.project_id).await;
+        if let Some(existing) = canonical_index.find_by_canonical(&new_url) {
+            return ErrorResponse::new(
+                StatusCode::CONFLICT,
+                "DUPLICATE_CANONICAL_URL",
+                format!(
+                    "Canonical URL '{}' is already used by resource '{}'",
+                    new_url, existing.id
+                ),
+            )
+            .into_response();
+        }
+    }
+
+    // Find dependents before anything moves
+    let profiles = match storage.list_profiles(&params.project_id).await {
+        Ok(p) => p,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    let mut graph = DependencyGraph::new();
+    for profile in &profiles {
+        for dep in profile.resource.referenced_canonicals() {
+            let dep = dep.split('|').next().unwrap_or(dep);
+            if dep != profile.metadata.url {
+                graph.add_dependency(&profile.metadata.url, dep);
+            }
+        }
+    }
+    let dependent_urls: std::collections::HashSet<String> = graph
+        .dependents_of(&old_url)
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+
+    // Rewrite references in dependents, keeping the originals for a rollback
+    let mut dependents = Vec::new();
+    let mut originals = Vec::new();
+    if new_url != old_url {
+        for mut dependent in profiles {
+            if dependent.metadata.id == old_id || !dependent_urls.contains(&dependent.metadata.url)
+            {
+                continue;
+            }
+            let original = dependent.clone();
+            if dependent
+                .resource
+                .rewrite_canonical_references(&old_url, &new_url)
+                == 0
+            {
+                continue;
+            }
+            dependent.mark_dirty();
+            dependents.push(dependent);
+            originals.push(original);
+        }
+    }
+    let updated_dependents: Vec<String> =
+        dependents.iter().map(|d| d.metadata.id.clone()).collect();
+
+    // Rename the document, saving it together with its dependents
+    let original = doc.clone();
+    doc.metadata.id = new_id.clone();
+    doc.metadata.name = new_name.clone();
+    doc.metadata.url = new_url.clone();
+    doc.resource.url = new_url.clone();
+    doc.mark_dirty();
+    dependents.push(doc.clone());
+
+    if let Err(e) = storage.save_profiles(&params.project_id, &dependents).await {
+        return ErrorResponse::internal_error(e.to_string()).into_response();
+    }
+
+    // Move the files kept next to the profile, undoing everything on failure
+    let lock = state.annotation_lock(&params.project_id, &old_id);
+    let _guard = lock.lock().await;
+    let moved = move_renamed_profile(
+        &state,
+        &params.project_id,
+        &project_files,
+        &project_service,
+        &original,
+        &doc,
+    )
+    .await;
+    if let Err(message) = moved {
+        originals.push(original);
+        roll_back_rename(&state, &params.project_id, &project_files, &originals, &doc).await;
+        return ErrorResponse::internal_error(message).into_response();
+    }
+    state.notify_operation_applied(
+        &params.project_id,
+        &new_id,
+        format!("Rename profile {}", old_id),
+        None,
+        doc.history.state(),
+    );
+    for id in updated_dependents.iter().chain(std::iter::once(&new_id)) {
+        state.notify_profile_saved(&params.project_id, id);
+    }
+
+    let hydrated = match hydrate_profile_document(&state, doc).await {
+        Ok(d) => d,
+        Err(e) => return e.into_response(),
+    };
+    let response = RenameProfileResponse {
+        profile: ProfileDetailsResponse::from(&hydrated),
+        updated_dependents,
+    };
+    Json(ApiResponse::ok(response)).into_response()
+}
+
 /// PATCH /api/projects/:projectId/profiles/:profileId/elements/:path
 /// Update an element's constraints.
+///
+/// With `?recursive=true`, `flags.mustSupport` is applied to the element and
+/// every descendant (children and slices).
 async fn update_element(
     State(state): State<AppState>,
     Path(params): Path<ElementPath>,
-    Json(req): Json<UpdateElementRequest>,
+    Query(query): Query<UpdateElementQuery>,
+    Json(mut req): Json<UpdateElementRequest>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load existing profile
-    let mut doc = match storage.load_profile(&params.profile_id).await {
+    let mut doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(d) => d,
         Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
     };
+    let snapshot_mode = query.mode.unwrap_or(doc.editing_mode) == EditingMode::Snapshot;
     let mut doc = match hydrate_profile_document(&state, doc).await {
         Ok(d) => d,
         Err(e) => return e.into_response(),
@@ -473,18 +1156,105 @@ async fn update_element(
 
     // Find or create element at path
     let element_path = params.path.trim_start_matches('/');
+    let recursive_must_support = req
+        .flags
+        .as_ref()
+        .and_then(|flags| flags.must_support)
+        .filter(|_| query.recursive);
+
+    let example_updates = req.examples.take().unwrap_or_default();
+    let example_removals = req.remove_examples.take().unwrap_or_default();
+    let slicing_update = req.slicing.take();
 
     // Apply constraint updates and collect diagnostics
-    let (constraints, diagnostics) =
-        apply_element_updates(&mut doc.resource.root, element_path, req);
+    let mut referenced_canonicals = Vec::new();
+    let (mut constraints, diagnostics) = match apply_element_updates(
+        &mut doc,
+        element_path,
+        req,
+        recursive_must_support.is_some(),
+        &mut referenced_canonicals,
+    ) {
+        Ok(updated) => updated,
+        Err(e) => return e.into_response(),
+    };
+
+    if !example_updates.is_empty() || !example_removals.is_empty() {
+        for label in example_removals {
+            let op = RemoveExample::new(element_path, label);
+            if let Err(e) = apply_operation(&mut doc, &op) {
+                return ErrorResponse::bad_request(e.to_string()).into_response();
+            }
+        }
+        for update in example_updates {
+            let mut op = SetExample::new(element_path, update.label, update.value);
+            if let Some(value_type) = update.value_type {
+                op = op.with_value_type(value_type);
+            }
+            if let Err(e) = apply_operation(&mut doc, &op) {
+                return ErrorResponse::bad_request(e.to_string()).into_response();
+            }
+        }
+        if let Some(element) = doc.resource.find_element(element_path) {
+            constraints.examples = element.constraints.examples.clone();
+        }
+    }
+
+    if let Some(update) = slicing_update {
+        if let Err(e) = apply_slicing_update(&mut doc, element_path, update) {
+            return e.into_response();
+        }
+    }
+
+    if let Some(value) = recursive_must_support {
+        let op = SetMustSupportRecursive::new(element_path, value);
+        if let Err(e) = apply_operation(&mut doc, &op) {
+            return ErrorResponse::bad_request(e.to_string()).into_response();
+        }
+        if let Some(element) = doc.resource.find_element(element_path) {
+            constraints = element.constraints.clone();
+        }
+    }
+
+    // Specializations and logical models have no base tree: every element,
+    // including intermediates created for a new path, is defined by the type itself
+    if doc.resource.is_specialization() {
+        mark_inherited_as_added(&mut doc.resource.root);
+    }
 
     // Mark document as modified
     doc.mark_dirty();
 
-    // Save updated profile
-    if let Err(e) = storage.save_profile(&doc).await {
+    // Snapshot edits store only what differs from the base
+    if snapshot_mode {
+        if let Err(e) = minimize_to_base(&state, &mut doc).await {
+            return e.into_response();
+        }
+    }
+
+    // Save updated profile (the stored differential is derived from the tree)
+    if let Err(e) = storage.save_profile(&params.project_id, &doc).await {
         return ErrorResponse::internal_error(e.to_string()).into_response();
     }
+    state.notify_operation_applied(
+        &params.project_id,
+        &params.profile_id,
+        format!("Update element {}", element_path),
+        Some(element_path.to_string()),
+        doc.history.state(),
+    );
+    state.notify_profile_saved(&params.project_id, &params.profile_id);
+
+    // The quick-pick list is best effort; the edit itself already succeeded
+    let project_service = ProjectService::new(state.workspace_dir().clone());
+    let lock = state.recent_canonicals_lock(&params.project_id);
+    let _guard = lock.lock().await;
+    if let Err(e) = project_service
+        .record_recent_canonicals(&params.project_id, &referenced_canonicals)
+        .await
+    {
+        tracing::warn!("Failed to record recent canonicals: {}", e);
+    }
 
     let response = UpdateElementResponse {
         path: element_path.to_string(),
@@ -495,51 +1265,146 @@ async fn update_element(
     Json(ApiResponse::ok(response)).into_response()
 }
 
+/// DELETE /api/projects/:projectId/profiles/:profileId/elements/:path
+/// Remove an element added by this profile, with its subtree.
+async fn remove_element(
+    State(state): State<AppState>,
+    Path(params): Path<ElementPath>,
+) -> impl IntoResponse {
+    if let Some((element_path, annotation_id)) = annotation_target(&params.path) {
+        return delete_annotation(&state, &params, element_path, annotation_id).await;
+    }
+
+    let storage = state.storage();
+
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(d) => d,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    let mut doc = match hydrate_profile_document(&state, doc).await {
+        Ok(d) => d,
+        Err(e) => return e.into_response(),
+    };
+
+    let element_path = params.path.trim_start_matches('/');
+    let op = RemoveElement::new(element_path);
+    match apply_operation(&mut doc, &op) {
+        Ok(()) => {}
+        Err(OperationError::ElementNotFound { path }) => {
+            return ErrorResponse::not_found("Element", &path).into_response();
+        }
+        Err(e @ OperationError::CannotRemoveElement { .. }) => {
+            return ErrorResponse::new(StatusCode::CONFLICT, "CANNOT_REMOVE_ELEMENT", e.to_string())
+                .into_response();
+        }
+        Err(e) => return ErrorResponse::bad_request(e.to_string()).into_response(),
+    }
+
+    if let Err(e) = storage.save_profile(&params.project_id, &doc).await {
+        return ErrorResponse::internal_error(e.to_string()).into_response();
+    }
+    state.notify_operation_applied(
+        &params.project_id,
+        &params.profile_id,
+        op.description(),
+        Some(element_path.to_string()),
+        doc.history.state(),
+    );
+    state.notify_profile_saved(&params.project_id, &params.profile_id);
+
+    let response = RemoveElementResponse {
+        path: element_path.to_string(),
+    };
+    Json(ApiResponse::ok(response)).into_response()
+}
+
+/// Apply slicing changes through the slicing operations.
+fn apply_slicing_update(
+    doc: &mut ProfileDocument,
+    element_path: &str,
+    update: SlicingUpdate,
+) -> Result<(), (StatusCode, Json<ErrorResponse>)> {
+    for d in update.remove_discriminators.unwrap_or_default() {
+        let op = RemoveDiscriminator::new(element_path, d.discriminator_type, d.path);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    for d in update.add_discriminators.unwrap_or_default() {
+        let op = AddDiscriminator::new(element_path, d.discriminator_type, d.path);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    if let Some(rules) = update.rules {
+        let rules = match rules.as_str() {
+            "open" => SlicingRules::Open,
+            "closed" => SlicingRules::Closed,
+            "openAtEnd" => SlicingRules::OpenAtEnd,
+            other => {
+                return Err(ErrorResponse::bad_request(format!(
+                    "Invalid slicing rules: {}. Valid values: open, closed, openAtEnd",
+                    other
+                )));
+            }
+        };
+        let op = SetSlicingRules::new(element_path, rules);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    if let Some(description) = update.description {
+        let description = Some(description).filter(|d| !d.trim().is_empty());
+        let op = SetSlicingDescription::new(element_path, description);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    Ok(())
+}
+
 /// Apply updates to an element and return the updated constraints.
+///
+/// Each change runs through its typed operation via [`apply_operation`], so
+/// it is validated and recorded in the edit history. An operation that fails
+/// validation aborts the update with a `400`. `flags.mustSupport` is skipped
+/// when `recursive_must_support` will apply it to the whole subtree. The
+/// canonicals the applied operations reference are added to `references`.
 fn apply_element_updates(
-    root: &mut ElementNode,
+    doc: &mut ProfileDocument,
     element_path: &str,
     req: UpdateElementRequest,
-) -> (crate::ir::ElementConstraints, Vec<Diagnostic>) {
-    let element = find_or_create_element(root, element_path);
+    recursive_must_support: bool,
+    references: &mut Vec<(CanonicalUsage, String)>,
+) -> Result<(crate::ir::ElementConstraints, Vec<Diagnostic>), (StatusCode, Json<ErrorResponse>)> {
+    // Operations need an existing element; a new path gets its intermediates
+    find_or_create_element(&mut doc.resource.root, element_path)?;
     let mut diagnostics = Vec::new();
 
     if let Some(cardinality) = req.cardinality {
         let min = cardinality.min.unwrap_or(0);
         let max = cardinality.max.map(|m| m.to_option()).unwrap_or(None);
-
-        // Validate cardinality
-        if let Some(max_val) = max {
-            if min > max_val {
-                diagnostics.push(Diagnostic {
-                    severity: DiagnosticSeverity::Error,
-                    code: "INVALID_CARDINALITY".to_string(),
-                    message: format!("min ({}) cannot be greater than max ({})", min, max_val),
-                    path: Some(element_path.to_string()),
-                });
-            }
-        }
-
-        element.constraints.cardinality = Some(Cardinality { min, max });
+        let op = SetCardinality::new(element_path, min, max);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
     }
 
     if let Some(flags) = req.flags {
-        if let Some(must_support) = flags.must_support {
-            element.constraints.flags.must_support = must_support;
-        }
-        if let Some(is_modifier) = flags.is_modifier {
-            element.constraints.flags.is_modifier = is_modifier;
+        if let Some(must_support) = flags.must_support.filter(|_| !recursive_must_support) {
+            let op = SetMustSupport::new(element_path, must_support);
+            apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
         }
-        if let Some(reason) = flags.is_modifier_reason {
-            element.constraints.flags.is_modifier_reason = Some(reason);
+        if flags.is_modifier.is_some() || flags.is_modifier_reason.is_some() {
+            let current = &element_constraints(doc, element_path)?.flags;
+            let is_modifier = flags.is_modifier.unwrap_or(current.is_modifier);
+            let reason = flags
+                .is_modifier_reason
+                .or_else(|| current.is_modifier_reason.clone());
+            let op = SetIsModifier::new(element_path, is_modifier, reason);
+            apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
         }
         if let Some(is_summary) = flags.is_summary {
-            element.constraints.flags.is_summary = is_summary;
+            let op = SetIsSummary::new(element_path, is_summary);
+            apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
         }
     }
 
     if let Some(types) = req.types {
-        element.constraints.types = types
+        let types = types
             .into_iter()
             .map(|t| {
                 let mut tc = TypeConstraint::simple(&t.code);
@@ -552,6 +1417,9 @@ fn apply_element_updates(
                 tc
             })
             .collect();
+        let op = SetTypeConstraints::new(element_path, types);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+        references.extend(op.referenced_canonicals());
     }
 
     if let Some(binding) = req.binding {
@@ -561,39 +1429,99 @@ fn apply_element_updates(
             "preferred" => BindingStrength::Preferred,
             _ => BindingStrength::Example,
         };
-
-        element.constraints.binding = Some(Binding {
-            strength,
-            value_set: binding.value_set,
-            description: binding.description,
-        });
+        let mut op = SetBinding::new(element_path, binding.value_set, strength);
+        if let Some(description) = binding.description {
+            op = op.with_description(description);
+        }
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+        references.extend(op.referenced_canonicals());
     }
 
     if let Some(short) = req.short {
-        element.constraints.short = Some(short);
+        let op = SetShort::new(element_path, short);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
     }
     if let Some(definition) = req.definition {
-        element.constraints.definition = Some(definition);
+        let op = SetDefinition::new(element_path, definition);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
     }
     if let Some(comment) = req.comment {
-        element.constraints.comment = Some(comment);
+        let op = SetComment::new(element_path, comment);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    if let Some(default_value) = req.default_value {
+        let op = SetDefaultValue::new(element_path, default_value);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+    }
+    if let Some(meaning) = req.meaning_when_missing {
+        let min = element_constraints(doc, element_path)?
+            .cardinality
+            .as_ref()
+            .map_or(0, |c| c.min);
+        if min > 0 {
+            diagnostics.push(Diagnostic {
+                severity: DiagnosticSeverity::Error,
+                code: "MEANING_WHEN_MISSING_ON_REQUIRED".to_string(),
+                message: format!(
+                    "meaningWhenMissing is only allowed when min cardinality is 0 (min is {})",
+                    min
+                ),
+                path: Some(element_path.to_string()),
+                ..Default::default()
+            });
+        } else {
+            let op = SetMeaningWhenMissing::new(element_path, meaning);
+            apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
+        }
+    }
+    if let Some(must_have_value) = req.must_have_value {
+        let op = SetMustHaveValue::new(element_path, must_have_value);
+        apply_operation(doc, &op).map_err(|e| ErrorResponse::bad_request(e.to_string()))?;
     }
 
     // Mark element as modified
+    let element = find_or_create_element(&mut doc.resource.root, element_path)?;
     element.source = crate::ir::ElementSource::Modified;
 
-    (element.constraints.clone(), diagnostics)
+    Ok((element.constraints.clone(), diagnostics))
+}
+
+/// Constraints of an element that [`find_or_create_element`] already created.
+fn element_constraints<'a>(
+    doc: &'a ProfileDocument,
+    element_path: &str,
+) -> Result<&'a crate::ir::ElementConstraints, (StatusCode, Json<ErrorResponse>)> {
+    doc.resource
+        .find_element(element_path)
+        .map(|element| &element.constraints)
+        .ok_or_else(|| ErrorResponse::not_found("Element", element_path))
+}
+
+/// Mark every inherited descendant of `root` as added.
+fn mark_inherited_as_added(root: &mut ElementNode) {
+    for child in &mut root.children {
+        if child.source == crate::ir::ElementSource::Inherited {
+            child.source = crate::ir::ElementSource::Added;
+        }
+        mark_inherited_as_added(child);
+    }
 }
 
 /// Find or create an element at the given path.
-fn find_or_create_element<'a>(root: &'a mut ElementNode, path: &str) -> &'a mut ElementNode {
+///
+/// Missing child elements are created, but slices are not: a path naming an
+/// unknown slice (e.g. a mistyped `identifier:mnr`) is a `404`.
+fn find_or_create_element<'a>(
+    root: &'a mut ElementNode,
+    path: &str,
+) -> Result<&'a mut ElementNode, (StatusCode, Json<ErrorResponse>)> {
     // Split path into segments
     let segments: Vec<&str> = path.split('.').collect();
 
     if segments.is_empty() || segments[0] == root.short_name() {
         // Path starts with root, navigate from there
         if segments.len() <= 1 {
-            return root;
+            return Ok(root);
         }
         navigate_to_element(root, &segments[1..])
     } else {
@@ -602,14 +1530,27 @@ fn find_or_create_element<'a>(root: &'a mut ElementNode, path: &str) -> &'a mut
     }
 }
 
-fn navigate_to_element<'a>(current: &'a mut ElementNode, segments: &[&str]) -> &'a mut ElementNode {
+fn navigate_to_element<'a>(
+    current: &'a mut ElementNode,
+    segments: &[&str],
+) -> Result<&'a mut ElementNode, (StatusCode, Json<ErrorResponse>)> {
     if segments.is_empty() {
-        return current;
+        return Ok(current);
     }
 
     let segment = segments[0];
     let remaining = &segments[1..];
 
+    // `identifier:mrn` targets the slice entry, not the sliced element
+    if let Some((name, slice_name)) = segment.split_once(':') {
+        let sliced = navigate_to_element(current, &[name])?;
+        let slice_path = format!("{}:{}", sliced.path, slice_name);
+        return match sliced.slices.get_mut(slice_name) {
+            Some(slice) => navigate_to_element(&mut slice.element, remaining),
+            None => Err(ErrorResponse::not_found("Slice", &slice_path)),
+        };
+    }
+
     // Find existing child or create new
     let child_idx = current
         .children
@@ -633,27 +1574,34 @@ fn navigate_to_element<'a>(current: &'a mut ElementNode, segments: &[&str]) -> &
 async fn import_profile(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
+    Query(query): Query<ImportQuery>,
     Json(req): Json<ImportProfileRequest>,
 ) -> impl IntoResponse {
-    use crate::project::{ProjectService, ResourceKind, SourceFormat};
+    use crate::project::SourceFormat;
     use tokio::fs;
-    use tokio::io::AsyncWriteExt;
 
-    let project_dir = state.project_path(&params.project_id);
+    let project_dir = match state.project_path(&params.project_id) {
+        Ok(dir) => dir,
+        Err(e) => return ErrorResponse::from(e).into_response(),
+    };
     let project_service = ProjectService::new(state.workspace_dir().clone());
     let storage = ProfileStorage::new(&project_dir);
 
-    // Ensure directories exist
-    let ir_resources_dir = project_dir.join("IR").join("resources");
     let sd_dir = project_dir.join("SD").join("StructureDefinition");
-    if let Err(e) = fs::create_dir_all(&ir_resources_dir).await {
-        return ErrorResponse::internal_error(format!("Failed to create IR directory: {}", e)).into_response();
-    }
-    if let Err(e) = fs::create_dir_all(&sd_dir).await {
-        return ErrorResponse::internal_error(format!("Failed to create SD directory: {}", e)).into_response();
-    }
-    if let Err(e) = storage.init().await {
-        return ErrorResponse::internal_error(e.to_string()).into_response();
+
+    // Dry runs import and hydrate only; nothing is written or indexed
+    if !query.dry_run {
+        // Ensure directories exist
+        let ir_resources_dir = project_dir.join("IR").join("resources");
+        if let Err(e) = fs::create_dir_all(&ir_resources_dir).await {
+            return ErrorResponse::internal_error(format!("Failed to create IR directory: {}", e)).into_response();
+        }
+        if let Err(e) = fs::create_dir_all(&sd_dir).await {
+            return ErrorResponse::internal_error(format!("Failed to create SD directory: {}", e)).into_response();
+        }
+        if let Err(e) = storage.init().await {
+            return ErrorResponse::internal_error(e.to_string()).into_response();
+        }
     }
 
     let mut diagnostics = Vec::new();
@@ -662,64 +1610,74 @@ async fn import_profile(
         ImportFormat::Json => {
             // Import using the import module
             let importer = crate::import::StructureDefinitionImporter::new();
-            match importer.import_json(&req.content).await {
-                Ok(imported_doc) => {
+            match importer.import_json_with_warnings(&req.content).await {
+                Ok(imported) => {
+                    diagnostics.extend(imported.warnings.iter().map(Diagnostic::from));
                     // Use imported document directly (no merge with existing for now)
-                    let doc = imported_doc;
-
-                    // Save raw SD JSON to SD folder
-                    let sd_path = sd_dir.join(format!("{}.json", doc.metadata.name));
-                    if let Err(e) = fs::write(&sd_path, &req.content).await {
-                        diagnostics.push(Diagnostic {
-                            severity: DiagnosticSeverity::Warning,
-                            code: "SAVE_SOURCE_FAILED".to_string(),
-                            message: format!("Failed to save source file: {}", e),
-                            path: None,
-                        });
-                    }
-
-                    // Save profile document to IR/resources (differential-only)
-                    if let Err(e) = storage.save_profile(&doc).await {
-                        return ErrorResponse::internal_error(format!("Failed to save profile: {}", e)).into_response();
-                    }
-
-                    // Register in project index for tree visibility
-                    let resource_kind = match doc.resource.kind {
-                        crate::ir::StructureKind::Resource | crate::ir::StructureKind::ComplexType => {
-                            // Check if it's an extension based on base definition
-                            if doc.resource.base.url.contains("Extension") {
-                                ResourceKind::Extension
-                            } else {
-                                ResourceKind::Profile
-                            }
+                    let doc = imported.value;
+
+                    if !query.dry_run {
+                        // Save the profile document to IR/resources (differential-only)
+                        // and the raw SD JSON to the SD folder in one transaction
+                        if let Err(e) = state
+                            .storage()
+                            .save_profile_with_sources(
+                                &params.project_id,
+                                &doc,
+                                Some(&req.content),
+                                None,
+                            )
+                            .await
+                        {
+                            return ErrorResponse::internal_error(format!("Failed to save profile: {}", e)).into_response();
                         }
-                        crate::ir::StructureKind::Logical => ResourceKind::Profile,
-                        crate::ir::StructureKind::PrimitiveType => ResourceKind::Profile,
-                    };
-
-                    let add_request = crate::project::AddResourceRequest {
-                        id: Some(doc.metadata.id.clone()),
-                        name: doc.metadata.name.clone(),
-                        kind: resource_kind,
-                        canonical_url: Some(doc.metadata.url.clone()),
-                        base: Some(doc.resource.base.url.clone()),
-                        source_format: Some(SourceFormat::Sd),
-                        description: doc.metadata.description.clone(),
-                        context: None,
-                        purpose: None,
-                        content: Some(req.content.clone()),
-                    };
-
-                    // Try to add to project index, but don't fail if it already exists
-                    if let Err(e) = project_service.add_resource(&params.project_id, add_request).await {
-                        // If resource already exists, try to update it instead
-                        if !e.to_string().contains("already exists") {
-                            diagnostics.push(Diagnostic {
-                                severity: DiagnosticSeverity::Warning,
-                                code: "INDEX_UPDATE_FAILED".to_string(),
-                                message: format!("Failed to update project index: {}", e),
-                                path: None,
-                            });
+                        state.notify_operation_applied(
+                            &params.project_id,
+                            &doc.metadata.id,
+                            "Import profile from JSON",
+                            None,
+                            doc.history.state(),
+                        );
+                        state.notify_profile_saved(&params.project_id, &doc.metadata.id);
+
+                        // Register in project index for tree visibility
+                        let resource_kind = match doc.resource.kind {
+                            crate::ir::StructureKind::Resource | crate::ir::StructureKind::ComplexType => {
+                                if is_extension_in_project(&state, &params.project_id, &doc).await {
+                                    ResourceKind::Extension
+                                } else {
+                                    ResourceKind::Profile
+                                }
+                            }
+                            crate::ir::StructureKind::Logical => ResourceKind::Profile,
+                            crate::ir::StructureKind::PrimitiveType => ResourceKind::Profile,
+                        };
+
+                        let add_request = crate::project::AddResourceRequest {
+                            id: Some(doc.metadata.id.clone()),
+                            name: doc.metadata.name.clone(),
+                            kind: resource_kind,
+                            canonical_url: Some(doc.metadata.url.clone()),
+                            base: Some(doc.resource.base.url.clone()),
+                            source_format: Some(SourceFormat::Sd),
+                            description: doc.metadata.description.clone(),
+                            context: None,
+                            purpose: None,
+                            content: Some(req.content.clone()),
+                        };
+
+                        // Try to add to project index, but don't fail if it already exists
+                        if let Err(e) = project_service.add_resource(&params.project_id, add_request).await {
+                            // If resource already exists, try to update it instead
+                            if !e.to_string().contains("already exists") {
+                                diagnostics.push(Diagnostic {
+                                    severity: DiagnosticSeverity::Warning,
+                                    code: "INDEX_UPDATE_FAILED".to_string(),
+                                    message: format!("Failed to update project index: {}", e),
+                                    path: None,
+                                    ..Default::default()
+                                });
+                            }
                         }
                     }
 
@@ -731,6 +1689,8 @@ async fn import_profile(
                     let response = ImportResponse {
                         profile: ProfileDetailsResponse::from(&hydrated),
                         diagnostics,
+                        instances: Vec::new(),
+                        dry_run: query.dry_run,
                     };
 
                     Json(ApiResponse::ok(response)).into_response()
@@ -741,7 +1701,7 @@ async fn import_profile(
         }
         ImportFormat::Fsh => {
             // Import FSH using the fsh module
-            use crate::fsh::{FshImporter, FshImportOptions};
+            use crate::fsh::{FshImportOutput, FshImporter, FshImportOptions};
             use crate::ir::FhirVersion;
 
             // Load project for canonical URL and FHIR version
@@ -768,19 +1728,13 @@ async fn import_profile(
             };
 
             let temp_path = std::path::PathBuf::from(&params.profile_id).with_extension("fsh");
-            match importer.import_content(&req.content, &temp_path).await {
+            match importer.import_content_with_instances(&req.content, &temp_path).await {
                 Ok(result) => {
                     // Convert FSH warnings to API diagnostics
-                    for warning in &result.warnings {
-                        diagnostics.push(Diagnostic {
-                            severity: DiagnosticSeverity::Warning,
-                            code: format!("{:?}", warning.code),
-                            message: warning.message.clone(),
-                            path: warning.file.as_ref().map(|p| p.display().to_string()),
-                        });
-                    }
+                    diagnostics.extend(result.warnings.iter().map(Diagnostic::from));
 
-                    if result.value.is_empty() {
+                    let FshImportOutput { profiles, instances } = result.value;
+                    if profiles.is_empty() {
                         return ErrorResponse::validation_error(
                             "No profiles found in FSH content",
                         )
@@ -788,68 +1742,121 @@ async fn import_profile(
                     }
 
                     // Use the first imported profile
-                    let doc = result.value.into_iter().next().unwrap();
-
-                    // Save FSH source file
-                    let fsh_dir = project_dir.join("FSH");
-                    if let Err(e) = fs::create_dir_all(&fsh_dir).await {
-                        diagnostics.push(Diagnostic {
-                            severity: DiagnosticSeverity::Warning,
-                            code: "SAVE_SOURCE_FAILED".to_string(),
-                            message: format!("Failed to create FSH directory: {}", e),
-                            path: None,
-                        });
-                    } else {
-                        let fsh_path = fsh_dir.join(format!("{}.fsh", doc.metadata.name));
-                        if let Err(e) = fs::write(&fsh_path, &req.content).await {
-                            diagnostics.push(Diagnostic {
-                                severity: DiagnosticSeverity::Warning,
-                                code: "SAVE_SOURCE_FAILED".to_string(),
-                                message: format!("Failed to save FSH source: {}", e),
-                                path: None,
-                            });
+                    let doc = profiles.into_iter().next().unwrap();
+
+                    if !query.dry_run {
+                        // Save the profile document to IR/resources (differential-only)
+                        // and the FSH source to the FSH folder in one transaction
+                        if let Err(e) = state
+                            .storage()
+                            .save_profile_with_sources(
+                                &params.project_id,
+                                &doc,
+                                None,
+                                Some(&req.content),
+                            )
+                            .await
+                        {
+                            return ErrorResponse::internal_error(format!(
+                                "Failed to save profile: {}",
+                                e
+                            ))
+                            .into_response();
                         }
-                    }
-
-                    // Save profile document to IR/resources (differential-only)
-                    if let Err(e) = storage.save_profile(&doc).await {
-                        return ErrorResponse::internal_error(format!("Failed to save profile: {}", e)).into_response();
-                    }
-
-                    // Register in project index for tree visibility
-                    let resource_kind = match doc.resource.kind {
-                        crate::ir::StructureKind::Resource | crate::ir::StructureKind::ComplexType => {
-                            if doc.resource.base.url.contains("Extension") {
-                                ResourceKind::Extension
-                            } else {
-                                ResourceKind::Profile
+                        state.notify_operation_applied(
+                            &params.project_id,
+                            &doc.metadata.id,
+                            "Import profile from FSH",
+                            None,
+                            doc.history.state(),
+                        );
+                        state.notify_profile_saved(&params.project_id, &doc.metadata.id);
+
+                        // Register in project index for tree visibility
+                        let resource_kind = match doc.resource.kind {
+                            crate::ir::StructureKind::Resource | crate::ir::StructureKind::ComplexType => {
+                                if is_extension_in_project(&state, &params.project_id, &doc).await {
+                                    ResourceKind::Extension
+                                } else {
+                                    ResourceKind::Profile
+                                }
+                            }
+                            crate::ir::StructureKind::Logical => ResourceKind::Profile,
+                            crate::ir::StructureKind::PrimitiveType => ResourceKind::Profile,
+                        };
+
+                        let add_request = crate::project::AddResourceRequest {
+                            id: Some(doc.metadata.id.clone()),
+                            name: doc.metadata.name.clone(),
+                            kind: resource_kind,
+                            canonical_url: Some(doc.metadata.url.clone()),
+                            base: Some(doc.resource.base.url.clone()),
+                            source_format: Some(SourceFormat::Fsh),
+                            description: doc.metadata.description.clone(),
+                            context: None,
+                            purpose: None,
+                            content: None, // FSH content already saved separately
+                        };
+
+                        if let Err(e) = project_service.add_resource(&params.project_id, add_request).await {
+                            if !e.to_string().contains("already exists") {
+                                diagnostics.push(Diagnostic {
+                                    severity: DiagnosticSeverity::Warning,
+                                    code: "INDEX_UPDATE_FAILED".to_string(),
+                                    message: format!("Failed to update project index: {}", e),
+                                    path: None,
+                                    ..Default::default()
+                                });
                             }
                         }
-                        crate::ir::StructureKind::Logical => ResourceKind::Profile,
-                        crate::ir::StructureKind::PrimitiveType => ResourceKind::Profile,
-                    };
-
-                    let add_request = crate::project::AddResourceRequest {
-                        id: Some(doc.metadata.id.clone()),
-                        name: doc.metadata.name.clone(),
-                        kind: resource_kind,
-                        canonical_url: Some(doc.metadata.url.clone()),
-                        base: Some(doc.resource.base.url.clone()),
-                        source_format: Some(SourceFormat::Fsh),
-                        description: doc.metadata.description.clone(),
-                        context: None,
-                        purpose: None,
-                        content: None, // FSH content already saved separately
-                    };
+                    }
 
-                    if let Err(e) = project_service.add_resource(&params.project_id, add_request).await {
-                        if !e.to_string().contains("already exists") {
-                            diagnostics.push(Diagnostic {
+                    // Persist example instances as raw project resources (listed only on dry runs)
+                    let mut imported_instances = Vec::new();
+                    for instance in instances {
+                        let resource_type = instance.resource["resourceType"]
+                            .as_str()
+                            .unwrap_or("Resource")
+                            .to_string();
+                        if query.dry_run {
+                            imported_instances.push(ImportedInstance {
+                                id: instance.id,
+                                name: instance.name,
+                                resource_type,
+                                instance_of: instance.instance_of,
+                            });
+                            continue;
+                        }
+                        let add_request = crate::project::AddResourceRequest {
+                            id: Some(instance.id.clone()),
+                            name: instance.name.clone(),
+                            kind: ResourceKind::Instance,
+                            canonical_url: Some(format!(
+                                "{}/{}/{}",
+                                project.canonical_base, resource_type, instance.id
+                            )),
+                            base: Some(instance.instance_of.clone()),
+                            source_format: Some(SourceFormat::Fsh),
+                            description: None,
+                            context: None,
+                            purpose: None,
+                            content: Some(instance.resource.to_string()),
+                        };
+
+                        match project_service.add_resource(&params.project_id, add_request).await {
+                            Ok(_) => imported_instances.push(ImportedInstance {
+                                id: instance.id,
+                                name: instance.name,
+                                resource_type,
+                                instance_of: instance.instance_of,
+                            }),
+                            Err(e) => diagnostics.push(Diagnostic {
                                 severity: DiagnosticSeverity::Warning,
-                                code: "INDEX_UPDATE_FAILED".to_string(),
-                                message: format!("Failed to update project index: {}", e),
+                                code: "INSTANCE_SAVE_FAILED".to_string(),
+                                message: format!("Failed to save instance '{}': {}", instance.id, e),
                                 path: None,
-                            });
+                                ..Default::default()
+                            }),
                         }
                     }
 
@@ -861,10 +1868,23 @@ async fn import_profile(
                     let response = ImportResponse {
                         profile: ProfileDetailsResponse::from(&hydrated),
                         diagnostics,
+                        instances: imported_instances,
+                        dry_run: query.dry_run,
                     };
 
                     Json(ApiResponse::ok(response)).into_response()
                 }
+                Err(crate::fsh::FshError::Import(e)) => {
+                    // Keep file/line/column so the editor can underline the FSH
+                    let diagnostics = Diagnostic::from_fsh_error(&e);
+                    ErrorResponse::new(
+                        StatusCode::UNPROCESSABLE_ENTITY,
+                        "FSH_IMPORT_FAILED",
+                        format!("FSH import failed: {}", e),
+                    )
+                    .with_details(serde_json::json!({ "diagnostics": diagnostics }))
+                    .into_response()
+                }
                 Err(e) => {
                     ErrorResponse::validation_error(format!("FSH import failed: {}", e))
                         .into_response()
@@ -874,9 +1894,132 @@ async fn import_profile(
     }
 }
 
+/// GET /api/projects/:projectId/profiles/:profileId/fsh/validate
+/// Parse and check FSH content without importing or saving anything.
+async fn validate_fsh(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+    Query(query): Query<ValidateFshQuery>,
+) -> impl IntoResponse {
+    use crate::fsh::{FshImportOptions, FshImporter};
+
+    let project_service = ProjectService::new(state.workspace_dir().clone());
+    let project = match project_service.load_project(&params.project_id).await {
+        Ok(p) => p,
+        Err(ProjectError::NotFound(_)) => {
+            return ErrorResponse::not_found("Project", &params.project_id).into_response();
+        }
+        Err(e) => {
+            return ErrorResponse::internal_error(format!("Failed to load project: {}", e))
+                .into_response();
+        }
+    };
+
+    // Linting must stay cheap, so skip the package-backed fishing context
+    let mut options = FshImportOptions::default()
+        .with_canonical_base(&project.canonical_base)
+        .with_fhir_version(project.fhir_version);
+    options.resolve_dependencies = false;
+
+    let importer = match FshImporter::with_options(options).await {
+        Ok(i) => i,
+        Err(e) => {
+            return ErrorResponse::internal_error(format!(
+                "Failed to initialize FSH importer: {}",
+                e
+            ))
+            .into_response();
+        }
+    };
+
+    let source_file = std::path::PathBuf::from(&params.profile_id).with_extension("fsh");
+    let checked = match importer.check_content(&query.content, &source_file) {
+        Ok(c) => c,
+        Err(e) => {
+            return ErrorResponse::internal_error(format!("Failed to check FSH: {}", e))
+                .into_response();
+        }
+    };
+
+    let mut diagnostics: Vec<Diagnostic> = checked
+        .value
+        .iter()
+        .flat_map(Diagnostic::from_fsh_error)
+        .collect();
+    diagnostics.extend(checked.warnings.iter().map(Diagnostic::from));
+
+    let response = ValidateFshResponse {
+        valid: checked.value.is_empty(),
+        diagnostics,
+    };
+    Json(ApiResponse::ok(response)).into_response()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ir::{Cardinality, SliceNode};
+
+    fn patient_document() -> ProfileDocument {
+        let url = "http://example.org/fhir/StructureDefinition/TestPatient";
+        let resource = ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        ProfileDocument::new(DocumentMetadata::new("test-patient", url, "TestPatient"), resource)
+    }
+
+    #[test]
+    fn test_profile_stats_fixture() {
+        use crate::export::DifferentialStats;
+        use crate::ir::{Discriminator, ElementSource, SlicingDefinition};
+        use crate::merge::DifferentialElement;
+
+        let mut doc = patient_document();
+
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.source = ElementSource::Modified;
+        name.add_child(ElementNode::new("Patient.name.family".to_string()));
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        identifier.source = ElementSource::Modified;
+        identifier.slicing = Some(SlicingDefinition::new(vec![Discriminator::value("system")]));
+        let mut mrn = SliceNode::with_path("mrn", "Patient.identifier:mrn");
+        mrn.element.source = ElementSource::Added;
+        mrn.element
+            .add_child(ElementNode::new("Patient.identifier.system".to_string()));
+        identifier.add_slice("mrn".to_string(), mrn);
+        doc.resource.root.add_child(name);
+        doc.resource.root.add_child(identifier);
+
+        let mut name = DifferentialElement::new("Patient.name".to_string());
+        name.constraints.cardinality = Some(Cardinality::required());
+        name.constraints.flags.must_support = true;
+        let mut identifier = DifferentialElement::new("Patient.identifier".to_string());
+        identifier.slicing = Some(SlicingDefinition::new(vec![Discriminator::value("system")]));
+        let mut mrn = DifferentialElement::new("Patient.identifier".to_string());
+        mrn.slice_name = Some("mrn".to_string());
+        mrn.constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+        doc.resource.differential = vec![name, identifier, mrn];
+
+        let stats = ProfileStatsResponse::from(&doc);
+        assert_eq!(
+            stats.differential,
+            DifferentialStats {
+                element_count: 3,
+                cardinality_changes: 2,
+                type_constraints: 0,
+                binding_constraints: 0,
+                must_support_count: 1,
+                sliced_elements: 1,
+                slice_count: 1,
+            }
+        );
+        // Patient, name, family, identifier, identifier:mrn, its system
+        assert_eq!(stats.element_count, 6);
+        assert_eq!(stats.constrained_element_count, 3);
+        assert_eq!(stats.slice_count, 1);
+
+        let json = serde_json::to_value(&stats).unwrap();
+        assert_eq!(json["differential"]["mustSupportCount"], 1);
+        assert_eq!(json["sliceCount"], 1);
+    }
 
     #[test]
     fn test_error_response_creation() {
@@ -885,15 +2028,903 @@ mod tests {
         assert_eq!(json.0.code, "NOT_FOUND");
     }
 
+    #[test]
+    fn test_check_replace_consistency() {
+        let url = "http://example.org/fhir/StructureDefinition/MyPatient";
+        let doc = ProfileDocument::new(
+            DocumentMetadata::new("my-patient", url, "MyPatient"),
+            ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient")),
+        );
+        assert!(check_replace_consistency(&doc, "my-patient", url).is_ok());
+
+        let err = check_replace_consistency(&doc, "other-patient", url).unwrap_err();
+        assert!(err.contains("other-patient"));
+
+        let err = check_replace_consistency(
+            &doc,
+            "my-patient",
+            "http://example.org/fhir/StructureDefinition/Other",
+        )
+        .unwrap_err();
+        assert!(err.contains("rename"));
+
+        let mut doc = doc;
+        doc.resource
+            .differential
+            .push(crate::merge::DifferentialElement::new("Observation.status".to_string()));
+        let err = check_replace_consistency(&doc, "my-patient", url).unwrap_err();
+        assert!(err.contains("Observation.status"));
+    }
+
+    #[tokio::test]
+    async fn test_create_profile_idempotency_key() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", "create-once".parse().unwrap());
+        let request = || {
+            serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+                "kind": "logical",
+                "fhirVersion": "R4",
+                "name": "Clicked"
+            }))
+            .unwrap()
+        };
+
+        let project = || {
+            Path(ProjectPath {
+                project_id: "demo".to_string(),
+            })
+        };
+        let first =
+            create_profile(State(state.clone()), project(), headers.clone(), Json(request()))
+                .await
+                .into_response();
+        let second = create_profile(State(state.clone()), project(), headers, Json(request()))
+            .await
+            .into_response();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert_eq!(second.status(), StatusCode::CREATED);
+
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        let index = storage.read_index().await.unwrap();
+        assert_eq!(index.profiles.len(), 1);
+        assert_eq!(
+            state.idempotent_profile("demo", "create-once"),
+            Some(index.profiles[0].id.clone())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_profile_idempotency_key_in_progress() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let mut headers = HeaderMap::new();
+        headers.insert("idempotency-key", "create-once".parse().unwrap());
+        let request = |fhir_version: &str| {
+            serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+                "kind": "logical",
+                "fhirVersion": fhir_version,
+                "name": "Clicked"
+            }))
+            .unwrap()
+        };
+        let project = || {
+            Path(ProjectPath {
+                project_id: "demo".to_string(),
+            })
+        };
+
+        // Another request holds the key
+        state.reserve_idempotency_key("demo", "create-once");
+        let response = create_profile(
+            State(state.clone()),
+            project(),
+            headers.clone(),
+            Json(request("R4")),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // A rejected create releases the key for the retry
+        state.release_idempotency_key("demo", "create-once");
+        let response = create_profile(
+            State(state.clone()),
+            project(),
+            headers.clone(),
+            Json(request("R9")),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = create_profile(
+            State(state.clone()),
+            project(),
+            headers,
+            Json(request("R4")),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_rename_route_updates_extension_context_dependents() {
+        use crate::ir::{ExtensionContext, ExtensionContextType};
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let storage = state.storage();
+
+        let old_url = "http://example.org/fhir/StructureDefinition/LabBatch";
+        let new_url = "http://example.org/fhir/StructureDefinition/SpecimenBatch";
+        let resource = ProfiledResource::logical(old_url, FhirVersion::R4, "LabBatch");
+        let metadata = DocumentMetadata::new("labbatch", old_url, "LabBatch");
+        storage
+            .save_profile("demo", &ProfileDocument::new(metadata, resource))
+            .await
+            .unwrap();
+
+        let ext_url = "http://example.org/fhir/StructureDefinition/batch-note";
+        let mut resource = ProfiledResource::new(
+            ext_url,
+            FhirVersion::R4,
+            BaseDefinition::new("http://hl7.org/fhir/StructureDefinition/Extension"),
+        );
+        resource.context = vec![ExtensionContext::new(
+            ExtensionContextType::Extension,
+            old_url,
+        )];
+        let metadata = DocumentMetadata::new("batch-note", ext_url, "BatchNote");
+        storage
+            .save_profile("demo", &ProfileDocument::new(metadata, resource))
+            .await
+            .unwrap();
+
+        let router = Router::new()
+            .nest("/api/projects/{projectId}/profiles", profile_routes())
+            .with_state(state.clone());
+        let body = serde_json::json!({ "id": "specimen-batch", "url": new_url }).to_string();
+        let request = axum::http::Request::builder()
+            .method(axum::http::Method::POST)
+            .uri("/api/projects/demo/profiles/labbatch/rename")
+            .header(axum::http::header::CONTENT_TYPE, "application/json")
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(
+            json["data"]["updatedDependents"],
+            serde_json::json!(["batch-note"])
+        );
+
+        let dependent = storage.load_profile("demo", "batch-note").await.unwrap();
+        assert_eq!(dependent.resource.context[0].expression, new_url);
+        assert!(storage
+            .profile_exists("demo", "specimen-batch")
+            .await
+            .unwrap());
+        assert!(!storage.profile_exists("demo", "labbatch").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_rename_rolls_back_when_sources_fail_to_move() {
+        use crate::ir::{ExtensionContext, ExtensionContextType};
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let storage = state.storage();
+
+        let old_url = "http://example.org/fhir/StructureDefinition/LabBatch";
+        let resource = ProfiledResource::logical(old_url, FhirVersion::R4, "LabBatch");
+        let metadata = DocumentMetadata::new("labbatch", old_url, "LabBatch");
+        storage
+            .save_profile("demo", &ProfileDocument::new(metadata, resource))
+            .await
+            .unwrap();
+
+        let ext_url = "http://example.org/fhir/StructureDefinition/batch-note";
+        let mut resource = ProfiledResource::new(
+            ext_url,
+            FhirVersion::R4,
+            BaseDefinition::new("http://hl7.org/fhir/StructureDefinition/Extension"),
+        );
+        resource.context = vec![ExtensionContext::new(
+            ExtensionContextType::Extension,
+            old_url,
+        )];
+        let metadata = DocumentMetadata::new("batch-note", ext_url, "BatchNote");
+        storage
+            .save_profile("demo", &ProfileDocument::new(metadata, resource))
+            .await
+            .unwrap();
+
+        // An unreadable SD source makes the rename fail after the batch save
+        let project_files = ProfileStorage::new(state.project_path("demo").unwrap());
+        project_files
+            .save_sd_json("LabBatch", "{ not json")
+            .await
+            .unwrap();
+
+        let req = serde_json::from_value::<RenameProfileRequest>(serde_json::json!({
+            "id": "specimen-batch",
+            "url": "http://example.org/fhir/StructureDefinition/SpecimenBatch"
+        }))
+        .unwrap();
+        let path = Path(ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: "labbatch".to_string(),
+        });
+        let response = rename_profile(State(state.clone()), path, Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let restored = storage.load_profile("demo", "labbatch").await.unwrap();
+        assert_eq!(restored.metadata.url, old_url);
+        assert!(!storage
+            .profile_exists("demo", "specimen-batch")
+            .await
+            .unwrap());
+        let dependent = storage.load_profile("demo", "batch-note").await.unwrap();
+        assert_eq!(dependent.resource.context[0].expression, old_url);
+    }
+
+    #[tokio::test]
+    async fn test_handlers_use_configured_storage() {
+        use crate::api::persistence::MemoryStorage;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::with_storage(
+            crate::Config::default(),
+            workspace.path().to_path_buf(),
+            std::sync::Arc::new(MemoryStorage::new()),
+        );
+
+        let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+            "id": "stored",
+            "kind": "logical",
+            "fhirVersion": "R4",
+            "name": "Stored"
+        }))
+        .unwrap();
+        let path = Path(ProjectPath {
+            project_id: "demo".to_string(),
+        });
+        let response = create_profile(State(state.clone()), path, HeaderMap::new(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let profile = || {
+            Path(ProfilePath {
+                project_id: "demo".to_string(),
+                profile_id: "stored".to_string(),
+            })
+        };
+        let req = serde_json::from_value::<UpdateMetadataRequest>(serde_json::json!({
+            "title": "Stored in memory"
+        }))
+        .unwrap();
+        let response = update_metadata(State(state.clone()), profile(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let doc = state
+            .storage()
+            .load_profile("demo", "stored")
+            .await
+            .unwrap();
+        assert_eq!(doc.metadata.title.as_deref(), Some("Stored in memory"));
+        assert!(!workspace
+            .path()
+            .join("demo/IR/resources/stored.json")
+            .exists());
+
+        let response = delete_profile(State(state.clone()), profile())
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert!(state
+            .storage()
+            .list_profiles("demo")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replace_profile_same_etag_only_once() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+            "kind": "logical",
+            "fhirVersion": "R4",
+            "name": "LabBatch"
+        }))
+        .unwrap();
+        let path = Path(ProjectPath {
+            project_id: "demo".to_string(),
+        });
+        let response = create_profile(State(state.clone()), path, HeaderMap::new(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let storage = state.storage();
+        let etag = storage.profile_etag("demo", "labbatch").await.unwrap();
+        let stored = storage.load_profile("demo", "labbatch").await.unwrap();
+
+        // Two clients replace the profile starting from the same version
+        let put = |title: &str| {
+            let mut doc = stored.clone();
+            doc.metadata.title = Some(title.to_string());
+            let mut headers = HeaderMap::new();
+            headers.insert(header::IF_MATCH, format!("\"{}\"", etag).parse().unwrap());
+            let path = Path(ProfilePath {
+                project_id: "demo".to_string(),
+                profile_id: "labbatch".to_string(),
+            });
+            replace_profile(
+                State(state.clone()),
+                path,
+                headers,
+                Json(serde_json::to_value(&doc).unwrap()),
+            )
+        };
+        let (first, second) = tokio::join!(put("First"), put("Second"));
+        let mut statuses = vec![
+            first.into_response().status(),
+            second.into_response().status(),
+        ];
+        statuses.sort();
+        assert_eq!(statuses, vec![StatusCode::OK, StatusCode::CONFLICT]);
+    }
+
+    #[tokio::test]
+    async fn test_create_profile_ids_from_name() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let mut ids = Vec::new();
+        for url in ["http://example.org/fhir/A", "http://example.org/fhir/B"] {
+            let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+                "kind": "logical",
+                "fhirVersion": "R4",
+                "name": "My Patient",
+                "url": url
+            }))
+            .unwrap();
+            let path = Path(ProjectPath {
+                project_id: "demo".to_string(),
+            });
+            let response = create_profile(State(state.clone()), path, HeaderMap::new(), Json(req))
+                .await
+                .into_response();
+            assert_eq!(response.status(), StatusCode::CREATED);
+
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+            ids.push(json["data"]["metadata"]["id"].as_str().unwrap().to_string());
+        }
+
+        assert_eq!(ids, vec!["my-patient", "my-patient-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_create_profile_uses_project_canonical_base() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        ProjectService::new(workspace.path())
+            .create_project(crate::project::CreateProjectRequest {
+                id: "acme".to_string(),
+                name: "Acme".to_string(),
+                canonical_base: "https://fhir.acme.test/".to_string(),
+                fhir_version: None,
+                description: None,
+                publisher: None,
+                dependencies: None,
+            })
+            .await
+            .unwrap();
+
+        let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+            "kind": "logical",
+            "fhirVersion": "R4",
+            "name": "AcmeModel"
+        }))
+        .unwrap();
+        let path = Path(ProjectPath {
+            project_id: "acme".to_string(),
+        });
+        let response = create_profile(State(state), path, HeaderMap::new(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let url = json["data"]["metadata"]["url"].as_str().unwrap();
+        assert_eq!(url, "https://fhir.acme.test/StructureDefinition/AcmeModel");
+        assert!(!url.contains("example.org"));
+    }
+
+    #[tokio::test]
+    async fn test_list_profiles_modified_since() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let storage = ProfileStorage::new(state.project_path("acme").unwrap());
+
+        let mut older = ProfileDocument::for_resource(
+            "older",
+            "http://example.org/fhir/StructureDefinition/Older",
+            "Older",
+            "Patient",
+            FhirVersion::R4,
+        );
+        let mut newer = ProfileDocument::for_resource(
+            "newer",
+            "http://example.org/fhir/StructureDefinition/Newer",
+            "Newer",
+            "Patient",
+            FhirVersion::R4,
+        );
+        let cutoff = chrono::Utc::now();
+        older.modified_at = cutoff - chrono::Duration::hours(1);
+        newer.modified_at = cutoff + chrono::Duration::seconds(1);
+        storage.save_profile(&older).await.unwrap();
+        storage.save_profile(&newer).await.unwrap();
+
+        let list = |modified_since| {
+            let query = ListProfilesQuery {
+                fhir_version: None,
+                page: None,
+                page_size: None,
+                modified_since,
+            };
+            let path = Path(ProjectPath {
+                project_id: "acme".to_string(),
+            });
+            let state = state.clone();
+            async move {
+                let response = list_profiles(State(state), path, Query(query))
+                    .await
+                    .into_response();
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+                let mut ids: Vec<String> = json["data"]["profiles"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|p| p["id"].as_str().unwrap().to_string())
+                    .collect();
+                ids.sort();
+                ids
+            }
+        };
+
+        assert_eq!(list(None).await, vec!["newer", "older"]);
+        assert_eq!(list(Some(cutoff)).await, vec!["newer"]);
+        // The bound is inclusive
+        assert_eq!(list(Some(older.modified_at)).await, vec!["newer", "older"]);
+    }
+
+    #[test]
+    fn test_unique_profile_id() {
+        assert_eq!(unique_profile_id("US Core Patient", |_| false), "us-core-patient");
+        assert_eq!(unique_profile_id("!!!", |_| false), "profile");
+        assert_eq!(
+            unique_profile_id("Obs", |id| id == "obs" || id == "obs-2"),
+            "obs-3"
+        );
+    }
+
     #[test]
     fn test_find_or_create_element() {
         let mut root = ElementNode::new("Patient".to_string());
 
         // Find/create a nested element
-        let element = find_or_create_element(&mut root, "Patient.name.family");
+        let element = find_or_create_element(&mut root, "Patient.name.family").unwrap();
         assert_eq!(element.path, "Patient.name.family");
 
         // Should have created intermediate elements
         assert!(!root.children.is_empty());
     }
+
+    #[test]
+    fn test_update_default_value_and_meaning_when_missing() {
+        let mut doc = patient_document();
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "defaultValue": true,
+            "meaningWhenMissing": "Assume the patient record is active"
+        }))
+        .unwrap();
+
+        let (constraints, diagnostics) =
+            apply_element_updates(&mut doc, "Patient.active", req, false, &mut Vec::new()).unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(constraints.default_value, Some(serde_json::json!(true)));
+        assert_eq!(
+            constraints.meaning_when_missing.as_deref(),
+            Some("Assume the patient record is active")
+        );
+
+        // Not allowed once the element is required
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 1, "max": 1 },
+            "meaningWhenMissing": "Never missing"
+        }))
+        .unwrap();
+        let (constraints, diagnostics) =
+            apply_element_updates(&mut doc, "Patient.gender", req, false, &mut Vec::new()).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "MEANING_WHEN_MISSING_ON_REQUIRED");
+        assert!(constraints.meaning_when_missing.is_none());
+    }
+
+    #[test]
+    fn test_slice_entry_cardinality() {
+        let mut doc = patient_document();
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        identifier.constraints.cardinality = Some(Cardinality::new(0, None));
+        identifier.slices.insert(
+            "mrn".to_string(),
+            SliceNode::with_path("mrn", "Patient.identifier:mrn"),
+        );
+        doc.resource.root.add_child(identifier);
+
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 1, "max": 1 }
+        }))
+        .unwrap();
+        let (constraints, diagnostics) = apply_element_updates(
+            &mut doc,
+            "Patient.identifier:mrn",
+            req,
+            false,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert!(diagnostics.is_empty());
+        assert_eq!(constraints.cardinality, Some(Cardinality::new(1, Some(1))));
+
+        let root = &doc.resource.root;
+        let identifier = &root.children[0];
+        assert_eq!(root.children.len(), 1);
+        assert_eq!(
+            identifier.slices["mrn"].element.constraints.cardinality,
+            Some(Cardinality::new(1, Some(1)))
+        );
+        // The sliced element keeps its own cardinality
+        assert_eq!(
+            identifier.constraints.cardinality,
+            Some(Cardinality::new(0, None))
+        );
+
+        // A mistyped slice name is not created
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 1, "max": 1 }
+        }))
+        .unwrap();
+        let (status, _) = apply_element_updates(
+            &mut doc,
+            "Patient.identifier:mnr",
+            req,
+            false,
+            &mut Vec::new(),
+        )
+        .unwrap_err();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(doc.resource.root.children[0].slices.len(), 1);
+    }
+
+    #[test]
+    fn test_element_updates_run_through_operations() {
+        let mut doc = patient_document();
+
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 1, "max": "*" },
+            "short": "Patient names"
+        }))
+        .unwrap();
+        apply_element_updates(&mut doc, "Patient.name", req, false, &mut Vec::new()).unwrap();
+        assert_eq!(doc.history.undo_count(), 2);
+
+        // Operation validation rejects the update
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 2, "max": 1 }
+        }))
+        .unwrap();
+        let (status, _) =
+            apply_element_updates(&mut doc, "Patient.name", req, false, &mut Vec::new())
+                .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_update_element_records_history() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+            "kind": "logical",
+            "fhirVersion": "R4",
+            "name": "LabBatch"
+        }))
+        .unwrap();
+        let path = Path(ProjectPath {
+            project_id: "demo".to_string(),
+        });
+        let response = create_profile(State(state.clone()), path, HeaderMap::new(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 1, "max": 1 }
+        }))
+        .unwrap();
+        let path = Path(ElementPath {
+            project_id: "demo".to_string(),
+            profile_id: "labbatch".to_string(),
+            path: "LabBatch.batchNumber".to_string(),
+        });
+        let response = update_element(
+            State(state.clone()),
+            path,
+            Query(UpdateElementQuery::default()),
+            Json(req),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        let doc = storage.load_profile("labbatch").await.unwrap();
+        assert_eq!(doc.history.undo_count(), 1);
+        assert_eq!(
+            doc.history.undo_description(),
+            Some("Set cardinality of LabBatch.batchNumber to 1..1")
+        );
+        assert!(doc
+            .resource
+            .differential
+            .iter()
+            .any(|diff| diff.path == "LabBatch.batchNumber"));
+    }
+
+    #[tokio::test]
+    async fn test_metadata_update_keeps_differential() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let req = serde_json::from_value::<CreateProfileRequest>(serde_json::json!({
+            "kind": "logical",
+            "fhirVersion": "R4",
+            "name": "LabBatch"
+        }))
+        .unwrap();
+        let path = Path(ProjectPath {
+            project_id: "demo".to_string(),
+        });
+        let response = create_profile(State(state.clone()), path, HeaderMap::new(), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        let differential = storage
+            .load_profile("labbatch")
+            .await
+            .unwrap()
+            .resource
+            .differential;
+        assert!(!differential.is_empty());
+
+        let req: UpdateMetadataRequest = serde_json::from_value(serde_json::json!({
+            "title": "Lab batch"
+        }))
+        .unwrap();
+        let path = Path(ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: "labbatch".to_string(),
+        });
+        let response = update_metadata(State(state.clone()), path, Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let doc = storage.load_profile("labbatch").await.unwrap();
+        assert_eq!(doc.metadata.title.as_deref(), Some("Lab batch"));
+        let paths = |diff: &[crate::merge::DifferentialElement]| {
+            diff.iter().map(|d| d.path.clone()).collect::<Vec<_>>()
+        };
+        assert_eq!(paths(&doc.resource.differential), paths(&differential));
+    }
+
+    #[tokio::test]
+    async fn test_json_import_keeps_differential() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+
+        let content = serde_json::json!({
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/LabBatch",
+            "name": "LabBatch",
+            "status": "draft",
+            "kind": "logical",
+            "abstract": false,
+            "type": "LabBatch",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Base",
+            "derivation": "specialization",
+            "differential": {
+                "element": [
+                    { "id": "LabBatch", "path": "LabBatch" },
+                    {
+                        "id": "LabBatch.batchNumber",
+                        "path": "LabBatch.batchNumber",
+                        "short": "Lot number",
+                        "min": 1,
+                        "max": "1",
+                        "type": [{ "code": "string" }]
+                    }
+                ]
+            }
+        });
+        let req: ImportProfileRequest = serde_json::from_value(serde_json::json!({
+            "format": "json",
+            "content": content.to_string()
+        }))
+        .unwrap();
+        let path = Path(ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: "labbatch".to_string(),
+        });
+        let response = import_profile(
+            State(state.clone()),
+            path,
+            Query(ImportQuery::default()),
+            Json(req),
+        )
+        .await
+        .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let id = json["data"]["profile"]["metadata"]["id"].as_str().unwrap();
+
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        let doc = storage.load_profile(id).await.unwrap();
+        let batch_number = doc
+            .resource
+            .differential
+            .iter()
+            .find(|diff| diff.path == "LabBatch.batchNumber")
+            .expect("imported differential was not stored");
+        assert_eq!(
+            batch_number.constraints.short.as_deref(),
+            Some("Lot number")
+        );
+    }
+
+    /// Store a Patient profile requiring a name, with its base tree cached.
+    async fn seed_patient_profile(state: &AppState) -> ProfileStorage {
+        use crate::merge::DifferentialElement;
+
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.constraints.cardinality = Some(Cardinality::new(0, None));
+        let mut gender = ElementNode::new("Patient.gender".to_string());
+        gender.constraints.cardinality = Some(Cardinality::new(0, Some(1)));
+        gender.constraints.short = Some("male | female | other | unknown".to_string());
+        base.add_child(name);
+        base.add_child(gender);
+
+        let mut doc = patient_document();
+        state.base_tree_cache().insert(
+            &doc.resource.base.canonical(),
+            doc.resource.fhir_version,
+            base,
+        );
+
+        let mut name_diff = DifferentialElement::new("Patient.name".to_string());
+        name_diff.constraints.cardinality = Some(Cardinality::new(1, None));
+        doc.resource.differential.push(name_diff);
+
+        let storage = ProfileStorage::new(state.project_path("demo").unwrap());
+        storage.init().await.unwrap();
+        storage.save_profile(&doc).await.unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_mode_patch_stores_minimal_differential() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let storage = seed_patient_profile(&state).await;
+
+        // The whole element is submitted; only mustSupport differs from the base
+        let req: UpdateElementRequest = serde_json::from_value(serde_json::json!({
+            "cardinality": { "min": 0, "max": 1 },
+            "short": "male | female | other | unknown",
+            "flags": { "mustSupport": true }
+        }))
+        .unwrap();
+        let path = Path(ElementPath {
+            project_id: "demo".to_string(),
+            profile_id: "test-patient".to_string(),
+            path: "Patient.gender".to_string(),
+        });
+        let query = UpdateElementQuery {
+            mode: Some(EditingMode::Snapshot),
+            ..Default::default()
+        };
+        let response = update_element(State(state.clone()), path, Query(query), Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let doc = storage.load_profile("test-patient").await.unwrap();
+        let differential = &doc.resource.differential;
+        let paths: Vec<&str> = differential.iter().map(|diff| diff.path.as_str()).collect();
+        assert_eq!(paths, vec!["Patient.name", "Patient.gender"]);
+        assert_eq!(
+            differential[0].constraints.cardinality,
+            Some(Cardinality::new(1, None))
+        );
+
+        let gender = &differential[1].constraints;
+        assert!(gender.flags.must_support);
+        assert!(gender.cardinality.is_none());
+        assert!(gender.short.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_editing_mode_persists_through_metadata() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let storage = seed_patient_profile(&state).await;
+
+        let req: UpdateMetadataRequest = serde_json::from_value(serde_json::json!({
+            "editingMode": "snapshot"
+        }))
+        .unwrap();
+        let path = Path(ProfilePath {
+            project_id: "demo".to_string(),
+            profile_id: "test-patient".to_string(),
+        });
+        let response = update_metadata(State(state.clone()), path, Json(req))
+            .await
+            .into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["data"]["editingMode"], "snapshot");
+
+        let doc = storage.load_profile("test-patient").await.unwrap();
+        assert_eq!(doc.editing_mode, EditingMode::Snapshot);
+        assert_eq!(doc.resource.differential.len(), 1);
+    }
 }
@@ -13,6 +13,10 @@
 //! - `DELETE /api/projects/:projectId/resources/:resourceId` - Remove a resource
 //! - `GET    /api/projects/:projectId/tree` - Get project file tree
 //! - `GET    /api/projects/:projectId/dependencies` - Get dependency graph
+//! - `GET    /api/projects/:projectId/dependencies/usage` - Flag unused package dependencies
+//! - `GET    /api/projects/:projectId/recent-canonicals` - Recently referenced value sets/extensions
+//! - `POST   /api/projects/:projectId/compatibility` - Check profiles against a CapabilityStatement
+//! - `POST   /api/projects/:projectId/import/ig` - Seed package id, FHIR version and dependencies from an ImplementationGuide
 
 use axum::{
     extract::{Path, State},
@@ -21,14 +25,17 @@ use axum::{
     Json, Router,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 use crate::project::{
-    AddResourceRequest, CreateProjectRequest, FileTreeNode, Project, ProjectError,
-    ProjectResource, ProjectService, ProjectStatus, ResourceKind, UpdateProjectRequest,
+    compute_compatibility, compute_dependency_usage, AddResourceRequest, CreateProjectRequest,
+    DependencyUsage, FileTreeNode, ImplementationGuideSeed, Project, ProfileCompatibility,
+    ProjectError, ProjectResource, ProjectService, ProjectStatus, RecentCanonical, ResourceKind,
+    ServerCapabilities, UpdateProjectRequest,
 };
 use crate::state::AppState;
 
-use super::dto::ApiResponse;
+use super::dto::{ApiResponse, Diagnostic, DiagnosticSeverity};
 
 /// Create project routes.
 pub fn project_routes() -> Router<AppState> {
@@ -48,6 +55,10 @@ pub fn project_routes() -> Router<AppState> {
         .route("/{projectId}/artifacts/{resourceId}", delete(remove_resource))
         .route("/{projectId}/tree", get(get_file_tree))
         .route("/{projectId}/dependencies", get(get_dependencies))
+        .route("/{projectId}/dependencies/usage", get(get_dependency_usage))
+        .route("/{projectId}/recent-canonicals", get(get_recent_canonicals))
+        .route("/{projectId}/compatibility", post(check_compatibility))
+        .route("/{projectId}/import/ig", post(import_implementation_guide))
 }
 
 // === Path Parameters ===
@@ -122,6 +133,8 @@ pub struct ArtifactCreatedResponse {
 pub struct DependencyGraphResponse {
     pub resources: Vec<ResourceNode>,
     pub edges: Vec<DependencyEdge>,
+    /// Groups of canonical URLs that depend on each other circularly.
+    pub cycles: Vec<Vec<String>>,
 }
 
 #[derive(Debug, Serialize)]
@@ -140,6 +153,46 @@ pub struct DependencyEdge {
     pub to: String,
 }
 
+/// Package dependency usage response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyUsageResponse {
+    pub dependencies: Vec<DependencyUsage>,
+    /// Names of declared dependencies that no resource uses.
+    pub unused: Vec<String>,
+    /// Referenced canonical URLs that no installed package provides.
+    pub unresolved: Vec<String>,
+}
+
+/// Compatibility report for a single profile.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileCompatibilityReport {
+    #[serde(flatten)]
+    pub compatibility: ProfileCompatibility,
+    pub compatible: bool,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Compatibility report response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompatibilityResponse {
+    /// Whether every profile is supported by the target server.
+    pub compatible: bool,
+    pub profiles: Vec<ProfileCompatibilityReport>,
+}
+
+/// ImplementationGuide import response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImplementationGuideImportResponse {
+    /// Project after applying the guide's settings.
+    pub project: Project,
+    /// Resources the guide references, for the client to import next.
+    pub resources: Vec<String>,
+}
+
 // === Error Handling ===
 
 fn handle_error(err: ProjectError) -> (StatusCode, Json<ApiResponse<()>>) {
@@ -150,8 +203,10 @@ fn handle_error(err: ProjectError) -> (StatusCode, Json<ApiResponse<()>>) {
         ProjectError::ResourceAlreadyExists(_) => (StatusCode::CONFLICT, "RESOURCE_ALREADY_EXISTS"),
         ProjectError::InvalidStructure(_) => (StatusCode::BAD_REQUEST, "INVALID_STRUCTURE"),
         ProjectError::InvalidCanonicalUrl(_) => (StatusCode::BAD_REQUEST, "INVALID_CANONICAL_URL"),
+        ProjectError::DuplicateCanonicalUrl(..) => (StatusCode::CONFLICT, "DUPLICATE_CANONICAL_URL"),
         ProjectError::DependencyError(_) => (StatusCode::CONFLICT, "DEPENDENCY_ERROR"),
         ProjectError::CircularDependency(_) => (StatusCode::CONFLICT, "CIRCULAR_DEPENDENCY"),
+        ProjectError::InvalidId(_) => (StatusCode::BAD_REQUEST, "INVALID_ID"),
         _ => (StatusCode::INTERNAL_SERVER_ERROR, "INTERNAL_ERROR"),
     };
 
@@ -361,14 +416,13 @@ async fn get_dependencies(
         .await
         .map_err(handle_error)?;
 
-    // Build dependency graph (validates the project exists and has valid structure)
-    let _graph = service
+    let graph = service
         .build_dependency_graph(&path.project_id)
         .await
         .map_err(handle_error)?;
 
     // Build response
-    let resources: Vec<ResourceNode> = index
+    let mut resources: Vec<ResourceNode> = index
         .resources
         .values()
         .map(|r| ResourceNode {
@@ -378,20 +432,201 @@ async fn get_dependencies(
             kind: r.kind,
         })
         .collect();
+    resources.sort_by(|a, b| a.canonical_url.cmp(&b.canonical_url));
+
+    let edges = graph
+        .edges()
+        .into_iter()
+        .map(|(from, to)| DependencyEdge {
+            from: from.to_string(),
+            to: to.to_string(),
+        })
+        .collect();
+
+    Ok(Json(ApiResponse::ok(DependencyGraphResponse {
+        resources,
+        edges,
+        cycles: graph.cycles(),
+    })))
+}
+
+/// GET /api/projects/:projectId/recent-canonicals
+/// Value sets and extensions recently referenced in the project, most recent first.
+async fn get_recent_canonicals(
+    State(state): State<AppState>,
+    Path(path): Path<ProjectPath>,
+) -> Result<Json<ApiResponse<Vec<RecentCanonical>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let service = ProjectService::new(state.workspace_dir().clone());
+
+    let recent = service
+        .load_recent_canonicals(&path.project_id)
+        .await
+        .map_err(handle_error)?;
+
+    Ok(Json(ApiResponse::ok(recent.entries)))
+}
+
+/// GET /api/projects/:projectId/dependencies/usage
+/// Report which declared package dependencies are used by project resources.
+async fn get_dependency_usage(
+    State(state): State<AppState>,
+    Path(path): Path<ProjectPath>,
+) -> Result<Json<ApiResponse<DependencyUsageResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let service = ProjectService::new(state.workspace_dir().clone());
+
+    let project = service
+        .load_project(&path.project_id)
+        .await
+        .map_err(handle_error)?;
+    let references = service
+        .external_references(&path.project_id)
+        .await
+        .map_err(handle_error)?;
+
+    let manager = state.canonical_manager().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::<()>::error(
+                "INTERNAL_ERROR",
+                format!("Canonical manager error: {}", e),
+            )),
+        )
+    })?;
+
+    let mut resolved = BTreeMap::new();
+    let mut unresolved = Vec::new();
+    for url in references {
+        match manager.resolve(&url).await {
+            Ok(resource) => {
+                resolved.insert(url, resource.package_info.name.clone());
+            }
+            Err(_) => unresolved.push(url),
+        }
+    }
+
+    let dependencies = compute_dependency_usage(&project.dependencies, &resolved);
+    let unused = dependencies
+        .iter()
+        .filter(|usage| !usage.used)
+        .map(|usage| usage.name.clone())
+        .collect();
+
+    Ok(Json(ApiResponse::ok(DependencyUsageResponse {
+        dependencies,
+        unused,
+        unresolved,
+    })))
+}
+
+/// POST /api/projects/:projectId/import/ig
+///
+/// Seed the project from an existing ImplementationGuide: `packageId`,
+/// `fhirVersion` and the packages in `dependsOn`. Referenced resources are
+/// listed, not fetched.
+async fn import_implementation_guide(
+    State(state): State<AppState>,
+    Path(path): Path<ProjectPath>,
+    Json(guide): Json<serde_json::Value>,
+) -> Result<Json<ApiResponse<ImplementationGuideImportResponse>>, (StatusCode, Json<ApiResponse<()>>)>
+{
+    let seed = ImplementationGuideSeed::from_implementation_guide(&guide).map_err(handle_error)?;
+
+    let service = ProjectService::new(state.workspace_dir().clone());
+    let project = service
+        .apply_implementation_guide(&path.project_id, &seed)
+        .await
+        .map_err(handle_error)?;
 
-    let mut edges = Vec::new();
-    for resource in index.resources.values() {
-        for dep in &resource.dependencies {
-            edges.push(DependencyEdge {
-                from: resource.canonical_url.clone(),
-                to: dep.clone(),
+    Ok(Json(ApiResponse::ok(ImplementationGuideImportResponse {
+        project,
+        resources: seed.resources,
+    })))
+}
+
+/// POST /api/projects/:projectId/compatibility
+///
+/// Check which canonicals referenced by the project's profiles a target
+/// server does not declare in its CapabilityStatement. Read-only.
+async fn check_compatibility(
+    State(state): State<AppState>,
+    Path(path): Path<ProjectPath>,
+    Json(statement): Json<serde_json::Value>,
+) -> Result<Json<ApiResponse<CompatibilityResponse>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if statement.get("resourceType").and_then(|v| v.as_str()) != Some("CapabilityStatement") {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<()>::error(
+                "INVALID_CAPABILITY_STATEMENT",
+                "Request body must be a CapabilityStatement resource",
+            )),
+        ));
+    }
+
+    let service = ProjectService::new(state.workspace_dir().clone());
+    let profiles = service
+        .profile_references(&path.project_id)
+        .await
+        .map_err(handle_error)?;
+    let capabilities = ServerCapabilities::from_capability_statement(&statement);
+
+    // Resolution only enriches messages; the report works without packages
+    let manager = match state.canonical_manager().await {
+        Ok(manager) => Some(manager),
+        Err(e) => {
+            tracing::warn!("Canonical manager unavailable for compatibility report: {}", e);
+            None
+        }
+    };
+
+    let mut reports = Vec::with_capacity(profiles.len());
+    for profile in &profiles {
+        let compatibility = compute_compatibility(profile, &capabilities);
+        let mut diagnostics = Vec::new();
+
+        if let Some(resource_type) = &compatibility.unsupported_resource_type {
+            diagnostics.push(Diagnostic {
+                severity: DiagnosticSeverity::Error,
+                code: "UNSUPPORTED_RESOURCE_TYPE".to_string(),
+                message: format!("Server does not support resource type {}", resource_type),
+                path: None,
+                ..Default::default()
+            });
+        }
+        for url in &compatibility.unsupported {
+            let package = match &manager {
+                Some(manager) => manager
+                    .resolve(url)
+                    .await
+                    .ok()
+                    .map(|resource| resource.package_info.name.clone()),
+                None => None,
+            };
+            let message = match package {
+                Some(package) => format!(
+                    "Server does not declare support for {} (from package {})",
+                    url, package
+                ),
+                None => format!("Server does not declare support for {}", url),
+            };
+            diagnostics.push(Diagnostic {
+                severity: DiagnosticSeverity::Warning,
+                code: "UNSUPPORTED_CANONICAL".to_string(),
+                message,
+                path: None,
+                ..Default::default()
             });
         }
+
+        reports.push(ProfileCompatibilityReport {
+            compatible: compatibility.is_compatible(),
+            compatibility,
+            diagnostics,
+        });
     }
 
-    Ok(Json(ApiResponse::ok(DependencyGraphResponse {
-        resources,
-        edges,
+    Ok(Json(ApiResponse::ok(CompatibilityResponse {
+        compatible: reports.iter().all(|report| report.compatible),
+        profiles: reports,
     })))
 }
 
@@ -0,0 +1,341 @@
+//! Element provenance ("explain inheritance") route handler.
+//!
+//! Answers "where did this constraint come from?" for a hydrated element:
+//! every constraint field that is set is reported as inherited from the base
+//! definition, or as modified or added by this profile, together with the
+//! URL of the definition it came from. Origins are recorded while the
+//! differential is merged onto the base tree; the IR itself does not carry
+//! them. Inherited fields are attributed to the nearest definition in the
+//! base chain whose differential sets them.
+//!
+//! # Routes
+//!
+//! - `GET /api/projects/:projectId/profiles/:profileId/elements/:path/provenance` - Explain element constraints
+//!
+//! Like the annotation routes, the `/provenance` suffix is split off the
+//! element wildcard segment, and the request is dispatched from the element
+//! `GET` handler.
+
+use std::collections::{BTreeSet, HashMap};
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::base::BaseResolver;
+use crate::import::ElementTreeBuilder;
+use crate::ir::element::element_definition_id;
+use crate::ir::{ElementNode, ElementSource, ProfileDocument};
+use crate::merge::{constraint_fields, differential_fields, ConstraintOrigins};
+use crate::state::AppState;
+
+use super::dto::ApiResponse;
+use super::profile_merge::hydrate_with_origins;
+use super::profiles::{ElementPath, ErrorResponse};
+
+// === Types ===
+
+/// Constraint provenance of one element.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ElementProvenance {
+    /// Element path.
+    pub path: String,
+    /// Whether the element itself is inherited, modified or added.
+    pub source: ElementSource,
+    /// Every constraint field set on the element.
+    pub fields: Vec<FieldProvenance>,
+}
+
+/// Provenance of a single constraint field.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldProvenance {
+    /// Field name (e.g. `cardinality`, `fixedValue`, `flags.mustSupport`).
+    pub field: String,
+    /// Effective value on the merged element.
+    pub value: serde_json::Value,
+    /// Where the value comes from.
+    pub origin: ElementSource,
+    /// Canonical URL of the definition that sets the value.
+    pub defined_by: String,
+}
+
+/// How many base definitions are followed at most when attributing
+/// inherited constraints.
+const MAX_BASE_CHAIN: usize = 16;
+
+/// The constraint fields one definition of the base chain sets itself.
+#[derive(Debug, Clone)]
+pub struct BaseLayer {
+    /// Canonical URL of the definition.
+    pub url: String,
+    /// Fields set by the definition's differential, by element id.
+    fields: HashMap<String, BTreeSet<String>>,
+}
+
+impl BaseLayer {
+    /// A definition whose differential is unknown.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            fields: HashMap::new(),
+        }
+    }
+
+    /// Read the fields set by a StructureDefinition's differential.
+    pub fn from_structure_definition(url: impl Into<String>, sd: &Value) -> Self {
+        let mut layer = Self::new(url);
+        let elements = sd
+            .pointer("/differential/element")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        match ElementTreeBuilder::new().build_differential_elements(elements) {
+            Ok(differential) => {
+                for diff in &differential {
+                    layer
+                        .fields
+                        .entry(element_definition_id(
+                            diff.element_id.as_deref(),
+                            &diff.path,
+                        ))
+                        .or_default()
+                        .extend(differential_fields(diff));
+                }
+            }
+            Err(e) => tracing::warn!("Skipping differential of '{}': {}", layer.url, e),
+        }
+        layer
+    }
+
+    /// Whether the definition sets `field` on the element with `element_id`.
+    fn sets(&self, element_id: &str, field: &str) -> bool {
+        self.fields
+            .get(element_id)
+            .is_some_and(|fields| fields.contains(field))
+    }
+}
+
+/// Split a wildcard element segment of the form `<path>/provenance`.
+pub fn provenance_target(segment: &str) -> Option<&str> {
+    segment
+        .trim_start_matches('/')
+        .strip_suffix("/provenance")
+}
+
+/// Explain where each constraint of a merged element comes from.
+///
+/// `base_chain` lists the definitions the profile inherits from, nearest
+/// first. An inherited field is attributed to the first of them whose
+/// differential sets it, or else to the last one.
+pub fn explain_element(
+    element: &ElementNode,
+    origins: &ConstraintOrigins,
+    base_chain: &[BaseLayer],
+    profile_url: &str,
+) -> ElementProvenance {
+    let element_id = element.element_definition_id();
+    let mut fields = constraint_fields(&element.constraints);
+    if let Some(slicing) = &element.slicing {
+        fields.push((
+            "slicing".to_string(),
+            serde_json::to_value(slicing).unwrap_or_default(),
+        ));
+    }
+
+    let fields = fields
+        .into_iter()
+        .map(|(field, value)| {
+            let origin = if !origins.is_from_differential(element.id, &field) {
+                ElementSource::Inherited
+            } else if element.source == ElementSource::Added {
+                ElementSource::Added
+            } else {
+                ElementSource::Modified
+            };
+            let defined_by = match origin {
+                ElementSource::Inherited => base_chain
+                    .iter()
+                    .find(|layer| layer.sets(&element_id, &field))
+                    .or(base_chain.last())
+                    .map_or("", |layer| layer.url.as_str()),
+                ElementSource::Modified | ElementSource::Added => profile_url,
+            };
+            FieldProvenance {
+                field,
+                value,
+                origin,
+                defined_by: defined_by.to_string(),
+            }
+        })
+        .collect();
+
+    ElementProvenance {
+        path: element.path.clone(),
+        source: element.source,
+        fields,
+    }
+}
+
+// === Route Handlers ===
+
+/// GET /api/projects/:projectId/profiles/:profileId/elements/:path/provenance
+///
+/// Dispatched from the element `GET` handler.
+pub(super) async fn element_provenance(
+    state: &AppState,
+    params: &ElementPath,
+    element_path: &str,
+) -> Response {
+    let storage = state.storage();
+
+    let doc = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(doc) => doc,
+        Err(e) => return Into::<(StatusCode, Json<ErrorResponse>)>::into(e).into_response(),
+    };
+    let (doc, origins) = match hydrate_with_origins(state, doc).await {
+        Ok(hydrated) => hydrated,
+        Err(e) => return e.into_response(),
+    };
+
+    let Some(element) = doc.resource.find_element(element_path) else {
+        return ErrorResponse::not_found("Element", element_path).into_response();
+    };
+
+    let base_chain = load_base_chain(state, &doc).await;
+    let provenance = explain_element(element, &origins, &base_chain, &doc.resource.url);
+    Json(ApiResponse::ok(provenance)).into_response()
+}
+
+/// Load the definitions a profile inherits from, nearest first.
+///
+/// The walk follows `baseDefinition` up to the first specialization (which
+/// defines its elements itself) and stops early when a definition cannot be
+/// resolved; the unresolved definition is still listed, without fields.
+async fn load_base_chain(state: &AppState, doc: &ProfileDocument) -> Vec<BaseLayer> {
+    let mut canonical = doc.resource.base.canonical();
+    let resolver = match state.canonical_manager().await {
+        Ok(canonical_manager) => BaseResolver::new(canonical_manager.clone())
+            .with_retry(state.config().resolve_retry_policy()),
+        Err(e) => {
+            tracing::warn!("Canonical manager unavailable for provenance: {}", e);
+            return vec![BaseLayer::new(canonical)];
+        }
+    };
+
+    let mut chain = Vec::new();
+    while chain.len() < MAX_BASE_CHAIN {
+        let Ok(sd) = resolver.load_base_sd_json(&canonical).await else {
+            chain.push(BaseLayer::new(canonical));
+            break;
+        };
+        chain.push(BaseLayer::from_structure_definition(canonical.clone(), &sd));
+
+        let next = sd.get("baseDefinition").and_then(Value::as_str);
+        match next {
+            Some(next) if sd.get("derivation").and_then(Value::as_str) == Some("constraint") => {
+                canonical = next.to_string();
+            }
+            _ => break,
+        }
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Binding, BindingStrength, Cardinality};
+    use crate::merge::{DifferentialElement, ElementTreeMerger};
+
+    const BASE_URL: &str = "http://hl7.org/fhir/StructureDefinition/Observation";
+    const PROFILE_URL: &str = "http://example.org/fhir/StructureDefinition/MyObservation";
+
+    fn field<'a>(provenance: &'a ElementProvenance, name: &str) -> &'a FieldProvenance {
+        provenance
+            .fields
+            .iter()
+            .find(|f| f.field == name)
+            .unwrap_or_else(|| panic!("missing field {name}"))
+    }
+
+    #[test]
+    fn test_inherited_vs_modified_fields() {
+        let mut base = ElementNode::new("Observation".to_string());
+        let mut code = ElementNode::new("Observation.code".to_string());
+        code.constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+        code.constraints.binding = Some(Binding::new(
+            BindingStrength::Example,
+            "http://hl7.org/fhir/ValueSet/observation-codes",
+        ));
+        base.add_child(code);
+
+        let mut diff = DifferentialElement::new("Observation.code".to_string());
+        diff.constraints.binding = Some(Binding::new(
+            BindingStrength::Required,
+            "http://example.org/fhir/ValueSet/my-codes",
+        ));
+
+        let (merged, origins) = ElementTreeMerger::new().merge_with_origins(base, &[diff]);
+        let code = merged.find_descendant("code").unwrap();
+        let provenance = explain_element(code, &origins, &[BaseLayer::new(BASE_URL)], PROFILE_URL);
+
+        assert_eq!(provenance.source, ElementSource::Modified);
+
+        let cardinality = field(&provenance, "cardinality");
+        assert_eq!(cardinality.origin, ElementSource::Inherited);
+        assert_eq!(cardinality.defined_by, BASE_URL);
+
+        let binding = field(&provenance, "binding");
+        assert_eq!(binding.origin, ElementSource::Modified);
+        assert_eq!(binding.defined_by, PROFILE_URL);
+        assert_eq!(binding.value["strength"], "required");
+    }
+
+    #[test]
+    fn test_inherited_fields_name_the_defining_base() {
+        const PARENT_URL: &str = "http://example.org/fhir/StructureDefinition/CodedObservation";
+
+        let parent = BaseLayer::from_structure_definition(
+            PARENT_URL,
+            &serde_json::json!({
+                "differential": { "element": [
+                    { "id": "Observation.code", "path": "Observation.code", "mustSupport": true }
+                ] }
+            }),
+        );
+        let base_chain = [parent, BaseLayer::new(BASE_URL)];
+
+        let mut code = ElementNode::new("Observation.code".to_string());
+        code.constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+        code.constraints.flags.must_support = true;
+        let provenance = explain_element(
+            &code,
+            &ConstraintOrigins::default(),
+            &base_chain,
+            PROFILE_URL,
+        );
+
+        let must_support = field(&provenance, "flags.mustSupport");
+        assert_eq!(must_support.origin, ElementSource::Inherited);
+        assert_eq!(must_support.defined_by, PARENT_URL);
+        assert_eq!(field(&provenance, "cardinality").defined_by, BASE_URL);
+    }
+
+    #[test]
+    fn test_provenance_target() {
+        assert_eq!(
+            provenance_target("/Observation.code/provenance"),
+            Some("Observation.code")
+        );
+        assert_eq!(provenance_target("Observation.code/annotations"), None);
+    }
+}
@@ -3,6 +3,7 @@
 //! Provides REST endpoints for searching FHIR resources across installed packages.
 
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use axum::{
     Json, Router,
@@ -12,13 +13,15 @@ use axum::{
     routing::get,
 };
 
+use crate::base::BaseResource;
+use crate::ir::FhirVersion;
 use crate::state::AppState;
 
 use super::packages_dto::{
-    BaseResourceDto, BaseResourceSearchQuery, ElementDto, ElementSearchQuery, ExtensionContextDto,
-    ExtensionDto, ExtensionSearchQuery, FacetsDto, PackageErrorResponse, ProfileDto,
-    ProfileSearchQuery, ResourceSearchQuery, SearchResponseWithFacets, SearchResultDto,
-    ValueSetDto, ValueSetSearchQuery,
+    BaseResourceListQuery, BaseResourceListResponse, BaseResourceSearchQuery, ElementDto,
+    ElementSearchQuery, ExtensionContextDto, ExtensionDto, ExtensionSearchQuery, FacetsDto,
+    PackageErrorResponse, ProfileDto, ProfileSearchQuery, ResourceSearchQuery,
+    SearchResponseWithFacets, SearchResultDto, ValueSetDto, ValueSetSearchQuery,
 };
 
 /// Create resource search routes.
@@ -32,6 +35,11 @@ pub fn search_routes() -> Router<AppState> {
         .route("/base-resources", get(search_base_resources))
 }
 
+/// Create the base resource listing route (mounted at `/api/base-resources`).
+pub fn base_resource_routes() -> Router<AppState> {
+    Router::new().route("/", get(list_base_resources))
+}
+
 /// Extract extension contexts from a StructureDefinition.
 fn extract_extension_contexts(content: &serde_json::Value) -> Vec<ExtensionContextDto> {
     let mut contexts = Vec::new();
@@ -117,14 +125,13 @@ async fn search_extensions(
         }
     }
 
-    builder = builder.limit(query.limit.unwrap_or(50) * 3);
-    if let Some(offset) = query.offset {
-        builder = builder.offset(offset);
-    }
+    // Facets count every match, so page locally rather than in the query
+    builder = builder.limit(MAX_MATCHES_SCANNED);
 
     match builder.execute().await {
         Ok(result) => {
             let limit = query.limit.unwrap_or(50);
+            let offset = query.offset.unwrap_or(0);
             let extensions: Vec<ExtensionDto> = result
                 .resources
                 .into_iter()
@@ -171,17 +178,17 @@ async fn search_extensions(
                         score: Some(r.score),
                     })
                 })
-                .take(limit)
                 .collect();
 
-            // Build facets
-            let mut facets = FacetsDto::default();
-            for ext in &extensions {
-                *facets.packages.entry(ext.package_name.clone()).or_insert(0) += 1;
-            }
+            // Facets describe every match, not just the returned page
+            let extensions = rank_by_score(extensions, query.min_score, |ext| ext.score);
+            let (extensions, total_count, facets) =
+                paginate_with_facets(extensions, offset, limit, |ext, facets| {
+                    count_facet(&mut facets.packages, &ext.package_name);
+                });
 
             Json(SearchResponseWithFacets {
-                total_count: result.total_count,
+                total_count,
                 results: extensions,
                 facets: Some(facets),
             })
@@ -230,14 +237,13 @@ async fn search_valuesets(
         }
     }
 
-    builder = builder.limit(query.limit.unwrap_or(50) * 2);
-    if let Some(offset) = query.offset {
-        builder = builder.offset(offset);
-    }
+    // Facets count every match, so page locally rather than in the query
+    builder = builder.limit(MAX_MATCHES_SCANNED);
 
     match builder.execute().await {
         Ok(result) => {
             let limit = query.limit.unwrap_or(50);
+            let offset = query.offset.unwrap_or(0);
             let valuesets: Vec<ValueSetDto> = result
                 .resources
                 .into_iter()
@@ -281,17 +287,17 @@ async fn search_valuesets(
                         score: Some(r.score),
                     })
                 })
-                .take(limit)
                 .collect();
 
-            // Build facets
-            let mut facets = FacetsDto::default();
-            for vs in &valuesets {
-                *facets.packages.entry(vs.package_name.clone()).or_insert(0) += 1;
-            }
+            // Facets describe every match, not just the returned page
+            let valuesets = rank_by_score(valuesets, query.min_score, |vs| vs.score);
+            let (valuesets, total_count, facets) =
+                paginate_with_facets(valuesets, offset, limit, |vs, facets| {
+                    count_facet(&mut facets.packages, &vs.package_name);
+                });
 
             Json(SearchResponseWithFacets {
-                total_count: result.total_count,
+                total_count,
                 results: valuesets,
                 facets: Some(facets),
             })
@@ -307,6 +313,51 @@ async fn search_valuesets(
     }
 }
 
+/// Drop matches below `min_score` and order the rest by descending score.
+///
+/// The sort is stable, so equally scored matches keep the manager's order.
+fn rank_by_score<T>(
+    mut items: Vec<T>,
+    min_score: Option<f64>,
+    score_of: impl Fn(&T) -> Option<f64>,
+) -> Vec<T> {
+    let score = |item: &T| score_of(item).unwrap_or(0.0);
+    if let Some(min) = min_score {
+        items.retain(|item| score(item) >= min);
+    }
+    items.sort_by(|a, b| score(b).total_cmp(&score(a)));
+    items
+}
+
+/// Upper bound on matches fetched for extension, value set, profile and
+/// resource search; facets and paging cover all of them.
+const MAX_MATCHES_SCANNED: usize = 1000;
+
+/// Count facets over all matches, then cut out the requested page.
+///
+/// Returns `(page, total_count, facets)` where `total_count` and the facets
+/// describe every filtered match, not just the returned page.
+fn paginate_with_facets<T>(
+    items: Vec<T>,
+    offset: usize,
+    limit: usize,
+    count: impl Fn(&T, &mut FacetsDto),
+) -> (Vec<T>, usize, FacetsDto) {
+    let total_count = items.len();
+    let mut facets = FacetsDto::default();
+    for item in &items {
+        count(item, &mut facets);
+    }
+
+    let page = items.into_iter().skip(offset).take(limit).collect();
+    (page, total_count, facets)
+}
+
+/// Add one match to a facet's counts.
+fn count_facet(counts: &mut HashMap<String, usize>, value: &str) {
+    *counts.entry(value.to_string()).or_insert(0) += 1;
+}
+
 /// Check if a ValueSet uses a specific code system.
 fn check_valueset_uses_system(content: &serde_json::Value, system: &str) -> bool {
     // Check compose.include
@@ -371,14 +422,13 @@ async fn search_profiles(
         }
     }
 
-    builder = builder.limit(query.limit.unwrap_or(50) * 3);
-    if let Some(offset) = query.offset {
-        builder = builder.offset(offset);
-    }
+    // Facets count every match, so page locally rather than in the query
+    builder = builder.limit(MAX_MATCHES_SCANNED);
 
     match builder.execute().await {
         Ok(result) => {
             let limit = query.limit.unwrap_or(50);
+            let offset = query.offset.unwrap_or(0);
             let profiles: Vec<ProfileDto> = result
                 .resources
                 .into_iter()
@@ -442,24 +492,17 @@ async fn search_profiles(
                         score: Some(r.score),
                     })
                 })
-                .take(limit)
                 .collect();
-
-            // Build facets
-            let mut facets = FacetsDto::default();
-            for profile in &profiles {
-                *facets
-                    .resource_types
-                    .entry(profile.base_type.clone())
-                    .or_insert(0) += 1;
-                *facets
-                    .packages
-                    .entry(profile.package_name.clone())
-                    .or_insert(0) += 1;
-            }
+            // Facets describe every match, not just the returned page
+            let profiles = rank_by_score(profiles, query.min_score, |p| p.score);
+            let (profiles, total_count, facets) =
+                paginate_with_facets(profiles, offset, limit, |p, facets| {
+                    count_facet(&mut facets.resource_types, &p.base_type);
+                    count_facet(&mut facets.packages, &p.package_name);
+                });
 
             Json(SearchResponseWithFacets {
-                total_count: result.total_count,
+                total_count,
                 results: profiles,
                 facets: Some(facets),
             })
@@ -475,7 +518,14 @@ async fn search_profiles(
     }
 }
 
-/// GET /api/search/elements - Search elements within a profile.
+/// Default page size for element search.
+const DEFAULT_ELEMENT_LIMIT: usize = 100;
+
+/// Upper bound on profiles scanned when searching across all installed profiles.
+const MAX_PROFILES_SCANNED: usize = 1000;
+
+/// GET /api/search/elements - Search elements within a profile, or across
+/// installed profiles when no profile is given.
 async fn search_elements(
     State(state): State<AppState>,
     Query(query): Query<ElementSearchQuery>,
@@ -493,52 +543,141 @@ async fn search_elements(
         }
     };
 
-    // Get the profile by URL or ID
-    let profile = match manager.resolve(&query.profile_id).await {
-        Ok(p) => p,
-        Err(_) => {
-            // Try by ID pattern with full URL
-            match manager
-                .resolve(&format!(
-                    "http://hl7.org/fhir/StructureDefinition/{}",
-                    query.profile_id
-                ))
-                .await
-            {
-                Ok(p) => p,
-                Err(_) => {
-                    return (
-                        StatusCode::NOT_FOUND,
-                        Json(PackageErrorResponse::not_found(format!(
-                            "Profile not found: {}",
-                            query.profile_id
-                        ))),
-                    )
-                        .into_response();
+    let filter = ElementFilter {
+        text: query.q.as_deref().filter(|q| !q.is_empty()).map(str::to_lowercase),
+        must_support_only: query.must_support_only,
+    };
+
+    let mut elements = Vec::new();
+
+    if let Some(profile_id) = &query.profile_id {
+        // Get the profile by URL or ID
+        let profile = match manager.resolve(profile_id).await {
+            Ok(p) => p,
+            Err(_) => {
+                // Try by ID pattern with full URL
+                match manager
+                    .resolve(&format!(
+                        "http://hl7.org/fhir/StructureDefinition/{}",
+                        profile_id
+                    ))
+                    .await
+                {
+                    Ok(p) => p,
+                    Err(_) => {
+                        return (
+                            StatusCode::NOT_FOUND,
+                            Json(PackageErrorResponse::not_found(format!(
+                                "Profile not found: {}",
+                                profile_id
+                            ))),
+                        )
+                            .into_response();
+                    }
                 }
             }
+        };
+
+        let content = &profile.resource.content;
+        if matches_resource_type(content, query.resource_type.as_deref()) {
+            elements = extract_elements(content, &filter);
+        }
+    } else {
+        let builder = manager
+            .search()
+            .await
+            .resource_type("StructureDefinition")
+            .limit(MAX_PROFILES_SCANNED);
+
+        let result = match builder.execute().await {
+            Ok(result) => result,
+            Err(e) => {
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(PackageErrorResponse::install_failed(format!(
+                        "Search failed: {e}"
+                    ))),
+                )
+                    .into_response();
+            }
+        };
+
+        for r in result.resources {
+            let content = &r.resource.content;
+            if !matches_resource_type(content, query.resource_type.as_deref()) {
+                continue;
+            }
+            elements.extend(extract_elements(content, &filter).into_iter().map(|mut e| {
+                e.package_name = Some(r.index.package_name.clone());
+                e
+            }));
         }
-    };
+    }
 
-    // Extract elements from snapshot or differential
-    let content = &profile.resource.content;
-    let elements = extract_elements(content, query.q.as_deref(), query.limit.unwrap_or(100));
+    let (results, total_count, facets) = paginate_elements(
+        elements,
+        query.offset.unwrap_or(0),
+        query.limit.unwrap_or(DEFAULT_ELEMENT_LIMIT),
+    );
 
     Json(SearchResponseWithFacets {
-        total_count: elements.len(),
-        results: elements,
-        facets: None,
+        total_count,
+        results,
+        facets: Some(facets),
     })
     .into_response()
 }
 
-/// Extract elements from a StructureDefinition.
-fn extract_elements(
-    content: &serde_json::Value,
-    query: Option<&str>,
+/// Filters applied to individual elements during element search.
+#[derive(Debug, Default)]
+struct ElementFilter {
+    /// Lowercased text matched against path, short and definition.
+    text: Option<String>,
+    /// Only keep elements flagged mustSupport.
+    must_support_only: bool,
+}
+
+/// Check a StructureDefinition against an optional resource type filter.
+fn matches_resource_type(content: &serde_json::Value, resource_type: Option<&str>) -> bool {
+    match resource_type {
+        Some(resource_type) => {
+            content.get("type").and_then(|v| v.as_str()) == Some(resource_type)
+        }
+        None => true,
+    }
+}
+
+/// Count facets over all matches, then cut out the requested page.
+///
+/// Returns `(page, total_count, facets)` where `total_count` and the facets
+/// describe the full match set, not just the returned page.
+fn paginate_elements(
+    elements: Vec<ElementDto>,
+    offset: usize,
     limit: usize,
-) -> Vec<ElementDto> {
+) -> (Vec<ElementDto>, usize, FacetsDto) {
+    let total_count = elements.len();
+
+    let mut facets = FacetsDto::default();
+    for element in &elements {
+        let resource_type = element.path.split('.').next().unwrap_or_default();
+        *facets
+            .resource_types
+            .entry(resource_type.to_string())
+            .or_insert(0) += 1;
+        if let Some(package_name) = &element.package_name {
+            *facets.packages.entry(package_name.clone()).or_insert(0) += 1;
+        }
+    }
+
+    let page = elements.into_iter().skip(offset).take(limit).collect();
+    (page, total_count, facets)
+}
+
+/// Extract matching elements from a StructureDefinition.
+fn extract_elements(content: &serde_json::Value, filter: &ElementFilter) -> Vec<ElementDto> {
     let mut elements = Vec::new();
+    let profile_url = content.get("url").and_then(|v| v.as_str()).map(String::from);
 
     // Prefer snapshot, fall back to differential
     let element_source = content
@@ -547,15 +686,21 @@ fn extract_elements(
         .or_else(|| content.get("differential").and_then(|d| d.get("element")));
 
     if let Some(element_arr) = element_source.and_then(|e| e.as_array()) {
-        let query_lower = query.map(|q| q.to_lowercase());
-
         for elem in element_arr {
             let path = elem.get("path").and_then(|v| v.as_str()).unwrap_or("");
             let short = elem.get("short").and_then(|v| v.as_str());
             let definition = elem.get("definition").and_then(|v| v.as_str());
+            let must_support = elem
+                .get("mustSupport")
+                .and_then(|v| v.as_bool())
+                .unwrap_or(false);
+
+            if filter.must_support_only && !must_support {
+                continue;
+            }
 
             // Apply text filter if provided
-            if let Some(ref q) = query_lower {
+            if let Some(ref q) = filter.text {
                 let path_match = path.to_lowercase().contains(q);
                 let short_match = short.map(|s| s.to_lowercase().contains(q)).unwrap_or(false);
                 let def_match = definition
@@ -589,11 +734,10 @@ fn extract_elements(
                 types,
                 min,
                 max,
+                must_support,
+                profile_url: profile_url.clone(),
+                package_name: None,
             });
-
-            if elements.len() >= limit {
-                break;
-            }
         }
     }
 
@@ -639,23 +783,19 @@ async fn search_resources(
         }
     }
 
-    builder = builder.limit(query.limit.unwrap_or(50));
-    if let Some(offset) = query.offset {
-        builder = builder.offset(offset);
-    }
+    // Facets count every match, so page locally rather than in the query
+    builder = builder.limit(MAX_MATCHES_SCANNED);
 
     match builder.execute().await {
         Ok(result) => {
-            let total = result.total_count;
-
-            // Build facet counts before filtering
-            let mut type_counts: HashMap<String, usize> = HashMap::new();
-            let mut package_counts: HashMap<String, usize> = HashMap::new();
-
             let resources: Vec<SearchResultDto> = result
                 .resources
                 .into_iter()
                 .filter_map(|r| {
+                    if query.min_score.is_some_and(|min| r.score < min) {
+                        return None;
+                    }
+
                     // Apply FHIR version filter
                     if let Some(ref fhir_ver) = query.fhir_version {
                         let res_fhir_ver = r
@@ -670,13 +810,6 @@ async fn search_resources(
                     }
 
                     let index = &r.index;
-
-                    // Count for facets
-                    *type_counts.entry(index.resource_type.clone()).or_insert(0) += 1;
-                    *package_counts
-                        .entry(index.package_name.clone())
-                        .or_insert(0) += 1;
-
                     Some(SearchResultDto {
                         id: index.id.clone().unwrap_or_default(),
                         url: index.canonical_url.clone(),
@@ -701,14 +834,21 @@ async fn search_resources(
                 })
                 .collect();
 
-            let facets = FacetsDto {
-                resource_types: type_counts,
-                packages: package_counts,
-            };
+            // Facets describe every match, not just the returned page
+            let resources = rank_by_score(resources, None, |r| r.score);
+            let (resources, total_count, facets) = paginate_with_facets(
+                resources,
+                query.offset.unwrap_or(0),
+                query.limit.unwrap_or(50),
+                |r, facets| {
+                    count_facet(&mut facets.resource_types, &r.resource_type);
+                    count_facet(&mut facets.packages, &r.package_name);
+                },
+            );
 
             Json(SearchResponseWithFacets {
                 results: resources,
-                total_count: total,
+                total_count,
                 facets: Some(facets),
             })
             .into_response()
@@ -752,7 +892,7 @@ async fn search_base_resources(
             let text_filter = query.q.as_ref().map(|q| q.to_lowercase());
 
             // Filter by text query if provided and build response
-            let mut resources: Vec<BaseResourceDto> = type_names
+            let mut resources: Vec<BaseResource> = type_names
                 .into_iter()
                 .filter(|name| {
                     // Skip Extension type
@@ -768,7 +908,7 @@ async fn search_base_resources(
                 .map(|name| {
                     // Build canonical URL from type name
                     let url = format!("http://hl7.org/fhir/StructureDefinition/{}", name);
-                    BaseResourceDto {
+                    BaseResource {
                         name: name.clone(),
                         url,
                         title: Some(name.clone()), // Use name as title for base resources
@@ -799,3 +939,271 @@ async fn search_base_resources(
             .into_response(),
     }
 }
+
+/// Whether a StructureDefinition defines a concrete, profileable resource type.
+fn is_profileable_base(kind: Option<&str>, derivation: Option<&str>, content: &serde_json::Value) -> bool {
+    kind == Some("resource")
+        && derivation == Some("specialization")
+        && !content
+            .get("abstract")
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or(false)
+}
+
+/// GET /api/base-resources?fhirVersion= - Base resource types of a core package.
+///
+/// Results are cached per FHIR version until installed packages change.
+async fn list_base_resources(
+    State(state): State<AppState>,
+    Query(query): Query<BaseResourceListQuery>,
+) -> Response {
+    let requested = query.fhir_version.as_deref().unwrap_or("R4");
+    let Some(fhir_version) = FhirVersion::from_str(requested) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(PackageErrorResponse::bad_request(format!(
+                "Invalid FHIR version: {requested}. Valid values: R4, R4B, R5, R6"
+            ))),
+        )
+            .into_response();
+    };
+    let package_name = fhir_version.base_package();
+
+    if let Some(resources) = state.cached_base_resources(fhir_version) {
+        return Json(BaseResourceListResponse {
+            fhir_version: fhir_version.label().to_string(),
+            package_name: package_name.to_string(),
+            resources: resources.as_ref().clone(),
+        })
+        .into_response();
+    }
+
+    let manager = match state.canonical_manager().await {
+        Ok(m) => m,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PackageErrorResponse::lookup_failed(format!(
+                    "Failed to initialize package manager: {e}"
+                ))),
+            )
+                .into_response();
+        }
+    };
+
+    let result = manager
+        .search()
+        .await
+        .resource_type("StructureDefinition")
+        .package(package_name)
+        .limit(5000)
+        .execute()
+        .await;
+    let result = match result {
+        Ok(result) => result,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(PackageErrorResponse::lookup_failed(format!(
+                    "Failed to list base resources: {e}"
+                ))),
+            )
+                .into_response();
+        }
+    };
+
+    let mut resources: Vec<BaseResource> = result
+        .resources
+        .into_iter()
+        .filter(|r| {
+            is_profileable_base(
+                r.index.sd_kind.as_deref(),
+                r.index.sd_derivation.as_deref(),
+                &r.resource.content,
+            )
+        })
+        .filter_map(|r| {
+            let name = r.index.sd_type.clone()?;
+            Some(BaseResource {
+                title: r
+                    .resource
+                    .content
+                    .get("title")
+                    .and_then(|v: &serde_json::Value| v.as_str())
+                    .map(String::from),
+                description: None,
+                url: r.index.canonical_url.clone(),
+                package_name: r.index.package_name.clone(),
+                package_version: r.index.package_version.clone(),
+                name,
+            })
+        })
+        .collect();
+    resources.sort_by(|a, b| a.name.cmp(&b.name));
+    resources.dedup_by(|a, b| a.name == b.name);
+
+    let resources = Arc::new(resources);
+    state.cache_base_resources(fhir_version, resources.clone());
+
+    Json(BaseResourceListResponse {
+        fhir_version: fhir_version.label().to_string(),
+        package_name: package_name.to_string(),
+        resources: resources.as_ref().clone(),
+    })
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn test_sd() -> serde_json::Value {
+        json!({
+            "url": "http://example.org/fhir/StructureDefinition/MyPatient",
+            "type": "Patient",
+            "snapshot": {
+                "element": [
+                    { "path": "Patient" },
+                    { "path": "Patient.name", "short": "A name", "mustSupport": true },
+                    { "path": "Patient.gender", "mustSupport": false },
+                    { "path": "Patient.birthDate", "mustSupport": true }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn test_extract_elements_must_support_only() {
+        let filter = ElementFilter {
+            text: None,
+            must_support_only: true,
+        };
+        let elements = extract_elements(&test_sd(), &filter);
+
+        let paths: Vec<&str> = elements.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["Patient.name", "Patient.birthDate"]);
+        assert!(elements.iter().all(|e| e.must_support));
+        assert_eq!(
+            elements[0].profile_url.as_deref(),
+            Some("http://example.org/fhir/StructureDefinition/MyPatient")
+        );
+    }
+
+    #[test]
+    fn test_matches_resource_type() {
+        assert!(matches_resource_type(&test_sd(), None));
+        assert!(matches_resource_type(&test_sd(), Some("Patient")));
+        assert!(!matches_resource_type(&test_sd(), Some("Observation")));
+    }
+
+    fn test_valueset(name: &str, package_name: &str) -> ValueSetDto {
+        ValueSetDto {
+            id: name.to_string(),
+            url: format!("http://example.org/fhir/ValueSet/{name}"),
+            name: name.to_string(),
+            title: None,
+            description: None,
+            status: None,
+            package_name: package_name.to_string(),
+            package_version: "1.0.0".to_string(),
+            score: None,
+        }
+    }
+
+    #[test]
+    fn test_facets_counted_before_truncation() {
+        let valuesets = vec![
+            test_valueset("a", "pkg.one"),
+            test_valueset("b", "pkg.one"),
+            test_valueset("c", "pkg.two"),
+            test_valueset("d", "pkg.one"),
+            test_valueset("e", "pkg.two"),
+        ];
+
+        let (results, total_count, facets) = paginate_with_facets(valuesets, 1, 2, |vs, facets| {
+            count_facet(&mut facets.packages, &vs.package_name);
+        });
+
+        let names: Vec<&str> = results.iter().map(|vs| vs.name.as_str()).collect();
+        assert_eq!(names, vec!["b", "c"]);
+        assert_eq!(total_count, 5);
+        assert_eq!(facets.packages.get("pkg.one"), Some(&3));
+        assert_eq!(facets.packages.get("pkg.two"), Some(&2));
+        let facet_total: usize = facets.packages.values().sum();
+        assert!(facet_total > results.len());
+    }
+
+    #[test]
+    fn test_rank_by_score_descending() {
+        let scored = |name: &str, score: f64| ValueSetDto {
+            score: Some(score),
+            ..test_valueset(name, "pkg.one")
+        };
+        let valuesets = vec![
+            scored("low", 0.2),
+            scored("high", 0.9),
+            test_valueset("unscored", "pkg.one"),
+            scored("mid", 0.5),
+        ];
+
+        let ranked = rank_by_score(valuesets.clone(), None, |vs| vs.score);
+        let names: Vec<&str> = ranked.iter().map(|vs| vs.name.as_str()).collect();
+        assert_eq!(names, vec!["high", "mid", "low", "unscored"]);
+        assert!(ranked
+            .windows(2)
+            .all(|w| w[0].score.unwrap_or(0.0) >= w[1].score.unwrap_or(0.0)));
+
+        let ranked = rank_by_score(valuesets, Some(0.4), |vs| vs.score);
+        let names: Vec<&str> = ranked.iter().map(|vs| vs.name.as_str()).collect();
+        assert_eq!(names, vec!["high", "mid"]);
+
+        // The total counts the matches left after the score cut, not the page
+        let (page, total_count, _) = paginate_with_facets(ranked, 0, 1, |_, _| {});
+        assert_eq!(page.len(), 1);
+        assert_eq!(total_count, 2);
+    }
+
+    #[test]
+    fn test_paginate_elements_total_before_truncation() {
+        let elements = extract_elements(&test_sd(), &ElementFilter::default());
+        assert_eq!(elements.len(), 4);
+
+        let (page, total_count, facets) = paginate_elements(elements, 1, 2);
+        assert_eq!(total_count, 4);
+        assert_eq!(facets.resource_types.get("Patient"), Some(&4));
+
+        let paths: Vec<&str> = page.iter().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, vec!["Patient.name", "Patient.gender"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_base_resources_rejects_invalid_version() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let query = BaseResourceListQuery {
+            fhir_version: Some("R9".to_string()),
+        };
+
+        let response = list_base_resources(State(state), Query(query)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["code"], "BAD_REQUEST");
+        assert_eq!(json["status"], 400);
+    }
+
+    #[test]
+    fn test_is_profileable_base() {
+        let concrete = json!({ "type": "Patient", "abstract": false });
+        let abstract_type = json!({ "type": "DomainResource", "abstract": true });
+
+        assert!(is_profileable_base(Some("resource"), Some("specialization"), &concrete));
+        assert!(!is_profileable_base(Some("resource"), Some("specialization"), &abstract_type));
+        // Profiles and datatypes are not base resources
+        assert!(!is_profileable_base(Some("resource"), Some("constraint"), &concrete));
+        assert!(!is_profileable_base(Some("complex-type"), Some("specialization"), &concrete));
+    }
+}
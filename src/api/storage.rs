@@ -5,8 +5,12 @@
 //! <workspace>/<projectId>/
 //! ├── IR/
 //! │   ├── index.json          # Index of all profiles
-//! │   └── resources/
-//! │       └── <profileId>.json # Profile IR documents
+//! │   ├── resources/
+//! │   │   └── <profileId>.json # Profile IR documents
+//! │   ├── annotations/
+//! │   │   └── <profileId>.json # Element review annotations
+//! │   └── hashes/
+//! │       └── <profileId>.sha256 # Content hash of the stored profile
 //! ├── SD/
 //! │   └── StructureDefinition/
 //! │       └── <name>.json      # Exported SD JSON files
@@ -14,16 +18,23 @@
 //!     └── profiles/
 //!         └── <name>.fsh       # FSH source files
 //! ```
+//!
+//! This is the filesystem layout behind [`FileStorage`](super::persistence::FileStorage),
+//! the default [`Storage`](super::persistence::Storage) backend.
 
 use std::path::{Path, PathBuf};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
 
 use crate::ir::ProfileDocument;
 
+use super::annotations::Annotation;
+use crate::paths::{validate_path_id, InvalidPathId};
+
 /// Profile storage service.
 #[derive(Debug, Clone)]
 pub struct ProfileStorage {
@@ -125,10 +136,64 @@ pub enum StorageError {
     /// Concurrent modification detected.
     #[error("Concurrent modification detected for profile: {0}")]
     ConcurrentModification(String),
+
+    /// Id or name cannot be used as a file name.
+    #[error(transparent)]
+    InvalidId(#[from] InvalidPathId),
 }
 
 pub type StorageResult<T> = Result<T, StorageError>;
 
+/// The form a profile is persisted in: differential-only, without the
+/// merged element tree, which is rebuilt from the base on load.
+///
+/// Handlers edit the hydrated tree, so whenever a document was hydrated the
+/// differential is re-derived from it. Exports (SD and FSH) read the
+/// differential; deriving it here keeps every save path in sync with the
+/// tree, not just the ones that remember to extract. Documents that were
+/// never hydrated (loaded or imported as-is) keep their differential, unless
+/// they only have a tree.
+pub fn stored_form(doc: &ProfileDocument) -> ProfileDocument {
+    let mut stored = doc.clone();
+    let tree_only = stored.resource.differential.is_empty() && !stored.resource.root.is_empty();
+    if stored.resource.hydrated || tree_only {
+        stored.resource.extract_differential();
+    }
+    stored.resource.root = crate::ir::ElementNode::default();
+    stored
+}
+
+/// Hash recorded for a stored profile.
+///
+/// Covers the content (see [`ProfileDocument::content_hash`]) and the edit
+/// history, which is stored with the profile: undo, redo and squash change
+/// only the history and must still be written.
+fn stored_hash_of(stored: &ProfileDocument) -> String {
+    let history = serde_json::to_vec(&stored.history).unwrap_or_default();
+    format!("{}-{:x}", stored.content_hash(), Sha256::digest(&history))
+}
+
+/// Entity tag of stored profile content.
+pub(super) fn etag_of(content: &[u8]) -> String {
+    let digest = Sha256::digest(content);
+    format!("{:x}", digest)[..16].to_string()
+}
+
+/// Check an `If-Match` header value against a profile's current entity tag.
+pub(super) fn check_if_match(
+    profile_id: &str,
+    current: &str,
+    if_match: Option<&str>,
+) -> StorageResult<()> {
+    if let Some(expected) = if_match {
+        let expected = expected.trim().trim_start_matches("W/").trim_matches('"');
+        if expected != "*" && expected != current {
+            return Err(StorageError::ConcurrentModification(profile_id.to_string()));
+        }
+    }
+    Ok(())
+}
+
 impl ProfileStorage {
     /// Create a new storage instance for a project.
     pub fn new(project_dir: impl Into<PathBuf>) -> Self {
@@ -158,8 +223,27 @@ impl ProfileStorage {
     }
 
     /// Get the profile file path.
-    fn profile_path(&self, profile_id: &str) -> PathBuf {
-        self.ir_resources_dir().join(format!("{}.json", profile_id))
+    fn profile_path(&self, profile_id: &str) -> StorageResult<PathBuf> {
+        validate_path_id("profile id", profile_id)?;
+        Ok(self.ir_resources_dir().join(format!("{}.json", profile_id)))
+    }
+
+    /// Get the content hash file path for a profile.
+    fn hash_path(&self, profile_id: &str) -> StorageResult<PathBuf> {
+        validate_path_id("profile id", profile_id)?;
+        Ok(self
+            .ir_dir()
+            .join("hashes")
+            .join(format!("{}.sha256", profile_id)))
+    }
+
+    /// Get the annotations file path for a profile.
+    fn annotations_path(&self, profile_id: &str) -> StorageResult<PathBuf> {
+        validate_path_id("profile id", profile_id)?;
+        Ok(self
+            .ir_dir()
+            .join("annotations")
+            .join(format!("{}.json", profile_id)))
     }
 
     /// Get the SD directory path.
@@ -172,6 +256,18 @@ impl ProfileStorage {
         self.project_dir.join("FSH").join("profiles")
     }
 
+    /// Get the SD JSON file path for a profile name.
+    pub fn sd_path(&self, name: &str) -> StorageResult<PathBuf> {
+        validate_path_id("profile name", name)?;
+        Ok(self.sd_dir().join(format!("{}.json", name)))
+    }
+
+    /// Get the FSH file path for a profile name.
+    pub fn fsh_path(&self, name: &str) -> StorageResult<PathBuf> {
+        validate_path_id("profile name", name)?;
+        Ok(self.fsh_dir().join(format!("{}.fsh", name)))
+    }
+
     /// Get the project config file path.
     fn config_path(&self) -> PathBuf {
         self.project_dir.join("project.json")
@@ -272,7 +368,7 @@ impl ProfileStorage {
 
     /// Load a profile by ID.
     pub async fn load_profile(&self, profile_id: &str) -> StorageResult<ProfileDocument> {
-        let path = self.profile_path(profile_id);
+        let path = self.profile_path(profile_id)?;
         if !path.exists() {
             return Err(StorageError::NotFound(profile_id.to_string()));
         }
@@ -283,25 +379,79 @@ impl ProfileStorage {
     }
 
     /// Save a profile to disk.
+    ///
+    /// Nothing is written if the stored profile already has the same content
+    /// and edit history (see [`stored_hash_of`]), so no-op saves keep the file
+    /// and its modification time. The hash of the last write is kept next to
+    /// the profile, so the check does not read the profile itself.
     pub async fn save_profile(&self, doc: &ProfileDocument) -> StorageResult<()> {
-        // Ensure directories exist
-        fs::create_dir_all(self.ir_resources_dir()).await?;
+        let stored = stored_form(doc);
+        let profile_exists = self.profile_path(&doc.metadata.id)?.exists();
+        if profile_exists
+            && self.stored_hash(&doc.metadata.id).await? == Some(stored_hash_of(&stored))
+        {
+            return Ok(());
+        }
+        self.write_profile(doc, &stored, None, None).await
+    }
 
-        // Save profile document (persist differential-only IR)
-        let path = self.profile_path(&doc.metadata.id);
-        let mut doc_to_save = doc.clone();
-        if doc_to_save.resource.differential.is_empty() && !doc_to_save.resource.root.is_empty() {
-            doc_to_save.resource.extract_differential();
+    /// Content hash recorded when a profile was last written, if any.
+    async fn stored_hash(&self, profile_id: &str) -> StorageResult<Option<String>> {
+        match fs::read_to_string(self.hash_path(profile_id)?).await {
+            Ok(hash) => Ok(Some(hash.trim().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
         }
-        doc_to_save.resource.root = crate::ir::ElementNode::default();
-        let content = serde_json::to_string_pretty(&doc_to_save)?;
+    }
 
-        // Write atomically
-        let temp_path = path.with_extension("json.tmp");
-        let mut file = fs::File::create(&temp_path).await?;
-        file.write_all(content.as_bytes()).await?;
-        file.sync_all().await?;
-        fs::rename(&temp_path, &path).await?;
+    /// Save a profile together with its SD JSON and FSH source files.
+    ///
+    /// The files are written as one transaction (see [`atomic_write_all`]),
+    /// IR last: if any write fails, none of the files change, so the IR never
+    /// disagrees with the sources saved next to it.
+    pub async fn save_profile_with_sources(
+        &self,
+        doc: &ProfileDocument,
+        sd_json: Option<&str>,
+        fsh: Option<&str>,
+    ) -> StorageResult<()> {
+        self.write_profile(doc, &stored_form(doc), sd_json, fsh)
+            .await
+    }
+
+    /// Write the stored form of `doc`, its content hash and any sources.
+    async fn write_profile(
+        &self,
+        doc: &ProfileDocument,
+        stored: &ProfileDocument,
+        sd_json: Option<&str>,
+        fsh: Option<&str>,
+    ) -> StorageResult<()> {
+        let mut files = Vec::with_capacity(3);
+        if let Some(sd_json) = sd_json {
+            fs::create_dir_all(self.sd_dir()).await?;
+            files.push((self.sd_path(&doc.metadata.name)?, sd_json.to_string()));
+        }
+        if let Some(fsh) = fsh {
+            fs::create_dir_all(self.fsh_dir()).await?;
+            files.push((self.fsh_path(&doc.metadata.name)?, fsh.to_string()));
+        }
+
+        // Save profile document (persist differential-only IR)
+        fs::create_dir_all(self.ir_resources_dir()).await?;
+        let content = serde_json::to_string_pretty(stored)?;
+        files.push((self.profile_path(&doc.metadata.id)?, content));
+
+        // Drop the recorded hash first: if the write is interrupted there is
+        // no hash, and the next save writes again rather than being skipped
+        let hash_path = self.clear_stored_hash(&doc.metadata.id).await?;
+
+        atomic_write_all(&files).await?;
+
+        if let Some(dir) = hash_path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        atomic_write_all(&[(hash_path, stored_hash_of(stored))]).await?;
 
         // Update index
         self.update_index_entry(doc).await?;
@@ -309,6 +459,81 @@ impl ProfileStorage {
         Ok(())
     }
 
+    /// Save several profiles as one transaction.
+    ///
+    /// The IR files of all profiles are written together (see
+    /// [`atomic_write_all`]): if any write fails, none of the profiles change.
+    pub async fn save_profiles(&self, docs: &[ProfileDocument]) -> StorageResult<()> {
+        fs::create_dir_all(self.ir_resources_dir()).await?;
+        let mut files = Vec::with_capacity(docs.len());
+        let mut hashes = Vec::with_capacity(docs.len());
+        for doc in docs {
+            let stored = stored_form(doc);
+            let content = serde_json::to_string_pretty(&stored)?;
+            files.push((self.profile_path(&doc.metadata.id)?, content));
+            hashes.push((
+                self.clear_stored_hash(&doc.metadata.id).await?,
+                stored_hash_of(&stored),
+            ));
+        }
+
+        atomic_write_all(&files).await?;
+
+        if let Some(dir) = hashes.first().and_then(|(path, _)| path.parent()) {
+            fs::create_dir_all(dir).await?;
+        }
+        atomic_write_all(&hashes).await?;
+
+        for doc in docs {
+            self.update_index_entry(doc).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Remove the content hash recorded for a profile, returning its path.
+    async fn clear_stored_hash(&self, profile_id: &str) -> StorageResult<PathBuf> {
+        let hash_path = self.hash_path(profile_id)?;
+        if let Err(e) = fs::remove_file(&hash_path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err(e.into());
+            }
+        }
+        Ok(hash_path)
+    }
+
+    /// Entity tag of the stored profile, derived from its file content.
+    pub async fn profile_etag(&self, profile_id: &str) -> StorageResult<String> {
+        let path = self.profile_path(profile_id)?;
+        if !path.exists() {
+            return Err(StorageError::NotFound(profile_id.to_string()));
+        }
+
+        let content = fs::read(&path).await?;
+        Ok(etag_of(&content))
+    }
+
+    /// Replace an existing profile wholesale.
+    ///
+    /// When `if_match` is given (an `If-Match` header value), the stored
+    /// profile must still carry that entity tag, otherwise
+    /// [`StorageError::ConcurrentModification`] is returned. The check and
+    /// the write are not atomic; [`FileStorage`](super::persistence::FileStorage)
+    /// serializes replacements of the same profile.
+    pub async fn replace_profile(
+        &self,
+        doc: &ProfileDocument,
+        if_match: Option<&str>,
+    ) -> StorageResult<()> {
+        let current = self.profile_etag(&doc.metadata.id).await?;
+        check_if_match(&doc.metadata.id, &current, if_match)?;
+
+        // A replacement is a modification, whatever time the client sent
+        let mut doc = doc.clone();
+        doc.modified_at = Utc::now();
+        self.save_profile(&doc).await
+    }
+
     /// Update or add an entry in the index.
     async fn update_index_entry(&self, doc: &ProfileDocument) -> StorageResult<()> {
         let mut index = self.read_index().await?;
@@ -337,11 +562,21 @@ impl ProfileStorage {
     /// Delete a profile.
     pub async fn delete_profile(&self, profile_id: &str) -> StorageResult<()> {
         // Remove profile file
-        let path = self.profile_path(profile_id);
+        let path = self.profile_path(profile_id)?;
         if path.exists() {
             fs::remove_file(&path).await?;
         }
 
+        // Remove annotations and content hash sidecars
+        for sidecar in [
+            self.annotations_path(profile_id)?,
+            self.hash_path(profile_id)?,
+        ] {
+            if sidecar.exists() {
+                fs::remove_file(&sidecar).await?;
+            }
+        }
+
         // Update index
         let mut index = self.read_index().await?;
         index.profiles.retain(|e| e.id != profile_id);
@@ -351,26 +586,83 @@ impl ProfileStorage {
         Ok(())
     }
 
+    /// Load the element annotations of a profile.
+    ///
+    /// Profiles without an annotations sidecar have none.
+    pub async fn load_annotations(&self, profile_id: &str) -> StorageResult<Vec<Annotation>> {
+        let path = self.annotations_path(profile_id)?;
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Append an annotation to a profile's annotations sidecar.
+    pub async fn add_annotation(
+        &self,
+        profile_id: &str,
+        annotation: Annotation,
+    ) -> StorageResult<()> {
+        let mut annotations = self.load_annotations(profile_id).await?;
+        annotations.push(annotation);
+        self.write_annotations(profile_id, &annotations).await
+    }
+
+    /// Delete an annotation by ID, returning whether it existed.
+    pub async fn delete_annotation(
+        &self,
+        profile_id: &str,
+        annotation_id: &str,
+    ) -> StorageResult<bool> {
+        let mut annotations = self.load_annotations(profile_id).await?;
+        let count = annotations.len();
+        annotations.retain(|a| a.id != annotation_id);
+        if annotations.len() == count {
+            return Ok(false);
+        }
+
+        self.write_annotations(profile_id, &annotations).await?;
+        Ok(true)
+    }
+
+    /// Move a profile's annotations sidecar to a new profile ID.
+    pub async fn rename_annotations(&self, old_id: &str, new_id: &str) -> StorageResult<()> {
+        let old_path = self.annotations_path(old_id)?;
+        if old_id != new_id && old_path.exists() {
+            fs::rename(&old_path, self.annotations_path(new_id)?).await?;
+        }
+        Ok(())
+    }
+
+    /// Write a profile's annotations sidecar.
+    async fn write_annotations(
+        &self,
+        profile_id: &str,
+        annotations: &[Annotation],
+    ) -> StorageResult<()> {
+        let path = self.annotations_path(profile_id)?;
+        fs::create_dir_all(self.ir_dir().join("annotations")).await?;
+
+        let content = serde_json::to_string_pretty(annotations)?;
+        let temp_path = path.with_extension("json.tmp");
+        fs::write(&temp_path, content).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
     /// Check if a profile exists.
     pub async fn profile_exists(&self, profile_id: &str) -> bool {
-        self.profile_path(profile_id).exists()
+        self.profile_path(profile_id).is_ok_and(|path| path.exists())
     }
 
     /// Save an imported SD JSON file.
     pub async fn save_sd_json(&self, name: &str, content: &str) -> StorageResult<PathBuf> {
+        let path = self.sd_path(name)?;
         fs::create_dir_all(self.sd_dir()).await?;
 
-        let path = self.sd_dir().join(format!("{}.json", name));
-        fs::write(&path, content).await?;
-
-        Ok(path)
-    }
-
-    /// Save an imported FSH file.
-    pub async fn save_fsh(&self, name: &str, content: &str) -> StorageResult<PathBuf> {
-        fs::create_dir_all(self.fsh_dir()).await?;
-
-        let path = self.fsh_dir().join(format!("{}.fsh", name));
         fs::write(&path, content).await?;
 
         Ok(path)
@@ -396,19 +688,51 @@ impl ProfileStorage {
         Ok(files)
     }
 
-    /// Delete all files associated with a profile.
-    pub async fn delete_profile_files(&self, profile_id: &str, name: &str) -> StorageResult<()> {
-        // Delete IR file
-        self.delete_profile(profile_id).await?;
+    /// Move a profile's SD and FSH source files to match a renamed document.
+    ///
+    /// The SD JSON's `id`, `name` and `url` are updated to the new values so
+    /// merged exports don't resurrect the old identity.
+    pub async fn rename_profile_sources(
+        &self,
+        old_name: &str,
+        doc: &ProfileDocument,
+    ) -> StorageResult<()> {
+        let new_name = &doc.metadata.name;
+
+        let old_sd = self.sd_path(old_name)?;
+        if old_sd.exists() {
+            let content = fs::read_to_string(&old_sd).await?;
+            let mut sd: serde_json::Value = serde_json::from_str(&content)?;
+            if let Some(obj) = sd.as_object_mut() {
+                obj.insert("id".to_string(), doc.metadata.id.clone().into());
+                obj.insert("name".to_string(), new_name.clone().into());
+                obj.insert("url".to_string(), doc.metadata.url.clone().into());
+            }
+            atomic_write_all(&[(self.sd_path(new_name)?, serde_json::to_string_pretty(&sd)?)])
+                .await?;
+            if old_name != new_name {
+                fs::remove_file(&old_sd).await?;
+            }
+        }
+
+        let old_fsh = self.fsh_path(old_name)?;
+        if old_name != new_name && old_fsh.exists() {
+            fs::rename(&old_fsh, self.fsh_path(new_name)?).await?;
+        }
+
+        Ok(())
+    }
 
+    /// Delete the SD and FSH source files of a profile.
+    pub async fn delete_profile_sources(&self, name: &str) -> StorageResult<()> {
         // Delete SD file if exists
-        let sd_path = self.sd_dir().join(format!("{}.json", name));
+        let sd_path = self.sd_path(name)?;
         if sd_path.exists() {
             fs::remove_file(&sd_path).await?;
         }
 
         // Delete FSH file if exists
-        let fsh_path = self.fsh_dir().join(format!("{}.fsh", name));
+        let fsh_path = self.fsh_path(name)?;
         if fsh_path.exists() {
             fs::remove_file(&fsh_path).await?;
         }
@@ -417,6 +741,101 @@ impl ProfileStorage {
     }
 }
 
+/// Write a group of files so that either all of them change or none do.
+///
+/// Every file is first written and synced to a `.tmp` sibling. Only when all
+/// of them are staged are the targets replaced, in order, each by an atomic
+/// rename after copying the previous content to a `.bak` sibling. If staging
+/// fails nothing is touched; if a replacement fails the files already
+/// replaced are restored from their backups. Put the file that marks the
+/// save as complete (e.g. the IR) last, so a crash mid-commit never leaves it
+/// ahead of the others.
+///
+/// Parent directories must already exist.
+pub async fn atomic_write_all(files: &[(PathBuf, String)]) -> std::io::Result<()> {
+    // Stage every file next to its target
+    let mut staged = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        let temp_path = sibling_path(path, "tmp");
+        if let Err(e) = write_synced(&temp_path, content).await {
+            let _ = fs::remove_file(&temp_path).await;
+            remove_all(&staged).await;
+            return Err(e);
+        }
+        staged.push(temp_path);
+    }
+
+    // Replace the targets, keeping backups until every rename succeeded
+    let mut committed: Vec<(&Path, Option<PathBuf>)> = Vec::with_capacity(files.len());
+    for ((path, _), temp_path) in files.iter().zip(&staged) {
+        match replace_with_backup(path, temp_path).await {
+            Ok(backup) => committed.push((path, backup)),
+            Err(e) => {
+                remove_all(&staged[committed.len()..]).await;
+                for (path, backup) in committed.into_iter().rev() {
+                    match backup {
+                        Some(backup) => {
+                            let _ = fs::rename(&backup, path).await;
+                        }
+                        None => {
+                            let _ = fs::remove_file(path).await;
+                        }
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    let backups: Vec<PathBuf> = committed
+        .into_iter()
+        .filter_map(|(_, backup)| backup)
+        .collect();
+    remove_all(&backups).await;
+    Ok(())
+}
+
+/// `path` with `.suffix` appended to its file name.
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Write and sync a file.
+async fn write_synced(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut file = fs::File::create(path).await?;
+    file.write_all(content.as_bytes()).await?;
+    file.sync_all().await
+}
+
+/// Move `temp_path` over `path`, returning the backup of the replaced file.
+async fn replace_with_backup(path: &Path, temp_path: &Path) -> std::io::Result<Option<PathBuf>> {
+    let backup = if fs::try_exists(path).await? {
+        let backup = sibling_path(path, "bak");
+        fs::copy(path, &backup).await?;
+        Some(backup)
+    } else {
+        None
+    };
+
+    if let Err(e) = fs::rename(temp_path, path).await {
+        if let Some(backup) = &backup {
+            let _ = fs::remove_file(backup).await;
+        }
+        return Err(e);
+    }
+    Ok(backup)
+}
+
+/// Best-effort removal of leftover temp or backup files.
+async fn remove_all(paths: &[PathBuf]) {
+    for path in paths {
+        let _ = fs::remove_file(path).await;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -465,6 +884,155 @@ mod tests {
         assert_eq!(loaded.metadata.id, "test-profile");
     }
 
+    #[tokio::test]
+    async fn test_save_with_sources_is_all_or_nothing() {
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        let mut doc = create_test_document("atomic");
+        storage
+            .save_profile_with_sources(&doc, Some("{\"v\":1}"), Some("// v1"))
+            .await
+            .unwrap();
+        let ir_path = storage.profile_path("atomic").unwrap();
+        let sd_path = storage.sd_path("Testatomic").unwrap();
+        let fsh_path = storage.fsh_path("Testatomic").unwrap();
+        let ir_before = fs::read_to_string(&ir_path).await.unwrap();
+
+        // Inject a failure on the SD write: its temp file cannot be created
+        fs::create_dir_all(sd_path.with_extension("json.tmp")).await.unwrap();
+
+        doc.metadata.title = Some("Changed".to_string());
+        let result = storage
+            .save_profile_with_sources(&doc, Some("{\"v\":2}"), Some("// v2"))
+            .await;
+        assert!(result.is_err());
+
+        assert_eq!(fs::read_to_string(&ir_path).await.unwrap(), ir_before);
+        assert_eq!(fs::read_to_string(&sd_path).await.unwrap(), "{\"v\":1}");
+        assert_eq!(fs::read_to_string(&fsh_path).await.unwrap(), "// v1");
+        assert!(!fsh_path.with_extension("fsh.tmp").exists());
+        assert!(!ir_path.with_extension("json.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn test_save_derives_differential_from_tree() {
+        use crate::ir::{Cardinality, ElementNode, ElementSource};
+        use crate::merge::DifferentialElement;
+
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        // Hydrated document edited on the tree after its differential was taken
+        let mut doc = create_test_document("edited");
+        doc.resource
+            .differential
+            .push(DifferentialElement::new("Patient.gender".to_string()));
+        doc.resource.hydrated = true;
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.source = ElementSource::Modified;
+        name.constraints.cardinality = Some(Cardinality::required());
+        doc.resource.root.add_child(name);
+        storage.save_profile(&doc).await.unwrap();
+
+        let loaded = storage.load_profile("edited").await.unwrap();
+        let paths: Vec<&str> = loaded
+            .resource
+            .differential
+            .iter()
+            .map(|diff| diff.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["Patient.name"]);
+        assert_eq!(
+            loaded.resource.differential[0].constraints.cardinality,
+            Some(Cardinality::required())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_identical_save_is_skipped() {
+        use crate::ir::{Cardinality, ElementNode, ElementSource};
+
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        // A hydrated document: every hydration builds the tree with fresh node ids
+        let hydrated = || {
+            let mut doc = create_test_document("unchanged");
+            let mut name = ElementNode::new("Patient.name".to_string());
+            name.source = ElementSource::Modified;
+            name.constraints.cardinality = Some(Cardinality::required());
+            doc.resource.root.add_child(name);
+            doc.resource.hydrated = true;
+            doc
+        };
+
+        storage.save_profile(&hydrated()).await.unwrap();
+        let path = storage.profile_path("unchanged").unwrap();
+        let mtime = || std::fs::metadata(&path).unwrap().modified().unwrap();
+        let saved_at = mtime();
+
+        // Only node ids and timestamps differ: the file is left alone
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        let mut doc = hydrated();
+        doc.mark_dirty();
+        storage.save_profile(&doc).await.unwrap();
+        assert_eq!(mtime(), saved_at);
+
+        doc.metadata.title = Some("Changed".to_string());
+        storage.save_profile(&doc).await.unwrap();
+        assert_ne!(mtime(), saved_at);
+        let loaded = storage.load_profile("unchanged").await.unwrap();
+        assert_eq!(loaded.metadata.title.as_deref(), Some("Changed"));
+    }
+
+    #[tokio::test]
+    async fn test_undo_is_persisted() {
+        use crate::ir::{Change, NodeId, Operation};
+
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        let mut doc = create_test_document("undone");
+        for i in 0..2 {
+            doc.history.push(Operation::single(
+                format!("Operation {i}"),
+                Change::set(NodeId::new(), "field", None, serde_json::json!(i)),
+            ));
+        }
+        storage.save_profile(&doc).await.unwrap();
+
+        // Undo changes only the history, which must still be written
+        for remaining in [1, 0] {
+            let mut loaded = storage.load_profile("undone").await.unwrap();
+            loaded.history.undo();
+            storage.save_profile(&loaded).await.unwrap();
+
+            let reloaded = storage.load_profile("undone").await.unwrap();
+            assert_eq!(reloaded.history.current_index(), remaining);
+            assert_eq!(reloaded.history.can_undo(), remaining > 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replace_profile_checks_etag() {
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        let mut doc = create_test_document("replaced");
+        storage.save_profile(&doc).await.unwrap();
+        let etag = storage.profile_etag("replaced").await.unwrap();
+
+        doc.metadata.title = Some("Replaced".to_string());
+        let quoted = format!("\"{}\"", etag);
+        storage.replace_profile(&doc, Some(&quoted)).await.unwrap();
+        let loaded = storage.load_profile("replaced").await.unwrap();
+        assert_eq!(loaded.metadata.title.as_deref(), Some("Replaced"));
+
+        // The old tag is stale after the replace
+        let result = storage.replace_profile(&doc, Some(&etag)).await;
+        assert!(matches!(result, Err(StorageError::ConcurrentModification(_))));
+
+        let missing = create_test_document("missing");
+        let result = storage.replace_profile(&missing, None).await;
+        assert!(matches!(result, Err(StorageError::NotFound(_))));
+    }
+
     #[tokio::test]
     async fn test_list_profiles() {
         let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
@@ -493,6 +1061,31 @@ mod tests {
         assert!(!storage.profile_exists("to-delete").await);
     }
 
+    #[tokio::test]
+    async fn test_rename_keeps_annotations() {
+        let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        let doc = create_test_document("old-id");
+        storage.save_profile(&doc).await.unwrap();
+        let annotation = Annotation::new(&doc.resource.root, "alice", "Keep me");
+        storage
+            .add_annotation("old-id", annotation.clone())
+            .await
+            .unwrap();
+
+        storage
+            .rename_annotations("old-id", "new-id")
+            .await
+            .unwrap();
+        storage.delete_profile("old-id").await.unwrap();
+
+        assert!(storage.load_annotations("old-id").await.unwrap().is_empty());
+        assert_eq!(
+            storage.load_annotations("new-id").await.unwrap(),
+            vec![annotation]
+        );
+    }
+
     #[tokio::test]
     async fn test_load_nonexistent_profile() {
         let (storage, _temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
@@ -501,4 +1094,30 @@ mod tests {
             storage.load_profile("nonexistent").await;
         assert!(matches!(result, Err(StorageError::NotFound(_))));
     }
+
+    #[tokio::test]
+    async fn test_rejects_traversal_ids() {
+        let (storage, temp_dir): (ProfileStorage, TempDir) = create_test_storage().await;
+
+        for id in ["../escape", "..", "nested/profile", "/etc/passwd", "..\\escape"] {
+            let result = storage.load_profile(id).await;
+            assert!(
+                matches!(result, Err(StorageError::InvalidId(_))),
+                "{id} should be rejected"
+            );
+            assert!(matches!(
+                storage.delete_profile(id).await,
+                Err(StorageError::InvalidId(_))
+            ));
+            assert!(!storage.profile_exists(id).await);
+        }
+
+        let doc = create_test_document("../../outside");
+        let result = storage.save_profile(&doc).await;
+        assert!(matches!(result, Err(StorageError::InvalidId(_))));
+        assert!(!temp_dir.path().join("../outside.json").exists());
+
+        let result = storage.save_sd_json("../Escaped", "{}").await;
+        assert!(matches!(result, Err(StorageError::InvalidId(_))));
+    }
 }
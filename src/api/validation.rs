@@ -7,12 +7,18 @@
 //! - `POST /api/projects/:projectId/profiles/:profileId/validate` - Full validation
 //! - `POST /api/projects/:projectId/profiles/:profileId/validate/quick` - Quick structural validation
 //! - `POST /api/projects/:projectId/profiles/:profileId/validate/element` - Validate specific element
+//! - `GET /api/projects/:projectId/profiles/:profileId/validate/summary` - Severity counts only
+//! - `GET /api/projects/:projectId/profiles/:profileId/validate/snapshot` - Imported snapshot freshness
 //! - `GET /api/projects/:projectId/profiles/:profileId/validation` - Get cached validation results
+//! - `POST /api/projects/:projectId/validate` - Validate every profile in the project
 //! - `POST /api/projects/:projectId/validate/batch` - Batch validate multiple profiles
 //! - `POST /api/projects/:projectId/profiles/:profileId/apply-fix` - Apply a quick fix
 //! - `GET /api/validation/config` - Get validation configuration
 //! - `PUT /api/validation/config` - Update validation configuration
 
+use std::collections::BTreeMap;
+use std::time::Instant;
+
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -21,16 +27,27 @@ use axum::{
     Json, Router,
 };
 use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-
-use super::profile_merge::hydrate_profile_document;
-use super::profiles::ErrorResponse;
-use super::storage::ProfileStorage;
+use tokio::task::JoinSet;
+use tracing::Instrument;
+
+use super::export::load_project_instances;
+use super::profile_merge::{hydrate_or_respond, hydrate_profile_document, load_base_tree};
+use super::profiles::{load_canonical_index, ErrorResponse};
+use crate::export::SnapshotGenerator;
+use crate::ir::ProfileDocument;
+use crate::project::ProjectIndex;
 use crate::state::{AppState, ValidationConfig};
-use crate::validation::{QuickFixKind, ValidationEngine, ValidationLevel, ValidationResult};
+use crate::validation::rules::instance::validate_abstract_profile_instances;
+use crate::validation::rules::snapshot::validate_snapshot_freshness;
+use crate::validation::{
+    Diagnostic, QuickFixKind, ValidationEngine, ValidationLevel, ValidationOptions,
+    ValidationResult,
+};
 
 /// Validation request options.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ValidateRequest {
     /// Validation level to perform.
     #[serde(default)]
@@ -48,7 +65,7 @@ fn default_true() -> bool {
 }
 
 /// Validation response with diagnostics.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ValidateResponse {
     /// Profile ID.
@@ -66,7 +83,7 @@ pub struct ValidateResponse {
 }
 
 /// Validation statistics.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, JsonSchema)]
 pub struct ValidationStats {
     /// Number of errors found.
     pub errors: usize,
@@ -76,8 +93,22 @@ pub struct ValidationStats {
     pub info: usize,
 }
 
+/// Severity counts without the diagnostics payload.
+#[derive(Debug, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationSummaryResponse {
+    /// Number of errors found.
+    pub error_count: usize,
+    /// Number of warnings found.
+    pub warning_count: usize,
+    /// Number of info diagnostics.
+    pub info_count: usize,
+    /// Whether the profile is valid.
+    pub is_valid: bool,
+}
+
 /// Diagnostic DTO for API responses.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DiagnosticDto {
     /// Severity level.
@@ -97,7 +128,7 @@ pub struct DiagnosticDto {
 }
 
 /// Quick fix DTO for API responses.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QuickFixDto {
     /// Display title.
@@ -154,6 +185,16 @@ fn to_response(result: ValidationResult, profile_id: &str, level: &str) -> Valid
     }
 }
 
+/// Tally a ValidationResult into a summary.
+fn to_summary(result: &ValidationResult) -> ValidationSummaryResponse {
+    ValidationSummaryResponse {
+        error_count: result.error_count(),
+        warning_count: result.warning_count(),
+        info_count: result.info_count(),
+        is_valid: result.is_valid,
+    }
+}
+
 /// Parse validation level from string.
 fn parse_level(level: Option<&str>) -> ValidationLevel {
     match level {
@@ -178,18 +219,37 @@ struct ProjectPath {
     project_id: String,
 }
 
+/// Build a validation engine that also checks a hydrated document against its base.
+async fn engine_with_base(state: &AppState, document: &ProfileDocument) -> ValidationEngine {
+    engine_with_base_options(state, document, ValidationOptions::default()).await
+}
+
+/// Like [`engine_with_base`], with custom engine options.
+async fn engine_with_base_options(
+    state: &AppState,
+    document: &ProfileDocument,
+    options: ValidationOptions,
+) -> ValidationEngine {
+    let engine = ValidationEngine::with_options(options);
+    match load_base_tree(state, document).await {
+        Ok(Some(base_tree)) => engine.with_base_tree(base_tree),
+        _ => engine,
+    }
+}
+
 /// Validate a profile with full validation.
 async fn validate_profile(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
     Json(request): Json<ValidateRequest>,
 ) -> impl IntoResponse {
-    // Get project directory and create storage
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load the profile
-    let document = match storage.load_profile(&params.profile_id).await {
+    let document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(doc) => doc,
         Err(e) => {
             return (
@@ -199,41 +259,18 @@ async fn validate_profile(
                 .into_response();
         }
     };
-    let document = match hydrate_profile_document(&state, document).await {
+    let document = match hydrate_or_respond(&state, document).await {
         Ok(doc) => doc,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(e),
-            )
-                .into_response();
-        }
-    };
-    let document = match hydrate_profile_document(&state, document).await {
-        Ok(doc) => doc,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(e),
-            )
-                .into_response();
-        }
-    };
-    let document = match hydrate_profile_document(&state, document).await {
-        Ok(doc) => doc,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(e),
-            )
-                .into_response();
-        }
+        Err(response) => return response,
     };
 
     // Perform validation
     let level = parse_level(request.level.as_deref());
-    let engine = ValidationEngine::new();
-    let result = engine.validate(&document, level).await;
+    let engine = engine_with_base(&state, &document).await;
+    let started = Instant::now();
+    let mut result = engine.validate(&document, level).await;
+    result.merge(instance_validation(&state, &params.project_id, &document).await);
+    let duration_ms = started.elapsed().as_millis() as u64;
 
     // Cache the result
     state.cache_validation(
@@ -242,6 +279,12 @@ async fn validate_profile(
         result.clone(),
         document.modified_at,
     );
+    state.notify_validation_completed(
+        &params.project_id,
+        &params.profile_id,
+        &result,
+        duration_ms,
+    );
 
     let level_str = level.as_str();
     let response = to_response(result, &params.profile_id, level_str);
@@ -249,17 +292,41 @@ async fn validate_profile(
     Json(response).into_response()
 }
 
+/// Check the project's example instances against an abstract profile.
+///
+/// Instances are only loaded for abstract profiles.
+async fn instance_validation(
+    state: &AppState,
+    project_id: &str,
+    document: &ProfileDocument,
+) -> ValidationResult {
+    let mut diagnostics = Vec::new();
+    if document.resource.is_abstract {
+        if let Ok(project_dir) = state.project_path(project_id) {
+            let instances = load_project_instances(&project_dir).await;
+            diagnostics = validate_abstract_profile_instances(
+                document,
+                instances
+                    .iter()
+                    .map(|instance| (instance.id.as_str(), &instance.resource)),
+            );
+        }
+    }
+    ValidationResult::with_diagnostics(diagnostics, ValidationLevel::Structural)
+}
+
 /// Quick structural validation (fast, synchronous).
 async fn validate_quick(
     State(state): State<AppState>,
     Path(params): Path<ProfilePath>,
 ) -> impl IntoResponse {
-    // Get project directory and create storage
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load the profile
-    let document = match storage.load_profile(&params.profile_id).await {
+    let document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(doc) => doc,
         Err(e) => {
             return (
@@ -272,7 +339,9 @@ async fn validate_quick(
 
     // Perform quick structural validation only
     let engine = ValidationEngine::new();
+    let started = Instant::now();
     let result = engine.validate(&document, ValidationLevel::Structural).await;
+    let duration_ms = started.elapsed().as_millis() as u64;
 
     // Cache the result
     state.cache_validation(
@@ -281,14 +350,120 @@ async fn validate_quick(
         result.clone(),
         document.modified_at,
     );
+    state.notify_validation_completed(
+        &params.project_id,
+        &params.profile_id,
+        &result,
+        duration_ms,
+    );
 
     let response = to_response(result, &params.profile_id, "structural");
 
     Json(response).into_response()
 }
 
+/// Structural validation returning only severity counts.
+///
+/// Quick fixes are dropped and no diagnostic DTOs are built, so dashboards
+/// can poll this cheaply. The result is not cached.
+async fn validate_summary(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> impl IntoResponse {
+    let storage = state.storage();
+
+    let document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(doc) => doc,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": format!("{}", e) })),
+            )
+                .into_response();
+        }
+    };
+    let document = match hydrate_or_respond(&state, document).await {
+        Ok(doc) => doc,
+        Err(response) => return response,
+    };
+
+    let options = ValidationOptions::default().without_quick_fixes();
+    let engine = engine_with_base_options(&state, &document, options).await;
+    let result = engine.validate(&document, ValidationLevel::Structural).await;
+
+    Json(to_summary(&result)).into_response()
+}
+
+/// Compare the snapshot a profile was imported with against a regenerated one.
+///
+/// The snapshot is regenerated from the differential merged onto the base;
+/// divergent element paths are reported in a single warning. Profiles
+/// imported without a snapshot, or created in the editor, are always fresh.
+async fn validate_snapshot(
+    State(state): State<AppState>,
+    Path(params): Path<ProfilePath>,
+) -> impl IntoResponse {
+    let storage = state.storage();
+
+    let document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
+        Ok(doc) => doc,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(serde_json::json!({ "error": format!("{}", e) })),
+            )
+                .into_response();
+        }
+    };
+
+    let mut diagnostics = Vec::new();
+    if !document.resource.imported_snapshot.is_empty() {
+        // Without the base every inherited element would look divergent
+        if !document.resource.is_specialization()
+            && !matches!(load_base_tree(&state, &document).await, Ok(Some(_)))
+        {
+            return ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "BASE_NOT_RESOLVED",
+                format!(
+                    "Base definition '{}' could not be resolved from installed packages",
+                    document.resource.base.url
+                ),
+            )
+            .into_response();
+        }
+
+        let document = match hydrate_or_respond(&state, document).await {
+            Ok(doc) => doc,
+            Err(response) => return response,
+        };
+        let regenerated = match SnapshotGenerator::new().generate(&document.resource).await {
+            Ok(elements) => elements,
+            Err(e) => {
+                return ErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "SNAPSHOT_GENERATION_FAILED",
+                    e.to_string(),
+                )
+                .into_response();
+            }
+        };
+        diagnostics =
+            validate_snapshot_freshness(&document.resource.imported_snapshot, &regenerated);
+    }
+
+    let result = ValidationResult::with_diagnostics(diagnostics, ValidationLevel::Structural);
+    Json(to_response(result, &params.profile_id, "snapshot")).into_response()
+}
+
 /// Validate specific element request.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
 pub struct ValidateElementRequest {
     /// Element path to validate.
     pub path: String,
@@ -300,12 +475,13 @@ async fn validate_element(
     Path(params): Path<ProfilePath>,
     Json(request): Json<ValidateElementRequest>,
 ) -> impl IntoResponse {
-    // Get project directory and create storage
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load the profile
-    let document = match storage.load_profile(&params.profile_id).await {
+    let document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(doc) => doc,
         Err(e) => {
             return (
@@ -373,6 +549,9 @@ pub struct BatchValidateResponse {
     pub valid_count: usize,
     /// Number of invalid profiles.
     pub invalid_count: usize,
+    /// Project-level diagnostics not tied to a single profile.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub project_diagnostics: Vec<DiagnosticDto>,
 }
 
 /// Single item in batch validation results.
@@ -398,10 +577,8 @@ async fn validate_batch(
     Path(params): Path<ProjectPath>,
     Json(request): Json<BatchValidateRequest>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
     let level = parse_level(request.level.as_deref());
-    let engine = ValidationEngine::new();
 
     let mut results = Vec::new();
     let mut valid_count = 0;
@@ -409,7 +586,7 @@ async fn validate_batch(
 
     // Validate each profile
     for profile_id in &request.profile_ids {
-        match storage.load_profile(profile_id).await {
+        match storage.load_profile(&params.project_id, profile_id).await {
             Ok(document) => {
                 let document = match hydrate_profile_document(&state, document).await {
                     Ok(doc) => doc,
@@ -420,13 +597,16 @@ async fn validate_batch(
                             is_valid: false,
                             error_count: 0,
                             warning_count: 0,
-                            error: Some("Hydration failed".to_string()),
+                            error: Some(format!("Hydration failed: {}", e.message())),
                         });
                         continue;
                     }
                 };
 
+                let engine = engine_with_base(&state, &document).await;
+                let started = Instant::now();
                 let result = engine.validate(&document, level).await;
+                let duration_ms = started.elapsed().as_millis() as u64;
 
                 // Cache result
                 state.cache_validation(
@@ -435,6 +615,12 @@ async fn validate_batch(
                     result.clone(),
                     document.modified_at,
                 );
+                state.notify_validation_completed(
+                    &params.project_id,
+                    profile_id,
+                    &result,
+                    duration_ms,
+                );
 
                 if result.is_valid {
                     valid_count += 1;
@@ -463,15 +649,221 @@ async fn validate_batch(
         }
     }
 
+    let canonical_index = load_canonical_index(&state, &params.project_id).await;
+    let project_diagnostics = duplicate_canonical_diagnostics(&canonical_index);
+
     Json(BatchValidateResponse {
         total: results.len(),
         valid_count,
         invalid_count,
         results,
+        project_diagnostics,
+    })
+    .into_response()
+}
+
+/// Maximum number of profiles validated concurrently by project validation.
+const PROJECT_VALIDATION_CONCURRENCY: usize = 8;
+
+/// Project-wide validation response.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectValidateResponse {
+    /// Project ID.
+    pub project_id: String,
+    /// Validation result per profile ID.
+    pub results: BTreeMap<String, ValidationResult>,
+    /// Aggregate counts across all profiles and project-level checks.
+    pub summary: ProjectValidationSummary,
+    /// Project-level diagnostics not tied to a single profile.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub project_diagnostics: Vec<DiagnosticDto>,
+}
+
+/// Aggregate summary of a project-wide validation run.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectValidationSummary {
+    /// Total profiles validated.
+    pub total: usize,
+    /// Number of valid profiles.
+    pub valid_count: usize,
+    /// Number of invalid profiles.
+    pub invalid_count: usize,
+    /// Errors across all profiles, including project-level errors.
+    pub error_count: usize,
+    /// Warnings across all profiles, including project-level warnings.
+    pub warning_count: usize,
+    /// Info diagnostics across all profiles.
+    pub info_count: usize,
+    /// Whether the project as a whole is valid.
+    pub is_valid: bool,
+}
+
+/// Aggregate per-profile results and project-level diagnostics.
+fn summarize_project(
+    results: &BTreeMap<String, ValidationResult>,
+    project_diagnostics: &[DiagnosticDto],
+) -> ProjectValidationSummary {
+    let valid_count = results.values().filter(|r| r.is_valid).count();
+    let project_count =
+        |severity: &str| project_diagnostics.iter().filter(|d| d.severity == severity).count();
+    let project_errors = project_count("error");
+
+    ProjectValidationSummary {
+        total: results.len(),
+        valid_count,
+        invalid_count: results.len() - valid_count,
+        error_count: results.values().map(|r| r.error_count()).sum::<usize>() + project_errors,
+        warning_count: results.values().map(|r| r.warning_count()).sum::<usize>()
+            + project_count("warning"),
+        info_count: results.values().map(|r| r.info_count()).sum::<usize>()
+            + project_count("info"),
+        is_valid: valid_count == results.len() && project_errors == 0,
+    }
+}
+
+/// Result recording a profile that could not be validated.
+fn failed_validation(code: &str, message: String) -> ValidationResult {
+    ValidationResult::with_diagnostics(
+        vec![Diagnostic::error(code, message)],
+        ValidationLevel::Structural,
+    )
+}
+
+/// Give every profile without a result (its task panicked or was cancelled)
+/// an error result, so it still counts in the summary.
+fn record_unfinished_profiles(
+    results: &mut BTreeMap<String, ValidationResult>,
+    profile_ids: impl IntoIterator<Item = String>,
+) {
+    for profile_id in profile_ids {
+        results.entry(profile_id).or_insert_with(|| {
+            failed_validation(
+                "VALIDATION_TASK_FAILED",
+                "Validation of this profile did not complete".to_string(),
+            )
+        });
+    }
+}
+
+/// Hydrate and structurally validate a single profile of a project.
+async fn validate_project_profile(
+    state: AppState,
+    project_id: String,
+    document: ProfileDocument,
+) -> ValidationResult {
+    let profile_id = document.metadata.id.clone();
+    let document = match hydrate_profile_document(&state, document).await {
+        Ok(doc) => doc,
+        Err(e) => {
+            return failed_validation(
+                "HYDRATION_FAILED",
+                format!("Hydration failed: {}", e.message()),
+            );
+        }
+    };
+
+    let engine = engine_with_base(&state, &document).await;
+    let started = Instant::now();
+    let mut result = engine
+        .validate(&document, ValidationLevel::Structural)
+        .await;
+    result.merge(instance_validation(&state, &project_id, &document).await);
+    let duration_ms = started.elapsed().as_millis() as u64;
+
+    state.cache_validation(&project_id, &profile_id, result.clone(), document.modified_at);
+    state.notify_validation_completed(&project_id, &profile_id, &result, duration_ms);
+
+    result
+}
+
+/// Validate every profile in a project.
+///
+/// Profiles are validated concurrently, at most
+/// [`PROJECT_VALIDATION_CONCURRENCY`] at a time.
+async fn validate_project(
+    State(state): State<AppState>,
+    Path(params): Path<ProjectPath>,
+) -> impl IntoResponse {
+    let profiles = match state.storage().list_profiles(&params.project_id).await {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            return ErrorResponse::internal_error(format!("Failed to list profiles: {}", e))
+                .into_response();
+        }
+    };
+
+    // Profiles whose task panics or is cancelled still get a result
+    let profile_ids: Vec<String> = profiles.iter().map(|p| p.metadata.id.clone()).collect();
+
+    let mut results = BTreeMap::new();
+    let mut tasks = JoinSet::new();
+    for document in profiles {
+        if tasks.len() >= PROJECT_VALIDATION_CONCURRENCY {
+            match tasks.join_next().await {
+                Some(Ok((id, result))) => {
+                    results.insert(id, result);
+                }
+                Some(Err(e)) => tracing::warn!("Profile validation task failed: {}", e),
+                None => {}
+            }
+        }
+
+        let state = state.clone();
+        let project_id = params.project_id.clone();
+        tasks.spawn(
+            async move {
+                let profile_id = document.metadata.id.clone();
+                let result = validate_project_profile(state, project_id, document).await;
+                (profile_id, result)
+            }
+            .in_current_span(),
+        );
+    }
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((id, result)) => {
+                results.insert(id, result);
+            }
+            Err(e) => tracing::warn!("Profile validation task failed: {}", e),
+        }
+    }
+    record_unfinished_profiles(&mut results, profile_ids);
+
+    let canonical_index = load_canonical_index(&state, &params.project_id).await;
+    let project_diagnostics = duplicate_canonical_diagnostics(&canonical_index);
+    let summary = summarize_project(&results, &project_diagnostics);
+
+    Json(ProjectValidateResponse {
+        project_id: params.project_id,
+        results,
+        summary,
+        project_diagnostics,
     })
     .into_response()
 }
 
+/// Report canonical URLs shared by more than one resource in the project.
+fn duplicate_canonical_diagnostics(index: &ProjectIndex) -> Vec<DiagnosticDto> {
+    index
+        .duplicate_canonical_urls()
+        .into_iter()
+        .map(|(url, ids)| DiagnosticDto {
+            severity: "error".to_string(),
+            code: "DUPLICATE_CANONICAL_URL".to_string(),
+            message: format!(
+                "Canonical URL '{}' is used by multiple resources: {}",
+                url,
+                ids.join(", ")
+            ),
+            element_path: None,
+            source: "ir".to_string(),
+            quick_fixes: Vec::new(),
+        })
+        .collect()
+}
+
 /// Apply quick fix request.
 #[derive(Debug, Deserialize)]
 pub struct ApplyFixRequest {
@@ -501,11 +893,13 @@ async fn apply_fix(
     Path(params): Path<ProfilePath>,
     Json(request): Json<ApplyFixRequest>,
 ) -> impl IntoResponse {
-    let project_dir = state.project_path(&params.project_id);
-    let storage = ProfileStorage::new(&project_dir);
+    let storage = state.storage();
 
     // Load the profile
-    let mut document = match storage.load_profile(&params.profile_id).await {
+    let mut document = match storage
+        .load_profile(&params.project_id, &params.profile_id)
+        .await
+    {
         Ok(doc) => doc,
         Err(e) => {
             return (
@@ -515,15 +909,9 @@ async fn apply_fix(
                 .into_response();
         }
     };
-    let mut document = match hydrate_profile_document(&state, document).await {
+    let mut document = match hydrate_or_respond(&state, document).await {
         Ok(doc) => doc,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(e),
-            )
-                .into_response();
-        }
+        Err(response) => return response,
     };
 
     // Apply the fix based on kind
@@ -531,7 +919,7 @@ async fn apply_fix(
 
     if success {
         // Save the updated document
-        if let Err(e) = storage.save_profile(&document).await {
+        if let Err(e) = storage.save_profile(&params.project_id, &document).await {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(serde_json::json!({ "error": format!("Failed to save: {}", e) })),
@@ -541,6 +929,7 @@ async fn apply_fix(
 
         // Invalidate cache and re-validate
         state.invalidate_validation(&params.project_id, &params.profile_id);
+        state.notify_profile_saved(&params.project_id, &params.profile_id);
 
         // Re-validate if we have a path
         let validation = if let Some(ref p) = path {
@@ -697,6 +1086,14 @@ pub fn validation_routes() -> Router<AppState> {
             "/api/projects/{project_id}/profiles/{profile_id}/validate/element",
             post(validate_element),
         )
+        .route(
+            "/api/projects/{project_id}/profiles/{profile_id}/validate/summary",
+            get(validate_summary),
+        )
+        .route(
+            "/api/projects/{project_id}/profiles/{profile_id}/validate/snapshot",
+            get(validate_snapshot),
+        )
         .route(
             "/api/projects/{project_id}/profiles/{profile_id}/validation",
             get(get_validation),
@@ -706,6 +1103,7 @@ pub fn validation_routes() -> Router<AppState> {
             post(apply_fix),
         )
         // Project-level validation
+        .route("/api/projects/{project_id}/validate", post(validate_project))
         .route(
             "/api/projects/{project_id}/validate/batch",
             post(validate_batch),
@@ -742,4 +1140,159 @@ mod tests {
             ValidationLevel::Structural
         ));
     }
+
+    #[test]
+    fn test_summary_matches_full_response_stats() {
+        use crate::validation::Diagnostic;
+
+        let result = ValidationResult::with_diagnostics(
+            vec![
+                Diagnostic::error("E1", "first error"),
+                Diagnostic::error("E2", "second error"),
+                Diagnostic::warning("W1", "a warning"),
+                Diagnostic::info("I1", "some info"),
+                Diagnostic::info("I2", "more info"),
+                Diagnostic::info("I3", "even more info"),
+            ],
+            ValidationLevel::Structural,
+        );
+
+        let summary = to_summary(&result);
+        let full = to_response(result, "p1", "structural");
+
+        assert_eq!(summary.error_count, full.stats.errors);
+        assert_eq!(summary.warning_count, full.stats.warnings);
+        assert_eq!(summary.info_count, full.stats.info);
+        assert_eq!(summary.is_valid, full.is_valid);
+        assert_eq!((summary.error_count, summary.warning_count, summary.info_count), (2, 1, 3));
+        assert!(!summary.is_valid);
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["errorCount"], 2);
+        assert_eq!(json["isValid"], false);
+    }
+
+    #[test]
+    fn test_summarize_project() {
+        let mut results = BTreeMap::new();
+        results.insert(
+            "a".to_string(),
+            ValidationResult::with_diagnostics(
+                vec![Diagnostic::warning("W1", "a warning")],
+                ValidationLevel::Structural,
+            ),
+        );
+        results.insert(
+            "b".to_string(),
+            ValidationResult::with_diagnostics(
+                vec![
+                    Diagnostic::error("E1", "an error"),
+                    Diagnostic::info("I1", "some info"),
+                ],
+                ValidationLevel::Structural,
+            ),
+        );
+
+        let summary = summarize_project(&results, &[]);
+        assert_eq!(summary.total, 2);
+        assert_eq!(summary.valid_count, 1);
+        assert_eq!(summary.invalid_count, 1);
+        assert_eq!((summary.error_count, summary.warning_count, summary.info_count), (1, 1, 1));
+        assert!(!summary.is_valid);
+
+        results.remove("b");
+        assert!(summarize_project(&results, &[]).is_valid);
+
+        // Project-level errors invalidate an otherwise clean project.
+        use crate::project::{ProjectResource, ResourceKind};
+        let url = "http://example.org/fhir/StructureDefinition/MyPatient";
+        let mut index = ProjectIndex::new();
+        index.add_resource(ProjectResource::new("a", url, "MyPatient", ResourceKind::Profile));
+        index.add_resource(ProjectResource::new("c", url, "MyPatient", ResourceKind::Profile));
+        let project_diagnostics = duplicate_canonical_diagnostics(&index);
+
+        let summary = summarize_project(&results, &project_diagnostics);
+        assert_eq!(summary.error_count, 1);
+        assert_eq!(summary.valid_count, 1);
+        assert!(!summary.is_valid);
+    }
+
+    #[test]
+    fn test_unfinished_profiles_count_as_invalid() {
+        let mut results = BTreeMap::new();
+        results.insert(
+            "done".to_string(),
+            ValidationResult::valid(ValidationLevel::Structural),
+        );
+
+        record_unfinished_profiles(&mut results, ["done".to_string(), "panicked".to_string()]);
+
+        assert!(results["done"].is_valid);
+        assert!(!results["panicked"].is_valid);
+        assert_eq!(
+            results["panicked"].diagnostics[0].code,
+            "VALIDATION_TASK_FAILED"
+        );
+
+        let summary = summarize_project(&results, &[]);
+        assert_eq!((summary.total, summary.invalid_count), (2, 1));
+        assert!(!summary.is_valid);
+    }
+
+    #[test]
+    fn test_duplicate_canonical_diagnostics() {
+        use crate::project::{ProjectResource, ResourceKind};
+
+        let url = "http://example.org/fhir/StructureDefinition/MyPatient";
+        let mut index = ProjectIndex::new();
+        assert!(duplicate_canonical_diagnostics(&index).is_empty());
+
+        index.add_resource(ProjectResource::new("a", url, "MyPatient", ResourceKind::Profile));
+        index.add_resource(ProjectResource::new("b", url, "MyPatient", ResourceKind::Profile));
+
+        let diagnostics = duplicate_canonical_diagnostics(&index);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, "DUPLICATE_CANONICAL_URL");
+        assert!(diagnostics[0].message.contains("a, b"));
+    }
+
+    #[tokio::test]
+    async fn test_instance_validation_reports_abstract_profile_instances() {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+        use crate::validation::rules::instance::codes;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let state = AppState::new(crate::Config::default(), workspace.path().to_path_buf());
+        let instance_dir = workspace.path().join("demo").join("SD").join("Instance");
+        std::fs::create_dir_all(&instance_dir).unwrap();
+        let url = "http://example.org/fhir/StructureDefinition/BasePatient";
+        std::fs::write(
+            instance_dir.join("base-example.json"),
+            serde_json::json!({ "resourceType": "Patient", "meta": { "profile": [url] } })
+                .to_string(),
+        )
+        .unwrap();
+
+        let mut resource =
+            ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        resource.is_abstract = true;
+        let mut document = ProfileDocument::new(
+            DocumentMetadata::new("base-patient", url, "BasePatient"),
+            resource,
+        );
+
+        let result = instance_validation(&state, "demo", &document).await;
+        assert!(!result.is_valid);
+        assert_eq!(
+            result.diagnostics[0].code,
+            codes::INSTANCE_OF_ABSTRACT_PROFILE
+        );
+
+        document.resource.is_abstract = false;
+        assert!(
+            instance_validation(&state, "demo", &document)
+                .await
+                .is_valid
+        );
+    }
 }
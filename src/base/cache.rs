@@ -0,0 +1,219 @@
+//! Cache of parsed base definition trees.
+//!
+//! Parsing a base snapshot (Patient has hundreds of elements) is expensive, so
+//! parsed trees are kept in a small LRU cache keyed by `(base_url, FhirVersion)`.
+//! The cache is cleared whenever installed packages change.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use indexmap::IndexMap;
+
+use crate::ir::{ElementNode, FhirVersion};
+
+/// Default number of base trees kept in the cache.
+pub const DEFAULT_BASE_TREE_CACHE_CAPACITY: usize = 64;
+
+type CacheKey = (String, FhirVersion);
+
+/// LRU cache of parsed base element trees.
+#[derive(Debug)]
+pub struct BaseTreeCache {
+    /// Cached trees in least-recently-used order (oldest first).
+    entries: Mutex<IndexMap<CacheKey, Arc<ElementNode>>>,
+    /// Maximum number of cached trees.
+    capacity: usize,
+    /// Bumped on invalidation so in-flight loads don't store stale trees.
+    generation: AtomicU64,
+    /// Lookups through [`BaseTreeCache::get_or_load`] served from the cache.
+    hits: AtomicU64,
+    /// Lookups through [`BaseTreeCache::get_or_load`] that had to load.
+    misses: AtomicU64,
+}
+
+impl Default for BaseTreeCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_TREE_CACHE_CAPACITY)
+    }
+}
+
+impl BaseTreeCache {
+    /// Create a cache holding at most `capacity` trees.
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: Mutex::new(IndexMap::new()),
+            capacity: capacity.max(1),
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Get a cached tree, marking it as most recently used.
+    #[must_use]
+    pub fn get(&self, base_url: &str, fhir_version: FhirVersion) -> Option<ElementNode> {
+        let mut entries = self.lock();
+        let key = (base_url.to_string(), fhir_version);
+        let tree = entries.shift_remove(&key)?;
+        let result = (*tree).clone();
+        entries.insert(key, tree);
+        Some(result)
+    }
+
+    /// Store a tree, evicting the least recently used entry if full.
+    pub fn insert(&self, base_url: &str, fhir_version: FhirVersion, tree: ElementNode) {
+        let mut entries = self.lock();
+        let key = (base_url.to_string(), fhir_version);
+        entries.shift_remove(&key);
+        while entries.len() >= self.capacity {
+            entries.shift_remove_index(0);
+        }
+        entries.insert(key, Arc::new(tree));
+    }
+
+    /// Get a cached tree or load it with `load` and cache the result.
+    ///
+    /// Errors from `load` are returned as-is and never cached.
+    pub async fn get_or_load<F, Fut, E>(
+        &self,
+        base_url: &str,
+        fhir_version: FhirVersion,
+        load: F,
+    ) -> Result<ElementNode, E>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<ElementNode, E>>,
+    {
+        if let Some(tree) = self.get(base_url, fhir_version) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(tree);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let tree = load().await?;
+        if self.generation.load(Ordering::Acquire) == generation {
+            self.insert(base_url, fhir_version, tree.clone());
+        }
+        Ok(tree)
+    }
+
+    /// Drop all cached trees (e.g., after a package install or uninstall).
+    pub fn invalidate_all(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.lock().clear();
+    }
+
+    /// Cache hits and misses of [`BaseTreeCache::get_or_load`] so far.
+    #[must_use]
+    pub fn stats(&self) -> (u64, u64) {
+        (
+            self.hits.load(Ordering::Relaxed),
+            self.misses.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Number of cached trees.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Check if the cache is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, IndexMap<CacheKey, Arc<ElementNode>>> {
+        self.entries.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    const PATIENT: &str = "http://hl7.org/fhir/StructureDefinition/Patient";
+
+    /// Stub base source that counts how often the parse path runs.
+    struct CountingLoader {
+        calls: AtomicUsize,
+    }
+
+    impl CountingLoader {
+        async fn load(&self, base_url: &str) -> Result<ElementNode, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let type_name = base_url.rsplit('/').next().unwrap_or("Resource");
+            Ok(ElementNode::new(type_name.to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_second_resolution_hits_cache() {
+        let cache = BaseTreeCache::default();
+        let loader = CountingLoader {
+            calls: AtomicUsize::new(0),
+        };
+
+        let first = cache
+            .get_or_load(PATIENT, FhirVersion::R4, || loader.load(PATIENT))
+            .await
+            .unwrap();
+        let second = cache
+            .get_or_load(PATIENT, FhirVersion::R4, || loader.load(PATIENT))
+            .await
+            .unwrap();
+
+        assert_eq!(first.path, "Patient");
+        assert_eq!(second.path, "Patient");
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 1);
+
+        // A different FHIR version is a different key
+        cache
+            .get_or_load(PATIENT, FhirVersion::R5, || loader.load(PATIENT))
+            .await
+            .unwrap();
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_forces_reload() {
+        let cache = BaseTreeCache::default();
+        let loader = CountingLoader {
+            calls: AtomicUsize::new(0),
+        };
+
+        cache
+            .get_or_load(PATIENT, FhirVersion::R4, || loader.load(PATIENT))
+            .await
+            .unwrap();
+        cache.invalidate_all();
+        assert!(cache.is_empty());
+
+        cache
+            .get_or_load(PATIENT, FhirVersion::R4, || loader.load(PATIENT))
+            .await
+            .unwrap();
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let cache = BaseTreeCache::new(2);
+        cache.insert("a", FhirVersion::R4, ElementNode::new("A".to_string()));
+        cache.insert("b", FhirVersion::R4, ElementNode::new("B".to_string()));
+
+        // Touch "a" so "b" becomes least recently used
+        assert!(cache.get("a", FhirVersion::R4).is_some());
+        cache.insert("c", FhirVersion::R4, ElementNode::new("C".to_string()));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("a", FhirVersion::R4).is_some());
+        assert!(cache.get("b", FhirVersion::R4).is_none());
+        assert!(cache.get("c", FhirVersion::R4).is_some());
+    }
+}
@@ -2,14 +2,25 @@
 //!
 //! Loads and parses base FHIR resource/profile definitions from the canonical manager.
 //! Used to build the complete element tree by merging differential with base.
+//! Transient resolution failures are retried according to a [`RetryPolicy`].
+//! Definitions come from a [`DefinitionSource`], normally the canonical manager.
+
+mod cache;
+mod resources;
+mod retry;
 
 use std::sync::Arc;
 
-use octofhir_canonical_manager::CanonicalManager;
+use async_trait::async_trait;
+use octofhir_canonical_manager::{CanonicalManager, FcmError};
 use thiserror::Error;
 
 use crate::import::ElementTreeBuilder;
-use crate::ir::{ElementNode, FhirVersion};
+use crate::ir::{ElementNode, FhirVersion, NodeId};
+
+pub use cache::{BaseTreeCache, DEFAULT_BASE_TREE_CACHE_CAPACITY};
+pub use resources::BaseResource;
+pub use retry::{RetryPolicy, TransientError, DEFAULT_RESOLVE_ATTEMPTS};
 
 /// Errors that can occur when resolving base definitions.
 #[derive(Debug, Error)]
@@ -26,25 +37,92 @@ pub enum BaseResolverError {
     #[error("Failed to parse base definition elements: {0}")]
     ParseFailed(String),
 
+    /// The pinned version of a base definition is not installed.
+    #[error("Base definition '{0}' version '{1}' is not installed")]
+    VersionNotInstalled(String, String),
+
     /// Canonical manager not available.
     #[error("Canonical manager not available: {0}")]
     ManagerUnavailable(String),
 }
 
+/// Where a [`BaseResolver`] looks up StructureDefinitions.
+///
+/// Implemented by the canonical manager; abstracted so the resolver and its
+/// cache can be exercised without installed packages.
+#[async_trait]
+pub trait DefinitionSource: Send + Sync {
+    /// Content of the resource a canonical (optionally `|version` pinned)
+    /// resolves to.
+    async fn definition(&self, canonical: &str) -> Result<serde_json::Value, FcmError>;
+
+    /// Content of every installed StructureDefinition matching a URL.
+    async fn definitions_for_url(&self, url: &str) -> Result<Vec<serde_json::Value>, FcmError>;
+}
+
+#[async_trait]
+impl DefinitionSource for CanonicalManager {
+    async fn definition(&self, canonical: &str) -> Result<serde_json::Value, FcmError> {
+        Ok(self.resolve(canonical).await?.resource.content)
+    }
+
+    async fn definitions_for_url(&self, url: &str) -> Result<Vec<serde_json::Value>, FcmError> {
+        Ok(self
+            .search()
+            .await
+            .resource_type("StructureDefinition")
+            .text(url)
+            .execute()
+            .await?
+            .resources
+            .into_iter()
+            .map(|r| r.resource.content)
+            .collect())
+    }
+}
+
 /// Resolves base FHIR definitions from the canonical manager.
 ///
 /// This resolver loads base StructureDefinitions (like Patient, Observation)
 /// from installed FHIR packages and parses them into the IR element tree format.
 pub struct BaseResolver {
-    /// Reference to the canonical manager.
-    canonical_manager: Arc<CanonicalManager>,
+    /// Source of the definitions (the canonical manager).
+    source: Arc<dyn DefinitionSource>,
+    /// Optional cache of parsed base trees shared across requests.
+    cache: Option<Arc<BaseTreeCache>>,
+    /// Retry policy for canonical manager calls.
+    retry: RetryPolicy,
 }
 
 impl BaseResolver {
     /// Create a new base resolver with the given canonical manager.
     #[must_use]
     pub fn new(canonical_manager: Arc<CanonicalManager>) -> Self {
-        Self { canonical_manager }
+        Self::from_source(canonical_manager)
+    }
+
+    /// Create a base resolver reading definitions from `source`.
+    #[must_use]
+    pub fn from_source(source: Arc<dyn DefinitionSource>) -> Self {
+        Self {
+            source,
+            cache: None,
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Use a custom retry policy for canonical manager calls.
+    #[must_use]
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Use a shared cache for parsed base trees.
+    #[must_use]
+    pub fn with_cache(mut self, cache: Arc<BaseTreeCache>) -> Self {
+        self.cache = Some(cache);
+        self
     }
 
     /// Load a base definition and parse it into an element tree.
@@ -52,8 +130,9 @@ impl BaseResolver {
     /// # Arguments
     ///
     /// * `base_url` - Canonical URL of the base resource/profile
-    ///   (e.g., "http://hl7.org/fhir/StructureDefinition/Patient")
-    /// * `_fhir_version` - FHIR version for context (currently unused but may be needed for version-specific parsing)
+    ///   (e.g., "http://hl7.org/fhir/StructureDefinition/Patient"), optionally
+    ///   pinned to a version with a `|version` suffix
+    /// * `fhir_version` - FHIR version, part of the cache key when a cache is configured
     ///
     /// # Returns
     ///
@@ -65,16 +144,22 @@ impl BaseResolver {
     pub async fn load_base_tree(
         &self,
         base_url: &str,
-        _fhir_version: FhirVersion,
+        fhir_version: FhirVersion,
     ) -> Result<ElementNode, BaseResolverError> {
-        // Resolve the base definition from packages
-        let resolved = self
-            .canonical_manager
-            .resolve(base_url)
-            .await
-            .map_err(|e| BaseResolverError::ResolutionFailed(base_url.to_string(), e.to_string()))?;
+        match &self.cache {
+            Some(cache) => {
+                cache
+                    .get_or_load(base_url, fhir_version, || self.parse_base_tree(base_url))
+                    .await
+            }
+            None => self.parse_base_tree(base_url).await,
+        }
+    }
 
-        let content = &resolved.resource.content;
+    /// Resolve a base definition and parse it into an element tree (uncached).
+    async fn parse_base_tree(&self, base_url: &str) -> Result<ElementNode, BaseResolverError> {
+        // Resolve the base definition from packages
+        let content = &self.resolve_content(base_url).await?;
 
         // Extract elements from snapshot (preferred) or differential
         let elements = self.extract_elements(content, base_url)?;
@@ -88,9 +173,10 @@ impl BaseResolver {
 
         // Build the element tree
         let builder = ElementTreeBuilder::new();
-        let root = builder
+        let mut root = builder
             .build_tree(type_name, &elements, None)
             .map_err(|e| BaseResolverError::ParseFailed(e.to_string()))?;
+        assign_base_node_ids(&mut root);
 
         Ok(root)
     }
@@ -103,13 +189,46 @@ impl BaseResolver {
         &self,
         base_url: &str,
     ) -> Result<serde_json::Value, BaseResolverError> {
+        self.resolve_content(base_url).await
+    }
+
+    /// Resolve a canonical to its StructureDefinition content.
+    ///
+    /// A `|version` suffix pins the version: the versioned canonical is
+    /// passed to the canonical manager, and if it answers with a different
+    /// version the installed versions are searched for the pinned one.
+    async fn resolve_content(&self, canonical: &str) -> Result<serde_json::Value, BaseResolverError> {
+        let (url, version) = match canonical.split_once('|') {
+            Some((url, version)) if !version.is_empty() => (url, Some(version)),
+            _ => (canonical.trim_end_matches('|'), None),
+        };
+
         let resolved = self
-            .canonical_manager
-            .resolve(base_url)
+            .retry
+            .run("Canonical resolution", || self.source.definition(canonical))
+            .await;
+        let Some(version) = version else {
+            return resolved
+                .map_err(|e| BaseResolverError::ResolutionFailed(url.to_string(), e.to_string()));
+        };
+
+        if let Ok(content) = resolved {
+            if resource_version(&content) == Some(version) {
+                return Ok(content);
+            }
+        }
+
+        let candidates = self
+            .retry
+            .run("Canonical search", || self.source.definitions_for_url(url))
             .await
-            .map_err(|e| BaseResolverError::ResolutionFailed(base_url.to_string(), e.to_string()))?;
+            .map_err(|e| {
+                BaseResolverError::ResolutionFailed(canonical.to_string(), e.to_string())
+            })?;
 
-        Ok(resolved.resource.content.clone())
+        select_version(&candidates, url, version)
+            .cloned()
+            .ok_or_else(|| BaseResolverError::VersionNotInstalled(url.to_string(), version.to_string()))
     }
 
     /// Extract elements array from StructureDefinition content.
@@ -132,7 +251,39 @@ impl BaseResolver {
 
     /// Check if a base definition is available in the package cache.
     pub async fn is_available(&self, base_url: &str) -> bool {
-        self.canonical_manager.resolve(base_url).await.is_ok()
+        self.resolve_content(base_url).await.is_ok()
+    }
+}
+
+/// `StructureDefinition.version` of resolved content.
+fn resource_version(content: &serde_json::Value) -> Option<&str> {
+    content.get("version").and_then(|v| v.as_str())
+}
+
+/// Pick the candidate with the given canonical URL and version.
+fn select_version<'a>(
+    candidates: &'a [serde_json::Value],
+    url: &str,
+    version: &str,
+) -> Option<&'a serde_json::Value> {
+    candidates.iter().find(|content| {
+        content.get("url").and_then(|v| v.as_str()) == Some(url)
+            && resource_version(content) == Some(version)
+    })
+}
+
+/// Give every node of a parsed base tree an ID derived from its element id.
+///
+/// Annotations and stored differentials refer to inherited elements by node
+/// ID, so the same base must produce the same IDs on every parse.
+fn assign_base_node_ids(element: &mut ElementNode) {
+    element.id = NodeId::for_base_element(element.element_id.as_deref().unwrap_or(&element.path));
+    for child in &mut element.children {
+        assign_base_node_ids(child);
+    }
+    for slice in element.slices.values_mut() {
+        assign_base_node_ids(&mut slice.element);
+        slice.id = slice.element.id;
     }
 }
 
@@ -140,6 +291,102 @@ impl BaseResolver {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_select_pinned_version() {
+        let url = "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient";
+        let candidates = vec![
+            serde_json::json!({ "url": url, "version": "7.0.0", "type": "Patient" }),
+            serde_json::json!({ "url": url, "version": "6.1.0", "type": "Patient" }),
+            serde_json::json!({
+                "url": "http://example.org/fhir/StructureDefinition/other",
+                "version": "6.1.0"
+            }),
+        ];
+
+        let selected = select_version(&candidates, url, "6.1.0").unwrap();
+        assert_eq!(selected["version"], "6.1.0");
+        assert_eq!(selected["url"], url);
+        assert!(select_version(&candidates, url, "5.0.1").is_none());
+    }
+
+    #[test]
+    fn test_base_node_ids_are_stable() {
+        let parse = || {
+            let mut root = ElementNode::new("Patient".to_string());
+            root.add_child(ElementNode::new("Patient.name".to_string()));
+            root.add_child(ElementNode::new("Patient.gender".to_string()));
+            assign_base_node_ids(&mut root);
+            root
+        };
+
+        let (first, second) = (parse(), parse());
+        assert_eq!(first.id, second.id);
+        assert_eq!(first.children[0].id, second.children[0].id);
+        assert_ne!(first.children[0].id, first.children[1].id);
+    }
+
+    /// Definition source serving a minimal Patient, counting resolutions.
+    struct StubSource {
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl DefinitionSource for StubSource {
+        async fn definition(&self, canonical: &str) -> Result<serde_json::Value, FcmError> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(serde_json::json!({
+                "url": canonical,
+                "type": "Patient",
+                "snapshot": {
+                    "element": [
+                        { "id": "Patient", "path": "Patient", "min": 0, "max": "*" },
+                        { "id": "Patient.name", "path": "Patient.name", "min": 0, "max": "*" }
+                    ]
+                }
+            }))
+        }
+
+        async fn definitions_for_url(
+            &self,
+            _url: &str,
+        ) -> Result<Vec<serde_json::Value>, FcmError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_resolver_parses_base_once() {
+        let source = Arc::new(StubSource {
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let cache = Arc::new(BaseTreeCache::default());
+        let resolver = BaseResolver::from_source(source.clone()).with_cache(cache.clone());
+        let patient = "http://hl7.org/fhir/StructureDefinition/Patient";
+
+        let first = resolver
+            .load_base_tree(patient, FhirVersion::R4)
+            .await
+            .unwrap();
+        let second = resolver
+            .load_base_tree(patient, FhirVersion::R4)
+            .await
+            .unwrap();
+
+        assert_eq!(first.path, "Patient");
+        assert_eq!(second.children.len(), first.children.len());
+        assert_eq!(second.id, first.id);
+        assert_eq!(source.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(cache.stats(), (1, 1));
+
+        // A resolver without the cache resolves every time
+        let uncached = BaseResolver::from_source(source.clone());
+        uncached
+            .load_base_tree(patient, FhirVersion::R4)
+            .await
+            .unwrap();
+        assert_eq!(source.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
     // Note: These tests require a configured canonical manager with packages installed.
     // They are marked as ignore by default and can be run with `cargo test -- --ignored`
 
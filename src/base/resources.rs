@@ -0,0 +1,27 @@
+//! Profileable base resource types.
+//!
+//! Summaries of the core resource definitions a new profile can derive from.
+//! They are listed per FHIR version by the search API and cached in the
+//! application state until installed packages change.
+
+use serde::Serialize;
+
+/// Base resource type information (for profile creation).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaseResource {
+    /// Resource type name (e.g., "Patient", "Observation")
+    pub name: String,
+    /// Canonical URL
+    pub url: String,
+    /// Resource title
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Resource description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Package name
+    pub package_name: String,
+    /// Package version
+    pub package_version: String,
+}
@@ -0,0 +1,193 @@
+//! Bounded retry with exponential backoff for canonical resolution.
+//!
+//! Resolving against installed packages (and the registry behind them) can
+//! fail transiently, e.g. on I/O or a locked package database. Such failures
+//! are retried a few times; a definition that is simply not installed, or a
+//! canonical that cannot be parsed, is reported immediately.
+
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+use octofhir_canonical_manager::FcmError;
+
+/// Default number of attempts per resolution (including the first one).
+pub const DEFAULT_RESOLVE_ATTEMPTS: u32 = 3;
+
+/// Backoff before the first retry; doubled for every further retry.
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How often, and how patiently, a failed resolution is retried.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one (at least 1).
+    pub attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESOLVE_ATTEMPTS)
+    }
+}
+
+impl RetryPolicy {
+    /// Policy making `attempts` attempts with the default backoff.
+    #[must_use]
+    pub fn new(attempts: u32) -> Self {
+        Self {
+            attempts: attempts.max(1),
+            initial_backoff: INITIAL_BACKOFF,
+        }
+    }
+
+    /// Policy that never retries.
+    #[must_use]
+    pub fn none() -> Self {
+        Self::new(1)
+    }
+
+    /// Set the delay before the first retry.
+    #[must_use]
+    pub fn with_initial_backoff(mut self, backoff: Duration) -> Self {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Run `operation`, retrying transient failures.
+    ///
+    /// `what` names the operation in the retry log.
+    pub async fn run<T, E, F, Fut>(&self, what: &str, mut operation: F) -> Result<T, E>
+    where
+        E: TransientError + Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.attempts && e.is_transient() => {
+                    tracing::debug!(
+                        "{} failed (attempt {}/{}), retrying in {:?}: {}",
+                        what,
+                        attempt,
+                        self.attempts,
+                        backoff,
+                        e
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = backoff.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Errors that [`RetryPolicy::run`] can tell apart as worth retrying.
+pub trait TransientError {
+    /// Whether the failed operation may succeed when tried again.
+    fn is_transient(&self) -> bool;
+}
+
+impl TransientError for FcmError {
+    /// I/O, package storage and registry failures are transient; resolution,
+    /// configuration and parse errors fail the same way on every attempt.
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            FcmError::Io(_) | FcmError::Storage(_) | FcmError::Registry(_)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Stub resolution error.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum StubError {
+        Io,
+        NotFound,
+    }
+
+    impl Display for StubError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{:?}", self)
+        }
+    }
+
+    impl TransientError for StubError {
+        fn is_transient(&self) -> bool {
+            *self == StubError::Io
+        }
+    }
+
+    /// Stub canonical manager: fails `failures` times with `error`, then resolves.
+    struct StubManager {
+        calls: AtomicU32,
+        failures: u32,
+        error: StubError,
+    }
+
+    impl StubManager {
+        async fn resolve(&self, canonical: &str) -> Result<String, StubError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst);
+            if call < self.failures {
+                Err(self.error)
+            } else {
+                Ok(canonical.to_string())
+            }
+        }
+    }
+
+    const PATIENT: &str = "http://hl7.org/fhir/StructureDefinition/Patient";
+
+    #[tokio::test]
+    async fn test_transient_failure_is_retried() {
+        let policy = RetryPolicy::new(3).with_initial_backoff(Duration::ZERO);
+        let manager = StubManager {
+            calls: AtomicU32::new(0),
+            failures: 1,
+            error: StubError::Io,
+        };
+
+        let resolved = policy.run("resolve", || manager.resolve(PATIENT)).await;
+        assert_eq!(resolved.as_deref(), Ok(PATIENT));
+        assert_eq!(manager.calls.load(Ordering::SeqCst), 2);
+
+        // Attempts are bounded
+        let manager = StubManager {
+            calls: AtomicU32::new(0),
+            failures: 5,
+            error: StubError::Io,
+        };
+        assert!(policy.run("resolve", || manager.resolve(PATIENT)).await.is_err());
+        assert_eq!(manager.calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_not_found_is_not_retried() {
+        let policy = RetryPolicy::new(3).with_initial_backoff(Duration::ZERO);
+        let manager = StubManager {
+            calls: AtomicU32::new(0),
+            failures: 1,
+            error: StubError::NotFound,
+        };
+
+        assert!(policy.run("resolve", || manager.resolve(PATIENT)).await.is_err());
+        assert_eq!(manager.calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_canonical_manager_io_error_is_transient() {
+        let error = FcmError::Io(std::io::Error::other("connection reset by peer"));
+        assert!(error.is_transient());
+    }
+}
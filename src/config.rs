@@ -10,6 +10,8 @@ use std::path::PathBuf;
 
 use clap::Parser;
 
+use crate::base::{RetryPolicy, DEFAULT_RESOLVE_ATTEMPTS};
+
 /// NITEN FHIR Profile Builder Server
 #[derive(Debug, Clone, Parser)]
 #[command(name = "niten")]
@@ -41,9 +43,10 @@ pub struct Config {
     #[arg(long, env = "BASE_PATH")]
     pub base_path: Option<String>,
 
-    /// CORS allowed origins (comma-separated, or "*" for all)
-    #[arg(long, env = "CORS_ORIGINS")]
-    pub cors_origins: Option<String>,
+    /// CORS allowed origins (comma-separated, or "*" for all).
+    /// When empty, debug builds allow any origin and release builds none.
+    #[arg(long = "cors-origins", env = "CORS_ORIGINS", value_delimiter = ',')]
+    pub cors_allowed_origins: Vec<String>,
 
     /// Request timeout in seconds
     #[arg(long, env = "REQUEST_TIMEOUT", default_value = "30")]
@@ -52,6 +55,37 @@ pub struct Config {
     /// Shutdown timeout in seconds (time to wait for requests to complete)
     #[arg(long, env = "SHUTDOWN_TIMEOUT", default_value = "10")]
     pub shutdown_timeout: u64,
+
+    /// Mount internal debugging endpoints under /api/_debug
+    #[arg(long, env = "ENABLE_DEBUG_ROUTES")]
+    pub enable_debug_routes: bool,
+
+    /// Compress JSON and FSH responses (gzip/deflate via Accept-Encoding)
+    #[arg(long, env = "ENABLE_COMPRESSION")]
+    pub enable_compression: bool,
+
+    /// Expose Prometheus metrics at /metrics
+    #[arg(long, env = "ENABLE_METRICS")]
+    pub enable_metrics: bool,
+
+    /// Canonical base for profiles created without a URL, used when the
+    /// project defines no canonical base of its own
+    #[arg(long, env = "DEFAULT_CANONICAL_BASE", default_value = "http://example.org/fhir")]
+    pub default_canonical_base: String,
+
+    /// Maximum request body size in bytes (larger uploads get 413)
+    #[arg(long, env = "MAX_UPLOAD_BYTES", default_value = "16777216")]
+    pub max_upload_bytes: usize,
+
+    /// Attempts per canonical resolution; transient failures are retried
+    /// with exponential backoff, missing definitions are not
+    #[arg(long, env = "RESOLVE_ATTEMPTS", default_value = "3")]
+    pub resolve_attempts: u32,
+
+    /// Time limit in seconds for a single FSH decompilation or FHIR Schema
+    /// conversion (exceeding it returns 504)
+    #[arg(long = "export-timeout", env = "EXPORT_TIMEOUT", default_value = "20")]
+    pub export_timeout_secs: u64,
 }
 
 impl Config {
@@ -119,6 +153,27 @@ impl Config {
             );
         }
 
+        // An unparseable origin would otherwise be dropped from the allow-list
+        if let Some(origin) = self
+            .cors_origins_list()
+            .iter()
+            .find(|o| o.parse::<axum::http::HeaderValue>().is_err())
+        {
+            anyhow::bail!("Invalid CORS origin: '{}'", origin);
+        }
+
+        if self.max_upload_bytes == 0 {
+            anyhow::bail!("Max upload bytes must be greater than 0");
+        }
+
+        if self.export_timeout_secs == 0 {
+            anyhow::bail!("Export timeout must be greater than 0");
+        }
+
+        if self.resolve_attempts == 0 {
+            anyhow::bail!("Resolve attempts must be greater than 0");
+        }
+
         // Validate base path format
         if let Some(ref base_path) = self.base_path {
             if !base_path.starts_with('/') {
@@ -132,18 +187,14 @@ impl Config {
         Ok(())
     }
 
-    /// Get CORS allowed origins as a vector.
+    /// Get CORS allowed origins, trimmed and without empty entries.
     #[must_use]
     pub fn cors_origins_list(&self) -> Vec<String> {
-        match &self.cors_origins {
-            Some(origins) if origins == "*" => vec!["*".to_string()],
-            Some(origins) => origins
-                .split(',')
-                .map(|s| s.trim().to_string())
-                .filter(|s| !s.is_empty())
-                .collect(),
-            None => vec![],
-        }
+        self.cors_allowed_origins
+            .iter()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect()
     }
 
     /// Get request timeout as Duration.
@@ -157,6 +208,18 @@ impl Config {
     pub fn shutdown_timeout_duration(&self) -> std::time::Duration {
         std::time::Duration::from_secs(self.shutdown_timeout)
     }
+
+    /// Get the export timeout as Duration.
+    #[must_use]
+    pub fn export_timeout_duration(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.export_timeout_secs)
+    }
+
+    /// Get the retry policy for canonical resolution.
+    #[must_use]
+    pub fn resolve_retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(self.resolve_attempts)
+    }
 }
 
 impl Default for Config {
@@ -168,9 +231,16 @@ impl Default for Config {
             packages_cache_dir: None,
             log_level: "info".to_string(),
             base_path: None,
-            cors_origins: None,
+            cors_allowed_origins: Vec::new(),
             request_timeout: 30,
             shutdown_timeout: 10,
+            enable_debug_routes: false,
+            enable_compression: false,
+            enable_metrics: false,
+            default_canonical_base: "http://example.org/fhir".to_string(),
+            max_upload_bytes: 16 * 1024 * 1024,
+            resolve_attempts: DEFAULT_RESOLVE_ATTEMPTS,
+            export_timeout_secs: 20,
         }
     }
 }
@@ -204,14 +274,17 @@ mod tests {
 
         // Wildcard
         let config = Config {
-            cors_origins: Some("*".to_string()),
+            cors_allowed_origins: vec!["*".to_string()],
             ..Default::default()
         };
         assert_eq!(config.cors_origins_list(), vec!["*"]);
 
-        // Multiple origins
+        // Multiple origins (as split from "http://localhost:3000, https://example.com")
         let config = Config {
-            cors_origins: Some("http://localhost:3000, https://example.com".to_string()),
+            cors_allowed_origins: vec![
+                "http://localhost:3000".to_string(),
+                " https://example.com".to_string(),
+            ],
             ..Default::default()
         };
         assert_eq!(
@@ -220,6 +293,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cors_origins_from_args() {
+        let config = Config::try_parse_from([
+            "niten",
+            "--workspace-dir",
+            "/tmp/ws",
+            "--cors-origins",
+            "http://localhost:3000,https://example.com",
+        ])
+        .unwrap();
+        assert_eq!(
+            config.cors_origins_list(),
+            vec!["http://localhost:3000", "https://example.com"]
+        );
+    }
+
+    #[test]
+    fn test_validate_rejects_invalid_cors_origin() {
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            cors_allowed_origins: vec!["http://exa\u{7f}mple.com".to_string()],
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_timeout_durations() {
         let config = Config {
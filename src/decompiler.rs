@@ -78,6 +78,9 @@ pub enum DecompilerError {
 
     #[error("Failed to process StructureDefinition: {0}")]
     ProcessFailed(String),
+
+    #[error("FSH decompilation timed out after {0:?}")]
+    TimedOut(std::time::Duration),
 }
 
 /// Get or initialize the global decompiler context.
@@ -260,6 +260,7 @@ impl ProfileBuilderEngine {
         // Get the change for recording
         let change = operation.as_change();
         let description = operation.description();
+        let referenced_canonicals = operation.referenced_canonicals();
 
         // Apply operation
         self.document_manager.with_document_mut(doc_id, |doc| {
@@ -288,6 +289,7 @@ impl ProfileBuilderEngine {
             description,
             path: None, // TODO: Extract from change
             history_state,
+            referenced_canonicals,
             timestamp: Utc::now(),
         }));
 
@@ -355,6 +357,78 @@ impl ProfileBuilderEngine {
         Ok(description)
     }
 
+    // === Refactorings ===
+
+    /// Extract an inline extension slice into a standalone Extension document.
+    ///
+    /// Creates a new Extension document in the same project (canonical base
+    /// taken from the source profile) capturing the slice's constraints, then
+    /// rewrites the slice to reference it by URL. Returns the new document ID.
+    pub fn extract_inline_extension(
+        &self,
+        doc_id: &DocumentId,
+        element_path: &str,
+    ) -> EngineResult<DocumentId> {
+        let source = self.document_manager.get_document(doc_id)?;
+        let project_id = self.document_manager.get_metadata(doc_id)?.project_id;
+
+        let slice = source
+            .resource
+            .find_element(element_path)
+            .ok_or_else(|| OperationError::element_not_found(element_path))?;
+        let name = operations::inline_extension_name(slice).ok_or_else(|| {
+            OperationError::NotInlineExtension {
+                path: element_path.to_string(),
+            }
+        })?;
+        let canonical_base = source
+            .metadata
+            .url
+            .rsplit_once("/StructureDefinition/")
+            .map(|(base, _)| base)
+            .ok_or_else(|| {
+                EngineError::Config(format!(
+                    "Cannot derive canonical base from {}",
+                    source.metadata.url
+                ))
+            })?;
+        let context_path = element_path
+            .rsplit_once('.')
+            .map_or(element_path, |(parent, _)| parent);
+
+        let extension_id =
+            self.document_manager
+                .create_profile(&project_id, name, "Extension", canonical_base)?;
+        let extension_url = self.document_manager.with_document_mut(&extension_id, |doc| {
+            doc.resource = operations::standalone_extension_resource(
+                slice,
+                &doc.metadata.url,
+                source.resource.fhir_version,
+                context_path,
+            );
+            doc.mark_dirty();
+            doc.metadata.url.clone()
+        })?;
+
+        let rewrite = operations::UseStandaloneExtension::new(element_path, &extension_url);
+        if let Err(e) = self.apply_operation(doc_id, &rewrite) {
+            // Don't leave an orphaned extension behind
+            let _ = self.document_manager.close_document(&extension_id);
+            return Err(e);
+        }
+
+        let extension = self.document_manager.get_document(&extension_id)?;
+        self.emit(EngineEvent::DocumentOpened(DocumentOpenedEvent {
+            document_id: extension_id.clone(),
+            project_id,
+            name: extension.metadata.name.clone(),
+            url: extension_url,
+            timestamp: Utc::now(),
+        }));
+
+        Ok(extension_id)
+    }
+
     // === Validation ===
 
     /// Validate a document synchronously.
@@ -506,6 +580,71 @@ mod tests {
         assert!(event_count.load(Ordering::SeqCst) >= 1);
     }
 
+    #[tokio::test]
+    async fn test_extract_inline_extension() {
+        let engine = ProfileBuilderEngine::new(test_config()).await.unwrap();
+        let doc_id = engine
+            .create_profile("my-project", "MyPatient", "Patient", "http://example.org/fhir")
+            .unwrap();
+        engine
+            .document_manager
+            .with_document_mut(&doc_id, |doc| {
+                crate::operations::add_inline_extension(&mut doc.resource);
+            })
+            .unwrap();
+
+        let referenced = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let captured = referenced.clone();
+        engine.add_listener(Arc::new(CallbackListener::new(move |event| {
+            if let EngineEvent::OperationApplied(applied) = event {
+                captured
+                    .lock()
+                    .unwrap()
+                    .extend(applied.referenced_canonicals.clone());
+            }
+        })));
+
+        let extension_id = engine
+            .extract_inline_extension(&doc_id, "Patient.extension:birthPlace")
+            .unwrap();
+
+        let extension = engine.get_document(&extension_id).unwrap();
+        assert_eq!(
+            extension.metadata.url,
+            "http://example.org/fhir/StructureDefinition/birthPlace"
+        );
+        assert!(extension.resource.is_extension());
+        assert!(extension.resource.find_element("Extension.value[x]").is_some());
+
+        let profile = engine.get_document(&doc_id).unwrap();
+        let slice = profile
+            .resource
+            .find_element("Patient.extension:birthPlace")
+            .unwrap();
+        assert!(slice.children.is_empty());
+        assert_eq!(
+            slice.constraints.types[0].profile,
+            vec![extension.metadata.url.clone()]
+        );
+        assert!(engine.get_history_state(&doc_id).unwrap().can_undo);
+
+        // The rewrite reports the new definition for the recent canonicals
+        assert_eq!(
+            *referenced.lock().unwrap(),
+            vec![(
+                crate::project::CanonicalUsage::Extension,
+                extension.metadata.url.clone()
+            )]
+        );
+
+        // Not an inline extension anymore
+        assert!(
+            engine
+                .extract_inline_extension(&doc_id, "Patient.extension:birthPlace")
+                .is_err()
+        );
+    }
+
     #[tokio::test]
     async fn test_close_document() {
         let engine = ProfileBuilderEngine::new(test_config()).await.unwrap();
@@ -1,11 +1,16 @@
 //! Engine events for UI updates.
 //!
 //! Events are emitted by the engine to notify listeners of state changes.
+//! [`EventBroadcaster`] fans them out to per-document subscribers (e.g.
+//! WebSocket clients).
 
 use chrono::{DateTime, Utc};
+use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 
 use crate::ir::HistoryState;
+use crate::project::CanonicalUsage;
 use crate::validation::ValidationResult;
 
 /// Unique identifier for a document within the engine.
@@ -43,6 +48,24 @@ pub enum EngineEvent {
     Error(ErrorEvent),
 }
 
+impl EngineEvent {
+    /// Document the event belongs to, if any.
+    #[must_use]
+    pub fn document_id(&self) -> Option<&str> {
+        match self {
+            Self::DocumentOpened(e) => Some(&e.document_id),
+            Self::DocumentModified(e) => Some(&e.document_id),
+            Self::DocumentSaved(e) => Some(&e.document_id),
+            Self::DocumentClosed(e) => Some(&e.document_id),
+            Self::OperationApplied(e) => Some(&e.document_id),
+            Self::OperationUndone(e) => Some(&e.document_id),
+            Self::OperationRedone(e) => Some(&e.document_id),
+            Self::ValidationCompleted(e) => Some(&e.document_id),
+            Self::Error(e) => e.document_id.as_deref(),
+        }
+    }
+}
+
 /// Event emitted when a document is opened.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentOpenedEvent {
@@ -106,6 +129,10 @@ pub struct OperationAppliedEvent {
     pub path: Option<String>,
     /// Updated history state.
     pub history_state: HistoryState,
+    /// Value sets and extension definitions the operation referenced,
+    /// for the project's recently used canonicals.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub referenced_canonicals: Vec<(CanonicalUsage, String)>,
     /// Timestamp.
     pub timestamp: DateTime<Utc>,
 }
@@ -249,6 +276,62 @@ where
     }
 }
 
+/// Buffered events per document before slow subscribers start lagging.
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// Broadcasts events to subscribers of each document.
+///
+/// Channels are created on first subscription and dropped once the last
+/// subscriber is gone. Events for documents without subscribers are
+/// discarded.
+#[derive(Debug, Default)]
+pub struct EventBroadcaster {
+    channels: DashMap<DocumentId, broadcast::Sender<EngineEvent>>,
+}
+
+impl EventBroadcaster {
+    /// Create a new broadcaster with no subscribers.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to events of a document.
+    pub fn subscribe(&self, document_id: &str) -> broadcast::Receiver<EngineEvent> {
+        self.channels
+            .entry(document_id.to_string())
+            .or_insert_with(|| broadcast::channel(EVENT_CHANNEL_CAPACITY).0)
+            .subscribe()
+    }
+
+    /// Publish an event to the subscribers of its document.
+    pub fn publish(&self, event: EngineEvent) {
+        let Some(document_id) = event.document_id().map(str::to_string) else {
+            return;
+        };
+        let delivered = self
+            .channels
+            .get(&document_id)
+            .is_some_and(|sender| sender.send(event).is_ok());
+        if !delivered {
+            self.channels
+                .remove_if(&document_id, |_, sender| sender.receiver_count() == 0);
+        }
+    }
+
+    /// Number of subscribers for a document.
+    pub fn subscriber_count(&self, document_id: &str) -> usize {
+        self.channels
+            .get(document_id)
+            .map_or(0, |sender| sender.receiver_count())
+    }
+}
+
+impl EventListener for EventBroadcaster {
+    fn on_event(&self, event: &EngineEvent) {
+        self.publish(event.clone());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,4 +355,28 @@ mod tests {
         assert!(summary.is_valid);
         assert_eq!(summary.error_count, 0);
     }
+
+    #[tokio::test]
+    async fn test_broadcaster_routes_by_document() {
+        let broadcaster = EventBroadcaster::new();
+        let mut patient = broadcaster.subscribe("demo/patient");
+        let mut observation = broadcaster.subscribe("demo/observation");
+
+        broadcaster.publish(EngineEvent::DocumentSaved(DocumentSavedEvent {
+            document_id: "demo/patient".to_string(),
+            formats: vec!["ir".to_string()],
+            timestamp: Utc::now(),
+        }));
+
+        let event = patient.recv().await.unwrap();
+        assert_eq!(event.document_id(), Some("demo/patient"));
+        assert!(observation.try_recv().is_err());
+
+        drop(patient);
+        broadcaster.publish(EngineEvent::Error(
+            ErrorEvent::new("TEST", "ignored").for_document("demo/patient".to_string()),
+        ));
+        assert_eq!(broadcaster.subscriber_count("demo/patient"), 0);
+        assert_eq!(broadcaster.subscriber_count("demo/observation"), 1);
+    }
 }
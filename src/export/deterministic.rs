@@ -5,6 +5,8 @@
 //! - Consistent handling of optional fields
 //! - Canonical JSON formatting
 
+use std::collections::{HashMap, HashSet};
+
 use indexmap::IndexMap;
 use serde_json::{Map, Value};
 
@@ -280,20 +282,129 @@ fn is_empty_value(value: &Value) -> bool {
 }
 
 /// Sort an array of element definitions by path for deterministic ordering.
+///
+/// Elements inside a slice are ordered by their id, which carries the slice
+/// name (e.g., `Observation.component:foo/bar.code`), so slice children stay
+/// grouped with their slice. Slices of an element whose slicing is `ordered`
+/// keep the order in which they appear in `elements`; all other slices are
+/// sorted by name.
 pub fn sort_elements_by_path(elements: &mut [Value]) {
+    let ranks = ordered_slice_ranks(elements.iter());
     elements.sort_by(|a, b| {
-        let path_a = a.get("path").and_then(Value::as_str).unwrap_or("");
-        let path_b = b.get("path").and_then(Value::as_str).unwrap_or("");
-        compare_element_paths(path_a, path_b)
+        compare_element_paths(element_sort_key(a), element_sort_key(b), &ranks)
     });
 }
 
+/// Order element definitions, keeping imported elements in source order.
+///
+/// Elements with a source index keep their imported order, so an unmodified
+/// import round-trips unchanged. Newly added elements (no index) are then
+/// placed, in path order, next to the elements they belong to: before their
+/// first descendant if they have one, otherwise right after the last
+/// descendant of their closest ancestor.
+pub fn order_elements_by_source(elements: Vec<(Value, Option<usize>)>) -> Vec<Value> {
+    let ranks = ordered_slice_ranks(elements.iter().map(|(value, _)| value));
+    let (mut indexed, mut added): (Vec<_>, Vec<_>) =
+        elements.into_iter().partition(|(_, index)| index.is_some());
+    indexed.sort_by_key(|(_, index)| *index);
+    added.sort_by(|(a, _), (b, _)| {
+        compare_element_paths(element_sort_key(a), element_sort_key(b), &ranks)
+    });
+
+    let mut ordered: Vec<Value> = indexed.into_iter().map(|(value, _)| value).collect();
+    for (element, _) in added {
+        let position = insertion_position(&ordered, element_sort_key(&element));
+        ordered.insert(position, element);
+    }
+    ordered
+}
+
+/// Position at which a newly added element with `key` joins `ordered`.
+fn insertion_position(ordered: &[Value], key: &str) -> usize {
+    let keys: Vec<&str> = ordered.iter().map(element_sort_key).collect();
+    if let Some(first_descendant) = keys.iter().position(|k| is_descendant_key(k, key)) {
+        return first_descendant;
+    }
+
+    let ancestor = keys
+        .iter()
+        .enumerate()
+        .filter(|(_, k)| is_descendant_key(key, k))
+        .max_by_key(|(_, k)| k.len());
+    match ancestor {
+        Some((index, ancestor)) => keys
+            .iter()
+            .enumerate()
+            .skip(index + 1)
+            .rev()
+            .find(|(_, k)| is_descendant_key(k, ancestor))
+            .map_or(index + 1, |(last, _)| last + 1),
+        None => ordered.len(),
+    }
+}
+
+/// Whether element key `key` lies below `ancestor` (child or slice).
+fn is_descendant_key(key: &str, ancestor: &str) -> bool {
+    key.strip_prefix(ancestor)
+        .is_some_and(|rest| rest.starts_with(['.', ':']))
+}
+
+/// Get the ordering key of an element definition.
+fn element_sort_key(element: &Value) -> &str {
+    match element.get("id").and_then(Value::as_str) {
+        Some(id) if id.contains(':') => id,
+        _ => element.get("path").and_then(Value::as_str).unwrap_or(""),
+    }
+}
+
+/// Position of each slice of an `ordered` slicing, keyed by the slice's
+/// sort key (e.g., `Observation.component:systolic`).
+///
+/// Positions follow the order in which the slices appear in `elements`,
+/// which is the order they were defined in.
+fn ordered_slice_ranks<'a>(elements: impl Iterator<Item = &'a Value>) -> HashMap<String, usize> {
+    let elements: Vec<&Value> = elements.collect();
+
+    let ordered: HashSet<&str> = elements
+        .iter()
+        .filter(|e| e["slicing"]["ordered"].as_bool() == Some(true))
+        .map(|e| element_sort_key(e))
+        .collect();
+    if ordered.is_empty() {
+        return HashMap::new();
+    }
+
+    let mut next_rank: HashMap<&str, usize> = HashMap::new();
+    let mut ranks = HashMap::new();
+    for element in elements {
+        if element.get("sliceName").is_none() {
+            continue;
+        }
+        let key = element_sort_key(element);
+        let Some((sliced, _)) = key.rsplit_once(':') else {
+            continue;
+        };
+        if let Some(&sliced) = ordered.get(sliced) {
+            let rank = next_rank.entry(sliced).or_insert(0);
+            ranks.insert(key.to_string(), *rank);
+            *rank += 1;
+        }
+    }
+    ranks
+}
+
 /// Compare two element paths for ordering.
 /// Handles slice names correctly according to FHIR spec:
 /// - Base element comes first
 /// - Slices of an element come before children of that element
 /// - E.g., "Patient.name" < "Patient.name:official" < "Patient.name.family"
-fn compare_element_paths(a: &str, b: &str) -> std::cmp::Ordering {
+///
+/// Slices listed in `slice_ranks` are compared by position instead of name.
+fn compare_element_paths(
+    a: &str,
+    b: &str,
+    slice_ranks: &HashMap<String, usize>,
+) -> std::cmp::Ordering {
     use std::cmp::Ordering;
 
     let parts_a: Vec<&str> = a.split('.').collect();
@@ -333,10 +444,19 @@ fn compare_element_paths(a: &str, b: &str) -> std::cmp::Ordering {
                 }
                 return Ordering::Greater; // Slice comes after base
             }
-            (Some(sa), Some(sb)) => match sa.cmp(sb) {
-                Ordering::Equal => {}
-                other => return other,
-            },
+            (Some(sa), Some(sb)) => {
+                let by_rank = if sa != sb && !slice_ranks.is_empty() {
+                    let rank_a = slice_ranks.get(&parts_a[..=i].join("."));
+                    let rank_b = slice_ranks.get(&parts_b[..=i].join("."));
+                    rank_a.zip(rank_b).map(|(ra, rb)| ra.cmp(rb))
+                } else {
+                    None
+                };
+                match by_rank.unwrap_or_else(|| sa.cmp(sb)) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+            }
         }
     }
 
@@ -422,7 +542,7 @@ mod tests {
             "Patient.identifier",
         ];
 
-        paths.sort_by(|a, b| compare_element_paths(a, b));
+        paths.sort_by(|a, b| compare_element_paths(a, b, &HashMap::new()));
 
         assert_eq!(
             paths,
@@ -436,6 +556,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_order_elements_by_source() {
+        let element = |path: &str| serde_json::json!({ "path": path });
+        let ordered = order_elements_by_source(vec![
+            (element("Patient.name"), Some(0)),
+            (element("Patient.birthDate"), None),
+            (element("Patient.identifier"), Some(1)),
+            (element("Patient"), None),
+        ]);
+
+        let paths: Vec<&str> = ordered.iter().filter_map(|e| e["path"].as_str()).collect();
+        // Imported elements keep source order; new ones join their parent
+        assert_eq!(
+            paths,
+            vec![
+                "Patient",
+                "Patient.name",
+                "Patient.identifier",
+                "Patient.birthDate"
+            ]
+        );
+
+        // A new child goes right after its parent's last descendant
+        let ordered = order_elements_by_source(vec![
+            (element("Patient"), Some(0)),
+            (element("Patient.identifier"), Some(1)),
+            (element("Patient.identifier.system"), Some(2)),
+            (element("Patient.name"), Some(3)),
+            (element("Patient.identifier.value"), None),
+        ]);
+        let paths: Vec<&str> = ordered.iter().filter_map(|e| e["path"].as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "Patient",
+                "Patient.identifier",
+                "Patient.identifier.system",
+                "Patient.identifier.value",
+                "Patient.name"
+            ]
+        );
+    }
+
     #[test]
     fn test_optional_fields_omitted() {
         let mut builder = DeterministicJsonBuilder::for_element();
@@ -4,11 +4,16 @@
 //! The differential shows what constraints were added or changed
 //! compared to the base definition.
 
+use std::borrow::Cow;
+
+use schemars::JsonSchema;
+use serde::Serialize;
 use serde_json::Value;
 
 use crate::ir::ProfiledResource;
+use crate::merge::prune_inherited_text;
 
-use super::deterministic::sort_elements_by_path;
+use super::deterministic::order_elements_by_source;
 use super::element_serializer::ElementSerializer;
 use super::error::ExportResult;
 
@@ -31,28 +36,35 @@ impl DifferentialGenerator {
     /// Generate differential elements from a profiled resource.
     ///
     /// Returns a vector of ElementDefinition JSON values containing
-    /// only modified elements in canonical order.
+    /// only modified elements in canonical order. For a hydrated resource,
+    /// `short`/`definition`/`comment` values repeating the base are left out.
     pub async fn generate(&self, resource: &ProfiledResource) -> ExportResult<Vec<Value>> {
         let mut elements = Vec::new();
 
+        // Text repeating the base is inherited, not part of the differential
+        let differential = match resource.hydrated_base.as_deref() {
+            Some(base) => {
+                let mut differential = resource.differential.clone();
+                prune_inherited_text(&mut differential, base);
+                Cow::Owned(differential)
+            }
+            None => Cow::Borrowed(resource.differential.as_slice()),
+        };
+
         let root_path = resource.resource_type();
-        let has_root = resource
-            .differential
-            .iter()
-            .any(|diff| diff.path == root_path);
+        let has_root = differential.iter().any(|diff| diff.path == root_path);
         if !has_root {
             let root_diff = crate::merge::DifferentialElement::new(root_path.to_string());
-            elements.push(self.serializer.serialize_differential_element(&root_diff)?);
+            elements.push((self.serializer.serialize_differential_element(&root_diff)?, None));
         }
 
-        for diff in &resource.differential {
-            elements.push(self.serializer.serialize_differential_element(diff)?);
+        for diff in differential.iter() {
+            let element = self.serializer.serialize_differential_element(diff)?;
+            elements.push((element, diff.source_index));
         }
 
-        // Sort elements by path for deterministic ordering
-        sort_elements_by_path(&mut elements);
-
-        Ok(elements)
+        // Keep imported order; new elements are placed next to their parent
+        Ok(order_elements_by_source(elements))
     }
 }
 
@@ -88,7 +100,8 @@ impl DifferentialAnalyzer {
 }
 
 /// Statistics about the differential.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
 pub struct DifferentialStats {
     /// Total number of elements in differential.
     pub element_count: usize,
@@ -146,8 +159,13 @@ impl DifferentialStats {
 
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use super::*;
-    use crate::ir::{BaseDefinition, Cardinality, DifferentialElement, FhirVersion, ProfiledResource};
+    use crate::ir::{
+        BaseDefinition, Cardinality, DifferentialElement, ElementNode, FhirVersion,
+        ProfiledResource,
+    };
 
     fn create_test_resource() -> ProfiledResource {
         let mut resource = ProfiledResource::new(
@@ -201,6 +219,33 @@ mod tests {
         assert_eq!(elements[0].get("path").unwrap(), "Patient");
     }
 
+    #[tokio::test]
+    async fn test_differential_omits_inherited_text() {
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.constraints.short = Some("A name associated with the patient".to_string());
+        base.add_child(name);
+
+        let mut resource = create_test_resource();
+        resource.differential[0].constraints.short =
+            Some("A name associated with the patient".to_string());
+        resource.hydrated_base = Some(Arc::new(base));
+
+        let elements = DifferentialGenerator::new()
+            .generate(&resource)
+            .await
+            .unwrap();
+        let name = elements
+            .iter()
+            .find(|e| e.get("path").and_then(Value::as_str) == Some("Patient.name"))
+            .unwrap();
+        assert!(name.get("short").is_none());
+        assert_eq!(name.get("mustSupport"), Some(&Value::Bool(true)));
+
+        // The stored differential keeps the text
+        assert!(resource.differential[0].constraints.short.is_some());
+    }
+
     #[test]
     fn test_differential_stats() {
         let resource = create_test_resource();
@@ -8,6 +8,7 @@ use crate::ir::{
     Binding, BindingStrength, DifferentialElement, ElementConstraints, ElementNode, ElementSource,
     FixedValue, Invariant, InvariantSeverity, SliceNode, SlicingDefinition, TypeConstraint,
 };
+use crate::ir::element::element_definition_id;
 
 use super::deterministic::DeterministicJsonBuilder;
 use super::error::ExportResult;
@@ -89,9 +90,16 @@ impl ElementSerializer {
 
         // Slice identity
         let slice_path = format!("{}:{}", parent_path, slice.name);
-        builder.add_string("id", &self.generate_slice_id(&slice_path));
+        let slice_id = slice
+            .element
+            .element_id
+            .clone()
+            .filter(|id| id.contains(':'))
+            .unwrap_or_else(|| self.generate_slice_id(&slice_path));
+        builder.add_string("id", &slice_id);
         builder.add_string("path", parent_path);
         builder.add_string("sliceName", &slice.name);
+        builder.add_bool_if_true("sliceIsConstraining", slice.slice_is_constraining);
 
         // Constraints from the slice element
         self.serialize_constraints(&mut builder, &slice.element.constraints)?;
@@ -109,15 +117,27 @@ impl ElementSerializer {
     ) -> ExportResult<Value> {
         let mut builder = DeterministicJsonBuilder::for_element();
 
+        // Element paths never carry slice names, even for slice children
+        let path = strip_slice_names(&diff.path);
+
         if let Some(slice_name) = diff.slice_name.as_deref() {
-            let slice_id = format!("{}:{}", diff.path, slice_name);
+            // Prefer the stored id so re-slices (`foo/bar`) and slice children keep it
+            let slice_id = diff
+                .element_id
+                .clone()
+                .filter(|id| id.contains(':'))
+                .unwrap_or_else(|| format!("{}:{}", path, slice_name));
             builder.add_string("id", &slice_id);
-            builder.add_string("path", &diff.path);
-            builder.add_string("sliceName", slice_name);
+            builder.add_string("path", &path);
+            // Only the slice entry itself carries sliceName, not its children
+            if slice_id.ends_with(&format!(":{}", slice_name)) {
+                builder.add_string("sliceName", slice_name);
+                builder.add_bool_if_true("sliceIsConstraining", diff.slice_is_constraining);
+            }
         } else {
-            let id = diff.element_id.clone().unwrap_or_else(|| diff.path.clone());
+            let id = element_definition_id(diff.element_id.as_deref(), &diff.path);
             builder.add_string("id", &id);
-            builder.add_string("path", &diff.path);
+            builder.add_string("path", &path);
         }
 
         if let Some(slicing) = &diff.slicing {
@@ -193,6 +213,16 @@ impl ElementSerializer {
             builder.add_number("maxLength", max_len);
         }
 
+        // Conditions
+        if !constraints.conditions.is_empty() {
+            let conditions: Vec<Value> = constraints
+                .conditions
+                .iter()
+                .map(|key| Value::String(key.clone()))
+                .collect();
+            builder.add_array("condition", conditions);
+        }
+
         // Examples
         if !constraints.examples.is_empty() {
             let examples: Vec<Value> = constraints
@@ -201,14 +231,30 @@ impl ElementSerializer {
                 .map(|ex| {
                     let mut obj = Map::new();
                     obj.insert("label".to_string(), Value::String(ex.label.clone()));
-                    // Determine the type suffix for the example value
-                    self.insert_polymorphic_value(&mut obj, "value", &ex.value);
+                    // Prefer the recorded type, otherwise infer it from the value
+                    match &ex.value_type {
+                        Some(value_type) => {
+                            obj.insert(format!("value{}", value_type), ex.value.clone());
+                        }
+                        None => self.insert_polymorphic_value(&mut obj, "value", &ex.value),
+                    }
                     Value::Object(obj)
                 })
                 .collect();
             builder.add_array("example", examples);
         }
 
+        // Value requirements (R5+; stripped for older versions on export)
+        builder.add_bool_if_true("mustHaveValue", constraints.must_have_value);
+        if !constraints.value_alternatives.is_empty() {
+            let alternatives: Vec<Value> = constraints
+                .value_alternatives
+                .iter()
+                .map(|url| Value::String(url.clone()))
+                .collect();
+            builder.add_array("valueAlternatives", alternatives);
+        }
+
         // Flags
         builder.add_bool_if_true("mustSupport", constraints.flags.must_support);
         builder.add_bool_if_true("isModifier", constraints.flags.is_modifier);
@@ -347,6 +393,23 @@ impl ElementSerializer {
             obj.insert("description".to_string(), Value::String(desc.clone()));
         }
 
+        if !binding.additional.is_empty() {
+            let additional = binding
+                .additional
+                .iter()
+                .map(|a| {
+                    let mut entry = Map::new();
+                    entry.insert("purpose".to_string(), Value::String(a.purpose.clone()));
+                    entry.insert("valueSet".to_string(), Value::String(a.value_set.clone()));
+                    if let Some(doc) = &a.documentation {
+                        entry.insert("documentation".to_string(), Value::String(doc.clone()));
+                    }
+                    Value::Object(entry)
+                })
+                .collect();
+            obj.insert("additional".to_string(), Value::Array(additional));
+        }
+
         Value::Object(obj)
     }
 
@@ -441,9 +504,9 @@ impl ElementSerializer {
         }
     }
 
-    /// Generate element ID from path.
+    /// Generate element ID, preferring the stored id.
     fn generate_element_id(&self, element: &ElementNode) -> String {
-        element.element_id.clone().unwrap_or_else(|| element.path.clone())
+        element.element_definition_id()
     }
 
     /// Generate slice element ID.
@@ -473,6 +536,17 @@ impl ElementSerializer {
     }
 }
 
+/// Remove slice names from an element path (`a.b:s.c` becomes `a.b.c`).
+fn strip_slice_names(path: &str) -> String {
+    if !path.contains(':') {
+        return path.to_string();
+    }
+    path.split('.')
+        .map(|segment| segment.split_once(':').map_or(segment, |(name, _)| name))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -519,4 +593,29 @@ mod tests {
         let binding = result.get("binding").unwrap();
         assert_eq!(binding.get("strength").unwrap(), "required");
     }
+
+    #[test]
+    fn test_serialize_reslice_differential_element() {
+        let mut diff = DifferentialElement::new("Observation.component".to_string());
+        diff.element_id = Some("Observation.component:foo/bar".to_string());
+        diff.slice_name = Some("foo/bar".to_string());
+        diff.slice_is_constraining = true;
+
+        let serializer = ElementSerializer::new();
+        let result = serializer.serialize_differential_element(&diff).unwrap();
+
+        assert_eq!(result.get("id").unwrap(), "Observation.component:foo/bar");
+        assert_eq!(result.get("path").unwrap(), "Observation.component");
+        assert_eq!(result.get("sliceName").unwrap(), "foo/bar");
+        assert_eq!(result.get("sliceIsConstraining").unwrap(), true);
+    }
+
+    #[test]
+    fn test_strip_slice_names() {
+        assert_eq!(
+            strip_slice_names("Observation.component:foo/bar.code"),
+            "Observation.component.code"
+        );
+        assert_eq!(strip_slice_names("Patient.name"), "Patient.name");
+    }
 }
@@ -331,9 +331,12 @@ fn merge_array_objects_by_key(target: &mut Vec<Value>, source: &[Value], key: &s
         }
     }
 
-    for (id, source_obj) in source_map {
-        if !seen.contains(&id) {
-            target.push(Value::Object(source_obj.clone()));
+    // Append unmatched entries in source order so output stays deterministic
+    for value in source {
+        let Some(obj) = value.as_object() else { continue };
+        let Some(id) = obj.get(key).and_then(Value::as_str) else { continue };
+        if seen.insert(id.to_string()) {
+            target.push(Value::Object(obj.clone()));
         }
     }
 }
@@ -439,4 +442,18 @@ mod tests {
         assert!(b.contains_key("x"));
         assert!(b.contains_key("y"));
     }
+
+    #[test]
+    fn test_merge_by_key_keeps_source_order() {
+        let source: Vec<Value> = ["ele-1", "obs-6", "obs-7", "bp-1"]
+            .iter()
+            .map(|key| serde_json::json!({ "key": key }))
+            .collect();
+        let mut target = vec![serde_json::json!({ "key": "obs-6", "severity": "error" })];
+
+        merge_array_objects_by_key(&mut target, &source, "key");
+
+        let keys: Vec<&str> = target.iter().filter_map(|v| v["key"].as_str()).collect();
+        assert_eq!(keys, vec!["obs-6", "ele-1", "obs-7", "bp-1"]);
+    }
 }
@@ -82,8 +82,8 @@ mod snapshot_generator;
 
 // Re-export main types
 pub use deterministic::{
-    DeterministicJsonBuilder, recursively_sort_value, sort_elements_by_path, to_canonical_json,
-    to_pretty_json,
+    DeterministicJsonBuilder, order_elements_by_source, recursively_sort_value,
+    sort_elements_by_path, to_canonical_json, to_pretty_json,
 };
 pub use differential_generator::{DifferentialAnalyzer, DifferentialGenerator, DifferentialStats};
 pub use element_serializer::ElementSerializer;
@@ -94,7 +94,7 @@ pub use field_preservation::{
     FieldPreserver, UnknownFieldCollector, UnknownFieldInfo, merge_original_sd_fields,
 };
 pub use sd_exporter::{ExportConfig, StructureDefinitionExporter};
-pub use snapshot_generator::{SnapshotConfig, SnapshotGenerator};
+pub use snapshot_generator::{SnapshotConfig, SnapshotGenerator, SnapshotMode};
 
 #[cfg(test)]
 mod tests {
@@ -6,13 +6,13 @@
 
 use serde_json::{Map, Value};
 
-use crate::ir::{ProfileDocument, ProfileStatus, StructureKind};
+use crate::ir::{Derivation, ProfileDocument, ProfileStatus, StructureKind};
 
 use super::deterministic::{to_canonical_json, to_pretty_json, DeterministicJsonBuilder};
 use super::differential_generator::DifferentialGenerator;
 use super::error::{ExportError, ExportResult, ExportResultWithWarnings, ExportWarning, ExportWarningCode};
 use super::field_preservation::FieldPreserver;
-use super::snapshot_generator::SnapshotGenerator;
+use super::snapshot_generator::{SnapshotConfig, SnapshotGenerator, SnapshotMode};
 
 /// Configuration for SD export.
 #[derive(Debug, Clone)]
@@ -27,6 +27,11 @@ pub struct ExportConfig {
     pub pretty_print: bool,
     /// Whether to preserve unknown fields.
     pub preserve_unknown_fields: bool,
+    /// Whether to stamp the export time as `date` when the metadata has none.
+    /// When false, a missing date is omitted.
+    pub default_date_to_now: bool,
+    /// Which elements the snapshot lists.
+    pub snapshot_mode: SnapshotMode,
 }
 
 impl Default for ExportConfig {
@@ -37,6 +42,8 @@ impl Default for ExportConfig {
             validate: true,
             pretty_print: false,
             preserve_unknown_fields: true,
+            default_date_to_now: false,
+            snapshot_mode: SnapshotMode::Full,
         }
     }
 }
@@ -75,6 +82,20 @@ impl ExportConfig {
         self.validate = false;
         self
     }
+
+    /// Emit the current time as `date` when the metadata has none.
+    #[must_use]
+    pub fn with_default_date(mut self) -> Self {
+        self.default_date_to_now = true;
+        self
+    }
+
+    /// Emit a minimal, non-standard snapshot (see [`SnapshotMode::Minimal`]).
+    #[must_use]
+    pub fn minimal_snapshot(mut self) -> Self {
+        self.snapshot_mode = SnapshotMode::Minimal;
+        self
+    }
 }
 
 /// Main exporter for StructureDefinition.
@@ -108,9 +129,13 @@ impl StructureDefinitionExporter {
     /// Create an exporter with custom configuration.
     #[must_use]
     pub fn with_config(config: ExportConfig) -> Self {
+        let snapshot_generator = SnapshotGenerator::with_config(SnapshotConfig {
+            mode: config.snapshot_mode,
+            ..SnapshotConfig::default()
+        });
         Self {
             config,
-            snapshot_generator: SnapshotGenerator::new(),
+            snapshot_generator,
             differential_generator: DifferentialGenerator::new(),
             field_preserver: FieldPreserver::new(),
         }
@@ -191,9 +216,12 @@ impl StructureDefinitionExporter {
         builder.add_bool_if_true("experimental", metadata.experimental);
 
         // Date
-        if let Some(date) = &metadata.date {
-            builder.add_string("date", &date.to_rfc3339());
-        }
+        let date = metadata.date.clone().or_else(|| {
+            self.config
+                .default_date_to_now
+                .then(crate::ir::document::now_date_time)
+        });
+        builder.add_optional_string("date", date.as_deref());
 
         // Publisher and contact
         builder.add_optional_string("publisher", metadata.publisher.as_deref());
@@ -247,25 +275,96 @@ impl StructureDefinitionExporter {
 
         // Structure metadata
         builder.add_string("kind", self.format_kind(resource.kind));
-        builder.add_bool("abstract", false);
+        builder.add_bool("abstract", resource.is_abstract);
+
+        // Extension context
+        if !resource.context.is_empty() {
+            let contexts: Vec<Value> = resource
+                .context
+                .iter()
+                .map(|c| {
+                    serde_json::json!({
+                        "type": c.context_type.as_str(),
+                        "expression": c.expression,
+                    })
+                })
+                .collect();
+            builder.add_array("context", contexts);
+        }
+        if !resource.context_invariants.is_empty() {
+            let invariants: Vec<Value> = resource
+                .context_invariants
+                .iter()
+                .map(|expression| Value::String(expression.clone()))
+                .collect();
+            builder.add_array("contextInvariant", invariants);
+        }
+
         builder.add_string("type", resource.resource_type());
-        builder.add_string("baseDefinition", &resource.base.url);
-        builder.add_string("derivation", "constraint");
+        builder.add_string("baseDefinition", &resource.base.canonical());
+        let derivation = if resource.is_specialization() {
+            Derivation::Specialization
+        } else {
+            resource.derivation
+        };
+        builder.add_string("derivation", derivation.as_str());
+
+        // binding.additional only exists from R5 on
+        let strip_additional_bindings = !resource.fhir_version.supports_additional_bindings();
+        let mut stripped_additional = 0;
+
+        // So do mustHaveValue and valueAlternatives
+        let strip_value_constraints = !resource.fhir_version.supports_value_constraints();
+        let mut stripped_value_constraints = 0;
 
         // Generate snapshot if configured
         if self.config.include_snapshot {
-            let snapshot_elements = self.snapshot_generator.generate(resource).await?;
+            let mut snapshot_elements = self.snapshot_generator.generate(resource).await?;
+            if strip_additional_bindings {
+                stripped_additional += strip_additional_bindings_from(&mut snapshot_elements);
+            }
+            if strip_value_constraints {
+                stripped_value_constraints += strip_value_constraints_from(&mut snapshot_elements);
+            }
             let snapshot_obj = self.build_element_array("snapshot", snapshot_elements);
             builder.add_value("snapshot", snapshot_obj);
         }
 
         // Generate differential if configured
         if self.config.include_differential {
-            let diff_elements = self.differential_generator.generate(resource).await?;
+            let mut diff_elements = self.differential_generator.generate(resource).await?;
+            if strip_additional_bindings {
+                stripped_additional += strip_additional_bindings_from(&mut diff_elements);
+            }
+            if strip_value_constraints {
+                stripped_value_constraints += strip_value_constraints_from(&mut diff_elements);
+            }
             let diff_obj = self.build_element_array("differential", diff_elements);
             builder.add_value("differential", diff_obj);
         }
 
+        if stripped_additional > 0 {
+            warnings.push(ExportWarning::new(
+                ExportWarningCode::ValidationHint,
+                format!(
+                    "{} additional binding(s) omitted: not supported in FHIR {}",
+                    stripped_additional,
+                    resource.fhir_version.label()
+                ),
+            ));
+        }
+
+        if stripped_value_constraints > 0 {
+            warnings.push(ExportWarning::new(
+                ExportWarningCode::ValidationHint,
+                format!(
+                    "{} mustHaveValue/valueAlternatives constraint(s) omitted: not supported in FHIR {}",
+                    stripped_value_constraints,
+                    resource.fhir_version.label()
+                ),
+            ));
+        }
+
         // Preserve unknown fields
         if self.config.preserve_unknown_fields {
             let mut result = builder.build();
@@ -555,6 +654,35 @@ fn is_valid_name(name: &str) -> bool {
     name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
 }
 
+/// Remove `binding.additional` from serialized elements, returning how many
+/// elements carried one.
+fn strip_additional_bindings_from(elements: &mut [Value]) -> usize {
+    let mut stripped = 0;
+    for element in elements.iter_mut() {
+        if let Some(binding) = element.get_mut("binding").and_then(Value::as_object_mut) {
+            if binding.remove("additional").is_some() {
+                stripped += 1;
+            }
+        }
+    }
+    stripped
+}
+
+/// Remove `mustHaveValue` and `valueAlternatives`, returning how many were removed.
+fn strip_value_constraints_from(elements: &mut [Value]) -> usize {
+    let mut stripped = 0;
+    for element in elements.iter_mut() {
+        if let Some(obj) = element.as_object_mut() {
+            for key in ["mustHaveValue", "valueAlternatives"] {
+                if obj.remove(key).is_some() {
+                    stripped += 1;
+                }
+            }
+        }
+    }
+    stripped
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -604,6 +732,36 @@ mod tests {
         assert_eq!(parsed.get("derivation").unwrap(), "constraint");
     }
 
+    #[tokio::test]
+    async fn test_export_logical_model() {
+        let url = "http://example.org/fhir/StructureDefinition/LabSample";
+        let metadata = DocumentMetadata::new("lab-sample", url, "LabSample");
+        let mut resource = ProfiledResource::logical(url, FhirVersion::R4, "LabSample");
+
+        let mut barcode = ElementNode::new("LabSample.barcode".to_string());
+        barcode.source = ElementSource::Added;
+        barcode.constraints.cardinality = Some(Cardinality::required());
+        barcode.constraints.types = vec![crate::ir::TypeConstraint::simple("string")];
+        resource.root.add_child(barcode);
+        resource.extract_differential();
+
+        let document = ProfileDocument::new(metadata, resource);
+        let mut exporter = StructureDefinitionExporter::new();
+        let parsed: Value = serde_json::from_str(&exporter.export(&document).await.unwrap()).unwrap();
+
+        assert_eq!(parsed["kind"], "logical");
+        assert_eq!(parsed["derivation"], "specialization");
+        assert_eq!(parsed["type"], "LabSample");
+        assert_eq!(
+            parsed["baseDefinition"],
+            "http://hl7.org/fhir/StructureDefinition/Element"
+        );
+
+        let differential = parsed["differential"]["element"].as_array().unwrap();
+        assert!(differential.iter().any(|e| e["path"] == "LabSample"));
+        assert!(differential.iter().any(|e| e["path"] == "LabSample.barcode"));
+    }
+
     #[tokio::test]
     async fn test_export_with_snapshot() {
         let document = create_test_document();
@@ -684,6 +842,104 @@ mod tests {
         assert!(!is_valid_name("")); // Empty
     }
 
+    #[tokio::test]
+    async fn test_export_with_slices_is_byte_identical() {
+        use crate::ir::{Discriminator, SliceNode, SlicingDefinition};
+
+        let url = "http://example.org/fhir/StructureDefinition/TestBP";
+        let metadata = DocumentMetadata::new("test-bp", url, "TestBP");
+        let mut resource = ProfiledResource::new(
+            url,
+            FhirVersion::R4,
+            BaseDefinition::resource("Observation"),
+        );
+
+        let mut component = ElementNode::new("Observation.component".to_string());
+        component.source = ElementSource::Modified;
+        component.slicing = Some(SlicingDefinition::new(vec![Discriminator::value("code")]));
+        for name in ["systolic", "diastolic", "mean"] {
+            let mut slice =
+                SliceNode::with_path(name, format!("Observation.component:{}", name));
+            slice.element.source = ElementSource::Modified;
+            slice.element.constraints.cardinality = Some(Cardinality::required());
+            component.add_slice(name.to_string(), slice);
+        }
+        resource.root.add_child(component);
+        resource.extract_differential();
+
+        let document = ProfileDocument::new(metadata, resource);
+        let first = StructureDefinitionExporter::new().export(&document).await.unwrap();
+        let second = StructureDefinitionExporter::new().export(&document).await.unwrap();
+        assert_eq!(first, second);
+
+        let parsed: Value = serde_json::from_str(&first).unwrap();
+        let slice_names: Vec<&str> = parsed["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|e| e.get("sliceName").and_then(Value::as_str))
+            .collect();
+        assert_eq!(slice_names.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_ordered_slices_round_trip_in_defined_order() {
+        use crate::import::StructureDefinitionImporter;
+        use crate::ir::{Discriminator, SliceNode, SlicingDefinition};
+
+        let url = "http://example.org/fhir/StructureDefinition/OrderedBP";
+        let metadata = DocumentMetadata::new("ordered-bp", url, "OrderedBP");
+        let mut resource = ProfiledResource::new(
+            url,
+            FhirVersion::R4,
+            BaseDefinition::resource("Observation"),
+        );
+
+        let mut component = ElementNode::new("Observation.component".to_string());
+        component.source = ElementSource::Modified;
+        let mut slicing = SlicingDefinition::new(vec![Discriminator::value("code")]);
+        slicing.ordered = true;
+        component.slicing = Some(slicing);
+        // Deliberately not alphabetical
+        let defined = ["zeta", "alpha", "mid"];
+        for name in defined {
+            let mut slice =
+                SliceNode::with_path(name, format!("Observation.component:{}", name));
+            slice.element.source = ElementSource::Modified;
+            slice.element.constraints.cardinality = Some(Cardinality::required());
+            component.add_slice(name.to_string(), slice);
+        }
+        resource.root.add_child(component);
+        resource.extract_differential();
+
+        let slice_names = |sd: &Value, section: &str| -> Vec<String> {
+            sd[section]["element"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|e| e.get("sliceName").and_then(Value::as_str))
+                .map(str::to_string)
+                .collect()
+        };
+
+        let document = ProfileDocument::new(metadata, resource);
+        let exported = StructureDefinitionExporter::new().export(&document).await.unwrap();
+        let parsed: Value = serde_json::from_str(&exported).unwrap();
+        assert_eq!(slice_names(&parsed, "differential"), defined);
+        assert_eq!(slice_names(&parsed, "snapshot"), defined);
+
+        let imported = StructureDefinitionImporter::new()
+            .import_json(&exported)
+            .await
+            .unwrap();
+        let config = ExportConfig::differential_only().skip_validation();
+        let reexported = StructureDefinitionExporter::with_config(config)
+            .export_value(&imported)
+            .await
+            .unwrap();
+        assert_eq!(slice_names(&reexported, "differential"), defined);
+    }
+
     #[tokio::test]
     async fn test_differential_only_export() {
         let document = create_test_document();
@@ -3,10 +3,15 @@
 //! Generates complete snapshot from the IR element tree.
 //! The snapshot contains all elements with their full constraints,
 //! including inherited values from the base definition.
+//!
+//! [`SnapshotMode::Minimal`] instead emits only the elements the profile
+//! touches plus the ancestors needed to reach them. Such a snapshot is
+//! **not** a valid FHIR snapshot (which must list every element); it is meant
+//! for tooling that only needs the constrained parts and wants them fast.
 
 use serde_json::Value;
 
-use crate::ir::{ElementNode, ProfiledResource};
+use crate::ir::{ElementNode, ElementSource, ProfiledResource};
 
 use super::deterministic::sort_elements_by_path;
 use super::element_serializer::ElementSerializer;
@@ -17,14 +22,23 @@ use super::error::{ExportError, ExportResult};
 pub struct SnapshotGenerator {
     /// Element serializer.
     serializer: ElementSerializer,
+    /// Generator configuration.
+    config: SnapshotConfig,
 }
 
 impl SnapshotGenerator {
     /// Create a new snapshot generator.
     #[must_use]
     pub fn new() -> Self {
+        Self::with_config(SnapshotConfig::default())
+    }
+
+    /// Create a snapshot generator with custom configuration.
+    #[must_use]
+    pub fn with_config(config: SnapshotConfig) -> Self {
         Self {
             serializer: ElementSerializer::new().include_inherited(true),
+            config,
         }
     }
 
@@ -32,19 +46,28 @@ impl SnapshotGenerator {
     ///
     /// Returns a vector of ElementDefinition JSON values in canonical order.
     pub async fn generate(&self, resource: &ProfiledResource) -> ExportResult<Vec<Value>> {
+        let minimal;
+        let root = match self.config.mode {
+            SnapshotMode::Full => &resource.root,
+            SnapshotMode::Minimal => {
+                minimal = minimal_tree(&resource.root);
+                &minimal
+            }
+        };
+
         let mut elements = Vec::new();
 
         // Recursively collect all elements from the tree
-        self.collect_elements(&resource.root, &mut elements)?;
+        self.collect_elements(root, &mut elements)?;
 
         // Sort elements by path for deterministic ordering
         sort_elements_by_path(&mut elements);
 
         // Validate snapshot element count
-        let expected_count = count_all_elements(&resource.root);
+        let expected_count = count_all_elements(root);
         if elements.len() != expected_count {
             return Err(ExportError::snapshot_generation(
-                &resource.root.path,
+                &root.path,
                 format!(
                     "Element count mismatch: expected {}, got {}",
                     expected_count,
@@ -142,6 +165,34 @@ impl SnapshotGenerator {
     }
 }
 
+/// Copy of a tree keeping only touched elements and their ancestors.
+///
+/// The root is always kept.
+fn minimal_tree(root: &ElementNode) -> ElementNode {
+    let mut pruned = root.clone();
+    prune_untouched(&mut pruned);
+    pruned
+}
+
+/// Drop descendants that neither are touched nor lead to a touched element.
+fn prune_untouched(element: &mut ElementNode) {
+    element.children.retain(is_touched);
+    element.slices.retain(|_, slice| is_touched(&slice.element));
+    for child in &mut element.children {
+        prune_untouched(child);
+    }
+    for slice in element.slices.values_mut() {
+        prune_untouched(&mut slice.element);
+    }
+}
+
+/// Whether the profile touches an element or anything below it.
+fn is_touched(element: &ElementNode) -> bool {
+    element.source != ElementSource::Inherited
+        || element.children.iter().any(is_touched)
+        || element.slices.values().any(|slice| is_touched(&slice.element))
+}
+
 /// Count all elements in a tree (including slices).
 fn count_all_elements(root: &ElementNode) -> usize {
     let mut count = 1; // Count this element
@@ -170,6 +221,17 @@ fn count_slice_children(element: &ElementNode) -> usize {
     count
 }
 
+/// Which elements a generated snapshot lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SnapshotMode {
+    /// Every element, inherited ones included (a standard FHIR snapshot).
+    #[default]
+    Full,
+    /// Only touched elements plus required ancestors. Non-standard: FHIR
+    /// requires a snapshot to list every element.
+    Minimal,
+}
+
 /// Snapshot generator configuration.
 #[derive(Debug, Clone)]
 pub struct SnapshotConfig {
@@ -177,6 +239,8 @@ pub struct SnapshotConfig {
     pub validate_ids: bool,
     /// Whether to include base element info.
     pub include_base: bool,
+    /// Which elements to list.
+    pub mode: SnapshotMode,
 }
 
 impl Default for SnapshotConfig {
@@ -184,6 +248,18 @@ impl Default for SnapshotConfig {
         Self {
             validate_ids: true,
             include_base: false,
+            mode: SnapshotMode::Full,
+        }
+    }
+}
+
+impl SnapshotConfig {
+    /// Config for a minimal (non-standard) snapshot.
+    #[must_use]
+    pub fn minimal() -> Self {
+        Self {
+            mode: SnapshotMode::Minimal,
+            ..Self::default()
         }
     }
 }
@@ -252,4 +328,39 @@ mod tests {
         assert!(paths.contains(&"Patient.identifier"));
         assert!(paths.contains(&"Patient.name"));
     }
+
+    #[tokio::test]
+    async fn test_minimal_snapshot_has_fewer_elements() {
+        let mut resource = create_test_resource();
+        resource.root.children[0].source = ElementSource::Modified;
+
+        // Touched element under an untouched parent
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        let mut system = ElementNode::new("Patient.identifier.system".to_string());
+        system.source = ElementSource::Modified;
+        identifier.add_child(system);
+        resource.root.add_child(identifier);
+        resource
+            .root
+            .add_child(ElementNode::new("Patient.gender".to_string()));
+
+        let full = SnapshotGenerator::new().generate(&resource).await.unwrap();
+        let minimal = SnapshotGenerator::with_config(SnapshotConfig::minimal())
+            .generate(&resource)
+            .await
+            .unwrap();
+        assert_eq!(full.len(), 6);
+        assert_eq!(minimal.len(), 4);
+
+        let paths: Vec<&str> = minimal
+            .iter()
+            .filter_map(|e| e.get("path").and_then(Value::as_str))
+            .collect();
+        assert!(paths.contains(&"Patient"));
+        assert!(paths.contains(&"Patient.name"));
+        assert!(paths.contains(&"Patient.identifier"));
+        assert!(paths.contains(&"Patient.identifier.system"));
+        assert!(!paths.contains(&"Patient.name.family"));
+        assert!(!paths.contains(&"Patient.gender"));
+    }
 }
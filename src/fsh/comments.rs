@@ -0,0 +1,100 @@
+//! FSH Comment Preservation
+//!
+//! The maki parser discards comments, so a round trip through the IR would
+//! lose them. Only the comment block at the top of a file (before the first
+//! declaration) is kept: it usually describes the file, and it has an obvious
+//! place to go back to in the decompiled output.
+
+/// Extract the comment block that opens an FSH file.
+///
+/// Covers `//` line comments and `/* */` block comments, and any blank lines
+/// between them. Returns `None` if the first declaration comes before any
+/// comment.
+pub fn leading_comment(fsh: &str) -> Option<String> {
+    let mut block: Vec<&str> = Vec::new();
+    let mut in_block_comment = false;
+
+    for line in fsh.lines() {
+        let trimmed = line.trim();
+
+        if in_block_comment {
+            block.push(line);
+            if let Some(end) = trimmed.find("*/") {
+                in_block_comment = false;
+                // Code after the closing marker ends the block; keep the comment anyway
+                if !trimmed[end + 2..].trim().is_empty() {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        if trimmed.is_empty() {
+            if !block.is_empty() {
+                block.push(line);
+            }
+        } else if trimmed.starts_with("//") {
+            block.push(line);
+        } else if let Some(rest) = trimmed.strip_prefix("/*") {
+            match rest.find("*/") {
+                Some(end) if !rest[end + 2..].trim().is_empty() => break,
+                Some(_) => block.push(line),
+                None => {
+                    block.push(line);
+                    in_block_comment = true;
+                }
+            }
+        } else {
+            break;
+        }
+    }
+
+    while block.last().is_some_and(|line| line.trim().is_empty()) {
+        block.pop();
+    }
+
+    if block.is_empty() {
+        None
+    } else {
+        Some(block.iter().map(|line| line.trim_end()).collect::<Vec<_>>().join("\n"))
+    }
+}
+
+/// Put a preserved comment block back at the top of generated FSH.
+///
+/// Output that already starts with the block is returned unchanged, so
+/// applying this twice does not duplicate the comment.
+pub fn with_leading_comment(fsh: &str, comment: Option<&str>) -> String {
+    match comment {
+        Some(comment) if !fsh.trim_start().starts_with(comment) => {
+            format!("{}\n\n{}", comment, fsh.trim_start())
+        }
+        _ => fsh.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leading_comment_forms() {
+        let fsh = "\n// Patient profiles for the demo IG\n/*\n * Maintained by the\n * demo team\n */\n\nProfile: DemoPatient\n// not leading\n";
+        assert_eq!(
+            leading_comment(fsh).as_deref(),
+            Some("// Patient profiles for the demo IG\n/*\n * Maintained by the\n * demo team\n */")
+        );
+
+        assert_eq!(leading_comment("Profile: DemoPatient\n// trailing\n"), None);
+        assert_eq!(leading_comment("/* inline */ Profile: DemoPatient\n"), None);
+    }
+
+    #[test]
+    fn test_with_leading_comment_is_idempotent() {
+        let fsh = "Profile: DemoPatient\nParent: Patient\n";
+        let restored = with_leading_comment(fsh, Some("// Demo"));
+        assert_eq!(restored, "// Demo\n\nProfile: DemoPatient\nParent: Patient\n");
+        assert_eq!(with_leading_comment(&restored, Some("// Demo")), restored);
+        assert_eq!(with_leading_comment(fsh, None), fsh);
+    }
+}
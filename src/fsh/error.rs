@@ -3,7 +3,7 @@
 //! Error handling for FSH import/export operations, preserving
 //! maki-core diagnostics and providing actionable error messages.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 /// Result type for FSH operations.
 pub type FshResult<T> = std::result::Result<T, FshError>;
@@ -94,6 +94,22 @@ impl FshImportError {
             message: message.into(),
         }
     }
+
+    /// Source file the error refers to, if known.
+    pub fn file(&self) -> Option<&Path> {
+        match self {
+            Self::Parse { file, .. } | Self::Semantic { file, .. } => Some(file),
+            _ => None,
+        }
+    }
+
+    /// Line and column (1-based) of the error, if known.
+    pub fn position(&self) -> Option<(usize, usize)> {
+        match self {
+            Self::Parse { line, column, .. } => Some((*line, *column)),
+            _ => None,
+        }
+    }
 }
 
 /// Warning during FSH import (non-fatal).
@@ -128,6 +144,21 @@ pub enum FshWarningCode {
     DuplicateDefinition,
 }
 
+impl FshWarningCode {
+    /// Get the diagnostic code.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnrecognizedConstruct => "UNRECOGNIZED_CONSTRUCT",
+            Self::DeprecatedSyntax => "DEPRECATED_SYNTAX",
+            Self::MissingMetadata => "MISSING_METADATA",
+            Self::PotentialDataLoss => "POTENTIAL_DATA_LOSS",
+            Self::UnresolvedReference => "UNRESOLVED_REFERENCE",
+            Self::DuplicateDefinition => "DUPLICATE_DEFINITION",
+        }
+    }
+}
+
 impl FshWarning {
     /// Create a new warning.
     pub fn new(code: FshWarningCode, message: impl Into<String>) -> Self {
@@ -147,6 +178,27 @@ impl FshWarning {
         self.column = Some(column);
         self
     }
+
+    /// Set the source file without a position.
+    pub fn with_file(mut self, file: PathBuf) -> Self {
+        self.file = Some(file);
+        self
+    }
+
+    /// Build a warning from an import error, keeping its file and position.
+    ///
+    /// Used when a failed file is skipped so the error stays locatable.
+    pub fn from_import_error(error: &FshImportError, file: &Path) -> Self {
+        let warning = Self::new(
+            FshWarningCode::PotentialDataLoss,
+            format!("Failed to import {}: {}", file.display(), error),
+        );
+        let file = error.file().unwrap_or(file).to_path_buf();
+        match error.position() {
+            Some((line, column)) => warning.with_location(file, line, column),
+            None => warning.with_file(file),
+        }
+    }
 }
 
 impl std::fmt::Display for FshWarning {
@@ -9,6 +9,7 @@ use crate::decompiler::{decompile_sd_to_fsh, DecompilerError};
 use crate::export::StructureDefinitionExporter;
 use crate::ir::ProfileDocument;
 
+use super::comments::with_leading_comment;
 use super::error::{FshError, FshResult, FshResultWithWarnings, FshWarning, FshWarningCode};
 
 /// Options for FSH export.
@@ -117,7 +118,12 @@ impl FshExporter {
                 other => FshError::Decompiler(other),
             })?;
 
-        // Step 3: Post-process FSH (apply formatting options)
+        // Step 3: Post-process FSH (restore comments, apply formatting options)
+        let fsh = if self.options.include_comments {
+            with_leading_comment(&fsh, document.resource.fsh_comment.as_deref())
+        } else {
+            fsh
+        };
         let formatted_fsh = self.post_process_fsh(&fsh);
 
         info!(
@@ -198,11 +204,13 @@ pub fn generate_basic_fsh(document: &ProfileDocument) -> String {
     // Profile header
     lines.push(format!("Profile: {}", document.metadata.name));
 
-    // Parent
-    if let Some(base_name) = &document.resource.base.name {
-        lines.push(format!("Parent: {}", base_name));
-    } else {
-        lines.push(format!("Parent: {}", document.resource.base.url));
+    // Parent (a version pin needs the full canonical)
+    let base = &document.resource.base;
+    match &base.name {
+        Some(base_name) if base.pinned_version().is_none() => {
+            lines.push(format!("Parent: {}", base_name));
+        }
+        _ => lines.push(format!("Parent: {}", base.canonical())),
     }
 
     // Id
@@ -386,6 +394,17 @@ mod tests {
         assert!(fsh.contains("Title: \"Test Patient Profile\""));
     }
 
+    #[test]
+    fn test_generate_basic_fsh_keeps_pinned_parent() {
+        let mut doc = create_test_document();
+        let url = "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient";
+        doc.resource.base = BaseDefinition::from_canonical(&format!("{url}|6.1.0"))
+            .with_name("USCorePatientProfile");
+
+        let fsh = generate_basic_fsh(&doc);
+        assert!(fsh.contains(&format!("Parent: {url}|6.1.0")));
+    }
+
     #[test]
     fn test_escape_fsh_string() {
         assert_eq!(escape_fsh_string("hello"), "hello");
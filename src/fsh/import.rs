@@ -16,8 +16,9 @@ use tracing::{debug, info, warn};
 
 use crate::ir::{FhirVersion, ProfileDocument};
 
+use super::comments::leading_comment;
 use super::error::{FshError, FshImportError, FshResult, FshResultWithWarnings, FshWarning, FshWarningCode};
-use super::mapper::FshToIrMapper;
+use super::mapper::{FshInstance, FshToIrMapper};
 
 /// Options for FSH import.
 #[derive(Debug, Clone)]
@@ -60,6 +61,15 @@ impl FshImportOptions {
     }
 }
 
+/// Everything imported from a FSH source: profiles and example instances.
+#[derive(Debug, Default)]
+pub struct FshImportOutput {
+    /// Profiles mapped to IR documents.
+    pub profiles: Vec<ProfileDocument>,
+    /// `Instance` definitions mapped to raw resource JSON.
+    pub instances: Vec<FshInstance>,
+}
+
 /// FSH file importer.
 ///
 /// Imports single FSH files into IR ProfileDocuments.
@@ -160,11 +170,34 @@ impl FshImporter {
     }
 
     /// Import FSH content from a string.
+    ///
+    /// Only profiles are returned; use [`Self::import_content_with_instances`]
+    /// to also collect `Instance` definitions.
     pub async fn import_content(
         &self,
         content: &str,
         source_file: &Path,
     ) -> FshResult<FshResultWithWarnings<Vec<ProfileDocument>>> {
+        let result = self.import_content_with_instances(content, source_file).await?;
+
+        if result.value.profiles.is_empty() {
+            return Err(FshError::Import(FshImportError::ResourceNotFound {
+                name: "Profile".to_string(),
+            }));
+        }
+
+        Ok(FshResultWithWarnings::with_warnings(
+            result.value.profiles,
+            result.warnings,
+        ))
+    }
+
+    /// Import FSH content from a string, including `Instance` definitions.
+    pub async fn import_content_with_instances(
+        &self,
+        content: &str,
+        source_file: &Path,
+    ) -> FshResult<FshResultWithWarnings<FshImportOutput>> {
         let mut warnings = Vec::new();
 
         // Parse FSH
@@ -202,24 +235,92 @@ impl FshImporter {
         }
 
         // Map to IR
-        let (documents, mapper_warnings) = self.mapper.map_semantic_model(&semantic_model)
+        let (mut documents, mapper_warnings) = self.mapper.map_semantic_model(&semantic_model)
             .map_err(FshError::Import)?;
-
         warnings.extend(mapper_warnings);
 
-        if documents.is_empty() {
+        // The parser drops comments; keep the file's leading block on the first
+        // profile only, so exporting every profile of the file repeats it once
+        if let Some(first) = documents.first_mut() {
+            first.resource.fsh_comment = leading_comment(content);
+        }
+
+        let (instances, instance_warnings) = self.mapper.map_instances(&semantic_model);
+        warnings.extend(instance_warnings);
+
+        // Mapper warnings only carry positions; attribute them to this file
+        for warning in &mut warnings {
+            warning.file.get_or_insert_with(|| source_file.to_path_buf());
+        }
+
+        if documents.is_empty() && instances.is_empty() {
             return Err(FshError::Import(FshImportError::ResourceNotFound {
-                name: "Profile".to_string(),
+                name: "Profile or Instance".to_string(),
             }));
         }
 
         info!(
-            "Successfully imported {} profile(s) from {}",
+            "Successfully imported {} profile(s) and {} instance(s) from {}",
             documents.len(),
+            instances.len(),
             source_file.display()
         );
 
-        Ok(FshResultWithWarnings::with_warnings(documents, warnings))
+        Ok(FshResultWithWarnings::with_warnings(
+            FshImportOutput {
+                profiles: documents,
+                instances,
+            },
+            warnings,
+        ))
+    }
+
+    /// Check FSH content without importing it.
+    ///
+    /// Reports every parse error rather than stopping at the first, then runs
+    /// semantic analysis and IR mapping to collect their errors and warnings.
+    /// Nothing is added to the fishing context.
+    pub fn check_content(
+        &self,
+        content: &str,
+        source_file: &Path,
+    ) -> FshResult<FshResultWithWarnings<Vec<FshImportError>>> {
+        let mut errors = Vec::new();
+        let mut warnings = Vec::new();
+
+        let parse_result = self.parse_fsh(content, source_file)?;
+        for error in &parse_result.errors {
+            errors.push(FshImportError::parse(
+                source_file,
+                error.line + 1,
+                error.column + 1,
+                &error.message,
+            ));
+        }
+        // A broken CST only produces follow-on noise in later stages
+        if !errors.is_empty() {
+            return Ok(FshResultWithWarnings::with_warnings(errors, warnings));
+        }
+
+        let semantic_model = match self.analyze_semantics(&parse_result, source_file) {
+            Ok(model) => model,
+            Err(e) => {
+                errors.push(FshImportError::semantic(source_file, e.to_string()));
+                return Ok(FshResultWithWarnings::with_warnings(errors, warnings));
+            }
+        };
+
+        match self.mapper.map_semantic_model(&semantic_model) {
+            Ok((_, mapper_warnings)) => warnings.extend(mapper_warnings),
+            Err(e) => errors.push(e),
+        }
+        let (_, instance_warnings) = self.mapper.map_instances(&semantic_model);
+        warnings.extend(instance_warnings);
+        for warning in &mut warnings {
+            warning.file.get_or_insert_with(|| source_file.to_path_buf());
+        }
+
+        Ok(FshResultWithWarnings::with_warnings(errors, warnings))
     }
 
     /// Parse FSH content.
@@ -322,10 +423,15 @@ impl FshProjectImporter {
                 }
                 Err(e) => {
                     if self.importer.options.continue_on_error {
-                        all_warnings.push(FshWarning::new(
-                            FshWarningCode::PotentialDataLoss,
-                            format!("Failed to import {}: {}", file.display(), e),
-                        ));
+                        let warning = match &e {
+                            FshError::Import(error) => FshWarning::from_import_error(error, file),
+                            _ => FshWarning::new(
+                                FshWarningCode::PotentialDataLoss,
+                                format!("Failed to import {}: {}", file.display(), e),
+                            )
+                            .with_file(file.clone()),
+                        };
+                        all_warnings.push(warning);
                     } else {
                         return Err(e);
                     }
@@ -398,6 +504,130 @@ Description: "A simple test profile"
         }
     }
 
+    const INSTANCE_FSH: &str = r#"
+Instance: ExamplePatient
+InstanceOf: Patient
+Usage: #example
+
+* name[0].family = "Smith"
+* gender = #female
+"#;
+
+    #[tokio::test]
+    async fn test_import_fsh_instance() {
+        let importer = FshImporter::new().await;
+
+        // May fail if canonical manager not available
+        if let Ok(importer) = importer {
+            let result = importer
+                .import_content_with_instances(INSTANCE_FSH, Path::new("example.fsh"))
+                .await;
+
+            if let Ok(result) = result {
+                assert!(result.value.profiles.is_empty());
+                assert_eq!(result.value.instances.len(), 1);
+
+                let instance = &result.value.instances[0];
+                assert_eq!(instance.instance_of, "Patient");
+                assert_eq!(instance.resource["resourceType"], "Patient");
+                assert_eq!(instance.resource["name"][0]["family"], "Smith");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_content_valid_fsh() {
+        let options = FshImportOptions {
+            resolve_dependencies: false,
+            ..Default::default()
+        };
+        let importer = FshImporter::with_options(options).await.unwrap();
+
+        let result = importer
+            .check_content(SIMPLE_FSH, Path::new("test.fsh"))
+            .unwrap();
+        assert!(result.value.is_empty(), "unexpected errors: {:?}", result.value);
+    }
+
+    // Line 5 is not valid FSH
+    const BROKEN_FSH: &str = concat!(
+        "Profile: BrokenPatient\n",
+        "Parent: Patient\n",
+        "\n",
+        "* name 1..* MS\n",
+        "}}} not fsh {{{\n",
+    );
+
+    #[tokio::test]
+    async fn test_broken_fsh_reports_line() {
+        let options = FshImportOptions {
+            continue_on_error: false,
+            resolve_dependencies: false,
+            ..Default::default()
+        };
+        let importer = FshImporter::with_options(options).await.unwrap();
+
+        let err = importer
+            .import_content(BROKEN_FSH, Path::new("broken.fsh"))
+            .await
+            .unwrap_err();
+        let FshError::Import(error) = err else {
+            panic!("expected an import error, got {err:?}");
+        };
+        assert_eq!(error.file(), Some(Path::new("broken.fsh")));
+        let (line, _) = error.position().expect("parse error should have a position");
+        assert_eq!(line, 5);
+
+        // The lint path reports the same position
+        let checked = importer
+            .check_content(BROKEN_FSH, Path::new("broken.fsh"))
+            .unwrap();
+        assert_eq!(checked.value[0].position().map(|(line, _)| line), Some(5));
+    }
+
+    const COMMENTED_FSH: &str = r#"// Demo patient profiles
+// Maintained alongside the demo IG
+
+Profile: CommentedPatient
+Parent: Patient
+Id: commented-patient
+
+* name 1..* MS
+
+Profile: CommentedObservation
+Parent: Observation
+Id: commented-observation
+
+* status MS
+"#;
+
+    #[tokio::test]
+    async fn test_leading_comment_survives_round_trip() {
+        let options = FshImportOptions {
+            resolve_dependencies: false,
+            ..Default::default()
+        };
+        let importer = FshImporter::with_options(options).await.unwrap();
+
+        let result = importer
+            .import_content(COMMENTED_FSH, Path::new("commented.fsh"))
+            .await
+            .unwrap();
+        assert_eq!(result.value.len(), 2);
+        let comment = "// Demo patient profiles\n// Maintained alongside the demo IG";
+        assert_eq!(
+            result.value[0].resource.fsh_comment.as_deref(),
+            Some(comment)
+        );
+        // Only the first profile of a file carries its leading comment
+        assert_eq!(result.value[1].resource.fsh_comment, None);
+
+        // The comment lives beside the IR and survives storage
+        let stored: ProfileDocument =
+            serde_json::from_value(serde_json::to_value(&result.value[0]).unwrap()).unwrap();
+        assert_eq!(stored.resource.fsh_comment.as_deref(), Some(comment));
+    }
+
     #[test]
     fn test_import_options_builder() {
         let options = FshImportOptions::default()
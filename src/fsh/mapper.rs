@@ -5,6 +5,7 @@
 
 use std::path::Path;
 
+use maki_core::Location;
 use maki_core::semantic::{
     Cardinality as FshCardinality, Constraint, ConstraintType, Element as FshElement,
     ElementFlag, FhirResource, ResourceMetadata, ResourceType, SemanticModel,
@@ -18,6 +19,21 @@ use crate::ir::{
 
 use super::error::{FshImportError, FshWarning, FshWarningCode};
 
+/// A FSH `Instance` mapped to raw resource JSON.
+///
+/// Instances are not edited through the IR; they are persisted as-is.
+#[derive(Debug, Clone)]
+pub struct FshInstance {
+    /// Instance id (from `Id:` or the instance name).
+    pub id: String,
+    /// Instance name.
+    pub name: String,
+    /// The `InstanceOf` reference (resource type, profile name, or URL).
+    pub instance_of: String,
+    /// Resource JSON built from the instance assignments.
+    pub resource: serde_json::Value,
+}
+
 /// Maps FSH semantic model to IR.
 pub struct FshToIrMapper {
     /// Base URL for canonical URLs.
@@ -115,9 +131,12 @@ impl FshToIrMapper {
                 }
                 Err(e) => {
                     // Add as warning and continue for partial import
-                    all_warnings.push(FshWarning::new(
-                        FshWarningCode::PotentialDataLoss,
-                        format!("Failed to map profile '{}': {}", profile.id, e),
+                    all_warnings.push(at_location(
+                        FshWarning::new(
+                            FshWarningCode::PotentialDataLoss,
+                            format!("Failed to map profile '{}': {}", profile.id, e),
+                        ),
+                        &profile.location,
                     ));
                 }
             }
@@ -132,6 +151,89 @@ impl FshToIrMapper {
         Ok((documents, all_warnings))
     }
 
+    /// Map a single FhirResource (Instance) to resource JSON.
+    ///
+    /// `InstanceOf` a core resource sets `resourceType` directly; any other
+    /// parent is treated as a profile and recorded in `meta.profile`, with the
+    /// resource type taken from `profiles` defined alongside the instance.
+    pub fn map_instance(
+        &self,
+        resource: &FhirResource,
+        profiles: &[&FhirResource],
+    ) -> Result<FshInstance, FshImportError> {
+        if resource.resource_type != ResourceType::Instance {
+            return Err(FshImportError::mapping(format!(
+                "Expected Instance, got {:?}",
+                resource.resource_type
+            )));
+        }
+
+        let instance_of = resource.parent.clone().ok_or_else(|| {
+            FshImportError::mapping(format!("Instance '{}' has no InstanceOf", resource.id))
+        })?;
+
+        let mut json = serde_json::Map::new();
+        let resource_type = if is_core_resource(&instance_of) {
+            instance_of.clone()
+        } else {
+            let profile_url =
+                if instance_of.starts_with("http://") || instance_of.starts_with("https://") {
+                    instance_of.clone()
+                } else {
+                    format!("{}/StructureDefinition/{}", self.canonical_base, instance_of)
+                };
+            json.insert(
+                "meta".to_string(),
+                serde_json::json!({ "profile": [profile_url] }),
+            );
+            resolve_instance_type(&instance_of, profiles)
+        };
+        json.insert("resourceType".to_string(), resource_type.clone().into());
+        json.insert("id".to_string(), resource.id.clone().into());
+
+        let mut value = serde_json::Value::Object(json);
+        for element in &resource.elements {
+            let path = element
+                .path
+                .strip_prefix(&format!("{}.", resource_type))
+                .unwrap_or(&element.path);
+            for constraint in &element.constraints {
+                if matches!(constraint.constraint_type, ConstraintType::FixedValue) {
+                    set_instance_value(&mut value, path, parse_assigned_value(&constraint.value));
+                }
+            }
+        }
+
+        Ok(FshInstance {
+            id: resource.id.clone(),
+            name: resource.name.clone().unwrap_or_else(|| resource.id.clone()),
+            instance_of,
+            resource: value,
+        })
+    }
+
+    /// Map all instances from a SemanticModel to resource JSON.
+    pub fn map_instances(&self, model: &SemanticModel) -> (Vec<FshInstance>, Vec<FshWarning>) {
+        let mut instances = Vec::new();
+        let mut warnings = Vec::new();
+
+        let profiles = model.get_resources_by_type(ResourceType::Profile);
+        for resource in model.get_resources_by_type(ResourceType::Instance) {
+            match self.map_instance(resource, &profiles) {
+                Ok(instance) => instances.push(instance),
+                Err(e) => warnings.push(at_location(
+                    FshWarning::new(
+                        FshWarningCode::PotentialDataLoss,
+                        format!("Failed to map instance '{}': {}", resource.id, e),
+                    ),
+                    &resource.location,
+                )),
+            }
+        }
+
+        (instances, warnings)
+    }
+
     /// Map FSH resource metadata to DocumentMetadata.
     fn map_metadata(
         &self,
@@ -185,19 +287,25 @@ impl FshToIrMapper {
 
         // Check if it's a URL
         if parent.starts_with("http://") || parent.starts_with("https://") {
-            // Extract resource type from URL
-            let resource_type = parent
+            // Extract resource type from URL, keeping any `|version` pin
+            let base = BaseDefinition::from_canonical(parent);
+            let resource_type = base
+                .url
                 .rsplit('/')
                 .next()
-                .unwrap_or("Resource");
-            return Ok(BaseDefinition::new(parent).with_name(resource_type.to_string()));
+                .unwrap_or("Resource")
+                .to_string();
+            return Ok(base.with_name(resource_type));
         }
 
         // Assume it's a profile name - construct the URL
         let base_url = format!("{}/StructureDefinition/{}", self.canonical_base, parent);
-        warnings.push(FshWarning::new(
-            FshWarningCode::UnresolvedReference,
-            format!("Parent '{}' resolved to assumed URL: {}", parent, base_url),
+        warnings.push(at_location(
+            FshWarning::new(
+                FshWarningCode::UnresolvedReference,
+                format!("Parent '{}' resolved to assumed URL: {}", parent, base_url),
+            ),
+            &resource.location,
         ));
 
         Ok(BaseDefinition::new(&base_url).with_name(parent.to_string()))
@@ -375,6 +483,76 @@ impl FshToIrMapper {
     }
 }
 
+/// Resolve the resource type of an instance of `instance_of`.
+///
+/// Follows `Parent` through profiles defined in the same model; an unresolved
+/// URL falls back to its last segment and a name to itself.
+fn resolve_instance_type(instance_of: &str, profiles: &[&FhirResource]) -> String {
+    let mut current = instance_of;
+    for _ in 0..=profiles.len() {
+        if is_core_resource(current) {
+            return current.to_string();
+        }
+        let Some(parent) = profiles
+            .iter()
+            .find(|p| p.id == current || p.name.as_deref() == Some(current))
+            .and_then(|p| p.parent.as_deref())
+        else {
+            break;
+        };
+        current = parent;
+    }
+    current.rsplit('/').next().unwrap_or(current).to_string()
+}
+
+/// Parse an FSH assignment value into JSON.
+///
+/// JSON literals (numbers, booleans, quoted strings) are parsed as-is; codes
+/// (`#code`) and other bare tokens become strings.
+fn parse_assigned_value(raw: &str) -> serde_json::Value {
+    let raw = raw.trim();
+    if let Ok(value) = serde_json::from_str(raw) {
+        return value;
+    }
+    let code = raw.split_whitespace().next().unwrap_or(raw);
+    serde_json::Value::String(code.trim_start_matches('#').to_string())
+}
+
+/// Set a value at a dotted FSH path (`name[0].given[1]`), creating objects
+/// and arrays along the way.
+fn set_instance_value(root: &mut serde_json::Value, path: &str, value: serde_json::Value) {
+    let mut current = root;
+    for segment in path.split('.') {
+        let (key, index) = match segment.split_once('[') {
+            Some((key, rest)) => (key, rest.trim_end_matches(']').parse::<usize>().ok()),
+            None => (segment, None),
+        };
+
+        let Some(object) = current.as_object_mut() else {
+            return;
+        };
+        current = match index {
+            Some(index) => {
+                let entry = object
+                    .entry(key.to_string())
+                    .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+                if !entry.is_array() {
+                    *entry = serde_json::Value::Array(Vec::new());
+                }
+                let items = entry.as_array_mut().expect("just ensured array");
+                while items.len() <= index {
+                    items.push(serde_json::Value::Object(serde_json::Map::new()));
+                }
+                &mut items[index]
+            }
+            None => object
+                .entry(key.to_string())
+                .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new())),
+        };
+    }
+    *current = value;
+}
+
 /// Check if a name is a core FHIR resource type.
 fn is_core_resource(name: &str) -> bool {
     matches!(
@@ -408,10 +586,21 @@ fn is_core_resource(name: &str) -> bool {
     )
 }
 
+/// Attach a resource's source position to a warning.
+///
+/// The file is filled in by the importer, which knows the source path.
+fn at_location(mut warning: FshWarning, location: &Location) -> FshWarning {
+    // Synthesized resources carry a default (zero) location
+    if location.line > 0 {
+        warning.line = Some(location.line);
+        warning.column = Some(location.column);
+    }
+    warning
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use maki_core::Location;
 
     fn create_test_profile() -> FhirResource {
         FhirResource {
@@ -455,6 +644,43 @@ mod tests {
         assert_eq!(ir_card.max, Some(1));
     }
 
+    #[test]
+    fn test_map_instance() {
+        let mapper = FshToIrMapper::new();
+        let profile = create_test_profile();
+        let mut instance = create_test_profile();
+        instance.resource_type = ResourceType::Instance;
+        instance.id = "example-patient".to_string();
+        instance.parent = Some("TestPatient".to_string());
+
+        let mapped = mapper.map_instance(&instance, &[&profile]).unwrap();
+        assert_eq!(mapped.id, "example-patient");
+        assert_eq!(mapped.instance_of, "TestPatient");
+        assert_eq!(mapped.resource["resourceType"], "Patient");
+        assert_eq!(mapped.resource["id"], "example-patient");
+        assert_eq!(
+            mapped.resource["meta"]["profile"][0],
+            "http://example.org/fhir/StructureDefinition/TestPatient"
+        );
+
+        // Profiles are not instances
+        assert!(mapper.map_instance(&profile, &[]).is_err());
+    }
+
+    #[test]
+    fn test_set_instance_value() {
+        let mut resource = serde_json::json!({ "resourceType": "Patient" });
+        set_instance_value(&mut resource, "name[0].family", parse_assigned_value("\"Smith\""));
+        set_instance_value(&mut resource, "name[0].given[1]", parse_assigned_value("\"Ann\""));
+        set_instance_value(&mut resource, "gender", parse_assigned_value("#female"));
+        set_instance_value(&mut resource, "active", parse_assigned_value("true"));
+
+        assert_eq!(resource["name"][0]["family"], "Smith");
+        assert_eq!(resource["name"][0]["given"][1], "Ann");
+        assert_eq!(resource["gender"], "female");
+        assert_eq!(resource["active"], true);
+    }
+
     #[test]
     fn test_parse_binding() {
         let mapper = FshToIrMapper::new();
@@ -26,6 +26,8 @@
 //! - **Multi-file Support**: Import entire FSH projects
 //! - **FishingContext Integration**: Resolve external dependencies
 //! - **Error Handling**: Preserve FSH parser diagnostics
+//! - **Comment Preservation**: The comment block leading an imported file is
+//!   kept on the profile and written back on export
 //!
 //! # Example
 //!
@@ -46,12 +48,14 @@
 //! }
 //! ```
 
+mod comments;
 mod error;
 mod export;
 mod import;
 mod mapper;
 
+pub use comments::{leading_comment, with_leading_comment};
 pub use error::{FshError, FshImportError, FshResult, FshWarning};
 pub use export::{FshExportOptions, FshExporter};
-pub use import::{FshImportOptions, FshImporter, FshProjectImporter};
-pub use mapper::FshToIrMapper;
+pub use import::{FshImportOptions, FshImportOutput, FshImporter, FshProjectImporter};
+pub use mapper::{FshInstance, FshToIrMapper};
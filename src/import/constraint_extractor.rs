@@ -183,6 +183,22 @@ impl ConstraintExtractor {
             }
         }
 
+        // Conditions (invariant keys)
+        if let Some(conditions) = diff_element.get("condition").and_then(Value::as_array) {
+            node.constraints.conditions = conditions
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+        }
+
+        // Examples
+        if let Some(examples) = diff_element.get("example").and_then(Value::as_array) {
+            node.constraints.examples = examples
+                .iter()
+                .filter_map(crate::ir::Example::from_json)
+                .collect();
+        }
+
         // Max length
         if let Some(max_length) = diff_element.get("maxLength").and_then(Value::as_u64) {
             node.constraints.max_length = Some(max_length as u32);
@@ -252,6 +268,25 @@ impl ConstraintExtractor {
 
         let value_set = binding.get("valueSet").and_then(Value::as_str)?;
 
+        let additional = binding
+            .get("additional")
+            .and_then(Value::as_array)
+            .map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|entry| {
+                        let purpose = entry.get("purpose").and_then(Value::as_str)?;
+                        let value_set = entry.get("valueSet").and_then(Value::as_str)?;
+                        let additional = crate::ir::AdditionalBinding::new(purpose, value_set);
+                        Some(match entry.get("documentation").and_then(Value::as_str) {
+                            Some(doc) => additional.with_documentation(doc),
+                            None => additional,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
         Some(crate::ir::Binding {
             strength,
             value_set: value_set.to_string(),
@@ -259,6 +294,7 @@ impl ConstraintExtractor {
                 .get("description")
                 .and_then(Value::as_str)
                 .map(String::from),
+            additional,
         })
     }
 
@@ -110,7 +110,7 @@ impl ElementTreeBuilder {
     ) -> ImportResult<Vec<DifferentialElement>> {
         let mut differential = Vec::new();
 
-        for element in elements {
+        for (index, element) in elements.iter().enumerate() {
             let path = element
                 .get("path")
                 .and_then(Value::as_str)
@@ -129,6 +129,7 @@ impl ElementTreeBuilder {
             }
 
             let mut diff = DifferentialElement::new(effective_path);
+            diff.source_index = Some(index);
 
             // Stable ID for UI
             if let Some(id) = element_id {
@@ -139,6 +140,10 @@ impl ElementTreeBuilder {
             if let Some(slice_name) = slice_name {
                 diff.slice_name = Some(slice_name.to_string());
             }
+            diff.slice_is_constraining = element
+                .get("sliceIsConstraining")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
 
             // Parse constraints and slicing
             diff.constraints = self.parse_constraints(element)?;
@@ -255,12 +260,32 @@ impl ElementTreeBuilder {
                 .collect();
         }
 
+        // Conditions (invariant keys)
+        if let Some(conditions) = element.get("condition").and_then(Value::as_array) {
+            constraints.conditions = conditions
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+        }
+
         // Max length
         constraints.max_length = element
             .get("maxLength")
             .and_then(Value::as_u64)
             .map(|v| v as u32);
 
+        // Value requirements (R5+)
+        constraints.must_have_value = element
+            .get("mustHaveValue")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if let Some(alternatives) = element.get("valueAlternatives").and_then(Value::as_array) {
+            constraints.value_alternatives = alternatives
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect();
+        }
+
         // Meaning when missing
         constraints.meaning_when_missing = element
             .get("meaningWhenMissing")
@@ -478,19 +503,7 @@ impl ElementTreeBuilder {
 
     /// Parse example from JSON.
     fn parse_example(&self, example: &Value) -> Option<crate::ir::constraint::Example> {
-        let label = example.get("label").and_then(Value::as_str)?;
-
-        // Find value[x] field
-        let obj = example.as_object()?;
-        let value = obj
-            .iter()
-            .find(|(k, _)| k.starts_with("value"))
-            .map(|(_, v)| v.clone())?;
-
-        Some(crate::ir::constraint::Example {
-            label: label.to_string(),
-            value,
-        })
+        crate::ir::constraint::Example::from_json(example)
     }
 
     /// Extract unknown fields from element JSON.
@@ -524,6 +537,8 @@ impl ElementTreeBuilder {
             "maxLength",
             "condition",
             "constraint",
+            "mustHaveValue",
+            "valueAlternatives",
             "mustSupport",
             "isModifier",
             "isModifierReason",
@@ -183,6 +183,27 @@ pub enum ImportWarningCode {
     IncompleteSlicing,
     /// Element has no constraints defined.
     NoConstraints,
+    /// Bundle entry is not a StructureDefinition and was skipped.
+    SkippedBundleEntry,
+    /// Element path was normalized or is not rooted at the profiled type.
+    NormalizedPath,
+}
+
+impl ImportWarningCode {
+    /// Get the diagnostic code.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::UnknownFieldPreserved => "UNKNOWN_FIELD_PRESERVED",
+            Self::MissingSnapshot => "MISSING_SNAPSHOT",
+            Self::DeprecatedField => "DEPRECATED_FIELD",
+            Self::BaseUnresolved => "BASE_UNRESOLVED",
+            Self::IncompleteSlicing => "INCOMPLETE_SLICING",
+            Self::NoConstraints => "NO_CONSTRAINTS",
+            Self::SkippedBundleEntry => "SKIPPED_BUNDLE_ENTRY",
+            Self::NormalizedPath => "NORMALIZED_PATH",
+        }
+    }
 }
 
 /// Import result with warnings.
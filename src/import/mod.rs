@@ -48,10 +48,14 @@
 
 mod element_builder;
 mod error;
+mod path_normalizer;
 mod sd_parser;
 
 pub use element_builder::ElementTreeBuilder;
-pub use error::{ImportError, ImportResult, ImportWarning};
+pub use error::{
+    ImportError, ImportResult, ImportResultWithWarnings, ImportWarning, ImportWarningCode,
+};
+pub use path_normalizer::normalize_element_paths;
 pub use sd_parser::{ParsedStructureDefinition, StructureDefinitionParser};
 
 use std::collections::HashMap;
@@ -110,6 +114,81 @@ impl StructureDefinitionImporter {
         self.build_document(parsed).await
     }
 
+    /// Import a StructureDefinition from JSON string, keeping import warnings.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error under the same conditions as [`Self::import_json`].
+    pub async fn import_json_with_warnings(
+        &self,
+        json: &str,
+    ) -> ImportResult<ImportResultWithWarnings<ProfileDocument>> {
+        let parsed = self.parser.parse(json)?;
+        self.build_document_with_warnings(parsed).await
+    }
+
+    /// Import every StructureDefinition in a FHIR Bundle.
+    ///
+    /// Entries that are not StructureDefinitions are skipped with a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the JSON is not a Bundle or any contained
+    /// StructureDefinition fails to import.
+    pub async fn import_bundle(
+        &self,
+        json: &str,
+    ) -> ImportResult<ImportResultWithWarnings<Vec<ProfileDocument>>> {
+        let bundle: Value =
+            serde_json::from_str(json).map_err(ImportError::json_parse_with_source)?;
+        let resource_type = bundle
+            .get("resourceType")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        if resource_type != "Bundle" {
+            return Err(ImportError::invalid_value(
+                "resourceType",
+                format!("expected 'Bundle', got '{}'", resource_type),
+            ));
+        }
+
+        let entries = bundle
+            .get("entry")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        let mut documents = Vec::new();
+        let mut warnings = Vec::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let Some(resource) = entry.get("resource") else {
+                warnings.push(ImportWarning::new(
+                    ImportWarningCode::SkippedBundleEntry,
+                    format!("Bundle entry {} has no resource", index),
+                ));
+                continue;
+            };
+            match resource.get("resourceType").and_then(Value::as_str) {
+                Some("StructureDefinition") => {
+                    let parsed = self.parser.parse_value(resource.clone())?;
+                    let imported = self.build_document_with_warnings(parsed).await?;
+                    warnings.extend(imported.warnings);
+                    documents.push(imported.value);
+                }
+                other => warnings.push(ImportWarning::new(
+                    ImportWarningCode::SkippedBundleEntry,
+                    format!(
+                        "Skipped bundle entry {}: {} is not a StructureDefinition",
+                        index,
+                        other.unwrap_or("unknown resource")
+                    ),
+                )),
+            }
+        }
+
+        Ok(ImportResultWithWarnings::with_warnings(documents, warnings))
+    }
+
     /// Import a StructureDefinition from a JSON value.
     ///
     /// # Errors
@@ -123,8 +202,20 @@ impl StructureDefinitionImporter {
         self.build_document(parsed).await
     }
 
-    /// Build a ProfileDocument from parsed SD.
+    /// Build a ProfileDocument from parsed SD, logging import warnings.
     async fn build_document(&self, parsed: ParsedStructureDefinition) -> ImportResult<ProfileDocument> {
+        let imported = self.build_document_with_warnings(parsed).await?;
+        for warning in &imported.warnings {
+            tracing::warn!("Import warning: {}", warning);
+        }
+        Ok(imported.value)
+    }
+
+    /// Build a ProfileDocument from parsed SD.
+    async fn build_document_with_warnings(
+        &self,
+        mut parsed: ParsedStructureDefinition,
+    ) -> ImportResult<ImportResultWithWarnings<ProfileDocument>> {
         // Extract metadata
         let metadata = self.extract_metadata(&parsed)?;
 
@@ -132,8 +223,7 @@ impl StructureDefinitionImporter {
         let fhir_version = self.determine_fhir_version(&parsed);
 
         // Build base definition reference
-        let base = BaseDefinition::new(&parsed.base_definition)
-            .with_name(parsed.type_name.clone());
+        let base = BaseDefinition::from_canonical(&parsed.base_definition);
 
         // Create the profiled resource
         let mut resource = ProfiledResource::new(&parsed.url, fhir_version, base);
@@ -147,6 +237,24 @@ impl StructureDefinitionImporter {
             "logical" => crate::ir::StructureKind::Logical,
             _ => crate::ir::StructureKind::Resource,
         };
+        resource.derivation = parsed
+            .derivation
+            .as_deref()
+            .and_then(crate::ir::Derivation::from_code)
+            .unwrap_or_default();
+        resource.is_abstract = parsed.is_abstract;
+        if resource.is_specialization() {
+            resource.type_name = Some(parsed.type_name.clone());
+            resource.root = crate::ir::ElementNode::new(parsed.type_name.clone());
+        } else {
+            resource.base.name = Some(parsed.type_name.clone());
+        }
+
+        // Fix up inconsistent element paths before building the tree
+        let mut warnings = Vec::new();
+        if let Some(diff_elements) = parsed.differential_elements.as_mut() {
+            warnings = normalize_element_paths(diff_elements, &parsed.type_name);
+        }
 
         // Build differential-only representation
         let mut differential = if let Some(diff_elements) = &parsed.differential_elements {
@@ -161,8 +269,19 @@ impl StructureDefinitionImporter {
 
         resource.differential = differential;
 
+        // Extension context
+        if let Some(contexts) = &parsed.context {
+            resource.context = contexts
+                .iter()
+                .filter_map(crate::ir::ExtensionContext::from_json)
+                .collect();
+        }
+
+        resource.context_invariants = parsed.context_invariant.unwrap_or_default();
+
         // Preserve unknown fields
         resource.unknown_fields = parsed.unknown_fields;
+        resource.imported_snapshot = parsed.snapshot_elements.unwrap_or_default();
 
         // Create the document
         let mut document = ProfileDocument::new(metadata, resource);
@@ -170,7 +289,7 @@ impl StructureDefinitionImporter {
         // Mark as not dirty (freshly imported)
         document.mark_saved();
 
-        Ok(document)
+        Ok(ImportResultWithWarnings::with_warnings(document, warnings))
     }
 
     /// Extract document metadata from parsed SD.
@@ -196,11 +315,7 @@ impl StructureDefinitionImporter {
         metadata.purpose = parsed.purpose.clone();
         metadata.copyright = parsed.copyright.clone();
 
-        if let Some(date_str) = &parsed.date {
-            if let Ok(date) = chrono::DateTime::parse_from_rfc3339(date_str) {
-                metadata.date = Some(date.with_timezone(&chrono::Utc));
-            }
-        }
+        metadata.date = parsed.date.clone().filter(|date| is_fhir_date_time(date));
 
         Ok(metadata)
     }
@@ -329,6 +444,15 @@ impl StructureDefinitionImporter {
     }
 }
 
+/// Check that a value is a FHIR `dateTime` the editor keeps.
+///
+/// Full timestamps and date-only values (`2024-01-15`) are kept as
+/// written; other partial dates are ignored.
+fn is_fhir_date_time(value: &str) -> bool {
+    chrono::DateTime::parse_from_rfc3339(value).is_ok()
+        || chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d").is_ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -493,4 +617,827 @@ mod tests {
         assert_eq!(slicing.discriminator[0].path, "url");
         assert_eq!(slicing.discriminator[0].discriminator_type, DiscriminatorType::Value);
     }
+
+    #[tokio::test]
+    async fn test_import_reslice_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/ResliceProfile",
+            "name": "ResliceProfile",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Observation",
+            "baseDefinition": "http://example.org/fhir/StructureDefinition/SlicedObservation",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Observation", "path": "Observation" },
+                    {
+                        "id": "Observation.component:foo/bar",
+                        "path": "Observation.component",
+                        "sliceName": "foo/bar",
+                        "sliceIsConstraining": true,
+                        "min": 1,
+                        "max": "1"
+                    },
+                    {
+                        "id": "Observation.component:foo/bar.code",
+                        "path": "Observation.component.code",
+                        "mustSupport": true
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        let reslice = doc
+            .resource
+            .differential
+            .iter()
+            .find(|d| d.slice_name.as_deref() == Some("foo/bar"))
+            .expect("Missing re-slice differential element");
+        assert!(reslice.slice_is_constraining);
+        assert_eq!(
+            reslice.element_id.as_deref(),
+            Some("Observation.component:foo/bar")
+        );
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+        let elements = exported["differential"]["element"].as_array().unwrap();
+
+        let reslice = elements
+            .iter()
+            .find(|e| e["id"] == "Observation.component:foo/bar")
+            .expect("Re-slice missing from export");
+        assert_eq!(reslice["path"], "Observation.component");
+        assert_eq!(reslice["sliceName"], "foo/bar");
+        assert_eq!(reslice["sliceIsConstraining"], true);
+
+        let child = elements
+            .iter()
+            .find(|e| e["id"] == "Observation.component:foo/bar.code")
+            .expect("Re-slice child missing from export");
+        assert_eq!(child["path"], "Observation.component.code");
+        assert!(child.get("sliceName").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_element_ids_survive_merge_round_trip() {
+        use crate::ir::{ElementNode, ElementSource};
+        use crate::merge::{ElementTreeMerger, extract_differential};
+
+        let ids = [
+            "Observation",
+            "Observation.status",
+            "Observation.component",
+            "Observation.component:systolic",
+            "Observation.component:systolic.code",
+            "Observation.component:systolic/high",
+            "Observation.value[x]:valueQuantity",
+        ];
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/IdProfile",
+            "name": "IdProfile",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Observation",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Observation",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Observation", "path": "Observation" },
+                    { "id": "Observation.status", "path": "Observation.status", "mustSupport": true },
+                    {
+                        "id": "Observation.component",
+                        "path": "Observation.component",
+                        "slicing": {
+                            "discriminator": [{ "type": "pattern", "path": "code" }],
+                            "rules": "open"
+                        }
+                    },
+                    {
+                        "id": "Observation.component:systolic",
+                        "path": "Observation.component",
+                        "sliceName": "systolic",
+                        "min": 0,
+                        "max": "1"
+                    },
+                    {
+                        "id": "Observation.component:systolic.code",
+                        "path": "Observation.component.code",
+                        "mustSupport": true
+                    },
+                    {
+                        "id": "Observation.component:systolic/high",
+                        "path": "Observation.component",
+                        "sliceName": "systolic/high",
+                        "max": "1"
+                    },
+                    {
+                        "id": "Observation.value[x]:valueQuantity",
+                        "path": "Observation.value[x]",
+                        "sliceName": "valueQuantity",
+                        "min": 1
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let mut doc = importer.import_json(json).await.expect("Import failed");
+
+        // Base tree with the elements the differential refers to
+        let mut base = ElementNode::new("Observation".to_string());
+        for path in ["Observation.status", "Observation.component", "Observation.value[x]"] {
+            base.add_child(ElementNode::new(path.to_string()));
+        }
+        let mut code = ElementNode::new("Observation.component.code".to_string());
+        code.source = ElementSource::Inherited;
+        base.children[1].add_child(code);
+
+        // Regenerate the differential from the merged tree, as edits do
+        let merged = ElementTreeMerger::new().merge(base, &doc.resource.differential);
+        doc.resource.differential = extract_differential(&merged);
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        let exported_ids: Vec<&str> = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["id"].as_str().unwrap())
+            .collect();
+        assert_eq!(exported_ids, ids);
+    }
+
+    #[tokio::test]
+    async fn test_differential_order_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/OrderedPatient",
+            "name": "OrderedPatient",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Patient.name", "path": "Patient.name", "min": 1 },
+                    { "id": "Patient.identifier", "path": "Patient.identifier", "min": 1 },
+                    { "id": "Patient.birthDate", "path": "Patient.birthDate", "mustSupport": true }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        let paths: Vec<&str> = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|e| e["path"].as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            vec!["Patient", "Patient.name", "Patient.identifier", "Patient.birthDate"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_must_have_value_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/ValuedPatient",
+            "name": "ValuedPatient",
+            "status": "draft",
+            "fhirVersion": "5.0.0",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    {
+                        "id": "Patient.birthDate",
+                        "path": "Patient.birthDate",
+                        "mustHaveValue": true,
+                        "valueAlternatives": [
+                            "http://hl7.org/fhir/StructureDefinition/data-absent-reason"
+                        ]
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let mut doc = importer.import_json(json).await.expect("Import failed");
+        assert_eq!(doc.resource.fhir_version, crate::ir::FhirVersion::R5);
+        let birth_date = doc
+            .resource
+            .differential
+            .iter()
+            .find(|e| e.path == "Patient.birthDate")
+            .unwrap();
+        assert!(birth_date.constraints.must_have_value);
+        assert_eq!(
+            birth_date.constraints.value_alternatives,
+            vec!["http://hl7.org/fhir/StructureDefinition/data-absent-reason".to_string()]
+        );
+        assert!(birth_date.unknown_fields.is_empty());
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config.clone());
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+        let birth_date = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Patient.birthDate")
+            .unwrap();
+        assert_eq!(birth_date["mustHaveValue"], true);
+        assert_eq!(
+            birth_date["valueAlternatives"],
+            serde_json::json!(["http://hl7.org/fhir/StructureDefinition/data-absent-reason"])
+        );
+
+        // Not part of R4 ElementDefinition
+        doc.resource.fhir_version = crate::ir::FhirVersion::R4;
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+        let birth_date = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Patient.birthDate")
+            .unwrap();
+        assert!(birth_date.get("mustHaveValue").is_none());
+        assert!(birth_date.get("valueAlternatives").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_condition_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/ConditionPatient",
+            "name": "ConditionPatient",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    {
+                        "id": "Patient.contact",
+                        "path": "Patient.contact",
+                        "condition": ["pat-1"],
+                        "constraint": [{
+                            "key": "pat-1",
+                            "severity": "error",
+                            "human": "SHALL at least contain a contact's details or a reference to an organization",
+                            "expression": "name.exists() or telecom.exists() or address.exists() or organization.exists()"
+                        }]
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+        let contact = doc
+            .resource
+            .differential
+            .iter()
+            .find(|e| e.path == "Patient.contact")
+            .unwrap();
+        assert_eq!(contact.constraints.conditions, vec!["pat-1".to_string()]);
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        let contact = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Patient.contact")
+            .unwrap();
+        assert_eq!(contact["condition"], serde_json::json!(["pat-1"]));
+    }
+
+    #[tokio::test]
+    async fn test_example_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/ExampleObservation",
+            "name": "ExampleObservation",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Observation",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Observation",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    {
+                        "id": "Observation.effective[x]",
+                        "path": "Observation.effective[x]",
+                        "example": [
+                            { "label": "Instant", "valueDateTime": "2024-01-01T10:00:00Z" },
+                            { "label": "Range", "valuePeriod": { "start": "2024-01-01" } }
+                        ]
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+        let effective = doc
+            .resource
+            .differential
+            .iter()
+            .find(|e| e.path == "Observation.effective[x]")
+            .unwrap();
+        let examples = &effective.constraints.examples;
+        assert_eq!(examples.len(), 2);
+        assert_eq!(examples[0].value_type.as_deref(), Some("DateTime"));
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        let effective = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Observation.effective[x]")
+            .unwrap();
+        assert_eq!(
+            effective["example"],
+            serde_json::json!([
+                { "label": "Instant", "valueDateTime": "2024-01-01T10:00:00Z" },
+                { "label": "Range", "valuePeriod": { "start": "2024-01-01" } }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_bundle() {
+        let json = r#"{
+            "resourceType": "Bundle",
+            "type": "collection",
+            "entry": [
+                {
+                    "resource": {
+                        "resourceType": "StructureDefinition",
+                        "url": "http://example.org/fhir/StructureDefinition/BundlePatient",
+                        "name": "BundlePatient",
+                        "status": "draft",
+                        "kind": "resource",
+                        "abstract": false,
+                        "type": "Patient",
+                        "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+                        "derivation": "constraint",
+                        "differential": {
+                            "element": [
+                                { "id": "Patient.name", "path": "Patient.name", "min": 1 }
+                            ]
+                        }
+                    }
+                },
+                {
+                    "resource": {
+                        "resourceType": "ValueSet",
+                        "url": "http://example.org/fhir/ValueSet/colors",
+                        "status": "draft"
+                    }
+                },
+                {
+                    "resource": {
+                        "resourceType": "StructureDefinition",
+                        "url": "http://example.org/fhir/StructureDefinition/BundleObservation",
+                        "name": "BundleObservation",
+                        "status": "draft",
+                        "kind": "resource",
+                        "abstract": false,
+                        "type": "Observation",
+                        "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Observation",
+                        "derivation": "constraint",
+                        "differential": {
+                            "element": [
+                                { "id": "Observation.subject", "path": "Observation.subject", "min": 1 }
+                            ]
+                        }
+                    }
+                }
+            ]
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let result = importer.import_bundle(json).await.expect("Import failed");
+
+        let names: Vec<&str> = result
+            .value
+            .iter()
+            .map(|doc| doc.metadata.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["BundlePatient", "BundleObservation"]);
+        assert_eq!(result.warnings.len(), 1);
+        assert_eq!(result.warnings[0].code, ImportWarningCode::SkippedBundleEntry);
+        assert!(result.warnings[0].message.contains("ValueSet"));
+    }
+
+    #[tokio::test]
+    async fn test_specialization_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/LabBatch",
+            "name": "LabBatch",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "LabBatch",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/DomainResource",
+            "derivation": "specialization",
+            "differential": {
+                "element": [
+                    { "id": "LabBatch", "path": "LabBatch" },
+                    {
+                        "id": "LabBatch.batchNumber",
+                        "path": "LabBatch.batchNumber",
+                        "min": 1,
+                        "max": "1",
+                        "type": [{ "code": "string" }]
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+        assert_eq!(doc.resource.derivation, crate::ir::Derivation::Specialization);
+        assert_eq!(doc.resource.resource_type(), "LabBatch");
+        assert_eq!(doc.resource.type_name.as_deref(), Some("LabBatch"));
+        assert_eq!(doc.resource.base.name, None);
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        assert_eq!(exported["derivation"], "specialization");
+        assert_eq!(exported["type"], "LabBatch");
+        assert_eq!(
+            exported["baseDefinition"],
+            "http://hl7.org/fhir/StructureDefinition/DomainResource"
+        );
+        let paths: Vec<&str> = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|e| e["path"].as_str())
+            .collect();
+        assert_eq!(paths, vec!["LabBatch", "LabBatch.batchNumber"]);
+    }
+
+    #[tokio::test]
+    async fn test_extension_context_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/patient-nickname",
+            "name": "PatientNickname",
+            "status": "draft",
+            "kind": "complex-type",
+            "abstract": false,
+            "context": [
+                { "type": "element", "expression": "Patient.name" },
+                { "type": "fhirpath", "expression": "Patient.contact.name" }
+            ],
+            "type": "Extension",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Extension",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Extension", "path": "Extension" }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        assert_eq!(
+            doc.resource.context,
+            vec![
+                crate::ir::ExtensionContext::new(
+                    crate::ir::ExtensionContextType::Element,
+                    "Patient.name"
+                ),
+                crate::ir::ExtensionContext::new(
+                    crate::ir::ExtensionContextType::Fhirpath,
+                    "Patient.contact.name"
+                ),
+            ]
+        );
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        assert_eq!(
+            exported["context"],
+            serde_json::json!([
+                { "type": "element", "expression": "Patient.name" },
+                { "type": "fhirpath", "expression": "Patient.contact.name" }
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_extension_context_invariant_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/nickname-use",
+            "name": "NicknameUse",
+            "status": "draft",
+            "kind": "complex-type",
+            "abstract": false,
+            "context": [
+                { "type": "element", "expression": "Patient.name" }
+            ],
+            "contextInvariant": ["%extension.value.exists() implies %resource.active"],
+            "type": "Extension",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Extension",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Extension", "path": "Extension" }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        assert_eq!(
+            doc.resource.context_invariants,
+            vec!["%extension.value.exists() implies %resource.active".to_string()]
+        );
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        assert_eq!(
+            exported["contextInvariant"],
+            serde_json::json!(["%extension.value.exists() implies %resource.active"])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metadata_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/MetaPatient",
+            "name": "MetaPatient",
+            "status": "active",
+            "experimental": true,
+            "date": "2024-03-01T12:30:00Z",
+            "publisher": "Example Publisher",
+            "description": "Patient profile with full metadata",
+            "purpose": "Exercise metadata round-trips",
+            "copyright": "CC0-1.0",
+            "fhirVersion": "4.0.1",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Patient", "path": "Patient" }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        assert_eq!(exported["status"], "active");
+        assert_eq!(exported["experimental"], true);
+        assert_eq!(exported["date"], "2024-03-01T12:30:00Z");
+        assert_eq!(exported["publisher"], "Example Publisher");
+        assert_eq!(exported["description"], "Patient profile with full metadata");
+        assert_eq!(exported["purpose"], "Exercise metadata round-trips");
+        assert_eq!(exported["copyright"], "CC0-1.0");
+
+        // Date-only values keep their precision
+        let date_only = json.replace("2024-03-01T12:30:00Z", "2024-03-01");
+        let doc_date_only = importer
+            .import_json(&date_only)
+            .await
+            .expect("Import failed");
+        let exported = exporter
+            .export_value(&doc_date_only)
+            .await
+            .expect("Export failed");
+        assert_eq!(exported["date"], "2024-03-01");
+
+        // A missing date is omitted unless the config stamps one
+        let mut undated = doc.clone();
+        undated.metadata.date = None;
+        let exported = exporter.export_value(&undated).await.expect("Export failed");
+        assert!(exported.get("date").is_none());
+
+        let config = crate::export::ExportConfig::differential_only()
+            .skip_validation()
+            .with_default_date();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&undated).await.expect("Export failed");
+        assert!(exported["date"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_abstract_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/AbstractPatient",
+            "name": "AbstractPatient",
+            "status": "draft",
+            "fhirVersion": "4.0.1",
+            "kind": "resource",
+            "abstract": true,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Patient", "path": "Patient" }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+        assert!(doc.resource.is_abstract);
+
+        // The flag survives IR storage
+        let stored: ProfileDocument =
+            serde_json::from_str(&serde_json::to_string(&doc).unwrap()).unwrap();
+        assert!(stored.resource.is_abstract);
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&stored).await.expect("Export failed");
+        assert_eq!(exported["abstract"], true);
+    }
+
+    #[test]
+    fn test_is_fhir_date_time() {
+        assert!(is_fhir_date_time("2024-03-01T12:30:00Z"));
+        assert!(is_fhir_date_time("2024-03-01"));
+        assert!(!is_fhir_date_time("2024-03"));
+        assert!(!is_fhir_date_time("yesterday"));
+    }
+
+    #[tokio::test]
+    async fn test_additional_binding_round_trip() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/R5Observation",
+            "name": "R5Observation",
+            "status": "draft",
+            "fhirVersion": "5.0.0",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Observation",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Observation",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "Observation", "path": "Observation" },
+                    {
+                        "id": "Observation.code",
+                        "path": "Observation.code",
+                        "binding": {
+                            "strength": "extensible",
+                            "valueSet": "http://example.org/ValueSet/codes",
+                            "additional": [
+                                {
+                                    "purpose": "minimum",
+                                    "valueSet": "http://example.org/ValueSet/core-codes",
+                                    "documentation": "Systems must support these"
+                                }
+                            ]
+                        }
+                    }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let doc = importer.import_json(json).await.expect("Import failed");
+
+        let config = crate::export::ExportConfig::differential_only().skip_validation();
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config.clone());
+        let exported = exporter.export_value(&doc).await.expect("Export failed");
+
+        let code = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Observation.code")
+            .unwrap();
+        assert_eq!(
+            code["binding"]["additional"],
+            serde_json::json!([{
+                "purpose": "minimum",
+                "valueSet": "http://example.org/ValueSet/core-codes",
+                "documentation": "Systems must support these"
+            }])
+        );
+
+        // R4 has no binding.additional
+        let mut r4 = doc.clone();
+        r4.resource.fhir_version = FhirVersion::R4;
+        let mut exporter = crate::export::StructureDefinitionExporter::with_config(config);
+        let exported = exporter.export_value(&r4).await.expect("Export failed");
+        let code = exported["differential"]["element"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["path"] == "Observation.code")
+            .unwrap();
+        assert!(code["binding"].get("additional").is_none());
+        assert_eq!(code["binding"]["valueSet"], "http://example.org/ValueSet/codes");
+    }
+
+    #[tokio::test]
+    async fn test_differential_paths_missing_type_prefix() {
+        let json = r#"{
+            "resourceType": "StructureDefinition",
+            "url": "http://example.org/fhir/StructureDefinition/loose-patient",
+            "name": "LoosePatient",
+            "status": "draft",
+            "kind": "resource",
+            "abstract": false,
+            "type": "Patient",
+            "baseDefinition": "http://hl7.org/fhir/StructureDefinition/Patient",
+            "derivation": "constraint",
+            "differential": {
+                "element": [
+                    { "id": "name", "path": "name", "min": 1 },
+                    { "id": "patient.gender", "path": " patient..gender ", "mustSupport": true }
+                ]
+            }
+        }"#;
+
+        let importer = StructureDefinitionImporter::new();
+        let imported = importer
+            .import_json_with_warnings(json)
+            .await
+            .expect("Import failed");
+
+        let paths: Vec<&str> = imported
+            .value
+            .resource
+            .differential
+            .iter()
+            .map(|e| e.path.as_str())
+            .collect();
+        assert_eq!(paths, vec!["Patient.name", "Patient.gender"]);
+        assert_eq!(
+            imported.value.resource.differential[0].element_id.as_deref(),
+            Some("Patient.name")
+        );
+
+        assert_eq!(imported.warnings.len(), 2);
+        assert!(imported
+            .warnings
+            .iter()
+            .all(|w| w.code == ImportWarningCode::NormalizedPath));
+        assert_eq!(imported.warnings[0].path.as_deref(), Some("Patient.name"));
+    }
 }
@@ -0,0 +1,121 @@
+//! Element path normalization for imported differentials.
+//!
+//! Hand-written or tool-generated differentials sometimes carry stray
+//! whitespace, empty segments (`Patient..name`), a root with the wrong casing
+//! (`patient.name`) or no root at all (`name.family`). These are corrected
+//! before the element tree is built, with one [`ImportWarning`] per fixed
+//! element. Paths rooted at a different type cannot be corrected and only
+//! produce a warning.
+
+use serde_json::Value;
+
+use super::error::{ImportWarning, ImportWarningCode};
+
+/// Normalize the `path` and `id` of every element against `type_name`.
+///
+/// Logical models whose type is a URL are left untouched, since their root
+/// is the model name rather than the type.
+pub fn normalize_element_paths(elements: &mut [Value], type_name: &str) -> Vec<ImportWarning> {
+    if type_name.is_empty() || type_name.contains([':', '/']) {
+        return Vec::new();
+    }
+
+    let mut warnings = Vec::new();
+    for element in elements.iter_mut() {
+        let Some(obj) = element.as_object_mut() else {
+            continue;
+        };
+        let Some(path) = obj.get("path").and_then(Value::as_str) else {
+            continue;
+        };
+
+        let original = path.to_string();
+        let Some(normalized) = normalize_path(&original, type_name) else {
+            warnings.push(
+                ImportWarning::new(
+                    ImportWarningCode::NormalizedPath,
+                    format!("Element path is not rooted at '{}'", type_name),
+                )
+                .at_path(&original),
+            );
+            continue;
+        };
+        if normalized == original {
+            continue;
+        }
+
+        let normalized_id = obj
+            .get("id")
+            .and_then(Value::as_str)
+            .and_then(|id| normalize_path(id, type_name));
+        if let Some(normalized_id) = normalized_id {
+            obj.insert("id".to_string(), Value::String(normalized_id));
+        }
+        obj.insert("path".to_string(), Value::String(normalized.clone()));
+        warnings.push(
+            ImportWarning::new(
+                ImportWarningCode::NormalizedPath,
+                format!("Normalized element path '{}' to '{}'", original, normalized),
+            )
+            .at_path(normalized),
+        );
+    }
+
+    warnings
+}
+
+/// Normalize one path (or element id).
+///
+/// Returns `None` when the path is rooted at another type.
+fn normalize_path(path: &str, type_name: &str) -> Option<String> {
+    let mut segments: Vec<&str> = path
+        .split('.')
+        .map(str::trim)
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    // Compare the root without any slice name (`Extension:foo` stays as is)
+    let root = segments.first().copied().map(|s| s.split(':').next().unwrap_or(s));
+    match root {
+        Some(root) if root == type_name => {}
+        Some(root) if root.eq_ignore_ascii_case(type_name) => {
+            let fixed = format!("{}{}", type_name, &segments[0][root.len()..]);
+            let rest = segments[1..].iter().map(|s| format!(".{}", s));
+            return Some(std::iter::once(fixed).chain(rest).collect());
+        }
+        Some(root) if root.starts_with(|c: char| c.is_ascii_uppercase()) => return None,
+        _ => segments.insert(0, type_name),
+    }
+
+    Some(segments.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("Patient.name", "Patient").as_deref(), Some("Patient.name"));
+        assert_eq!(
+            normalize_path(" Patient..name. ", "Patient").as_deref(),
+            Some("Patient.name")
+        );
+        assert_eq!(normalize_path("patient.name", "Patient").as_deref(), Some("Patient.name"));
+        assert_eq!(
+            normalize_path("identifier:mrn.system", "Patient").as_deref(),
+            Some("Patient.identifier:mrn.system")
+        );
+        assert_eq!(normalize_path("", "Patient").as_deref(), Some("Patient"));
+        assert_eq!(normalize_path("Observation.code", "Patient"), None);
+    }
+
+    #[test]
+    fn test_logical_model_paths_untouched() {
+        let mut elements = vec![json!({ "path": "MyModel.field" })];
+        let warnings = normalize_element_paths(&mut elements, "http://example.org/MyModel");
+        assert!(warnings.is_empty());
+        assert_eq!(elements[0]["path"], "MyModel.field");
+    }
+}
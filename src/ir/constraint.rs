@@ -5,6 +5,7 @@
 //! bindings, and fixed/pattern values.
 
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 /// Cardinality constraint for an element (min..max).
@@ -23,7 +24,7 @@ use serde::{Deserialize, Serialize};
 /// // Required with at least one: 1..*
 /// let at_least_one = Cardinality::new(1, None);
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Cardinality {
     /// Minimum cardinality (0 or more).
     pub min: u32,
@@ -112,11 +113,51 @@ impl std::fmt::Display for Cardinality {
     }
 }
 
+impl std::str::FromStr for Cardinality {
+    type Err = ParseCardinalityError;
+
+    /// Parse the FHIR string form (`"0..1"`, `"1..*"`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = |reason| ParseCardinalityError {
+            input: s.to_string(),
+            reason,
+        };
+
+        let (min, max) = s.trim().split_once("..").ok_or_else(|| error("expected 'min..max'"))?;
+        let min = min
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| error("min must be a non-negative integer"))?;
+        let max = match max.trim() {
+            "*" => None,
+            max => Some(
+                max.parse::<u32>()
+                    .map_err(|_| error("max must be a non-negative integer or '*'"))?,
+            ),
+        };
+        if max.is_some_and(|max| min > max) {
+            return Err(error("min is greater than max"));
+        }
+
+        Ok(Self { min, max })
+    }
+}
+
+/// Error parsing a FHIR cardinality string.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid cardinality '{input}': {reason}")]
+pub struct ParseCardinalityError {
+    /// The rejected input.
+    pub input: String,
+    /// Why it was rejected.
+    pub reason: &'static str,
+}
+
 /// Type constraint for an element.
 ///
 /// Specifies allowed types for a polymorphic element or profiles that
 /// the type must conform to.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct TypeConstraint {
     /// FHIR type code (e.g., "string", "Reference", "CodeableConcept").
     pub code: String,
@@ -177,7 +218,7 @@ impl TypeConstraint {
 }
 
 /// Terminology binding strength.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum BindingStrength {
     /// Required: must be from the specified value set.
@@ -229,7 +270,7 @@ impl std::fmt::Display for BindingStrength {
 }
 
 /// Terminology binding for coded elements.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Binding {
     /// Binding strength.
     pub strength: BindingStrength,
@@ -240,6 +281,10 @@ pub struct Binding {
     /// Human-readable description of the binding.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Additional bindings with purposes (`binding.additional`, R5+).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional: Vec<AdditionalBinding>,
 }
 
 impl Binding {
@@ -250,6 +295,7 @@ impl Binding {
             strength,
             value_set: value_set.into(),
             description: None,
+            additional: Vec::new(),
         }
     }
 
@@ -273,8 +319,55 @@ impl Binding {
     }
 }
 
+/// Additional binding (`ElementDefinition.binding.additional`, R5+).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct AdditionalBinding {
+    /// Binding purpose code (e.g. "maximum", "minimum", "ui").
+    pub purpose: String,
+
+    /// Canonical URL of the value set.
+    pub value_set: String,
+
+    /// Documentation of the binding's use.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+}
+
+impl AdditionalBinding {
+    /// Purpose codes defined by the R5 `additional-binding-purpose` value set.
+    pub const PURPOSES: &'static [&'static str] = &[
+        "maximum",
+        "minimum",
+        "required",
+        "extensible",
+        "candidate",
+        "current",
+        "preferred",
+        "ui",
+        "starter",
+        "component",
+    ];
+
+    /// Create a new additional binding.
+    #[must_use]
+    pub fn new(purpose: impl Into<String>, value_set: impl Into<String>) -> Self {
+        Self {
+            purpose: purpose.into(),
+            value_set: value_set.into(),
+            documentation: None,
+        }
+    }
+
+    /// Add documentation.
+    #[must_use]
+    pub fn with_documentation(mut self, documentation: impl Into<String>) -> Self {
+        self.documentation = Some(documentation.into());
+        self
+    }
+}
+
 /// Fixed or pattern value for an element.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(tag = "type", content = "value")]
 pub enum FixedValue {
     /// Exact fixed value (must match exactly).
@@ -318,7 +411,7 @@ impl FixedValue {
 }
 
 /// Element flags (mustSupport, isModifier, etc.).
-#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct ElementFlags {
     /// Element must be supported by implementations.
     #[serde(default, skip_serializing_if = "std::ops::Not::not")]
@@ -346,7 +439,7 @@ impl ElementFlags {
 }
 
 /// Complete set of constraints for an element.
-#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ElementConstraints {
     /// Cardinality constraint.
@@ -397,6 +490,10 @@ pub struct ElementConstraints {
     #[serde(default, skip_serializing_if = "is_default_flags")]
     pub flags: ElementFlags,
 
+    /// Keys of invariants that may affect this element's presence (`condition`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<String>,
+
     /// FHIRPath invariants (constraint key -> expression).
     #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
     pub invariants: IndexMap<String, Invariant>,
@@ -409,6 +506,14 @@ pub struct ElementConstraints {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_length: Option<u32>,
 
+    /// Instances must carry a value, not only extensions (`mustHaveValue`, R5+).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub must_have_value: bool,
+
+    /// Extensions allowed in place of a value (`valueAlternatives`, R5+).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub value_alternatives: Vec<String>,
+
     /// Example values.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub examples: Vec<Example>,
@@ -434,9 +539,12 @@ impl ElementConstraints {
             || self.meaning_when_missing.is_some()
             || self.binding.is_some()
             || self.flags.has_any()
+            || !self.conditions.is_empty()
             || !self.invariants.is_empty()
             || !self.mappings.is_empty()
             || self.max_length.is_some()
+            || self.must_have_value
+            || !self.value_alternatives.is_empty()
             || !self.examples.is_empty()
     }
 
@@ -466,7 +574,7 @@ impl ElementConstraints {
 }
 
 /// FHIRPath invariant constraint.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Invariant {
     /// Invariant key (e.g., "ele-1").
     pub key: String,
@@ -490,7 +598,7 @@ pub struct Invariant {
 }
 
 /// Invariant severity level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum InvariantSeverity {
     /// Violation is an error.
@@ -501,7 +609,7 @@ pub enum InvariantSeverity {
 }
 
 /// Mapping to external specification.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Mapping {
     /// Mapping identity (e.g., "rim", "v2").
     pub identity: String,
@@ -519,13 +627,52 @@ pub struct Mapping {
 }
 
 /// Example value for an element.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Example {
     /// Label describing the example.
     pub label: String,
 
     /// The example value.
     pub value: serde_json::Value,
+
+    /// FHIR type of the value (the `value[x]` suffix, e.g. "Quantity").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value_type: Option<String>,
+}
+
+impl Example {
+    /// Create an example with an untyped value.
+    #[must_use]
+    pub fn new(label: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            label: label.into(),
+            value,
+            value_type: None,
+        }
+    }
+
+    /// Set the FHIR type of the value.
+    #[must_use]
+    pub fn with_value_type(mut self, value_type: impl Into<String>) -> Self {
+        self.value_type = Some(value_type.into());
+        self
+    }
+
+    /// Parse an `ElementDefinition.example` entry, keeping the `value[x]` type.
+    #[must_use]
+    pub fn from_json(example: &serde_json::Value) -> Option<Self> {
+        let label = example.get("label").and_then(serde_json::Value::as_str)?;
+        let (key, value) = example
+            .as_object()?
+            .iter()
+            .find(|(k, _)| k.starts_with("value"))?;
+
+        let example = Self::new(label, value.clone());
+        Some(match key.strip_prefix("value").filter(|t| !t.is_empty()) {
+            Some(value_type) => example.with_value_type(value_type),
+            None => example,
+        })
+    }
 }
 
 #[cfg(test)]
@@ -540,6 +687,26 @@ mod tests {
         assert_eq!(Cardinality::new(2, Some(5)).to_string(), "2..5");
     }
 
+    #[test]
+    fn test_cardinality_parsing() {
+        assert_eq!("0..1".parse::<Cardinality>(), Ok(Cardinality::optional()));
+        assert_eq!("1..*".parse::<Cardinality>(), Ok(Cardinality::required_unbounded()));
+        assert_eq!(" 2..5 ".parse::<Cardinality>(), Ok(Cardinality::new(2, Some(5))));
+
+        let err = "2..1".parse::<Cardinality>().unwrap_err();
+        assert_eq!(err.reason, "min is greater than max");
+        assert!(err.to_string().contains("'2..1'"));
+
+        for malformed in ["1", "..1", "a..1", "1..b", "-1..1"] {
+            assert!(malformed.parse::<Cardinality>().is_err(), "{malformed}");
+        }
+
+        // Display round-trips
+        for text in ["0..0", "0..1", "1..*"] {
+            assert_eq!(text.parse::<Cardinality>().unwrap().to_string(), text);
+        }
+    }
+
     #[test]
     fn test_cardinality_comparison() {
         let base = Cardinality::unbounded(); // 0..*
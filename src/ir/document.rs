@@ -3,15 +3,17 @@
 //! This module defines [`ProfileDocument`], which wraps a [`ProfiledResource`]
 //! with editing metadata like dirty state, edit history, and document lifecycle.
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, Utc};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use super::resource::ProfiledResource;
 use super::tracking::EditHistory;
 
 /// Publication status of a profile.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum ProfileStatus {
     /// Work in progress.
@@ -50,8 +52,28 @@ impl std::fmt::Display for ProfileStatus {
     }
 }
 
+/// What element edits of a profile operate on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EditingMode {
+    /// Edits record constraints in the differential.
+    #[default]
+    Differential,
+    /// Edits operate on the full (hydrated) snapshot; the differential is
+    /// recomputed on save as the minimal difference from the base.
+    Snapshot,
+}
+
+impl EditingMode {
+    /// Whether this is the default differential mode.
+    #[must_use]
+    pub const fn is_differential(&self) -> bool {
+        matches!(self, Self::Differential)
+    }
+}
+
 /// Document metadata for a profile.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DocumentMetadata {
     /// Profile ID (business identifier).
@@ -95,9 +117,12 @@ pub struct DocumentMetadata {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub copyright: Option<String>,
 
-    /// Date of last change.
+    /// Date of last change, as a FHIR `dateTime` string.
+    ///
+    /// Kept as written so imported dates keep their precision and
+    /// time zone on export.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub date: Option<DateTime<Utc>>,
+    pub date: Option<String>,
 
     /// Use context (jurisdiction, clinical focus, etc.).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -132,7 +157,7 @@ impl DocumentMetadata {
             description: None,
             purpose: None,
             copyright: None,
-            date: Some(Utc::now()),
+            date: Some(now_date_time()),
             use_context: Vec::new(),
             jurisdiction: Vec::new(),
             keyword: Vec::new(),
@@ -170,12 +195,17 @@ impl DocumentMetadata {
 
     /// Update the last modified date.
     pub fn touch(&mut self) {
-        self.date = Some(Utc::now());
+        self.date = Some(now_date_time());
     }
 }
 
+/// The current time as a FHIR `dateTime`.
+pub fn now_date_time() -> String {
+    Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
 /// Use context for a profile.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct UseContext {
     /// Type of context (workflow, task, venue, etc.).
     pub code: Coding,
@@ -184,7 +214,7 @@ pub struct UseContext {
 }
 
 /// Value for a use context.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(untagged)]
 pub enum UseContextValue {
     /// CodeableConcept value.
@@ -198,7 +228,7 @@ pub enum UseContextValue {
 }
 
 /// Simple coding (system + code).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Coding {
     /// Code system URI.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -231,7 +261,7 @@ impl Coding {
 }
 
 /// CodeableConcept (multiple codings + text).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct CodeableConcept {
     /// Codings.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
@@ -262,7 +292,7 @@ impl CodeableConcept {
 }
 
 /// Simple quantity.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Quantity {
     /// Numeric value.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -279,7 +309,7 @@ pub struct Quantity {
 }
 
 /// Range (low..high).
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct Range {
     /// Low bound.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -290,7 +320,7 @@ pub struct Range {
 }
 
 /// Reference to another resource.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Reference {
     /// Reference URL.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -301,7 +331,7 @@ pub struct Reference {
 }
 
 /// Contact details.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContactDetail {
     /// Name of contact.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -312,7 +342,7 @@ pub struct ContactDetail {
 }
 
 /// Contact point (phone, email, etc.).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ContactPoint {
     /// System (phone, email, url, etc.).
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -370,6 +400,10 @@ pub struct ProfileDocument {
     #[serde(default)]
     pub history: EditHistory,
 
+    /// What element edits operate on.
+    #[serde(default, skip_serializing_if = "EditingMode::is_differential")]
+    pub editing_mode: EditingMode,
+
     /// Whether the document has unsaved changes.
     #[serde(default)]
     dirty: bool,
@@ -395,6 +429,7 @@ impl ProfileDocument {
             metadata,
             resource,
             history: EditHistory::default(),
+            editing_mode: EditingMode::Differential,
             dirty: false,
             file_path: None,
             created_at: now,
@@ -467,6 +502,35 @@ impl ProfileDocument {
     pub fn element_count(&self) -> usize {
         self.resource.element_count()
     }
+
+    /// Hash of the document content (metadata, differential and editing mode).
+    ///
+    /// The hash covers a canonical view of the stored form: the merged element
+    /// tree, generated node ids, edit history and timestamps (including
+    /// `metadata.date`) are left out, so two documents with the same
+    /// constraints hash equal even if one was re-hydrated or touched since.
+    #[must_use]
+    pub fn content_hash(&self) -> String {
+        let mut metadata = self.metadata.clone();
+        metadata.date = None;
+
+        let mut resource = serde_json::to_value(&self.resource).unwrap_or_default();
+        if let Some(fields) = resource.as_object_mut() {
+            fields.remove("root");
+            if let Some(serde_json::Value::Array(differential)) = fields.get_mut("differential") {
+                for diff in differential
+                    .iter_mut()
+                    .filter_map(|diff| diff.as_object_mut())
+                {
+                    diff.remove("id");
+                }
+            }
+        }
+
+        let content =
+            serde_json::to_vec(&(&metadata, &resource, self.editing_mode)).unwrap_or_default();
+        format!("{:x}", Sha256::digest(&content))
+    }
 }
 
 #[cfg(test)]
@@ -5,7 +5,9 @@
 //! child elements.
 
 use indexmap::IndexMap;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 use super::constraint::ElementConstraints;
@@ -24,7 +26,7 @@ use super::slicing::{SliceNode, SlicingDefinition};
 /// let id = NodeId::new();
 /// println!("Created node: {}", id);
 /// ```
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 #[serde(transparent)]
 pub struct NodeId(Uuid);
 
@@ -41,6 +43,18 @@ impl NodeId {
         Self(uuid)
     }
 
+    /// Deterministic node ID for an element of a base definition.
+    ///
+    /// Base trees are parsed again after every restart; deriving their node
+    /// IDs from the element id keeps references to inherited nodes valid.
+    #[must_use]
+    pub fn for_base_element(element_id: &str) -> Self {
+        let digest = Sha256::digest(element_id.as_bytes());
+        let mut bytes = [0; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        Self(Uuid::from_bytes(bytes))
+    }
+
     /// Get the underlying UUID.
     #[must_use]
     pub const fn as_uuid(&self) -> &Uuid {
@@ -61,7 +75,7 @@ impl std::fmt::Display for NodeId {
 }
 
 /// Indicates whether an element's value is inherited from base or explicitly modified.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ElementSource {
     /// Value is inherited from the base resource/profile (default).
@@ -116,7 +130,7 @@ impl ElementSource {
 /// let mut node = ElementNode::new("Patient.name".to_string());
 /// node.constraints_mut().cardinality = Some(Cardinality::new(1, Some(1)));
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ElementNode {
     /// Stable unique identifier for UI operations.
@@ -125,7 +139,9 @@ pub struct ElementNode {
     /// Element path (e.g., "Patient.name", "Patient.name.family").
     pub path: String,
 
-    /// Short element ID within the path (e.g., "name" for "Patient.name").
+    /// `ElementDefinition.id` (e.g., "Patient.name" or "Patient.identifier:mrn").
+    ///
+    /// Imported ids are kept verbatim; new elements default to their path.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub element_id: Option<String>,
 
@@ -162,7 +178,7 @@ impl ElementNode {
     /// Create a new element node with the given path.
     #[must_use]
     pub fn new(path: String) -> Self {
-        let element_id = path.rsplit('.').next().map(ToString::to_string);
+        let element_id = Some(path.clone());
         Self {
             id: NodeId::new(),
             path,
@@ -178,19 +194,22 @@ impl ElementNode {
     }
 
     /// Check if this is an uninitialized placeholder node.
+    ///
+    /// [`ElementNode::default`] gives the placeholder an empty element id, so
+    /// an empty id counts as none.
     #[must_use]
     pub fn is_empty(&self) -> bool {
         self.path.is_empty()
             && self.children.is_empty()
             && self.slices.is_empty()
-            && self.element_id.is_none()
+            && self.element_id.as_deref().is_none_or(str::is_empty)
             && self.unknown_fields.is_empty()
     }
 
     /// Create a new element node with explicit ID.
     #[must_use]
     pub fn with_id(id: NodeId, path: String) -> Self {
-        let element_id = path.rsplit('.').next().map(ToString::to_string);
+        let element_id = Some(path.clone());
         Self {
             id,
             path,
@@ -219,6 +238,12 @@ impl ElementNode {
         self.path.rsplit('.').next().unwrap_or(&self.path)
     }
 
+    /// Get the `ElementDefinition.id` to export for this element.
+    #[must_use]
+    pub fn element_definition_id(&self) -> String {
+        element_definition_id(self.element_id.as_deref(), &self.path)
+    }
+
     /// Get mutable access to constraints.
     pub fn constraints_mut(&mut self) -> &mut ElementConstraints {
         self.source = ElementSource::Modified;
@@ -269,7 +294,7 @@ impl ElementNode {
             return Some(self);
         }
 
-        let child = self.find_child(segments[0])?;
+        let child = self.find_segment(segments[0])?;
         if segments.len() == 1 {
             Some(child)
         } else {
@@ -277,6 +302,15 @@ impl ElementNode {
         }
     }
 
+    /// Find a child by path segment, where `name:slice` resolves to the
+    /// slice entry of child `name`.
+    fn find_segment(&self, segment: &str) -> Option<&ElementNode> {
+        self.find_child(segment).or_else(|| {
+            let (name, slice) = segment.split_once(':')?;
+            self.find_child(name)?.slices.get(slice).map(|s| &s.element)
+        })
+    }
+
     /// Find a descendant element by relative path (mutable).
     pub fn find_descendant_mut(&mut self, relative_path: &str) -> Option<&mut ElementNode> {
         let segments: Vec<&str> = relative_path.split('.').collect();
@@ -288,7 +322,7 @@ impl ElementNode {
             return Some(self);
         }
 
-        let child = self.find_child_mut(segments[0])?;
+        let child = self.find_segment_mut(segments[0])?;
         if segments.len() == 1 {
             Some(child)
         } else {
@@ -296,6 +330,17 @@ impl ElementNode {
         }
     }
 
+    fn find_segment_mut(&mut self, segment: &str) -> Option<&mut ElementNode> {
+        match segment.split_once(':') {
+            Some((name, slice)) if self.find_child(segment).is_none() => self
+                .find_child_mut(name)?
+                .slices
+                .get_mut(slice)
+                .map(|s| &mut s.element),
+            _ => self.find_child_mut(segment),
+        }
+    }
+
     /// Find a node by its stable ID.
     #[must_use]
     pub fn find_by_id(&self, id: NodeId) -> Option<&ElementNode> {
@@ -348,6 +393,20 @@ impl Default for ElementNode {
     }
 }
 
+/// Resolve the `ElementDefinition.id` for an element.
+///
+/// Stored ids are used verbatim. Older IR stored only the last path segment
+/// (e.g., "name" for "Patient.name"); such ids fall back to the path.
+#[must_use]
+pub fn element_definition_id(element_id: Option<&str>, path: &str) -> String {
+    match element_id {
+        Some(id) if !id.is_empty() && (id.contains('.') || id.contains(':') || id == path) => {
+            id.to_string()
+        }
+        _ => path.to_string(),
+    }
+}
+
 /// Depth-first iterator over element tree.
 struct ElementIterator<'a> {
     stack: Vec<&'a ElementNode>,
@@ -399,6 +458,21 @@ mod tests {
         assert!(!node.is_modified());
     }
 
+    #[test]
+    fn test_element_definition_id() {
+        let node = ElementNode::new("Patient.name".to_string());
+        assert_eq!(node.element_id.as_deref(), Some("Patient.name"));
+        assert_eq!(node.element_definition_id(), "Patient.name");
+
+        // Legacy short ids fall back to the path
+        assert_eq!(element_definition_id(Some("name"), "Patient.name"), "Patient.name");
+        assert_eq!(element_definition_id(Some("Patient"), "Patient"), "Patient");
+        assert_eq!(
+            element_definition_id(Some("Patient.identifier:mrn.system"), "Patient.identifier.system"),
+            "Patient.identifier:mrn.system"
+        );
+    }
+
     #[test]
     fn test_child_navigation() {
         let mut parent = ElementNode::new("Patient".to_string());
@@ -422,4 +496,20 @@ mod tests {
         assert!(root.find_descendant("name.family").is_some());
         assert!(root.find_descendant("unknown").is_none());
     }
+
+    #[test]
+    fn test_slice_entry_search() {
+        let mut root = ElementNode::new("Patient".to_string());
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        let mut mrn = SliceNode::with_path("mrn", "Patient.identifier:mrn");
+        mrn.element
+            .add_child(ElementNode::new("Patient.identifier:mrn.system".to_string()));
+        identifier.slices.insert("mrn".to_string(), mrn);
+        root.add_child(identifier);
+
+        let slice = root.find_descendant("identifier:mrn").unwrap();
+        assert_eq!(slice.path, "Patient.identifier:mrn");
+        assert!(root.find_descendant("identifier:mrn.system").is_some());
+        assert!(root.find_descendant_mut("identifier:other").is_none());
+    }
 }
@@ -52,12 +52,15 @@ mod validation;
 
 // Re-export main types at module level
 pub use constraint::{
-    Binding, BindingStrength, Cardinality, ElementConstraints, FixedValue, Invariant,
-    InvariantSeverity, TypeConstraint,
+    AdditionalBinding, Binding, BindingStrength, Cardinality, ElementConstraints, Example, FixedValue, Invariant,
+    InvariantSeverity, ParseCardinalityError, TypeConstraint,
 };
-pub use document::{DocumentMetadata, ProfileDocument, ProfileStatus};
+pub use document::{DocumentMetadata, EditingMode, ProfileDocument, ProfileStatus};
 pub use element::{ElementNode, ElementSource, NodeId};
-pub use resource::{BaseDefinition, FhirVersion, ProfiledResource, StructureKind};
+pub use resource::{
+    BaseDefinition, Derivation, ExtensionContext, ExtensionContextType, FhirVersion,
+    ProfiledResource, StructureKind, EXTENSION_BASE_URL,
+};
 pub use slicing::{Discriminator, DiscriminatorType, SliceNode, SlicingDefinition, SlicingRules};
 pub use tracking::{
     Change, ChangeKind, ChangeTracker, EditHistory, HistoryState, Operation, OperationSummary,
@@ -10,13 +10,16 @@
 //! elements are stored. The full element tree is computed at load time
 //! by merging the differential with the base definition.
 
+use std::sync::Arc;
+
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::element::ElementNode;
 use crate::merge::DifferentialElement;
 
 /// FHIR version identifier.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 pub enum FhirVersion {
     /// FHIR R4 (4.0.1)
     #[default]
@@ -45,6 +48,19 @@ impl FhirVersion {
         }
     }
 
+    /// Whether `ElementDefinition.binding.additional` exists in this version.
+    #[must_use]
+    pub const fn supports_additional_bindings(&self) -> bool {
+        matches!(self, Self::R5 | Self::R6)
+    }
+
+    /// Whether `ElementDefinition.mustHaveValue` and `valueAlternatives`
+    /// exist in this version.
+    #[must_use]
+    pub const fn supports_value_constraints(&self) -> bool {
+        matches!(self, Self::R5 | Self::R6)
+    }
+
     /// Get the short label (R4, R5, etc.).
     #[must_use]
     pub const fn label(&self) -> &'static str {
@@ -86,8 +102,11 @@ impl std::fmt::Display for FhirVersion {
     }
 }
 
+/// Canonical URL of the core Extension StructureDefinition.
+pub const EXTENSION_BASE_URL: &str = "http://hl7.org/fhir/StructureDefinition/Extension";
+
 /// Reference to a base definition (resource or profile).
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct BaseDefinition {
     /// Canonical URL of the base.
     pub url: String,
@@ -112,6 +131,15 @@ impl BaseDefinition {
         }
     }
 
+    /// Create a base reference from a canonical, splitting off a `|version` pin.
+    #[must_use]
+    pub fn from_canonical(canonical: &str) -> Self {
+        match canonical.split_once('|') {
+            Some((url, version)) if !version.is_empty() => Self::new(url).with_version(version),
+            _ => Self::new(strip_canonical_version(canonical)),
+        }
+    }
+
     /// Create a reference to a core FHIR resource.
     #[must_use]
     pub fn resource(resource_type: impl Into<String>) -> Self {
@@ -137,6 +165,25 @@ impl BaseDefinition {
         self
     }
 
+    /// The pinned version, from `version` or a `|version` suffix on the URL.
+    #[must_use]
+    pub fn pinned_version(&self) -> Option<&str> {
+        self.version
+            .as_deref()
+            .or_else(|| self.url.split_once('|').map(|(_, version)| version))
+            .filter(|version| !version.is_empty())
+    }
+
+    /// The canonical reference to resolve and export (`url|version` when pinned).
+    #[must_use]
+    pub fn canonical(&self) -> String {
+        let url = strip_canonical_version(&self.url);
+        match self.pinned_version() {
+            Some(version) => format!("{url}|{version}"),
+            None => url.to_string(),
+        }
+    }
+
     /// Check if this is a core FHIR resource (not a profile).
     #[must_use]
     pub fn is_core_resource(&self) -> bool {
@@ -172,7 +219,7 @@ impl BaseDefinition {
 /// let name_element = ElementNode::new("Patient.name".to_string());
 /// resource.root.add_child(name_element);
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ProfiledResource {
     /// Canonical URL of this profile.
@@ -192,6 +239,20 @@ pub struct ProfiledResource {
     #[serde(default = "default_kind")]
     pub kind: StructureKind,
 
+    /// How this definition relates to its base (`StructureDefinition.derivation`).
+    #[serde(default)]
+    pub derivation: Derivation,
+
+    /// Type a specialization defines (`StructureDefinition.type`); constraint
+    /// profiles take their type from the base.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub type_name: Option<String>,
+
+    /// Whether this definition is abstract (`StructureDefinition.abstract`):
+    /// usable as a base, but instances may not claim conformance to it.
+    #[serde(rename = "abstract", default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_abstract: bool,
+
     /// Root element node (represents the resource type).
     ///
     /// This is the merged view of base + differential, computed at load time.
@@ -210,9 +271,71 @@ pub struct ProfiledResource {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub extensions: Vec<ExtensionDefinition>,
 
+    /// Where this extension may be used (`StructureDefinition.context`).
+    ///
+    /// Only meaningful when the resource is an Extension definition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context: Vec<ExtensionContext>,
+
+    /// FHIRPath invariants that must hold where the extension is used
+    /// (`StructureDefinition.contextInvariant`).
+    ///
+    /// Only meaningful when the resource is an Extension definition.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_invariants: Vec<String>,
+
     /// Unknown fields preserved for lossless round-trip.
     #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
     pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+
+    /// Snapshot elements the StructureDefinition was imported with.
+    ///
+    /// Kept only to check the imported snapshot against one regenerated from
+    /// the differential; export always generates a fresh snapshot. Dropped by
+    /// the first edit, after which it no longer describes the profile.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imported_snapshot: Vec<serde_json::Value>,
+
+    /// Comment block that opened the FSH source this profile was imported from.
+    ///
+    /// Set on the first profile of the file only. Re-emitted above the
+    /// decompiled FSH on export; it never reaches the StructureDefinition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fsh_comment: Option<String>,
+
+    /// Whether `root` is the merged tree built from the base on load.
+    ///
+    /// Edits land in the hydrated tree, so a hydrated document's differential
+    /// is re-derived from it on save. Never persisted.
+    #[serde(skip)]
+    pub hydrated: bool,
+
+    /// Base tree `root` was hydrated from, when the base could be resolved.
+    ///
+    /// Export uses it to leave out text that merely repeats the base; the
+    /// stored differential keeps what the user wrote. Never persisted.
+    #[serde(skip)]
+    pub hydrated_base: Option<Arc<ElementNode>>,
+}
+
+/// Replace `old_url` in a canonical reference, keeping any `|version` suffix.
+fn rewrite_canonical(reference: &str, old_url: &str, new_url: &str) -> Option<String> {
+    let (url, version) = match reference.split_once('|') {
+        Some((url, version)) => (url, Some(version)),
+        None => (reference, None),
+    };
+    if url != old_url {
+        return None;
+    }
+    Some(match version {
+        Some(version) => format!("{new_url}|{version}"),
+        None => new_url.to_string(),
+    })
+}
+
+/// Strip a `|version` suffix from a canonical URL.
+fn strip_canonical_version(url: &str) -> &str {
+    url.split('|').next().unwrap_or(url)
 }
 
 fn default_kind() -> StructureKind {
@@ -237,13 +360,119 @@ impl ProfiledResource {
             fhir_version,
             base,
             kind: StructureKind::Resource,
+            derivation: Derivation::Constraint,
+            type_name: None,
+            is_abstract: false,
             root: ElementNode::new(root_path),
             differential: Vec::new(),
             extensions: Vec::new(),
+            context: Vec::new(),
+            context_invariants: Vec::new(),
             unknown_fields: serde_json::Map::new(),
+            imported_snapshot: Vec::new(),
+            fsh_comment: None,
+            hydrated: false,
+            hydrated_base: None,
         }
     }
 
+    /// Create a specialization that defines the new type `name` from `base_type`
+    /// (e.g., a custom resource derived from `DomainResource`).
+    #[must_use]
+    pub fn specialization(
+        url: impl Into<String>,
+        fhir_version: FhirVersion,
+        base_type: &str,
+        name: impl Into<String>,
+    ) -> Self {
+        let name = name.into();
+        let mut resource = Self::new(url, fhir_version, BaseDefinition::resource(base_type));
+        resource.derivation = Derivation::Specialization;
+        resource.root = ElementNode::new(name.clone());
+        resource.type_name = Some(name);
+        resource
+    }
+
+    /// Create a logical model.
+    ///
+    /// Logical models specialize the abstract root type (`Base` from R5,
+    /// `Element` before) and define every element themselves.
+    #[must_use]
+    pub fn logical(url: impl Into<String>, fhir_version: FhirVersion, name: impl Into<String>) -> Self {
+        let root_type = match fhir_version {
+            FhirVersion::R4 | FhirVersion::R4B => "Element",
+            FhirVersion::R5 | FhirVersion::R6 => "Base",
+        };
+        let mut resource = Self::specialization(url, fhir_version, root_type, name);
+        resource.kind = StructureKind::Logical;
+        resource
+    }
+
+    /// Canonical URLs of other structure definitions this resource references.
+    ///
+    /// Covers the base definition, type `profile`/`targetProfile` URLs in
+    /// the differential, and extension contexts naming another extension.
+    pub fn referenced_canonicals(&self) -> impl Iterator<Item = &str> {
+        let type_refs = self
+            .differential
+            .iter()
+            .flat_map(|element| &element.constraints.types)
+            .flat_map(|ty| ty.profile.iter().chain(&ty.target_profile));
+        let context_refs = self
+            .context
+            .iter()
+            .filter(|context| context.context_type == ExtensionContextType::Extension)
+            .map(|context| &context.expression);
+        std::iter::once(&self.base.url)
+            .chain(type_refs)
+            .chain(context_refs)
+            .map(String::as_str)
+    }
+
+    /// Rewrite references to `old_url` so they point to `new_url`.
+    ///
+    /// Rewrites the base definition, type profiles and target profiles in the
+    /// differential, and extension contexts. A `|version` suffix is kept.
+    /// Returns the number of references rewritten.
+    pub fn rewrite_canonical_references(&mut self, old_url: &str, new_url: &str) -> usize {
+        let mut rewritten = 0;
+        let mut rewrite = |reference: &mut String| {
+            if let Some(updated) = rewrite_canonical(reference, old_url, new_url) {
+                *reference = updated;
+                rewritten += 1;
+            }
+        };
+
+        rewrite(&mut self.base.url);
+        for element in &mut self.differential {
+            for ty in &mut element.constraints.types {
+                ty.profile.iter_mut().for_each(&mut rewrite);
+                ty.target_profile.iter_mut().for_each(&mut rewrite);
+            }
+        }
+        for context in &mut self.context {
+            if context.context_type == ExtensionContextType::Extension {
+                rewrite(&mut context.expression);
+            }
+        }
+
+        rewritten
+    }
+
+    /// Check if this is a logical model.
+    #[must_use]
+    pub fn is_logical(&self) -> bool {
+        self.kind == StructureKind::Logical
+    }
+
+    /// Check if this defines a new type rather than constraining its base.
+    ///
+    /// Logical models are always specializations.
+    #[must_use]
+    pub fn is_specialization(&self) -> bool {
+        self.derivation == Derivation::Specialization || self.is_logical()
+    }
+
     /// Set the differential elements.
     #[must_use]
     pub fn with_differential(mut self, differential: Vec<DifferentialElement>) -> Self {
@@ -255,8 +484,20 @@ impl ProfiledResource {
     ///
     /// This collects all modified elements from `root` and stores them
     /// in `differential` for storage.
+    ///
+    /// Imported source positions are carried over from the previous
+    /// differential by node id, so re-extraction keeps the original order.
     pub fn extract_differential(&mut self) {
+        let source_indexes: std::collections::HashMap<_, _> = self
+            .differential
+            .iter()
+            .filter_map(|diff| diff.source_index.map(|index| (diff.id, index)))
+            .collect();
+
         self.differential = crate::merge::extract_differential(&self.root);
+        for diff in &mut self.differential {
+            diff.source_index = source_indexes.get(&diff.id).copied();
+        }
     }
 
     /// Check if the profile has a populated differential.
@@ -273,8 +514,16 @@ impl ProfiledResource {
     }
 
     /// Get the resource type name.
+    ///
+    /// For specializations (including logical models) this is the new type,
+    /// not the type it specializes.
     #[must_use]
     pub fn resource_type(&self) -> &str {
+        if self.is_specialization() {
+            let model_type = self.type_name.as_deref().unwrap_or(&self.url);
+            return model_type.rsplit('/').next().unwrap_or(model_type);
+        }
+
         self.base
             .url
             .rsplit('/')
@@ -335,10 +584,46 @@ impl ProfiledResource {
     pub fn has_modifications(&self) -> bool {
         self.elements().any(|e| e.is_modified())
     }
+
+    /// Check if this resource is an Extension definition.
+    ///
+    /// Only the direct base is considered; use [`Self::is_extension_with`]
+    /// when the base itself may be a custom extension.
+    #[must_use]
+    pub fn is_extension(&self) -> bool {
+        self.is_extension_with(|_| None)
+    }
+
+    /// Check if this resource is an Extension definition, following the base
+    /// canonical chain.
+    ///
+    /// `base_of` maps a canonical URL to the base URL of that definition, or
+    /// `None` if it is unknown. The resource is an extension when the chain
+    /// reaches the core Extension StructureDefinition.
+    pub fn is_extension_with<F>(&self, base_of: F) -> bool
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let mut visited = std::collections::HashSet::new();
+        let mut current = strip_canonical_version(&self.base.url).to_string();
+
+        loop {
+            if current == EXTENSION_BASE_URL {
+                return true;
+            }
+            if !visited.insert(current.clone()) {
+                return false;
+            }
+            match base_of(&current) {
+                Some(next) => current = strip_canonical_version(&next).to_string(),
+                None => return false,
+            }
+        }
+    }
 }
 
 /// Kind of structure definition.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "kebab-case")]
 pub enum StructureKind {
     /// FHIR resource type.
@@ -365,8 +650,105 @@ impl StructureKind {
     }
 }
 
+/// How a structure definition relates to its base definition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Derivation {
+    /// Constrains the base (a profile).
+    #[default]
+    Constraint,
+    /// Defines a new type from the base (new resources, logical models).
+    Specialization,
+}
+
+impl Derivation {
+    /// Get the FHIR code.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Constraint => "constraint",
+            Self::Specialization => "specialization",
+        }
+    }
+
+    /// Parse a FHIR derivation code.
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "constraint" => Some(Self::Constraint),
+            "specialization" => Some(Self::Specialization),
+            _ => None,
+        }
+    }
+}
+
+/// Kind of context an extension can be used in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExtensionContextType {
+    /// Expression is an element id (e.g., `Patient.address`).
+    Element,
+    /// Expression is the canonical URL of another extension.
+    Extension,
+    /// Expression is a FHIRPath expression.
+    Fhirpath,
+}
+
+impl ExtensionContextType {
+    /// Get the FHIR code.
+    #[must_use]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Element => "element",
+            Self::Extension => "extension",
+            Self::Fhirpath => "fhirpath",
+        }
+    }
+
+    /// Parse from a FHIR code.
+    #[must_use]
+    pub fn from_code(code: &str) -> Option<Self> {
+        match code {
+            "element" => Some(Self::Element),
+            "extension" => Some(Self::Extension),
+            "fhirpath" => Some(Self::Fhirpath),
+            _ => None,
+        }
+    }
+}
+
+/// A single entry of an extension's `StructureDefinition.context`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ExtensionContext {
+    /// How `expression` should be interpreted.
+    #[serde(rename = "type")]
+    pub context_type: ExtensionContextType,
+
+    /// Where the extension can be used.
+    pub expression: String,
+}
+
+impl ExtensionContext {
+    /// Create a new context entry.
+    #[must_use]
+    pub fn new(context_type: ExtensionContextType, expression: impl Into<String>) -> Self {
+        Self {
+            context_type,
+            expression: expression.into(),
+        }
+    }
+
+    /// Parse a context entry from its FHIR JSON form.
+    #[must_use]
+    pub fn from_json(value: &serde_json::Value) -> Option<Self> {
+        let context_type = ExtensionContextType::from_code(value.get("type")?.as_str()?)?;
+        let expression = value.get("expression")?.as_str()?;
+        Some(Self::new(context_type, expression))
+    }
+}
+
 /// Extension definition included in a profile.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExtensionDefinition {
     /// Canonical URL of the extension.
     pub url: String,
@@ -429,6 +811,24 @@ mod tests {
         assert!(!profile.is_core_resource());
     }
 
+    #[test]
+    fn test_base_definition_version_pin() {
+        let url = "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient";
+        let pinned = BaseDefinition::from_canonical(&format!("{url}|6.1.0"));
+        assert_eq!(pinned.url, url);
+        assert_eq!(pinned.pinned_version(), Some("6.1.0"));
+        assert_eq!(pinned.canonical(), format!("{url}|6.1.0"));
+
+        let unpinned = BaseDefinition::from_canonical(url);
+        assert_eq!(unpinned.pinned_version(), None);
+        assert_eq!(unpinned.canonical(), url);
+
+        // Legacy documents keep the pin inside the URL
+        let legacy = BaseDefinition::new(format!("{url}|3.1.1"));
+        assert_eq!(legacy.pinned_version(), Some("3.1.1"));
+        assert_eq!(legacy.canonical(), format!("{url}|3.1.1"));
+    }
+
     #[test]
     fn test_profiled_resource_creation() {
         let resource = ProfiledResource::new(
@@ -456,4 +856,84 @@ mod tests {
         assert!(resource.find_element("Patient.name").is_some());
         assert!(resource.find_element("Patient.unknown").is_none());
     }
+
+    #[test]
+    fn test_is_extension() {
+        let extension = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/nickname",
+            FhirVersion::R4,
+            BaseDefinition::resource("Extension"),
+        );
+        assert!(extension.is_extension());
+
+        // A profile on a type whose name merely contains "Extension"
+        let false_positive = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/MyProfile",
+            FhirVersion::R4,
+            BaseDefinition::new("http://example.org/fhir/StructureDefinition/ExtensionRegistry"),
+        );
+        assert!(!false_positive.is_extension());
+        assert!(!false_positive.is_extension_with(|_| None));
+    }
+
+    #[test]
+    fn test_rewrite_canonical_references() {
+        let old_url = "http://example.org/fhir/StructureDefinition/base-patient";
+        let new_url = "http://example.org/fhir/StructureDefinition/core-patient";
+        let mut resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/derived-patient",
+            FhirVersion::R4,
+            BaseDefinition::new(format!("{old_url}|1.0.0")),
+        );
+        let mut subject = DifferentialElement::new("Patient.link.other".to_string());
+        let mut reference = crate::ir::TypeConstraint::simple("Reference");
+        reference.target_profile = vec![
+            old_url.to_string(),
+            "http://hl7.org/fhir/StructureDefinition/RelatedPerson".to_string(),
+        ];
+        subject.constraints.types = vec![reference];
+        resource.differential.push(subject);
+        resource.context = vec![
+            ExtensionContext::new(ExtensionContextType::Extension, old_url),
+            ExtensionContext::new(ExtensionContextType::Element, "Patient"),
+        ];
+
+        assert_eq!(
+            resource
+                .referenced_canonicals()
+                .filter(|url| url.starts_with(old_url))
+                .count(),
+            3
+        );
+        assert_eq!(resource.rewrite_canonical_references(old_url, new_url), 3);
+        assert_eq!(resource.context[0].expression, new_url);
+        assert_eq!(resource.base.url, format!("{new_url}|1.0.0"));
+        assert_eq!(
+            resource.differential[0].constraints.types[0].target_profile,
+            vec![
+                new_url.to_string(),
+                "http://hl7.org/fhir/StructureDefinition/RelatedPerson".to_string(),
+            ]
+        );
+        assert_eq!(resource.rewrite_canonical_references(old_url, new_url), 0);
+    }
+
+    #[test]
+    fn test_is_extension_follows_base_chain() {
+        let custom = "http://example.org/fhir/StructureDefinition/base-ext";
+        let derived = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/derived-ext",
+            FhirVersion::R4,
+            BaseDefinition::new(format!("{custom}|1.0.0")),
+        );
+
+        assert!(!derived.is_extension());
+        assert!(derived.is_extension_with(|url| {
+            (url == custom).then(|| EXTENSION_BASE_URL.to_string())
+        }));
+
+        // Cycles terminate
+        let cyclic = |url: &str| (url == custom).then(|| custom.to_string());
+        assert!(!derived.is_extension_with(cyclic));
+    }
 }
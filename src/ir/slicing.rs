@@ -16,13 +16,14 @@
 //! └── passport (slice): type = Passport, cardinality 0..*
 //! ```
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use super::constraint::ElementConstraints;
 use super::element::{ElementNode, ElementSource, NodeId};
 
 /// How slices are discriminated (identified).
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum DiscriminatorType {
     /// Slice by value of an element.
@@ -62,7 +63,7 @@ impl std::fmt::Display for DiscriminatorType {
 }
 
 /// A discriminator that identifies which slice an element belongs to.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct Discriminator {
     /// Type of discrimination.
     #[serde(rename = "type")]
@@ -108,7 +109,7 @@ impl Discriminator {
 }
 
 /// Rules for how slices are handled.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Default, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum SlicingRules {
     /// Only the defined slices are allowed.
@@ -160,7 +161,7 @@ impl std::fmt::Display for SlicingRules {
 /// .with_rules(SlicingRules::Open)
 /// .with_description("Sliced by identifier system");
 /// ```
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct SlicingDefinition {
     /// Discriminators that identify slices.
     pub discriminator: Vec<Discriminator>,
@@ -248,7 +249,7 @@ impl SlicingDefinition {
 /// let slice = SliceNode::new("official")
 ///     .with_cardinality(Cardinality::required());
 /// ```
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SliceNode {
     /// Stable unique identifier for UI operations.
@@ -267,6 +268,10 @@ pub struct SliceNode {
     /// Whether this slice was added by this profile or inherited.
     #[serde(default)]
     pub source: ElementSource,
+
+    /// Whether this slice constrains an inherited slice (`sliceIsConstraining`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub slice_is_constraining: bool,
 }
 
 impl SliceNode {
@@ -281,6 +286,7 @@ impl SliceNode {
             element: ElementNode::with_id(id, name),
             parent_id: None,
             source: ElementSource::Added,
+            slice_is_constraining: false,
         }
     }
 
@@ -295,6 +301,7 @@ impl SliceNode {
             element: ElementNode::with_id(id, path.into()),
             parent_id: None,
             source: ElementSource::Added,
+            slice_is_constraining: false,
         }
     }
 
@@ -312,6 +319,25 @@ impl SliceNode {
         self
     }
 
+    /// Mark this slice as constraining an inherited slice.
+    #[must_use]
+    pub const fn with_slice_is_constraining(mut self, constraining: bool) -> Self {
+        self.slice_is_constraining = constraining;
+        self
+    }
+
+    /// Check if this is a re-slice (e.g., `foo/bar`) of an existing slice.
+    #[must_use]
+    pub fn is_reslice(&self) -> bool {
+        self.name.contains('/')
+    }
+
+    /// Get the name of the slice this re-slice refines (e.g., `foo` for `foo/bar`).
+    #[must_use]
+    pub fn parent_slice_name(&self) -> Option<&str> {
+        self.name.rsplit_once('/').map(|(parent, _)| parent)
+    }
+
     /// Get mutable access to the element's constraints.
     pub fn constraints_mut(&mut self) -> &mut ElementConstraints {
         self.element.constraints_mut()
@@ -363,6 +389,17 @@ mod tests {
         assert!(slice.element.constraints.cardinality.is_some());
     }
 
+    #[test]
+    fn test_reslice_name() {
+        let slice = SliceNode::with_path("foo/bar", "Patient.extension:foo/bar")
+            .with_slice_is_constraining(true);
+
+        assert!(slice.is_reslice());
+        assert_eq!(slice.parent_slice_name(), Some("foo"));
+        assert!(slice.slice_is_constraining);
+        assert!(!SliceNode::new("foo").is_reslice());
+    }
+
     #[test]
     fn test_slicing_rules() {
         assert!(SlicingRules::Open.allows_additional());
@@ -367,6 +367,21 @@ impl EditHistory {
         self.saved_index = None;
     }
 
+    /// Drop all recorded operations while keeping the document as it is.
+    ///
+    /// Unlike [`Self::clear`], a document that was at its saved state stays
+    /// clean. Returns the number of operations dropped; this cannot be undone.
+    pub fn squash(&mut self) -> usize {
+        let dropped = self.undo_stack.len() + self.redo_stack.len();
+        let was_saved = self.is_at_saved_state();
+
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.saved_index = was_saved.then_some(0);
+
+        dropped
+    }
+
     /// Get the number of undoable operations.
     #[must_use]
     pub fn undo_count(&self) -> usize {
@@ -447,6 +462,20 @@ impl EditHistory {
         result
     }
 
+    /// Get the most recent operations for UI display.
+    ///
+    /// Like [`Self::get_operations`], but keeps at most `limit` entries
+    /// (the latest ones) when a limit is given.
+    #[must_use]
+    pub fn recent_operations(&self, limit: Option<usize>) -> Vec<OperationSummary> {
+        let mut operations = self.get_operations();
+        if let Some(limit) = limit {
+            let skip = operations.len().saturating_sub(limit);
+            operations.drain(..skip);
+        }
+        operations
+    }
+
     /// Get the current position in history.
     #[must_use]
     pub fn current_index(&self) -> usize {
@@ -644,6 +673,46 @@ mod tests {
         assert_eq!(inverse.new_value, Some(serde_json::json!("old")));
     }
 
+    #[test]
+    fn test_squash_history() {
+        let mut history = EditHistory::new(10);
+        for i in 0..3 {
+            history.push(Operation::single(
+                format!("Operation {i}"),
+                Change::set(NodeId::new(), "field", None, serde_json::json!(i)),
+            ));
+        }
+        history.undo();
+        history.mark_saved();
+
+        assert_eq!(history.squash(), 3);
+        assert!(!history.can_undo());
+        assert!(!history.can_redo());
+        assert!(history.is_at_saved_state());
+        assert!(history.get_operations().is_empty());
+    }
+
+    #[test]
+    fn test_recent_operations_limit() {
+        let mut history = EditHistory::new(10);
+        for i in 0..5 {
+            history.push(Operation::single(
+                format!("Operation {i}"),
+                Change::set(NodeId::new(), "field", None, serde_json::json!(i)),
+            ));
+        }
+
+        assert_eq!(history.recent_operations(None).len(), 5);
+
+        let recent = history.recent_operations(Some(2));
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].description, "Operation 3");
+        assert_eq!(recent[0].index, 3);
+        assert!(recent[1].is_current);
+
+        assert_eq!(history.recent_operations(Some(10)).len(), 5);
+    }
+
     #[test]
     fn test_edit_history_undo_redo() {
         let mut history = EditHistory::new(10);
@@ -42,12 +42,15 @@ pub mod fsh;
 pub mod import;
 pub mod ir;
 pub mod merge;
+pub mod metrics;
 pub mod operations;
+pub mod paths;
 pub mod project;
 pub mod server;
 pub mod state;
 pub mod static_files;
 pub mod validation;
+pub mod version;
 
 pub use config::Config;
 pub use error::{Error, Result};
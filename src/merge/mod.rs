@@ -3,13 +3,15 @@
 //! Merges differential elements onto a base element tree to produce
 //! the combined view needed for UI display.
 
+use std::collections::{BTreeSet, HashMap};
+
 use crate::ir::{ElementConstraints, ElementNode, ElementSource, NodeId, SliceNode, SlicingDefinition};
 
 /// A differential element representing a modification to the base.
 ///
 /// Unlike `ElementNode`, this is a flat representation without children,
 /// as the tree structure comes from the base.
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct DifferentialElement {
     /// Stable unique identifier for UI operations.
@@ -18,14 +20,18 @@ pub struct DifferentialElement {
     /// Element path (e.g., "Patient.name", "Patient.name.family").
     pub path: String,
 
-    /// Element ID within the path (e.g., "name" for "Patient.name").
+    /// `ElementDefinition.id`, preserved verbatim from import.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub element_id: Option<String>,
 
-    /// Slice name if this is a slice definition.
+    /// Slice name if this is a slice definition (re-slices use `parent/child`).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub slice_name: Option<String>,
 
+    /// Whether this slice constrains an inherited slice (`sliceIsConstraining`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub slice_is_constraining: bool,
+
     /// The constraints that are modified from the base.
     #[serde(default)]
     pub constraints: ElementConstraints,
@@ -37,21 +43,30 @@ pub struct DifferentialElement {
     /// Unknown fields preserved for lossless round-trip.
     #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
     pub unknown_fields: serde_json::Map<String, serde_json::Value>,
+
+    /// Position of this element in the imported differential.
+    ///
+    /// Export keeps imported elements in this order; elements without one
+    /// (added after import) are placed next to their parent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_index: Option<usize>,
 }
 
 impl DifferentialElement {
     /// Create a new differential element.
     #[must_use]
     pub fn new(path: String) -> Self {
-        let element_id = path.rsplit('.').next().map(ToString::to_string);
+        let element_id = Some(path.clone());
         Self {
             id: NodeId::new(),
             path,
             element_id,
             slice_name: None,
+            slice_is_constraining: false,
             constraints: ElementConstraints::default(),
             slicing: None,
             unknown_fields: serde_json::Map::new(),
+            source_index: None,
         }
     }
 
@@ -63,9 +78,11 @@ impl DifferentialElement {
             path: node.path.clone(),
             element_id: node.element_id.clone(),
             slice_name: None,
+            slice_is_constraining: false,
             constraints: node.constraints.clone(),
             slicing: node.slicing.clone(),
             unknown_fields: node.unknown_fields.clone(),
+            source_index: None,
         }
     }
 
@@ -108,15 +125,31 @@ impl ElementTreeMerger {
     #[must_use]
     pub fn merge(
         &self,
-        mut base_tree: ElementNode,
+        base_tree: ElementNode,
         differential: &[DifferentialElement],
     ) -> ElementNode {
+        self.merge_with_origins(base_tree, differential).0
+    }
+
+    /// Merge differential elements onto a base tree, recording which
+    /// constraint fields each merged node took from the differential.
+    ///
+    /// Fields not recorded for a node were inherited from the base tree.
+    #[must_use]
+    pub fn merge_with_origins(
+        &self,
+        mut base_tree: ElementNode,
+        differential: &[DifferentialElement],
+    ) -> (ElementNode, ConstraintOrigins) {
+        let mut origins = ConstraintOrigins::default();
+
         // Apply differential entries in order (FHIR differential order is significant)
         for diff in differential {
             self.apply_differential_entry(&mut base_tree, diff);
+            origins.record(diff);
         }
 
-        base_tree
+        (base_tree, origins)
     }
 
     /// Apply a single differential entry to the tree.
@@ -128,8 +161,13 @@ impl ElementTreeMerger {
 
         if let Some(slice_name) = slice_name {
             // Clone children from target BEFORE mutably borrowing for slice lookup
-            // to avoid borrow checker conflicts
-            let children_to_clone: Vec<_> = target
+            // to avoid borrow checker conflicts. Re-slices (`foo/bar`) start from
+            // the slice they refine rather than the unsliced element.
+            let template = slice_name
+                .rsplit_once('/')
+                .and_then(|(parent, _)| target.slices.get(parent))
+                .map_or(&*target, |parent| &parent.element);
+            let children_to_clone: Vec<_> = template
                 .children
                 .iter()
                 .map(|child| self.clone_child_for_slice(child, &path, slice_name))
@@ -169,6 +207,9 @@ impl ElementTreeMerger {
                 };
                 slice.element.id = diff.id;
             }
+            if diff.slice_is_constraining {
+                slice.slice_is_constraining = true;
+            }
             slice.source = if slice_was_added {
                 ElementSource::Added
             } else {
@@ -261,6 +302,19 @@ impl ElementTreeMerger {
             element.constraints.max_length = constraints.max_length;
         }
 
+        // Apply value requirements if set
+        if constraints.must_have_value {
+            element.constraints.must_have_value = true;
+        }
+        if !constraints.value_alternatives.is_empty() {
+            element.constraints.value_alternatives = constraints.value_alternatives.clone();
+        }
+
+        // Apply conditions if set
+        if !constraints.conditions.is_empty() {
+            element.constraints.conditions = constraints.conditions.clone();
+        }
+
         // Merge invariants
         if !constraints.invariants.is_empty() {
             for (key, invariant) in &constraints.invariants {
@@ -293,23 +347,20 @@ impl ElementTreeMerger {
     }
 
     /// Parse slice context from a differential element.
+    ///
+    /// Returns the sliced element path, the slice name and the path of the
+    /// slice child (if any). Re-slice names such as `foo/bar` are kept whole,
+    /// so `Patient.extension:foo/bar.value[x]` yields
+    /// `("Patient.extension", Some("foo/bar"), Some("value[x]"))`.
     fn parse_slice_context<'a>(
         &self,
         diff: &'a DifferentialElement,
     ) -> (String, Option<&'a str>, Option<&'a str>) {
         if diff.slice_name.is_some() {
             if let Some(element_id) = diff.element_id.as_deref() {
-                if element_id.contains(':') {
-                    if let Some((base_path, slice_suffix)) = element_id.split_once(':') {
-                        if let Some((slice_name, child_path)) = slice_suffix.split_once('.') {
-                            return (
-                                base_path.to_string(),
-                                Some(slice_name),
-                                Some(child_path),
-                            );
-                        }
-                        return (base_path.to_string(), Some(slice_suffix), None);
-                    }
+                if let Some((base_path, slice_suffix)) = element_id.split_once(':') {
+                    let (slice_name, child_path) = split_slice_suffix(slice_suffix);
+                    return (base_path.to_string(), Some(slice_name), child_path);
                 }
             }
 
@@ -317,14 +368,8 @@ impl ElementTreeMerger {
         }
 
         if let Some((base_path, slice_suffix)) = diff.path.split_once(':') {
-            if let Some((slice_name, child_path)) = slice_suffix.split_once('.') {
-                return (
-                    base_path.to_string(),
-                    Some(slice_name),
-                    Some(child_path),
-                );
-            }
-            return (base_path.to_string(), Some(slice_suffix), None);
+            let (slice_name, child_path) = split_slice_suffix(slice_suffix);
+            return (base_path.to_string(), Some(slice_name), child_path);
         }
 
         (diff.path.clone(), None, None)
@@ -447,10 +492,15 @@ impl ElementTreeMerger {
         base_path: &str,
         slice_name: &str,
     ) -> ElementNode {
-        let prefix = format!("{}.", base_path);
+        // Children may come from the unsliced element (`base.child`) or, for
+        // re-slices, from the parent slice (`base:slice.child`).
         let relative = child
             .path
-            .strip_prefix(&prefix)
+            .strip_prefix(base_path)
+            .and_then(|rest| match rest.strip_prefix(':') {
+                Some(slice_suffix) => split_slice_suffix(slice_suffix).1,
+                None => rest.strip_prefix('.'),
+            })
             .unwrap_or_else(|| child.short_name());
         let new_path = format!("{}:{}.{}", base_path, slice_name, relative);
         let mut cloned = ElementNode::new(new_path.clone());
@@ -469,6 +519,189 @@ impl ElementTreeMerger {
     }
 }
 
+/// Split the part of an element id after `:` into slice name and child path.
+///
+/// Slice names may contain `/` for re-slices but never `.`, so the first `.`
+/// always starts the child path.
+fn split_slice_suffix(slice_suffix: &str) -> (&str, Option<&str>) {
+    match slice_suffix.split_once('.') {
+        Some((slice_name, child_path)) => (slice_name, Some(child_path)),
+        None => (slice_suffix, None),
+    }
+}
+
+/// Constraint fields set by the differential, per merged node.
+///
+/// Kept next to the merged tree instead of on [`ElementConstraints`], so
+/// the IR stays unchanged. Field names are those of [`constraint_fields`].
+#[derive(Debug, Clone, Default)]
+pub struct ConstraintOrigins {
+    fields: HashMap<NodeId, BTreeSet<String>>,
+}
+
+impl ConstraintOrigins {
+    /// Record the fields a differential entry sets on its node.
+    fn record(&mut self, diff: &DifferentialElement) {
+        self.fields
+            .entry(diff.id)
+            .or_default()
+            .extend(differential_fields(diff));
+    }
+
+    /// Whether `field` of the node was set by the differential.
+    #[must_use]
+    pub fn is_from_differential(&self, node: NodeId, field: &str) -> bool {
+        self.fields.get(&node).is_some_and(|fields| fields.contains(field))
+    }
+}
+
+/// Names of the constraint fields a differential entry sets, including
+/// `slicing`.
+#[must_use]
+pub fn differential_fields(diff: &DifferentialElement) -> BTreeSet<String> {
+    let mut fields: BTreeSet<String> = constraint_fields(&diff.constraints)
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    if diff.slicing.is_some() {
+        fields.insert("slicing".to_string());
+    }
+    fields
+}
+
+/// The constraint fields that are set, with their JSON values.
+///
+/// Names are camelCase like the serialized constraints (`cardinality`,
+/// `fixedValue`, ...); each flag is reported on its own as `flags.<name>`
+/// (e.g. `flags.mustSupport`).
+#[must_use]
+pub fn constraint_fields(constraints: &ElementConstraints) -> Vec<(String, serde_json::Value)> {
+    let Ok(serde_json::Value::Object(object)) = serde_json::to_value(constraints) else {
+        return Vec::new();
+    };
+
+    let mut fields = Vec::with_capacity(object.len());
+    for (name, value) in object {
+        match value {
+            serde_json::Value::Object(flags) if name == "flags" => {
+                fields.extend(
+                    flags
+                        .into_iter()
+                        .map(|(flag, value)| (format!("flags.{}", camel_case(&flag)), value)),
+                );
+            }
+            value => fields.push((name, value)),
+        }
+    }
+    fields
+}
+
+/// Convert a snake_case field name (`must_support`) to camelCase.
+fn camel_case(name: &str) -> String {
+    let mut camel = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            camel.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            camel.push(c);
+        }
+    }
+    camel
+}
+
+/// The base element a differential path constrains, if the base has it.
+fn base_element_for<'a>(base: &'a ElementNode, path: &str) -> Option<&'a ElementNode> {
+    if path == base.path {
+        return Some(base);
+    }
+    path.strip_prefix(&base.path)
+        .and_then(|rest| rest.strip_prefix('.'))
+        .and_then(|relative| base.find_descendant(relative))
+}
+
+/// Drop `short`, `definition` and `comment` values that repeat the base.
+///
+/// Elements left with nothing to say after pruning are removed, so a user
+/// retyping the base text does not grow the exported differential.
+pub fn prune_inherited_text(differential: &mut Vec<DifferentialElement>, base: &ElementNode) {
+    differential.retain_mut(|diff| {
+        let Some(base_element) = base_element_for(base, &diff.path) else {
+            return true;
+        };
+
+        let inherited = &base_element.constraints;
+        let constraints = &mut diff.constraints;
+        let mut pruned = false;
+        for (value, base_value) in [
+            (&mut constraints.short, &inherited.short),
+            (&mut constraints.definition, &inherited.definition),
+            (&mut constraints.comment, &inherited.comment),
+        ] {
+            if value.is_some() && value == base_value {
+                *value = None;
+                pruned = true;
+            }
+        }
+
+        !pruned
+            || diff.has_constraints()
+            || diff.slice_name.is_some()
+            || !diff.unknown_fields.is_empty()
+    });
+}
+
+/// Reduce a differential extracted from a full tree to what differs from the base.
+///
+/// Every constraint field equal to the base element's is dropped (flags and
+/// invariants one by one), as is slicing identical to the base slicing.
+/// Entries left empty are removed unless they define a slice. Elements the
+/// base does not have are kept as they are.
+pub fn minimize_differential(differential: &mut Vec<DifferentialElement>, base: &ElementNode) {
+    differential.retain_mut(|diff| {
+        let Some(base_element) = base_element_for(base, &diff.path) else {
+            return true;
+        };
+
+        diff.constraints = strip_inherited(&diff.constraints, &base_element.constraints);
+        if diff.slicing.is_some() && diff.slicing == base_element.slicing {
+            diff.slicing = None;
+        }
+
+        diff.has_constraints() || diff.slice_name.is_some() || !diff.unknown_fields.is_empty()
+    });
+}
+
+/// Constraints with every value equal to the base removed.
+fn strip_inherited(
+    constraints: &ElementConstraints,
+    base: &ElementConstraints,
+) -> ElementConstraints {
+    use serde_json::Value;
+
+    let (Ok(Value::Object(mut own)), Ok(Value::Object(inherited))) =
+        (serde_json::to_value(constraints), serde_json::to_value(base))
+    else {
+        return constraints.clone();
+    };
+
+    own.retain(|name, value| match (value, inherited.get(name)) {
+        // Flags and invariants are compared entry by entry
+        (Value::Object(entries), Some(Value::Object(base_entries)))
+            if name == "flags" || name == "invariants" =>
+        {
+            entries.retain(|key, entry| base_entries.get(key) != Some(entry));
+            !entries.is_empty()
+        }
+        (value, base_value) => base_value != Some(value),
+    });
+
+    serde_json::from_value(Value::Object(own)).unwrap_or_else(|_| constraints.clone())
+}
+
 /// Extract differential elements from an existing element tree.
 ///
 /// This is used when converting an existing IR (with full tree)
@@ -483,29 +716,32 @@ pub fn extract_differential(root: &ElementNode) -> Vec<DifferentialElement> {
 fn collect_modified_elements(
     element: &ElementNode,
     result: &mut Vec<DifferentialElement>,
-    slice_name: Option<&str>,
+    slice: Option<&SliceNode>,
 ) {
+    let is_constraining_slice_root =
+        slice.is_some_and(|s| s.slice_is_constraining && std::ptr::eq(&s.element, element));
+
     // Include if modified or has constraints
-    if element.source.is_modified() {
+    if element.source.is_modified() || is_constraining_slice_root {
         let mut diff = DifferentialElement::from_element_node(element);
-        diff.slice_name = slice_name.map(String::from);
+        diff.slice_name = slice.map(|s| s.name.clone());
+        diff.slice_is_constraining = is_constraining_slice_root;
 
-        if let Some(slice_name) = slice_name {
-            if let Some((base_path, _)) = element.path.split_once(':') {
-                let child_suffix = element
-                    .path
-                    .split_once(':')
-                    .and_then(|(_, rest)| rest.split_once('.'))
-                    .map(|(_, child)| child);
+        if let Some(slice_name) = slice.map(|s| s.name.as_str()) {
+            if let Some((base_path, rest)) = element.path.split_once(':') {
+                let (_, child_suffix) = split_slice_suffix(rest);
 
                 diff.path = match child_suffix {
                     Some(child) => format!("{}.{}", base_path, child),
                     None => base_path.to_string(),
                 };
 
-                diff.element_id = Some(match child_suffix {
-                    Some(child) => format!("{}:{}.{}", base_path, slice_name, child),
-                    None => format!("{}:{}", base_path, slice_name),
+                // Keep the stored id unless it predates full slice ids
+                let stored_id = element.element_id.as_deref().filter(|id| id.contains(':'));
+                diff.element_id = Some(match (stored_id, child_suffix) {
+                    (Some(id), _) => id.to_string(),
+                    (None, Some(child)) => format!("{}:{}.{}", base_path, slice_name, child),
+                    (None, None) => format!("{}:{}", base_path, slice_name),
                 });
             }
         }
@@ -515,12 +751,12 @@ fn collect_modified_elements(
 
     // Process children
     for child in &element.children {
-        collect_modified_elements(child, result, slice_name);
+        collect_modified_elements(child, result, slice);
     }
 
     // Process slices
     for slice in element.slices.values() {
-        collect_modified_elements(&slice.element, result, Some(&slice.name));
+        collect_modified_elements(&slice.element, result, Some(slice));
     }
 }
 
@@ -547,6 +783,33 @@ mod tests {
         root
     }
 
+    #[test]
+    fn test_merge_records_constraint_origins() {
+        let mut base = create_base_tree();
+        base.children[0].constraints.short = Some("Name of the patient".to_string());
+
+        let mut diff = DifferentialElement::new("Patient.name".to_string());
+        diff.constraints.cardinality = Some(Cardinality::new(1, None));
+        diff.constraints.flags.must_support = true;
+
+        let merger = ElementTreeMerger::new();
+        let (merged, origins) = merger.merge_with_origins(base, &[diff]);
+        let name = merged.find_descendant("name").unwrap();
+
+        assert!(origins.is_from_differential(name.id, "cardinality"));
+        assert!(origins.is_from_differential(name.id, "flags.mustSupport"));
+        assert!(!origins.is_from_differential(name.id, "short"));
+
+        let fields: Vec<String> = constraint_fields(&name.constraints)
+            .into_iter()
+            .map(|(field, _)| field)
+            .collect();
+        assert_eq!(fields, vec!["cardinality", "short", "flags.mustSupport"]);
+
+        let family = merged.find_descendant("name.family").unwrap();
+        assert!(!origins.is_from_differential(family.id, "cardinality"));
+    }
+
     #[test]
     fn test_merge_cardinality_constraint() {
         let base = create_base_tree();
@@ -606,4 +869,123 @@ mod tests {
         assert_eq!(differential.len(), 1);
         assert_eq!(differential[0].path, "Patient.name");
     }
+
+    #[test]
+    fn test_merge_reslice_round_trip() {
+        let mut root = ElementNode::new("Patient".to_string());
+        root.source = ElementSource::Inherited;
+        let mut extension = ElementNode::new("Patient.extension".to_string());
+        extension.source = ElementSource::Inherited;
+        let mut value = ElementNode::new("Patient.extension.value[x]".to_string());
+        value.source = ElementSource::Inherited;
+        extension.add_child(value);
+        root.add_child(extension);
+
+        let mut foo = DifferentialElement::new("Patient.extension".to_string());
+        foo.element_id = Some("Patient.extension:foo".to_string());
+        foo.slice_name = Some("foo".to_string());
+        foo.constraints.cardinality = Some(Cardinality::new(0, Some(2)));
+
+        let mut bar = DifferentialElement::new("Patient.extension".to_string());
+        bar.element_id = Some("Patient.extension:foo/bar".to_string());
+        bar.slice_name = Some("foo/bar".to_string());
+        bar.slice_is_constraining = true;
+        bar.constraints.cardinality = Some(Cardinality::new(0, Some(1)));
+
+        let mut bar_value = DifferentialElement::new("Patient.extension.value[x]".to_string());
+        bar_value.element_id = Some("Patient.extension:foo/bar.value[x]".to_string());
+        bar_value.slice_name = Some("foo/bar".to_string());
+        bar_value.constraints.flags.must_support = true;
+
+        let merger = ElementTreeMerger::new();
+        let merged = merger.merge(root, &[foo, bar, bar_value]);
+
+        let extension = &merged.children[0];
+        assert_eq!(extension.slices.len(), 2);
+        let reslice = &extension.slices["foo/bar"];
+        assert!(reslice.is_reslice());
+        assert!(reslice.slice_is_constraining);
+        assert!(!extension.slices["foo"].slice_is_constraining);
+        assert_eq!(reslice.element.path, "Patient.extension:foo/bar");
+        assert_eq!(reslice.element.children.len(), 1);
+        assert_eq!(
+            reslice.element.children[0].path,
+            "Patient.extension:foo/bar.value[x]"
+        );
+        assert!(reslice.element.children[0].constraints.flags.must_support);
+
+        let differential = extract_differential(&merged);
+        let ids: Vec<_> = differential
+            .iter()
+            .map(|d| d.element_id.as_deref().unwrap_or_default())
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                "Patient.extension:foo",
+                "Patient.extension:foo/bar",
+                "Patient.extension:foo/bar.value[x]",
+            ]
+        );
+        assert!(differential[1].slice_is_constraining);
+        assert_eq!(differential[2].path, "Patient.extension.value[x]");
+    }
+
+    #[test]
+    fn test_snapshot_edit_yields_minimal_differential() {
+        let mut base = create_base_tree();
+        base.children[0].constraints.flags.is_summary = true;
+
+        // Hydrate a profile that already requires a name
+        let mut name_diff = DifferentialElement::new("Patient.name".to_string());
+        name_diff.constraints.cardinality = Some(Cardinality::new(1, None));
+        let mut tree = ElementTreeMerger::new().merge(base.clone(), &[name_diff]);
+
+        // Snapshot edits submit whole elements: family comes back with its
+        // base values unchanged, name gains mustSupport
+        let family = tree.find_descendant_mut("name.family").unwrap();
+        family.source = ElementSource::Modified;
+        let name = tree.find_descendant_mut("name").unwrap();
+        name.constraints.flags.must_support = true;
+
+        let mut differential = extract_differential(&tree);
+        minimize_differential(&mut differential, &base);
+
+        // Only name remains, carrying just what differs from the base
+        assert_eq!(differential.len(), 1);
+        let name = &differential[0];
+        assert_eq!(name.path, "Patient.name");
+        assert_eq!(name.constraints.cardinality, Some(Cardinality::new(1, None)));
+        assert!(name.constraints.flags.must_support);
+        assert!(!name.constraints.flags.is_summary);
+        assert!(name.constraints.types.is_empty());
+    }
+
+    #[test]
+    fn test_prune_inherited_text() {
+        let mut base = create_base_tree();
+        let name = base.find_descendant_mut("name").unwrap();
+        name.constraints.short = Some("A name associated with the patient".to_string());
+        name.constraints.definition = Some("A name associated with the individual.".to_string());
+        let family = name.find_descendant_mut("family").unwrap();
+        family.constraints.short = Some("Family name (often called 'Surname')".to_string());
+
+        let mut name_diff = DifferentialElement::new("Patient.name".to_string());
+        name_diff.constraints.short = Some("A name associated with the patient".to_string());
+        name_diff.constraints.definition = Some("The patient's legal name.".to_string());
+        let mut family_diff = DifferentialElement::new("Patient.name.family".to_string());
+        family_diff.constraints.short = Some("Family name (often called 'Surname')".to_string());
+
+        let mut differential = vec![name_diff, family_diff];
+        prune_inherited_text(&mut differential, &base);
+
+        // The unchanged short is dropped; the changed definition stays
+        assert_eq!(differential.len(), 1);
+        assert_eq!(differential[0].path, "Patient.name");
+        assert!(differential[0].constraints.short.is_none());
+        assert_eq!(
+            differential[0].constraints.definition.as_deref(),
+            Some("The patient's legal name.")
+        );
+    }
 }
@@ -0,0 +1,184 @@
+//! Prometheus metrics.
+//!
+//! Counts requests by route and status, and records request latency, export
+//! and validation durations, and base tree cache hits. Rendered in the
+//! OpenMetrics text format at `GET /metrics` when `Config::enable_metrics`
+//! is set.
+
+use std::sync::atomic::AtomicU64;
+use std::time::Duration;
+
+use prometheus_client::encoding::text::encode;
+use prometheus_client::encoding::EncodeLabelSet;
+use prometheus_client::metrics::counter::Counter;
+use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
+use prometheus_client::registry::Registry;
+
+use crate::base::BaseTreeCache;
+
+/// Content type of the rendered metrics.
+pub const METRICS_CONTENT_TYPE: &str =
+    "application/openmetrics-text; version=1.0.0; charset=utf-8";
+
+/// Route label for requests that matched no route.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RequestLabels {
+    method: String,
+    route: String,
+    status: u16,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct RouteLabels {
+    method: String,
+    route: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct FormatLabels {
+    format: String,
+}
+
+type HistogramFamily<L> = Family<L, Histogram, fn() -> Histogram>;
+
+/// Duration buckets from 1ms to ~16s.
+fn duration_histogram() -> Histogram {
+    Histogram::new(exponential_buckets(0.001, 2.0, 15))
+}
+
+/// Server metrics registry.
+pub struct Metrics {
+    registry: Registry,
+    requests: Family<RequestLabels, Counter>,
+    request_duration: HistogramFamily<RouteLabels>,
+    export_duration: HistogramFamily<FormatLabels>,
+    validation_duration: Histogram,
+    base_tree_cache_hits: Gauge,
+    base_tree_cache_misses: Gauge,
+    base_tree_cache_hit_ratio: Gauge<f64, AtomicU64>,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    /// Create a registry with all server metrics registered.
+    #[must_use]
+    pub fn new() -> Self {
+        let mut registry = Registry::with_prefix("niten");
+
+        let requests = Family::<RequestLabels, Counter>::default();
+        registry.register(
+            "http_requests",
+            "HTTP requests by method, route and status",
+            requests.clone(),
+        );
+        let request_duration =
+            HistogramFamily::<RouteLabels>::new_with_constructor(duration_histogram);
+        registry.register(
+            "http_request_duration_seconds",
+            "HTTP request latency by method and route",
+            request_duration.clone(),
+        );
+        let export_duration =
+            HistogramFamily::<FormatLabels>::new_with_constructor(duration_histogram);
+        registry.register(
+            "export_duration_seconds",
+            "Profile export generation time by format",
+            export_duration.clone(),
+        );
+        let validation_duration = duration_histogram();
+        registry.register(
+            "validation_duration_seconds",
+            "Profile validation time",
+            validation_duration.clone(),
+        );
+        let base_tree_cache_hits = Gauge::default();
+        registry.register(
+            "base_tree_cache_hits",
+            "Base definition lookups served from the cache",
+            base_tree_cache_hits.clone(),
+        );
+        let base_tree_cache_misses = Gauge::default();
+        registry.register(
+            "base_tree_cache_misses",
+            "Base definition lookups resolved through the canonical manager",
+            base_tree_cache_misses.clone(),
+        );
+        let base_tree_cache_hit_ratio = Gauge::<f64, AtomicU64>::default();
+        registry.register(
+            "base_tree_cache_hit_ratio",
+            "Share of base definition lookups served from the cache",
+            base_tree_cache_hit_ratio.clone(),
+        );
+
+        Self {
+            registry,
+            requests,
+            request_duration,
+            export_duration,
+            validation_duration,
+            base_tree_cache_hits,
+            base_tree_cache_misses,
+            base_tree_cache_hit_ratio,
+        }
+    }
+
+    /// Record a handled request. `route` is the matched route template, if any.
+    pub fn record_request(&self, method: &str, route: Option<&str>, status: u16, elapsed: Duration) {
+        let route = route.unwrap_or(UNMATCHED_ROUTE).to_string();
+        self.requests
+            .get_or_create(&RequestLabels {
+                method: method.to_string(),
+                route: route.clone(),
+                status,
+            })
+            .inc();
+        self.request_duration
+            .get_or_create(&RouteLabels {
+                method: method.to_string(),
+                route,
+            })
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record how long generating an export in `format` took.
+    pub fn observe_export(&self, format: &str, elapsed: Duration) {
+        self.export_duration
+            .get_or_create(&FormatLabels {
+                format: format.to_string(),
+            })
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Record how long a validation run took.
+    pub fn observe_validation(&self, elapsed: Duration) {
+        self.validation_duration.observe(elapsed.as_secs_f64());
+    }
+
+    /// Render all metrics, sampling the base tree cache counters first.
+    #[must_use]
+    pub fn render(&self, cache: &BaseTreeCache) -> String {
+        let (hits, misses) = cache.stats();
+        self.base_tree_cache_hits.set(hits as i64);
+        self.base_tree_cache_misses.set(misses as i64);
+        let lookups = hits + misses;
+        if lookups > 0 {
+            self.base_tree_cache_hit_ratio
+                .set(hits as f64 / lookups as f64);
+        }
+
+        let mut output = String::new();
+        if let Err(e) = encode(&mut output, &self.registry) {
+            tracing::warn!("Failed to encode metrics: {}", e);
+        }
+        output
+    }
+}
@@ -3,16 +3,22 @@
 //! This module provides operations for modifying element constraints:
 //! - Cardinality (min/max)
 //! - Type constraints
-//! - Flags (mustSupport, isModifier, isSummary)
-//! - Bindings (terminology)
+//! - Flags (mustSupport, isModifier, isSummary), including recursive mustSupport
+//! - Bindings (terminology), including R5 additional bindings
 //! - Text (short, definition, comment)
+//! - Default value and meaningWhenMissing
+//! - R5 mustHaveValue
+
+use std::collections::HashMap;
+use std::sync::Mutex;
 
 use serde_json::json;
 
 use crate::ir::{
-    Binding, BindingStrength, Cardinality, Change, ElementNode, NodeId,
-    ProfileDocument, TypeConstraint,
+    AdditionalBinding, Binding, BindingStrength, Cardinality, Change, ElementNode, ElementSource,
+    NodeId, ProfileDocument, TypeConstraint,
 };
+use crate::project::CanonicalUsage;
 
 use super::error::{OperationError, OperationResult};
 use super::traits::Operation;
@@ -218,6 +224,15 @@ impl Operation for AddTypeConstraint {
             }),
         )
     }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        match &self.profile {
+            Some(profile) if self.type_code == "Extension" => {
+                vec![(CanonicalUsage::Extension, profile.clone())]
+            }
+            _ => Vec::new(),
+        }
+    }
 }
 
 // =============================================================================
@@ -378,6 +393,117 @@ impl Operation for SetMustSupport {
     }
 }
 
+// =============================================================================
+// SetMustSupportRecursive
+// =============================================================================
+
+/// Set the mustSupport flag on an element and every descendant.
+///
+/// Descendants include children and slice elements at any depth.
+#[derive(Debug)]
+pub struct SetMustSupportRecursive {
+    /// Root element path.
+    pub path: String,
+    /// New value.
+    pub value: bool,
+    /// Previous flag and source per node (for undo), captured when applied.
+    prev_values: Mutex<HashMap<NodeId, (bool, ElementSource)>>,
+}
+
+impl SetMustSupportRecursive {
+    /// Create a new recursive set must support operation.
+    pub fn new(path: impl Into<String>, value: bool) -> Self {
+        Self {
+            path: path.into(),
+            value,
+            prev_values: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+/// Visit an element and all its children and slice elements.
+fn visit_subtree_mut(element: &mut ElementNode, f: &mut impl FnMut(&mut ElementNode)) {
+    f(element);
+    for child in &mut element.children {
+        visit_subtree_mut(child, f);
+    }
+    for slice in element.slices.values_mut() {
+        visit_subtree_mut(&mut slice.element, f);
+    }
+}
+
+impl Operation for SetMustSupportRecursive {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        if document.resource.find_element(&self.path).is_none() {
+            return Err(OperationError::element_not_found(&self.path));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let mut prev_values = self
+            .prev_values
+            .lock()
+            .map_err(|_| OperationError::internal("SetMustSupportRecursive state poisoned"))?;
+
+        prev_values.clear();
+        visit_subtree_mut(element, &mut |node| {
+            prev_values.insert(node.id, (node.constraints.flags.must_support, node.source));
+            node.constraints.flags.must_support = self.value;
+            // Slices and other added elements stay `Added`
+            if node.source == ElementSource::Inherited {
+                node.source = ElementSource::Modified;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let mut prev_values = self
+            .prev_values
+            .lock()
+            .map_err(|_| OperationError::internal("SetMustSupportRecursive state poisoned"))?;
+        if prev_values.is_empty() {
+            return Err(OperationError::CannotUndo);
+        }
+
+        visit_subtree_mut(element, &mut |node| {
+            if let Some((must_support, source)) = prev_values.remove(&node.id) {
+                node.constraints.flags.must_support = must_support;
+                node.source = source;
+            }
+        });
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        if self.value {
+            format!("Set mustSupport on {} and descendants", self.path)
+        } else {
+            format!("Clear mustSupport on {} and descendants", self.path)
+        }
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "constraints.flags.must_support",
+            None,
+            json!({ "value": self.value, "recursive": true }),
+        )
+    }
+}
+
 // =============================================================================
 // SetIsModifier
 // =============================================================================
@@ -603,6 +729,10 @@ impl Operation for SetBinding {
         if let Some(ref desc) = self.description {
             binding = binding.with_description(desc);
         }
+        // Additional bindings survive a change of the main binding
+        if let Some(prev) = element.constraints.binding.take() {
+            binding.additional = prev.additional;
+        }
 
         element.constraints.binding = Some(binding);
         element.source = crate::ir::ElementSource::Modified;
@@ -640,6 +770,175 @@ impl Operation for SetBinding {
             }),
         )
     }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        vec![(CanonicalUsage::ValueSet, self.value_set.clone())]
+    }
+}
+
+// =============================================================================
+// SetAdditionalBinding
+// =============================================================================
+
+/// Add an additional binding (R5 `binding.additional`) to an element's
+/// binding, replacing the entry with the same purpose and value set.
+#[derive(Debug)]
+pub struct SetAdditionalBinding {
+    /// Element path.
+    pub path: String,
+    /// Binding purpose code.
+    pub purpose: String,
+    /// Value set URL.
+    pub value_set: String,
+    /// Documentation (optional).
+    pub documentation: Option<String>,
+    /// Previous binding and source (for undo).
+    prev: Mutex<Option<(Binding, ElementSource)>>,
+}
+
+impl SetAdditionalBinding {
+    /// Create a new set additional binding operation.
+    pub fn new(
+        path: impl Into<String>,
+        purpose: impl Into<String>,
+        value_set: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            purpose: purpose.into(),
+            value_set: value_set.into(),
+            documentation: None,
+            prev: Mutex::new(None),
+        }
+    }
+
+    /// Add documentation.
+    pub fn with_documentation(mut self, documentation: impl Into<String>) -> Self {
+        self.documentation = Some(documentation.into());
+        self
+    }
+}
+
+impl Operation for SetAdditionalBinding {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let fhir_version = document.resource.fhir_version;
+        if !fhir_version.supports_additional_bindings() {
+            return Err(OperationError::UnsupportedInFhirVersion {
+                feature: "binding.additional".to_string(),
+                version: fhir_version.label().to_string(),
+            });
+        }
+
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        if element.constraints.binding.is_none() {
+            return Err(OperationError::NoBindingDefined {
+                path: self.path.clone(),
+            });
+        }
+
+        if !AdditionalBinding::PURPOSES.contains(&self.purpose.as_str()) {
+            return Err(OperationError::InvalidBindingPurpose {
+                purpose: self.purpose.clone(),
+            });
+        }
+
+        if !self.value_set.starts_with("http://") && !self.value_set.starts_with("https://") {
+            return Err(OperationError::InvalidValueSetUrl {
+                url: self.value_set.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let binding = element
+            .constraints
+            .binding
+            .as_mut()
+            .ok_or_else(|| OperationError::NoBindingDefined {
+                path: self.path.clone(),
+            })?;
+
+        let mut prev = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetAdditionalBinding state poisoned"))?;
+        *prev = Some((binding.clone(), element.source));
+
+        let mut additional = AdditionalBinding::new(&self.purpose, &self.value_set);
+        if let Some(ref doc) = self.documentation {
+            additional = additional.with_documentation(doc);
+        }
+
+        match binding
+            .additional
+            .iter_mut()
+            .find(|a| a.purpose == self.purpose && a.value_set == self.value_set)
+        {
+            Some(existing) => *existing = additional,
+            None => binding.additional.push(additional),
+        }
+        element.source = crate::ir::ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (binding, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetAdditionalBinding state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        element.constraints.binding = Some(binding);
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!(
+            "Add {} additional binding to {} on {}",
+            self.purpose, self.value_set, self.path
+        )
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self
+            .prev
+            .lock()
+            .ok()
+            .and_then(|prev| prev.as_ref().map(|(binding, _)| json!(binding.additional)));
+        Change::set(
+            NodeId::new(),
+            "constraints.binding.additional",
+            prev,
+            json!({
+                "purpose": self.purpose,
+                "valueSet": self.value_set,
+                "documentation": self.documentation
+            }),
+        )
+    }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        vec![(CanonicalUsage::ValueSet, self.value_set.clone())]
+    }
 }
 
 // =============================================================================
@@ -1065,6 +1364,376 @@ impl Operation for SetPatternValue {
     }
 }
 
+// =============================================================================
+// SetTypeConstraints
+// =============================================================================
+
+/// Replace the type constraints of an element.
+#[derive(Debug)]
+pub struct SetTypeConstraints {
+    /// Element path.
+    pub path: String,
+    /// New type constraints.
+    pub types: Vec<TypeConstraint>,
+    /// Previous type constraints and source (for undo).
+    prev: Mutex<Option<(Vec<TypeConstraint>, ElementSource)>>,
+}
+
+impl SetTypeConstraints {
+    /// Create a new set type constraints operation.
+    pub fn new(path: impl Into<String>, types: Vec<TypeConstraint>) -> Self {
+        Self {
+            path: path.into(),
+            types,
+            prev: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for SetTypeConstraints {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        if document.resource.find_element(&self.path).is_none() {
+            return Err(OperationError::element_not_found(&self.path));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let mut prev = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetTypeConstraints state poisoned"))?;
+        *prev = Some((element.constraints.types.clone(), element.source));
+        element.constraints.types = self.types.clone();
+        element.source = ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (types, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetTypeConstraints state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        element.constraints.types = types;
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        let codes: Vec<&str> = self.types.iter().map(|t| t.code.as_str()).collect();
+        format!("Set types of {} to {}", self.path, codes.join(" | "))
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self
+            .prev
+            .lock()
+            .ok()
+            .and_then(|prev| prev.as_ref().map(|(types, _)| json!(types)));
+        Change::set(NodeId::new(), "constraints.types", prev, json!(self.types))
+    }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        self.types
+            .iter()
+            .filter(|t| t.code == "Extension")
+            .flat_map(|t| &t.profile)
+            .map(|profile| (CanonicalUsage::Extension, profile.clone()))
+            .collect()
+    }
+}
+
+// =============================================================================
+// SetDefaultValue
+// =============================================================================
+
+/// Set the default value of an element.
+#[derive(Debug)]
+pub struct SetDefaultValue {
+    /// Element path.
+    pub path: String,
+    /// Default value (JSON).
+    pub value: serde_json::Value,
+    /// Previous default value and source (for undo).
+    prev: Mutex<Option<(Option<serde_json::Value>, ElementSource)>>,
+}
+
+impl SetDefaultValue {
+    /// Create a new set default value operation.
+    pub fn new(path: impl Into<String>, value: serde_json::Value) -> Self {
+        Self {
+            path: path.into(),
+            value,
+            prev: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for SetDefaultValue {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        if document.resource.find_element(&self.path).is_none() {
+            return Err(OperationError::element_not_found(&self.path));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let mut prev = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetDefaultValue state poisoned"))?;
+        *prev = Some((element.constraints.default_value.clone(), element.source));
+        element.constraints.default_value = Some(self.value.clone());
+        element.source = ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (value, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetDefaultValue state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        element.constraints.default_value = value;
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set default value on {}", self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self
+            .prev
+            .lock()
+            .ok()
+            .and_then(|prev| prev.as_ref().and_then(|(value, _)| value.clone()));
+        Change::set(
+            NodeId::new(),
+            "constraints.default_value",
+            prev,
+            self.value.clone(),
+        )
+    }
+}
+
+// =============================================================================
+// SetMeaningWhenMissing
+// =============================================================================
+
+/// Set the meaning of an element's absence (`meaningWhenMissing`).
+#[derive(Debug)]
+pub struct SetMeaningWhenMissing {
+    /// Element path.
+    pub path: String,
+    /// New meaning text.
+    pub text: String,
+    /// Previous value and source (for undo).
+    prev: Mutex<Option<(Option<String>, ElementSource)>>,
+}
+
+impl SetMeaningWhenMissing {
+    /// Create a new set meaningWhenMissing operation.
+    pub fn new(path: impl Into<String>, text: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            text: text.into(),
+            prev: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for SetMeaningWhenMissing {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        if document.resource.find_element(&self.path).is_none() {
+            return Err(OperationError::element_not_found(&self.path));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let mut prev = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetMeaningWhenMissing state poisoned"))?;
+        *prev = Some((
+            element.constraints.meaning_when_missing.clone(),
+            element.source,
+        ));
+        element.constraints.meaning_when_missing = Some(self.text.clone());
+        element.source = ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (text, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetMeaningWhenMissing state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        element.constraints.meaning_when_missing = text;
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set meaningWhenMissing on {}", self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self.prev.lock().ok().and_then(|prev| {
+            prev.as_ref()
+                .and_then(|(text, _)| text.as_ref().map(|t| json!(t)))
+        });
+        Change::set(
+            NodeId::new(),
+            "constraints.meaning_when_missing",
+            prev,
+            json!(self.text),
+        )
+    }
+}
+
+// =============================================================================
+// SetMustHaveValue
+// =============================================================================
+
+/// Require instances to carry a value rather than only extensions
+/// (`mustHaveValue`, R5+).
+#[derive(Debug)]
+pub struct SetMustHaveValue {
+    /// Element path.
+    pub path: String,
+    /// New mustHaveValue value.
+    pub value: bool,
+    /// Previous value and source (for undo).
+    prev: Mutex<Option<(bool, ElementSource)>>,
+}
+
+impl SetMustHaveValue {
+    /// Create a new set mustHaveValue operation.
+    pub fn new(path: impl Into<String>, value: bool) -> Self {
+        Self {
+            path: path.into(),
+            value,
+            prev: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for SetMustHaveValue {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let fhir_version = document.resource.fhir_version;
+        if !fhir_version.supports_value_constraints() {
+            return Err(OperationError::UnsupportedInFhirVersion {
+                feature: "mustHaveValue".to_string(),
+                version: fhir_version.label().to_string(),
+            });
+        }
+
+        if document.resource.find_element(&self.path).is_none() {
+            return Err(OperationError::element_not_found(&self.path));
+        }
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let mut prev = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetMustHaveValue state poisoned"))?;
+        *prev = Some((element.constraints.must_have_value, element.source));
+        element.constraints.must_have_value = self.value;
+        if element.source == ElementSource::Inherited {
+            element.source = ElementSource::Modified;
+        }
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (value, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetMustHaveValue state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        element.constraints.must_have_value = value;
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set mustHaveValue={} on {}", self.value, self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self
+            .prev
+            .lock()
+            .ok()
+            .and_then(|prev| prev.map(|(value, _)| json!(value)));
+        Change::set(
+            NodeId::new(),
+            "constraints.must_have_value",
+            prev,
+            json!(self.value),
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1131,6 +1800,46 @@ mod tests {
         assert!(element.constraints.flags.must_support);
     }
 
+    #[test]
+    fn test_set_must_support_recursive() {
+        let mut doc = create_test_document();
+
+        let mut identifier = crate::ir::ElementNode::new("Patient.identifier".to_string());
+        identifier.add_child(crate::ir::ElementNode::new(
+            "Patient.identifier.system".to_string(),
+        ));
+        let mut mrn = crate::ir::SliceNode::new("mrn");
+        mrn.element.path = "Patient.identifier".to_string();
+        mrn.element.source = ElementSource::Added;
+        mrn.element.constraints.flags.must_support = true;
+        identifier.slices.insert("mrn".to_string(), mrn);
+        doc.resource.root.add_child(identifier);
+
+        let op = SetMustSupportRecursive::new("Patient.identifier", true);
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let identifier = doc.resource.find_element("Patient.identifier").unwrap();
+        assert!(identifier
+            .descendants()
+            .all(|e| e.constraints.flags.must_support));
+        assert_eq!(identifier.children[0].source, ElementSource::Modified);
+        assert_eq!(
+            identifier.slices["mrn"].element.source,
+            ElementSource::Added
+        );
+
+        op.undo(&mut doc).unwrap();
+
+        let identifier = doc.resource.find_element("Patient.identifier").unwrap();
+        assert!(!identifier.constraints.flags.must_support);
+        assert!(!identifier.children[0].constraints.flags.must_support);
+        assert!(identifier.slices["mrn"].element.constraints.flags.must_support);
+
+        let op = SetMustSupportRecursive::new("Patient.invalid", true);
+        assert!(op.validate(&doc).is_err());
+    }
+
     #[test]
     fn test_set_binding() {
         let mut doc = create_test_document();
@@ -1147,4 +1856,152 @@ mod tests {
         assert_eq!(binding.strength, BindingStrength::Required);
         assert_eq!(binding.value_set, "http://example.org/ValueSet/names");
     }
+
+    #[test]
+    fn test_set_must_have_value() {
+        let mut doc = create_test_document();
+
+        let op = SetMustHaveValue::new("Patient.name", true);
+        // R4 has no mustHaveValue
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::UnsupportedInFhirVersion { .. })
+        ));
+
+        doc.resource.fhir_version = FhirVersion::R5;
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.name").unwrap();
+        assert!(element.constraints.must_have_value);
+        assert_eq!(element.source, ElementSource::Modified);
+
+        op.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.name").unwrap();
+        assert!(!element.constraints.must_have_value);
+    }
+
+    #[test]
+    fn test_set_additional_binding() {
+        let mut doc = create_test_document();
+
+        let op = SetAdditionalBinding::new(
+            "Patient.name",
+            "minimum",
+            "http://example.org/ValueSet/core-names",
+        );
+        // R4 has no binding.additional
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::UnsupportedInFhirVersion { .. })
+        ));
+
+        doc.resource.fhir_version = FhirVersion::R5;
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NoBindingDefined { .. })
+        ));
+
+        SetBinding::new(
+            "Patient.name",
+            "http://example.org/ValueSet/names",
+            BindingStrength::Extensible,
+        )
+        .apply(&mut doc)
+        .unwrap();
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let bad_purpose =
+            SetAdditionalBinding::new("Patient.name", "sometimes", "http://example.org/ValueSet/x");
+        assert!(matches!(
+            bad_purpose.validate(&doc),
+            Err(OperationError::InvalidBindingPurpose { .. })
+        ));
+
+        // Changing the main binding keeps additional bindings
+        SetBinding::new(
+            "Patient.name",
+            "http://example.org/ValueSet/other-names",
+            BindingStrength::Required,
+        )
+        .apply(&mut doc)
+        .unwrap();
+
+        let element = doc.resource.find_element("Patient.name").unwrap();
+        let binding = element.constraints.binding.as_ref().unwrap();
+        assert_eq!(
+            binding.additional,
+            vec![AdditionalBinding::new("minimum", "http://example.org/ValueSet/core-names")]
+        );
+    }
+
+    #[test]
+    fn test_element_value_operations_undo() {
+        let mut doc = create_test_document();
+        let name = doc.resource.find_element_mut("Patient.name").unwrap();
+        name.constraints.types = vec![TypeConstraint::simple("HumanName")];
+        name.constraints.default_value = Some(json!({ "text": "Anonymous" }));
+
+        let types = SetTypeConstraints::new("Patient.name", vec![TypeConstraint::simple("string")]);
+        let default_value = SetDefaultValue::new("Patient.name", json!({ "text": "Unknown" }));
+        let meaning = SetMeaningWhenMissing::new("Patient.name", "No name recorded");
+        types.apply(&mut doc).unwrap();
+        default_value.apply(&mut doc).unwrap();
+        meaning.apply(&mut doc).unwrap();
+        assert_eq!(
+            default_value.as_change().old_value,
+            Some(json!({ "text": "Anonymous" }))
+        );
+
+        meaning.undo(&mut doc).unwrap();
+        default_value.undo(&mut doc).unwrap();
+        types.undo(&mut doc).unwrap();
+
+        let element = doc.resource.find_element("Patient.name").unwrap();
+        assert_eq!(
+            element.constraints.types,
+            vec![TypeConstraint::simple("HumanName")]
+        );
+        assert_eq!(
+            element.constraints.default_value,
+            Some(json!({ "text": "Anonymous" }))
+        );
+        assert!(element.constraints.meaning_when_missing.is_none());
+        assert_eq!(element.source, ElementSource::Inherited);
+        assert!(matches!(
+            types.undo(&mut doc),
+            Err(OperationError::CannotUndo)
+        ));
+    }
+
+    #[test]
+    fn test_set_additional_binding_undo() {
+        let mut doc = create_test_document();
+        doc.resource.fhir_version = FhirVersion::R5;
+        SetBinding::new(
+            "Patient.name",
+            "http://example.org/ValueSet/names",
+            BindingStrength::Extensible,
+        )
+        .apply(&mut doc)
+        .unwrap();
+        let before = doc.resource.find_element("Patient.name").unwrap().clone();
+
+        let op = SetAdditionalBinding::new(
+            "Patient.name",
+            "minimum",
+            "http://example.org/ValueSet/core-names",
+        );
+        op.apply(&mut doc).unwrap();
+        assert_eq!(
+            op.as_change().old_value,
+            Some(json!(Vec::<AdditionalBinding>::new()))
+        );
+        op.undo(&mut doc).unwrap();
+
+        let element = doc.resource.find_element("Patient.name").unwrap();
+        assert_eq!(element.constraints.binding, before.constraints.binding);
+        assert_eq!(element.source, before.source);
+        assert!(matches!(op.undo(&mut doc), Err(OperationError::CannotUndo)));
+    }
 }
@@ -0,0 +1,769 @@
+//! Element structure operations.
+//!
+//! This module provides operations that change the shape of the element tree:
+//! - Remove an element added by this profile (with its subtree)
+//! - Prohibit an element (`0..0`), optionally clearing its subtree
+
+use std::sync::Mutex;
+
+use serde_json::json;
+
+use crate::ir::{
+    Cardinality, Change, ElementConstraints, ElementNode, ElementSource, NodeId, ProfileDocument,
+    ProfiledResource, SliceNode,
+};
+use crate::merge::DifferentialElement;
+
+use super::error::{OperationError, OperationResult};
+use super::traits::Operation;
+
+// =============================================================================
+// RemoveElement
+// =============================================================================
+
+/// Where an element sits in its parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Location {
+    /// Position among the parent's children.
+    Child(usize),
+    /// Position among the slices of the parent (the sliced element).
+    Slice(usize),
+}
+
+/// An element taken out of the tree, with its subtree.
+#[derive(Debug)]
+enum RemovedNode {
+    /// A child element, with its position.
+    Child(usize, ElementNode),
+    /// A slice, with its position among the slices.
+    Slice(usize, SliceNode),
+}
+
+/// State captured by `RemoveElement::apply` for undo.
+#[derive(Debug)]
+struct RemovedElement {
+    /// Path of the element the removed node was taken from.
+    parent_path: String,
+    /// The removed element and its subtree.
+    node: RemovedNode,
+    /// Differential entries removed with the element, with their positions.
+    differential: Vec<(usize, DifferentialElement)>,
+}
+
+/// Remove an element that was added by this profile.
+///
+/// Only elements with `source == Added` (e.g., a mistaken extension or
+/// slice child) and slices added by the profile (`Patient.identifier:mrn`)
+/// can be removed; inherited base elements are refused.
+#[derive(Debug)]
+pub struct RemoveElement {
+    /// Element path.
+    pub path: String,
+    /// Removed subtree (for undo), captured when applied.
+    removed: Mutex<Option<RemovedElement>>,
+}
+
+impl RemoveElement {
+    /// Create a new remove element operation.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            removed: Mutex::new(None),
+        }
+    }
+
+    /// Path of the parent element.
+    fn parent_path(&self) -> OperationResult<&str> {
+        self.path
+            .rsplit_once('.')
+            .map(|(parent, _)| parent)
+            .ok_or_else(|| OperationError::CannotRemoveElement {
+                path: self.path.clone(),
+                reason: "the root element cannot be removed".to_string(),
+            })
+    }
+
+    /// Find the element: among its parent's children or, for a `name:slice`
+    /// path, among the slices of the sliced element.
+    ///
+    /// Returns the path of the element holding it and its location there.
+    fn locate<'a>(&'a self, resource: &ProfiledResource) -> OperationResult<(&'a str, Location)> {
+        let parent_path = self.parent_path()?;
+        let segment = self.path.rsplit('.').next().unwrap_or(&self.path);
+        let child = resource.find_element(parent_path).and_then(|parent| {
+            parent
+                .children
+                .iter()
+                .position(|c| c.path == self.path || c.short_name() == segment)
+        });
+        if let Some(index) = child {
+            return Ok((parent_path, Location::Child(index)));
+        }
+
+        let slice = self
+            .path
+            .rsplit_once(':')
+            .filter(|(_, name)| !name.contains('.'))
+            .and_then(|(sliced_path, name)| {
+                let sliced = resource.find_element(sliced_path)?;
+                Some((sliced_path, sliced.slices.get_index_of(name)?))
+            });
+        match slice {
+            Some((sliced_path, index)) => Ok((sliced_path, Location::Slice(index))),
+            None => Err(OperationError::element_not_found(&self.path)),
+        }
+    }
+
+    /// Whether a differential entry belongs to the removed subtree.
+    ///
+    /// Slice entries carry the slice name in their element id only.
+    fn covers(&self, entry: &DifferentialElement) -> bool {
+        let within = |path: &str| {
+            path == self.path
+                || path
+                    .strip_prefix(self.path.as_str())
+                    .is_some_and(|rest| rest.starts_with('.') || rest.starts_with(':'))
+        };
+        within(&entry.path) || entry.element_id.as_deref().is_some_and(within)
+    }
+}
+
+impl Operation for RemoveElement {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let (parent_path, location) = self.locate(&document.resource)?;
+        let parent = document
+            .resource
+            .find_element(parent_path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let source = match location {
+            Location::Child(index) => parent.children[index].source,
+            Location::Slice(index) => parent.slices[index].source,
+        };
+
+        if source != ElementSource::Added {
+            return Err(OperationError::CannotRemoveElement {
+                path: self.path.clone(),
+                reason: "inherited base elements cannot be removed".to_string(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let (parent_path, location) = self.locate(&document.resource)?;
+        let parent = document
+            .resource
+            .find_element_mut(parent_path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let node = match location {
+            Location::Child(index) => RemovedNode::Child(index, parent.children.remove(index)),
+            Location::Slice(index) => {
+                let (_, slice) = parent
+                    .slices
+                    .shift_remove_index(index)
+                    .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+                RemovedNode::Slice(index, slice)
+            }
+        };
+
+        // Drop the subtree's differential entries so the removal persists
+        let mut differential = Vec::new();
+        let mut position = 0;
+        document.resource.differential.retain(|entry| {
+            let keep = !self.covers(entry);
+            if !keep {
+                differential.push((position, entry.clone()));
+            }
+            position += 1;
+            keep
+        });
+
+        *self
+            .removed
+            .lock()
+            .map_err(|_| OperationError::internal("RemoveElement state poisoned"))? =
+            Some(RemovedElement {
+                parent_path: parent_path.to_string(),
+                node,
+                differential,
+            });
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let removed = self
+            .removed
+            .lock()
+            .map_err(|_| OperationError::internal("RemoveElement state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+
+        let parent = document
+            .resource
+            .find_element_mut(&removed.parent_path)
+            .ok_or_else(|| OperationError::element_not_found(&removed.parent_path))?;
+        match removed.node {
+            RemovedNode::Child(index, node) => {
+                let index = index.min(parent.children.len());
+                parent.children.insert(index, node);
+            }
+            RemovedNode::Slice(index, slice) => {
+                let index = index.min(parent.slices.len());
+                parent.slices.shift_insert(index, slice.name.clone(), slice);
+            }
+        }
+
+        // Positions were recorded in ascending order against the original list
+        let differential = &mut document.resource.differential;
+        for (position, entry) in removed.differential {
+            let position = position.min(differential.len());
+            differential.insert(position, entry);
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Remove element {}", self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        let removed = self.removed.lock().ok();
+        match removed.as_deref().and_then(Option::as_ref).map(|r| &r.node) {
+            Some(RemovedNode::Child(_, node)) => Change::remove(node.id, "children", json!(node)),
+            Some(RemovedNode::Slice(_, slice)) => Change::remove(slice.id, "slices", json!(slice)),
+            None => Change::remove(NodeId::new(), "children", json!({ "path": self.path })),
+        }
+    }
+}
+
+// =============================================================================
+// ProhibitElement
+// =============================================================================
+
+/// State captured by `ProhibitElement::apply` for undo.
+#[derive(Debug)]
+struct ProhibitedElement {
+    /// The element and its subtree before prohibiting.
+    node: ElementNode,
+    /// Descendant differential entries dropped by the cascade, with their positions.
+    differential: Vec<(usize, DifferentialElement)>,
+}
+
+/// Prohibit an element by constraining it to `0..0`.
+///
+/// With `cascade`, constraints below the element are dropped as well, since
+/// they are meaningless once it is prohibited: elements and slices added by
+/// the profile are removed and modified descendants revert to inherited.
+///
+/// Prohibiting an element that is currently required (`min >= 1`) is refused
+/// unless acknowledged with [`ProhibitElement::allow_required`]. The element's
+/// own cardinality only reflects the base on hydrated documents; otherwise pass
+/// the base minimum with [`ProhibitElement::with_base_min`].
+#[derive(Debug)]
+pub struct ProhibitElement {
+    /// Element path.
+    pub path: String,
+    /// Whether to clear constraints on descendants.
+    pub cascade: bool,
+    /// Whether prohibiting a required element was acknowledged.
+    pub allow_required: bool,
+    /// Minimum cardinality of the element in the base definition, if known.
+    pub base_min: Option<u32>,
+    /// Prior subtree (for undo), captured when applied.
+    prohibited: Mutex<Option<ProhibitedElement>>,
+}
+
+impl ProhibitElement {
+    /// Create a new prohibit element operation.
+    pub fn new(path: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            cascade: false,
+            allow_required: false,
+            base_min: None,
+            prohibited: Mutex::new(None),
+        }
+    }
+
+    /// Also clear constraints on the element's descendants.
+    pub fn with_cascade(mut self) -> Self {
+        self.cascade = true;
+        self
+    }
+
+    /// Acknowledge prohibiting an element that is currently required.
+    pub fn allow_required(mut self) -> Self {
+        self.allow_required = true;
+        self
+    }
+
+    /// Set the element's minimum cardinality in the base definition.
+    pub fn with_base_min(mut self, min: u32) -> Self {
+        self.base_min = Some(min);
+        self
+    }
+
+    /// Whether a differential entry belongs to a descendant of the element.
+    fn covers_descendant(&self, entry: &DifferentialElement) -> bool {
+        let below = |path: &str| {
+            path.strip_prefix(self.path.as_str())
+                .is_some_and(|rest| rest.starts_with('.') || rest.starts_with(':'))
+        };
+        below(&entry.path) || entry.element_id.as_deref().is_some_and(below)
+    }
+}
+
+/// Drop profile constraints below `element`.
+///
+/// Remaining descendants keep their ids. With a hydrated base, `base` is the
+/// base element matching `element` and descendants get the base constraints
+/// back; without one, only descendants the profile modified are cleared, so
+/// inherited constraints stay in the tree.
+fn clear_descendants(element: &mut ElementNode, base: Option<&ElementNode>, has_base: bool) {
+    element.children.retain(|child| child.source != ElementSource::Added);
+    element
+        .slices
+        .retain(|_, slice| slice.element.source != ElementSource::Added);
+
+    for child in &mut element.children {
+        let base_child = base.and_then(|b| b.children.iter().find(|c| c.path == child.path));
+        reset_descendant(child, base_child, has_base);
+    }
+    for (name, slice) in &mut element.slices {
+        let base_slice = base.and_then(|b| b.slices.get(name)).map(|s| &s.element);
+        reset_descendant(&mut slice.element, base_slice, has_base);
+    }
+}
+
+/// Reset one descendant (see [`clear_descendants`]).
+fn reset_descendant(node: &mut ElementNode, base: Option<&ElementNode>, has_base: bool) {
+    match base {
+        Some(base) => node.constraints = base.constraints.clone(),
+        None if has_base || node.source != ElementSource::Inherited => {
+            node.constraints = ElementConstraints::default();
+        }
+        None => {}
+    }
+    node.source = ElementSource::Inherited;
+    clear_descendants(node, base, has_base);
+}
+
+/// Find an element of a hydrated base tree by path.
+fn find_base_element<'a>(root: &'a ElementNode, path: &str) -> Option<&'a ElementNode> {
+    if path == root.path {
+        return Some(root);
+    }
+    let relative = path
+        .strip_prefix(&root.path)
+        .and_then(|s| s.strip_prefix('.'))
+        .unwrap_or(path);
+    root.find_descendant(relative)
+}
+
+impl Operation for ProhibitElement {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let own_min = element.constraints.cardinality.as_ref().map(|c| c.min);
+        let min = own_min.max(self.base_min).unwrap_or(0);
+        if min > 0 && !self.allow_required {
+            return Err(OperationError::ProhibitRequiredElement {
+                path: self.path.clone(),
+                min,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let base = document.resource.hydrated_base.clone();
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let node = element.clone();
+
+        element.constraints.cardinality = Some(Cardinality::new(0, Some(0)));
+        element.source = ElementSource::Modified;
+
+        let mut differential = Vec::new();
+        if self.cascade {
+            let base_element = base
+                .as_deref()
+                .and_then(|root| find_base_element(root, &self.path));
+            clear_descendants(element, base_element, base.is_some());
+
+            let mut position = 0;
+            document.resource.differential.retain(|entry| {
+                let keep = !self.covers_descendant(entry);
+                if !keep {
+                    differential.push((position, entry.clone()));
+                }
+                position += 1;
+                keep
+            });
+        }
+
+        *self
+            .prohibited
+            .lock()
+            .map_err(|_| OperationError::internal("ProhibitElement state poisoned"))? =
+            Some(ProhibitedElement { node, differential });
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let prohibited = self
+            .prohibited
+            .lock()
+            .map_err(|_| OperationError::internal("ProhibitElement state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        *element = prohibited.node;
+
+        // Positions were recorded in ascending order against the original list
+        let differential = &mut document.resource.differential;
+        for (position, entry) in prohibited.differential {
+            let position = position.min(differential.len());
+            differential.insert(position, entry);
+        }
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        if self.cascade {
+            format!("Prohibit {} and clear its children", self.path)
+        } else {
+            format!("Prohibit {}", self.path)
+        }
+    }
+
+    fn as_change(&self) -> Change {
+        let prohibited = self.prohibited.lock().ok();
+        let (target_id, prev) = match prohibited.as_deref().and_then(Option::as_ref) {
+            Some(prohibited) => (
+                prohibited.node.id,
+                prohibited.node.constraints.cardinality.as_ref().map(|c| json!(c)),
+            ),
+            None => (NodeId::new(), None),
+        };
+        Change::set(
+            target_id,
+            "constraints.cardinality",
+            prev,
+            json!({ "min": 0, "max": 0, "cascade": self.cascade }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BaseDefinition, Cardinality, DocumentMetadata, FhirVersion, ProfiledResource};
+
+    fn create_test_document() -> ProfileDocument {
+        let metadata = DocumentMetadata::new(
+            "test-profile",
+            "http://example.org/fhir/StructureDefinition/test-profile",
+            "TestProfile",
+        );
+        let mut resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/test-profile",
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+
+        let name = ElementNode::new("Patient.name".to_string());
+        resource.root.add_child(name);
+        crate::operations::add_inline_extension(&mut resource);
+
+        ProfileDocument::new(metadata, resource)
+    }
+
+    #[test]
+    fn test_remove_added_element() {
+        let mut doc = create_test_document();
+        let differential_before = doc.resource.differential.len();
+        assert!(doc.resource.find_element("Patient.extension:birthPlace").is_some());
+
+        let op = RemoveElement::new("Patient.extension:birthPlace");
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        assert!(doc.resource.find_element("Patient.extension:birthPlace").is_none());
+        assert!(doc.resource.find_element("Patient.name").is_some());
+        assert!(
+            !doc.resource
+                .differential
+                .iter()
+                .any(|d| d.path.starts_with("Patient.extension:birthPlace"))
+        );
+
+        op.undo(&mut doc).unwrap();
+        let restored = doc
+            .resource
+            .find_element("Patient.extension:birthPlace")
+            .expect("element restored by undo");
+        assert_eq!(restored.children.len(), 1);
+        assert_eq!(doc.resource.differential.len(), differential_before);
+    }
+
+    #[test]
+    fn test_remove_added_slice() {
+        use crate::ir::SlicingDefinition;
+
+        let mut doc = create_test_document();
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        identifier.slicing = Some(SlicingDefinition::by_value("system"));
+        identifier.source = ElementSource::Modified;
+        for name in ["mrn", "ssn"] {
+            let mut slice = SliceNode::with_path(name, format!("Patient.identifier:{name}"))
+                .with_cardinality(Cardinality::new(0, Some(1)));
+            slice.element.source = ElementSource::Added;
+            let mut system = ElementNode::new(format!("Patient.identifier:{name}.system"));
+            system.source = ElementSource::Added;
+            slice.element.add_child(system);
+            identifier.add_slice(name.to_string(), slice);
+        }
+        doc.resource.root.add_child(identifier);
+        doc.resource.extract_differential();
+        let differential_before = doc.resource.differential.clone();
+
+        let op = RemoveElement::new("Patient.identifier:mrn");
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let slice_names = |doc: &ProfileDocument| -> Vec<String> {
+            let identifier = doc.resource.find_element("Patient.identifier").unwrap();
+            identifier.slices.keys().cloned().collect()
+        };
+        assert_eq!(slice_names(&doc), vec!["ssn"]);
+        assert!(doc
+            .resource
+            .find_element("Patient.identifier:mrn")
+            .is_none());
+        assert!(!doc.resource.differential.iter().any(|d| {
+            d.element_id
+                .as_deref()
+                .is_some_and(|id| id.starts_with("Patient.identifier:mrn"))
+        }));
+        assert_eq!(
+            doc.resource.differential.len(),
+            differential_before.len() - 2
+        );
+
+        op.undo(&mut doc).unwrap();
+        assert_eq!(slice_names(&doc), vec!["mrn", "ssn"]);
+        let ids = |diff: &[DifferentialElement]| -> Vec<Option<String>> {
+            diff.iter().map(|d| d.element_id.clone()).collect()
+        };
+        assert_eq!(ids(&doc.resource.differential), ids(&differential_before));
+
+        // Slices the profile did not add stay
+        doc.resource
+            .find_element_mut("Patient.identifier")
+            .unwrap()
+            .slices["ssn"]
+            .source = ElementSource::Inherited;
+        assert!(matches!(
+            RemoveElement::new("Patient.identifier:ssn").validate(&doc),
+            Err(OperationError::CannotRemoveElement { .. })
+        ));
+    }
+
+    #[test]
+    fn test_refuse_removing_inherited_element() {
+        let mut doc = create_test_document();
+
+        let op = RemoveElement::new("Patient.name");
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::CannotRemoveElement { .. })
+        ));
+        assert!(crate::operations::apply_operation(&mut doc, &op).is_err());
+        assert!(doc.resource.find_element("Patient.name").is_some());
+    }
+
+    #[tokio::test]
+    async fn test_prohibit_element_with_cascade() {
+        use crate::export::DifferentialGenerator;
+
+        let mut doc = create_test_document();
+        let mut modified = ElementNode::new("Patient.extension:birthPlace.id".to_string());
+        modified.source = ElementSource::Modified;
+        modified.constraints.short = Some("Place identifier".to_string());
+        modified.constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+        let modified_id = modified.id;
+        doc.resource
+            .find_element_mut("Patient.extension:birthPlace")
+            .unwrap()
+            .add_child(modified);
+        doc.resource.extract_differential();
+        let differential_before = doc.resource.differential.clone();
+
+        let op = ProhibitElement::new("Patient.extension:birthPlace").with_cascade();
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let element = doc
+            .resource
+            .find_element("Patient.extension:birthPlace")
+            .unwrap();
+        assert_eq!(
+            element.constraints.cardinality,
+            Some(Cardinality::new(0, Some(0)))
+        );
+        // The added value[x] is gone, the modified id keeps its id but not its constraints
+        assert_eq!(element.children.len(), 1);
+        let id = &element.children[0];
+        assert_eq!(id.id, modified_id);
+        assert_eq!(id.source, ElementSource::Inherited);
+        assert_eq!(id.constraints, ElementConstraints::default());
+
+        // Only the prohibited element reaches the exported differential
+        doc.resource.extract_differential();
+        let exported = DifferentialGenerator::new()
+            .generate(&doc.resource)
+            .await
+            .unwrap();
+        let exported: Vec<(&str, Option<u64>, Option<&str>)> = exported
+            .iter()
+            .map(|element| {
+                (
+                    element["path"].as_str().unwrap(),
+                    element["min"].as_u64(),
+                    element["max"].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            exported,
+            vec![
+                ("Patient", None, None),
+                ("Patient.extension", Some(0), Some("0")),
+            ]
+        );
+
+        op.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.extension:birthPlace").unwrap();
+        assert_eq!(element.constraints.cardinality, Some(Cardinality::new(0, Some(1))));
+        assert_eq!(element.children.len(), 2);
+        assert_eq!(element.children[1].source, ElementSource::Modified);
+        assert_eq!(
+            element.children[1].constraints.short.as_deref(),
+            Some("Place identifier")
+        );
+        doc.resource.extract_differential();
+        let paths = |diff: &[DifferentialElement]| -> Vec<String> {
+            diff.iter().map(|d| d.path.clone()).collect()
+        };
+        assert_eq!(paths(&doc.resource.differential), paths(&differential_before));
+    }
+
+    #[test]
+    fn test_prohibit_cascade_restores_base_constraints() {
+        let mut doc = create_test_document();
+
+        // Base: Patient.name with family and given, each with base constraints
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut base_name = ElementNode::new("Patient.name".to_string());
+        for (path, short) in [
+            ("Patient.name.family", "Family name"),
+            ("Patient.name.given", "Given names"),
+        ] {
+            let mut child = ElementNode::new(path.to_string());
+            child.source = ElementSource::Inherited;
+            child.constraints.short = Some(short.to_string());
+            child.constraints.cardinality = Some(Cardinality::new(0, None));
+            base_name.add_child(child);
+        }
+        base.add_child(base_name.clone());
+        doc.resource.hydrated_base = Some(std::sync::Arc::new(base));
+
+        // Hydrated profile tree: family constrained by the profile, given inherited
+        let name = doc.resource.find_element_mut("Patient.name").unwrap();
+        *name = base_name;
+        name.children[0].source = ElementSource::Modified;
+        name.children[0].constraints.short = Some("Surname".to_string());
+        name.children[0].constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+
+        ProhibitElement::new("Patient.name")
+            .with_cascade()
+            .apply(&mut doc)
+            .unwrap();
+
+        let name = doc.resource.find_element("Patient.name").unwrap();
+        let family = &name.children[0];
+        assert_eq!(family.source, ElementSource::Inherited);
+        assert_eq!(family.constraints.short.as_deref(), Some("Family name"));
+        assert_eq!(
+            family.constraints.cardinality,
+            Some(Cardinality::new(0, None))
+        );
+        assert_eq!(
+            name.children[1].constraints.short.as_deref(),
+            Some("Given names")
+        );
+    }
+
+    #[test]
+    fn test_prohibit_without_cascade_keeps_children() {
+        let mut doc = create_test_document();
+
+        let op = ProhibitElement::new("Patient.extension:birthPlace");
+        op.apply(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.extension:birthPlace").unwrap();
+        assert_eq!(element.constraints.cardinality, Some(Cardinality::new(0, Some(0))));
+        assert_eq!(element.children.len(), 1);
+    }
+
+    #[test]
+    fn test_prohibit_required_element_needs_acknowledgement() {
+        let mut doc = create_test_document();
+        doc.resource
+            .find_element_mut("Patient.name")
+            .unwrap()
+            .constraints
+            .cardinality = Some(Cardinality::new(1, None));
+
+        let op = ProhibitElement::new("Patient.name");
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::ProhibitRequiredElement { min: 1, .. })
+        ));
+        assert!(ProhibitElement::new("Patient.name").allow_required().validate(&doc).is_ok());
+    }
+
+    #[test]
+    fn test_prohibit_checks_base_min_on_unhydrated_documents() {
+        // Unhydrated: the tree only carries the profile's own constraints
+        let doc = create_test_document();
+        assert!(ProhibitElement::new("Patient.name").validate(&doc).is_ok());
+
+        let op = ProhibitElement::new("Patient.name").with_base_min(1);
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::ProhibitRequiredElement { min: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_refuse_removing_root() {
+        let doc = create_test_document();
+        assert!(RemoveElement::new("Patient").validate(&doc).is_err());
+    }
+}
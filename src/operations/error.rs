@@ -69,6 +69,10 @@ pub enum OperationError {
     #[error("Invalid discriminator path: {path}")]
     InvalidDiscriminatorPath { path: String },
 
+    /// Discriminator not found on the slicing.
+    #[error("Discriminator {discriminator} not found at {path}")]
+    DiscriminatorNotFound { path: String, discriminator: String },
+
     /// Extension not found.
     #[error("Extension not found: {url} at {path}")]
     ExtensionNotFound { path: String, url: String },
@@ -81,6 +85,14 @@ pub enum OperationError {
         reason: String,
     },
 
+    /// Element is not an inline extension slice.
+    #[error("Element is not an inline extension: {path}")]
+    NotInlineExtension { path: String },
+
+    /// Operation requires an Extension definition.
+    #[error("Resource {url} is not an Extension definition")]
+    NotAnExtension { url: String },
+
     /// Invariant key already exists.
     #[error("Invariant key already exists: {key}")]
     DuplicateInvariantKey { key: String },
@@ -93,6 +105,26 @@ pub enum OperationError {
     #[error("Invalid FHIRPath expression: {expression} - {reason}")]
     InvalidFhirPathExpression { expression: String, reason: String },
 
+    /// Element has no binding to extend.
+    #[error("No binding defined at {path}")]
+    NoBindingDefined { path: String },
+
+    /// Unknown additional binding purpose.
+    #[error("Invalid additional binding purpose: {purpose}")]
+    InvalidBindingPurpose { purpose: String },
+
+    /// Feature not available in the document's FHIR version.
+    #[error("{feature} is not supported in FHIR {version}")]
+    UnsupportedInFhirVersion { feature: String, version: String },
+
+    /// Example not found.
+    #[error("Example not found: {label} at {path}")]
+    ExampleNotFound { path: String, label: String },
+
+    /// Example label is empty.
+    #[error("Example label must not be empty")]
+    EmptyExampleLabel,
+
     /// Invalid fixed/pattern value type.
     #[error("Value type mismatch: expected {expected}, got {actual}")]
     ValueTypeMismatch { expected: String, actual: String },
@@ -101,6 +133,14 @@ pub enum OperationError {
     #[error("Document is read-only (status: {status})")]
     DocumentReadOnly { status: String },
 
+    /// Element cannot be removed.
+    #[error("Cannot remove element {path}: {reason}")]
+    CannotRemoveElement { path: String, reason: String },
+
+    /// Prohibiting a required element was not acknowledged.
+    #[error("Element {path} is required (min {min}); prohibiting it must be confirmed")]
+    ProhibitRequiredElement { path: String, min: u32 },
+
     /// Operation cannot be undone (no previous state).
     #[error("Operation cannot be undone: no previous state recorded")]
     CannotUndo,
@@ -0,0 +1,460 @@
+//! Example value operations for profile elements.
+//!
+//! This module provides operations for managing `ElementDefinition.example`
+//! entries, keyed by their label:
+//! - Set (add or replace) example
+//! - Remove example
+
+use std::sync::Mutex;
+
+use serde_json::{json, Value};
+
+use crate::ir::{Change, ElementNode, ElementSource, Example, NodeId, ProfileDocument};
+
+use super::error::{OperationError, OperationResult};
+use super::traits::Operation;
+
+// =============================================================================
+// SetExample
+// =============================================================================
+
+/// Add an example to an element, or replace the one with the same label.
+#[derive(Debug)]
+pub struct SetExample {
+    /// Element path.
+    pub path: String,
+    /// Example label.
+    pub label: String,
+    /// Example value (JSON).
+    pub value: Value,
+    /// FHIR type of the value, required when the element allows several types.
+    pub value_type: Option<String>,
+    /// Previous example with the same label and the element source (for undo).
+    prev: Mutex<Option<(Option<Example>, ElementSource)>>,
+}
+
+impl SetExample {
+    /// Create a new set example operation.
+    pub fn new(path: impl Into<String>, label: impl Into<String>, value: Value) -> Self {
+        Self {
+            path: path.into(),
+            label: label.into(),
+            value,
+            value_type: None,
+            prev: Mutex::new(None),
+        }
+    }
+
+    /// Set the FHIR type of the value.
+    pub fn with_value_type(mut self, value_type: impl Into<String>) -> Self {
+        self.value_type = Some(value_type.into());
+        self
+    }
+
+    /// The example this operation stores, with its resolved value type.
+    fn example(&self, element: &ElementNode) -> Example {
+        let example = Example::new(&self.label, self.value.clone());
+        let value_type = self.value_type.as_deref().map(type_suffix);
+        match value_type.or_else(|| single_type_suffix(element)) {
+            Some(value_type) => example.with_value_type(value_type),
+            None => example,
+        }
+    }
+}
+
+impl Operation for SetExample {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        if self.label.trim().is_empty() {
+            return Err(OperationError::EmptyExampleLabel);
+        }
+
+        check_example_shape(element, self.value_type.as_deref(), &self.value)
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let example = self.example(element);
+        let source = element.source;
+        let examples = &mut element.constraints.examples;
+        let existing = examples.iter_mut().find(|e| e.label == self.label);
+        *self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetExample state poisoned"))? =
+            Some((existing.as_deref().cloned(), source));
+        match existing {
+            Some(existing) => *existing = example,
+            None => examples.push(example),
+        }
+        element.source = ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (prev_example, source) = self
+            .prev
+            .lock()
+            .map_err(|_| OperationError::internal("SetExample state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        let examples = &mut element.constraints.examples;
+        match prev_example {
+            Some(prev) => {
+                if let Some(existing) = examples.iter_mut().find(|e| e.label == self.label) {
+                    *existing = prev;
+                }
+            }
+            None => examples.retain(|e| e.label != self.label),
+        }
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Set example '{}' on {}", self.label, self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        let prev = self.prev.lock().ok().and_then(|prev| {
+            prev.as_ref()
+                .and_then(|(example, _)| example.as_ref().map(|e| json!(e)))
+        });
+        Change::set(
+            NodeId::new(),
+            format!("constraints.examples.{}", self.label),
+            prev,
+            json!({
+                "label": self.label,
+                "value": self.value,
+                "valueType": self.value_type
+            }),
+        )
+    }
+}
+
+// =============================================================================
+// RemoveExample
+// =============================================================================
+
+/// Remove an example from an element by label.
+#[derive(Debug)]
+pub struct RemoveExample {
+    /// Element path.
+    pub path: String,
+    /// Label of the example to remove.
+    pub label: String,
+    /// Removed example, its position and the element source (for undo).
+    removed: Mutex<Option<(usize, Example, ElementSource)>>,
+}
+
+impl RemoveExample {
+    /// Create a new remove example operation.
+    pub fn new(path: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            label: label.into(),
+            removed: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for RemoveExample {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        if !element.constraints.examples.iter().any(|e| e.label == self.label) {
+            return Err(OperationError::ExampleNotFound {
+                path: self.path.clone(),
+                label: self.label.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let examples = &mut element.constraints.examples;
+        let index = examples
+            .iter()
+            .position(|e| e.label == self.label)
+            .ok_or_else(|| OperationError::ExampleNotFound {
+                path: self.path.clone(),
+                label: self.label.clone(),
+            })?;
+        let example = examples.remove(index);
+        *self
+            .removed
+            .lock()
+            .map_err(|_| OperationError::internal("RemoveExample state poisoned"))? =
+            Some((index, example, element.source));
+        element.source = ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let (index, example, source) = self
+            .removed
+            .lock()
+            .map_err(|_| OperationError::internal("RemoveExample state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+        let examples = &mut element.constraints.examples;
+        examples.insert(index.min(examples.len()), example);
+        element.source = source;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Remove example '{}' from {}", self.label, self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        Change::remove(
+            NodeId::new(),
+            "constraints.examples",
+            json!({ "label": self.label }),
+        )
+    }
+}
+
+/// The `value[x]` suffix for an element with exactly one declared type.
+fn single_type_suffix(element: &ElementNode) -> Option<String> {
+    match element.constraints.types.as_slice() {
+        [single] => Some(type_suffix(&single.code)),
+        _ => None,
+    }
+}
+
+/// Convert a FHIR type code to its `value[x]` suffix (`dateTime` -> `DateTime`).
+fn type_suffix(code: &str) -> String {
+    let mut chars = code.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Check an example value's JSON shape against the element's declared types.
+///
+/// Elements without declared types (e.g. unhydrated backbone paths) accept
+/// any value. With several declared types the check needs `value_type`.
+fn check_example_shape(
+    element: &ElementNode,
+    value_type: Option<&str>,
+    value: &Value,
+) -> OperationResult<()> {
+    let types = &element.constraints.types;
+    if types.is_empty() {
+        return Ok(());
+    }
+
+    let code = match value_type {
+        Some(value_type) => types
+            .iter()
+            .map(|t| t.code.as_str())
+            .find(|code| type_suffix(code) == type_suffix(value_type))
+            .ok_or_else(|| OperationError::TypeNotFound {
+                type_code: value_type.to_string(),
+            })?,
+        None => match types.as_slice() {
+            [single] => single.code.as_str(),
+            _ => return Ok(()),
+        },
+    };
+
+    let Some(expected) = expected_json_kind(code) else {
+        return Ok(());
+    };
+    let actual = json_kind(value);
+    if expected != actual {
+        return Err(OperationError::ValueTypeMismatch {
+            expected: format!("{} ({})", code, expected),
+            actual: actual.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// The JSON kind a FHIR type is represented as, when known.
+fn expected_json_kind(code: &str) -> Option<&'static str> {
+    match code {
+        "boolean" => Some("boolean"),
+        "integer" | "positiveInt" | "unsignedInt" => Some("integer"),
+        "decimal" => Some("number"),
+        "string" | "code" | "id" | "markdown" | "uri" | "url" | "canonical" | "oid" | "uuid"
+        | "date" | "dateTime" | "instant" | "time" | "base64Binary" | "integer64" => {
+            Some("string")
+        }
+        _ if code.starts_with(|c: char| c.is_ascii_uppercase()) => Some("object"),
+        _ => None,
+    }
+}
+
+/// The JSON kind of a value.
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Null => "null",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource, TypeConstraint};
+
+    fn create_test_document() -> ProfileDocument {
+        let metadata = DocumentMetadata::new(
+            "test-patient",
+            "http://example.org/fhir/StructureDefinition/TestPatient",
+            "TestPatient",
+        );
+        let resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/TestPatient",
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        let mut doc = ProfileDocument::new(metadata, resource);
+
+        let mut birth_date = ElementNode::new("Patient.birthDate".to_string());
+        birth_date.constraints.types = vec![TypeConstraint::simple("date")];
+        doc.resource.root.add_child(birth_date);
+
+        let mut deceased = ElementNode::new("Patient.deceased[x]".to_string());
+        deceased.constraints.types =
+            vec![TypeConstraint::simple("boolean"), TypeConstraint::simple("dateTime")];
+        doc.resource.root.add_child(deceased);
+
+        doc
+    }
+
+    #[test]
+    fn test_set_and_replace_example() {
+        let mut doc = create_test_document();
+
+        let op = SetExample::new("Patient.birthDate", "General", json!("1970-01-01"));
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert_eq!(element.constraints.examples.len(), 1);
+        assert_eq!(element.constraints.examples[0].value_type.as_deref(), Some("Date"));
+
+        // Same label replaces in place
+        let op = SetExample::new("Patient.birthDate", "General", json!("1980-02-02"));
+        op.apply(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert_eq!(element.constraints.examples.len(), 1);
+        assert_eq!(element.constraints.examples[0].value, json!("1980-02-02"));
+    }
+
+    #[test]
+    fn test_example_shape_mismatch() {
+        let doc = create_test_document();
+
+        let op = SetExample::new("Patient.birthDate", "General", json!(1970));
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::ValueTypeMismatch { .. })
+        ));
+
+        // Choice elements are checked against the named type
+        let op = SetExample::new("Patient.deceased[x]", "Flag", json!(true)).with_value_type("Boolean");
+        assert!(op.validate(&doc).is_ok());
+        let op = SetExample::new("Patient.deceased[x]", "Flag", json!(true)).with_value_type("Quantity");
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::TypeNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_remove_example() {
+        let mut doc = create_test_document();
+
+        SetExample::new("Patient.birthDate", "General", json!("1970-01-01"))
+            .apply(&mut doc)
+            .unwrap();
+
+        let missing = RemoveExample::new("Patient.birthDate", "Other");
+        assert!(matches!(
+            missing.validate(&doc),
+            Err(OperationError::ExampleNotFound { .. })
+        ));
+
+        let op = RemoveExample::new("Patient.birthDate", "General");
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert!(element.constraints.examples.is_empty());
+
+        op.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert_eq!(element.constraints.examples.len(), 1);
+        assert_eq!(element.constraints.examples[0].label, "General");
+    }
+
+    #[test]
+    fn test_set_example_undo() {
+        let mut doc = create_test_document();
+
+        let first = SetExample::new("Patient.birthDate", "General", json!("1970-01-01"));
+        first.apply(&mut doc).unwrap();
+        let replace = SetExample::new("Patient.birthDate", "General", json!("1980-02-02"));
+        replace.apply(&mut doc).unwrap();
+
+        // Undoing a replacement restores the previous example
+        replace.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert_eq!(element.constraints.examples.len(), 1);
+        assert_eq!(element.constraints.examples[0].value, json!("1970-01-01"));
+
+        // Undoing an addition removes the example and restores the source
+        first.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.birthDate").unwrap();
+        assert!(element.constraints.examples.is_empty());
+        assert_eq!(element.source, ElementSource::Inherited);
+        assert!(matches!(
+            first.undo(&mut doc),
+            Err(OperationError::CannotUndo)
+        ));
+    }
+}
@@ -4,13 +4,21 @@
 //! - Add extension to an element
 //! - Configure extension cardinality and values
 //! - Remove extension
+//! - Set the context of an Extension definition
+//! - Replace an inline extension with a standalone Extension definition
+
+use std::collections::HashSet;
+use std::sync::Mutex;
 
 use serde_json::json;
 
 use crate::ir::{
-    Cardinality, Change, ElementNode, ElementSource, NodeId,
-    ProfileDocument, TypeConstraint,
+    BaseDefinition, Cardinality, Change, ElementNode, ElementSource, ExtensionContext,
+    ExtensionContextType, FhirVersion, FixedValue, NodeId, ProfileDocument, ProfiledResource,
+    StructureKind, TypeConstraint,
 };
+use crate::merge::DifferentialElement;
+use crate::project::CanonicalUsage;
 
 use super::error::{OperationError, OperationResult};
 use super::traits::Operation;
@@ -139,6 +147,10 @@ impl Operation for AddExtension {
             }),
         )
     }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        vec![(CanonicalUsage::Extension, self.extension_url.clone())]
+    }
 }
 
 // =============================================================================
@@ -504,6 +516,340 @@ impl Operation for SetExtensionFixedValue {
     }
 }
 
+// =============================================================================
+// SetExtensionContext
+// =============================================================================
+
+/// Replace the context list of an Extension definition.
+///
+/// Definitions based directly on the core Extension, or hydrated onto an
+/// `Extension` tree, are recognized from the document. One derived from a
+/// custom extension profile only is if the caller, who can follow the base
+/// chain, marks it with [`SetExtensionContext::derived_from_extension`].
+#[derive(Debug, Clone)]
+pub struct SetExtensionContext {
+    /// New context entries.
+    pub contexts: Vec<ExtensionContext>,
+    /// Whether the base chain was found to reach the core Extension.
+    pub derived_from_extension: bool,
+    /// Previous context entries (for undo).
+    prev_contexts: Vec<ExtensionContext>,
+}
+
+impl SetExtensionContext {
+    /// Create a new set extension context operation.
+    pub fn new(contexts: Vec<ExtensionContext>) -> Self {
+        Self {
+            contexts,
+            derived_from_extension: false,
+            prev_contexts: Vec::new(),
+        }
+    }
+
+    /// Mark the definition as derived (through its base chain) from Extension.
+    #[must_use]
+    pub fn derived_from_extension(mut self) -> Self {
+        self.derived_from_extension = true;
+        self
+    }
+
+    /// Record the context being replaced so the operation can be undone.
+    #[must_use]
+    pub fn with_previous(mut self, prev_contexts: Vec<ExtensionContext>) -> Self {
+        self.prev_contexts = prev_contexts;
+        self
+    }
+}
+
+impl Operation for SetExtensionContext {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let resource = &document.resource;
+        let extension_type = !resource.is_specialization() && resource.root.path == "Extension";
+        if !(resource.is_extension() || extension_type || self.derived_from_extension) {
+            return Err(OperationError::NotAnExtension {
+                url: resource.url.clone(),
+            });
+        }
+
+        for context in &self.contexts {
+            let expression = context.expression.trim();
+            let reason = if expression.is_empty() {
+                Some("context expression is empty")
+            } else if context.context_type == ExtensionContextType::Extension
+                && !expression.contains("://")
+            {
+                Some("extension context must be a canonical URL")
+            } else {
+                None
+            };
+
+            if let Some(reason) = reason {
+                return Err(OperationError::InvalidExtensionContext {
+                    url: resource.url.clone(),
+                    path: context.expression.clone(),
+                    reason: reason.to_string(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        document.resource.context = self.contexts.clone();
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        document.resource.context = self.prev_contexts.clone();
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        let expressions: Vec<&str> = self
+            .contexts
+            .iter()
+            .map(|c| c.expression.as_str())
+            .collect();
+        format!("Set extension context to [{}]", expressions.join(", "))
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "context",
+            Some(json!(self.prev_contexts)),
+            json!(self.contexts),
+        )
+    }
+}
+
+// =============================================================================
+// UseStandaloneExtension
+// =============================================================================
+
+/// State captured by `UseStandaloneExtension::apply` for undo.
+#[derive(Debug)]
+struct ReplacedInlineExtension {
+    /// The inline extension element as it was.
+    element: ElementNode,
+    /// The differential as it was.
+    differential: Vec<DifferentialElement>,
+}
+
+/// Replace an inline extension slice with a reference to a standalone
+/// Extension definition.
+///
+/// The slice keeps its cardinality and documentation; its children (`url`,
+/// `value[x]`, nested extensions) are expected to live in the standalone
+/// definition (see [`standalone_extension_resource`]).
+#[derive(Debug)]
+pub struct UseStandaloneExtension {
+    /// Inline extension slice path (e.g., "Patient.extension:birthPlace").
+    pub path: String,
+    /// Canonical URL of the standalone Extension definition.
+    pub extension_url: String,
+    /// Replaced state (for undo), captured when applied.
+    replaced: Mutex<Option<ReplacedInlineExtension>>,
+}
+
+impl UseStandaloneExtension {
+    /// Create a new use standalone extension operation.
+    pub fn new(path: impl Into<String>, extension_url: impl Into<String>) -> Self {
+        Self {
+            path: path.into(),
+            extension_url: extension_url.into(),
+            replaced: Mutex::new(None),
+        }
+    }
+}
+
+impl Operation for UseStandaloneExtension {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        if inline_extension_name(element).is_none() {
+            return Err(OperationError::NotInlineExtension {
+                path: self.path.clone(),
+            });
+        }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        let previous = element.clone();
+
+        element.children.clear();
+        element.constraints.types =
+            vec![TypeConstraint::with_profile("Extension", &self.extension_url)];
+        element.source = ElementSource::Modified;
+        let types = element.constraints.types.clone();
+
+        // Children now live in the standalone definition
+        let moved: HashSet<NodeId> = previous
+            .descendants()
+            .skip(1)
+            .map(|node| node.id)
+            .collect();
+        let differential = document.resource.differential.clone();
+        document
+            .resource
+            .differential
+            .retain(|entry| !moved.contains(&entry.id));
+        if let Some(entry) = document
+            .resource
+            .differential
+            .iter_mut()
+            .find(|entry| entry.id == previous.id)
+        {
+            entry.constraints.types = types;
+        }
+
+        *self
+            .replaced
+            .lock()
+            .map_err(|_| OperationError::internal("UseStandaloneExtension state poisoned"))? =
+            Some(ReplacedInlineExtension {
+                element: previous,
+                differential,
+            });
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let replaced = self
+            .replaced
+            .lock()
+            .map_err(|_| OperationError::internal("UseStandaloneExtension state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        *element = replaced.element;
+        document.resource.differential = replaced.differential;
+
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        format!("Use standalone extension {} at {}", self.extension_url, self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "constraints.types",
+            None,
+            json!([{ "code": "Extension", "profile": [self.extension_url] }]),
+        )
+    }
+
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        vec![(CanonicalUsage::Extension, self.extension_url.clone())]
+    }
+}
+
+/// Slice name of an inline extension element.
+///
+/// An inline extension is an `extension`/`modifierExtension` slice whose
+/// type does not reference an Extension definition.
+pub fn inline_extension_name(element: &ElementNode) -> Option<&str> {
+    let segment = element.path.rsplit('.').next()?;
+    let (name, slice) = segment.split_once(':')?;
+    if name != "extension" && name != "modifierExtension" {
+        return None;
+    }
+    let references_definition = element
+        .constraints
+        .types
+        .iter()
+        .any(|ty| ty.code == "Extension" && !ty.profile.is_empty());
+    (!references_definition && !slice.is_empty()).then_some(slice)
+}
+
+/// Build a standalone Extension definition from an inline extension slice.
+///
+/// The slice's documentation becomes the root `Extension` element, its
+/// children are rebased from the slice path onto `Extension`, and `url` is
+/// fixed to `extension_url`. `context_path` is where the inline extension
+/// was used (e.g., "Patient").
+pub fn standalone_extension_resource(
+    slice: &ElementNode,
+    extension_url: &str,
+    fhir_version: FhirVersion,
+    context_path: &str,
+) -> ProfiledResource {
+    let mut resource = ProfiledResource::new(
+        extension_url,
+        fhir_version,
+        BaseDefinition::resource("Extension"),
+    );
+    resource.kind = StructureKind::ComplexType;
+    resource.context = vec![ExtensionContext::new(
+        ExtensionContextType::Element,
+        context_path,
+    )];
+
+    let root = &mut resource.root;
+    root.constraints.short = slice.constraints.short.clone();
+    root.constraints.definition = slice.constraints.definition.clone();
+    root.constraints.comment = slice.constraints.comment.clone();
+    if root.constraints.short.is_some() || root.constraints.definition.is_some() {
+        root.source = ElementSource::Modified;
+    }
+
+    for child in &slice.children {
+        let mut child = child.clone();
+        rebase_element(&mut child, &slice.path, "Extension");
+        root.add_child(child);
+    }
+
+    match root.find_child_mut("url") {
+        Some(url) => {
+            url.constraints.fixed_value = Some(FixedValue::fixed(json!(extension_url)));
+            url.source = ElementSource::Modified;
+        }
+        None => {
+            let mut url = ElementNode::new("Extension.url".to_string());
+            url.constraints.fixed_value = Some(FixedValue::fixed(json!(extension_url)));
+            url.source = ElementSource::Added;
+            root.add_child(url);
+        }
+    }
+
+    resource.extract_differential();
+    resource
+}
+
+/// Move an element subtree from `from` to `to`, giving it new node IDs.
+fn rebase_element(element: &mut ElementNode, from: &str, to: &str) {
+    if let Some(rest) = element.path.strip_prefix(from) {
+        element.path = format!("{to}{rest}");
+    }
+    element.id = NodeId::new();
+    element.element_id = None;
+    let parent_id = element.id;
+    for child in &mut element.children {
+        rebase_element(child, from, to);
+        child.parent_id = Some(parent_id);
+    }
+    for slice in element.slices.values_mut() {
+        rebase_element(&mut slice.element, from, to);
+    }
+}
+
 /// Extract a short slug from an extension URL for use in paths.
 fn extension_slug(url: &str) -> String {
     url.rsplit('/')
@@ -552,6 +898,158 @@ mod tests {
         assert!(element.children.iter().any(|c| c.path.contains("extension:namePrefix")));
     }
 
+    fn create_extension_document() -> ProfileDocument {
+        let metadata = DocumentMetadata::new(
+            "patient-nickname",
+            "http://example.org/fhir/StructureDefinition/patient-nickname",
+            "PatientNickname",
+        );
+        let resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/patient-nickname",
+            FhirVersion::R4,
+            BaseDefinition::resource("Extension"),
+        );
+        ProfileDocument::new(metadata, resource)
+    }
+
+    #[test]
+    fn test_set_extension_context() {
+        let mut doc = create_extension_document();
+        let contexts = vec![
+            ExtensionContext::new(ExtensionContextType::Element, "Patient.name"),
+            ExtensionContext::new(ExtensionContextType::Fhirpath, "Patient.contact.name"),
+        ];
+
+        let op = SetExtensionContext::new(contexts.clone());
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        assert_eq!(doc.resource.context, contexts);
+
+        op.undo(&mut doc).unwrap();
+        assert!(doc.resource.context.is_empty());
+    }
+
+    #[test]
+    fn test_set_extension_context_requires_extension() {
+        let doc = create_test_document();
+        let op = SetExtensionContext::new(vec![ExtensionContext::new(
+            ExtensionContextType::Element,
+            "Patient",
+        )]);
+
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NotAnExtension { .. })
+        ));
+    }
+
+    #[test]
+    fn test_set_extension_context_on_derived_extension() {
+        // Constrains a custom extension rather than the core Extension
+        let metadata = DocumentMetadata::new(
+            "strict-nickname",
+            "http://example.org/fhir/StructureDefinition/strict-nickname",
+            "StrictNickname",
+        );
+        let resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/strict-nickname",
+            FhirVersion::R4,
+            BaseDefinition::new("http://example.org/fhir/StructureDefinition/patient-nickname"),
+        );
+        let mut doc = ProfileDocument::new(metadata, resource);
+        let contexts = vec![ExtensionContext::new(
+            ExtensionContextType::Element,
+            "Patient",
+        )];
+
+        let op = SetExtensionContext::new(contexts.clone());
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NotAnExtension { .. })
+        ));
+
+        // Known from the base chain
+        let op = SetExtensionContext::new(contexts.clone()).derived_from_extension();
+        assert!(op.validate(&doc).is_ok());
+
+        // Known from the hydrated tree, whose root is the base's type
+        doc.resource.root = ElementNode::new("Extension".to_string());
+        let op = SetExtensionContext::new(contexts.clone());
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        assert_eq!(doc.resource.context, contexts);
+    }
+
+    #[test]
+    fn test_set_extension_context_rejects_empty_expression() {
+        let doc = create_extension_document();
+        let op = SetExtensionContext::new(vec![ExtensionContext::new(
+            ExtensionContextType::Element,
+            " ",
+        )]);
+
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::InvalidExtensionContext { .. })
+        ));
+    }
+
+    #[test]
+    fn test_standalone_extension_resource() {
+        let mut doc = create_test_document();
+        crate::operations::add_inline_extension(&mut doc.resource);
+        let slice = doc.resource.find_element("Patient.extension:birthPlace").unwrap();
+        assert_eq!(inline_extension_name(slice), Some("birthPlace"));
+
+        let url = "http://example.org/fhir/StructureDefinition/birthPlace";
+        let resource = standalone_extension_resource(slice, url, FhirVersion::R4, "Patient");
+
+        assert!(resource.is_extension());
+        assert_eq!(resource.kind, StructureKind::ComplexType);
+        assert_eq!(resource.context[0].expression, "Patient");
+        assert_eq!(resource.root.constraints.short.as_deref(), Some("Place of birth"));
+        let value = resource.find_element("Extension.value[x]").unwrap();
+        assert_eq!(value.constraints.types[0].code, "Address");
+        let url_element = resource.find_element("Extension.url").unwrap();
+        assert_eq!(
+            url_element.constraints.fixed_value,
+            Some(FixedValue::fixed(json!(url)))
+        );
+    }
+
+    #[test]
+    fn test_use_standalone_extension() {
+        let mut doc = create_test_document();
+        crate::operations::add_inline_extension(&mut doc.resource);
+        let url = "http://example.org/fhir/StructureDefinition/birthPlace";
+
+        let op = UseStandaloneExtension::new("Patient.extension:birthPlace", url);
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+
+        let slice = doc.resource.find_element("Patient.extension:birthPlace").unwrap();
+        assert!(slice.children.is_empty());
+        assert_eq!(slice.constraints.types[0].profile, vec![url.to_string()]);
+        assert_eq!(slice.constraints.cardinality, Some(Cardinality::new(0, Some(1))));
+        assert!(inline_extension_name(slice).is_none());
+        assert!(
+            !doc.resource
+                .differential
+                .iter()
+                .any(|d| d.path.starts_with("Patient.extension:birthPlace."))
+        );
+
+        // Already references a definition
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NotInlineExtension { .. })
+        ));
+
+        op.undo(&mut doc).unwrap();
+        let slice = doc.resource.find_element("Patient.extension:birthPlace").unwrap();
+        assert_eq!(slice.children.len(), 2);
+    }
+
     #[test]
     fn test_extension_slug() {
         assert_eq!(
@@ -14,10 +14,13 @@
 //! # Operation Types
 //!
 //! - **Constraint Operations**: Cardinality, types, flags, bindings, text
-//! - **Slicing Operations**: Create slicing, add/remove slices, discriminators
+//! - **Element Operations**: Remove elements added by the profile, prohibit elements
+//! - **Slicing Operations**: Create slicing, add/remove slices, discriminators and rules
 //! - **Extension Operations**: Add/remove/configure extensions
 //! - **Fixed/Pattern Operations**: Set fixed or pattern values
+//! - **Example Operations**: Set/remove example values by label
 //! - **Invariant Operations**: Add/update/remove FHIRPath invariants
+//! - **Resource Operations**: Profile-level flags such as `abstract`
 //!
 //! # Example
 //!
@@ -41,16 +44,22 @@
 //! ```
 
 mod constraint;
+mod element;
 mod error;
+mod example;
 mod extension;
 mod invariant;
+mod resource;
 mod slicing;
 mod traits;
 
 pub use constraint::*;
+pub use element::*;
 pub use error::{OperationError, OperationResult};
+pub use example::*;
 pub use extension::*;
 pub use invariant::*;
+pub use resource::*;
 pub use slicing::*;
 pub use traits::{Operation, OperationContext};
 
@@ -72,8 +81,9 @@ pub fn apply_operation<O: Operation>(
     // Apply the operation
     op.apply(doc)?;
 
-    // Mark document as modified
+    // Mark document as modified; the imported snapshot no longer describes it
     doc.mark_dirty();
+    doc.resource.imported_snapshot.clear();
 
     // Record in history
     let tracking_op = crate::ir::tracking::Operation::single(
@@ -87,7 +97,10 @@ pub fn apply_operation<O: Operation>(
 
 /// Apply multiple operations as a single batch.
 ///
-/// If any operation fails, all changes are rolled back.
+/// If any operation fails, all changes are rolled back: validation runs
+/// against the original document, so a later operation can still fail to
+/// apply once earlier ones changed it. The document is then restored from a
+/// snapshot taken before the first operation.
 pub fn apply_batch<O: Operation>(
     doc: &mut ProfileDocument,
     ops: &[O],
@@ -100,14 +113,19 @@ pub fn apply_batch<O: Operation>(
     // Collect changes for batch history
     let mut changes = Vec::with_capacity(ops.len());
 
-    // Apply all operations
+    // Apply all operations, restoring the snapshot on failure
+    let snapshot = doc.clone();
     for op in ops {
-        op.apply(doc)?;
+        if let Err(e) = op.apply(doc) {
+            *doc = snapshot;
+            return Err(e);
+        }
         changes.push(op.as_change());
     }
 
-    // Mark document as modified
+    // Mark document as modified; the imported snapshot no longer describes it
     doc.mark_dirty();
+    doc.resource.imported_snapshot.clear();
 
     // Record as single batch operation
     if !changes.is_empty() {
@@ -123,3 +141,89 @@ pub fn apply_batch<O: Operation>(
 
     Ok(())
 }
+
+/// Add an inline `Patient.extension:birthPlace` extension (0..1, with an
+/// `Address` value) to a Patient profile, for tests.
+#[cfg(test)]
+pub(crate) fn add_inline_extension(resource: &mut crate::ir::ProfiledResource) {
+    use crate::ir::{Cardinality, ElementNode, ElementSource, TypeConstraint};
+
+    let mut slice = ElementNode::new("Patient.extension:birthPlace".to_string());
+    slice.source = ElementSource::Added;
+    slice.constraints.cardinality = Some(Cardinality::new(0, Some(1)));
+    slice.constraints.short = Some("Place of birth".to_string());
+    let mut value = ElementNode::new("Patient.extension:birthPlace.value[x]".to_string());
+    value.source = ElementSource::Added;
+    value
+        .constraints
+        .types
+        .push(TypeConstraint::simple("Address"));
+    slice.add_child(value);
+    resource.root.add_child(slice);
+    resource.extract_differential();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{
+        BaseDefinition, DocumentMetadata, ElementNode, ElementSource, FhirVersion,
+        ProfiledResource,
+    };
+
+    #[test]
+    fn test_batch_rolls_back_on_apply_failure() {
+        let url = "http://example.org/fhir/StructureDefinition/TestPatient";
+        let resource = ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        let mut doc = ProfileDocument::new(DocumentMetadata::new("test-patient", url, "TestPatient"), resource);
+
+        let mut contact = ElementNode::new("Patient.contact".to_string());
+        contact.source = ElementSource::Added;
+        let mut name = ElementNode::new("Patient.contact.name".to_string());
+        name.source = ElementSource::Added;
+        contact.add_child(name);
+        doc.resource.root.add_child(contact);
+        doc.resource.extract_differential();
+
+        let before = serde_json::to_value(&doc.resource).unwrap();
+
+        // Both validate against the original document, but removing the
+        // parent first leaves nothing for the second operation to remove
+        let ops = [
+            RemoveElement::new("Patient.contact"),
+            RemoveElement::new("Patient.contact.name"),
+        ];
+        let result = apply_batch(&mut doc, &ops);
+
+        assert!(matches!(result, Err(OperationError::ElementNotFound { .. })));
+        assert_eq!(serde_json::to_value(&doc.resource).unwrap(), before);
+        assert!(doc.resource.find_element("Patient.contact.name").is_some());
+        assert!(!doc.history.can_undo());
+        assert!(!doc.is_dirty());
+    }
+
+    #[test]
+    fn test_edit_drops_imported_snapshot() {
+        let url = "http://example.org/fhir/StructureDefinition/TestPatient";
+        let resource =
+            ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        let mut doc = ProfileDocument::new(
+            DocumentMetadata::new("test-patient", url, "TestPatient"),
+            resource,
+        );
+        doc.resource
+            .root
+            .add_child(ElementNode::new("Patient.name".to_string()));
+        doc.resource.imported_snapshot =
+            vec![serde_json::json!({ "id": "Patient.name", "path": "Patient.name", "min": 0 })];
+
+        // A failed edit keeps it
+        assert!(
+            apply_operation(&mut doc, &SetCardinality::new("Patient.name", 2, Some(1))).is_err()
+        );
+        assert_eq!(doc.resource.imported_snapshot.len(), 1);
+
+        apply_operation(&mut doc, &SetCardinality::new("Patient.name", 1, None)).unwrap();
+        assert!(doc.resource.imported_snapshot.is_empty());
+    }
+}
@@ -0,0 +1,104 @@
+//! Resource-level operations.
+//!
+//! This module provides operations on properties of the profiled
+//! StructureDefinition itself rather than on its elements:
+//! - Set the `abstract` flag
+
+use serde_json::json;
+
+use crate::ir::{Change, NodeId, ProfileDocument};
+
+use super::error::OperationResult;
+use super::traits::Operation;
+
+// =============================================================================
+// SetAbstract
+// =============================================================================
+
+/// Mark a profile as abstract (usable as a base, never instantiated) or not.
+#[derive(Debug, Clone)]
+pub struct SetAbstract {
+    /// New value of `StructureDefinition.abstract`.
+    pub is_abstract: bool,
+    /// Previous value (for undo).
+    prev_abstract: bool,
+}
+
+impl SetAbstract {
+    /// Create a new set abstract operation.
+    pub fn new(is_abstract: bool) -> Self {
+        Self {
+            is_abstract,
+            prev_abstract: false,
+        }
+    }
+
+    /// Record the value being replaced so the operation can be undone.
+    #[must_use]
+    pub fn with_previous(mut self, prev_abstract: bool) -> Self {
+        self.prev_abstract = prev_abstract;
+        self
+    }
+}
+
+impl Operation for SetAbstract {
+    fn validate(&self, _document: &ProfileDocument) -> OperationResult<()> {
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        document.resource.is_abstract = self.is_abstract;
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        document.resource.is_abstract = self.prev_abstract;
+        Ok(())
+    }
+
+    fn description(&self) -> String {
+        if self.is_abstract {
+            "Mark profile as abstract".to_string()
+        } else {
+            "Mark profile as not abstract".to_string()
+        }
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "abstract",
+            Some(json!(self.prev_abstract)),
+            json!(self.is_abstract),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+    #[test]
+    fn test_set_abstract_and_undo() {
+        let metadata = DocumentMetadata::new(
+            "base-patient",
+            "http://example.org/fhir/StructureDefinition/BasePatient",
+            "BasePatient",
+        );
+        let resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/BasePatient",
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        let mut doc = ProfileDocument::new(metadata, resource);
+
+        let op = SetAbstract::new(true).with_previous(doc.resource.is_abstract);
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        assert!(doc.resource.is_abstract);
+
+        op.undo(&mut doc).unwrap();
+        assert!(!doc.resource.is_abstract);
+    }
+}
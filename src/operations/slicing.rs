@@ -3,7 +3,9 @@
 //! This module provides operations for managing element slicing:
 //! - Create slicing on an element
 //! - Add/remove slices
-//! - Configure discriminators
+//! - Configure discriminators, rules and description on existing slicing
+
+use std::sync::Mutex;
 
 use serde_json::json;
 
@@ -439,7 +441,7 @@ impl Operation for RemoveSlice {
 // =============================================================================
 
 /// Add a discriminator to a sliced element.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct AddDiscriminator {
     /// Element path (must have slicing).
     pub path: String,
@@ -447,6 +449,8 @@ pub struct AddDiscriminator {
     pub discriminator_type: DiscriminatorType,
     /// Discriminator path.
     pub discriminator_path: String,
+    /// Slicing before the change (for undo), captured when applied.
+    prev_slicing: PrevSlicing,
 }
 
 impl AddDiscriminator {
@@ -460,6 +464,7 @@ impl AddDiscriminator {
             path: path.into(),
             discriminator_type,
             discriminator_path: discriminator_path.into(),
+            prev_slicing: PrevSlicing::default(),
         }
     }
 
@@ -501,6 +506,7 @@ impl Operation for AddDiscriminator {
                 path: self.path.clone(),
             }
         })?;
+        self.prev_slicing.store(slicing)?;
 
         slicing.discriminator.push(Discriminator::new(
             self.discriminator_type,
@@ -512,19 +518,7 @@ impl Operation for AddDiscriminator {
     }
 
     fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
-        let element = document
-            .resource
-            .find_element_mut(&self.path)
-            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
-
-        if let Some(ref mut slicing) = element.slicing {
-            slicing.discriminator.retain(|d| {
-                !(d.discriminator_type == self.discriminator_type
-                    && d.path == self.discriminator_path)
-            });
-        }
-
-        Ok(())
+        self.prev_slicing.restore(document, &self.path)
     }
 
     fn description(&self) -> String {
@@ -551,14 +545,14 @@ impl Operation for AddDiscriminator {
 // =============================================================================
 
 /// Set slicing rules on an element.
-#[derive(Debug, Clone)]
+#[derive(Debug)]
 pub struct SetSlicingRules {
     /// Element path.
     pub path: String,
     /// New slicing rules.
     pub rules: SlicingRules,
-    /// Previous rules (for undo).
-    prev_rules: Option<SlicingRules>,
+    /// Slicing before the change (for undo), captured when applied.
+    prev_slicing: PrevSlicing,
 }
 
 impl SetSlicingRules {
@@ -567,7 +561,7 @@ impl SetSlicingRules {
         Self {
             path: path.into(),
             rules,
-            prev_rules: None,
+            prev_slicing: PrevSlicing::default(),
         }
     }
 }
@@ -594,43 +588,263 @@ impl Operation for SetSlicingRules {
             .find_element_mut(&self.path)
             .ok_or_else(|| OperationError::element_not_found(&self.path))?;
 
-        if let Some(ref mut slicing) = element.slicing {
-            slicing.rules = self.rules;
+        let slicing = element.slicing.as_mut().ok_or_else(|| {
+            OperationError::NoSlicingDefined {
+                path: self.path.clone(),
+            }
+        })?;
+        self.prev_slicing.store(slicing)?;
+
+        slicing.rules = self.rules;
+        element.source = crate::ir::ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        self.prev_slicing.restore(document, &self.path)
+    }
+
+    fn description(&self) -> String {
+        format!("Set slicing rules to {} on {}", self.rules, self.path)
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "slicing.rules",
+            self.prev_slicing.rules().map(|r| json!(r.as_str())),
+            json!(self.rules.as_str()),
+        )
+    }
+}
+
+// =============================================================================
+// SetSlicingDescription
+// =============================================================================
+
+/// Set (or clear) the description of an element's slicing.
+#[derive(Debug)]
+pub struct SetSlicingDescription {
+    /// Element path.
+    pub path: String,
+    /// New description (`None` clears it).
+    pub description: Option<String>,
+    /// Slicing before the change (for undo), captured when applied.
+    prev_slicing: PrevSlicing,
+}
+
+impl SetSlicingDescription {
+    /// Create a new set slicing description operation.
+    pub fn new(path: impl Into<String>, description: Option<String>) -> Self {
+        Self {
+            path: path.into(),
+            description,
+            prev_slicing: PrevSlicing::default(),
+        }
+    }
+}
+
+impl Operation for SetSlicingDescription {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        if element.slicing.is_none() {
+            return Err(OperationError::NoSlicingDefined {
+                path: self.path.clone(),
+            });
         }
+
+        Ok(())
+    }
+
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let slicing = element.slicing.as_mut().ok_or_else(|| {
+            OperationError::NoSlicingDefined {
+                path: self.path.clone(),
+            }
+        })?;
+        self.prev_slicing.store(slicing)?;
+
+        slicing.description = self.description.clone();
         element.source = crate::ir::ElementSource::Modified;
 
         Ok(())
     }
 
     fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
-        if let Some(prev) = self.prev_rules {
-            let element = document
-                .resource
-                .find_element_mut(&self.path)
-                .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+        self.prev_slicing.restore(document, &self.path)
+    }
+
+    fn description(&self) -> String {
+        match &self.description {
+            Some(_) => format!("Set slicing description on {}", self.path),
+            None => format!("Clear slicing description on {}", self.path),
+        }
+    }
+
+    fn as_change(&self) -> Change {
+        Change::set(
+            NodeId::new(),
+            "slicing.description",
+            self.prev_slicing.description().map(|d| json!(d)),
+            json!(self.description),
+        )
+    }
+}
+
+// =============================================================================
+// RemoveDiscriminator
+// =============================================================================
+
+/// Remove a discriminator from a sliced element.
+#[derive(Debug)]
+pub struct RemoveDiscriminator {
+    /// Element path (must have slicing).
+    pub path: String,
+    /// Discriminator type.
+    pub discriminator_type: DiscriminatorType,
+    /// Discriminator path.
+    pub discriminator_path: String,
+    /// Slicing before the change (for undo), captured when applied.
+    prev_slicing: PrevSlicing,
+}
 
-            if let Some(ref mut slicing) = element.slicing {
-                slicing.rules = prev;
+impl RemoveDiscriminator {
+    /// Create a new remove discriminator operation.
+    pub fn new(
+        path: impl Into<String>,
+        discriminator_type: DiscriminatorType,
+        discriminator_path: impl Into<String>,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            discriminator_type,
+            discriminator_path: discriminator_path.into(),
+            prev_slicing: PrevSlicing::default(),
+        }
+    }
+
+    fn matches(&self, discriminator: &Discriminator) -> bool {
+        discriminator.discriminator_type == self.discriminator_type
+            && discriminator.path == self.discriminator_path
+    }
+}
+
+impl Operation for RemoveDiscriminator {
+    fn validate(&self, document: &ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let slicing = element.slicing.as_ref().ok_or_else(|| {
+            OperationError::NoSlicingDefined {
+                path: self.path.clone(),
             }
+        })?;
+
+        if !slicing.discriminator.iter().any(|d| self.matches(d)) {
+            return Err(OperationError::DiscriminatorNotFound {
+                path: self.path.clone(),
+                discriminator: format!("{}:{}", self.discriminator_type, self.discriminator_path),
+            });
         }
 
         Ok(())
     }
 
+    fn apply(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        let element = document
+            .resource
+            .find_element_mut(&self.path)
+            .ok_or_else(|| OperationError::element_not_found(&self.path))?;
+
+        let slicing = element.slicing.as_mut().ok_or_else(|| {
+            OperationError::NoSlicingDefined {
+                path: self.path.clone(),
+            }
+        })?;
+        self.prev_slicing.store(slicing)?;
+
+        slicing.discriminator.retain(|d| !self.matches(d));
+        element.source = crate::ir::ElementSource::Modified;
+
+        Ok(())
+    }
+
+    fn undo(&self, document: &mut ProfileDocument) -> OperationResult<()> {
+        self.prev_slicing.restore(document, &self.path)
+    }
+
     fn description(&self) -> String {
-        format!("Set slicing rules to {} on {}", self.rules, self.path)
+        format!(
+            "Remove {} discriminator '{}' from {}",
+            self.discriminator_type, self.discriminator_path, self.path
+        )
     }
 
     fn as_change(&self) -> Change {
-        Change::set(
+        Change::remove(
             NodeId::new(),
-            "slicing.rules",
-            self.prev_rules.map(|r| json!(r.as_str())),
-            json!(self.rules.as_str()),
+            "slicing.discriminator",
+            json!({
+                "type": self.discriminator_type.as_str(),
+                "path": self.discriminator_path
+            }),
         )
     }
 }
 
+/// Slicing definition captured when an operation is applied, so undo can
+/// restore it exactly.
+#[derive(Debug, Default)]
+struct PrevSlicing(Mutex<Option<SlicingDefinition>>);
+
+impl PrevSlicing {
+    fn store(&self, slicing: &SlicingDefinition) -> OperationResult<()> {
+        let mut prev = self
+            .0
+            .lock()
+            .map_err(|_| OperationError::internal("slicing undo state poisoned"))?;
+        *prev = Some(slicing.clone());
+        Ok(())
+    }
+
+    fn rules(&self) -> Option<SlicingRules> {
+        self.0.lock().ok()?.as_ref().map(|s| s.rules)
+    }
+
+    fn description(&self) -> Option<String> {
+        self.0.lock().ok()?.as_ref()?.description.clone()
+    }
+
+    fn restore(&self, document: &mut ProfileDocument, path: &str) -> OperationResult<()> {
+        let prev = self
+            .0
+            .lock()
+            .map_err(|_| OperationError::internal("slicing undo state poisoned"))?
+            .take()
+            .ok_or(OperationError::CannotUndo)?;
+
+        let element = document
+            .resource
+            .find_element_mut(path)
+            .ok_or_else(|| OperationError::element_not_found(path))?;
+        element.slicing = Some(prev);
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -721,4 +935,142 @@ mod tests {
             Err(OperationError::DuplicateSliceName { .. })
         ));
     }
+
+    #[test]
+    fn test_set_slicing_rules_undo_restores_definition() {
+        let mut doc = create_test_document();
+
+        let op = SetSlicingRules::new("Patient.identifier", SlicingRules::Closed);
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NoSlicingDefined { .. })
+        ));
+
+        CreateSlicing::by_value("Patient.identifier", "system")
+            .apply(&mut doc)
+            .unwrap();
+        let original = doc
+            .resource
+            .find_element("Patient.identifier")
+            .unwrap()
+            .slicing
+            .clone();
+
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.identifier").unwrap();
+        assert_eq!(element.slicing.as_ref().unwrap().rules, SlicingRules::Closed);
+
+        op.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.identifier").unwrap();
+        assert_eq!(element.slicing, original);
+        assert!(matches!(op.undo(&mut doc), Err(OperationError::CannotUndo)));
+    }
+
+    #[tokio::test]
+    async fn test_set_slicing_description_round_trips() {
+        use crate::export::{ExportConfig, StructureDefinitionExporter};
+        use crate::import::StructureDefinitionImporter;
+
+        let mut doc = create_test_document();
+
+        let op = SetSlicingDescription::new(
+            "Patient.identifier",
+            Some("Identifiers by assigning system".to_string()),
+        );
+        assert!(matches!(
+            op.validate(&doc),
+            Err(OperationError::NoSlicingDefined { .. })
+        ));
+
+        CreateSlicing::by_value("Patient.identifier", "system")
+            .with_description("Sliced by system")
+            .apply(&mut doc)
+            .unwrap();
+        assert!(op.validate(&doc).is_ok());
+        op.apply(&mut doc).unwrap();
+        let slicing = doc
+            .resource
+            .find_element("Patient.identifier")
+            .unwrap()
+            .slicing
+            .clone()
+            .unwrap();
+        assert_eq!(
+            slicing.description.as_deref(),
+            Some("Identifiers by assigning system")
+        );
+
+        // Export and re-import keep the description
+        doc.resource.extract_differential();
+        let config = ExportConfig::differential_only().skip_validation();
+        let json = StructureDefinitionExporter::with_config(config)
+            .export(&doc)
+            .await
+            .unwrap();
+        let reimported = StructureDefinitionImporter::new()
+            .import_json(&json)
+            .await
+            .unwrap();
+        let identifier = reimported
+            .resource
+            .differential
+            .iter()
+            .find(|diff| diff.path == "Patient.identifier")
+            .unwrap();
+        assert_eq!(
+            identifier.slicing.as_ref().unwrap().description.as_deref(),
+            Some("Identifiers by assigning system")
+        );
+
+        op.undo(&mut doc).unwrap();
+        let element = doc.resource.find_element("Patient.identifier").unwrap();
+        assert_eq!(
+            element.slicing.as_ref().unwrap().description.as_deref(),
+            Some("Sliced by system")
+        );
+    }
+
+    #[test]
+    fn test_add_and_remove_discriminator() {
+        let mut doc = create_test_document();
+        CreateSlicing::by_value("Patient.identifier", "system")
+            .apply(&mut doc)
+            .unwrap();
+
+        let add = AddDiscriminator::new("Patient.identifier", DiscriminatorType::Pattern, "type");
+        add.apply(&mut doc).unwrap();
+
+        let missing = RemoveDiscriminator::new("Patient.identifier", DiscriminatorType::Value, "use");
+        assert!(matches!(
+            missing.validate(&doc),
+            Err(OperationError::DiscriminatorNotFound { .. })
+        ));
+
+        let remove = RemoveDiscriminator::new("Patient.identifier", DiscriminatorType::Value, "system");
+        assert!(remove.validate(&doc).is_ok());
+        remove.apply(&mut doc).unwrap();
+        let slicing = doc
+            .resource
+            .find_element("Patient.identifier")
+            .unwrap()
+            .slicing
+            .clone()
+            .unwrap();
+        assert_eq!(
+            slicing.discriminator,
+            vec![Discriminator::new(DiscriminatorType::Pattern, "type")]
+        );
+
+        remove.undo(&mut doc).unwrap();
+        add.undo(&mut doc).unwrap();
+        let slicing = doc
+            .resource
+            .find_element("Patient.identifier")
+            .unwrap()
+            .slicing
+            .clone()
+            .unwrap();
+        assert_eq!(slicing.discriminator, vec![Discriminator::value("system")]);
+    }
 }
@@ -1,6 +1,7 @@
 //! Operation trait definition.
 
 use crate::ir::{Change, ProfileDocument};
+use crate::project::CanonicalUsage;
 
 use super::error::OperationResult;
 
@@ -100,6 +101,13 @@ pub trait Operation: Send + Sync {
 
     /// Convert this operation to a Change for history tracking.
     fn as_change(&self) -> Change;
+
+    /// Value sets and extension definitions this operation references.
+    ///
+    /// Callers record these in the project's recently used canonicals.
+    fn referenced_canonicals(&self) -> Vec<(CanonicalUsage, String)> {
+        Vec::new()
+    }
 }
 
 /// Macro for implementing common operation boilerplate.
@@ -0,0 +1,117 @@
+//! Workspace path sandboxing.
+//!
+//! Project, profile and resource ids arrive in request paths and bodies and are
+//! joined onto the workspace directory. Every id used as a path component must
+//! pass [`validate_path_id`] so it cannot escape the workspace.
+
+use std::path::{Component, Path, PathBuf};
+
+use thiserror::Error;
+
+/// An id that is not safe to use as a single path component.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid {kind} '{id}': {reason}")]
+pub struct InvalidPathId {
+    /// What the id identifies (e.g., "project id").
+    pub kind: &'static str,
+    /// The rejected id.
+    pub id: String,
+    /// Why it was rejected.
+    pub reason: &'static str,
+}
+
+/// Check that an id is a single, relative path component.
+///
+/// Rejects empty ids, path separators (`/` and `\`), `..` and `.`, drive or
+/// scheme prefixes (`C:`), and control characters.
+///
+/// # Errors
+///
+/// Returns [`InvalidPathId`] describing the first problem found.
+pub fn validate_path_id(kind: &'static str, id: &str) -> Result<(), InvalidPathId> {
+    let reject = |reason| {
+        Err(InvalidPathId {
+            kind,
+            id: id.to_string(),
+            reason,
+        })
+    };
+
+    if id.is_empty() {
+        return reject("must not be empty");
+    }
+    if id.contains(['/', '\\']) {
+        return reject("must not contain path separators");
+    }
+    if id.contains("..") {
+        return reject("must not contain '..'");
+    }
+    if id.contains(':') {
+        return reject("must not contain ':'");
+    }
+    if id.chars().any(char::is_control) {
+        return reject("must not contain control characters");
+    }
+
+    let mut components = Path::new(id).components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(_)), None) => Ok(()),
+        _ => reject("must be a single relative path component"),
+    }
+}
+
+/// Join a validated id onto a directory.
+///
+/// # Errors
+///
+/// Returns [`InvalidPathId`] if the id fails [`validate_path_id`].
+pub fn join_id(dir: &Path, kind: &'static str, id: &str) -> Result<PathBuf, InvalidPathId> {
+    validate_path_id(kind, id)?;
+    Ok(dir.join(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accepts_plain_ids() {
+        for id in ["my-project", "us-core-patient", "Patient_v2", "ig.example.1"] {
+            assert!(validate_path_id("project id", id).is_ok(), "{id} should be accepted");
+        }
+    }
+
+    #[test]
+    fn test_rejects_malicious_ids() {
+        for id in [
+            "",
+            ".",
+            "..",
+            "../../etc",
+            "../secret",
+            "foo/../../bar",
+            "nested/project",
+            "/etc/passwd",
+            "\\windows\\system32",
+            "..\\..\\boot.ini",
+            "C:",
+            "C:\\Windows",
+            "name\0.json",
+            "line\nbreak",
+        ] {
+            let err = validate_path_id("project id", id).unwrap_err();
+            assert_eq!(err.id, id);
+            assert_eq!(err.kind, "project id");
+        }
+    }
+
+    #[test]
+    fn test_join_id() {
+        let workspace = Path::new("/workspace");
+        assert_eq!(
+            join_id(workspace, "project id", "demo").unwrap(),
+            PathBuf::from("/workspace/demo")
+        );
+        assert!(join_id(workspace, "project id", "../demo").is_err());
+    }
+}
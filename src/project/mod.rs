@@ -10,6 +10,7 @@
 //! <workspace_dir>/
 //!   <project_id>/
 //!     project.json                   # Project configuration
+//!     recent-canonicals.json         # Recently referenced value sets/extensions
 //!     IR/
 //!       index.json                   # Resource index + metadata
 //!       resources/
@@ -35,6 +35,9 @@ pub struct Project {
     /// Project version.
     #[serde(default = "default_version")]
     pub version: String,
+    /// NPM package id the project publishes as (e.g., "hl7.fhir.us.core").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_id: Option<String>,
     /// Project status.
     #[serde(default)]
     pub status: ProjectStatus,
@@ -76,6 +79,7 @@ impl Project {
             description: None,
             publisher: None,
             version: default_version(),
+            package_id: None,
             status: ProjectStatus::Draft,
             created_at: now,
             modified_at: now,
@@ -105,9 +109,59 @@ impl Project {
     }
 }
 
-/// A package dependency.
+/// How a recently referenced canonical was used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CanonicalUsage {
+    /// Bound as an element's value set.
+    ValueSet,
+    /// Referenced as an extension definition.
+    Extension,
+}
+
+/// A canonical recently referenced from a profile.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+pub struct RecentCanonical {
+    /// Canonical URL.
+    pub url: String,
+    /// How it was referenced.
+    pub usage: CanonicalUsage,
+    /// When it was last referenced.
+    pub used_at: DateTime<Utc>,
+}
+
+/// Most-recent-first list of canonicals referenced in a project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RecentCanonicals {
+    /// Entries, most recent first.
+    #[serde(default)]
+    pub entries: Vec<RecentCanonical>,
+}
+
+impl RecentCanonicals {
+    /// Maximum number of entries kept.
+    pub const MAX_ENTRIES: usize = 25;
+
+    /// Move a canonical to the front, dropping the oldest entries past the cap.
+    pub fn record(&mut self, url: impl Into<String>, usage: CanonicalUsage) {
+        let url = url.into();
+        self.entries.retain(|e| !(e.url == url && e.usage == usage));
+        self.entries.insert(
+            0,
+            RecentCanonical {
+                url,
+                usage,
+                used_at: Utc::now(),
+            },
+        );
+        self.entries.truncate(Self::MAX_ENTRIES);
+    }
+}
+
+/// A package dependency.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct PackageDependency {
     /// Package name (e.g., "hl7.fhir.us.core").
     pub name: String,
@@ -129,6 +183,72 @@ impl PackageDependency {
     }
 }
 
+/// Whether a declared package dependency is used by any project resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyUsage {
+    /// Package name.
+    pub name: String,
+    /// Declared version constraint.
+    pub version: String,
+    /// Whether at least one referenced canonical resolves to this package.
+    pub used: bool,
+    /// Referenced canonical URLs provided by this package (sorted).
+    pub canonicals: Vec<String>,
+}
+
+/// External canonical references of a single project resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileReferences {
+    /// Profile ID.
+    pub id: String,
+    /// Canonical URL of the profile.
+    pub url: String,
+    /// Core resource type constrained by the profile (resource profiles only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resource_type: Option<String>,
+    /// Referenced canonical URLs defined outside the project (sorted).
+    pub references: Vec<String>,
+}
+
+/// A project resource that references canonicals provided by a package.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageDependent {
+    /// Project ID.
+    pub project_id: String,
+    /// Profile ID.
+    pub profile_id: String,
+    /// Canonical URL of the profile.
+    pub url: String,
+    /// Referenced canonical URLs provided by the package (sorted).
+    pub canonicals: Vec<String>,
+}
+
+/// Compatibility of a profile with a target server's capabilities.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileCompatibility {
+    /// Profile ID.
+    pub id: String,
+    /// Canonical URL of the profile.
+    pub url: String,
+    /// Resource type not listed in the capability statement, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unsupported_resource_type: Option<String>,
+    /// Referenced canonicals the server does not declare (sorted).
+    pub unsupported: Vec<String>,
+}
+
+impl ProfileCompatibility {
+    /// Whether the server supports everything the profile relies on.
+    #[must_use]
+    pub fn is_compatible(&self) -> bool {
+        self.unsupported_resource_type.is_none() && self.unsupported.is_empty()
+    }
+}
+
 /// A resource within a project.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -340,6 +460,30 @@ impl ProjectIndex {
         self.resources.values().find(|r| r.canonical_url == canonical_url)
     }
 
+    /// Find canonical URLs shared by more than one resource.
+    ///
+    /// Returns `(canonical_url, resource_ids)` pairs sorted by URL, with IDs sorted.
+    pub fn duplicate_canonical_urls(&self) -> Vec<(String, Vec<String>)> {
+        let mut by_url: HashMap<&str, Vec<String>> = HashMap::new();
+        for resource in self.resources.values() {
+            by_url
+                .entry(resource.canonical_url.as_str())
+                .or_default()
+                .push(resource.id.clone());
+        }
+
+        let mut duplicates: Vec<(String, Vec<String>)> = by_url
+            .into_iter()
+            .filter(|(_, ids)| ids.len() > 1)
+            .map(|(url, mut ids)| {
+                ids.sort();
+                (url.to_string(), ids)
+            })
+            .collect();
+        duplicates.sort_by(|a, b| a.0.cmp(&b.0));
+        duplicates
+    }
+
     /// Get all resources of a specific kind.
     pub fn resources_by_kind(&self, kind: ResourceKind) -> Vec<&ProjectResource> {
         self.resources.values().filter(|r| r.kind == kind).collect()
@@ -464,16 +608,32 @@ impl DependencyGraph {
     }
 
     /// Build the graph from a project index.
+    ///
+    /// Edges come from each resource's declared dependencies and from its base
+    /// definition when that base is another resource in the project.
     pub fn from_index(index: &ProjectIndex) -> Self {
         let mut graph = Self::new();
         for resource in index.resources.values() {
             for dep in &resource.dependencies {
                 graph.add_dependency(&resource.canonical_url, dep);
             }
+            if let Some(base) = resource.base.as_deref() {
+                if base != resource.canonical_url
+                    && !resource.dependencies.iter().any(|d| d == base)
+                    && index.find_by_canonical(base).is_some()
+                {
+                    graph.add_dependency(&resource.canonical_url, base);
+                }
+            }
         }
         graph
     }
 
+    /// Register a resource so it appears in `topological_order` even without edges.
+    pub fn add_node(&mut self, canonical_url: &str) {
+        self.forward.entry(canonical_url.to_string()).or_default();
+    }
+
     /// Add a dependency relationship.
     pub fn add_dependency(&mut self, from: &str, to: &str) {
         self.forward
@@ -532,8 +692,8 @@ impl DependencyGraph {
         let mut visited = std::collections::HashSet::new();
         let mut temp_visited = std::collections::HashSet::new();
 
-        // Collect all nodes from both forward and reverse maps
-        let mut all_nodes = std::collections::HashSet::new();
+        // Collect all nodes from both forward and reverse maps (sorted for stable output)
+        let mut all_nodes = std::collections::BTreeSet::new();
         for node in self.forward.keys() {
             all_nodes.insert(node.clone());
         }
@@ -582,6 +742,97 @@ impl DependencyGraph {
 
         Ok(())
     }
+
+    /// Get all dependency edges as `(from, to)` pairs, sorted.
+    pub fn edges(&self) -> Vec<(&str, &str)> {
+        let mut edges: Vec<(&str, &str)> = self
+            .forward
+            .iter()
+            .flat_map(|(from, deps)| deps.iter().map(move |to| (from.as_str(), to.as_str())))
+            .collect();
+        edges.sort_unstable();
+        edges.dedup();
+        edges
+    }
+
+    /// Find all dependency cycles.
+    ///
+    /// Each cycle is a strongly connected component with more than one member
+    /// (or a resource depending on itself). Members are sorted, as is the list.
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        let mut nodes = std::collections::BTreeSet::new();
+        for (from, deps) in &self.forward {
+            nodes.insert(from.as_str());
+            nodes.extend(deps.iter().map(String::as_str));
+        }
+
+        let mut tarjan = TarjanState::default();
+        for node in nodes {
+            if !tarjan.index.contains_key(node) {
+                self.strong_connect(node, &mut tarjan);
+            }
+        }
+
+        let mut cycles: Vec<Vec<String>> = tarjan
+            .components
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1
+                    || self.dependencies_of(&component[0]).contains(&component[0].as_str())
+            })
+            .map(|mut component| {
+                component.sort();
+                component
+            })
+            .collect();
+        cycles.sort();
+        cycles
+    }
+
+    fn strong_connect<'a>(&'a self, node: &'a str, state: &mut TarjanState<'a>) {
+        let index = state.next_index;
+        state.index.insert(node, index);
+        state.lowlink.insert(node, index);
+        state.next_index += 1;
+        state.stack.push(node);
+        state.on_stack.insert(node);
+
+        if let Some(deps) = self.forward.get(node) {
+            for dep in deps {
+                if !state.index.contains_key(dep.as_str()) {
+                    self.strong_connect(dep, state);
+                    let low = state.lowlink[node].min(state.lowlink[dep.as_str()]);
+                    state.lowlink.insert(node, low);
+                } else if state.on_stack.contains(dep.as_str()) {
+                    let low = state.lowlink[node].min(state.index[dep.as_str()]);
+                    state.lowlink.insert(node, low);
+                }
+            }
+        }
+
+        if state.lowlink[node] == index {
+            let mut component = Vec::new();
+            while let Some(member) = state.stack.pop() {
+                state.on_stack.remove(member);
+                component.push(member.to_string());
+                if member == node {
+                    break;
+                }
+            }
+            state.components.push(component);
+        }
+    }
+}
+
+/// Bookkeeping for Tarjan's strongly connected components algorithm.
+#[derive(Default)]
+struct TarjanState<'a> {
+    next_index: usize,
+    index: HashMap<&'a str, usize>,
+    lowlink: HashMap<&'a str, usize>,
+    stack: Vec<&'a str>,
+    on_stack: std::collections::HashSet<&'a str>,
+    components: Vec<Vec<String>>,
 }
 
 /// Error indicating a circular dependency was detected.
@@ -653,6 +904,26 @@ mod tests {
         assert_eq!(found.unwrap().name, "MyPatient");
     }
 
+    #[test]
+    fn test_duplicate_canonical_urls() {
+        let mut index = ProjectIndex::new();
+        let url = "http://example.org/fhir/StructureDefinition/MyPatient";
+
+        index.add_resource(ProjectResource::new("b", url, "MyPatient", ResourceKind::Profile));
+        index.add_resource(ProjectResource::new("a", url, "MyPatient", ResourceKind::Profile));
+        index.add_resource(ProjectResource::new(
+            "other",
+            "http://example.org/fhir/StructureDefinition/Other",
+            "Other",
+            ResourceKind::Profile,
+        ));
+
+        let duplicates = index.duplicate_canonical_urls();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].0, url);
+        assert_eq!(duplicates[0].1, vec!["a".to_string(), "b".to_string()]);
+    }
+
     #[test]
     fn test_dependency_graph() {
         let mut graph = DependencyGraph::new();
@@ -689,4 +960,58 @@ mod tests {
         assert!(c_pos < b_pos);
         assert!(b_pos < a_pos);
     }
+
+    #[test]
+    fn test_dependency_cycles() {
+        let mut graph = DependencyGraph::new();
+        graph.add_dependency("A", "B");
+        graph.add_dependency("B", "A");
+        graph.add_dependency("B", "C");
+        graph.add_dependency("D", "D");
+        graph.add_dependency("E", "C");
+
+        assert_eq!(
+            graph.cycles(),
+            vec![vec!["A".to_string(), "B".to_string()], vec!["D".to_string()]]
+        );
+        assert_eq!(graph.edges().len(), 5);
+    }
+
+    #[test]
+    fn test_dependency_graph_from_index_base() {
+        let mut index = ProjectIndex::new();
+        index.add_resource(ProjectResource::new(
+            "base",
+            "http://example.org/fhir/StructureDefinition/Base",
+            "Base",
+            ResourceKind::Profile,
+        ));
+        index.add_resource(
+            ProjectResource::new(
+                "derived",
+                "http://example.org/fhir/StructureDefinition/Derived",
+                "Derived",
+                ResourceKind::Profile,
+            )
+            .with_base("http://example.org/fhir/StructureDefinition/Base"),
+        );
+
+        let graph = DependencyGraph::from_index(&index);
+        assert_eq!(
+            graph.dependents_of("http://example.org/fhir/StructureDefinition/Base"),
+            vec!["http://example.org/fhir/StructureDefinition/Derived"]
+        );
+        assert!(graph.cycles().is_empty());
+    }
+
+    #[test]
+    fn test_topological_order_includes_isolated_nodes() {
+        let mut graph = DependencyGraph::new();
+        graph.add_node("Z");
+        graph.add_node("B");
+        graph.add_dependency("B", "A");
+
+        let order = graph.topological_order().unwrap();
+        assert_eq!(order, vec!["A", "B", "Z"]);
+    }
 }
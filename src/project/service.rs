@@ -2,18 +2,21 @@
 //!
 //! Provides operations for creating, loading, saving, and managing projects.
 
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::{Path, PathBuf};
 
 use thiserror::Error;
 use tokio::fs;
-use tokio::io::AsyncWriteExt;
 
+use crate::api::storage::atomic_write_all;
 use crate::ir::{
     BaseDefinition, DocumentMetadata, FhirVersion, ProfileDocument, ProfiledResource,
+    StructureKind,
 };
+use crate::paths::{validate_path_id, InvalidPathId};
 
 /// Convert a string to a URL-safe slug.
-fn slugify(s: &str) -> String {
+pub fn slugify(s: &str) -> String {
     s.chars()
         .map(|c| {
             if c.is_ascii_alphanumeric() {
@@ -66,6 +69,10 @@ pub enum ProjectError {
     #[error("Invalid canonical URL: {0}")]
     InvalidCanonicalUrl(String),
 
+    /// Canonical URL already used by another resource (url, existing resource ID).
+    #[error("Canonical URL '{0}' is already used by resource '{1}'")]
+    DuplicateCanonicalUrl(String, String),
+
     /// Dependency error.
     #[error("Dependency error: {0}")]
     DependencyError(String),
@@ -73,6 +80,10 @@ pub enum ProjectError {
     /// Circular dependency detected.
     #[error("Circular dependency: {0}")]
     CircularDependency(String),
+
+    /// Id cannot be used as a path component.
+    #[error(transparent)]
+    InvalidId(#[from] InvalidPathId),
 }
 
 pub type ProjectResult<T> = Result<T, ProjectError>;
@@ -98,28 +109,41 @@ impl ProjectService {
     }
 
     /// Get the path to a project directory.
-    pub fn project_path(&self, project_id: &str) -> PathBuf {
-        self.workspace_dir.join(project_id)
+    ///
+    /// Fails if the project id would escape the workspace.
+    pub fn project_path(&self, project_id: &str) -> ProjectResult<PathBuf> {
+        Ok(crate::paths::join_id(&self.workspace_dir, "project id", project_id)?)
     }
 
     /// Get the path to project.json.
-    fn project_config_path(&self, project_id: &str) -> PathBuf {
-        self.project_path(project_id).join("project.json")
+    fn project_config_path(&self, project_id: &str) -> ProjectResult<PathBuf> {
+        Ok(self.project_path(project_id)?.join("project.json"))
+    }
+
+    /// Get the path to recent-canonicals.json.
+    fn recent_canonicals_path(&self, project_id: &str) -> ProjectResult<PathBuf> {
+        Ok(self.project_path(project_id)?.join("recent-canonicals.json"))
     }
 
     /// Get the path to IR/index.json.
-    fn index_path(&self, project_id: &str) -> PathBuf {
-        self.project_path(project_id).join("IR").join("index.json")
+    fn index_path(&self, project_id: &str) -> ProjectResult<PathBuf> {
+        Ok(self.project_path(project_id)?.join("IR").join("index.json"))
     }
 
     /// Get the path to IR/resources/.
-    fn resources_dir(&self, project_id: &str) -> PathBuf {
-        self.project_path(project_id).join("IR").join("resources")
+    fn resources_dir(&self, project_id: &str) -> ProjectResult<PathBuf> {
+        Ok(self.project_path(project_id)?.join("IR").join("resources"))
+    }
+
+    /// Get the path to IR/resources/<resource_id>.json.
+    fn resource_path(&self, project_id: &str, resource_id: &str) -> ProjectResult<PathBuf> {
+        validate_path_id("resource id", resource_id)?;
+        Ok(self.resources_dir(project_id)?.join(format!("{}.json", resource_id)))
     }
 
     /// Get the path to SD/<resource_type>/.
-    fn sd_dir(&self, project_id: &str, resource_type: &str) -> PathBuf {
-        self.project_path(project_id).join("SD").join(resource_type)
+    fn sd_dir(&self, project_id: &str, resource_type: &str) -> ProjectResult<PathBuf> {
+        Ok(self.project_path(project_id)?.join("SD").join(resource_type))
     }
 
     // === Project Operations ===
@@ -161,7 +185,7 @@ impl ProjectService {
 
     /// Create a new project.
     pub async fn create_project(&self, request: CreateProjectRequest) -> ProjectResult<Project> {
-        let project_dir = self.project_path(&request.id);
+        let project_dir = self.project_path(&request.id)?;
 
         // Check if project already exists
         if project_dir.exists() {
@@ -196,7 +220,7 @@ impl ProjectService {
 
     /// Load a project by ID.
     pub async fn load_project(&self, project_id: &str) -> ProjectResult<Project> {
-        let project_dir = self.project_path(project_id);
+        let project_dir = self.project_path(project_id)?;
 
         if !project_dir.exists() {
             return Err(ProjectError::NotFound(project_id.to_string()));
@@ -238,9 +262,44 @@ impl ProjectService {
         Ok(project)
     }
 
+    /// Seed a project's package id, FHIR version and dependencies from an
+    /// ImplementationGuide.
+    ///
+    /// Dependencies are merged by package name; a guide's version replaces a
+    /// dependency already declared with a different one.
+    pub async fn apply_implementation_guide(
+        &self,
+        project_id: &str,
+        seed: &ImplementationGuideSeed,
+    ) -> ProjectResult<Project> {
+        let mut project = self.load_project(project_id).await?;
+
+        if let Some(package_id) = &seed.package_id {
+            project.package_id = Some(package_id.clone());
+        }
+        if let Some(fhir_version) = seed.fhir_version {
+            project.fhir_version = fhir_version;
+        }
+        for dependency in &seed.dependencies {
+            match project
+                .dependencies
+                .iter_mut()
+                .find(|existing| existing.name == dependency.name)
+            {
+                Some(existing) => existing.version = dependency.version.clone(),
+                None => project.dependencies.push(dependency.clone()),
+            }
+        }
+
+        project.touch();
+        self.save_project_config(project_id, &project).await?;
+
+        Ok(project)
+    }
+
     /// Save project configuration.
     async fn save_project_config(&self, project_id: &str, project: &Project) -> ProjectResult<()> {
-        let path = self.project_config_path(project_id);
+        let path = self.project_config_path(project_id)?;
         let content = serde_json::to_string_pretty(project)?;
         self.atomic_write(&path, &content).await?;
         Ok(())
@@ -248,7 +307,7 @@ impl ProjectService {
 
     /// Delete a project (removes from disk).
     pub async fn delete_project(&self, project_id: &str) -> ProjectResult<()> {
-        let project_dir = self.project_path(project_id);
+        let project_dir = self.project_path(project_id)?;
 
         if !project_dir.exists() {
             return Err(ProjectError::NotFound(project_id.to_string()));
@@ -258,11 +317,50 @@ impl ProjectService {
         Ok(())
     }
 
+    // === Recent Canonicals ===
+
+    /// Load the recently referenced canonicals, most recent first.
+    pub async fn load_recent_canonicals(&self, project_id: &str) -> ProjectResult<RecentCanonicals> {
+        let project_dir = self.project_path(project_id)?;
+        if !project_dir.exists() {
+            return Err(ProjectError::NotFound(project_id.to_string()));
+        }
+
+        let path = self.recent_canonicals_path(project_id)?;
+        if !path.exists() {
+            return Ok(RecentCanonicals::default());
+        }
+
+        let content = fs::read_to_string(&path).await?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Record canonicals referenced by an edit, in the order they were set.
+    pub async fn record_recent_canonicals(
+        &self,
+        project_id: &str,
+        references: &[(CanonicalUsage, String)],
+    ) -> ProjectResult<()> {
+        if references.is_empty() {
+            return Ok(());
+        }
+
+        let mut recent = self.load_recent_canonicals(project_id).await?;
+        for (usage, url) in references {
+            recent.record(url.clone(), *usage);
+        }
+
+        let path = self.recent_canonicals_path(project_id)?;
+        let content = serde_json::to_string_pretty(&recent)?;
+        self.atomic_write(&path, &content).await?;
+        Ok(())
+    }
+
     // === Resource Index Operations ===
 
     /// Load the project index.
     pub async fn load_index(&self, project_id: &str) -> ProjectResult<ProjectIndex> {
-        let path = self.index_path(project_id);
+        let path = self.index_path(project_id)?;
 
         if !path.exists() {
             return Ok(ProjectIndex::new());
@@ -275,7 +373,7 @@ impl ProjectService {
 
     /// Save the project index.
     async fn save_index(&self, project_id: &str, index: &ProjectIndex) -> ProjectResult<()> {
-        let path = self.index_path(project_id);
+        let path = self.index_path(project_id)?;
         fs::create_dir_all(path.parent().unwrap()).await?;
         let content = serde_json::to_string_pretty(index)?;
         self.atomic_write(&path, &content).await?;
@@ -314,6 +412,14 @@ impl ProjectService {
             format!("{}/{}/{}", project.canonical_base, sd_type, request.name)
         });
 
+        // Canonical URLs must be unique within a project
+        if let Some(existing) = index.find_by_canonical(&canonical_url) {
+            return Err(ProjectError::DuplicateCanonicalUrl(
+                canonical_url,
+                existing.id.clone(),
+            ));
+        }
+
         // Create project resource
         let mut resource = ProjectResource::new(
             &resource_id,
@@ -389,10 +495,9 @@ impl ProjectService {
         resource_id: &str,
         doc: &ProfileDocument,
     ) -> ProjectResult<()> {
-        let dir = self.resources_dir(project_id);
-        fs::create_dir_all(&dir).await?;
+        let path = self.resource_path(project_id, resource_id)?;
+        fs::create_dir_all(self.resources_dir(project_id)?).await?;
 
-        let path = dir.join(format!("{}.json", resource_id));
         let content = serde_json::to_string_pretty(doc)?;
         self.atomic_write(&path, &content).await?;
 
@@ -407,7 +512,8 @@ impl ProjectService {
         resource_type: &str,
         content: &str,
     ) -> ProjectResult<PathBuf> {
-        let dir = self.sd_dir(project_id, resource_type);
+        validate_path_id("resource id", resource_id)?;
+        let dir = self.sd_dir(project_id, resource_type)?;
         fs::create_dir_all(&dir).await?;
 
         let path = dir.join(format!("{}.json", resource_id));
@@ -428,7 +534,7 @@ impl ProjectService {
         project_id: &str,
         resource_id: &str,
     ) -> ProjectResult<ProfileDocument> {
-        let path = self.resources_dir(project_id).join(format!("{}.json", resource_id));
+        let path = self.resource_path(project_id, resource_id)?;
 
         if !path.exists() {
             return Err(ProjectError::ResourceNotFound(resource_id.to_string()));
@@ -466,13 +572,14 @@ impl ProjectService {
             .ok_or_else(|| ProjectError::ResourceNotFound(resource_id.to_string()))?;
 
         // Remove IR file
-        let ir_path = self.resources_dir(project_id).join(format!("{}.json", resource_id));
+        let ir_path = self.resource_path(project_id, resource_id)?;
         if ir_path.exists() {
             fs::remove_file(&ir_path).await?;
         }
 
         // Remove SD file if exists
-        let sd_path = self.project_path(project_id)
+        let sd_path = self
+            .project_path(project_id)?
             .join("SD")
             .join(resource.kind.sd_subdir())
             .join(format!("{}.json", resource.name));
@@ -481,7 +588,8 @@ impl ProjectService {
         }
 
         // Remove FSH file if exists
-        let fsh_path = self.project_path(project_id)
+        let fsh_path = self
+            .project_path(project_id)?
             .join("FSH")
             .join(resource.kind.fsh_subdir())
             .join(format!("{}.fsh", resource.name));
@@ -495,6 +603,64 @@ impl ProjectService {
         Ok(resource)
     }
 
+    /// Update a resource's index entry after its id, name or URL changed.
+    ///
+    /// Re-keys the entry under `new_id`, moves a raw SD file saved under the
+    /// old id, and rewrites the `base` and declared dependencies of other
+    /// resources that pointed at the old canonical URL. Does nothing if the
+    /// resource is not in the index.
+    pub async fn rename_resource(
+        &self,
+        project_id: &str,
+        old_id: &str,
+        new_id: &str,
+        new_name: &str,
+        new_url: &str,
+    ) -> ProjectResult<()> {
+        validate_path_id("resource id", new_id)?;
+        let mut index = self.load_index(project_id).await?;
+        let Some(mut resource) = index.remove_resource(old_id) else {
+            return Ok(());
+        };
+        if new_id != old_id && index.get_resource(new_id).is_some() {
+            return Err(ProjectError::ResourceAlreadyExists(new_id.to_string()));
+        }
+
+        let old_url = std::mem::replace(&mut resource.canonical_url, new_url.to_string());
+        resource.id = new_id.to_string();
+        resource.name = new_name.to_string();
+        resource.ir_path = PathBuf::from(format!("IR/resources/{}.json", new_id));
+        if let Some(sd_path) = resource.sd_path.as_mut() {
+            let old_file = format!("{}.json", old_id);
+            let saved_under_old_id =
+                sd_path.file_name().and_then(|f| f.to_str()) == Some(old_file.as_str());
+            if new_id != old_id && saved_under_old_id {
+                let renamed = sd_path.with_file_name(format!("{}.json", new_id));
+                if sd_path.exists() {
+                    fs::rename(&*sd_path, &renamed).await?;
+                }
+                *sd_path = renamed;
+            }
+        }
+        resource.modified_at = chrono::Utc::now();
+
+        if old_url != new_url {
+            for other in index.resources.values_mut() {
+                if other.base.as_deref() == Some(old_url.as_str()) {
+                    other.base = Some(new_url.to_string());
+                }
+                for dep in &mut other.dependencies {
+                    if *dep == old_url {
+                        *dep = new_url.to_string();
+                    }
+                }
+            }
+        }
+
+        index.add_resource(resource);
+        self.save_index(project_id, &index).await
+    }
+
     /// List all resources in a project.
     pub async fn list_resources(&self, project_id: &str) -> ProjectResult<Vec<ProjectResource>> {
         let index = self.load_index(project_id).await?;
@@ -518,7 +684,7 @@ impl ProjectService {
 
     /// Build a file tree for the project explorer.
     pub async fn build_file_tree(&self, project_id: &str) -> ProjectResult<FileTreeNode> {
-        let project_dir = self.project_path(project_id);
+        let project_dir = self.project_path(project_id)?;
         let index = self.load_index(project_id).await?;
 
         // Root node uses IR as default root type
@@ -674,26 +840,373 @@ impl ProjectService {
         Ok(DependencyGraph::from_index(&index))
     }
 
+    // === Dependency Usage ===
+
+    /// Collect canonical URLs referenced by the project's resources that are
+    /// defined outside the project.
+    ///
+    /// Scans base definitions, binding value sets, and type profiles (including
+    /// extension URLs) and target profiles of every IR document. Version
+    /// suffixes (`|x.y.z`) are stripped.
+    pub async fn external_references(&self, project_id: &str) -> ProjectResult<BTreeSet<String>> {
+        let profiles = self.profile_references(project_id).await?;
+        Ok(profiles
+            .into_iter()
+            .flat_map(|profile| profile.references)
+            .collect())
+    }
+
+    /// Collect, per IR document, the canonical URLs it references that are
+    /// defined outside the project.
+    ///
+    /// Uses the same scan as [`Self::external_references`]; documents are
+    /// returned sorted by ID.
+    pub async fn profile_references(
+        &self,
+        project_id: &str,
+    ) -> ProjectResult<Vec<ProfileReferences>> {
+        let project = self.load_project(project_id).await?;
+        let index = self.load_index(project_id).await?;
+
+        let resources_dir = self.resources_dir(project_id)?;
+        if !resources_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut local_urls: BTreeSet<String> = index
+            .resources
+            .values()
+            .map(|r| r.canonical_url.clone())
+            .collect();
+        let mut documents = Vec::new();
+
+        let mut entries = fs::read_dir(&resources_dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().is_none_or(|e| e != "json") {
+                continue;
+            }
+            let content = fs::read_to_string(&path).await?;
+            match serde_json::from_str::<ProfileDocument>(&content) {
+                Ok(doc) => {
+                    local_urls.insert(doc.metadata.url.clone());
+                    documents.push(doc);
+                }
+                Err(e) => {
+                    tracing::warn!("Skipping unreadable IR document {}: {}", path.display(), e);
+                }
+            }
+        }
+        documents.sort_by(|a, b| a.metadata.id.cmp(&b.metadata.id));
+
+        let local_prefix = format!("{}/", project.canonical_base);
+        let profiles = documents
+            .iter()
+            .map(|doc| {
+                let mut references = BTreeSet::new();
+                collect_document_references(doc, &mut references);
+                references.retain(|url| !local_urls.contains(url) && !url.starts_with(&local_prefix));
+
+                let resource_type = (doc.resource.kind == StructureKind::Resource
+                    && doc.resource.base.is_core_resource())
+                .then(|| doc.resource.resource_type().to_string());
+
+                ProfileReferences {
+                    id: doc.metadata.id.clone(),
+                    url: doc.metadata.url.clone(),
+                    resource_type,
+                    references: references.into_iter().collect(),
+                }
+            })
+            .collect();
+
+        Ok(profiles)
+    }
+
+    /// Collect [`Self::profile_references`] for every project in the
+    /// workspace, sorted by project ID.
+    pub async fn workspace_references(
+        &self,
+    ) -> ProjectResult<Vec<(String, Vec<ProfileReferences>)>> {
+        let mut projects = self.list_projects().await?;
+        projects.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut references = Vec::with_capacity(projects.len());
+        for project in projects {
+            let profiles = self.profile_references(&project.id).await?;
+            references.push((project.id, profiles));
+        }
+        Ok(references)
+    }
+
     // === Helpers ===
 
     /// Write file atomically (write to temp, sync, rename).
     async fn atomic_write(&self, path: &Path, content: &str) -> ProjectResult<()> {
-        let temp_path = path.with_extension("tmp");
-
         // Ensure parent directory exists
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent).await?;
         }
 
-        // Write to temp file
-        let mut file = fs::File::create(&temp_path).await?;
-        file.write_all(content.as_bytes()).await?;
-        file.sync_all().await?;
+        atomic_write_all(&[(path.to_path_buf(), content.to_string())]).await?;
+        Ok(())
+    }
+}
 
-        // Rename to final path
-        fs::rename(&temp_path, path).await?;
+/// Collect canonical URLs referenced by a document's base and constraints.
+fn collect_document_references(doc: &ProfileDocument, references: &mut BTreeSet<String>) {
+    let mut add = |url: &str| {
+        let url = url.split('|').next().unwrap_or(url);
+        if url.contains("://") {
+            references.insert(url.to_string());
+        }
+    };
+
+    add(&doc.resource.base.url);
+
+    let constraints = doc
+        .resource
+        .differential
+        .iter()
+        .map(|element| &element.constraints)
+        .chain(doc.resource.root.descendants().map(|element| &element.constraints));
+    for constraints in constraints {
+        if let Some(binding) = &constraints.binding {
+            add(&binding.value_set);
+        }
+        for ty in &constraints.types {
+            for url in ty.profile.iter().chain(&ty.target_profile) {
+                add(url);
+            }
+        }
+    }
+}
 
-        Ok(())
+/// Determine which declared package dependencies are actually used.
+///
+/// `resolved` maps each referenced canonical URL to the name of the package
+/// that provides it. A dependency is used when at least one canonical resolves
+/// to it.
+pub fn compute_dependency_usage(
+    dependencies: &[PackageDependency],
+    resolved: &BTreeMap<String, String>,
+) -> Vec<DependencyUsage> {
+    dependencies
+        .iter()
+        .map(|dep| {
+            let canonicals: Vec<String> = resolved
+                .iter()
+                .filter(|(_, package)| **package == dep.name)
+                .map(|(url, _)| url.clone())
+                .collect();
+            DependencyUsage {
+                name: dep.name.clone(),
+                version: dep.version.clone(),
+                used: !canonicals.is_empty(),
+                canonicals,
+            }
+        })
+        .collect()
+}
+
+/// Find project resources that reference canonicals provided by a package.
+///
+/// `references` comes from [`ProjectService::workspace_references`];
+/// `resolved` maps each referenced canonical URL to the `(name, version)` of
+/// the package it resolves from. A `version` of `latest` matches any
+/// installed version of the package.
+pub fn find_package_dependents(
+    references: &[(String, Vec<ProfileReferences>)],
+    resolved: &BTreeMap<String, (String, String)>,
+    name: &str,
+    version: &str,
+) -> Vec<PackageDependent> {
+    let provided_by_package = |url: &String| {
+        resolved.get(url).is_some_and(|(package, package_version)| {
+            package == name && (version == "latest" || package_version == version)
+        })
+    };
+
+    references
+        .iter()
+        .flat_map(|(project_id, profiles)| {
+            profiles.iter().filter_map(move |profile| {
+                let canonicals: Vec<String> = profile
+                    .references
+                    .iter()
+                    .filter(|url| provided_by_package(url))
+                    .cloned()
+                    .collect();
+                (!canonicals.is_empty()).then(|| PackageDependent {
+                    project_id: project_id.clone(),
+                    profile_id: profile.id.clone(),
+                    url: profile.url.clone(),
+                    canonicals,
+                })
+            })
+        })
+        .collect()
+}
+
+/// Canonicals and resource types a FHIR server declares in its
+/// CapabilityStatement.
+#[derive(Debug, Clone, Default)]
+pub struct ServerCapabilities {
+    /// Resource types listed under `rest.resource.type`.
+    pub resource_types: BTreeSet<String>,
+    /// Declared canonicals (profiles, supported profiles, operations,
+    /// instantiated/imported statements, implementation guides).
+    pub canonicals: BTreeSet<String>,
+}
+
+impl ServerCapabilities {
+    /// Extract capabilities from a CapabilityStatement resource.
+    pub fn from_capability_statement(statement: &serde_json::Value) -> Self {
+        let mut capabilities = Self::default();
+        let mut add_canonical = |value: &serde_json::Value| {
+            if let Some(url) = value.as_str() {
+                let url = url.split('|').next().unwrap_or(url);
+                capabilities.canonicals.insert(url.to_string());
+            }
+        };
+        for field in ["instantiates", "imports", "implementationGuide"] {
+            each(statement.get(field)).for_each(&mut add_canonical);
+        }
+
+        let mut resource_types = BTreeSet::new();
+        for rest in each(statement.get("rest")) {
+            for resource in each(rest.get("resource")) {
+                if let Some(resource_type) = resource.get("type").and_then(|t| t.as_str()) {
+                    resource_types.insert(resource_type.to_string());
+                }
+                if let Some(profile) = resource.get("profile") {
+                    add_canonical(profile);
+                }
+                each(resource.get("supportedProfile")).for_each(&mut add_canonical);
+                for operation in each(resource.get("operation")) {
+                    if let Some(definition) = operation.get("definition") {
+                        add_canonical(definition);
+                    }
+                }
+            }
+            for operation in each(rest.get("operation")) {
+                if let Some(definition) = operation.get("definition") {
+                    add_canonical(definition);
+                }
+            }
+        }
+
+        capabilities.resource_types = resource_types;
+        capabilities
+    }
+
+    /// Whether the server supports a referenced canonical.
+    ///
+    /// Definitions from the core specification (e.g.
+    /// `http://hl7.org/fhir/ValueSet/administrative-gender`) are part of the
+    /// server's FHIR version and always supported; anything else must be
+    /// declared in the statement.
+    pub fn supports(&self, url: &str) -> bool {
+        is_core_canonical(url) || self.canonicals.contains(url)
+    }
+}
+
+/// Project settings read from an existing ImplementationGuide resource.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImplementationGuideSeed {
+    /// `packageId` of the guide.
+    pub package_id: Option<String>,
+    /// First supported `fhirVersion` this server knows.
+    pub fhir_version: Option<FhirVersion>,
+    /// Package dependencies from `dependsOn` entries that name a package.
+    pub dependencies: Vec<PackageDependency>,
+    /// References listed under `definition.resource` (e.g. `StructureDefinition/my-patient`).
+    pub resources: Vec<String>,
+}
+
+impl ImplementationGuideSeed {
+    /// Extract the seed from an ImplementationGuide resource.
+    pub fn from_implementation_guide(guide: &serde_json::Value) -> ProjectResult<Self> {
+        if guide.get("resourceType").and_then(|v| v.as_str()) != Some("ImplementationGuide") {
+            return Err(ProjectError::InvalidStructure(
+                "expected an ImplementationGuide resource".to_string(),
+            ));
+        }
+
+        let fhir_version = each(guide.get("fhirVersion"))
+            .filter_map(|v| v.as_str())
+            .find_map(FhirVersion::from_str);
+
+        let dependencies = each(guide.get("dependsOn"))
+            .filter_map(|dep| {
+                let package_id = dep.get("packageId").and_then(|v| v.as_str())?;
+                let version = dep.get("version").and_then(|v| v.as_str()).unwrap_or("latest");
+                Some(PackageDependency::new(package_id, version))
+            })
+            .collect();
+
+        let resources = guide
+            .get("definition")
+            .map(|definition| {
+                each(definition.get("resource"))
+                    .filter_map(|r| r.pointer("/reference/reference").and_then(|v| v.as_str()))
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Self {
+            package_id: guide
+                .get("packageId")
+                .and_then(|v| v.as_str())
+                .map(str::to_string),
+            fhir_version,
+            dependencies,
+            resources,
+        })
+    }
+}
+
+/// Iterate over the items of an optional JSON array.
+fn each(value: Option<&serde_json::Value>) -> impl Iterator<Item = &serde_json::Value> {
+    value
+        .and_then(serde_json::Value::as_array)
+        .into_iter()
+        .flatten()
+}
+
+/// Whether a canonical URL belongs to the core FHIR specification.
+///
+/// Core canonicals look like `http://hl7.org/fhir/{ResourceType}/{id}`; IG
+/// canonicals continue with a lowercase path (`http://hl7.org/fhir/us/core/...`).
+fn is_core_canonical(url: &str) -> bool {
+    url.strip_prefix("http://hl7.org/fhir/")
+        .and_then(|rest| rest.chars().next())
+        .is_some_and(|c| c.is_ascii_uppercase())
+}
+
+/// Check a profile's external references against server capabilities.
+pub fn compute_compatibility(
+    profile: &ProfileReferences,
+    capabilities: &ServerCapabilities,
+) -> ProfileCompatibility {
+    let unsupported_resource_type = profile
+        .resource_type
+        .as_ref()
+        .filter(|resource_type| !capabilities.resource_types.contains(*resource_type))
+        .cloned();
+    let unsupported = profile
+        .references
+        .iter()
+        .filter(|url| !capabilities.supports(url))
+        .cloned()
+        .collect();
+
+    ProfileCompatibility {
+        id: profile.id.clone(),
+        url: profile.url.clone(),
+        unsupported_resource_type,
+        unsupported,
     }
 }
 
@@ -811,7 +1324,7 @@ mod tests {
         assert_eq!(project.name, "My IG");
 
         // Verify directory structure
-        let project_dir = service.project_path("my-ig");
+        let project_dir = service.project_path("my-ig").unwrap();
         assert!(project_dir.exists());
         assert!(project_dir.join("IR/resources").exists());
         assert!(project_dir.join("SD/StructureDefinition").exists());
@@ -839,6 +1352,84 @@ mod tests {
         assert_eq!(loaded.name, "My IG");
     }
 
+    #[tokio::test]
+    async fn test_recent_canonicals_most_recent_first() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let request = CreateProjectRequest {
+            id: "my-ig".to_string(),
+            name: "My IG".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        service.create_project(request).await.unwrap();
+
+        let vs = "http://example.org/fhir/ValueSet/codes".to_string();
+        let ext = "http://example.org/fhir/StructureDefinition/nickname".to_string();
+        service
+            .record_recent_canonicals(
+                "my-ig",
+                &[(CanonicalUsage::ValueSet, vs.clone()), (CanonicalUsage::Extension, ext.clone())],
+            )
+            .await
+            .unwrap();
+        // Referencing the value set again moves it back to the front
+        service
+            .record_recent_canonicals("my-ig", &[(CanonicalUsage::ValueSet, vs.clone())])
+            .await
+            .unwrap();
+
+        let recent = service.load_recent_canonicals("my-ig").await.unwrap();
+        let urls: Vec<&str> = recent.entries.iter().map(|e| e.url.as_str()).collect();
+        assert_eq!(urls, vec![vs.as_str(), ext.as_str()]);
+
+        assert!(matches!(
+            service.load_recent_canonicals("missing").await,
+            Err(ProjectError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rejects_path_traversal_ids() {
+        // Workspace is nested so escaped paths would land in the temp dir
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(&outside).unwrap();
+        let workspace = temp_dir.path().join("workspace");
+        std::fs::create_dir_all(&workspace).unwrap();
+        let service = ProjectService::new(&workspace);
+
+        for id in ["..", "../outside", "/etc", "a/../../outside", "..\\outside"] {
+            assert!(matches!(
+                service.delete_project(id).await,
+                Err(ProjectError::InvalidId(_))
+            ));
+            assert!(matches!(
+                service.load_index(id).await,
+                Err(ProjectError::InvalidId(_))
+            ));
+        }
+        assert!(outside.exists());
+
+        let request = CreateProjectRequest {
+            id: "../outside-project".to_string(),
+            name: "Escape".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        assert!(matches!(
+            service.create_project(request).await,
+            Err(ProjectError::InvalidId(_))
+        ));
+        assert!(!temp_dir.path().join("outside-project").exists());
+    }
+
     #[tokio::test]
     async fn test_add_resource() {
         let (service, _temp_dir) = create_test_service().await;
@@ -954,4 +1545,339 @@ mod tests {
         let resources = service.list_resources("my-ig").await.unwrap();
         assert!(resources.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_rename_resource_rewrites_dependents() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let request = CreateProjectRequest {
+            id: "my-ig".to_string(),
+            name: "My IG".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        service.create_project(request).await.unwrap();
+
+        let base = service
+            .add_resource("my-ig", AddResourceRequest {
+                id: Some("base-patient".to_string()),
+                name: "BasePatient".to_string(),
+                kind: ResourceKind::Profile,
+                canonical_url: None,
+                base: Some("Patient".to_string()),
+                source_format: None,
+                description: None,
+                context: None,
+                purpose: None,
+                content: None,
+            })
+            .await
+            .unwrap();
+        service
+            .add_resource("my-ig", AddResourceRequest {
+                id: Some("derived-patient".to_string()),
+                name: "DerivedPatient".to_string(),
+                kind: ResourceKind::Profile,
+                canonical_url: None,
+                base: Some(base.canonical_url.clone()),
+                source_format: None,
+                description: None,
+                context: None,
+                purpose: None,
+                content: None,
+            })
+            .await
+            .unwrap();
+
+        let new_url = "http://example.org/fhir/StructureDefinition/CorePatient";
+        service
+            .rename_resource("my-ig", "base-patient", "core-patient", "CorePatient", new_url)
+            .await
+            .unwrap();
+
+        let index = service.load_index("my-ig").await.unwrap();
+        assert!(index.get_resource("base-patient").is_none());
+        let renamed = index.get_resource("core-patient").unwrap();
+        assert_eq!(renamed.name, "CorePatient");
+        assert_eq!(renamed.canonical_url, new_url);
+        assert_eq!(
+            index.get_resource("derived-patient").unwrap().base.as_deref(),
+            Some(new_url)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_add_resource_duplicate_canonical_url() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let request = CreateProjectRequest {
+            id: "my-ig".to_string(),
+            name: "My IG".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        service.create_project(request).await.unwrap();
+
+        for id in ["first", "second"] {
+            let add_request = AddResourceRequest {
+                id: Some(id.to_string()),
+                name: "MyPatient".to_string(),
+                kind: ResourceKind::Profile,
+                canonical_url: Some("http://example.org/fhir/StructureDefinition/MyPatient".to_string()),
+                base: Some("Patient".to_string()),
+                source_format: None,
+                description: None,
+                context: None,
+                purpose: None,
+                content: None,
+            };
+            let result = service.add_resource("my-ig", add_request).await;
+
+            if id == "first" {
+                assert!(result.is_ok());
+            } else {
+                match result {
+                    Err(ProjectError::DuplicateCanonicalUrl(url, existing)) => {
+                        assert!(url.ends_with("/MyPatient"));
+                        assert_eq!(existing, "first");
+                    }
+                    other => panic!("expected DuplicateCanonicalUrl, got {:?}", other),
+                }
+            }
+        }
+
+        let resources = service.list_resources("my-ig").await.unwrap();
+        assert_eq!(resources.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_external_references() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let request = CreateProjectRequest {
+            id: "my-ig".to_string(),
+            name: "My IG".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        service.create_project(request).await.unwrap();
+
+        let add_request = AddResourceRequest {
+            id: None,
+            name: "MyPatient".to_string(),
+            kind: ResourceKind::Profile,
+            canonical_url: None,
+            base: Some("Patient".to_string()),
+            source_format: None,
+            description: None,
+            context: None,
+            purpose: None,
+            content: None,
+        };
+        service.add_resource("my-ig", add_request).await.unwrap();
+
+        let references = service.external_references("my-ig").await.unwrap();
+        assert!(references.contains("http://hl7.org/fhir/StructureDefinition/Patient"));
+        assert!(!references.iter().any(|url| url.starts_with("http://example.org/fhir/")));
+    }
+
+    #[test]
+    fn test_compute_dependency_usage() {
+        let dependencies = vec![
+            PackageDependency::new("hl7.fhir.us.core", "6.1.0"),
+            PackageDependency::new("hl7.fhir.uv.extensions", "1.0.0"),
+        ];
+        let mut resolved = BTreeMap::new();
+        resolved.insert(
+            "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient".to_string(),
+            "hl7.fhir.us.core".to_string(),
+        );
+        resolved.insert(
+            "http://hl7.org/fhir/StructureDefinition/Patient".to_string(),
+            "hl7.fhir.r4.core".to_string(),
+        );
+
+        let usage = compute_dependency_usage(&dependencies, &resolved);
+        assert_eq!(usage.len(), 2);
+        assert!(usage[0].used);
+        assert_eq!(usage[0].canonicals.len(), 1);
+        assert!(!usage[1].used);
+        assert!(usage[1].canonicals.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_find_package_dependents() {
+        let (service, _temp_dir) = create_test_service().await;
+
+        let request = CreateProjectRequest {
+            id: "my-ig".to_string(),
+            name: "My IG".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: None,
+            description: None,
+            publisher: None,
+            dependencies: None,
+        };
+        service.create_project(request).await.unwrap();
+
+        let add_request = AddResourceRequest {
+            id: None,
+            name: "MyPatient".to_string(),
+            kind: ResourceKind::Profile,
+            canonical_url: None,
+            base: Some("Patient".to_string()),
+            source_format: None,
+            description: None,
+            context: None,
+            purpose: None,
+            content: None,
+        };
+        service.add_resource("my-ig", add_request).await.unwrap();
+
+        let references = service.workspace_references().await.unwrap();
+        let patient = "http://hl7.org/fhir/StructureDefinition/Patient".to_string();
+        let mut resolved = BTreeMap::new();
+        resolved.insert(
+            patient.clone(),
+            ("hl7.fhir.r4.core".to_string(), "4.0.1".to_string()),
+        );
+
+        // The profile's base comes from the core package
+        let dependents =
+            find_package_dependents(&references, &resolved, "hl7.fhir.r4.core", "4.0.1");
+        assert_eq!(dependents.len(), 1);
+        assert_eq!(dependents[0].project_id, "my-ig");
+        assert_eq!(dependents[0].canonicals, vec![patient]);
+        assert_eq!(
+            find_package_dependents(&references, &resolved, "hl7.fhir.r4.core", "latest").len(),
+            1
+        );
+
+        assert!(find_package_dependents(&references, &resolved, "hl7.fhir.r4.core", "4.3.0")
+            .is_empty());
+        assert!(find_package_dependents(&references, &resolved, "hl7.fhir.us.core", "6.1.0")
+            .is_empty());
+    }
+
+    #[test]
+    fn test_parse_implementation_guide() {
+        let guide = serde_json::json!({
+            "resourceType": "ImplementationGuide",
+            "url": "http://example.org/fhir/ImplementationGuide/example.ig",
+            "packageId": "example.ig",
+            "fhirVersion": ["4.0.1"],
+            "dependsOn": [
+                { "uri": "http://hl7.org/fhir/us/core/ImplementationGuide/hl7.fhir.us.core",
+                  "packageId": "hl7.fhir.us.core", "version": "6.1.0" },
+                { "uri": "http://example.org/no-package" }
+            ],
+            "definition": {
+                "resource": [
+                    { "reference": { "reference": "StructureDefinition/example-patient" } }
+                ]
+            }
+        });
+
+        let seed = ImplementationGuideSeed::from_implementation_guide(&guide).unwrap();
+        assert_eq!(seed.package_id.as_deref(), Some("example.ig"));
+        assert_eq!(seed.fhir_version, Some(FhirVersion::R4));
+        assert_eq!(
+            seed.dependencies,
+            vec![PackageDependency::new("hl7.fhir.us.core", "6.1.0")]
+        );
+        assert_eq!(seed.resources, vec!["StructureDefinition/example-patient"]);
+
+        let not_a_guide = serde_json::json!({ "resourceType": "Patient" });
+        assert!(matches!(
+            ImplementationGuideSeed::from_implementation_guide(&not_a_guide),
+            Err(ProjectError::InvalidStructure(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_apply_implementation_guide() {
+        let (service, _temp_dir) = create_test_service().await;
+        let request = CreateProjectRequest {
+            id: "seeded".to_string(),
+            name: "Seeded".to_string(),
+            canonical_base: "http://example.org/fhir".to_string(),
+            fhir_version: Some(FhirVersion::R5),
+            description: None,
+            publisher: None,
+            dependencies: Some(vec![PackageDependency::new("hl7.fhir.us.core", "5.0.1")]),
+        };
+        service.create_project(request).await.unwrap();
+
+        let seed = ImplementationGuideSeed {
+            package_id: Some("example.ig".to_string()),
+            fhir_version: Some(FhirVersion::R4),
+            dependencies: vec![
+                PackageDependency::new("hl7.fhir.us.core", "6.1.0"),
+                PackageDependency::new("hl7.terminology.r4", "5.3.0"),
+            ],
+            resources: Vec::new(),
+        };
+        service.apply_implementation_guide("seeded", &seed).await.unwrap();
+
+        let project = service.load_project("seeded").await.unwrap();
+        assert_eq!(project.package_id.as_deref(), Some("example.ig"));
+        assert_eq!(project.fhir_version, FhirVersion::R4);
+        assert_eq!(project.dependencies, seed.dependencies);
+    }
+
+    #[test]
+    fn test_compute_compatibility() {
+        let statement = serde_json::json!({
+            "resourceType": "CapabilityStatement",
+            "rest": [{
+                "mode": "server",
+                "resource": [{
+                    "type": "Patient",
+                    "supportedProfile": [
+                        "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient|6.1.0"
+                    ]
+                }]
+            }]
+        });
+        let capabilities = ServerCapabilities::from_capability_statement(&statement);
+
+        let profile = ProfileReferences {
+            id: "my-patient".to_string(),
+            url: "http://example.org/fhir/StructureDefinition/my-patient".to_string(),
+            resource_type: Some("Patient".to_string()),
+            references: vec![
+                "http://example.org/ValueSet/local-codes".to_string(),
+                "http://hl7.org/fhir/ValueSet/administrative-gender".to_string(),
+                "http://hl7.org/fhir/us/core/StructureDefinition/us-core-patient".to_string(),
+                "http://hl7.org/fhir/us/core/StructureDefinition/us-core-race".to_string(),
+            ],
+        };
+        let report = compute_compatibility(&profile, &capabilities);
+        assert!(report.unsupported_resource_type.is_none());
+        assert_eq!(
+            report.unsupported,
+            vec![
+                "http://example.org/ValueSet/local-codes",
+                "http://hl7.org/fhir/us/core/StructureDefinition/us-core-race",
+            ]
+        );
+        assert!(!report.is_compatible());
+
+        let observation = ProfileReferences {
+            resource_type: Some("Observation".to_string()),
+            references: Vec::new(),
+            ..profile
+        };
+        let report = compute_compatibility(&observation, &capabilities);
+        assert_eq!(report.unsupported_resource_type.as_deref(), Some("Observation"));
+    }
 }
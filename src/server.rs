@@ -4,48 +4,78 @@
 //! - API routes for profile management
 //! - Static file serving with embedded assets
 //! - SPA routing fallback
+//! - Optional response compression
+//! - Request ids and per-request tracing spans
+//! - Optional Prometheus metrics (`/metrics`)
+//! - Liveness (`/healthz`) and readiness (`/readyz`) probes
+//! - Build and maki toolchain versions (`/api/version`)
 //! - Graceful shutdown
 
-use std::time::Duration;
+use std::future::IntoFuture;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use axum::{
     body::Body,
-    extract::State,
-    http::{header, Method, StatusCode, Uri},
+    extract::{DefaultBodyLimit, MatchedPath, Request, State},
+    http::{
+        header, Extensions, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri, Version,
+    },
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
+use futures::StreamExt;
+use tokio::sync::Notify;
 use tower::ServiceBuilder;
 use tower_http::{
+    compression::{predicate::DefaultPredicate, CompressionLayer, Predicate},
     cors::{Any, CorsLayer},
     timeout::TimeoutLayer,
     trace::TraceLayer,
 };
+use tracing::Instrument;
 
 use crate::{
     api::{
-        export_routes, history_routes, package_routes, profile_routes, project_export_routes,
-        project_routes, search_routes, validation_routes,
+        annotation_routes, base_resource_routes, debug_routes, event_routes, export_routes,
+        history_routes, openapi_routes, package_routes, profile_routes, profiles::ErrorResponse,
+        project_export_routes, project_routes, search_routes, validation_routes,
     },
+    metrics::METRICS_CONTENT_TYPE,
     state::AppState,
     static_files::{has_embedded_assets, serve_static},
+    version::{check_maki_compatibility, VersionInfo},
     Config, Result,
 };
 
+/// Header carrying the request id, accepted inbound and echoed on responses.
+pub const X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Header making profile creation safe to retry.
+pub const IDEMPOTENCY_KEY: HeaderName = HeaderName::from_static("idempotency-key");
+
 /// Main server struct.
 pub struct Server {
     config: Config,
+    state: AppState,
     router: Router,
 }
 
 impl Server {
     /// Create a new server instance.
     pub async fn new(config: Config) -> Result<Self> {
+        check_maki_compatibility();
         let state = AppState::new(config.clone(), config.workspace_dir.clone());
-        let router = Self::build_router(&config, state).await;
+        let router = Self::build_router(&config, state.clone()).await;
 
-        Ok(Self { config, router })
+        Ok(Self {
+            config,
+            state,
+            router,
+        })
     }
 
     /// Build the API router with all routes and middleware.
@@ -60,28 +90,50 @@ impl Server {
         );
 
         // API routes
-        let api_routes = Router::new()
+        let mut api_routes = Router::new()
             .route("/status", get(status))
+            .route("/version", get(version))
+            .merge(openapi_routes())
             // Project management routes (includes list)
             .nest("/projects", project_routes())
             // Profile routes with export, validation, and history
             .nest(
                 "/projects/{projectId}/profiles",
                 profile_routes()
+                    .merge(annotation_routes())
                     .merge(export_routes())
                     .merge(validation_routes())
-                    .merge(history_routes()),
+                    .merge(history_routes())
+                    .merge(event_routes()),
             )
             .nest("/projects/{projectId}", project_export_routes())
             // Package management routes
             .nest("/packages", package_routes())
             // Resource search routes
             .nest("/search", search_routes())
+            .nest("/base-resources", base_resource_routes());
+
+        // Internal debugging routes
+        if config.enable_debug_routes {
+            tracing::warn!("Debug routes enabled under /api/_debug");
+            api_routes = api_routes.nest("/_debug", debug_routes());
+        }
+
+        // Reject oversized uploads (413) with the API's JSON error shape; the
+        // limit is enforced by our own middleware instead of axum's extractors
+        let api_routes = api_routes
+            .layer(DefaultBodyLimit::disable())
+            .layer(middleware::from_fn_with_state(
+                config.max_upload_bytes,
+                limit_request_body,
+            ))
             .with_state(state.clone());
 
         // Main router
         let mut router = Router::new()
             .route("/health", get(health_check))
+            .route("/healthz", get(health_check))
+            .route("/readyz", get(readiness_check))
             .nest("/api", api_routes);
 
         // Add static file serving
@@ -93,10 +145,24 @@ impl Server {
             router = router.fallback(no_ui_handler);
         }
 
-        // Apply middleware
+        // Request metrics; the route label is the matched route template
+        if config.enable_metrics {
+            router = router
+                .route("/metrics", get(metrics))
+                .layer(middleware::from_fn_with_state(state.clone(), track_metrics));
+        }
+
+        // Compress JSON/FSH responses when the client accepts it. ETags are
+        // computed by handlers over the uncompressed content.
+        if config.enable_compression {
+            router = router.layer(Self::build_compression_layer());
+        }
+
+        // Apply middleware; the request id span wraps everything below it
         router
             .layer(
                 ServiceBuilder::new()
+                    .layer(middleware::from_fn(request_id))
                     .layer(TraceLayer::new_for_http())
                     .layer(timeout)
                     .layer(cors),
@@ -105,6 +171,9 @@ impl Server {
     }
 
     /// Build CORS layer based on configuration.
+    ///
+    /// Without configured origins, debug builds allow any origin (for the
+    /// frontend dev server) while release builds allow none.
     fn build_cors_layer(config: &Config) -> CorsLayer {
         let origins = config.cors_origins_list();
 
@@ -121,26 +190,60 @@ impl Server {
                 header::CONTENT_TYPE,
                 header::AUTHORIZATION,
                 header::ACCEPT,
-            ]);
+                header::IF_MATCH,
+                IDEMPOTENCY_KEY,
+                X_REQUEST_ID,
+            ])
+            .expose_headers([header::ETAG, X_REQUEST_ID]);
 
-        if origins.is_empty() || origins.iter().any(|o| o == "*") {
+        if origins.is_empty() {
+            if cfg!(debug_assertions) {
+                cors.allow_origin(Any)
+            } else {
+                cors
+            }
+        } else if origins.iter().any(|o| o == "*") {
             cors.allow_origin(Any)
         } else {
-            // Parse specific origins
-            let parsed_origins: Vec<_> = origins
+            // Parse specific origins; a bad entry is never widened to `Any`
+            let parsed_origins: Vec<HeaderValue> = origins
                 .iter()
-                .filter_map(|o| o.parse().ok())
+                .filter_map(|o| match o.parse() {
+                    Ok(origin) => Some(origin),
+                    Err(_) => {
+                        tracing::error!("Ignoring invalid CORS origin '{}'", o);
+                        None
+                    }
+                })
                 .collect();
 
             if parsed_origins.is_empty() {
-                cors.allow_origin(Any)
+                tracing::error!(
+                    "No valid CORS origins configured, cross-origin requests are denied"
+                );
+                cors
             } else {
                 cors.allow_origin(parsed_origins)
             }
         }
     }
 
+    /// Build compression layer for JSON and FSH responses.
+    fn build_compression_layer() -> CompressionLayer<impl Predicate> {
+        let predicate = DefaultPredicate::new().and(
+            is_compressible_response as fn(StatusCode, Version, &HeaderMap, &Extensions) -> bool,
+        );
+        CompressionLayer::new().gzip(true).deflate(true).compress_when(predicate)
+    }
+
     /// Run the server with graceful shutdown.
+    ///
+    /// On SIGTERM or Ctrl+C the listener stops accepting connections and
+    /// in-flight requests (long exports, imports) are allowed to finish for
+    /// up to the configured shutdown timeout. Connections still open after
+    /// that (e.g. WebSocket streams) are dropped. The server's handle to the
+    /// shared state is dropped last; the canonical manager is freed once no
+    /// other task still holds the state.
     pub async fn run(self) -> anyhow::Result<()> {
         let addr = self.config.bind_addr();
         let shutdown_timeout = self.config.shutdown_timeout_duration();
@@ -149,18 +252,50 @@ impl Server {
 
         let listener = tokio::net::TcpListener::bind(addr).await?;
 
-        // Run server with graceful shutdown
-        axum::serve(listener, self.router)
-            .with_graceful_shutdown(shutdown_signal(shutdown_timeout))
-            .await?;
+        let draining = Arc::new(Notify::new());
+        let signal_received = draining.clone();
+        let serve = axum::serve(listener, self.router)
+            .with_graceful_shutdown(async move {
+                shutdown_signal().await;
+                tracing::info!(
+                    "Draining in-flight requests (up to {} seconds)",
+                    shutdown_timeout.as_secs()
+                );
+                signal_received.notify_one();
+            })
+            .into_future();
+
+        tokio::select! {
+            result = serve => {
+                result?;
+                tracing::info!("All in-flight requests completed");
+            }
+            () = drain_deadline(&draining, shutdown_timeout) => {
+                tracing::warn!(
+                    "Requests still in flight after {} seconds, closing remaining connections",
+                    shutdown_timeout.as_secs()
+                );
+            }
+        }
+
+        if self.state.has_canonical_manager() {
+            tracing::info!("Releasing the server's canonical manager handle");
+        }
+        drop(self.state);
 
         tracing::info!("Server shutdown complete");
         Ok(())
     }
 }
 
+/// Resolve `timeout` after draining starts.
+async fn drain_deadline(draining: &Notify, timeout: Duration) {
+    draining.notified().await;
+    tokio::time::sleep(timeout).await;
+}
+
 /// Wait for shutdown signal (SIGTERM, SIGINT, or Ctrl+C).
-async fn shutdown_signal(timeout: Duration) {
+async fn shutdown_signal() {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
@@ -186,13 +321,129 @@ async fn shutdown_signal(timeout: Duration) {
             tracing::info!("Received SIGTERM, initiating graceful shutdown");
         }
     }
+}
+
+/// Whether a response carries JSON or FSH content worth compressing.
+fn is_compressible_response(
+    _status: StatusCode,
+    _version: Version,
+    headers: &HeaderMap,
+    _extensions: &Extensions,
+) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|content_type| {
+            let mime = content_type.split(';').next().unwrap_or("").trim();
+            mime == "application/json" || mime.ends_with("+json") || mime == "text/plain"
+        })
+}
+
+/// Assign a request id and run the request inside a tracing span.
+///
+/// Reuses a well-formed inbound `X-Request-Id`, otherwise generates one, and
+/// echoes it on the response. Log lines emitted while handling the request
+/// carry the id, method, path and project id.
+async fn request_id(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty() && v.len() <= 128)
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+    let header_value =
+        HeaderValue::from_str(&id).unwrap_or_else(|_| HeaderValue::from_static("invalid"));
+    request.headers_mut().insert(X_REQUEST_ID, header_value.clone());
+
+    let path = request.uri().path();
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %path,
+        project_id = project_id_from_path(path).unwrap_or(""),
+    );
+
+    let mut response = next.run(request).instrument(span).await;
+    response.headers_mut().insert(X_REQUEST_ID, header_value);
+    response
+}
 
-    // Allow time for in-flight requests to complete
-    tracing::info!(
-        "Waiting up to {} seconds for requests to complete...",
-        timeout.as_secs()
+/// Count the request and record its latency by matched route.
+async fn track_metrics(State(state): State<AppState>, request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string());
+    let started = Instant::now();
+
+    let response = next.run(request).await;
+    state.metrics().record_request(
+        method.as_str(),
+        route.as_deref(),
+        response.status().as_u16(),
+        started.elapsed(),
     );
-    tokio::time::sleep(Duration::from_millis(100)).await;
+    response
+}
+
+/// Extract the project id from an `/api/projects/{projectId}/...` path.
+fn project_id_from_path(path: &str) -> Option<&str> {
+    path.strip_prefix("/api/projects/")?
+        .split('/')
+        .next()
+        .filter(|id| !id.is_empty())
+}
+
+/// Reject request bodies over `limit` bytes with a JSON 413.
+///
+/// A declared `Content-Length` over the limit is refused up front. Otherwise
+/// the body is counted as the handler reads it; once it passes the limit the
+/// read fails and the handler's response, whatever its extractor made of the
+/// failure, is replaced. Other 413 responses, e.g. from handlers, are passed
+/// through unchanged.
+async fn limit_request_body(State(limit): State<usize>, request: Request, next: Next) -> Response {
+    let declared = request
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<u64>().ok());
+    if declared.is_some_and(|length| length > limit as u64) {
+        return payload_too_large();
+    }
+
+    let exceeded = Arc::new(AtomicBool::new(false));
+    let flag = exceeded.clone();
+    let mut remaining = limit;
+    let request = request.map(|body| {
+        Body::from_stream(body.into_data_stream().map(move |chunk| {
+            let chunk = chunk?;
+            if chunk.len() > remaining {
+                flag.store(true, Ordering::Relaxed);
+                return Err(axum::Error::new("request body exceeds the upload limit"));
+            }
+            remaining -= chunk.len();
+            Ok(chunk)
+        }))
+    });
+
+    let response = next.run(request).await;
+    if exceeded.load(Ordering::Relaxed) {
+        return payload_too_large();
+    }
+    response
+}
+
+/// JSON 413 for a request body over the configured upload limit.
+fn payload_too_large() -> Response {
+    ErrorResponse::new(
+        StatusCode::PAYLOAD_TOO_LARGE,
+        "PAYLOAD_TOO_LARGE",
+        "Request body exceeds the configured upload limit",
+    )
+    .into_response()
 }
 
 // --- Route Handlers ---
@@ -202,6 +453,32 @@ async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Core profile whose resolution shows the core FHIR package is installed.
+const READINESS_CANONICAL: &str = "http://hl7.org/fhir/StructureDefinition/Patient";
+
+/// Readiness probe: 200 once the canonical manager is initialized and the
+/// core FHIR package resolves, 503 with the reason otherwise.
+async fn readiness_check(State(state): State<AppState>) -> Response {
+    let manager = match state.canonical_manager().await {
+        Ok(manager) => manager,
+        Err(e) => {
+            return not_ready(format!("Canonical manager failed to initialize: {}", e));
+        }
+    };
+    if let Err(e) = manager.resolve(READINESS_CANONICAL).await {
+        return not_ready(format!(
+            "Core FHIR package is not resolvable ({}): {}",
+            READINESS_CANONICAL, e
+        ));
+    }
+    (StatusCode::OK, "OK").into_response()
+}
+
+fn not_ready(reason: String) -> Response {
+    tracing::warn!("Readiness check failed: {}", reason);
+    ErrorResponse::new(StatusCode::SERVICE_UNAVAILABLE, "NOT_READY", reason).into_response()
+}
+
 /// Status endpoint returning server information.
 async fn status(State(state): State<AppState>) -> Json<serde_json::Value> {
     Json(serde_json::json!({
@@ -213,6 +490,17 @@ async fn status(State(state): State<AppState>) -> Json<serde_json::Value> {
     }))
 }
 
+/// Server, maki and supported FHIR versions.
+async fn version() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
+
+/// Prometheus metrics in the OpenMetrics text format.
+async fn metrics(State(state): State<AppState>) -> Response {
+    let body = state.metrics().render(state.base_tree_cache());
+    ([(header::CONTENT_TYPE, METRICS_CONTENT_TYPE)], body).into_response()
+}
+
 /// SPA fallback handler - serves index.html for unknown routes.
 async fn spa_fallback(uri: Uri) -> Response<Body> {
     let path = uri.path();
@@ -270,10 +558,317 @@ mod tests {
         assert_eq!(result, "OK");
     }
 
+    #[tokio::test]
+    async fn test_drain_deadline_starts_at_signal() {
+        let draining = Notify::new();
+        let timeout = Duration::from_millis(10);
+
+        // No shutdown signal yet: the deadline never starts
+        let waited =
+            tokio::time::timeout(Duration::from_millis(50), drain_deadline(&draining, timeout)).await;
+        assert!(waited.is_err());
+
+        draining.notify_one();
+        let waited =
+            tokio::time::timeout(Duration::from_millis(500), drain_deadline(&draining, timeout)).await;
+        assert!(waited.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_healthz_route() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let request = axum::http::Request::builder()
+            .uri("/healthz")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(bytes.as_ref(), b"OK");
+    }
+
+    #[tokio::test]
+    async fn test_version_route() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let request = axum::http::Request::builder()
+            .uri("/api/version")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(json["maki"]["core"], crate::version::MAKI_CORE_VERSION);
+        assert_eq!(json["maki"]["decompiler"], crate::version::MAKI_DECOMPILER_VERSION);
+        assert!(json["maki"]["compatible"].is_boolean());
+        assert!(json["fhirVersions"].as_array().unwrap().contains(&"4.0.1".into()));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_endpoint_counts_requests() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            enable_metrics: true,
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let request = axum::http::Request::builder()
+            .uri("/healthz")
+            .body(Body::empty())
+            .unwrap();
+        router.clone().oneshot(request).await.unwrap();
+
+        let request = axum::http::Request::builder()
+            .uri("/metrics")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.contains("niten_http_requests_total{"));
+        assert!(text.contains("route=\"/healthz\""));
+    }
+
+    #[tokio::test]
+    async fn test_compresses_large_export_when_gzip_accepted() {
+        use std::io::Read;
+        use tower::ServiceExt;
+
+        let body = serde_json::json!({
+            "resourceType": "StructureDefinition",
+            "snapshot": { "element": vec![serde_json::json!({ "path": "Patient.name" }); 2000] }
+        })
+        .to_string();
+        let expected = body.clone();
+        let router = Router::new()
+            .route(
+                "/export",
+                get(move || {
+                    std::future::ready((
+                        [
+                            (header::CONTENT_TYPE, "application/fhir+json"),
+                            (header::ETAG, "\"abc123\""),
+                        ],
+                        body.clone(),
+                    ))
+                }),
+            )
+            .layer(Server::build_compression_layer());
+
+        let request = axum::http::Request::builder()
+            .uri("/export")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.headers()[header::CONTENT_ENCODING], "gzip");
+        assert_eq!(response.headers()[header::ETAG], "\"abc123\"");
+
+        let compressed = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert!(compressed.len() < expected.len());
+        let mut decompressed = String::new();
+        flate2::read::GzDecoder::new(compressed.as_ref())
+            .read_to_string(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, expected);
+    }
+
+    #[tokio::test]
+    async fn test_rejects_oversized_import_with_413() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            max_upload_bytes: 1024,
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let body = serde_json::json!({
+            "format": "json",
+            "content": "x".repeat(4096),
+        })
+        .to_string();
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/api/projects/demo/profiles/patient/import")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body.clone()))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["code"], "PAYLOAD_TOO_LARGE");
+
+        // A declared length over the limit is refused before the handler runs
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/api/projects/demo/profiles/patient/import")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_LENGTH, body.len())
+            .body(Body::from(body))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn test_other_413_responses_are_not_rewritten() {
+        use tower::ServiceExt;
+
+        let router = Router::new()
+            .route(
+                "/batch",
+                axum::routing::post(|| async {
+                    (StatusCode::PAYLOAD_TOO_LARGE, "too many profiles in batch")
+                }),
+            )
+            .layer(middleware::from_fn_with_state(1024, limit_request_body));
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/batch")
+            .body(Body::from("[]"))
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(&bytes[..], b"too many profiles in batch");
+    }
+
+    #[tokio::test]
+    async fn test_responses_carry_request_id() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        let generated = response.headers()[&X_REQUEST_ID].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .header(&X_REQUEST_ID, "client-supplied-42")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.headers()[&X_REQUEST_ID], "client-supplied-42");
+    }
+
+    #[tokio::test]
+    async fn test_element_patch_reaches_fsh_export() {
+        use tower::ServiceExt;
+
+        let workspace = tempfile::TempDir::new().unwrap();
+        let config = Config {
+            workspace_dir: workspace.path().to_path_buf(),
+            ..Default::default()
+        };
+        let state = AppState::new(config.clone(), config.workspace_dir.clone());
+        let router = Server::build_router(&config, state).await;
+
+        let request = axum::http::Request::builder()
+            .method(Method::POST)
+            .uri("/api/projects/demo/profiles")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "kind": "logical", "fhirVersion": "R4", "name": "LabBatch" })
+                    .to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let request = axum::http::Request::builder()
+            .method(Method::PATCH)
+            .uri("/api/projects/demo/profiles/labbatch/elements/LabBatch.batchNumber")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(
+                serde_json::json!({ "short": "Lot number printed on the label" }).to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let request = axum::http::Request::builder()
+            .uri("/api/projects/demo/profiles/labbatch/export/fsh?force=true")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let fsh = json["data"]["data"].as_str().unwrap();
+        assert!(fsh.contains("Lot number printed on the label"));
+    }
+
+    #[test]
+    fn test_project_id_from_path() {
+        assert_eq!(project_id_from_path("/api/projects/demo/profiles/p1"), Some("demo"));
+        assert_eq!(project_id_from_path("/api/projects/demo"), Some("demo"));
+        assert_eq!(project_id_from_path("/api/projects/"), None);
+        assert_eq!(project_id_from_path("/api/packages"), None);
+    }
+
     #[test]
     fn test_build_cors_layer_permissive() {
         let config = Config {
-            cors_origins: Some("*".to_string()),
+            cors_allowed_origins: vec!["*".to_string()],
             ..Default::default()
         };
         // Should not panic
@@ -283,10 +878,99 @@ mod tests {
     #[test]
     fn test_build_cors_layer_specific_origins() {
         let config = Config {
-            cors_origins: Some("http://localhost:3000".to_string()),
+            cors_allowed_origins: vec!["http://localhost:3000".to_string()],
             ..Default::default()
         };
         // Should not panic
         let _ = Server::build_cors_layer(&config);
     }
+
+    #[tokio::test]
+    async fn test_cors_allows_configured_origin() {
+        use tower::ServiceExt;
+
+        let config = Config {
+            cors_allowed_origins: vec!["http://localhost:3000".to_string()],
+            ..Default::default()
+        };
+        let router = Router::new()
+            .route("/health", get(health_check))
+            .layer(Server::build_cors_layer(&config));
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .header(header::ORIGIN, "http://localhost:3000")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "http://localhost:3000"
+        );
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .header(header::ORIGIN, "http://evil.example")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_cors_invalid_origins_allow_none() {
+        use tower::ServiceExt;
+
+        let config = Config {
+            cors_allowed_origins: vec!["http://exa\u{7f}mple.com".to_string()],
+            ..Default::default()
+        };
+        let router = Router::new()
+            .route("/health", get(health_check))
+            .layer(Server::build_cors_layer(&config));
+
+        let request = axum::http::Request::builder()
+            .uri("/health")
+            .header(header::ORIGIN, "http://evil.example")
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert!(!response
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_allows_concurrency_headers() {
+        use tower::ServiceExt;
+
+        let config = Config {
+            cors_allowed_origins: vec!["http://localhost:3000".to_string()],
+            ..Default::default()
+        };
+        let router = Router::new()
+            .route("/health", get(health_check))
+            .layer(Server::build_cors_layer(&config));
+
+        let request = axum::http::Request::builder()
+            .method(Method::OPTIONS)
+            .uri("/health")
+            .header(header::ORIGIN, "http://localhost:3000")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "if-match,idempotency-key",
+            )
+            .body(Body::empty())
+            .unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        let allowed = response.headers()[header::ACCESS_CONTROL_ALLOW_HEADERS]
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        assert!(allowed.contains("if-match"));
+        assert!(allowed.contains("idempotency-key"));
+    }
 }
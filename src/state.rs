@@ -5,14 +5,25 @@
 
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use octofhir_canonical_manager::CanonicalManager;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{OnceCell, RwLock};
+use tokio::sync::{Mutex, OnceCell, RwLock};
 
 use crate::Config;
+use crate::api::persistence::{FileStorage, Storage};
 use crate::api::registry_catalog::{SharedRegistryCatalog, create_registry_catalog};
+use crate::base::{BaseResource, BaseTreeCache};
+use crate::engine::{
+    DocumentId, DocumentSavedEvent, EngineEvent, EventBroadcaster, OperationAppliedEvent,
+    ValidationCompletedEvent,
+};
+use crate::ir::{FhirVersion, HistoryState};
+use crate::metrics::Metrics;
+use crate::paths::{join_id, InvalidPathId};
 use crate::validation::ValidationResult;
 
 /// Shared application state accessible from all request handlers.
@@ -27,6 +38,8 @@ struct AppStateInner {
     config: Config,
     /// Workspace directory for project storage.
     workspace_dir: PathBuf,
+    /// Profile persistence backend.
+    storage: Arc<dyn Storage>,
     /// Active sessions (project_id -> session data).
     sessions: DashMap<String, SessionData>,
     /// Startup timestamp.
@@ -41,6 +54,46 @@ struct AppStateInner {
     validation_config: RwLock<ValidationConfig>,
     /// Registry catalog for package search.
     registry_catalog: SharedRegistryCatalog,
+    /// Parsed base definition trees (key: base URL + FHIR version).
+    base_tree_cache: Arc<BaseTreeCache>,
+    /// Profileable base resource types per FHIR version.
+    base_resources: DashMap<FhirVersion, Arc<Vec<BaseResource>>>,
+    /// Live document events (key: "project_id/profile_id").
+    events: EventBroadcaster,
+    /// Recently used create-profile idempotency keys (key: "project_id/key").
+    idempotency_keys: DashMap<String, IdempotentCreate>,
+    /// Per-profile locks serializing annotation writes (key: "project_id/profile_id").
+    annotation_locks: DashMap<String, Arc<Mutex<()>>>,
+    /// Per-project locks serializing recent-canonicals writes (key: project_id).
+    recent_canonicals_locks: DashMap<String, Arc<Mutex<()>>>,
+    /// Prometheus metrics.
+    metrics: Metrics,
+    /// Whether the canonical manager fails to initialize (tests only).
+    #[cfg(test)]
+    canonical_manager_unavailable: std::sync::atomic::AtomicBool,
+}
+
+/// How long an `Idempotency-Key` replays the profile it created.
+pub const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Profile created under an idempotency key.
+#[derive(Debug, Clone)]
+struct IdempotentCreate {
+    /// ID of the created profile (`None` while the create is in progress).
+    profile_id: Option<String>,
+    /// When the key was first used.
+    created_at: Instant,
+}
+
+/// Outcome of reserving an idempotency key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyReservation {
+    /// The key was free and is now held by the caller.
+    Reserved,
+    /// The key already created this profile.
+    Completed(String),
+    /// Another request holding the key has not finished yet.
+    InProgress,
 }
 
 /// Cached validation result with metadata.
@@ -110,10 +163,21 @@ pub struct SessionData {
 impl AppState {
     /// Create new application state.
     pub fn new(config: Config, workspace_dir: PathBuf) -> Self {
+        let storage = Arc::new(FileStorage::new(workspace_dir.clone()));
+        Self::with_storage(config, workspace_dir, storage)
+    }
+
+    /// Create application state with a custom profile persistence backend.
+    pub fn with_storage(
+        config: Config,
+        workspace_dir: PathBuf,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
         Self {
             inner: Arc::new(AppStateInner {
                 config,
                 workspace_dir,
+                storage,
                 sessions: DashMap::new(),
                 started_at: chrono::Utc::now(),
                 request_count: RwLock::new(0),
@@ -121,6 +185,15 @@ impl AppState {
                 validation_cache: DashMap::new(),
                 validation_config: RwLock::new(ValidationConfig::default()),
                 registry_catalog: create_registry_catalog(),
+                base_tree_cache: Arc::new(BaseTreeCache::default()),
+                base_resources: DashMap::new(),
+                events: EventBroadcaster::new(),
+                idempotency_keys: DashMap::new(),
+                annotation_locks: DashMap::new(),
+                recent_canonicals_locks: DashMap::new(),
+                metrics: Metrics::new(),
+                #[cfg(test)]
+                canonical_manager_unavailable: std::sync::atomic::AtomicBool::new(false),
             }),
         }
     }
@@ -132,6 +205,15 @@ impl AppState {
     pub async fn canonical_manager(
         &self,
     ) -> Result<&Arc<CanonicalManager>, octofhir_canonical_manager::FcmError> {
+        #[cfg(test)]
+        if self
+            .inner
+            .canonical_manager_unavailable
+            .load(std::sync::atomic::Ordering::SeqCst)
+        {
+            return Err(std::io::Error::other("package store unavailable").into());
+        }
+
         self.inner
             .canonical_manager
             .get_or_try_init(|| async {
@@ -148,6 +230,51 @@ impl AppState {
             .await
     }
 
+    /// Make every later canonical manager access fail, as if the package
+    /// store could not be opened.
+    #[cfg(test)]
+    pub(crate) fn make_canonical_manager_unavailable(&self) {
+        self.inner
+            .canonical_manager_unavailable
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Whether the canonical manager has been initialized.
+    #[must_use]
+    pub fn has_canonical_manager(&self) -> bool {
+        self.inner.canonical_manager.initialized()
+    }
+
+    /// Get the server metrics.
+    #[must_use]
+    pub fn metrics(&self) -> &Metrics {
+        &self.inner.metrics
+    }
+
+    /// Get the shared cache of parsed base definition trees.
+    #[must_use]
+    pub fn base_tree_cache(&self) -> &Arc<BaseTreeCache> {
+        &self.inner.base_tree_cache
+    }
+
+    /// Drop cached base trees and base resource lists after installed
+    /// packages change.
+    pub fn invalidate_base_trees(&self) {
+        self.inner.base_tree_cache.invalidate_all();
+        self.inner.base_resources.clear();
+    }
+
+    /// Cached base resource types for a FHIR version.
+    #[must_use]
+    pub fn cached_base_resources(&self, version: FhirVersion) -> Option<Arc<Vec<BaseResource>>> {
+        self.inner.base_resources.get(&version).map(|r| r.clone())
+    }
+
+    /// Cache the base resource types of a FHIR version.
+    pub fn cache_base_resources(&self, version: FhirVersion, resources: Arc<Vec<BaseResource>>) {
+        self.inner.base_resources.insert(version, resources);
+    }
+
     /// Get the registry catalog for package search.
     #[must_use]
     pub fn registry_catalog(&self) -> &SharedRegistryCatalog {
@@ -240,9 +367,16 @@ impl AppState {
     }
 
     /// Get path to a project directory.
-    #[must_use]
-    pub fn project_path(&self, project_id: &str) -> PathBuf {
-        self.inner.workspace_dir.join(project_id)
+    ///
+    /// Fails if the project id is not a single path component, so request
+    /// ids can never resolve outside the workspace.
+    pub fn project_path(&self, project_id: &str) -> Result<PathBuf, InvalidPathId> {
+        join_id(&self.inner.workspace_dir, "project id", project_id)
+    }
+
+    /// Get the profile persistence backend.
+    pub fn storage(&self) -> &Arc<dyn Storage> {
+        &self.inner.storage
     }
 
     /// Calculate server uptime in seconds.
@@ -295,6 +429,170 @@ impl AppState {
             .retain(|k, _| !k.starts_with(&prefix));
     }
 
+    // === Idempotency Methods ===
+
+    /// Profile created earlier in the project under this idempotency key.
+    ///
+    /// Expired keys are dropped on lookup.
+    pub fn idempotent_profile(&self, project_id: &str, key: &str) -> Option<String> {
+        self.expire_idempotency_keys();
+        self.inner
+            .idempotency_keys
+            .get(&format!("{}/{}", project_id, key))
+            .and_then(|entry| entry.profile_id.clone())
+    }
+
+    /// Atomically claim an idempotency key before creating a profile.
+    ///
+    /// A key that is free is reserved for the caller, who must then either
+    /// [remember](Self::remember_idempotency_key) the created profile or
+    /// [release](Self::release_idempotency_key) the key. Expired keys are
+    /// dropped first.
+    pub fn reserve_idempotency_key(&self, project_id: &str, key: &str) -> IdempotencyReservation {
+        self.expire_idempotency_keys();
+        match self
+            .inner
+            .idempotency_keys
+            .entry(format!("{}/{}", project_id, key))
+        {
+            Entry::Occupied(entry) => match &entry.get().profile_id {
+                Some(profile_id) => IdempotencyReservation::Completed(profile_id.clone()),
+                None => IdempotencyReservation::InProgress,
+            },
+            Entry::Vacant(entry) => {
+                entry.insert(IdempotentCreate {
+                    profile_id: None,
+                    created_at: Instant::now(),
+                });
+                IdempotencyReservation::Reserved
+            }
+        }
+    }
+
+    /// Remember the profile created under an idempotency key.
+    pub fn remember_idempotency_key(&self, project_id: &str, key: &str, profile_id: &str) {
+        self.inner.idempotency_keys.insert(
+            format!("{}/{}", project_id, key),
+            IdempotentCreate {
+                profile_id: Some(profile_id.to_string()),
+                created_at: Instant::now(),
+            },
+        );
+    }
+
+    /// Release a reserved idempotency key after the create failed.
+    pub fn release_idempotency_key(&self, project_id: &str, key: &str) {
+        self.inner
+            .idempotency_keys
+            .remove_if(&format!("{}/{}", project_id, key), |_, entry| {
+                entry.profile_id.is_none()
+            });
+    }
+
+    /// Forget that an idempotency key created `profile_id` (e.g. it was deleted).
+    pub fn forget_idempotent_profile(&self, project_id: &str, key: &str, profile_id: &str) {
+        self.inner
+            .idempotency_keys
+            .remove_if(&format!("{}/{}", project_id, key), |_, entry| {
+                entry.profile_id.as_deref() == Some(profile_id)
+            });
+    }
+
+    /// Drop idempotency keys older than [`IDEMPOTENCY_KEY_TTL`].
+    fn expire_idempotency_keys(&self) {
+        self.inner
+            .idempotency_keys
+            .retain(|_, entry| entry.created_at.elapsed() < IDEMPOTENCY_KEY_TTL);
+    }
+
+    // === Annotation Methods ===
+
+    /// Lock serializing reads and writes of a profile's annotations sidecar.
+    pub fn annotation_lock(&self, project_id: &str, profile_id: &str) -> Arc<Mutex<()>> {
+        self.inner
+            .annotation_locks
+            .entry(format!("{}/{}", project_id, profile_id))
+            .or_default()
+            .clone()
+    }
+
+    /// Lock serializing updates of a project's recently used canonicals.
+    pub fn recent_canonicals_lock(&self, project_id: &str) -> Arc<Mutex<()>> {
+        self.inner
+            .recent_canonicals_locks
+            .entry(project_id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    // === Event Methods ===
+
+    /// Get the broadcaster for live document events.
+    #[must_use]
+    pub fn events(&self) -> &EventBroadcaster {
+        &self.inner.events
+    }
+
+    /// Document ID used for a profile's events.
+    #[must_use]
+    pub fn profile_document_id(project_id: &str, profile_id: &str) -> DocumentId {
+        format!("{}/{}", project_id, profile_id)
+    }
+
+    /// Notify subscribers that a profile was saved.
+    pub fn notify_profile_saved(&self, project_id: &str, profile_id: &str) {
+        self.inner
+            .events
+            .publish(EngineEvent::DocumentSaved(DocumentSavedEvent {
+                document_id: Self::profile_document_id(project_id, profile_id),
+                formats: vec!["ir".to_string()],
+                timestamp: chrono::Utc::now(),
+            }));
+    }
+
+    /// Notify subscribers that an operation was applied to a profile.
+    pub fn notify_operation_applied(
+        &self,
+        project_id: &str,
+        profile_id: &str,
+        description: impl Into<String>,
+        path: Option<String>,
+        history_state: HistoryState,
+    ) {
+        self.inner
+            .events
+            .publish(EngineEvent::OperationApplied(OperationAppliedEvent {
+                document_id: Self::profile_document_id(project_id, profile_id),
+                description: description.into(),
+                path,
+                history_state,
+                referenced_canonicals: Vec::new(),
+                timestamp: chrono::Utc::now(),
+            }));
+    }
+
+    /// Notify subscribers that validation of a profile completed.
+    pub fn notify_validation_completed(
+        &self,
+        project_id: &str,
+        profile_id: &str,
+        result: &ValidationResult,
+        duration_ms: u64,
+    ) {
+        self.inner
+            .metrics
+            .observe_validation(Duration::from_millis(duration_ms));
+        self.inner
+            .events
+            .publish(EngineEvent::ValidationCompleted(ValidationCompletedEvent {
+                document_id: Self::profile_document_id(project_id, profile_id),
+                result: result.into(),
+                incremental: false,
+                duration_ms,
+                timestamp: chrono::Utc::now(),
+            }));
+    }
+
     /// Get validation configuration.
     pub async fn validation_config(&self) -> ValidationConfig {
         self.inner.validation_config.read().await.clone()
@@ -346,10 +644,18 @@ mod tests {
     #[test]
     fn test_project_path() {
         let state = create_test_state();
-        let path = state.project_path("my-project");
+        let path = state.project_path("my-project").unwrap();
         assert_eq!(path, PathBuf::from("/tmp/test-workspace/my-project"));
     }
 
+    #[test]
+    fn test_project_path_rejects_traversal() {
+        let state = create_test_state();
+        for id in ["../../etc", "..", "a/b", "/etc", "..\\windows", "C:"] {
+            assert!(state.project_path(id).is_err(), "{id} should be rejected");
+        }
+    }
+
     #[tokio::test]
     async fn test_request_counter() {
         let state = create_test_state();
@@ -364,4 +670,33 @@ mod tests {
 
         assert_eq!(state.request_count().await, 2);
     }
+
+    #[test]
+    fn test_idempotency_key_reservation() {
+        let state = create_test_state();
+
+        assert_eq!(
+            state.reserve_idempotency_key("demo", "create-once"),
+            IdempotencyReservation::Reserved
+        );
+        // A concurrent request with the same key does not get it
+        assert_eq!(
+            state.reserve_idempotency_key("demo", "create-once"),
+            IdempotencyReservation::InProgress
+        );
+
+        // A failed create frees the key again
+        state.release_idempotency_key("demo", "create-once");
+        assert_eq!(
+            state.reserve_idempotency_key("demo", "create-once"),
+            IdempotencyReservation::Reserved
+        );
+
+        state.remember_idempotency_key("demo", "create-once", "clicked");
+        state.release_idempotency_key("demo", "create-once");
+        assert_eq!(
+            state.reserve_idempotency_key("demo", "create-once"),
+            IdempotencyReservation::Completed("clicked".to_string())
+        );
+    }
 }
@@ -22,6 +22,8 @@ pub struct ValidationOptions {
     pub fail_fast: bool,
     /// Paths to validate (empty = all).
     pub paths: Vec<String>,
+    /// Whether to keep quick fixes attached to diagnostics.
+    pub include_quick_fixes: bool,
 }
 
 impl Default for ValidationOptions {
@@ -31,6 +33,7 @@ impl Default for ValidationOptions {
             include_info: true,
             fail_fast: false,
             paths: Vec::new(),
+            include_quick_fixes: true,
         }
     }
 }
@@ -59,6 +62,12 @@ impl ValidationOptions {
         self.paths = paths;
         self
     }
+
+    /// Drop quick fixes from diagnostics (for count-only callers).
+    pub fn without_quick_fixes(mut self) -> Self {
+        self.include_quick_fixes = false;
+        self
+    }
 }
 
 /// Trait for validators.
@@ -81,6 +90,8 @@ pub trait Validator: Send + Sync {
 pub struct ValidationEngine {
     /// Validation options.
     options: ValidationOptions,
+    /// Base element tree of the validated profile, if resolved.
+    base_tree: Option<ElementNode>,
 }
 
 impl Default for ValidationEngine {
@@ -94,12 +105,25 @@ impl ValidationEngine {
     pub fn new() -> Self {
         Self {
             options: ValidationOptions::default(),
+            base_tree: None,
         }
     }
 
     /// Create with custom options.
     pub fn with_options(options: ValidationOptions) -> Self {
-        Self { options }
+        Self {
+            options,
+            base_tree: None,
+        }
+    }
+
+    /// Compare the document against its base element tree.
+    ///
+    /// Enables the rules that need the base (binding strength, cardinality
+    /// and type narrowing). The document must be hydrated for these checks.
+    pub fn with_base_tree(mut self, base_tree: ElementNode) -> Self {
+        self.base_tree = Some(base_tree);
+        self
     }
 
     /// Validate a profile document.
@@ -131,6 +155,15 @@ impl ValidationEngine {
             let keys_result = ValidationResult::with_diagnostics(duplicate_keys, ValidationLevel::Structural);
             result.merge(keys_result);
 
+            // Check that condition keys refer to existing invariants
+            let conditions = rules::fhirpath::check_condition_keys(document);
+            result.merge(ValidationResult::with_diagnostics(conditions, ValidationLevel::Structural));
+
+            // Compare against the base definition when available
+            if let Some(base_tree) = &self.base_tree {
+                result.merge(rules::validate_against_base(document, base_tree));
+            }
+
             if self.options.fail_fast && !result.is_valid {
                 return result;
             }
@@ -161,6 +194,12 @@ impl ValidationEngine {
             });
         }
 
+        if !self.options.include_quick_fixes {
+            for diagnostic in &mut result.diagnostics {
+                diagnostic.quick_fix = None;
+            }
+        }
+
         info!(
             "Validation complete: {} errors, {} warnings",
             result.error_count(),
@@ -426,6 +465,34 @@ mod tests {
             .all(|d| d.severity != super::super::diagnostic::DiagnosticSeverity::Info));
     }
 
+    #[tokio::test]
+    async fn test_validate_against_base_tree() {
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut base_gender = ElementNode::new("Patient.gender".to_string());
+        base_gender.constraints.binding = Some(crate::ir::Binding::new(
+            crate::ir::BindingStrength::Required,
+            "http://hl7.org/fhir/ValueSet/administrative-gender",
+        ));
+        base.add_child(base_gender.clone());
+
+        let mut doc = create_test_document();
+        let mut gender = base_gender;
+        gender.constraints.binding.as_mut().unwrap().strength =
+            crate::ir::BindingStrength::Example;
+        doc.resource.root.add_child(gender);
+
+        let without_base = ValidationEngine::new()
+            .validate(&doc, ValidationLevel::Structural)
+            .await;
+        assert!(without_base.diagnostics.iter().all(|d| d.code != "BIND_003"));
+
+        let with_base = ValidationEngine::new()
+            .with_base_tree(base)
+            .validate(&doc, ValidationLevel::Structural)
+            .await;
+        assert!(with_base.diagnostics.iter().any(|d| d.code == "BIND_003"));
+    }
+
     #[test]
     fn test_find_element_by_path() {
         let mut root = ElementNode::new("Patient".to_string());
@@ -103,6 +103,20 @@ impl QuickFixFactory {
         .preferred()
     }
 
+    /// Create fix for cardinality wider than the base allows.
+    pub fn fix_cardinality_to_base(path: &str, min: u32, max: Option<u32>) -> QuickFix {
+        let max_label = max.map_or_else(|| "*".to_string(), |m| m.to_string());
+        QuickFix::new(
+            format!("Set cardinality to {}..{}", min, max_label),
+            QuickFixKind::SetCardinality {
+                path: path.to_string(),
+                min,
+                max,
+            },
+        )
+        .preferred()
+    }
+
     /// Create fix for negative cardinality min.
     pub fn fix_negative_cardinality_min(path: &str) -> QuickFix {
         QuickFix::new(
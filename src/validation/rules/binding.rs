@@ -96,6 +96,26 @@ pub fn validate_element_binding(element: &ElementNode) -> Vec<Diagnostic> {
     diagnostics
 }
 
+/// Validate that no binding in the tree is weaker than its base binding.
+pub fn validate_binding_against_base(root: &ElementNode, base: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    super::visit_with_base(root, base, &mut |element, base_element, _| {
+        if let (Some(binding), Some(base_binding)) = (
+            &element.constraints.binding,
+            &base_element.constraints.binding,
+        ) {
+            diagnostics.extend(validate_binding_refinement(
+                &element.path,
+                base_binding.strength,
+                binding.strength,
+            ));
+        }
+    });
+
+    diagnostics
+}
+
 /// Recursively validate bindings in element tree.
 fn validate_element_recursive(element: &ElementNode, diagnostics: &mut Vec<Diagnostic>) {
     diagnostics.extend(validate_element_binding(element));
@@ -244,6 +264,32 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_binding_downgrade_against_base() {
+        let value_set = "http://hl7.org/fhir/ValueSet/administrative-gender";
+
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut base_gender = ElementNode::new("Patient.gender".to_string());
+        base_gender.constraints.binding =
+            Some(Binding::new(BindingStrength::Required, value_set.to_string()));
+        base.add_child(base_gender);
+
+        let mut profile = ElementNode::new("Patient".to_string());
+        let mut gender = ElementNode::new("Patient.gender".to_string());
+        gender.constraints.binding =
+            Some(Binding::new(BindingStrength::Preferred, value_set.to_string()));
+        profile.add_child(gender);
+
+        let diagnostics = validate_binding_against_base(&profile, &base);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::BINDING_STRENGTH_WEAKENED);
+        assert_eq!(diagnostics[0].element_path.as_deref(), Some("Patient.gender"));
+        assert!(diagnostics[0].quick_fix.is_some());
+
+        // Keeping the base strength is fine
+        assert!(validate_binding_against_base(&base, &base).is_empty());
+    }
+
     #[test]
     fn test_empty_valueset_error() {
         let mut element = ElementNode::new("Patient.gender".to_string());
@@ -17,6 +17,8 @@ pub mod codes {
     pub const CARD_INVALID_MAX: &str = "CARD_003";
     pub const CARD_SLICE_SUM_EXCEEDS_PARENT: &str = "CARD_004";
     pub const CARD_REQUIRED_ELEMENT_IN_OPTIONAL_PARENT: &str = "CARD_005";
+    pub const CARD_MIN_BELOW_BASE: &str = "CARD_006";
+    pub const CARD_MAX_ABOVE_BASE: &str = "CARD_007";
 }
 
 /// Validate cardinality for an entire element tree.
@@ -156,6 +158,71 @@ fn validate_required_in_optional(element: &ElementNode) -> Vec<Diagnostic> {
     diagnostics
 }
 
+/// Validate that no cardinality in the tree is wider than its base element's.
+///
+/// Slices may lower the minimum (slice minimums add up against the sliced
+/// element), so only their maximum is compared with the base.
+pub fn validate_cardinality_against_base(root: &ElementNode, base: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    super::visit_with_base(root, base, &mut |element, base_element, is_slice| {
+        let (Some(card), Some(base_card)) = (
+            &element.constraints.cardinality,
+            &base_element.constraints.cardinality,
+        ) else {
+            return;
+        };
+
+        let fixed_min = if is_slice { card.min } else { card.min.max(base_card.min) };
+        let fixed_max = match (card.max, base_card.max) {
+            (Some(max), Some(base_max)) => Some(max.min(base_max)),
+            (None, base_max) => base_max,
+            (max, None) => max,
+        };
+        let fix = || {
+            QuickFixFactory::fix_cardinality_to_base(&element.path, fixed_min, fixed_max)
+        };
+
+        if !is_slice && card.min < base_card.min {
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::CARD_MIN_BELOW_BASE,
+                    format!(
+                        "Minimum cardinality ({}) is below base minimum ({})",
+                        card.min, base_card.min
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir)
+                .with_quick_fix(fix()),
+            );
+        }
+
+        if !is_valid_refinement(0, base_card.max, 0, card.max) {
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::CARD_MAX_ABOVE_BASE,
+                    format!(
+                        "Maximum cardinality ({}) exceeds base maximum ({})",
+                        format_max(card.max),
+                        format_max(base_card.max)
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir)
+                .with_quick_fix(fix()),
+            );
+        }
+    });
+
+    diagnostics
+}
+
+/// Format a maximum cardinality the way FHIR writes it.
+fn format_max(max: Option<u32>) -> String {
+    max.map_or_else(|| "*".to_string(), |m| m.to_string())
+}
+
 /// Check if a cardinality refinement is valid (doesn't widen the range).
 pub fn is_valid_refinement(base_min: u32, base_max: Option<u32>, new_min: u32, new_max: Option<u32>) -> bool {
     // New min must be >= base min
@@ -195,6 +262,43 @@ mod tests {
         assert!(diagnostics.is_empty());
     }
 
+    fn base_tree() -> ElementNode {
+        let mut base = ElementNode::new("Patient".to_string());
+        let mut gender = ElementNode::new("Patient.gender".to_string());
+        gender.constraints.cardinality = Some(Cardinality::new(1, Some(1)));
+        let mut name = ElementNode::new("Patient.name".to_string());
+        name.constraints.cardinality = Some(Cardinality::new(0, Some(3)));
+        base.add_child(gender);
+        base.add_child(name);
+        base
+    }
+
+    #[test]
+    fn test_min_widened_below_base() {
+        let mut profile = base_tree();
+        profile.children[0].constraints.cardinality = Some(Cardinality::new(0, Some(1)));
+
+        let diagnostics = validate_cardinality_against_base(&profile, &base_tree());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::CARD_MIN_BELOW_BASE);
+        assert_eq!(diagnostics[0].element_path.as_deref(), Some("Patient.gender"));
+    }
+
+    #[test]
+    fn test_max_widened_above_base() {
+        let mut profile = base_tree();
+        profile.children[1].constraints.cardinality = Some(Cardinality::new(0, None));
+
+        let diagnostics = validate_cardinality_against_base(&profile, &base_tree());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::CARD_MAX_ABOVE_BASE);
+        assert_eq!(diagnostics[0].element_path.as_deref(), Some("Patient.name"));
+
+        // Narrowing is fine
+        profile.children[1].constraints.cardinality = Some(Cardinality::new(1, Some(2)));
+        assert!(validate_cardinality_against_base(&profile, &base_tree()).is_empty());
+    }
+
     #[test]
     fn test_is_valid_refinement() {
         // Valid: tightening range
@@ -13,12 +13,37 @@ pub mod codes {
     pub const FHIRPATH_INVALID_FUNCTION: &str = "FP_003";
     pub const FHIRPATH_MISSING_KEY: &str = "FP_004";
     pub const FHIRPATH_DUPLICATE_KEY: &str = "FP_005";
+    pub const FHIRPATH_DANGLING_CONDITION: &str = "FP_006";
 }
 
 /// Validate FHIRPath expressions in a profile.
 pub fn validate_fhirpath_expressions(document: &ProfileDocument) -> Vec<Diagnostic> {
     let mut diagnostics = Vec::new();
     validate_element_recursive(&document.resource.root, &mut diagnostics);
+    diagnostics.extend(validate_context_invariants(document));
+    diagnostics
+}
+
+/// Validate the `contextInvariant` expressions of an Extension definition.
+pub fn validate_context_invariants(document: &ProfileDocument) -> Vec<Diagnostic> {
+    let path = &document.resource.root.path;
+    let mut diagnostics = Vec::new();
+
+    for expression in &document.resource.context_invariants {
+        if expression.trim().is_empty() {
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::FHIRPATH_EMPTY_EXPRESSION,
+                    "Context invariant has empty expression",
+                )
+                .with_path(path)
+                .with_source(DiagnosticSource::FhirPath),
+            );
+            continue;
+        }
+        diagnostics.extend(validate_expression(expression, path));
+    }
+
     diagnostics
 }
 
@@ -181,6 +206,45 @@ pub fn check_duplicate_invariant_keys(document: &ProfileDocument) -> Vec<Diagnos
     diagnostics
 }
 
+/// Check that every `condition` key refers to an invariant in the document.
+pub fn check_condition_keys(document: &ProfileDocument) -> Vec<Diagnostic> {
+    let mut keys = std::collections::HashSet::new();
+    // Duplicates are reported by `check_duplicate_invariant_keys`
+    let mut duplicates = Vec::new();
+    collect_invariant_keys(&document.resource.root, &mut keys, &mut duplicates);
+
+    let mut diagnostics = Vec::new();
+    collect_dangling_conditions(&document.resource.root, &keys, &mut diagnostics);
+    diagnostics
+}
+
+fn collect_dangling_conditions(
+    element: &ElementNode,
+    keys: &std::collections::HashSet<String>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    for condition in &element.constraints.conditions {
+        if !keys.contains(condition) {
+            diagnostics.push(
+                Diagnostic::warning(
+                    codes::FHIRPATH_DANGLING_CONDITION,
+                    format!("Condition '{}' does not match any invariant key", condition),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::FhirPath),
+            );
+        }
+    }
+
+    for child in &element.children {
+        collect_dangling_conditions(child, keys, diagnostics);
+    }
+
+    for slice in element.slices.values() {
+        collect_dangling_conditions(&slice.element, keys, diagnostics);
+    }
+}
+
 fn collect_invariant_keys(
     element: &ElementNode,
     seen: &mut std::collections::HashSet<String>,
@@ -250,6 +314,29 @@ mod tests {
         assert!(!diagnostics.is_empty());
     }
 
+    #[test]
+    fn test_context_invariants() {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+        let resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/ext",
+            FhirVersion::R4,
+            BaseDefinition::resource("Extension"),
+        );
+        let metadata = DocumentMetadata::new("ext", &resource.url, "Ext");
+        let mut document = ProfileDocument::new(metadata, resource);
+        document.resource.context_invariants = vec![
+            "active = true".to_string(),
+            "name.exists() and (active".to_string(),
+            String::new(),
+        ];
+
+        let diagnostics = validate_context_invariants(&document);
+        assert!(diagnostics.iter().any(|d| d.code == codes::FHIRPATH_EMPTY_EXPRESSION));
+        assert!(diagnostics.len() >= 2);
+        assert!(diagnostics.iter().all(|d| d.element_path.as_deref() == Some("Extension")));
+    }
+
     #[test]
     fn test_empty_expression() {
         let mut element = ElementNode::new("Patient".to_string());
@@ -294,4 +381,36 @@ mod tests {
             .iter()
             .any(|d| d.code == codes::FHIRPATH_MISSING_KEY));
     }
+
+    #[test]
+    fn test_dangling_condition_keys() {
+        use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+        let url = "http://example.org/fhir/StructureDefinition/TestPatient";
+        let mut resource =
+            ProfiledResource::new(url, FhirVersion::R4, BaseDefinition::resource("Patient"));
+        resource.root.constraints.invariants.insert(
+            "pat-1".to_string(),
+            Invariant {
+                key: "pat-1".to_string(),
+                severity: InvariantSeverity::Error,
+                human: "Contact needs a name".to_string(),
+                expression: "contact.name.exists()".to_string(),
+                xpath: None,
+                source: None,
+            },
+        );
+        let mut contact = ElementNode::new("Patient.contact".to_string());
+        contact.constraints.conditions = vec!["pat-1".to_string(), "pat-9".to_string()];
+        resource.root.add_child(contact);
+
+        let document = ProfileDocument::new(
+            DocumentMetadata::new("test-patient", url, "TestPatient"),
+            resource,
+        );
+        let diagnostics = check_condition_keys(&document);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::FHIRPATH_DANGLING_CONDITION);
+        assert!(diagnostics[0].message.contains("pat-9"));
+    }
 }
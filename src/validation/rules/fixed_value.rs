@@ -0,0 +1,188 @@
+//! Fixed/Pattern Value Validation Rules
+//!
+//! Validates fixed and pattern values against the rest of the element:
+//! - Prohibited (`max = 0`) elements cannot carry a fixed or pattern value
+//! - The value's JSON shape must match one of the element's declared types
+
+use serde_json::Value;
+
+use crate::ir::{ElementNode, FixedValue};
+use crate::validation::diagnostic::{Diagnostic, DiagnosticSource};
+
+/// Error codes for fixed/pattern value validation.
+pub mod codes {
+    pub const FIXED_ON_PROHIBITED: &str = "FIXED_001";
+    pub const FIXED_TYPE_MISMATCH: &str = "FIXED_002";
+}
+
+/// Validate fixed/pattern values for an entire element tree.
+pub fn validate_fixed_value_tree(root: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    validate_element_recursive(root, &mut diagnostics);
+    diagnostics
+}
+
+/// Validate a single element's fixed/pattern value.
+pub fn validate_element_fixed_value(element: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let Some(fixed) = &element.constraints.fixed_value else {
+        return diagnostics;
+    };
+    let kind = match fixed {
+        FixedValue::Fixed(_) => "Fixed",
+        FixedValue::Pattern(_) => "Pattern",
+    };
+
+    let prohibited = element
+        .constraints
+        .cardinality
+        .as_ref()
+        .is_some_and(|card| card.max == Some(0));
+    if prohibited {
+        diagnostics.push(
+            Diagnostic::error(
+                codes::FIXED_ON_PROHIBITED,
+                format!("{} value set on prohibited element (max = 0)", kind),
+            )
+            .with_path(&element.path)
+            .with_source(DiagnosticSource::Ir),
+        );
+    }
+
+    // Without declared types (e.g. unhydrated paths) there is nothing to compare
+    let expected: Vec<(&str, &str)> = element
+        .constraints
+        .types
+        .iter()
+        .filter_map(|t| expected_json_kind(&t.code).map(|kind| (t.code.as_str(), kind)))
+        .collect();
+    if expected.len() == element.constraints.types.len() && !expected.is_empty() {
+        let actual = json_kind(fixed.value());
+        if !expected.iter().any(|(_, kind)| json_kind_matches(kind, actual)) {
+            let type_codes: Vec<&str> = expected.iter().map(|(code, _)| *code).collect();
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::FIXED_TYPE_MISMATCH,
+                    format!(
+                        "{} value is a JSON {} but the element type is {}",
+                        kind,
+                        actual,
+                        type_codes.join(" | ")
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Recursively validate fixed/pattern values in element tree.
+fn validate_element_recursive(element: &ElementNode, diagnostics: &mut Vec<Diagnostic>) {
+    diagnostics.extend(validate_element_fixed_value(element));
+
+    for child in &element.children {
+        validate_element_recursive(child, diagnostics);
+    }
+
+    for slice in element.slices.values() {
+        validate_element_recursive(&slice.element, diagnostics);
+    }
+}
+
+/// The JSON kind a FHIR type is represented as, when known.
+fn expected_json_kind(code: &str) -> Option<&'static str> {
+    match code {
+        "boolean" => Some("boolean"),
+        "integer" | "positiveInt" | "unsignedInt" => Some("integer"),
+        "decimal" => Some("number"),
+        "string" | "code" | "id" | "markdown" | "uri" | "url" | "canonical" | "oid" | "uuid"
+        | "date" | "dateTime" | "instant" | "time" | "base64Binary" | "integer64" => {
+            Some("string")
+        }
+        _ if code.starts_with(|c: char| c.is_ascii_uppercase()) => Some("object"),
+        _ => None,
+    }
+}
+
+/// The JSON kind of a value.
+fn json_kind(value: &Value) -> &'static str {
+    match value {
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::Null => "null",
+    }
+}
+
+/// Whether a value of kind `actual` fits `expected` (integers are decimals).
+fn json_kind_matches(expected: &str, actual: &str) -> bool {
+    expected == actual || (expected == "number" && actual == "integer")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{Cardinality, TypeConstraint};
+    use serde_json::json;
+
+    fn element(path: &str, type_code: &str, fixed: FixedValue) -> ElementNode {
+        let mut element = ElementNode::new(path.to_string());
+        element.constraints.types = vec![TypeConstraint::simple(type_code)];
+        element.constraints.fixed_value = Some(fixed);
+        element
+    }
+
+    fn codes_of(diagnostics: &[Diagnostic]) -> Vec<&str> {
+        diagnostics.iter().map(|d| d.code.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fixed_value_on_prohibited_element() {
+        let mut gender = element("Patient.gender", "code", FixedValue::fixed(json!("female")));
+        gender.constraints.cardinality = Some(Cardinality::new(0, Some(0)));
+
+        let diagnostics = validate_element_fixed_value(&gender);
+        assert_eq!(codes_of(&diagnostics), vec![codes::FIXED_ON_PROHIBITED]);
+
+        gender.constraints.cardinality = Some(Cardinality::optional());
+        assert!(validate_element_fixed_value(&gender).is_empty());
+    }
+
+    #[test]
+    fn test_fixed_value_type_mismatch() {
+        let count = element("Patient.multipleBirth[x]", "integer", FixedValue::fixed(json!("2")));
+        let diagnostics = validate_element_fixed_value(&count);
+        assert_eq!(codes_of(&diagnostics), vec![codes::FIXED_TYPE_MISMATCH]);
+        assert_eq!(diagnostics[0].element_path.as_deref(), Some("Patient.multipleBirth[x]"));
+
+        let count = element("Patient.multipleBirth[x]", "integer", FixedValue::fixed(json!(2)));
+        assert!(validate_element_fixed_value(&count).is_empty());
+
+        // Complex types take object patterns
+        let code = element(
+            "Observation.code",
+            "CodeableConcept",
+            FixedValue::pattern(json!({ "coding": [{ "code": "x" }] })),
+        );
+        assert!(validate_element_fixed_value(&code).is_empty());
+        let code = element("Observation.code", "CodeableConcept", FixedValue::pattern(json!("x")));
+        assert_eq!(
+            codes_of(&validate_element_fixed_value(&code)),
+            vec![codes::FIXED_TYPE_MISMATCH]
+        );
+    }
+
+    #[test]
+    fn test_choice_element_accepts_any_declared_type() {
+        let mut deceased = element("Patient.deceased[x]", "boolean", FixedValue::fixed(json!("2020-01-01")));
+        deceased.constraints.types.push(TypeConstraint::simple("dateTime"));
+        assert!(validate_fixed_value_tree(&deceased).is_empty());
+    }
+}
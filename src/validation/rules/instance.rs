@@ -0,0 +1,102 @@
+//! Example Instance Rules
+//!
+//! Validates the project's example instances against the profiles they claim
+//! conformance to in `meta.profile`:
+//! - Abstract profiles may only be used as a base, never by an instance
+
+use serde_json::{json, Value};
+
+use crate::ir::ProfileDocument;
+use crate::validation::diagnostic::{Diagnostic, DiagnosticSource};
+
+/// Error codes for example instance validation.
+pub mod codes {
+    pub const INSTANCE_OF_ABSTRACT_PROFILE: &str = "INSTANCE_001";
+}
+
+/// Report example instances that claim conformance to an abstract profile.
+///
+/// `instances` are `(id, resource)` pairs; a versioned `meta.profile`
+/// canonical (`url|version`) matches the profile URL. Nothing is reported for
+/// a concrete profile.
+pub fn validate_abstract_profile_instances<'a>(
+    document: &ProfileDocument,
+    instances: impl IntoIterator<Item = (&'a str, &'a Value)>,
+) -> Vec<Diagnostic> {
+    if !document.resource.is_abstract {
+        return Vec::new();
+    }
+    let profile_url = document.metadata.url.as_str();
+
+    instances
+        .into_iter()
+        .flat_map(|(id, resource)| {
+            resource
+                .pointer("/meta/profile")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .filter(|profile| {
+                    profile.split_once('|').map_or(*profile, |(url, _)| url) == profile_url
+                })
+                .map(move |profile| {
+                    Diagnostic::error(
+                        codes::INSTANCE_OF_ABSTRACT_PROFILE,
+                        format!(
+                            "Instance '{}' claims conformance to abstract profile '{}'",
+                            id, profile
+                        ),
+                    )
+                    .with_source(DiagnosticSource::Reference)
+                    .with_details(json!({ "instanceId": id, "profile": profile }))
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ir::{BaseDefinition, DocumentMetadata, FhirVersion, ProfiledResource};
+
+    fn profile(is_abstract: bool) -> ProfileDocument {
+        let mut resource = ProfiledResource::new(
+            "http://example.org/fhir/StructureDefinition/BasePatient",
+            FhirVersion::R4,
+            BaseDefinition::resource("Patient"),
+        );
+        resource.is_abstract = is_abstract;
+        ProfileDocument::new(
+            DocumentMetadata::new(
+                "base-patient",
+                "http://example.org/fhir/StructureDefinition/BasePatient",
+                "BasePatient",
+            ),
+            resource,
+        )
+    }
+
+    #[test]
+    fn test_instance_of_abstract_profile() {
+        let versioned = json!({
+            "resourceType": "Patient",
+            "meta": { "profile": ["http://example.org/fhir/StructureDefinition/BasePatient|1.0.0"] }
+        });
+        let other = json!({
+            "resourceType": "Patient",
+            "meta": { "profile": ["http://example.org/fhir/StructureDefinition/OtherPatient"] }
+        });
+        let instances = [("abstract-example", &versioned), ("other-example", &other)];
+
+        let diagnostics = validate_abstract_profile_instances(&profile(true), instances);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::INSTANCE_OF_ABSTRACT_PROFILE);
+        assert_eq!(
+            diagnostics[0].details.as_ref().unwrap()["instanceId"],
+            "abstract-example"
+        );
+
+        assert!(validate_abstract_profile_instances(&profile(false), instances).is_empty());
+    }
+}
@@ -10,8 +10,11 @@
 pub mod binding;
 pub mod cardinality;
 pub mod fhirpath;
+pub mod fixed_value;
+pub mod instance;
 pub mod metadata;
 pub mod slicing;
+pub mod snapshot;
 pub mod type_refinement;
 
 use crate::ir::{ElementNode, ProfileDocument};
@@ -39,10 +42,82 @@ pub fn validate_structural(document: &ProfileDocument) -> ValidationResult {
     diagnostics.extend(type_refinement::validate_type_tree(&document.resource.root));
     diagnostics.extend(slicing::validate_slicing_tree(&document.resource.root));
     diagnostics.extend(binding::validate_binding_tree(&document.resource.root));
+    diagnostics.extend(fixed_value::validate_fixed_value_tree(&document.resource.root));
 
     ValidationResult::with_diagnostics(diagnostics, ValidationLevel::Structural)
 }
 
+/// Validate a hydrated document against the base element tree it constrains.
+///
+/// These rules compare each profile element with its counterpart in `base`
+/// and therefore only run when the base definition could be resolved.
+pub fn validate_against_base(document: &ProfileDocument, base: &ElementNode) -> ValidationResult {
+    let mut diagnostics = Vec::new();
+
+    diagnostics.extend(binding::validate_binding_against_base(
+        &document.resource.root,
+        base,
+    ));
+    diagnostics.extend(cardinality::validate_cardinality_against_base(
+        &document.resource.root,
+        base,
+    ));
+    diagnostics.extend(type_refinement::validate_types_against_base(
+        &document.resource.root,
+        base,
+    ));
+    diagnostics.extend(slicing::validate_slicing_against_base(
+        &document.resource.root,
+        base,
+    ));
+
+    ValidationResult::with_diagnostics(diagnostics, ValidationLevel::Structural)
+}
+
+/// Visit each profile element together with its counterpart in the base tree.
+///
+/// Slices are compared with the base element they slice; the visitor's third
+/// argument is `true` for a slice's root element. Profile elements without a
+/// base counterpart (e.g., new extension slices) are skipped.
+pub fn visit_with_base<'a>(
+    element: &'a ElementNode,
+    base: &'a ElementNode,
+    visit: &mut impl FnMut(&'a ElementNode, &'a ElementNode, bool),
+) {
+    visit_with_base_inner(element, base, false, visit);
+}
+
+fn visit_with_base_inner<'a>(
+    element: &'a ElementNode,
+    base: &'a ElementNode,
+    is_slice: bool,
+    visit: &mut impl FnMut(&'a ElementNode, &'a ElementNode, bool),
+) {
+    visit(element, base, is_slice);
+
+    for child in &element.children {
+        if let Some(base_child) = find_base_child(base, &child.path) {
+            visit_with_base_inner(child, base_child, false, visit);
+        }
+    }
+
+    for slice in element.slices.values() {
+        visit_with_base_inner(&slice.element, base, true, visit);
+    }
+}
+
+/// Find the base child matching a profile path, including choice renames
+/// (`Observation.valueQuantity` matches `Observation.value[x]`).
+fn find_base_child<'a>(base: &'a ElementNode, path: &str) -> Option<&'a ElementNode> {
+    base.children.iter().find(|c| c.path == path).or_else(|| {
+        base.children.iter().find(|c| {
+            c.path
+                .strip_suffix("[x]")
+                .is_some_and(|prefix| path.starts_with(prefix) && !path[prefix.len()..].contains('.'))
+        })
+    })
+}
+
 /// Validate a single element with structural rules.
 pub fn validate_element_structural(
     element: &ElementNode,
@@ -54,6 +129,7 @@ pub fn validate_element_structural(
     diagnostics.extend(type_refinement::validate_element_types(element));
     diagnostics.extend(slicing::validate_element_slicing(element));
     diagnostics.extend(binding::validate_element_binding(element));
+    diagnostics.extend(fixed_value::validate_element_fixed_value(element));
 
     ValidationResult::with_diagnostics(diagnostics, ValidationLevel::Structural)
 }
@@ -77,6 +153,32 @@ mod tests {
         ProfileDocument::new(metadata, resource)
     }
 
+    #[test]
+    fn test_visit_with_base_matches_choice_types() {
+        let mut base = ElementNode::new("Observation".to_string());
+        base.add_child(ElementNode::new("Observation.value[x]".to_string()));
+
+        let mut profile = ElementNode::new("Observation".to_string());
+        profile.add_child(ElementNode::new("Observation.valueQuantity".to_string()));
+        profile.add_child(ElementNode::new("Observation.extension".to_string()));
+
+        let mut pairs = Vec::new();
+        visit_with_base(&profile, &base, &mut |element, base_element, _| {
+            pairs.push((element.path.clone(), base_element.path.clone()));
+        });
+
+        assert_eq!(
+            pairs,
+            vec![
+                ("Observation".to_string(), "Observation".to_string()),
+                (
+                    "Observation.valueQuantity".to_string(),
+                    "Observation.value[x]".to_string()
+                ),
+            ]
+        );
+    }
+
     #[test]
     fn test_validate_structural_empty_document() {
         let doc = create_test_document();
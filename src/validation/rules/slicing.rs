@@ -5,8 +5,11 @@
 //! - Discriminator paths are valid element paths
 //! - Discriminator types are appropriate for path
 //! - Slicing rules are consistent
+//! - Ordered slicing keeps a stable slice order
+//! - Slicing rules only tighten the base's rules
+//! - Slices can be told apart by their `value`/`pattern` discriminators
 
-use crate::ir::{ElementNode, SlicingRules};
+use crate::ir::{DiscriminatorType, ElementNode, ElementSource, SlicingDefinition, SlicingRules};
 use crate::validation::diagnostic::{Diagnostic, DiagnosticSource};
 use crate::validation::quick_fix::QuickFixFactory;
 
@@ -20,6 +23,10 @@ pub mod codes {
     pub const SLICE_MISSING_DEFINITION: &str = "SLICE_006";
     pub const SLICE_NAME_EMPTY: &str = "SLICE_007";
     pub const SLICE_NAME_INVALID_CHARS: &str = "SLICE_008";
+    pub const SLICE_ORDER_NOT_GUARANTEED: &str = "SLICE_009";
+    pub const SLICE_RULES_REOPENED: &str = "SLICE_010";
+    pub const SLICE_RULES_CLOSED_OVER_OPEN_BASE: &str = "SLICE_011";
+    pub const SLICE_DISCRIMINATOR_COLLISION: &str = "SLICE_012";
 }
 
 /// Valid discriminator types.
@@ -112,6 +119,37 @@ pub fn validate_element_slicing(element: &ElementNode) -> Vec<Diagnostic> {
                 .with_source(DiagnosticSource::Ir),
             );
         }
+
+        // Ordered slicing: slices added here can only follow the inherited ones
+        if slicing.ordered {
+            let first_added = element
+                .slices
+                .iter()
+                .position(|(_, slice)| slice.source == ElementSource::Added);
+            let inherited_after = first_added.and_then(|first| {
+                element
+                    .slices
+                    .iter()
+                    .skip(first + 1)
+                    .find(|(_, slice)| slice.source != ElementSource::Added)
+            });
+            if let (Some(first), Some((inherited, _))) = (first_added, inherited_after) {
+                let (added, _) = element.slices.get_index(first).unwrap();
+                diagnostics.push(
+                    Diagnostic::warning(
+                        codes::SLICE_ORDER_NOT_GUARANTEED,
+                        format!(
+                            "Slicing is ordered but new slice '{}' is placed before inherited slice '{}'; the base slice order cannot be changed",
+                            added, inherited
+                        ),
+                    )
+                    .with_path(&element.path)
+                    .with_source(DiagnosticSource::Ir),
+                );
+            }
+        }
+
+        diagnostics.extend(validate_discriminator_collisions(element, slicing));
     }
 
     // Validate slice names
@@ -163,6 +201,141 @@ pub fn validate_element_slicing(element: &ElementNode) -> Vec<Diagnostic> {
     diagnostics
 }
 
+/// Check that `value`/`pattern` discriminators tell every pair of slices apart.
+///
+/// Only applies when all discriminators are `value` or `pattern`; other
+/// discriminator types may distinguish slices the values alone do not. Slices
+/// without a fixed or pattern value at some discriminator path are skipped.
+fn validate_discriminator_collisions(
+    element: &ElementNode,
+    slicing: &SlicingDefinition,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    let by_value = !slicing.discriminator.is_empty()
+        && slicing.discriminator.iter().all(|disc| {
+            matches!(
+                disc.discriminator_type,
+                DiscriminatorType::Value | DiscriminatorType::Pattern
+            )
+        });
+    if !by_value {
+        return diagnostics;
+    }
+
+    let mut seen: Vec<(&str, Vec<&serde_json::Value>)> = Vec::new();
+    for (name, slice) in &element.slices {
+        let values: Option<Vec<&serde_json::Value>> = slicing
+            .discriminator
+            .iter()
+            .map(|disc| discriminating_value(&slice.element, &disc.path))
+            .collect();
+        let Some(values) = values else {
+            continue;
+        };
+
+        if let Some((other, _)) = seen.iter().find(|(_, seen_values)| *seen_values == values) {
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::SLICE_DISCRIMINATOR_COLLISION,
+                    format!(
+                        "Slices '{}' and '{}' have the same discriminator values; instances cannot be assigned to a slice",
+                        other, name
+                    ),
+                )
+                .with_path(&slice.element.path)
+                .with_source(DiagnosticSource::Ir),
+            );
+        }
+        seen.push((name, values));
+    }
+
+    diagnostics
+}
+
+/// The fixed or pattern value a slice sets at a discriminator path.
+///
+/// The value may be set on the element at the path itself or on an ancestor
+/// whose fixed/pattern value contains it (e.g. a `patternIdentifier` with a
+/// `system` for a `system` discriminator).
+fn discriminating_value<'a>(element: &'a ElementNode, path: &str) -> Option<&'a serde_json::Value> {
+    let segments: Vec<&str> = match path {
+        "$this" => Vec::new(),
+        _ => path.split('.').collect(),
+    };
+
+    let mut current = element;
+    for (depth, segment) in segments.iter().enumerate() {
+        if let Some(fixed) = &current.constraints.fixed_value {
+            return segments[depth..]
+                .iter()
+                .try_fold(fixed.value(), |value, key| value.get(key));
+        }
+        current = current.find_child(segment)?;
+    }
+
+    current.constraints.fixed_value.as_ref().map(|fixed| fixed.value())
+}
+
+/// Validate slicing rules against the base element tree.
+///
+/// Rules may only tighten (`open` → `openAtEnd` → `closed`): loosening the
+/// base's rules is an error. Closing slicing the base leaves open is legal
+/// but often a mistake, since it forbids slices other profiles might add,
+/// so it is reported as a warning.
+pub fn validate_slicing_against_base(root: &ElementNode, base: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    super::visit_with_base(root, base, &mut |element, base_element, is_slice| {
+        // A slice root is compared with the sliced element; reslicing is separate
+        if is_slice {
+            return;
+        }
+        let (Some(slicing), Some(base_slicing)) = (&element.slicing, &base_element.slicing) else {
+            return;
+        };
+        let (rules, base_rules) = (slicing.rules, base_slicing.rules);
+
+        if openness(rules) > openness(base_rules) {
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::SLICE_RULES_REOPENED,
+                    format!(
+                        "Slicing rules '{}' loosen the base rules '{}'; slicing can only be tightened",
+                        rules.as_str(),
+                        base_rules.as_str()
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir),
+            );
+        } else if rules == SlicingRules::Closed && base_rules != SlicingRules::Closed {
+            diagnostics.push(
+                Diagnostic::warning(
+                    codes::SLICE_RULES_CLOSED_OVER_OPEN_BASE,
+                    format!(
+                        "Slicing is closed but the base leaves it '{}'; profiles derived from this one cannot add slices",
+                        base_rules.as_str()
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir),
+            );
+        }
+    });
+
+    diagnostics
+}
+
+/// How much a slicing rule permits, from `closed` (0) to `open` (2).
+fn openness(rules: SlicingRules) -> u8 {
+    match rules {
+        SlicingRules::Closed => 0,
+        SlicingRules::OpenAtEnd => 1,
+        SlicingRules::Open => 2,
+    }
+}
+
 /// Recursively validate slicing in element tree.
 fn validate_element_recursive(element: &ElementNode, diagnostics: &mut Vec<Diagnostic>) {
     diagnostics.extend(validate_element_slicing(element));
@@ -244,7 +417,8 @@ fn suggest_discriminator_path(path: &str) -> Option<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::ir::{Discriminator, DiscriminatorType, SliceNode, SlicingDefinition};
+    use crate::ir::{Discriminator, FixedValue, SliceNode};
+    use crate::validation::diagnostic::DiagnosticSeverity;
 
     #[test]
     fn test_valid_discriminator_paths() {
@@ -303,6 +477,31 @@ mod tests {
         // In practice, duplicates would come from parsing or the UI
     }
 
+    #[test]
+    fn test_ordered_slicing_with_new_slice_before_inherited() {
+        let mut element = ElementNode::new("Observation.component".to_string());
+        let mut slicing = SlicingDefinition::new(vec![Discriminator::value("code")]);
+        slicing.ordered = true;
+        element.slicing = Some(slicing);
+
+        let mut inherited = SliceNode::new("systolic");
+        inherited.source = ElementSource::Inherited;
+        element.add_slice("extra".to_string(), SliceNode::new("extra"));
+        element.add_slice("systolic".to_string(), inherited);
+
+        let diagnostics = validate_element_slicing(&element);
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.code == codes::SLICE_ORDER_NOT_GUARANTEED));
+
+        // Appending new slices after the inherited ones is fine
+        element.slices.move_index(0, 1);
+        let diagnostics = validate_element_slicing(&element);
+        assert!(!diagnostics
+            .iter()
+            .any(|d| d.code == codes::SLICE_ORDER_NOT_GUARANTEED));
+    }
+
     #[test]
     fn test_empty_discriminator_warning() {
         let mut element = ElementNode::new("Patient.identifier".to_string());
@@ -318,4 +517,109 @@ mod tests {
             .iter()
             .any(|d| d.code == codes::SLICE_EMPTY_DISCRIMINATOR));
     }
+
+    fn coded_component(name: &str, code: &str) -> SliceNode {
+        let mut slice = SliceNode::with_path(name, format!("Observation.component:{}", name));
+        let mut code_element = ElementNode::new(format!("Observation.component:{}.code", name));
+        code_element.constraints.fixed_value = Some(FixedValue::pattern(serde_json::json!({
+            "coding": [{ "system": "http://loinc.org", "code": code }]
+        })));
+        slice.element.add_child(code_element);
+        slice
+    }
+
+    #[test]
+    fn test_colliding_discriminator_values() {
+        let mut element = ElementNode::new("Observation.component".to_string());
+        element.slicing = Some(SlicingDefinition::new(vec![Discriminator::new(
+            DiscriminatorType::Pattern,
+            "code",
+        )]));
+        element.slices.insert("systolic".to_string(), coded_component("systolic", "8480-6"));
+        element.slices.insert("diastolic".to_string(), coded_component("diastolic", "8462-4"));
+
+        let diagnostics = validate_element_slicing(&element);
+        assert!(!diagnostics
+            .iter()
+            .any(|d| d.code == codes::SLICE_DISCRIMINATOR_COLLISION));
+
+        // A second slice fixing the systolic code cannot be told apart
+        element.slices.insert("systolic2".to_string(), coded_component("systolic2", "8480-6"));
+        let collisions: Vec<_> = validate_element_slicing(&element)
+            .into_iter()
+            .filter(|d| d.code == codes::SLICE_DISCRIMINATOR_COLLISION)
+            .collect();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].severity, DiagnosticSeverity::Error);
+        assert_eq!(
+            collisions[0].element_path.as_deref(),
+            Some("Observation.component:systolic2")
+        );
+    }
+
+    #[test]
+    fn test_discriminator_value_from_ancestor_pattern() {
+        let mut element = ElementNode::new("Patient.identifier".to_string());
+        element.slicing = Some(SlicingDefinition::new(vec![Discriminator::value("system")]));
+        for name in ["mrn", "mrn2"] {
+            let mut slice = SliceNode::with_path(name, format!("Patient.identifier:{}", name));
+            slice.element.constraints.fixed_value = Some(FixedValue::pattern(
+                serde_json::json!({ "system": "http://example.org/mrn" }),
+            ));
+            element.slices.insert(name.to_string(), slice);
+        }
+
+        let diagnostics = validate_element_slicing(&element);
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.code == codes::SLICE_DISCRIMINATOR_COLLISION));
+    }
+
+    fn sliced_identifier(rules: SlicingRules) -> ElementNode {
+        let mut root = ElementNode::new("Patient".to_string());
+        let mut identifier = ElementNode::new("Patient.identifier".to_string());
+        let mut slicing = SlicingDefinition::new(vec![Discriminator::value("system")]);
+        slicing.rules = rules;
+        identifier.slicing = Some(slicing);
+        root.add_child(identifier);
+        root
+    }
+
+    #[test]
+    fn test_tightening_slicing_rules_against_base() {
+        let base = sliced_identifier(SlicingRules::Open);
+
+        // Unchanged and openAtEnd are fine
+        for rules in [SlicingRules::Open, SlicingRules::OpenAtEnd] {
+            let diagnostics = validate_slicing_against_base(&sliced_identifier(rules), &base);
+            assert!(diagnostics.is_empty());
+        }
+
+        // Closing an open base is legal, with a warning
+        let diagnostics =
+            validate_slicing_against_base(&sliced_identifier(SlicingRules::Closed), &base);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::SLICE_RULES_CLOSED_OVER_OPEN_BASE);
+        assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Warning);
+        assert_eq!(diagnostics[0].element_path.as_deref(), Some("Patient.identifier"));
+    }
+
+    #[test]
+    fn test_reopening_closed_base_slicing() {
+        let base = sliced_identifier(SlicingRules::Closed);
+
+        for rules in [SlicingRules::Open, SlicingRules::OpenAtEnd] {
+            let diagnostics = validate_slicing_against_base(&sliced_identifier(rules), &base);
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].code, codes::SLICE_RULES_REOPENED);
+            assert_eq!(diagnostics[0].severity, DiagnosticSeverity::Error);
+        }
+
+        // openAtEnd cannot be widened back to open either
+        let diagnostics = validate_slicing_against_base(
+            &sliced_identifier(SlicingRules::Open),
+            &sliced_identifier(SlicingRules::OpenAtEnd),
+        );
+        assert_eq!(diagnostics[0].code, codes::SLICE_RULES_REOPENED);
+    }
 }
@@ -0,0 +1,184 @@
+//! Snapshot Freshness Rules
+//!
+//! Compares the snapshot a StructureDefinition was imported with against one
+//! regenerated from its differential and base. A snapshot edited by hand, or
+//! left stale after the differential changed, diverges from the regenerated
+//! one; each divergent element path is reported.
+//!
+//! Only the constraints that change what an instance may contain are
+//! compared (cardinality, types, binding, mustSupport, fixed/pattern values,
+//! slicing), so formatting and documentation differences are ignored.
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use crate::validation::diagnostic::{Diagnostic, DiagnosticSource};
+
+/// Error codes for snapshot freshness validation.
+pub mod codes {
+    pub const SNAPSHOT_STALE: &str = "SNAPSHOT_001";
+}
+
+/// Compare an imported snapshot with a regenerated one.
+///
+/// Returns a single warning listing every divergent element path, or nothing
+/// when the snapshots agree. An empty `imported` snapshot is never stale.
+pub fn validate_snapshot_freshness(imported: &[Value], regenerated: &[Value]) -> Vec<Diagnostic> {
+    let paths = divergent_paths(imported, regenerated);
+    if imported.is_empty() || paths.is_empty() {
+        return Vec::new();
+    }
+
+    vec![Diagnostic::warning(
+        codes::SNAPSHOT_STALE,
+        format!(
+            "Imported snapshot does not match the differential applied to the base at {} element(s): {}",
+            paths.len(),
+            paths.join(", ")
+        ),
+    )
+    .with_source(DiagnosticSource::Ir)
+    .with_details(json!({ "paths": paths }))]
+}
+
+/// Element ids (or paths) whose constraints differ between two snapshots,
+/// including elements present in only one of them.
+pub fn divergent_paths(imported: &[Value], regenerated: &[Value]) -> Vec<String> {
+    let imported = constraints_by_id(imported);
+    let regenerated = constraints_by_id(regenerated);
+
+    let mut paths: Vec<String> = imported
+        .iter()
+        .filter(|(id, constraints)| regenerated.get(*id) != Some(constraints))
+        .map(|(id, _)| id.clone())
+        .collect();
+    paths.extend(
+        regenerated
+            .keys()
+            .filter(|id| !imported.contains_key(*id))
+            .cloned(),
+    );
+    paths.sort();
+    paths
+}
+
+/// Index snapshot elements by id, falling back to path.
+fn constraints_by_id(elements: &[Value]) -> BTreeMap<String, Value> {
+    elements
+        .iter()
+        .filter_map(|element| {
+            let id = element
+                .get("id")
+                .or_else(|| element.get("path"))
+                .and_then(Value::as_str)?;
+            Some((id.to_string(), compared_constraints(element)))
+        })
+        .collect()
+}
+
+/// The constraints of an element that take part in the comparison.
+fn compared_constraints(element: &Value) -> Value {
+    let mut types: Vec<Value> = element
+        .get("type")
+        .and_then(Value::as_array)
+        .map(|types| {
+            types
+                .iter()
+                .map(|t| {
+                    json!({
+                        "code": t.get("code"),
+                        "profile": sorted_strings(t.get("profile")),
+                        "targetProfile": sorted_strings(t.get("targetProfile")),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    types.sort_by_key(|t| t.to_string());
+
+    let fixed: BTreeMap<&str, &Value> = element
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| key.starts_with("fixed") || key.starts_with("pattern"))
+        .map(|(key, value)| (key.as_str(), value))
+        .collect();
+
+    let binding = element.get("binding").map(|binding| {
+        json!({
+            "strength": binding.get("strength"),
+            "valueSet": binding.get("valueSet"),
+        })
+    });
+
+    json!({
+        "min": element.get("min").and_then(Value::as_u64).unwrap_or(0),
+        "max": element.get("max").and_then(Value::as_str).unwrap_or("*"),
+        "type": types,
+        "binding": binding,
+        "mustSupport": element.get("mustSupport").and_then(Value::as_bool).unwrap_or(false),
+        "fixed": fixed,
+        "slicing": element.get("slicing"),
+    })
+}
+
+fn sorted_strings(value: Option<&Value>) -> Vec<String> {
+    let mut strings: Vec<String> = value
+        .and_then(Value::as_array)
+        .map(|values| {
+            values
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default();
+    strings.sort();
+    strings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> Vec<Value> {
+        vec![
+            json!({ "id": "Patient", "path": "Patient", "min": 0, "max": "*" }),
+            json!({
+                "id": "Patient.gender",
+                "path": "Patient.gender",
+                "min": 1,
+                "max": "1",
+                "type": [{ "code": "code" }],
+                "binding": { "strength": "required", "valueSet": "http://hl7.org/fhir/ValueSet/administrative-gender" }
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_matching_snapshot_is_fresh() {
+        let mut regenerated = snapshot();
+        // Documentation differences do not count
+        regenerated[1]["short"] = json!("male | female | other | unknown");
+
+        assert!(validate_snapshot_freshness(&snapshot(), &regenerated).is_empty());
+        // Nothing was imported, nothing to compare
+        assert!(validate_snapshot_freshness(&[], &regenerated).is_empty());
+    }
+
+    #[test]
+    fn test_stale_snapshot_lists_divergent_paths() {
+        let mut imported = snapshot();
+        imported[1]["min"] = json!(0);
+        imported.push(json!({ "id": "Patient.extra", "path": "Patient.extra", "min": 0, "max": "1" }));
+
+        let diagnostics = validate_snapshot_freshness(&imported, &snapshot());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::SNAPSHOT_STALE);
+        assert_eq!(
+            divergent_paths(&imported, &snapshot()),
+            vec!["Patient.extra".to_string(), "Patient.gender".to_string()]
+        );
+    }
+}
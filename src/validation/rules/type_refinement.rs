@@ -17,6 +17,7 @@ pub mod codes {
     pub const TYPE_DUPLICATE: &str = "TYPE_004";
     pub const TYPE_REFERENCE_NO_TARGET: &str = "TYPE_005";
     pub const TYPE_REFERENCE_INVALID_TARGET: &str = "TYPE_006";
+    pub const TYPE_NOT_ALLOWED_BY_BASE: &str = "TYPE_007";
 }
 
 /// Known FHIR primitive types.
@@ -189,6 +190,50 @@ fn validate_element_recursive(element: &ElementNode, diagnostics: &mut Vec<Diagn
     }
 }
 
+/// Validate that every constrained type is allowed by the base element.
+///
+/// A type is allowed when it equals, or is a subtype of, one of the base
+/// element's type codes. Base elements without types are not checked.
+pub fn validate_types_against_base(root: &ElementNode, base: &ElementNode) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+
+    super::visit_with_base(root, base, &mut |element, base_element, _| {
+        let base_types = &base_element.constraints.types;
+        if base_types.is_empty() {
+            return;
+        }
+
+        for type_constraint in &element.constraints.types {
+            let allowed = base_types
+                .iter()
+                .any(|base_type| is_subtype_of(&type_constraint.code, &base_type.code));
+            if allowed {
+                continue;
+            }
+
+            let base_codes: Vec<&str> = base_types.iter().map(|t| t.code.as_str()).collect();
+            diagnostics.push(
+                Diagnostic::error(
+                    codes::TYPE_NOT_ALLOWED_BY_BASE,
+                    format!(
+                        "Type '{}' is not allowed by the base element (allowed: {})",
+                        type_constraint.code,
+                        base_codes.join(", ")
+                    ),
+                )
+                .with_path(&element.path)
+                .with_source(DiagnosticSource::Ir)
+                .with_quick_fix(QuickFixFactory::remove_invalid_type(
+                    &element.path,
+                    &type_constraint.code,
+                )),
+            );
+        }
+    });
+
+    diagnostics
+}
+
 /// Check if a type code is valid.
 pub fn is_valid_type_code(code: &str) -> bool {
     // Check primitives
@@ -339,6 +384,43 @@ mod tests {
         assert!(!is_subtype_of("string", "Quantity"));
     }
 
+    fn observation_base() -> ElementNode {
+        let mut base = ElementNode::new("Observation".to_string());
+        let mut value = ElementNode::new("Observation.value[x]".to_string());
+        value.constraints.types = ["Quantity", "CodeableConcept", "string"]
+            .into_iter()
+            .map(TypeConstraint::simple)
+            .collect();
+        base.add_child(value);
+        base
+    }
+
+    #[test]
+    fn test_type_not_allowed_by_base() {
+        let mut profile = ElementNode::new("Observation".to_string());
+        let mut value = ElementNode::new("Observation.value[x]".to_string());
+        value.constraints.types = vec![
+            TypeConstraint::simple("Quantity"),
+            TypeConstraint::simple("HumanName"),
+        ];
+        profile.add_child(value);
+
+        let diagnostics = validate_types_against_base(&profile, &observation_base());
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].code, codes::TYPE_NOT_ALLOWED_BY_BASE);
+        assert!(diagnostics[0].message.contains("HumanName"));
+    }
+
+    #[test]
+    fn test_subtype_allowed_by_base() {
+        let mut profile = ElementNode::new("Observation".to_string());
+        let mut value = ElementNode::new("Observation.valueQuantity".to_string());
+        value.constraints.types = vec![TypeConstraint::simple("Age")];
+        profile.add_child(value);
+
+        assert!(validate_types_against_base(&profile, &observation_base()).is_empty());
+    }
+
     #[test]
     fn test_duplicate_types() {
         let mut element = ElementNode::new("Patient.extension".to_string());
@@ -0,0 +1,102 @@
+//! Build and toolchain version information.
+//!
+//! FSH import and export go through maki-core and maki-decompiler, and a
+//! different maki release can parse or decompile FSH differently without
+//! raising any error. [`check_maki_compatibility`] logs the resolved versions
+//! at startup and warns when they are outside the release series this build
+//! is tested with; `GET /api/version` reports the same information.
+
+use serde::Serialize;
+
+use crate::ir::FhirVersion;
+
+/// maki-core version resolved at build time (from `Cargo.lock`).
+pub const MAKI_CORE_VERSION: &str = env!("NITEN_MAKI_CORE_VERSION");
+
+/// maki-decompiler version resolved at build time (from `Cargo.lock`).
+pub const MAKI_DECOMPILER_VERSION: &str = env!("NITEN_MAKI_DECOMPILER_VERSION");
+
+/// maki release series (`major.minor`) FSH import/export is tested against.
+pub const SUPPORTED_MAKI_SERIES: &str = "0.0";
+
+/// FHIR versions profiles can be authored in.
+pub const SUPPORTED_FHIR_VERSIONS: [FhirVersion; 4] =
+    [FhirVersion::R4, FhirVersion::R4B, FhirVersion::R5, FhirVersion::R6];
+
+/// Server and toolchain versions.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    /// Server name.
+    pub name: &'static str,
+    /// Server crate version.
+    pub version: &'static str,
+    /// maki crate versions.
+    pub maki: MakiVersions,
+    /// Supported FHIR versions.
+    pub fhir_versions: Vec<FhirVersion>,
+}
+
+/// Versions of the maki crates used for FSH.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MakiVersions {
+    /// maki-core version.
+    pub core: &'static str,
+    /// maki-decompiler version.
+    pub decompiler: &'static str,
+    /// Release series this build is tested with.
+    pub supported_series: &'static str,
+    /// Whether both crates belong to the supported series.
+    pub compatible: bool,
+}
+
+impl VersionInfo {
+    /// Versions of the running build.
+    pub fn current() -> Self {
+        Self {
+            name: "niten-backend",
+            version: env!("CARGO_PKG_VERSION"),
+            maki: MakiVersions {
+                core: MAKI_CORE_VERSION,
+                decompiler: MAKI_DECOMPILER_VERSION,
+                supported_series: SUPPORTED_MAKI_SERIES,
+                compatible: is_supported_maki(MAKI_CORE_VERSION)
+                    && is_supported_maki(MAKI_DECOMPILER_VERSION),
+            },
+            fhir_versions: SUPPORTED_FHIR_VERSIONS.to_vec(),
+        }
+    }
+}
+
+/// Whether a maki version belongs to [`SUPPORTED_MAKI_SERIES`].
+pub fn is_supported_maki(version: &str) -> bool {
+    version
+        .rsplit_once('.')
+        .is_some_and(|(series, _)| series == SUPPORTED_MAKI_SERIES)
+}
+
+/// Log the maki versions in use; a mismatch is reported but never fatal.
+pub fn check_maki_compatibility() -> VersionInfo {
+    let info = VersionInfo::current();
+    let fhir_versions: Vec<&str> = info.fhir_versions.iter().map(FhirVersion::as_str).collect();
+
+    if info.maki.compatible {
+        tracing::info!(
+            "Using maki-core {} and maki-decompiler {} (FHIR {})",
+            info.maki.core,
+            info.maki.decompiler,
+            fhir_versions.join(", ")
+        );
+    } else {
+        tracing::warn!(
+            "maki-core {} / maki-decompiler {} are outside the tested {}.x series; \
+             FSH import and export may differ from expected output",
+            info.maki.core,
+            info.maki.decompiler,
+            SUPPORTED_MAKI_SERIES
+        );
+    }
+
+    info
+}